@@ -0,0 +1,11 @@
+fn main() {
+    // Npcap installs wpcap.dll under System32\Npcap, which isn't on the DLL
+    // search path. Delay-loading it lets the app start without Npcap and
+    // add that directory (CaptureBackend::init) before the first pcap call.
+    let target_os = std::env::var("CARGO_CFG_TARGET_OS").unwrap_or_default();
+    let target_env = std::env::var("CARGO_CFG_TARGET_ENV").unwrap_or_default();
+    if target_os == "windows" && target_env == "msvc" {
+        println!("cargo:rustc-link-arg=/DELAYLOAD:wpcap.dll");
+        println!("cargo:rustc-link-lib=delayimp");
+    }
+}
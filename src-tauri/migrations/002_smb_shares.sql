@@ -0,0 +1,16 @@
+CREATE TABLE shares (
+    id TEXT PRIMARY KEY,
+    host_id TEXT NOT NULL,
+    name TEXT NOT NULL,
+    share_type TEXT,
+    comment TEXT,
+    anonymous_access TEXT,
+    user_access TEXT,
+    accessible BOOLEAN NOT NULL DEFAULT 0,
+    writable BOOLEAN NOT NULL DEFAULT 0,
+    discovered_at TIMESTAMP NOT NULL,
+    FOREIGN KEY (host_id) REFERENCES hosts (id) ON DELETE CASCADE,
+    UNIQUE (host_id, name)
+);
+
+CREATE INDEX idx_shares_host_id ON shares(host_id);
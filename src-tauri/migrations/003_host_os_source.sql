@@ -0,0 +1 @@
+ALTER TABLE hosts ADD COLUMN os_source TEXT;
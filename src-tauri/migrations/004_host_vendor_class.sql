@@ -0,0 +1 @@
+ALTER TABLE hosts ADD COLUMN vendor_class TEXT;
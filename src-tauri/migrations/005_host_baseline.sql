@@ -0,0 +1,4 @@
+ALTER TABLE hosts ADD COLUMN approved BOOLEAN NOT NULL DEFAULT 0;
+ALTER TABLE hosts ADD COLUMN rogue BOOLEAN NOT NULL DEFAULT 0;
+
+CREATE INDEX idx_hosts_mac_address ON hosts(mac_address);
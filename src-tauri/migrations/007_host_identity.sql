@@ -0,0 +1,53 @@
+-- no-transaction
+-- Hosts are identified by MAC where known, so two records may briefly share
+-- an IP after a DHCP lease moves. Rebuild the table without UNIQUE(ip);
+-- foreign keys must be off so dropping the old table doesn't cascade.
+PRAGMA foreign_keys = OFF;
+
+BEGIN;
+
+CREATE TABLE hosts_new (
+    id TEXT PRIMARY KEY,
+    ip TEXT NOT NULL,
+    hostname TEXT,
+    mac_address TEXT,
+    vendor TEXT,
+    vendor_class TEXT,
+    os_name TEXT,
+    os_family TEXT,
+    os_accuracy REAL,
+    os_source TEXT,
+    status TEXT NOT NULL DEFAULT 'unknown',
+    approved BOOLEAN NOT NULL DEFAULT 0,
+    rogue BOOLEAN NOT NULL DEFAULT 0,
+    created_at TIMESTAMP NOT NULL,
+    updated_at TIMESTAMP NOT NULL
+);
+
+INSERT INTO hosts_new (id, ip, hostname, mac_address, vendor, vendor_class, os_name, os_family,
+    os_accuracy, os_source, status, approved, rogue, created_at, updated_at)
+SELECT id, ip, hostname, mac_address, vendor, vendor_class, os_name, os_family,
+    os_accuracy, os_source, status, approved, rogue, created_at, updated_at
+FROM hosts;
+
+DROP TABLE hosts;
+ALTER TABLE hosts_new RENAME TO hosts;
+
+CREATE INDEX idx_hosts_ip ON hosts(ip);
+CREATE INDEX idx_hosts_mac_address ON hosts(mac_address);
+
+CREATE TABLE host_ip_history (
+    host_id TEXT NOT NULL,
+    ip TEXT NOT NULL,
+    first_seen TIMESTAMP NOT NULL,
+    last_seen TIMESTAMP NOT NULL,
+    PRIMARY KEY (host_id, ip),
+    FOREIGN KEY (host_id) REFERENCES hosts (id) ON DELETE CASCADE
+);
+
+INSERT INTO host_ip_history (host_id, ip, first_seen, last_seen)
+SELECT id, ip, created_at, updated_at FROM hosts;
+
+COMMIT;
+
+PRAGMA foreign_keys = ON;
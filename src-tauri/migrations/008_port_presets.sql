@@ -0,0 +1,16 @@
+CREATE TABLE port_presets (
+    id TEXT PRIMARY KEY,
+    name TEXT NOT NULL UNIQUE,
+    description TEXT,
+    ports TEXT NOT NULL,
+    created_at TIMESTAMP NOT NULL,
+    updated_at TIMESTAMP NOT NULL
+);
+
+INSERT INTO port_presets (id, name, description, ports, created_at, updated_at) VALUES
+    ('8c1f3e0a-5d6b-4a43-9d7e-0b3f1c2a9e01', 'OT protocols', 'Modbus, S7, DNP3, EtherNet/IP, BACnet, IEC 104 and friends',
+        '102,502,1089-1091,2222,2404,4000,4840,9600,18245-18246,20000,44818,47808', CURRENT_TIMESTAMP, CURRENT_TIMESTAMP),
+    ('8c1f3e0a-5d6b-4a43-9d7e-0b3f1c2a9e02', 'Windows admin', 'SMB, RPC, RDP, WinRM and directory services',
+        '88,135,139,389,445,464,593,636,3268-3269,3389,5985-5986,9389', CURRENT_TIMESTAMP, CURRENT_TIMESTAMP),
+    ('8c1f3e0a-5d6b-4a43-9d7e-0b3f1c2a9e03', 'Web tier', 'HTTP(S) listeners, proxies and common app servers',
+        '80-81,443,591,593,3000,4443,5000,7001,7443,8000-8001,8008,8080-8081,8443,8888,9000,9443', CURRENT_TIMESTAMP, CURRENT_TIMESTAMP);
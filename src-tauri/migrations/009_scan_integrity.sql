@@ -0,0 +1,4 @@
+ALTER TABLE scans ADD COLUMN raw_output BLOB;
+ALTER TABLE scans ADD COLUMN result_json TEXT;
+ALTER TABLE scans ADD COLUMN output_sha256 TEXT;
+ALTER TABLE scans ADD COLUMN result_sha256 TEXT;
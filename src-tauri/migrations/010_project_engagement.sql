@@ -0,0 +1,5 @@
+ALTER TABLE projects ADD COLUMN client_name TEXT;
+ALTER TABLE projects ADD COLUMN scope_statement TEXT;
+ALTER TABLE projects ADD COLUMN start_date DATE;
+ALTER TABLE projects ADD COLUMN end_date DATE;
+ALTER TABLE projects ADD COLUMN rules_of_engagement TEXT;
@@ -0,0 +1,20 @@
+CREATE TABLE project_scope (
+    id TEXT PRIMARY KEY,
+    project_id TEXT NOT NULL,
+    entry TEXT NOT NULL,
+    kind TEXT NOT NULL,
+    created_at TIMESTAMP NOT NULL,
+    UNIQUE (project_id, entry),
+    FOREIGN KEY (project_id) REFERENCES projects (id) ON DELETE CASCADE
+);
+
+CREATE TABLE audit_log (
+    id TEXT PRIMARY KEY,
+    project_id TEXT,
+    action TEXT NOT NULL,
+    target TEXT,
+    details TEXT,
+    created_at TIMESTAMP NOT NULL
+);
+
+CREATE INDEX idx_audit_log_project ON audit_log(project_id, created_at);
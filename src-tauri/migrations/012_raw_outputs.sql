@@ -0,0 +1,11 @@
+CREATE TABLE raw_outputs (
+    id TEXT PRIMARY KEY,
+    scan_id TEXT NOT NULL,
+    format TEXT NOT NULL,
+    data BLOB NOT NULL,
+    original_size INTEGER NOT NULL,
+    created_at TIMESTAMP NOT NULL,
+    FOREIGN KEY (scan_id) REFERENCES scans (id) ON DELETE CASCADE
+);
+
+CREATE INDEX idx_raw_outputs_scan ON raw_outputs(scan_id);
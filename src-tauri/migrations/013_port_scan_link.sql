@@ -0,0 +1,3 @@
+ALTER TABLE ports ADD COLUMN scan_id TEXT;
+
+CREATE INDEX idx_ports_scan ON ports(scan_id);
@@ -0,0 +1,11 @@
+CREATE TABLE host_status_history (
+    id TEXT PRIMARY KEY,
+    host_id TEXT NOT NULL,
+    status TEXT NOT NULL,
+    rtt_ms REAL,
+    source TEXT NOT NULL,
+    recorded_at TIMESTAMP NOT NULL,
+    FOREIGN KEY (host_id) REFERENCES hosts (id) ON DELETE CASCADE
+);
+
+CREATE INDEX idx_host_status_history_host ON host_status_history(host_id, recorded_at);
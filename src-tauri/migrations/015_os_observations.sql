@@ -0,0 +1,12 @@
+CREATE TABLE os_observations (
+    id TEXT PRIMARY KEY,
+    host_id TEXT NOT NULL,
+    source TEXT NOT NULL,
+    os_name TEXT NOT NULL,
+    os_family TEXT NOT NULL,
+    accuracy REAL NOT NULL,
+    observed_at TIMESTAMP NOT NULL,
+    FOREIGN KEY (host_id) REFERENCES hosts (id) ON DELETE CASCADE
+);
+
+CREATE INDEX idx_os_observations_host ON os_observations(host_id, observed_at);
@@ -0,0 +1,15 @@
+CREATE TABLE ssh_audits (
+    id TEXT PRIMARY KEY,
+    host_id TEXT NOT NULL,
+    port INTEGER NOT NULL,
+    banner TEXT NOT NULL,
+    kex_algorithms TEXT NOT NULL,
+    host_key_algorithms TEXT NOT NULL,
+    ciphers TEXT NOT NULL,
+    macs TEXT NOT NULL,
+    host_key_type TEXT,
+    host_key_bits INTEGER,
+    audited_at TIMESTAMP NOT NULL,
+    FOREIGN KEY (host_id) REFERENCES hosts (id) ON DELETE CASCADE,
+    UNIQUE (host_id, port)
+);
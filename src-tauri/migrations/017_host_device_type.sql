@@ -0,0 +1,4 @@
+ALTER TABLE hosts ADD COLUMN device_type TEXT;
+ALTER TABLE hosts ADD COLUMN device_product TEXT;
+
+CREATE INDEX idx_hosts_device_type ON hosts(device_type);
@@ -0,0 +1,15 @@
+CREATE TABLE ics_assets (
+    id TEXT PRIMARY KEY,
+    host_id TEXT NOT NULL,
+    protocol TEXT NOT NULL,
+    port INTEGER NOT NULL,
+    vendor TEXT,
+    model TEXT,
+    firmware TEXT,
+    details TEXT,
+    discovered_at TIMESTAMP NOT NULL,
+    FOREIGN KEY (host_id) REFERENCES hosts (id) ON DELETE CASCADE,
+    UNIQUE (host_id, protocol, port)
+);
+
+CREATE INDEX idx_ics_assets_host_id ON ics_assets(host_id);
@@ -0,0 +1,17 @@
+CREATE TABLE nat_port_mappings (
+    id TEXT PRIMARY KEY,
+    host_id TEXT NOT NULL,
+    remote_host TEXT,
+    external_port INTEGER NOT NULL,
+    protocol TEXT NOT NULL,
+    internal_client TEXT NOT NULL,
+    internal_port INTEGER NOT NULL,
+    enabled BOOLEAN NOT NULL DEFAULT 1,
+    description TEXT,
+    lease_duration INTEGER NOT NULL DEFAULT 0,
+    discovered_at TIMESTAMP NOT NULL,
+    FOREIGN KEY (host_id) REFERENCES hosts (id) ON DELETE CASCADE,
+    UNIQUE (host_id, external_port, protocol)
+);
+
+CREATE INDEX idx_nat_port_mappings_host_id ON nat_port_mappings(host_id);
@@ -0,0 +1,14 @@
+CREATE TABLE wireless_devices (
+    id TEXT PRIMARY KEY,
+    kind TEXT NOT NULL,
+    mac_address TEXT NOT NULL,
+    name TEXT,
+    rssi INTEGER,
+    services TEXT,
+    source TEXT NOT NULL,
+    first_seen TIMESTAMP NOT NULL,
+    last_seen TIMESTAMP NOT NULL,
+    UNIQUE (kind, mac_address)
+);
+
+CREATE INDEX idx_wireless_devices_kind ON wireless_devices(kind);
@@ -0,0 +1,3 @@
+ALTER TABLE wireless_devices ADD COLUMN channel INTEGER;
+ALTER TABLE wireless_devices ADD COLUMN encryption TEXT;
+ALTER TABLE wireless_devices ADD COLUMN bssid TEXT;
@@ -0,0 +1,14 @@
+CREATE TABLE cloud_assets (
+    id TEXT PRIMARY KEY,
+    provider TEXT NOT NULL,
+    kind TEXT NOT NULL,
+    resource_id TEXT NOT NULL,
+    name TEXT,
+    address TEXT NOT NULL,
+    region TEXT,
+    exposure TEXT,
+    discovered_at TIMESTAMP NOT NULL,
+    UNIQUE (provider, resource_id, address)
+);
+
+CREATE INDEX idx_cloud_assets_address ON cloud_assets(address);
@@ -0,0 +1,12 @@
+CREATE TABLE anonymous_access (
+    id TEXT PRIMARY KEY,
+    host_id TEXT NOT NULL,
+    protocol TEXT NOT NULL,
+    port INTEGER NOT NULL,
+    banner TEXT,
+    listing TEXT NOT NULL,
+    listing_sha256 TEXT NOT NULL,
+    checked_at TIMESTAMP NOT NULL,
+    FOREIGN KEY (host_id) REFERENCES hosts (id) ON DELETE CASCADE,
+    UNIQUE (host_id, protocol, port)
+);
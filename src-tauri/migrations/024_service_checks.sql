@@ -0,0 +1,12 @@
+CREATE TABLE service_checks (
+    id TEXT PRIMARY KEY,
+    host_id TEXT NOT NULL,
+    port INTEGER NOT NULL,
+    check_name TEXT NOT NULL,
+    vulnerable BOOLEAN NOT NULL,
+    evidence TEXT NOT NULL,
+    evidence_sha256 TEXT NOT NULL,
+    checked_at TIMESTAMP NOT NULL,
+    FOREIGN KEY (host_id) REFERENCES hosts (id) ON DELETE CASCADE,
+    UNIQUE (host_id, port, check_name)
+);
@@ -0,0 +1,5 @@
+ALTER TABLE vulnerabilities ADD COLUMN kev BOOLEAN NOT NULL DEFAULT 0;
+ALTER TABLE vulnerabilities ADD COLUMN epss REAL;
+ALTER TABLE hosts ADD COLUMN risk_score REAL;
+
+CREATE INDEX idx_hosts_risk_score ON hosts(risk_score);
@@ -0,0 +1,13 @@
+CREATE TABLE events (
+    id TEXT PRIMARY KEY,
+    category TEXT NOT NULL,
+    action TEXT NOT NULL,
+    host_id TEXT,
+    entity_id TEXT,
+    message TEXT NOT NULL,
+    created_at TIMESTAMP NOT NULL
+);
+
+CREATE INDEX idx_events_created ON events(created_at);
+CREATE INDEX idx_events_category ON events(category, created_at);
+CREATE INDEX idx_events_host ON events(host_id, created_at);
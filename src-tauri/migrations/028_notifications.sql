@@ -0,0 +1,12 @@
+CREATE TABLE notifications (
+    id TEXT PRIMARY KEY,
+    severity TEXT NOT NULL,
+    title TEXT NOT NULL,
+    message TEXT NOT NULL,
+    entity_type TEXT,
+    entity_id TEXT,
+    unread BOOLEAN NOT NULL DEFAULT 1,
+    created_at TIMESTAMP NOT NULL
+);
+
+CREATE INDEX idx_notifications_unread ON notifications(unread, created_at);
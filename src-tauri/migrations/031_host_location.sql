@@ -0,0 +1,2 @@
+ALTER TABLE hosts ADD COLUMN country_code TEXT;
+ALTER TABLE hosts ADD COLUMN city TEXT;
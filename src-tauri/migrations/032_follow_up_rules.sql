@@ -0,0 +1,12 @@
+CREATE TABLE follow_up_rules (
+    id TEXT PRIMARY KEY,
+    name TEXT NOT NULL,
+    match_port INTEGER,
+    match_service TEXT,
+    action TEXT NOT NULL,
+    arguments TEXT,
+    enabled BOOLEAN NOT NULL DEFAULT 1,
+    created_at TIMESTAMP NOT NULL,
+    updated_at TIMESTAMP NOT NULL,
+    CHECK (match_port IS NOT NULL OR match_service IS NOT NULL)
+);
@@ -0,0 +1,3 @@
+ALTER TABLE scans ADD COLUMN started_by TEXT;
+ALTER TABLE scans ADD COLUMN reason TEXT;
+ALTER TABLE scans ADD COLUMN ticket_ref TEXT;
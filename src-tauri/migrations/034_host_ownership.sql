@@ -0,0 +1,5 @@
+ALTER TABLE hosts ADD COLUMN owner TEXT;
+ALTER TABLE hosts ADD COLUMN business_unit TEXT;
+ALTER TABLE hosts ADD COLUMN criticality TEXT;
+
+CREATE INDEX idx_hosts_owner ON hosts(owner);
@@ -0,0 +1,23 @@
+-- Address is an IP for hosts and routers, or a CIDR for layer 2 segments
+CREATE TABLE topology_nodes (
+    id TEXT PRIMARY KEY,
+    address TEXT NOT NULL UNIQUE,
+    kind TEXT NOT NULL,
+    first_seen TIMESTAMP NOT NULL,
+    last_seen TIMESTAMP NOT NULL
+);
+
+CREATE TABLE topology_edges (
+    id TEXT PRIMARY KEY,
+    source_id TEXT NOT NULL,
+    target_id TEXT NOT NULL,
+    kind TEXT NOT NULL,
+    observations INTEGER NOT NULL DEFAULT 1,
+    first_seen TIMESTAMP NOT NULL,
+    last_seen TIMESTAMP NOT NULL,
+    FOREIGN KEY (source_id) REFERENCES topology_nodes (id) ON DELETE CASCADE,
+    FOREIGN KEY (target_id) REFERENCES topology_nodes (id) ON DELETE CASCADE,
+    UNIQUE (source_id, target_id, kind)
+);
+
+CREATE INDEX idx_topology_edges_target_id ON topology_edges(target_id);
@@ -0,0 +1,16 @@
+CREATE TABLE passive_dns (
+    id TEXT PRIMARY KEY,
+    query TEXT NOT NULL,
+    name TEXT NOT NULL,
+    record_type TEXT NOT NULL,
+    value TEXT NOT NULL,
+    ttl INTEGER NOT NULL,
+    client_ip TEXT NOT NULL,
+    server_ip TEXT NOT NULL,
+    observations INTEGER NOT NULL DEFAULT 1,
+    first_seen TIMESTAMP NOT NULL,
+    last_seen TIMESTAMP NOT NULL,
+    UNIQUE (name, record_type, value)
+);
+
+CREATE INDEX idx_passive_dns_value ON passive_dns(value);
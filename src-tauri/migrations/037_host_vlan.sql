@@ -0,0 +1,3 @@
+ALTER TABLE hosts ADD COLUMN vlan_id INTEGER;
+
+CREATE INDEX idx_hosts_vlan_id ON hosts(vlan_id);
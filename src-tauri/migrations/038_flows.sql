@@ -0,0 +1,14 @@
+CREATE TABLE flows (
+    id TEXT PRIMARY KEY,
+    client_ip TEXT NOT NULL,
+    server_ip TEXT NOT NULL,
+    protocol TEXT NOT NULL,
+    server_port INTEGER NOT NULL,
+    packets INTEGER NOT NULL DEFAULT 0,
+    bytes INTEGER NOT NULL DEFAULT 0,
+    first_seen TIMESTAMP NOT NULL,
+    last_seen TIMESTAMP NOT NULL,
+    UNIQUE (client_ip, server_ip, protocol, server_port)
+);
+
+CREATE INDEX idx_flows_server_ip ON flows(server_ip);
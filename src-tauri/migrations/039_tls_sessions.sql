@@ -0,0 +1,13 @@
+CREATE TABLE tls_sessions (
+    id TEXT PRIMARY KEY,
+    server_ip TEXT NOT NULL,
+    server_port INTEGER NOT NULL,
+    server_name TEXT NOT NULL DEFAULT '',
+    alpn TEXT,
+    tls_version TEXT NOT NULL,
+    client_ip TEXT NOT NULL,
+    observations INTEGER NOT NULL DEFAULT 1,
+    first_seen TIMESTAMP NOT NULL,
+    last_seen TIMESTAMP NOT NULL,
+    UNIQUE (server_ip, server_port, server_name)
+);
@@ -0,0 +1,17 @@
+CREATE TABLE packet_captures (
+    id TEXT PRIMARY KEY,
+    host_id TEXT NOT NULL,
+    interface TEXT NOT NULL,
+    filter TEXT NOT NULL,
+    status TEXT NOT NULL,
+    packets INTEGER NOT NULL DEFAULT 0,
+    evicted INTEGER NOT NULL DEFAULT 0,
+    data BLOB,
+    original_size INTEGER NOT NULL DEFAULT 0,
+    error TEXT,
+    started_at TIMESTAMP NOT NULL,
+    finished_at TIMESTAMP,
+    FOREIGN KEY (host_id) REFERENCES hosts (id) ON DELETE CASCADE
+);
+
+CREATE INDEX idx_packet_captures_host ON packet_captures(host_id);
@@ -0,0 +1,15 @@
+CREATE TABLE tls_fingerprints (
+    id TEXT PRIMARY KEY,
+    host_id TEXT NOT NULL,
+    kind TEXT NOT NULL,
+    hash TEXT NOT NULL,
+    software TEXT,
+    suspicious BOOLEAN NOT NULL DEFAULT FALSE,
+    observations INTEGER NOT NULL DEFAULT 1,
+    first_seen TIMESTAMP NOT NULL,
+    last_seen TIMESTAMP NOT NULL,
+    FOREIGN KEY (host_id) REFERENCES hosts (id) ON DELETE CASCADE,
+    UNIQUE (host_id, kind, hash)
+);
+
+CREATE INDEX idx_tls_fingerprints_hash ON tls_fingerprints(hash);
@@ -0,0 +1,14 @@
+CREATE TABLE http_metadata (
+    id TEXT PRIMARY KEY,
+    host_id TEXT NOT NULL,
+    port INTEGER NOT NULL DEFAULT 0,
+    kind TEXT NOT NULL,
+    value TEXT NOT NULL,
+    observations INTEGER NOT NULL DEFAULT 1,
+    first_seen TIMESTAMP NOT NULL,
+    last_seen TIMESTAMP NOT NULL,
+    FOREIGN KEY (host_id) REFERENCES hosts (id) ON DELETE CASCADE,
+    UNIQUE (host_id, port, kind, value)
+);
+
+CREATE INDEX idx_http_metadata_value ON http_metadata(kind, value);
@@ -0,0 +1,18 @@
+CREATE TABLE service_dependencies (
+    id TEXT PRIMARY KEY,
+    client_host_id TEXT NOT NULL,
+    server_host_id TEXT NOT NULL,
+    protocol TEXT NOT NULL,
+    server_port INTEGER NOT NULL,
+    service TEXT,
+    tier TEXT NOT NULL,
+    packets INTEGER NOT NULL DEFAULT 0,
+    bytes INTEGER NOT NULL DEFAULT 0,
+    first_seen TIMESTAMP NOT NULL,
+    last_seen TIMESTAMP NOT NULL,
+    FOREIGN KEY (client_host_id) REFERENCES hosts (id) ON DELETE CASCADE,
+    FOREIGN KEY (server_host_id) REFERENCES hosts (id) ON DELETE CASCADE,
+    UNIQUE (client_host_id, server_host_id, protocol, server_port)
+);
+
+CREATE INDEX idx_service_dependencies_server ON service_dependencies(server_host_id);
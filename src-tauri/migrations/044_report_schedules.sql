@@ -0,0 +1,24 @@
+CREATE TABLE report_schedules (
+    id TEXT PRIMARY KEY,
+    name TEXT NOT NULL,
+    targets TEXT NOT NULL,
+    scan_type TEXT NOT NULL,
+    interval_days INTEGER NOT NULL DEFAULT 7,
+    output_dir TEXT,
+    enabled BOOLEAN NOT NULL DEFAULT 1,
+    last_run_at TIMESTAMP,
+    next_run_at TIMESTAMP NOT NULL,
+    created_at TIMESTAMP NOT NULL
+);
+
+CREATE TABLE differential_reports (
+    id TEXT PRIMARY KEY,
+    schedule_id TEXT NOT NULL,
+    snapshot TEXT NOT NULL,
+    report TEXT NOT NULL,
+    changes INTEGER NOT NULL DEFAULT 0,
+    created_at TIMESTAMP NOT NULL,
+    FOREIGN KEY (schedule_id) REFERENCES report_schedules (id) ON DELETE CASCADE
+);
+
+CREATE INDEX idx_differential_reports_schedule ON differential_reports(schedule_id, created_at);
@@ -0,0 +1,2 @@
+ALTER TABLE vulnerabilities ADD COLUMN source TEXT;
+ALTER TABLE vulnerabilities ADD COLUMN original_severity TEXT;
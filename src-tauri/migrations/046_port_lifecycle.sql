@@ -0,0 +1,16 @@
+ALTER TABLE ports ADD COLUMN first_seen TIMESTAMP NOT NULL DEFAULT '1970-01-01 00:00:00+00:00';
+ALTER TABLE ports ADD COLUMN last_seen TIMESTAMP NOT NULL DEFAULT '1970-01-01 00:00:00+00:00';
+
+-- Each scan stores its own row for a port, so the lifecycle spans every
+-- row of the same host, number and protocol
+UPDATE ports SET
+    first_seen = (
+        SELECT MIN(other.created_at) FROM ports other
+        WHERE other.host_id = ports.host_id AND other.number = ports.number AND other.protocol = ports.protocol
+    ),
+    last_seen = (
+        SELECT MAX(other.created_at) FROM ports other
+        WHERE other.host_id = ports.host_id AND other.number = ports.number AND other.protocol = ports.protocol
+    );
+
+CREATE INDEX idx_ports_last_seen ON ports(last_seen);
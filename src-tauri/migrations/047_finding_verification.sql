@@ -0,0 +1,15 @@
+-- The check that produced a finding, so it can be re-run to verify a fix
+ALTER TABLE vulnerabilities ADD COLUMN check_ref TEXT;
+ALTER TABLE vulnerabilities ADD COLUMN status TEXT NOT NULL DEFAULT 'open';
+
+CREATE TABLE finding_verifications (
+    id TEXT PRIMARY KEY,
+    vulnerability_id TEXT NOT NULL,
+    outcome TEXT NOT NULL,
+    evidence TEXT NOT NULL,
+    evidence_sha256 TEXT NOT NULL,
+    verified_at TIMESTAMP NOT NULL,
+    FOREIGN KEY (vulnerability_id) REFERENCES vulnerabilities (id) ON DELETE CASCADE
+);
+
+CREATE INDEX idx_finding_verifications_vulnerability ON finding_verifications(vulnerability_id);
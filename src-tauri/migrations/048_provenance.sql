@@ -0,0 +1,5 @@
+-- What reported a port or finding and how far it can be relied on, 0 to
+-- 100. Rows from before this are left unknown.
+ALTER TABLE ports ADD COLUMN source TEXT;
+ALTER TABLE ports ADD COLUMN confidence REAL;
+ALTER TABLE vulnerabilities ADD COLUMN confidence REAL;
@@ -0,0 +1 @@
+ALTER TABLE hosts ADD COLUMN watched BOOLEAN NOT NULL DEFAULT 0;
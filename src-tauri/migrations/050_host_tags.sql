@@ -0,0 +1,9 @@
+CREATE TABLE host_tags (
+    host_id TEXT NOT NULL,
+    tag TEXT NOT NULL,
+    created_at TIMESTAMP NOT NULL,
+    PRIMARY KEY (host_id, tag),
+    FOREIGN KEY (host_id) REFERENCES hosts (id) ON DELETE CASCADE
+);
+
+CREATE INDEX idx_host_tags_tag ON host_tags(tag);
@@ -0,0 +1,22 @@
+-- Scans from before raw_outputs kept their tool output inline on the scan.
+-- Move it across as-is, marked uncompressed, so the column can go.
+ALTER TABLE raw_outputs ADD COLUMN compressed BOOLEAN NOT NULL DEFAULT 1;
+
+INSERT INTO raw_outputs (id, scan_id, format, data, original_size, created_at, compressed)
+SELECT
+    lower(hex(randomblob(16))),
+    id,
+    CASE
+        WHEN substr(raw_output, 1, 5) = CAST('<?xml' AS BLOB)
+          OR substr(raw_output, 1, 8) = CAST('<nmaprun' AS BLOB) THEN 'nmap-xml'
+        ELSE 'masscan-list'
+    END,
+    raw_output,
+    length(raw_output),
+    created_at,
+    0
+FROM scans
+WHERE raw_output IS NOT NULL
+  AND id NOT IN (SELECT scan_id FROM raw_outputs);
+
+ALTER TABLE scans DROP COLUMN raw_output;
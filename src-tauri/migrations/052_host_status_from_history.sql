@@ -0,0 +1,10 @@
+-- Scans only recorded liveness in host_status_history, leaving hosts at
+-- 'unknown'. Take each host's latest sample as its status.
+UPDATE hosts
+SET status = (
+    SELECT h.status FROM host_status_history h
+    WHERE h.host_id = hosts.id
+    ORDER BY h.recorded_at DESC
+    LIMIT 1
+)
+WHERE id IN (SELECT host_id FROM host_status_history);
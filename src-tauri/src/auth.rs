@@ -0,0 +1,198 @@
+use crate::database::{models::{IssuedApiToken, User}, operations::{ApiTokenOperations, AuditLogOperations, UserOperations}};
+use crate::error::LegionError;
+use chrono::Utc;
+use anyhow::{bail, Result};
+use serde::{Deserialize, Serialize};
+use sqlx::SqlitePool;
+
+/// What a user may do on a shared instance. Each role includes the
+/// permissions of the ones below it.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum Role {
+    Admin,
+    Operator,
+    ReadOnly,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+pub enum Permission {
+    View,
+    Scan,
+    Administer,
+}
+
+impl Permission {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Permission::View => "view",
+            Permission::Scan => "scan",
+            Permission::Administer => "administer",
+        }
+    }
+
+    pub fn parse(scope: &str) -> Result<Self> {
+        match scope {
+            "view" => Ok(Permission::View),
+            "scan" => Ok(Permission::Scan),
+            "administer" => Ok(Permission::Administer),
+            _ => bail!("Invalid token scope: {}", scope),
+        }
+    }
+}
+
+impl Role {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Role::Admin => "admin",
+            Role::Operator => "operator",
+            Role::ReadOnly => "read_only",
+        }
+    }
+
+    pub fn parse(role: &str) -> Result<Self> {
+        match role {
+            "admin" => Ok(Role::Admin),
+            "operator" => Ok(Role::Operator),
+            "read_only" => Ok(Role::ReadOnly),
+            _ => bail!("Invalid role: {}", role),
+        }
+    }
+
+    pub fn permits(&self, permission: Permission) -> bool {
+        let granted = match self {
+            Role::Admin => Permission::Administer,
+            Role::Operator => Permission::Scan,
+            Role::ReadOnly => Permission::View,
+        };
+        permission <= granted
+    }
+}
+
+/// Commands that change who may do what, what is in scope, or how the
+/// instance is configured.
+const ADMIN_COMMANDS: &[&str] = &[
+    "create_project",
+    "update_project_engagement",
+    "add_project_scope_entry",
+    "remove_project_scope_entry",
+    "get_audit_log",
+    "update_project_settings",
+    "set_config",
+    "set_bandwidth_budget",
+    "start_privileged_helper",
+    "stop_privileged_helper",
+    "ingest_cloud_assets",
+    "create_user",
+    "list_users",
+    "set_user_role",
+    "delete_user",
+    "create_api_token",
+    "list_api_tokens",
+    "revoke_api_token",
+];
+
+/// Commands that only read, besides the `get_`/`list_` family.
+const VIEW_COMMANDS: &[&str] = &[
+    "generate_report",
+    "summarize_targets",
+    "verify_scan_integrity",
+    "mark_read",
+];
+
+/// The permission a command needs. Anything not known to be read-only or
+/// administrative can touch the network and needs scanning rights.
+pub fn command_permission(command: &str) -> Permission {
+    if ADMIN_COMMANDS.contains(&command) {
+        Permission::Administer
+    } else if command.starts_with("get_") || command.starts_with("list_") || VIEW_COMMANDS.contains(&command) {
+        Permission::View
+    } else {
+        Permission::Scan
+    }
+}
+
+/// Per-command role checks for the API server. The desktop app talks to
+/// its own backend over IPC and is not subject to them.
+pub struct Authorizer;
+
+impl Authorizer {
+    /// Resolves a bearer token and checks that both the token's scope and
+    /// its owner's role permit `command`.
+    pub async fn authorize_token(pool: &SqlitePool, token: &str, command: &str) -> Result<User> {
+        let Some(api_token) = ApiTokenOperations::find_by_token(pool, token).await? else {
+            return Err(denied("Invalid API token".to_string()));
+        };
+        if api_token.revoked_at.is_some() {
+            return Err(denied(format!("API token {} has been revoked", api_token.name)));
+        }
+        if api_token.expires_at.is_some_and(|expires| expires <= Utc::now()) {
+            return Err(denied(format!("API token {} has expired", api_token.name)));
+        }
+
+        let Some(user) = UserOperations::find_by_id(pool, &api_token.user_id).await? else {
+            return Err(denied(format!("API token {} has no owner", api_token.name)));
+        };
+
+        let needed = command_permission(command);
+        if needed > Permission::parse(&api_token.scope)? {
+            return Err(denied(format!("API token {} is not scoped for {}", api_token.name, command)));
+        }
+        // A demoted user's older tokens lose the rights the role lost
+        if !Role::parse(&user.role)?.permits(needed) {
+            return Err(denied(format!("{} ({}) may not run {}", user.username, user.role, command)));
+        }
+
+        ApiTokenOperations::touch(pool, &api_token.id).await?;
+        Ok(user)
+    }
+
+    /// Issues a token for `user_id`, no broader than the owner's role
+    /// allows. `issued_by` is the API caller, already checked to be an
+    /// admin by `authorize_token`; `None` is the desktop app. The token is
+    /// only returned here; the database keeps its hash.
+    pub async fn issue_token(
+        pool: &SqlitePool,
+        issued_by: Option<&User>,
+        user_id: &str,
+        name: &str,
+        scope: &str,
+        expires_in_days: Option<i64>,
+    ) -> Result<IssuedApiToken> {
+        let Some(user) = UserOperations::find_by_id(pool, user_id).await? else {
+            return Err(LegionError::NotFound(format!("User not found: {}", user_id)).into());
+        };
+
+        let permission = Permission::parse(scope)?;
+        let role = Role::parse(&user.role)?;
+        if !role.permits(permission) {
+            return Err(denied(format!("{} ({}) cannot hold a {} token", user.username, role.as_str(), permission.as_str())));
+        }
+
+        let expires_at = match expires_in_days {
+            Some(days) if days <= 0 => return Err(LegionError::Validation(format!("Invalid token lifetime: {} days", days)).into()),
+            Some(days) => Some(Utc::now() + chrono::Duration::days(days)),
+            None => None,
+        };
+
+        let name = name.trim();
+        if name.is_empty() {
+            return Err(LegionError::Validation("Token name is required".to_string()).into());
+        }
+
+        let token = format!("lgn_{}{}", uuid::Uuid::new_v4().simple(), uuid::Uuid::new_v4().simple());
+        let api_token = ApiTokenOperations::create(pool, &user.id, name, &token, permission.as_str(), expires_at).await?;
+
+        let detail = match issued_by {
+            Some(caller) => format!("{} (issued by {})", name, caller.username),
+            None => name.to_string(),
+        };
+        AuditLogOperations::record(pool, None, "api_token_created", Some(&user.username), Some(&detail)).await?;
+
+        Ok(IssuedApiToken { token, api_token })
+    }
+}
+
+fn denied(message: String) -> anyhow::Error {
+    LegionError::Permission(message).into()
+}
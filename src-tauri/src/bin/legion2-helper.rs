@@ -0,0 +1,215 @@
+//! Privileged helper for LEGION2. `serve` is started by the app, usually
+//! through pkexec, and performs only raw-socket work: packet capture and
+//! nmap/masscan runs. `relay` runs unprivileged in place of a scanner and
+//! forwards the run to `serve`, so scanners read its output unchanged.
+
+// The app side uses the parts of the protocol this binary doesn't
+#[allow(dead_code)]
+#[path = "../helper/protocol.rs"]
+mod protocol;
+
+use anyhow::{Result, Context, bail};
+use base64::Engine;
+use protocol::*;
+use std::path::{Path, PathBuf};
+use std::process::Stdio;
+use tokio::io::{AsyncBufReadExt, AsyncReadExt, BufReader};
+use tokio::net::{TcpListener, TcpStream};
+use tokio::process::Command;
+use tokio::sync::mpsc;
+
+/// Directories scanners are run from. The caller's PATH is never used, so
+/// an unprivileged user can't substitute their own binary.
+#[cfg(unix)]
+const TOOL_DIRS: &[&str] = &["/usr/local/bin", "/usr/bin", "/usr/local/sbin", "/usr/sbin", "/opt/homebrew/bin"];
+#[cfg(windows)]
+const TOOL_DIRS: &[&str] = &[r"C:\Program Files (x86)\Nmap", r"C:\Program Files\Nmap", r"C:\Program Files\masscan"];
+
+/// Longest token line read from an unauthenticated connection
+const MAX_TOKEN_LINE: u64 = 256;
+
+#[tokio::main]
+async fn main() -> Result<()> {
+    let mut args = std::env::args().skip(1);
+    match args.next().as_deref() {
+        Some("serve") => serve().await,
+        Some("relay") => {
+            let tool = args.next().context("relay needs a tool name")?;
+            let code = relay(tool, args.collect()).await?;
+            std::process::exit(code);
+        }
+        _ => bail!("usage: legion2-helper serve | relay <tool> [args...]"),
+    }
+}
+
+async fn serve() -> Result<()> {
+    let mut stdin = BufReader::new(tokio::io::stdin());
+    let mut token = String::new();
+    stdin.read_line(&mut token).await?;
+    let token = token.trim().to_string();
+    if token.len() < 32 {
+        bail!("Expected an access token on stdin");
+    }
+
+    let listener = TcpListener::bind(("127.0.0.1", 0)).await?;
+    println!("{}", listener.local_addr()?.port());
+
+    // The app keeps stdin open for as long as it wants the helper
+    tokio::spawn(async move {
+        let mut rest = Vec::new();
+        let _ = stdin.read_to_end(&mut rest).await;
+        std::process::exit(0);
+    });
+
+    loop {
+        let (stream, _) = listener.accept().await?;
+        let token = token.clone();
+        tokio::spawn(async move {
+            if let Err(e) = handle(stream, &token).await {
+                eprintln!("legion2-helper: {}", e);
+            }
+        });
+    }
+}
+
+async fn handle(stream: TcpStream, token: &str) -> Result<()> {
+    let (reader, writer) = stream.into_split();
+    let mut reader = BufReader::new(reader);
+
+    let mut given = String::new();
+    (&mut reader).take(MAX_TOKEN_LINE).read_line(&mut given).await?;
+    if !token_matches(token, given.trim_end()) {
+        bail!("Rejected a connection with a bad token");
+    }
+
+    let mut connection = HelperConnection {
+        lines: reader.lines(),
+        writer,
+    };
+    let Some(request) = connection.receive::<HelperRequest>().await? else {
+        return Ok(());
+    };
+
+    let result = match request {
+        HelperRequest::Capture { interface } => capture(&mut connection, &interface).await,
+        HelperRequest::Scan { tool, args } => scan(&mut connection, &tool, args).await,
+    };
+    if let Err(e) = &result {
+        let _ = connection.send(&HelperResponse::Error { message: e.to_string() }).await;
+    }
+    result
+}
+
+/// Compares without short-circuiting so the token can't be guessed from
+/// response timing.
+fn token_matches(expected: &str, given: &str) -> bool {
+    expected.len() == given.len()
+        && expected.bytes().zip(given.bytes()).fold(0u8, |acc, (a, b)| acc | (a ^ b)) == 0
+}
+
+async fn capture(connection: &mut HelperConnection, interface: &str) -> Result<()> {
+    let mut capture = pcap::Capture::from_device(interface)
+        .and_then(|c| c.promisc(true).snaplen(65535).timeout(500).open())
+        .with_context(|| format!("Failed to open capture on {}", interface))?;
+    connection.send(&HelperResponse::Ready).await?;
+
+    let (frames_tx, mut frames_rx) = mpsc::channel::<Vec<u8>>(1000);
+    let interface = interface.to_string();
+    tokio::task::spawn_blocking(move || loop {
+        match capture.next_packet() {
+            Ok(packet) => {
+                if frames_tx.blocking_send(packet.data.to_vec()).is_err() {
+                    return;
+                }
+            }
+            Err(pcap::Error::TimeoutExpired) if !frames_tx.is_closed() => continue,
+            Err(pcap::Error::TimeoutExpired) => return,
+            Err(e) => {
+                eprintln!("legion2-helper: capture on {} stopped: {}", interface, e);
+                return;
+            }
+        }
+    });
+
+    while let Some(frame) = frames_rx.recv().await {
+        let data = base64::engine::general_purpose::STANDARD.encode(frame);
+        connection.send(&HelperResponse::Frame { data }).await?;
+    }
+
+    Ok(())
+}
+
+async fn scan(connection: &mut HelperConnection, tool: &str, args: Vec<String>) -> Result<()> {
+    check_scanner_args(tool, &args)?;
+
+    let mut cmd = Command::new(find_tool(tool)?);
+    cmd.args(&args)
+        .stdin(Stdio::null())
+        .stdout(Stdio::piped())
+        .stderr(Stdio::piped())
+        .kill_on_drop(true);
+    // Nothing from the environment the helper was elevated with
+    #[cfg(unix)]
+    cmd.env_clear().env("PATH", TOOL_DIRS.join(":"));
+
+    let mut child = cmd.spawn().with_context(|| format!("Failed to start {}", tool))?;
+    connection.send(&HelperResponse::Ready).await?;
+
+    let mut stdout = BufReader::new(child.stdout.take().context("stdout unavailable")?).lines();
+    let mut stderr = BufReader::new(child.stderr.take().context("stderr unavailable")?).lines();
+    let (mut stdout_open, mut stderr_open) = (true, true);
+
+    while stdout_open || stderr_open {
+        let response = tokio::select! {
+            line = stdout.next_line(), if stdout_open => match line? {
+                Some(line) => HelperResponse::Stdout { line },
+                None => {
+                    stdout_open = false;
+                    continue;
+                }
+            },
+            line = stderr.next_line(), if stderr_open => match line? {
+                Some(line) => HelperResponse::Stderr { line },
+                None => {
+                    stderr_open = false;
+                    continue;
+                }
+            },
+            // The relay never sends more, so anything here means it went
+            // away; dropping the child stops the scanner
+            _ = connection.lines.next_line() => return Ok(()),
+        };
+        connection.send(&response).await?;
+    }
+
+    let status = child.wait().await?;
+    connection.send(&HelperResponse::Exit { code: status.code().unwrap_or(-1) }).await
+}
+
+fn find_tool(tool: &str) -> Result<PathBuf> {
+    let file = format!("{}{}", tool, std::env::consts::EXE_SUFFIX);
+    TOOL_DIRS.iter()
+        .map(|dir| Path::new(dir).join(&file))
+        .find(|path| path.is_file())
+        .with_context(|| format!("{} not found in {}", tool, TOOL_DIRS.join(", ")))
+}
+
+async fn relay(tool: String, args: Vec<String>) -> Result<i32> {
+    let addr = std::env::var(ADDR_ENV).context("Helper address not set")?.parse()?;
+    let token = std::env::var(TOKEN_ENV).context("Helper token not set")?;
+
+    let mut connection = HelperConnection::open(addr, &token).await?;
+    connection.send(&HelperRequest::Scan { tool, args }).await?;
+
+    while let Some(response) = connection.receive().await? {
+        match response {
+            HelperResponse::Ready | HelperResponse::Frame { .. } => {}
+            HelperResponse::Stdout { line } => println!("{}", line),
+            HelperResponse::Stderr { line } => eprintln!("{}", line),
+            HelperResponse::Exit { code } => return Ok(code),
+            HelperResponse::Error { message } => bail!(message),
+        }
+    }
+
+    bail!("Privileged helper closed the connection")
+}
\ No newline at end of file
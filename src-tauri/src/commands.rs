@@ -1,38 +1,79 @@
+use crate::auth::{Authorizer, Role};
 use crate::scanning::*;
 use crate::database::{operations::*, models::*};
-use crate::utils::InputValidator;
+use crate::config::{AppConfig, ConfigUpdate};
+use crate::error::LegionError;
+use crate::reporting::{ReportGenerator, ReportScheduler};
+use crate::sniffer::SnifferRequest;
+use crate::sniffer::capture::{CaptureBackend, CaptureCapabilities};
+use crate::sniffer::dns::DnsParser;
+use crate::sniffer::host_capture::HostCaptureRequest;
+use crate::sniffer::import::PcapImportSummary;
+use crate::sources::{CloudCredentials, CloudIngestSummary, CloudInventory, CtDiscovery, CtDiscoverySummary, Source, WirelessImportSummary};
+use crate::utils::{channel_metrics, progress_channel, ChannelMetrics, CvssVector, InputValidator, NetworkInterface, NetworkUtils, ProcessManager, SshTarget};
 use crate::AppState;
 use serde::{Deserialize, Serialize};
 use tauri::State;
 use tokio::sync::mpsc;
+use ts_rs::TS;
 use anyhow::Result as AnyhowResult;
 
 #[tauri::command]
+#[allow(clippy::too_many_arguments)]
 pub async fn start_scan(
     state: State<'_, AppState>,
     target_ip: String,
-    scan_type: String,
+    scan_type: Option<String>,
+    ports: Option<String>,
+    options: Option<ScanOptions>,
+    started_by: Option<String>,
+    reason: Option<String>,
+    ticket_ref: Option<String>,
     window: tauri::Window,
-) -> Result<String, String> {
+) -> Result<String, LegionError> {
     let ip = InputValidator::validate_ip(&target_ip)
-        .map_err(|e| e.to_string())?;
+        .map_err(LegionError::from)?;
+
+    // Preset name or explicit port list; empty means the scan type's default
+    let ports = match ports.as_deref().map(str::trim).filter(|p| !p.is_empty()) {
+        Some(selection) => state.scan_coordinator
+            .resolve_ports(selection)
+            .await
+            .map_err(LegionError::from)?,
+        None => vec![],
+    };
+
+    // Fall back to the active project's default profile
+    let scan_type = scan_type
+        .or_else(|| state.scan_coordinator.default_scan_profile())
+        .unwrap_or_else(|| "quick".to_string());
     
     let scan_type_enum = match scan_type.as_str() {
         "quick" => ScanType::Quick,
         "comprehensive" => ScanType::Comprehensive,
         "stealth" => ScanType::Stealth,
+        "ot" => ScanType::Ot,
         _ => ScanType::Quick,
     };
 
+    // Annotations given here take precedence over any in the options
+    let mut options = options.unwrap_or_default();
+    let annotations = &mut options.annotations;
+    let non_empty = |value: Option<String>| value.map(|v| v.trim().to_string()).filter(|v| !v.is_empty());
+    annotations.started_by = non_empty(started_by).or(annotations.started_by.take());
+    annotations.reason = non_empty(reason).or(annotations.reason.take());
+    annotations.ticket_ref = non_empty(ticket_ref).or(annotations.ticket_ref.take());
+
     let target = ScanTarget {
         id: uuid::Uuid::new_v4(),
         ip,
         hostname: None,
-        ports: vec![],
+        ports,
         scan_type: scan_type_enum,
+        options,
     };
 
-    let (progress_tx, mut progress_rx) = mpsc::channel(100);
+    let (progress_tx, mut progress_rx) = progress_channel();
     
     // Forward progress updates to frontend
     let window_clone = window.clone();
@@ -49,37 +90,193 @@ pub async fn start_scan(
     let scan_id = state.scan_coordinator
         .start_scan(target, progress_tx)
         .await
-        .map_err(|e| e.to_string())?;
+        .map_err(LegionError::from)?;
 
     Ok(scan_id.to_string())
 }
 
+#[tauri::command]
+pub async fn create_massmap_plan(
+    state: State<'_, AppState>,
+    targets: Vec<String>,
+    scan_type: String,
+    options: Option<ScanOptions>,
+) -> Result<MassmapPlan, LegionError> {
+    let scan_type_enum = match scan_type.as_str() {
+        "quick" => ScanType::Quick,
+        "comprehensive" => ScanType::Comprehensive,
+        "stealth" => ScanType::Stealth,
+        "ot" => ScanType::Ot,
+        _ => ScanType::Quick,
+    };
+
+    state.scan_coordinator
+        .create_massmap_plan(&targets, scan_type_enum, options.unwrap_or_default())
+        .await
+        .map_err(LegionError::from)
+}
+
+#[tauri::command]
+pub async fn execute_massmap_plan(
+    state: State<'_, AppState>,
+    plan_id: String,
+) -> Result<(), LegionError> {
+    let uuid = uuid::Uuid::parse_str(&plan_id)
+        .map_err(|e| LegionError::Validation(format!("Invalid UUID: {}", e)))?;
+
+    state.scan_coordinator
+        .execute_massmap_plan(uuid)
+        .await
+        .map_err(LegionError::from)
+}
+
+#[tauri::command]
+pub async fn resume_massmap_plan(
+    state: State<'_, AppState>,
+    plan_id: String,
+) -> Result<(), LegionError> {
+    let uuid = uuid::Uuid::parse_str(&plan_id)
+        .map_err(|e| LegionError::Validation(format!("Invalid UUID: {}", e)))?;
+
+    state.scan_coordinator
+        .resume_massmap_plan(uuid)
+        .await
+        .map_err(LegionError::from)
+}
+
 #[tauri::command]
 pub async fn cancel_scan(
     state: State<'_, AppState>,
     scan_id: String,
-) -> Result<(), String> {
+) -> Result<(), LegionError> {
     let uuid = uuid::Uuid::parse_str(&scan_id)
-        .map_err(|e| format!("Invalid UUID: {}", e))?;
+        .map_err(|e| LegionError::Validation(format!("Invalid UUID: {}", e)))?;
     
     state.scan_coordinator
         .cancel_scan(uuid)
         .await
-        .map_err(|e| e.to_string())
+        .map_err(LegionError::from)
+}
+
+#[tauri::command]
+pub async fn pause_scan(
+    state: State<'_, AppState>,
+    scan_id: String,
+) -> Result<(), LegionError> {
+    let uuid = uuid::Uuid::parse_str(&scan_id)
+        .map_err(|e| LegionError::Validation(format!("Invalid UUID: {}", e)))?;
+
+    state.scan_coordinator
+        .pause_scan(uuid)
+        .await
+        .map_err(LegionError::from)
+}
+
+#[tauri::command]
+pub async fn resume_scan(
+    state: State<'_, AppState>,
+    scan_id: String,
+) -> Result<(), LegionError> {
+    let uuid = uuid::Uuid::parse_str(&scan_id)
+        .map_err(|e| LegionError::Validation(format!("Invalid UUID: {}", e)))?;
+
+    state.scan_coordinator
+        .resume_scan(uuid)
+        .await
+        .map_err(LegionError::from)
+}
+
+#[tauri::command]
+pub async fn resume_interrupted_scan(
+    state: State<'_, AppState>,
+    scan_id: String,
+    window: tauri::Window,
+) -> Result<String, LegionError> {
+    let uuid = uuid::Uuid::parse_str(&scan_id)
+        .map_err(|e| LegionError::Validation(format!("Invalid UUID: {}", e)))?;
+
+    // Progress is reported against the target, as for a fresh scan
+    let target = state.scan_coordinator
+        .list_interrupted_scans()
+        .await
+        .map_err(LegionError::from)?
+        .into_iter()
+        .find(|t| t.id == uuid)
+        .ok_or_else(|| LegionError::NotFound(format!("No interrupted scan {}", scan_id)))?;
+    let target_ip = target.ip.to_string();
+
+    let (progress_tx, mut progress_rx) = progress_channel();
+    tokio::spawn(async move {
+        while let Some(progress) = progress_rx.recv().await {
+            let _ = window.emit("scan-progress", &ScanProgressEvent {
+                target: target_ip.clone(),
+                progress,
+            });
+        }
+    });
+
+    let scan_id = state.scan_coordinator
+        .resume_interrupted_scan(uuid, progress_tx)
+        .await
+        .map_err(LegionError::from)?;
+
+    Ok(scan_id.to_string())
+}
+
+#[tauri::command]
+pub async fn list_interrupted_scans(
+    state: State<'_, AppState>,
+) -> Result<Vec<ScanTarget>, LegionError> {
+    state.scan_coordinator
+        .list_interrupted_scans()
+        .await
+        .map_err(LegionError::from)
+}
+
+#[tauri::command]
+pub async fn resume_masscan(
+    state: State<'_, AppState>,
+    scan_id: String,
+) -> Result<(), LegionError> {
+    let uuid = uuid::Uuid::parse_str(&scan_id)
+        .map_err(|e| LegionError::Validation(format!("Invalid UUID: {}", e)))?;
+
+    state.scan_coordinator
+        .resume_masscan(uuid)
+        .await
+        .map_err(LegionError::from)
+}
+
+#[tauri::command]
+pub async fn get_scan_output(
+    state: State<'_, AppState>,
+    scan_id: String,
+    from_line: Option<u64>,
+) -> Result<Vec<OutputLine>, LegionError> {
+    let uuid = uuid::Uuid::parse_str(&scan_id)
+        .map_err(|e| LegionError::Validation(format!("Invalid UUID: {}", e)))?;
+
+    Ok(state.scan_coordinator.get_scan_output(uuid, from_line.unwrap_or(0)))
 }
 
 #[tauri::command]
 pub async fn get_scan_results(
     state: State<'_, AppState>,
-) -> Result<Vec<ScanResult>, String> {
-    let results = state.scan_results.read().await;
-    Ok(results.clone())
+    filter: Option<ScanResultFilter>,
+) -> Result<Vec<ScanResult>, LegionError> {
+    let results = ScanOperations::find_results(&state.database.pool(), &filter.unwrap_or_default())
+        .await
+        .map_err(LegionError::from)?;
+
+    results.iter()
+        .map(|json| serde_json::from_str(json).map_err(LegionError::from))
+        .collect()
 }
 
 #[tauri::command]
 pub async fn get_active_scans(
     state: State<'_, AppState>,
-) -> Result<Vec<ActiveScanInfo>, String> {
+) -> Result<Vec<ActiveScanInfo>, LegionError> {
     let scans = state.scan_coordinator.get_active_scans().await;
     Ok(scans.into_iter()
         .map(|(id, status)| ActiveScanInfo {
@@ -94,21 +291,22 @@ pub async fn scan_network_range(
     state: State<'_, AppState>,
     range: NetworkRangeRequest,
     window: tauri::Window,
-) -> Result<Vec<String>, String> {
+) -> Result<NetworkScanCampaign, LegionError> {
     InputValidator::validate_cidr(&range.cidr)
-        .map_err(|e| e.to_string())?;
+        .map_err(LegionError::from)?;
     
     InputValidator::validate_scan_type(&range.scan_type)
-        .map_err(|e| e.to_string())?;
+        .map_err(LegionError::from)?;
 
     let scan_type_enum = match range.scan_type.as_str() {
         "quick" => ScanType::Quick,
         "comprehensive" => ScanType::Comprehensive,
         "stealth" => ScanType::Stealth,
+        "ot" => ScanType::Ot,
         _ => ScanType::Quick,
     };
 
-    let (progress_tx, mut progress_rx) = mpsc::channel(100);
+    let (progress_tx, mut progress_rx) = progress_channel();
     
     // Forward network scan progress
     let window_clone = window.clone();
@@ -118,116 +316,2234 @@ pub async fn scan_network_range(
         }
     });
 
-    let scan_ids = state.scan_coordinator
-        .scan_network_range(&range.cidr, &range.exclude, scan_type_enum, progress_tx)
+    state.scan_coordinator
+        .scan_network_range(&range.cidr, &range.exclude, scan_type_enum, range.options, progress_tx)
         .await
-        .map_err(|e| e.to_string())?;
-    
-    Ok(scan_ids.into_iter().map(|id| id.to_string()).collect())
+        .map_err(LegionError::from)
+}
+
+/// Scans every live host of the open project again as one campaign, or
+/// those matching `filter`, with `profile` or the project's default.
+#[tauri::command]
+pub async fn rescan_project(
+    state: State<'_, AppState>,
+    project_id: String,
+    profile: Option<String>,
+    filter: Option<HostFilter>,
+    window: tauri::Window,
+) -> Result<NetworkScanCampaign, LegionError> {
+    // Hosts are read from the open project's database
+    ensure_project_open(&state, &project_id)?;
+
+    let scan_type = profile
+        .or_else(|| state.scan_coordinator.default_scan_profile())
+        .unwrap_or_else(|| "quick".to_string());
+    InputValidator::validate_scan_type(&scan_type)
+        .map_err(LegionError::from)?;
+
+    let scan_type_enum = match scan_type.as_str() {
+        "quick" => ScanType::Quick,
+        "comprehensive" => ScanType::Comprehensive,
+        "stealth" => ScanType::Stealth,
+        "ot" => ScanType::Ot,
+        _ => ScanType::Quick,
+    };
+
+    let hosts = HostOperations::list(&state.database.pool(), &filter.unwrap_or_default())
+        .await
+        .map_err(LegionError::from)?;
+    let ips = hosts.iter()
+        .filter(|host| host.status == "up")
+        .filter_map(|host| host.ip.parse().ok())
+        .collect();
+
+    let (progress_tx, mut progress_rx) = progress_channel();
+    tokio::spawn(async move {
+        while let Some(progress) = progress_rx.recv().await {
+            let _ = window.emit("network-scan-progress", &progress);
+        }
+    });
+
+    let campaign = state.scan_coordinator
+        .rescan_hosts(ips, scan_type_enum, ScanOptions::default(), progress_tx)
+        .await
+        .map_err(LegionError::from)?;
+
+    record_user_event(
+        &state,
+        "project_rescanned",
+        None,
+        &campaign.campaign_id.to_string(),
+        &format!("Rescanning {} hosts with the {} profile", campaign.total_targets, scan_type),
+    ).await?;
+    Ok(campaign)
+}
+
+#[tauri::command]
+pub async fn delete_scan(
+    state: State<'_, AppState>,
+    scan_id: String,
+    rollback_ports: Option<bool>,
+) -> Result<u64, LegionError> {
+    let uuid = uuid::Uuid::parse_str(&scan_id)
+        .map_err(|e| LegionError::Validation(format!("Invalid UUID: {}", e)))?;
+
+    let removed = state.scan_coordinator
+        .delete_scan(uuid, rollback_ports.unwrap_or(false))
+        .await
+        .map_err(LegionError::from)?;
+
+    record_user_event(&state, "scan_deleted", None, &scan_id, &format!("Deleted scan {}", scan_id)).await?;
+    Ok(removed)
+}
+
+#[tauri::command]
+pub async fn cancel_campaign(
+    state: State<'_, AppState>,
+    campaign_id: String,
+) -> Result<usize, LegionError> {
+    let uuid = uuid::Uuid::parse_str(&campaign_id)
+        .map_err(|e| LegionError::Validation(format!("Invalid UUID: {}", e)))?;
+
+    state.scan_coordinator
+        .cancel_campaign(uuid)
+        .await
+        .map_err(LegionError::from)
+}
+
+#[tauri::command]
+pub async fn cancel_all_scans(
+    state: State<'_, AppState>,
+) -> Result<usize, LegionError> {
+    Ok(state.scan_coordinator.cancel_all_scans().await)
+}
+
+#[tauri::command]
+pub async fn verify_scan_integrity(
+    state: State<'_, AppState>,
+    scan_id: String,
+) -> Result<ScanIntegrity, LegionError> {
+    ScanOperations::verify_integrity(&state.database.pool(), &scan_id)
+        .await
+        .map_err(LegionError::from)
+}
+
+#[tauri::command]
+pub async fn get_scan_details(
+    state: State<'_, AppState>,
+    scan_id: String,
+) -> Result<ScanDetails, LegionError> {
+    let uuid = uuid::Uuid::parse_str(&scan_id)
+        .map_err(|e| LegionError::Validation(format!("Invalid UUID: {}", e)))?;
+    let pool = state.database.pool();
+
+    let scan = ScanOperations::find_by_id(&pool, &scan_id)
+        .await
+        .map_err(LegionError::from)?
+        .ok_or_else(|| LegionError::NotFound(format!("Scan not found: {}", scan_id)))?;
+
+    let targets: Vec<std::net::IpAddr> = serde_json::from_str(&scan.targets)
+        .map_err(LegionError::from)?;
+
+    let mut hosts = Vec::new();
+    for ip in &targets {
+        let Some(host) = HostOperations::find_by_ip(&pool, *ip).await.map_err(LegionError::from)? else {
+            continue;
+        };
+        let ports = PortOperations::find_by_host(&pool, &host.id)
+            .await
+            .map_err(LegionError::from)?;
+        hosts.push(ScanHostDetails { host, ports });
+    }
+
+    let raw_outputs = RawOutputOperations::find_by_scan(&pool, &scan_id)
+        .await
+        .map_err(LegionError::from)?;
+
+    let live_status = state.scan_coordinator.get_active_scans().await
+        .into_iter()
+        .find(|(id, _)| *id == uuid)
+        .map(|(_, status)| status);
+
+    Ok(ScanDetails {
+        duration_secs: scan.end_time.map(|end| (end - scan.start_time).num_seconds()),
+        has_raw_output: !raw_outputs.is_empty(),
+        scan,
+        targets,
+        hosts,
+        raw_outputs,
+        live_status,
+    })
+}
+
+#[tauri::command]
+pub async fn reparse_scan(
+    state: State<'_, AppState>,
+    scan_id: String,
+) -> Result<Vec<ScanResult>, LegionError> {
+    let uuid = uuid::Uuid::parse_str(&scan_id)
+        .map_err(|e| LegionError::Validation(format!("Invalid UUID: {}", e)))?;
+
+    state.scan_coordinator
+        .reparse_scan(uuid)
+        .await
+        .map_err(LegionError::from)
 }
 
 #[tauri::command]
 pub async fn get_scan_statistics(
     state: State<'_, AppState>,
-) -> Result<ScanStatistics, String> {
+) -> Result<ScanStatistics, LegionError> {
     Ok(state.scan_coordinator.get_scan_statistics().await)
 }
 
+#[tauri::command]
+pub async fn set_bandwidth_budget(
+    state: State<'_, AppState>,
+    packets_per_second: u32,
+) -> Result<BandwidthUsage, LegionError> {
+    if packets_per_second == 0 {
+        return Err(LegionError::Validation("Bandwidth budget must be greater than zero".to_string()));
+    }
+
+    state.scan_coordinator.set_bandwidth_budget(packets_per_second);
+    Ok(state.scan_coordinator.get_bandwidth_usage())
+}
+
+#[tauri::command]
+pub async fn get_bandwidth_usage(
+    state: State<'_, AppState>,
+) -> Result<BandwidthUsage, LegionError> {
+    Ok(state.scan_coordinator.get_bandwidth_usage())
+}
+
+/// Per-stage counters of the ingest pipeline
+#[tauri::command]
+pub async fn get_pipeline_metrics(
+    state: State<'_, AppState>,
+) -> Result<Vec<StageMetrics>, LegionError> {
+    Ok(state.scan_coordinator.pipeline_metrics())
+}
+
+/// Messages sent, dropped and merged on each internal channel
+#[tauri::command]
+pub async fn get_channel_metrics() -> Result<Vec<ChannelMetrics>, LegionError> {
+    Ok(channel_metrics())
+}
+
 // Database commands
 #[tauri::command]
 pub async fn get_hosts(
     state: State<'_, AppState>,
-) -> Result<Vec<Host>, String> {
-    HostOperations::list_all(state.database.pool())
+    filter: Option<HostFilter>,
+) -> Result<Vec<Host>, LegionError> {
+    HostOperations::list(&state.database.pool(), &filter.unwrap_or_default())
         .await
-        .map_err(|e| e.to_string())
+        .map_err(LegionError::from)
 }
 
 #[tauri::command]
 pub async fn get_host_details(
     state: State<'_, AppState>,
     host_id: String,
-) -> Result<HostDetails, String> {
-    let (host, ports) = HostOperations::get_with_ports(state.database.pool(), &host_id)
+) -> Result<HostDetails, LegionError> {
+    let (host, ports) = HostOperations::get_with_ports(&state.database.pool(), &host_id)
         .await
-        .map_err(|e| e.to_string())?;
+        .map_err(LegionError::from)?;
     
-    let vulnerabilities = VulnerabilityOperations::find_by_host(state.database.pool(), &host_id)
+    let vulnerabilities = VulnerabilityOperations::find_by_host(&state.database.pool(), &host_id)
         .await
-        .map_err(|e| e.to_string())?;
+        .map_err(LegionError::from)?;
+
+    let shares = ShareOperations::find_by_host(&state.database.pool(), &host_id)
+        .await
+        .map_err(LegionError::from)?;
+
+    let ip_history = HostIpHistoryOperations::find_by_host(&state.database.pool(), &host_id)
+        .await
+        .map_err(LegionError::from)?;
 
     Ok(HostDetails {
         host,
         ports,
         vulnerabilities,
+        shares,
+        ip_history,
     })
 }
 
 #[tauri::command]
-pub async fn get_vulnerabilities(
+pub async fn set_hosts_approved(
     state: State<'_, AppState>,
-    severity_filter: Option<String>,
-) -> Result<Vec<Vulnerability>, String> {
-    match severity_filter {
-        Some(_) => VulnerabilityOperations::find_high_severity(state.database.pool())
-            .await
-            .map_err(|e| e.to_string()),
-        None => {
-            // Get all vulnerabilities - you might want to add this method to VulnerabilityOperations
-            sqlx::query_as!(
-                Vulnerability,
-                "SELECT * FROM vulnerabilities ORDER BY discovered_at DESC"
-            )
-            .fetch_all(state.database.pool())
-            .await
-            .map_err(|e| e.to_string())
-        }
-    }
+    host_ids: Vec<String>,
+    approved: bool,
+) -> Result<(), LegionError> {
+    HostOperations::set_approved(&state.database.pool(), &host_ids, approved)
+        .await
+        .map_err(LegionError::from)
 }
 
+/// Stars hosts onto the watchlist, or takes them off it.
 #[tauri::command]
-pub async fn create_project(
+pub async fn set_hosts_watched(
     state: State<'_, AppState>,
-    name: String,
-    description: Option<String>,
-) -> Result<Project, String> {
-    ProjectOperations::create(state.database.pool(), &name, description.as_deref())
+    host_ids: Vec<String>,
+    watched: bool,
+) -> Result<(), LegionError> {
+    HostOperations::set_watched(&state.database.pool(), &host_ids, watched)
         .await
-        .map_err(|e| e.to_string())
+        .map_err(LegionError::from)
 }
 
 #[tauri::command]
-pub async fn list_projects(
+pub async fn get_watchlist(
     state: State<'_, AppState>,
-) -> Result<Vec<Project>, String> {
-    ProjectOperations::list_all(state.database.pool())
+) -> Result<Vec<Host>, LegionError> {
+    HostOperations::list_watched(&state.database.pool())
         .await
-        .map_err(|e| e.to_string())
+        .map_err(LegionError::from)
 }
 
-// Request/Response types
-#[derive(Serialize, Deserialize)]
-pub struct NetworkRangeRequest {
-    pub cidr: String,
-    pub exclude: Vec<String>,
-    pub scan_type: String,
+/// Assigns owner, business unit and criticality to many hosts at once.
+#[tauri::command]
+pub async fn bulk_update_hosts(
+    state: State<'_, AppState>,
+    host_ids: Vec<String>,
+    fields: HostOwnershipUpdate,
+) -> Result<(), LegionError> {
+    if let Some(criticality) = fields.criticality.as_deref().map(str::trim).filter(|c| !c.is_empty()) {
+        InputValidator::validate_criticality(criticality).map_err(LegionError::from)?;
+    }
+    for value in [&fields.owner, &fields.business_unit].into_iter().flatten() {
+        if value.len() > 128 {
+            return Err(LegionError::Validation("Owner and business unit must be at most 128 characters".to_string()));
+        }
+    }
+
+    HostOperations::update_ownership(&state.database.pool(), &host_ids, &fields)
+        .await
+        .map_err(LegionError::from)
 }
 
-#[derive(Serialize, Deserialize)]
-pub struct ActiveScanInfo {
-    pub id: String,
-    pub status: ScanStatus,
+/// Tags, untags, rates, moves or deletes a selection of hosts in one
+/// transaction. Returns the number of hosts changed.
+#[tauri::command]
+pub async fn bulk_hosts_action(
+    state: State<'_, AppState>,
+    host_ids: Vec<String>,
+    action: BulkHostAction,
+) -> Result<u64, LegionError> {
+    let pool = state.database.pool();
+
+    let action = match action {
+        BulkHostAction::Tag { tag } => BulkHostAction::Tag { tag: validate_tag(&tag)? },
+        BulkHostAction::Untag { tag } => BulkHostAction::Untag { tag: validate_tag(&tag)? },
+        BulkHostAction::SetCriticality { criticality } => {
+            let criticality = criticality.map(|c| c.trim().to_string()).filter(|c| !c.is_empty());
+            if let Some(criticality) = &criticality {
+                InputValidator::validate_criticality(criticality).map_err(LegionError::from)?;
+            }
+            BulkHostAction::SetCriticality { criticality }
+        }
+        action => action,
+    };
+
+    let changed = match &action {
+        BulkHostAction::MoveToProject { project_id } => {
+            let config = state.config.get();
+            if !config.database.per_project_files {
+                return Err(LegionError::Validation("Moving hosts needs per-project database files".to_string()));
+            }
+            ProjectOperations::find_by_id(state.database.main_pool(), project_id)
+                .await
+                .map_err(LegionError::from)?
+                .ok_or_else(|| LegionError::NotFound(format!("Project not found: {}", project_id)))?;
+            if state.database.open_project_id().as_deref() == Some(project_id.as_str()) {
+                return Err(LegionError::Validation("Hosts are already in this project".to_string()));
+            }
+
+            let path = config.project_database_path(project_id);
+            crate::database::Database::prepare_project_file(&path)
+                .await
+                .map_err(LegionError::from)?;
+            HostOperations::move_to_file(&pool, &host_ids, &path).await
+        }
+        action => HostOperations::bulk_action(&pool, &host_ids, action).await,
+    }
+    .map_err(LegionError::from)?;
+
+    let (kind, detail) = match &action {
+        BulkHostAction::Tag { tag } => ("tag", format!("Tagged {} hosts '{}'", changed, tag)),
+        BulkHostAction::Untag { tag } => ("untag", format!("Untagged {} hosts '{}'", changed, tag)),
+        BulkHostAction::SetCriticality { criticality } => (
+            "set_criticality",
+            format!("Set criticality of {} hosts to {}", changed, criticality.as_deref().unwrap_or("none")),
+        ),
+        BulkHostAction::MoveToProject { project_id } => ("move_to_project", format!("Moved {} hosts to project {}", changed, project_id)),
+        BulkHostAction::Delete => ("delete", format!("Deleted {} hosts", changed)),
+    };
+    record_user_event(&state, "hosts_bulk_action", None, kind, &detail).await?;
+    Ok(changed)
 }
 
-#[derive(Serialize, Deserialize)]
-pub struct ScanProgressEvent {
-    pub target: String,
-    pub progress: ScanProgress,
+fn validate_tag(tag: &str) -> Result<String, LegionError> {
+    let tag = tag.trim();
+    if tag.is_empty() || tag.len() > 64 {
+        return Err(LegionError::Validation("Tags must be 1 to 64 characters".to_string()));
+    }
+    Ok(tag.to_string())
 }
 
-#[derive(Serialize, Deserialize)]
-pub struct HostDetails {
-    pub host: Host,
-    pub ports: Vec<Port>,
-    pub vulnerabilities: Vec<Vulnerability>,
+#[tauri::command]
+pub async fn get_host_tags(
+    state: State<'_, AppState>,
+    host_id: String,
+) -> Result<Vec<String>, LegionError> {
+    HostOperations::tags(&state.database.pool(), &host_id)
+        .await
+        .map_err(LegionError::from)
+}
+
+#[tauri::command]
+pub async fn get_rogue_devices(
+    state: State<'_, AppState>,
+    filter: Option<HostFilter>,
+) -> Result<Vec<Host>, LegionError> {
+    HostOperations::list_rogue(&state.database.pool(), &filter.unwrap_or_default())
+        .await
+        .map_err(LegionError::from)
+}
+
+#[tauri::command]
+pub async fn get_baseline_summary(
+    state: State<'_, AppState>,
+) -> Result<BaselineSummary, LegionError> {
+    HostOperations::baseline_summary(&state.database.pool())
+        .await
+        .map_err(LegionError::from)
+}
+
+#[tauri::command]
+pub async fn get_dashboard_summary(
+    state: State<'_, AppState>,
+) -> Result<DashboardSummary, LegionError> {
+    DashboardOperations::summary(&state.database.pool())
+        .await
+        .map_err(LegionError::from)
+}
+
+/// Per-subnet host, service and finding counts for the heat map. IPv4
+/// hosts are grouped into /24s unless another prefix is asked for.
+#[tauri::command]
+pub async fn get_subnet_summary(
+    state: State<'_, AppState>,
+    project_id: String,
+    prefix_len: Option<u8>,
+    filter: Option<HostFilter>,
+) -> Result<Vec<SubnetSummary>, LegionError> {
+    let prefix_len = prefix_len.unwrap_or(24);
+    if !(1..=32).contains(&prefix_len) {
+        return Err(LegionError::Validation(format!("Subnet prefix must be between 1 and 32, got {}", prefix_len)));
+    }
+
+    ensure_project_open(&state, &project_id)?;
+
+    DashboardOperations::subnet_summary(&state.database.pool(), prefix_len, &filter.unwrap_or_default())
+        .await
+        .map_err(LegionError::from)
+}
+
+/// Hosts, routers and segments with the routes, layer 2 links and
+/// traffic seen between them.
+#[tauri::command]
+pub async fn get_topology(
+    state: State<'_, AppState>,
+    project_id: String,
+) -> Result<Topology, LegionError> {
+    ensure_project_open(&state, &project_id)?;
+
+    TopologyOperations::get(&state.database.pool())
+        .await
+        .map_err(LegionError::from)
+}
+
+/// Hosts ranked as places to pivot or monitor from, for the IoT Spider
+/// workflow.
+#[tauri::command]
+pub async fn get_pivot_candidates(
+    state: State<'_, AppState>,
+    project_id: String,
+) -> Result<Vec<PivotCandidate>, LegionError> {
+    ensure_project_open(&state, &project_id)?;
+
+    PivotScorer::candidates(&state.database.pool())
+        .await
+        .map_err(LegionError::from)
+}
+
+#[tauri::command]
+pub async fn enumerate_smb_shares(
+    state: State<'_, AppState>,
+    host_id: String,
+    username: Option<String>,
+    password: Option<String>,
+    domain: Option<String>,
+) -> Result<Vec<Share>, LegionError> {
+    let host = HostOperations::find_by_id(&state.database.pool(), &host_id)
+        .await
+        .map_err(LegionError::from)?
+        .ok_or_else(|| LegionError::NotFound(format!("Host not found: {}", host_id)))?;
+
+    let credentials = username.map(|username| SmbCredentials {
+        username,
+        password: password.unwrap_or_default(),
+        domain,
+    });
+
+    state.scan_coordinator
+        .enumerate_smb_shares(&host, credentials)
+        .await
+        .map_err(LegionError::from)
+}
+
+#[tauri::command]
+pub async fn list_port_presets(
+    state: State<'_, AppState>,
+) -> Result<Vec<PortPreset>, LegionError> {
+    PortPresetOperations::list_all(state.database.main_pool())
+        .await
+        .map_err(LegionError::from)
+}
+
+#[tauri::command]
+pub async fn create_port_preset(
+    state: State<'_, AppState>,
+    name: String,
+    description: Option<String>,
+    ports: String,
+) -> Result<PortPreset, LegionError> {
+    TopPorts::parse_spec(&ports).map_err(LegionError::from)?;
+
+    PortPresetOperations::create(state.database.main_pool(), &name, description.as_deref(), &ports)
+        .await
+        .map_err(LegionError::from)
+}
+
+#[tauri::command]
+pub async fn update_port_preset(
+    state: State<'_, AppState>,
+    preset_id: String,
+    name: String,
+    description: Option<String>,
+    ports: String,
+) -> Result<(), LegionError> {
+    TopPorts::parse_spec(&ports).map_err(LegionError::from)?;
+
+    PortPresetOperations::update(state.database.main_pool(), &preset_id, &name, description.as_deref(), &ports)
+        .await
+        .map_err(LegionError::from)
+}
+
+#[tauri::command]
+pub async fn delete_port_preset(
+    state: State<'_, AppState>,
+    preset_id: String,
+) -> Result<(), LegionError> {
+    PortPresetOperations::delete(state.database.main_pool(), &preset_id)
+        .await
+        .map_err(LegionError::from)
+}
+
+#[tauri::command]
+pub async fn list_follow_up_rules(
+    state: State<'_, AppState>,
+) -> Result<Vec<FollowUpRule>, LegionError> {
+    FollowUpRuleOperations::list_all(state.database.main_pool())
+        .await
+        .map_err(LegionError::from)
+}
+
+#[tauri::command]
+pub async fn create_follow_up_rule(
+    state: State<'_, AppState>,
+    name: String,
+    match_port: Option<u16>,
+    match_service: Option<String>,
+    action: String,
+    arguments: Option<String>,
+    enabled: Option<bool>,
+) -> Result<FollowUpRule, LegionError> {
+    FollowUpRules::validate(match_port, match_service.as_deref(), &action, arguments.as_deref())
+        .map_err(LegionError::from)?;
+
+    FollowUpRuleOperations::create(
+        state.database.main_pool(),
+        &name,
+        match_port,
+        match_service.as_deref().map(str::trim).filter(|s| !s.is_empty()),
+        &action,
+        arguments.as_deref().map(str::trim).filter(|a| !a.is_empty()),
+        enabled.unwrap_or(true),
+    )
+    .await
+    .map_err(LegionError::from)
+}
+
+#[tauri::command]
+#[allow(clippy::too_many_arguments)]
+pub async fn update_follow_up_rule(
+    state: State<'_, AppState>,
+    rule_id: String,
+    name: String,
+    match_port: Option<u16>,
+    match_service: Option<String>,
+    action: String,
+    arguments: Option<String>,
+    enabled: bool,
+) -> Result<(), LegionError> {
+    FollowUpRules::validate(match_port, match_service.as_deref(), &action, arguments.as_deref())
+        .map_err(LegionError::from)?;
+
+    FollowUpRuleOperations::update(
+        state.database.main_pool(),
+        &rule_id,
+        &name,
+        match_port,
+        match_service.as_deref().map(str::trim).filter(|s| !s.is_empty()),
+        &action,
+        arguments.as_deref().map(str::trim).filter(|a| !a.is_empty()),
+        enabled,
+    )
+    .await
+    .map_err(LegionError::from)
+}
+
+#[tauri::command]
+pub async fn delete_follow_up_rule(
+    state: State<'_, AppState>,
+    rule_id: String,
+) -> Result<(), LegionError> {
+    FollowUpRuleOperations::delete(state.database.main_pool(), &rule_id)
+        .await
+        .map_err(LegionError::from)
+}
+
+#[tauri::command]
+pub async fn list_interfaces() -> Result<Vec<NetworkInterface>, LegionError> {
+    NetworkUtils::list_interfaces().map_err(LegionError::from)
+}
+
+#[tauri::command]
+pub async fn list_nse_scripts(
+    state: State<'_, AppState>,
+) -> Result<Vec<NseScript>, LegionError> {
+    state.scan_coordinator
+        .list_nse_scripts()
+        .await
+        .map_err(LegionError::from)
+}
+
+#[tauri::command]
+pub async fn get_capture_capabilities() -> Result<CaptureCapabilities, LegionError> {
+    tokio::task::spawn_blocking(CaptureBackend::detect)
+        .await
+        .map_err(|e| LegionError::Internal(e.to_string()))
+}
+
+#[tauri::command]
+pub async fn summarize_targets(ips: Vec<String>) -> Result<Vec<String>, LegionError> {
+    let ips = ips.iter()
+        .map(|ip| InputValidator::validate_ip(ip).map_err(LegionError::from))
+        .collect::<Result<Vec<_>, _>>()?;
+
+    Ok(NetworkUtils::summarize_targets(&ips)
+        .iter()
+        .map(|block| block.to_string())
+        .collect())
+}
+
+#[tauri::command]
+pub async fn get_top_ports(count: usize) -> Result<Vec<u16>, LegionError> {
+    Ok(TopPorts::tcp(count))
+}
+
+#[tauri::command]
+pub async fn probe_udp_services(
+    state: State<'_, AppState>,
+    ip: String,
+) -> Result<Vec<crate::scanning::Port>, LegionError> {
+    let ip = InputValidator::validate_ip(&ip).map_err(LegionError::from)?;
+
+    state.scan_coordinator
+        .probe_udp_services(ip)
+        .await
+        .map_err(LegionError::from)
+}
+
+#[tauri::command]
+pub async fn ptr_sweep(
+    state: State<'_, AppState>,
+    cidr: String,
+    exclude: Option<Vec<String>>,
+) -> Result<Vec<PtrRecord>, LegionError> {
+    state.scan_coordinator
+        .ptr_sweep(&cidr, &exclude.unwrap_or_default())
+        .await
+        .map_err(LegionError::from)
+}
+
+#[tauri::command]
+pub async fn classify_device(
+    state: State<'_, AppState>,
+    host_id: String,
+) -> Result<Option<DeviceMatch>, LegionError> {
+    let pool = state.database.pool();
+    let host = HostOperations::find_by_id(&pool, &host_id)
+        .await
+        .map_err(LegionError::from)?
+        .ok_or_else(|| LegionError::NotFound(format!("Host not found: {}", host_id)))?;
+
+    let ports: Vec<crate::scanning::Port> = PortOperations::find_open_ports(&pool, &host.id)
+        .await
+        .map_err(LegionError::from)?
+        .into_iter()
+        .map(|p| crate::scanning::Port {
+            number: p.number as u16,
+            protocol: p.protocol,
+            state: p.state,
+            service: p.service,
+            version: p.version,
+            banner: p.banner,
+        })
+        .collect();
+
+    state.scan_coordinator
+        .classify_device(&host, &IotSpider::web_ports(&ports))
+        .await
+        .map_err(LegionError::from)
+}
+
+#[tauri::command]
+pub async fn get_iot_devices(
+    state: State<'_, AppState>,
+    filter: Option<HostFilter>,
+) -> Result<Vec<Host>, LegionError> {
+    HostOperations::list_iot(&state.database.pool(), &filter.unwrap_or_default())
+        .await
+        .map_err(LegionError::from)
+}
+
+#[tauri::command]
+pub async fn enumerate_port_mappings(
+    state: State<'_, AppState>,
+    host_id: String,
+) -> Result<Vec<NatPortMapping>, LegionError> {
+    let host = HostOperations::find_by_id(&state.database.pool(), &host_id)
+        .await
+        .map_err(LegionError::from)?
+        .ok_or_else(|| LegionError::NotFound(format!("Host not found: {}", host_id)))?;
+
+    state.scan_coordinator
+        .enumerate_port_mappings(&host)
+        .await
+        .map_err(LegionError::from)
+}
+
+#[tauri::command]
+pub async fn get_port_mappings(
+    state: State<'_, AppState>,
+    host_id: String,
+) -> Result<Vec<NatPortMapping>, LegionError> {
+    NatPortMappingOperations::find_by_host(&state.database.pool(), &host_id)
+        .await
+        .map_err(LegionError::from)
+}
+
+#[tauri::command]
+pub async fn get_ics_assets(
+    state: State<'_, AppState>,
+    host_id: Option<String>,
+) -> Result<Vec<IcsAsset>, LegionError> {
+    let pool = state.database.pool();
+    match host_id {
+        Some(host_id) => IcsAssetOperations::find_by_host(&pool, &host_id).await,
+        None => IcsAssetOperations::list_all(&pool).await,
+    }
+    .map_err(LegionError::from)
+}
+
+#[tauri::command]
+pub async fn audit_ssh(
+    state: State<'_, AppState>,
+    host_id: String,
+    port: Option<u16>,
+) -> Result<SshAudit, LegionError> {
+    let host = HostOperations::find_by_id(&state.database.pool(), &host_id)
+        .await
+        .map_err(LegionError::from)?
+        .ok_or_else(|| LegionError::NotFound(format!("Host not found: {}", host_id)))?;
+
+    state.scan_coordinator
+        .audit_ssh(&host, port.unwrap_or(SSH_PORT))
+        .await
+        .map_err(LegionError::from)
+}
+
+#[tauri::command]
+pub async fn check_anonymous_access(
+    state: State<'_, AppState>,
+    host_id: String,
+    protocol: String,
+    port: u16,
+) -> Result<Option<AnonymousAccess>, LegionError> {
+    let host = HostOperations::find_by_id(&state.database.pool(), &host_id)
+        .await
+        .map_err(LegionError::from)?
+        .ok_or_else(|| LegionError::NotFound(format!("Host not found: {}", host_id)))?;
+
+    state.scan_coordinator
+        .check_anonymous_access(&host, &protocol, port)
+        .await
+        .map_err(LegionError::from)
+}
+
+#[tauri::command]
+pub async fn get_anonymous_access(
+    state: State<'_, AppState>,
+    host_id: String,
+) -> Result<Vec<AnonymousAccess>, LegionError> {
+    AnonymousAccessOperations::find_by_host(&state.database.pool(), &host_id)
+        .await
+        .map_err(LegionError::from)
+}
+
+#[tauri::command]
+pub async fn test_smtp_relay(
+    state: State<'_, AppState>,
+    host_id: String,
+    port: Option<u16>,
+) -> Result<ServiceCheck, LegionError> {
+    let host = HostOperations::find_by_id(&state.database.pool(), &host_id)
+        .await
+        .map_err(LegionError::from)?
+        .ok_or_else(|| LegionError::NotFound(format!("Host not found: {}", host_id)))?;
+
+    state.scan_coordinator
+        .test_smtp_relay(&host, port.unwrap_or(SMTP_PORTS[0]))
+        .await
+        .map_err(LegionError::from)
+}
+
+#[tauri::command]
+pub async fn check_datastore(
+    state: State<'_, AppState>,
+    host_id: String,
+    datastore: String,
+    port: Option<u16>,
+) -> Result<ServiceCheck, LegionError> {
+    let host = HostOperations::find_by_id(&state.database.pool(), &host_id)
+        .await
+        .map_err(LegionError::from)?
+        .ok_or_else(|| LegionError::NotFound(format!("Host not found: {}", host_id)))?;
+
+    let port = match port {
+        Some(port) => port,
+        None => DATASTORES.iter()
+            .find(|(name, _, _)| *name == datastore)
+            .map(|(_, port, _)| *port)
+            .ok_or_else(|| LegionError::Validation(format!("Unsupported datastore: {}", datastore)))?,
+    };
+
+    state.scan_coordinator
+        .check_datastore(&host, &datastore, port)
+        .await
+        .map_err(LegionError::from)
+}
+
+#[tauri::command]
+pub async fn check_smb_security(
+    state: State<'_, AppState>,
+    host_id: String,
+) -> Result<SmbSecurityReport, LegionError> {
+    let host = HostOperations::find_by_id(&state.database.pool(), &host_id)
+        .await
+        .map_err(LegionError::from)?
+        .ok_or_else(|| LegionError::NotFound(format!("Host not found: {}", host_id)))?;
+
+    state.scan_coordinator
+        .check_smb_security(&host)
+        .await
+        .map_err(LegionError::from)
+}
+
+#[tauri::command]
+pub async fn probe_ipmi(
+    state: State<'_, AppState>,
+    host_id: String,
+    port: Option<u16>,
+) -> Result<Option<IpmiReport>, LegionError> {
+    let host = HostOperations::find_by_id(&state.database.pool(), &host_id)
+        .await
+        .map_err(LegionError::from)?
+        .ok_or_else(|| LegionError::NotFound(format!("Host not found: {}", host_id)))?;
+
+    state.scan_coordinator
+        .probe_ipmi(&host, port.unwrap_or(IPMI_PORT))
+        .await
+        .map_err(LegionError::from)
+}
+
+#[tauri::command]
+pub async fn get_service_checks(
+    state: State<'_, AppState>,
+    host_id: String,
+) -> Result<Vec<ServiceCheck>, LegionError> {
+    ServiceCheckOperations::find_by_host(&state.database.pool(), &host_id)
+        .await
+        .map_err(LegionError::from)
+}
+
+#[tauri::command]
+pub async fn get_ssh_audits(
+    state: State<'_, AppState>,
+    host_id: String,
+) -> Result<Vec<SshAudit>, LegionError> {
+    SshAuditOperations::find_by_host(&state.database.pool(), &host_id)
+        .await
+        .map_err(LegionError::from)
+}
+
+#[tauri::command]
+pub async fn get_os_observations(
+    state: State<'_, AppState>,
+    host_id: String,
+) -> Result<Vec<OsObservation>, LegionError> {
+    HostOperations::find_os_observations(&state.database.pool(), &host_id)
+        .await
+        .map_err(LegionError::from)
+}
+
+#[tauri::command]
+pub async fn get_host_shares(
+    state: State<'_, AppState>,
+    host_id: String,
+) -> Result<Vec<Share>, LegionError> {
+    ShareOperations::find_by_host(&state.database.pool(), &host_id)
+        .await
+        .map_err(LegionError::from)
+}
+
+#[tauri::command]
+pub async fn get_vulnerabilities(
+    state: State<'_, AppState>,
+    severity_filter: Option<String>,
+) -> Result<Vec<Vulnerability>, LegionError> {
+    match severity_filter {
+        Some(_) => VulnerabilityOperations::find_high_severity(&state.database.pool())
+            .await
+            .map_err(LegionError::from),
+        None => {
+            // Get all vulnerabilities - you might want to add this method to VulnerabilityOperations
+            sqlx::query_as!(
+                Vulnerability,
+                "SELECT * FROM vulnerabilities ORDER BY discovered_at DESC"
+            )
+            .fetch_all(&state.database.pool())
+            .await
+            .map_err(LegionError::from)
+        }
+    }
+}
+
+/// A manually entered finding's rating. With a CVSS vector the score is
+/// computed from it; with a score the severity is derived from it; only
+/// without either must the severity be given, on any scale the severity
+/// mapping knows.
+struct ManualRating {
+    severity: String,
+    cvss_score: Option<f32>,
+    cvss_vector: Option<String>,
+    /// The severity as entered, when it was
+    original_severity: Option<String>,
+}
+
+/// Checks a manually entered finding before it is stored: the host must
+/// exist and the port, when given, must belong to it.
+async fn validate_manual_vulnerability(
+    state: &State<'_, AppState>,
+    host_id: &str,
+    port_id: Option<&str>,
+    name: &str,
+    severity: Option<String>,
+    cvss_score: Option<f32>,
+    cvss_vector: Option<String>,
+) -> Result<ManualRating, LegionError> {
+    if name.trim().is_empty() {
+        return Err(LegionError::Validation("Vulnerability name is required".to_string()));
+    }
+
+    let rating = match (cvss_vector.filter(|v| !v.trim().is_empty()), cvss_score) {
+        (Some(vector), _) => {
+            let parsed = CvssVector::parse(&vector).map_err(LegionError::from)?;
+            ManualRating {
+                severity: parsed.severity().as_str().to_string(),
+                cvss_score: Some(parsed.base_score()),
+                cvss_vector: Some(vector.trim().to_string()),
+                original_severity: None,
+            }
+        }
+        (None, Some(score)) => {
+            InputValidator::validate_cvss_score(score).map_err(LegionError::from)?;
+            ManualRating {
+                severity: Severity::from_cvss(score).as_str().to_string(),
+                cvss_score: Some(score),
+                cvss_vector: None,
+                original_severity: None,
+            }
+        }
+        (None, None) => {
+            let severity = severity.ok_or_else(|| LegionError::Validation("A severity, CVSS score or CVSS vector is required".to_string()))?;
+            let mapped = SeverityNormalizer::map(SEVERITY_SOURCE_MANUAL, &severity)
+                .ok_or_else(|| LegionError::Validation(format!("Invalid severity: {}", severity)))?;
+            ManualRating {
+                severity: mapped.as_str().to_string(),
+                cvss_score: None,
+                cvss_vector: None,
+                original_severity: Some(severity.trim().to_string()),
+            }
+        }
+    };
+
+    HostOperations::find_by_id(&state.database.pool(), host_id)
+        .await
+        .map_err(LegionError::from)?
+        .ok_or_else(|| LegionError::NotFound(format!("Host not found: {}", host_id)))?;
+
+    if let Some(port_id) = port_id {
+        let ports = PortOperations::find_by_host(&state.database.pool(), host_id)
+            .await
+            .map_err(LegionError::from)?;
+        if !ports.iter().any(|p| p.id == port_id) {
+            return Err(LegionError::Validation(format!("Port {} does not belong to host {}", port_id, host_id)));
+        }
+    }
+
+    Ok(rating)
+}
+
+#[tauri::command]
+pub async fn create_vulnerability(
+    state: State<'_, AppState>,
+    host_id: String,
+    port_id: Option<String>,
+    name: String,
+    severity: Option<String>,
+    description: String,
+    cvss: Option<f32>,
+    cvss_vector: Option<String>,
+) -> Result<Vulnerability, LegionError> {
+    let rating = validate_manual_vulnerability(
+        &state, &host_id, port_id.as_deref(), &name, severity, cvss, cvss_vector,
+    ).await?;
+
+    // A finding on a service that announces itself is verified by grabbing
+    // the banner again
+    let check_ref = match port_id.as_deref() {
+        Some(port_id) => PortOperations::find_by_host(&state.database.pool(), &host_id)
+            .await
+            .map_err(LegionError::from)?
+            .into_iter()
+            .find(|p| p.id == port_id && p.protocol == "tcp" && p.banner.is_some())
+            .map(|p| FindingCheck::Banner { port: p.number as u16 }.to_ref()),
+        None => None,
+    };
+
+    let vuln = VulnerabilityOperations::create(
+        &state.database.pool(),
+        &host_id,
+        port_id.as_deref(),
+        name.trim(),
+        &rating.severity,
+        &description,
+        rating.cvss_score,
+        rating.cvss_vector.as_deref(),
+        SEVERITY_SOURCE_MANUAL,
+        rating.original_severity.as_deref(),
+        check_ref.as_deref(),
+    )
+    .await
+    .map_err(LegionError::from)?;
+
+    rescore_host(&state, &vuln.host_id).await?;
+    Ok(vuln)
+}
+
+/// Re-runs the check behind a finding to confirm whether it was fixed.
+#[tauri::command]
+pub async fn verify_fixed(
+    state: State<'_, AppState>,
+    vulnerability_id: String,
+) -> Result<FindingVerification, LegionError> {
+    state.scan_coordinator
+        .verify_finding(&vulnerability_id)
+        .await
+        .map_err(LegionError::from)
+}
+
+#[tauri::command]
+pub async fn get_finding_verifications(
+    state: State<'_, AppState>,
+    vulnerability_id: String,
+) -> Result<Vec<FindingVerification>, LegionError> {
+    FindingVerificationOperations::find_by_vulnerability(&state.database.pool(), &vulnerability_id)
+        .await
+        .map_err(LegionError::from)
+}
+
+#[tauri::command]
+pub async fn update_vulnerability(
+    state: State<'_, AppState>,
+    vulnerability_id: String,
+    port_id: Option<String>,
+    name: String,
+    severity: Option<String>,
+    description: String,
+    cvss: Option<f32>,
+    cvss_vector: Option<String>,
+) -> Result<Vulnerability, LegionError> {
+    let existing = VulnerabilityOperations::find_by_id(&state.database.pool(), &vulnerability_id)
+        .await
+        .map_err(LegionError::from)?
+        .ok_or_else(|| LegionError::NotFound(format!("Vulnerability not found: {}", vulnerability_id)))?;
+    let rating = validate_manual_vulnerability(
+        &state, &existing.host_id, port_id.as_deref(), &name, severity, cvss, cvss_vector,
+    ).await?;
+
+    let vuln = VulnerabilityOperations::update(
+        &state.database.pool(),
+        &vulnerability_id,
+        port_id.as_deref(),
+        name.trim(),
+        &rating.severity,
+        &description,
+        rating.cvss_score,
+        rating.cvss_vector.as_deref(),
+        rating.original_severity.as_deref(),
+    )
+    .await
+    .map_err(LegionError::from)?;
+
+    record_user_event(&state, "vulnerability_updated", Some(&vuln.host_id), &vuln.id, &format!("Edited finding: {}", vuln.name))
+        .await?;
+    rescore_host(&state, &vuln.host_id).await?;
+    Ok(vuln)
+}
+
+#[tauri::command]
+pub async fn delete_vulnerability(
+    state: State<'_, AppState>,
+    vulnerability_id: String,
+) -> Result<(), LegionError> {
+    let existing = VulnerabilityOperations::find_by_id(&state.database.pool(), &vulnerability_id)
+        .await
+        .map_err(LegionError::from)?;
+
+    VulnerabilityOperations::delete(&state.database.pool(), &vulnerability_id)
+        .await
+        .map_err(LegionError::from)?;
+
+    match existing {
+        Some(vuln) => {
+            record_user_event(&state, "vulnerability_deleted", Some(&vuln.host_id), &vuln.id, &format!("Deleted finding: {}", vuln.name))
+                .await?;
+            rescore_host(&state, &vuln.host_id).await
+        }
+        None => Ok(()),
+    }
+}
+
+#[tauri::command]
+pub async fn set_vulnerability_threat_intel(
+    state: State<'_, AppState>,
+    vulnerability_id: String,
+    kev: bool,
+    epss: Option<f32>,
+) -> Result<Vulnerability, LegionError> {
+    if let Some(epss) = epss {
+        if !(0.0..=1.0).contains(&epss) {
+            return Err(LegionError::Validation(format!("EPSS must be between 0.0 and 1.0: {}", epss)));
+        }
+    }
+
+    let vuln = VulnerabilityOperations::set_threat_intel(&state.database.pool(), &vulnerability_id, kev, epss)
+        .await
+        .map_err(LegionError::from)?;
+
+    record_user_event(&state, "threat_intel_updated", Some(&vuln.host_id), &vuln.id, &format!("Updated KEV/EPSS for {}", vuln.name))
+        .await?;
+    rescore_host(&state, &vuln.host_id).await?;
+    Ok(vuln)
+}
+
+#[tauri::command]
+pub async fn recompute_risk_scores(
+    state: State<'_, AppState>,
+) -> Result<Vec<Host>, LegionError> {
+    RiskScorer::update_all(&state.database.pool())
+        .await
+        .map_err(LegionError::from)?;
+
+    HostOperations::list_all(&state.database.pool())
+        .await
+        .map_err(LegionError::from)
+}
+
+async fn rescore_host(state: &State<'_, AppState>, host_id: &str) -> Result<(), LegionError> {
+    let host = HostOperations::find_by_id(&state.database.pool(), host_id)
+        .await
+        .map_err(LegionError::from)?
+        .ok_or_else(|| LegionError::NotFound(format!("Host not found: {}", host_id)))?;
+
+    RiskScorer::update_host(&state.database.pool(), &host)
+        .await
+        .map(|_| ())
+        .map_err(LegionError::from)
+}
+
+async fn record_user_event(
+    state: &State<'_, AppState>,
+    action: &str,
+    host_id: Option<&str>,
+    entity_id: &str,
+    message: &str,
+) -> Result<(), LegionError> {
+    EventOperations::record(&state.database.pool(), EVENT_USER, action, host_id, Some(entity_id), message)
+        .await
+        .map_err(LegionError::from)
+}
+
+#[tauri::command]
+pub async fn get_events(
+    state: State<'_, AppState>,
+    filter: Option<EventFilter>,
+    range: Option<TimeRange>,
+    limit: Option<i32>,
+) -> Result<Vec<TimelineEvent>, LegionError> {
+    EventOperations::list(
+        &state.database.pool(),
+        &filter.unwrap_or_default(),
+        &range.unwrap_or_default(),
+        limit.unwrap_or(500),
+    )
+    .await
+    .map_err(LegionError::from)
+}
+
+#[tauri::command]
+pub async fn get_notifications(
+    state: State<'_, AppState>,
+    unread_only: Option<bool>,
+    limit: Option<i32>,
+) -> Result<Vec<Notification>, LegionError> {
+    NotificationOperations::list(&state.database.pool(), unread_only.unwrap_or(false), limit.unwrap_or(200))
+        .await
+        .map_err(LegionError::from)
+}
+
+/// Marks the given notifications read, or all of them when no ids are
+/// passed. Returns how many changed.
+#[tauri::command]
+pub async fn mark_read(
+    state: State<'_, AppState>,
+    notification_ids: Option<Vec<String>>,
+) -> Result<u64, LegionError> {
+    let pool = state.database.pool();
+    let Some(ids) = notification_ids else {
+        return NotificationOperations::mark_all_read(&pool)
+            .await
+            .map_err(LegionError::from);
+    };
+
+    let mut changed = 0;
+    for id in &ids {
+        changed += NotificationOperations::mark_read(&pool, id)
+            .await
+            .map_err(LegionError::from)?;
+    }
+    Ok(changed)
+}
+
+#[tauri::command]
+pub async fn create_project(
+    state: State<'_, AppState>,
+    name: String,
+    description: Option<String>,
+) -> Result<Project, LegionError> {
+    ProjectOperations::create(state.database.main_pool(), &name, description.as_deref())
+        .await
+        .map_err(LegionError::from)
+}
+
+#[tauri::command]
+pub async fn update_project_engagement(
+    state: State<'_, AppState>,
+    project_id: String,
+    details: EngagementDetails,
+) -> Result<(), LegionError> {
+    ProjectOperations::update_engagement(state.database.main_pool(), &project_id, &details)
+        .await
+        .map_err(LegionError::from)
+}
+
+#[tauri::command]
+pub async fn generate_report(
+    state: State<'_, AppState>,
+    project_id: String,
+) -> Result<String, LegionError> {
+    ensure_project_open(&state, &project_id)?;
+
+    ReportGenerator::generate_markdown(&state.database, &project_id)
+        .await
+        .map_err(LegionError::from)
+}
+
+/// Schedules a re-scan of `targets` every `interval_days` (weekly by
+/// default), each run reporting what changed since the previous one. The
+/// first run starts right away.
+#[tauri::command]
+pub async fn create_report_schedule(
+    state: State<'_, AppState>,
+    name: String,
+    targets: Vec<String>,
+    scan_type: Option<String>,
+    interval_days: Option<i32>,
+    output_dir: Option<String>,
+) -> Result<ReportSchedule, LegionError> {
+    let name = name.trim();
+    if name.is_empty() {
+        return Err(LegionError::Validation("Schedule name is required".to_string()));
+    }
+    if targets.is_empty() {
+        return Err(LegionError::Validation("At least one target is required".to_string()));
+    }
+    for target in &targets {
+        InputValidator::validate_ip(target)
+            .map(|_| ())
+            .or_else(|_| InputValidator::validate_cidr(target))
+            .map_err(LegionError::from)?;
+    }
+
+    let scan_type = scan_type.unwrap_or_else(|| "quick".to_string());
+    InputValidator::validate_scan_type(&scan_type).map_err(LegionError::from)?;
+
+    let interval_days = interval_days.unwrap_or(7);
+    if interval_days < 1 {
+        return Err(LegionError::Validation("Interval must be at least one day".to_string()));
+    }
+
+    ReportScheduleOperations::create(
+        &state.database.pool(),
+        name,
+        &targets,
+        &scan_type,
+        interval_days,
+        output_dir.as_deref().map(str::trim).filter(|dir| !dir.is_empty()),
+        chrono::Utc::now(),
+    )
+    .await
+    .map_err(LegionError::from)
+}
+
+#[tauri::command]
+pub async fn list_report_schedules(
+    state: State<'_, AppState>,
+) -> Result<Vec<ReportSchedule>, LegionError> {
+    ReportScheduleOperations::list(&state.database.pool())
+        .await
+        .map_err(LegionError::from)
+}
+
+#[tauri::command]
+pub async fn set_report_schedule_enabled(
+    state: State<'_, AppState>,
+    schedule_id: String,
+    enabled: bool,
+) -> Result<(), LegionError> {
+    let updated = ReportScheduleOperations::set_enabled(&state.database.pool(), &schedule_id, enabled)
+        .await
+        .map_err(LegionError::from)?;
+    if !updated {
+        return Err(LegionError::NotFound(format!("Report schedule not found: {}", schedule_id)));
+    }
+
+    Ok(())
+}
+
+#[tauri::command]
+pub async fn delete_report_schedule(
+    state: State<'_, AppState>,
+    schedule_id: String,
+) -> Result<(), LegionError> {
+    let deleted = ReportScheduleOperations::delete(&state.database.pool(), &schedule_id)
+        .await
+        .map_err(LegionError::from)?;
+    if !deleted {
+        return Err(LegionError::NotFound(format!("Report schedule not found: {}", schedule_id)));
+    }
+
+    Ok(())
+}
+
+/// Runs a schedule now, outside its interval, and returns its report once
+/// the scans have finished. The next scheduled run is unaffected.
+#[tauri::command]
+pub async fn run_report_schedule(
+    state: State<'_, AppState>,
+    schedule_id: String,
+) -> Result<DifferentialReport, LegionError> {
+    let schedule = ReportScheduleOperations::find_by_id(&state.database.pool(), &schedule_id)
+        .await
+        .map_err(LegionError::from)?
+        .ok_or_else(|| LegionError::NotFound(format!("Report schedule not found: {}", schedule_id)))?;
+
+    ReportScheduler::run(&state.database, &state.scan_coordinator, &schedule)
+        .await
+        .map_err(LegionError::from)
+}
+
+/// A schedule's reports, newest first.
+#[tauri::command]
+pub async fn get_differential_reports(
+    state: State<'_, AppState>,
+    schedule_id: String,
+) -> Result<Vec<DifferentialReport>, LegionError> {
+    DifferentialReportOperations::find_by_schedule(&state.database.pool(), &schedule_id)
+        .await
+        .map_err(LegionError::from)
+}
+
+#[tauri::command]
+pub async fn list_project_scope(
+    state: State<'_, AppState>,
+    project_id: String,
+) -> Result<Vec<ScopeEntry>, LegionError> {
+    ScopeOperations::list(state.database.main_pool(), &project_id)
+        .await
+        .map_err(LegionError::from)
+}
+
+#[tauri::command]
+pub async fn add_project_scope_entry(
+    state: State<'_, AppState>,
+    project_id: String,
+    entry: String,
+) -> Result<ScopeEntry, LegionError> {
+    let (entry, kind) = ScopeGuard::parse_entry(&entry).map_err(LegionError::from)?;
+
+    ScopeOperations::add(state.database.main_pool(), &project_id, &entry, kind)
+        .await
+        .map_err(LegionError::from)
+}
+
+#[tauri::command]
+pub async fn remove_project_scope_entry(
+    state: State<'_, AppState>,
+    scope_id: String,
+) -> Result<(), LegionError> {
+    ScopeOperations::remove(state.database.main_pool(), &scope_id)
+        .await
+        .map_err(LegionError::from)
+}
+
+#[tauri::command]
+pub async fn create_user(
+    state: State<'_, AppState>,
+    username: String,
+    role: String,
+) -> Result<User, LegionError> {
+    InputValidator::validate_username(&username).map_err(LegionError::from)?;
+    let role = Role::parse(&role).map_err(LegionError::from)?;
+
+    let user = UserOperations::create(state.database.main_pool(), &username, role.as_str())
+        .await
+        .map_err(LegionError::from)?;
+
+    AuditLogOperations::record(state.database.main_pool(), None, "user_created", Some(&user.username), Some(role.as_str()))
+        .await
+        .map_err(LegionError::from)?;
+
+    Ok(user)
+}
+
+#[tauri::command]
+pub async fn list_users(
+    state: State<'_, AppState>,
+) -> Result<Vec<User>, LegionError> {
+    UserOperations::list(state.database.main_pool())
+        .await
+        .map_err(LegionError::from)
+}
+
+#[tauri::command]
+pub async fn set_user_role(
+    state: State<'_, AppState>,
+    user_id: String,
+    role: String,
+) -> Result<User, LegionError> {
+    let role = Role::parse(&role).map_err(LegionError::from)?;
+    if role != Role::Admin {
+        ensure_other_admin(&state, &user_id).await?;
+    }
+
+    let user = UserOperations::set_role(state.database.main_pool(), &user_id, role.as_str())
+        .await
+        .map_err(LegionError::from)?;
+
+    AuditLogOperations::record(state.database.main_pool(), None, "user_role_changed", Some(&user.username), Some(role.as_str()))
+        .await
+        .map_err(LegionError::from)?;
+
+    Ok(user)
+}
+
+#[tauri::command]
+pub async fn delete_user(
+    state: State<'_, AppState>,
+    user_id: String,
+) -> Result<(), LegionError> {
+    ensure_other_admin(&state, &user_id).await?;
+
+    let user = UserOperations::find_by_id(state.database.main_pool(), &user_id)
+        .await
+        .map_err(LegionError::from)?
+        .ok_or_else(|| LegionError::NotFound(format!("User not found: {}", user_id)))?;
+
+    UserOperations::delete(state.database.main_pool(), &user_id)
+        .await
+        .map_err(LegionError::from)?;
+
+    AuditLogOperations::record(state.database.main_pool(), None, "user_deleted", Some(&user.username), None)
+        .await
+        .map_err(LegionError::from)
+}
+
+/// Issues a token for the API server. The token is only returned here;
+/// the database keeps its hash.
+#[tauri::command]
+pub async fn create_api_token(
+    state: State<'_, AppState>,
+    user_id: String,
+    name: String,
+    scope: String,
+    expires_in_days: Option<i64>,
+) -> Result<IssuedApiToken, LegionError> {
+    Authorizer::issue_token(state.database.main_pool(), None, &user_id, &name, &scope, expires_in_days)
+        .await
+        .map_err(LegionError::from)
+}
+
+#[tauri::command]
+pub async fn list_api_tokens(
+    state: State<'_, AppState>,
+    user_id: Option<String>,
+) -> Result<Vec<ApiToken>, LegionError> {
+    ApiTokenOperations::list(state.database.main_pool(), user_id.as_deref())
+        .await
+        .map_err(LegionError::from)
+}
+
+#[tauri::command]
+pub async fn revoke_api_token(
+    state: State<'_, AppState>,
+    token_id: String,
+) -> Result<ApiToken, LegionError> {
+    let api_token = ApiTokenOperations::revoke(state.database.main_pool(), &token_id)
+        .await
+        .map_err(LegionError::from)?
+        .ok_or_else(|| LegionError::NotFound(format!("API token not found: {}", token_id)))?;
+
+    AuditLogOperations::record(state.database.main_pool(), None, "api_token_revoked", Some(&api_token.user_id), Some(&api_token.name))
+        .await
+        .map_err(LegionError::from)?;
+
+    Ok(api_token)
+}
+
+/// Refuses to demote or remove the last admin, which would leave nobody
+/// able to manage users.
+async fn ensure_other_admin(state: &State<'_, AppState>, user_id: &str) -> Result<(), LegionError> {
+    let user = UserOperations::find_by_id(state.database.main_pool(), user_id)
+        .await
+        .map_err(LegionError::from)?;
+    let Some(user) = user.filter(|u| u.role == Role::Admin.as_str()) else {
+        return Ok(());
+    };
+
+    let admins = UserOperations::count_admins(state.database.main_pool())
+        .await
+        .map_err(LegionError::from)?;
+    if admins <= 1 {
+        return Err(LegionError::Validation(format!("{} is the last admin", user.username)));
+    }
+
+    Ok(())
+}
+
+#[tauri::command]
+pub async fn get_audit_log(
+    state: State<'_, AppState>,
+    project_id: Option<String>,
+    limit: Option<i32>,
+) -> Result<Vec<AuditLogEntry>, LegionError> {
+    AuditLogOperations::list(state.database.main_pool(), project_id.as_deref(), limit.unwrap_or(200))
+        .await
+        .map_err(LegionError::from)
+}
+
+#[tauri::command]
+pub async fn open_project(
+    state: State<'_, AppState>,
+    project_id: String,
+) -> Result<ProjectSettings, LegionError> {
+    uuid::Uuid::parse_str(&project_id)
+        .map_err(|e| LegionError::Validation(format!("Invalid project ID: {}", e)))?;
+
+    ProjectOperations::find_by_id(state.database.main_pool(), &project_id)
+        .await
+        .map_err(LegionError::from)?
+        .ok_or_else(|| LegionError::NotFound(format!("Project not found: {}", project_id)))?;
+
+    ensure_no_active_scans(&state).await?;
+
+    let settings = ProjectSettingsOperations::load(state.database.main_pool(), &project_id)
+        .await
+        .map_err(LegionError::from)?;
+
+    let config = state.config.get();
+    if config.database.per_project_files {
+        state.database
+            .open_project_file(&project_id, &config.project_database_path(&project_id))
+            .await
+            .map_err(LegionError::from)?;
+    }
+
+    state.scan_coordinator.activate_project(&project_id, settings.clone());
+    Ok(settings)
+}
+
+#[tauri::command]
+pub async fn close_project(
+    state: State<'_, AppState>,
+) -> Result<(), LegionError> {
+    ensure_no_active_scans(&state).await?;
+
+    state.database.close_project_file().await;
+    state.scan_coordinator.deactivate_project();
+    Ok(())
+}
+
+/// Assessment data is only readable for the open project, so commands
+/// naming a project refuse any other one.
+fn ensure_project_open(state: &State<'_, AppState>, project_id: &str) -> Result<(), LegionError> {
+    if state.scan_coordinator.active_project().map(|p| p.id).as_deref() != Some(project_id) {
+        return Err(LegionError::Validation(format!("Project {} is not open", project_id)));
+    }
+    Ok(())
+}
+
+/// Switching the active pool mid-scan would split a scan's results across
+/// two databases.
+async fn ensure_no_active_scans(state: &State<'_, AppState>) -> Result<(), LegionError> {
+    if state.scan_coordinator.get_scan_statistics().await.total_active > 0 {
+        return Err(LegionError::Validation("Cannot switch projects while scans are running".to_string()));
+    }
+    Ok(())
+}
+
+#[tauri::command]
+pub async fn get_active_project(
+    state: State<'_, AppState>,
+) -> Result<Option<ActiveProject>, LegionError> {
+    Ok(state.scan_coordinator.active_project())
+}
+
+#[tauri::command]
+pub async fn test_ssh_target(target: SshTarget) -> Result<(), LegionError> {
+    ProcessManager::check_ssh(&target)
+        .await
+        .map_err(LegionError::from)
+}
+
+#[tauri::command]
+pub async fn test_proxy(proxy: ProxySettings) -> Result<(), LegionError> {
+    ProxyRouting::check(&proxy)
+        .await
+        .map_err(LegionError::from)
+}
+
+#[tauri::command]
+pub async fn get_project_settings(
+    state: State<'_, AppState>,
+    project_id: String,
+) -> Result<ProjectSettings, LegionError> {
+    ProjectSettingsOperations::load(state.database.main_pool(), &project_id)
+        .await
+        .map_err(LegionError::from)
+}
+
+#[tauri::command]
+pub async fn update_project_settings(
+    state: State<'_, AppState>,
+    project_id: String,
+    settings: ProjectSettings,
+) -> Result<(), LegionError> {
+    if let Some(profile) = &settings.default_scan_profile {
+        InputValidator::validate_scan_type(profile)
+            .map_err(LegionError::from)?;
+    }
+
+    for exclude in &settings.exclude_list {
+        InputValidator::validate_cidr(exclude)
+            .map_err(LegionError::from)?;
+    }
+
+    if let Some(proxy) = &settings.proxy {
+        ProxyRouting::validate(proxy)
+            .map_err(LegionError::from)?;
+    }
+
+    ProjectSettingsOperations::save(state.database.main_pool(), &project_id, &settings)
+        .await
+        .map_err(LegionError::from)?;
+
+    // Keep the live defaults in sync when editing the active project
+    if state.scan_coordinator.active_project().map(|p| p.id) == Some(project_id.clone()) {
+        state.scan_coordinator.activate_project(&project_id, settings);
+    }
+
+    Ok(())
+}
+
+#[tauri::command]
+pub async fn list_projects(
+    state: State<'_, AppState>,
+) -> Result<Vec<Project>, LegionError> {
+    ProjectOperations::list_all(state.database.main_pool())
+        .await
+        .map_err(LegionError::from)
+}
+
+// Configuration commands
+#[tauri::command]
+pub async fn get_config(
+    state: State<'_, AppState>,
+) -> Result<AppConfig, LegionError> {
+    Ok(state.config.get())
+}
+
+#[tauri::command]
+pub async fn set_config(
+    state: State<'_, AppState>,
+    config: AppConfig,
+) -> Result<ConfigUpdate, LegionError> {
+    let update = state.config
+        .set(config)
+        .map_err(LegionError::from)?;
+
+    state.scan_coordinator.apply_config(&update.config);
+    Ok(update)
+}
+
+// Passive monitoring commands
+#[tauri::command]
+pub async fn start_sniffer(
+    state: State<'_, AppState>,
+    interface: String,
+) -> Result<(), LegionError> {
+    let gateways = TopologyOperations::gateways(&state.database.pool())
+        .await
+        .map_err(LegionError::from)?;
+    let request = SnifferRequest {
+        interface,
+        helper: state.helper.endpoint().await,
+        gateways: gateways.into_iter()
+            .filter_map(|ip| match ip {
+                std::net::IpAddr::V4(v4) => Some(v4),
+                std::net::IpAddr::V6(_) => None,
+            })
+            .collect(),
+    };
+    let capture = state.net_sniffer.clone()
+        .start(uuid::Uuid::new_v4(), request)
+        .await
+        .map_err(LegionError::from)?;
+
+    state.scan_coordinator.spawn_ingest(capture);
+    Ok(())
+}
+
+#[tauri::command]
+pub async fn stop_sniffer(
+    state: State<'_, AppState>,
+) -> Result<(), LegionError> {
+    state.net_sniffer.stop();
+    Ok(())
+}
+
+/// Names the sniffer saw resolve to an address, including for hosts that
+/// were never scanned.
+#[tauri::command]
+pub async fn get_passive_dns(
+    state: State<'_, AppState>,
+    ip: String,
+) -> Result<Vec<PassiveDnsRecord>, LegionError> {
+    let ip = InputValidator::validate_ip(&ip).map_err(LegionError::from)?;
+
+    PassiveDnsOperations::find_names_for(&state.database.pool(), ip, &DnsParser::reverse_name(ip))
+        .await
+        .map_err(LegionError::from)
+}
+
+/// Traffic the sniffer counted to and from a host, busiest flows first.
+#[tauri::command]
+pub async fn get_host_flows(
+    state: State<'_, AppState>,
+    host_id: String,
+) -> Result<Vec<Flow>, LegionError> {
+    let pool = state.database.pool();
+    let host = HostOperations::find_by_id(&pool, &host_id)
+        .await
+        .map_err(LegionError::from)?
+        .ok_or_else(|| LegionError::NotFound(format!("Host not found: {}", host_id)))?;
+
+    FlowOperations::find_by_ip(&pool, &host.ip)
+        .await
+        .map_err(LegionError::from)
+}
+
+/// Captures a host's traffic for `duration` seconds into a pcap file
+/// stored with the project. Without an interface, the one on the host's
+/// subnet is used.
+#[tauri::command]
+pub async fn start_capture(
+    state: State<'_, AppState>,
+    host_id: String,
+    duration: u64,
+    bpf_filter: Option<String>,
+    interface: Option<String>,
+) -> Result<PacketCapture, LegionError> {
+    let pool = state.database.pool();
+    let host = HostOperations::find_by_id(&pool, &host_id)
+        .await
+        .map_err(LegionError::from)?
+        .ok_or_else(|| LegionError::NotFound(format!("Host not found: {}", host_id)))?;
+    let ip = InputValidator::validate_ip(&host.ip).map_err(LegionError::from)?;
+
+    let interface = match interface {
+        Some(interface) => interface,
+        None => NetworkUtils::interface_for(ip)
+            .map_err(LegionError::from)?
+            .map(|interface| interface.name)
+            .ok_or_else(|| LegionError::Validation("No network interface to capture on".to_string()))?,
+    };
+
+    let request = HostCaptureRequest {
+        host_id,
+        ip,
+        interface,
+        duration: std::time::Duration::from_secs(duration),
+        bpf_filter,
+    };
+    state.host_captures
+        .start(pool, request)
+        .await
+        .map_err(LegionError::from)
+}
+
+#[tauri::command]
+pub async fn stop_capture(
+    state: State<'_, AppState>,
+    capture_id: String,
+) -> Result<(), LegionError> {
+    if !state.host_captures.stop(&capture_id) {
+        return Err(LegionError::NotFound(format!("No running capture: {}", capture_id)));
+    }
+    Ok(())
+}
+
+#[tauri::command]
+pub async fn get_host_captures(
+    state: State<'_, AppState>,
+    host_id: String,
+) -> Result<Vec<PacketCapture>, LegionError> {
+    PacketCaptureOperations::find_by_host(&state.database.pool(), &host_id)
+        .await
+        .map_err(LegionError::from)
+}
+
+/// Writes a finished capture to `path` as a pcap file.
+#[tauri::command]
+pub async fn export_capture(
+    state: State<'_, AppState>,
+    capture_id: String,
+    path: String,
+) -> Result<(), LegionError> {
+    let capture = PacketCaptureOperations::find_by_id(&state.database.pool(), &capture_id)
+        .await
+        .map_err(LegionError::from)?
+        .ok_or_else(|| LegionError::NotFound(format!("Capture not found: {}", capture_id)))?;
+    let pcap = PacketCaptureOperations::decompress(&capture)
+        .map_err(LegionError::from)?
+        .ok_or_else(|| LegionError::Validation(format!("Capture {} has no packets stored yet", capture_id)))?;
+
+    tokio::fs::write(&path, pcap)
+        .await
+        .map_err(|e| LegionError::Validation(format!("Failed to write {}: {}", path, e)))
+}
+
+/// Starts the privileged helper and routes SYN scans and capture through
+/// it. Depending on `helper.elevate` this shows an elevation prompt.
+#[tauri::command]
+pub async fn start_privileged_helper(
+    state: State<'_, AppState>,
+) -> Result<(), LegionError> {
+    let endpoint = state.helper
+        .start(&state.config.get().helper)
+        .await
+        .map_err(LegionError::from)?;
+    state.scan_coordinator.set_helper(Some(endpoint));
+
+    AuditLogOperations::record(state.database.main_pool(), None, "privileged_helper_started", None, None)
+        .await
+        .map_err(LegionError::from)?;
+
+    Ok(())
+}
+
+#[tauri::command]
+pub async fn stop_privileged_helper(
+    state: State<'_, AppState>,
+) -> Result<(), LegionError> {
+    state.scan_coordinator.set_helper(None);
+    state.helper.stop().await;
+    Ok(())
+}
+
+#[tauri::command]
+pub async fn start_ble_survey(
+    state: State<'_, AppState>,
+    adapter: Option<String>,
+    window: tauri::Window,
+) -> Result<(), LegionError> {
+    let (notify_tx, mut notify_rx) = mpsc::channel(100);
+
+    state.ble_survey
+        .start(adapter.as_deref(), state.database.clone(), notify_tx)
+        .await
+        .map_err(LegionError::from)?;
+
+    tokio::spawn(async move {
+        while let Some(device) = notify_rx.recv().await {
+            let _ = window.emit("wireless-device", &device);
+        }
+    });
+
+    Ok(())
+}
+
+#[tauri::command]
+pub async fn stop_ble_survey(
+    state: State<'_, AppState>,
+) -> Result<(), LegionError> {
+    state.ble_survey.stop();
+    Ok(())
+}
+
+#[tauri::command]
+pub async fn import_wireless_survey(
+    state: State<'_, AppState>,
+    path: String,
+) -> Result<WirelessImportSummary, LegionError> {
+    let contents = tokio::fs::read_to_string(&path)
+        .await
+        .map_err(|e| LegionError::Validation(format!("Failed to read {}: {}", path, e)))?;
+
+    state.scan_coordinator
+        .import_wireless_survey(contents)
+        .await
+        .map_err(LegionError::from)
+}
+
+/// Analyzes a pcap or pcapng file taken elsewhere, storing the hosts,
+/// ports, DNS answers and TLS sessions in it as the live sniffer would.
+#[tauri::command]
+pub async fn import_pcap(
+    state: State<'_, AppState>,
+    path: String,
+) -> Result<PcapImportSummary, LegionError> {
+    let path = std::path::PathBuf::from(&path);
+    if !path.is_file() {
+        return Err(LegionError::Validation(format!("No capture file at {}", path.display())));
+    }
+
+    state.scan_coordinator
+        .import_pcap(path)
+        .await
+        .map_err(LegionError::from)
+}
+
+/// TLS sessions the sniffer saw to a host, by port and server name.
+#[tauri::command]
+pub async fn get_tls_sessions(
+    state: State<'_, AppState>,
+    host_id: String,
+) -> Result<Vec<TlsSession>, LegionError> {
+    let pool = state.database.pool();
+    let host = HostOperations::find_by_id(&pool, &host_id)
+        .await
+        .map_err(LegionError::from)?
+        .ok_or_else(|| LegionError::NotFound(format!("Host not found: {}", host_id)))?;
+
+    TlsSessionOperations::find_by_server(&pool, &host.ip)
+        .await
+        .map_err(LegionError::from)
+}
+
+/// JA3/JA3S hashes seen from a host, suspicious ones first.
+#[tauri::command]
+pub async fn get_tls_fingerprints(
+    state: State<'_, AppState>,
+    host_id: String,
+) -> Result<Vec<TlsFingerprint>, LegionError> {
+    TlsFingerprintOperations::find_by_host(&state.database.pool(), &host_id)
+        .await
+        .map_err(LegionError::from)
+}
+
+/// Every host seen with a JA3/JA3S hash, to find other installs of the
+/// same software.
+#[tauri::command]
+pub async fn get_hosts_by_tls_fingerprint(
+    state: State<'_, AppState>,
+    hash: String,
+) -> Result<Vec<TlsFingerprint>, LegionError> {
+    TlsFingerprintOperations::find_by_hash(&state.database.pool(), hash.trim())
+        .await
+        .map_err(LegionError::from)
+}
+
+/// User agents, virtual hosts and server software cleartext HTTP showed
+/// for a host.
+#[tauri::command]
+pub async fn get_http_metadata(
+    state: State<'_, AppState>,
+    host_id: String,
+) -> Result<Vec<HttpMetadata>, LegionError> {
+    HttpMetadataOperations::find_by_host(&state.database.pool(), &host_id)
+        .await
+        .map_err(LegionError::from)
+}
+
+/// Software the sniffer saw in use over HTTP across the project, whether
+/// or not a scan found it.
+#[tauri::command]
+pub async fn get_http_software_inventory(
+    state: State<'_, AppState>,
+) -> Result<Vec<HttpSoftware>, LegionError> {
+    HttpMetadataOperations::inventory(&state.database.pool())
+        .await
+        .map_err(LegionError::from)
+}
+
+/// Hosts showing one header value, e.g. a vulnerable server version.
+#[tauri::command]
+pub async fn get_http_software_hosts(
+    state: State<'_, AppState>,
+    kind: String,
+    value: String,
+) -> Result<Vec<HttpMetadata>, LegionError> {
+    HttpMetadataOperations::find_by_value(&state.database.pool(), &kind, &value)
+        .await
+        .map_err(LegionError::from)
+}
+
+/// Ports no scan or sniffed traffic has seen in the last `days` days, so
+/// stale records can be told apart from current exposure. Narrowed to one
+/// host when `host_id` is given.
+#[tauri::command]
+pub async fn get_stale_ports(
+    state: State<'_, AppState>,
+    days: u32,
+    host_id: Option<String>,
+) -> Result<Vec<crate::database::models::Port>, LegionError> {
+    if days == 0 {
+        return Err(LegionError::Validation("Days must be at least 1".to_string()));
+    }
+
+    let cutoff = chrono::Utc::now() - chrono::Duration::days(days as i64);
+    PortOperations::find_stale(&state.database.pool(), cutoff, host_id.as_deref())
+        .await
+        .map_err(LegionError::from)
+}
+
+/// Services the host was seen using on other hosts, and the hosts seen
+/// using its own.
+#[tauri::command]
+pub async fn get_dependencies(
+    state: State<'_, AppState>,
+    host_id: String,
+) -> Result<HostDependencies, LegionError> {
+    let pool = state.database.pool();
+    HostOperations::find_by_id(&pool, &host_id)
+        .await
+        .map_err(LegionError::from)?
+        .ok_or_else(|| LegionError::NotFound(format!("Host not found: {}", host_id)))?;
+
+    ServiceDependencyOperations::find_by_host(&pool, &host_id)
+        .await
+        .map_err(LegionError::from)
+}
+
+/// Maps the stored flows again, for hosts and services found after their
+/// traffic was sniffed. Returns the dependencies recorded.
+#[tauri::command]
+pub async fn rebuild_dependencies(
+    state: State<'_, AppState>,
+) -> Result<usize, LegionError> {
+    DependencyMapper::rebuild(&state.database.pool())
+        .await
+        .map_err(LegionError::from)
+}
+
+#[tauri::command]
+pub async fn get_wireless_devices(
+    state: State<'_, AppState>,
+    kind: Option<String>,
+) -> Result<Vec<WirelessDevice>, LegionError> {
+    WirelessDeviceOperations::list(&state.database.pool(), kind.as_deref())
+        .await
+        .map_err(LegionError::from)
+}
+
+#[tauri::command]
+pub async fn ingest_cloud_assets(
+    state: State<'_, AppState>,
+    project_id: String,
+    credentials: CloudCredentials,
+) -> Result<CloudIngestSummary, LegionError> {
+    ensure_project_open(&state, &project_id)?;
+    CloudInventory::new()
+        .ingest(&state.database, &project_id, &credentials)
+        .await
+        .map_err(LegionError::from)
+}
+
+#[tauri::command]
+pub async fn get_cloud_assets(
+    state: State<'_, AppState>,
+    provider: Option<String>,
+) -> Result<Vec<CloudAsset>, LegionError> {
+    CloudAssetOperations::list(&state.database.pool(), provider.as_deref())
+        .await
+        .map_err(LegionError::from)
+}
+
+#[tauri::command]
+pub async fn discover_ct_subdomains(
+    state: State<'_, AppState>,
+    project_id: String,
+) -> Result<CtDiscoverySummary, LegionError> {
+    ensure_project_open(&state, &project_id)?;
+    CtDiscovery::new()
+        .discover(&state.database, &project_id)
+        .await
+        .map_err(LegionError::from)
+}
+
+#[tauri::command]
+pub async fn start_host_monitor(
+    state: State<'_, AppState>,
+    interval_secs: u64,
+) -> Result<(), LegionError> {
+    state.host_monitor
+        .start(state.database.clone(), state.scan_coordinator.clone(), std::time::Duration::from_secs(interval_secs))
+        .map_err(LegionError::from)
+}
+
+#[tauri::command]
+pub async fn stop_host_monitor(
+    state: State<'_, AppState>,
+) -> Result<(), LegionError> {
+    state.host_monitor.stop();
+    Ok(())
+}
+
+#[tauri::command]
+pub async fn get_host_availability(
+    state: State<'_, AppState>,
+    host_id: String,
+    since: Option<chrono::DateTime<chrono::Utc>>,
+    until: Option<chrono::DateTime<chrono::Utc>>,
+) -> Result<HostAvailability, LegionError> {
+    HostStatusOperations::availability(&state.database.pool(), &host_id, since, until)
+        .await
+        .map_err(LegionError::from)
+}
+
+// Request/Response types
+#[derive(Serialize, Deserialize, TS)]
+#[ts(export, export_to = "../src/bindings/")]
+pub struct NetworkRangeRequest {
+    pub cidr: String,
+    pub exclude: Vec<String>,
+    pub scan_type: String,
+    #[serde(default)]
+    pub options: ScanOptions,
+}
+
+#[derive(Serialize, Deserialize, TS)]
+#[ts(export, export_to = "../src/bindings/")]
+pub struct ActiveScanInfo {
+    pub id: String,
+    pub status: ScanStatus,
+}
+
+#[derive(Serialize, Deserialize, TS)]
+#[ts(export, export_to = "../src/bindings/")]
+pub struct ScanProgressEvent {
+    pub target: String,
+    pub progress: ScanProgress,
+}
+
+#[derive(Serialize, Deserialize, TS)]
+#[ts(export, export_to = "../src/bindings/")]
+pub struct HostDetails {
+    pub host: Host,
+    pub ports: Vec<crate::database::models::Port>,
+    pub vulnerabilities: Vec<crate::database::models::Vulnerability>,
+    pub shares: Vec<Share>,
+    pub ip_history: Vec<HostIpHistory>,
+}
+
+#[derive(Serialize, Deserialize, TS)]
+#[ts(export, export_to = "../src/bindings/")]
+pub struct ScanHostDetails {
+    pub host: Host,
+    pub ports: Vec<crate::database::models::Port>,
+}
+
+/// A scan record with everything the scan detail view shows.
+#[derive(Serialize, Deserialize, TS)]
+#[ts(export, export_to = "../src/bindings/")]
+pub struct ScanDetails {
+    pub scan: Scan,
+    pub targets: Vec<std::net::IpAddr>,
+    /// Stored hosts for the scan's targets, with every port known for them
+    pub hosts: Vec<ScanHostDetails>,
+    #[ts(type = "number | null")]
+    pub duration_secs: Option<i64>,
+    pub has_raw_output: bool,
+    pub raw_outputs: Vec<RawOutput>,
+    /// Coordinator status while the scan is still queued or running
+    pub live_status: Option<ScanStatus>,
 }
\ No newline at end of file
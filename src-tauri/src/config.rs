@@ -0,0 +1,437 @@
+use crate::error::LegionError;
+use crate::scanning::{SeverityConfig, DEFAULT_BANDWIDTH_BUDGET_PPS};
+use crate::utils::{ChannelSettings, SandboxPolicy, SystemResources};
+use anyhow::{Result, Context};
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::path::{Path, PathBuf};
+use std::sync::RwLock;
+use std::time::SystemTime;
+
+/// The app's settings file, in the user's config directory. Falls back
+/// to the working directory where the platform has none.
+pub fn default_config_path() -> PathBuf {
+    tauri::api::path::config_dir()
+        .map(|dir| dir.join("legion2"))
+        .unwrap_or_default()
+        .join("legion2-config.toml")
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct AppConfig {
+    pub database: DatabaseConfig,
+    pub scanners: ScannerConfig,
+    pub rates: RateConfig,
+    pub concurrency: ConcurrencyConfig,
+    pub helper: HelperConfig,
+    pub server: ServerConfig,
+    pub enrichment: EnrichmentConfig,
+    pub channels: ChannelSettings,
+    /// How source severities map onto the app's scale
+    pub severity: SeverityConfig,
+    pub api_keys: HashMap<String, String>,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct DatabaseConfig {
+    pub path: String,
+    /// Give each project its own SQLite file under `<data dir>/projects`
+    pub per_project_files: bool,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct ScannerConfig {
+    pub nmap_path: String,
+    pub masscan_path: String,
+    /// Run by follow-up rules with the `nuclei` action
+    pub nuclei_path: String,
+    /// Applied to locally run nmap and masscan
+    pub sandbox: SandboxPolicy,
+}
+
+/// Databases the ingest pipeline enriches hosts from
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct EnrichmentConfig {
+    /// MaxMind DB such as GeoLite2-City.mmdb; without one GeoIP is skipped
+    pub geoip_database: Option<String>,
+    /// MAC prefix table in nmap-mac-prefixes format; nmap's own by default
+    pub oui_database: Option<String>,
+    /// Known JA3/JA3S hashes, such as abuse.ch's SSLBL JA3 CSV
+    pub ja3_database: Option<String>,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct RateConfig {
+    /// Global packets-per-second cap shared by all running scans
+    pub bandwidth_budget_pps: u32,
+    /// Scans that may start against one /24 (or /64) at once, refilled at
+    /// `network_refill_per_sec`; shared by all scan types
+    pub network_capacity: f64,
+    pub network_refill_per_sec: f64,
+    pub stealth_capacity: f64,
+    pub stealth_refill_per_sec: f64,
+    /// Share of probes reported lost (0.0 to 1.0) that counts as congestion
+    pub congestion_loss_threshold: f64,
+    /// Consecutive congested reports before a scan is flagged
+    pub congestion_samples: u32,
+    /// Stop a congested scanner until it is resumed (not on Windows)
+    pub congestion_auto_pause: bool,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct HelperConfig {
+    /// Run SYN scans and capture through the privileged helper instead of
+    /// requiring the whole app to run as root
+    pub enabled: bool,
+    /// Defaults to `legion2-helper` next to the app binary
+    pub path: Option<String>,
+    /// Command the helper is started under, e.g. `["pkexec"]`. Empty when
+    /// the helper is installed setuid or with capabilities.
+    pub elevate: Vec<String>,
+}
+
+/// Token-authenticated HTTP API for a shared team instance
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct ServerConfig {
+    pub enabled: bool,
+    /// Anything but a loopback address exposes the API to the network
+    pub bind: String,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct ConcurrencyConfig {
+    /// Left unset, the limits below are sized from the host's CPUs, memory
+    /// and open-file limit at startup
+    pub max_concurrent_scans: Option<usize>,
+    pub nmap_concurrency: Option<usize>,
+    pub masscan_concurrency: Option<usize>,
+    /// Capacity of the scan result and event channels
+    pub channel_capacity: Option<usize>,
+    pub process_timeout_secs: u64,
+}
+
+/// Concurrency limits in effect: config overrides, otherwise tuned values.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ConcurrencyLimits {
+    pub max_concurrent_scans: usize,
+    pub nmap_concurrency: usize,
+    pub masscan_concurrency: usize,
+    pub channel_capacity: usize,
+}
+
+/// Rough per-process costs used for tuning
+const NMAP_MEMORY_MB: u64 = 100;
+const MASSCAN_MEMORY_MB: u64 = 256;
+const NMAP_FDS: u64 = 128;
+const SCAN_FDS: u64 = 64;
+
+impl ConcurrencyConfig {
+    /// A zero limit would leave every scan waiting on an empty semaphore,
+    /// and a zero-capacity channel can't be created at all.
+    pub fn validate(&self) -> Result<()> {
+        let limits = [
+            ("max_concurrent_scans", self.max_concurrent_scans),
+            ("nmap_concurrency", self.nmap_concurrency),
+            ("masscan_concurrency", self.masscan_concurrency),
+            ("channel_capacity", self.channel_capacity),
+        ];
+        for (name, value) in limits {
+            if value == Some(0) {
+                return Err(LegionError::Validation(format!("concurrency.{} must be greater than 0", name)).into());
+            }
+        }
+        Ok(())
+    }
+
+    pub fn resolve(&self, resources: &SystemResources) -> ConcurrencyLimits {
+        let cpus = resources.cpu_count as u64;
+        let by_memory = |per_process: u64| resources.available_memory_mb.map_or(u64::MAX, |mb| mb / per_process);
+        let by_fds = |per_process: u64| resources.fd_limit.map_or(u64::MAX, |fds| fds / per_process);
+
+        let nmap = (cpus * 2).min(by_memory(NMAP_MEMORY_MB)).min(by_fds(NMAP_FDS)).clamp(1, 64) as usize;
+        let masscan = (cpus / 4).min(by_memory(MASSCAN_MEMORY_MB)).clamp(1, 8) as usize;
+        let scans = (nmap as u64 * 2).min(by_fds(SCAN_FDS)).clamp(2, 128) as usize;
+
+        let max_concurrent_scans = self.max_concurrent_scans.unwrap_or(scans);
+
+        ConcurrencyLimits {
+            max_concurrent_scans,
+            nmap_concurrency: self.nmap_concurrency.unwrap_or(nmap),
+            masscan_concurrency: self.masscan_concurrency.unwrap_or(masscan),
+            channel_capacity: self.channel_capacity
+                .unwrap_or_else(|| (max_concurrent_scans * 100).clamp(256, 10_000)),
+        }
+    }
+}
+
+impl Default for AppConfig {
+    fn default() -> Self {
+        Self {
+            database: DatabaseConfig::default(),
+            scanners: ScannerConfig::default(),
+            rates: RateConfig::default(),
+            concurrency: ConcurrencyConfig::default(),
+            helper: HelperConfig::default(),
+            server: ServerConfig::default(),
+            enrichment: EnrichmentConfig::default(),
+            channels: ChannelSettings::default(),
+            severity: SeverityConfig::default(),
+            api_keys: HashMap::new(),
+        }
+    }
+}
+
+impl Default for DatabaseConfig {
+    fn default() -> Self {
+        Self {
+            path: "data/legion2.db".to_string(),
+            per_project_files: false,
+        }
+    }
+}
+
+impl Default for ScannerConfig {
+    fn default() -> Self {
+        Self {
+            nmap_path: "nmap".to_string(),
+            masscan_path: "masscan".to_string(),
+            nuclei_path: "nuclei".to_string(),
+            sandbox: SandboxPolicy::default(),
+        }
+    }
+}
+
+impl Default for HelperConfig {
+    fn default() -> Self {
+        let elevate = match cfg!(target_os = "linux") {
+            true => vec!["pkexec".to_string()],
+            false => Vec::new(),
+        };
+        Self {
+            enabled: false,
+            path: None,
+            elevate,
+        }
+    }
+}
+
+impl Default for ServerConfig {
+    fn default() -> Self {
+        Self {
+            enabled: false,
+            bind: "127.0.0.1:8470".to_string(),
+        }
+    }
+}
+
+impl Default for RateConfig {
+    fn default() -> Self {
+        Self {
+            bandwidth_budget_pps: DEFAULT_BANDWIDTH_BUDGET_PPS,
+            network_capacity: 32.0,
+            network_refill_per_sec: 8.0,
+            stealth_capacity: 100.0,
+            stealth_refill_per_sec: 50.0,
+            congestion_loss_threshold: 0.25,
+            congestion_samples: 3,
+            congestion_auto_pause: false,
+        }
+    }
+}
+
+impl Default for ConcurrencyConfig {
+    fn default() -> Self {
+        Self {
+            max_concurrent_scans: None,
+            nmap_concurrency: None,
+            masscan_concurrency: None,
+            channel_capacity: None,
+            process_timeout_secs: 300,
+        }
+    }
+}
+
+impl AppConfig {
+    /// Directory holding the main database and the app's other state
+    fn data_dir(&self) -> &Path {
+        Path::new(&self.database.path)
+            .parent()
+            .unwrap_or_else(|| Path::new("data"))
+    }
+
+    pub fn project_database_path(&self, project_id: &str) -> PathBuf {
+        self.data_dir().join("projects").join(format!("{}.db", project_id))
+    }
+
+    /// Where masscan runs keep the state they need to be resumed
+    pub fn masscan_resume_dir(&self) -> PathBuf {
+        self.data_dir().join("masscan")
+    }
+
+    /// Grepable logs of chunked nmap runs, for `nmap --resume`
+    pub fn nmap_resume_dir(&self) -> PathBuf {
+        self.data_dir().join("nmap")
+    }
+
+    /// Progress of executing massmap plans
+    pub fn massmap_checkpoint_dir(&self) -> PathBuf {
+        self.data_dir().join("massmap")
+    }
+
+    /// Stages finished by comprehensive scans, so interrupted ones resume
+    pub fn scan_checkpoint_dir(&self) -> PathBuf {
+        self.data_dir().join("scans")
+    }
+
+    /// Per-scan directories custom scans write to through `{output_dir}`
+    pub fn scan_output_dir(&self) -> PathBuf {
+        self.data_dir().join("output")
+    }
+
+    pub fn concurrency_limits(&self) -> ConcurrencyLimits {
+        self.concurrency.resolve(&SystemResources::detect())
+    }
+
+    /// Lists the settings that differ from `other` and only take effect
+    /// after a restart.
+    pub fn restart_required_changes(&self, other: &AppConfig) -> Vec<String> {
+        let mut changes = Vec::new();
+
+        if self.database.path != other.database.path {
+            changes.push("database.path".to_string());
+        }
+        if self.concurrency != other.concurrency {
+            changes.push("concurrency".to_string());
+        }
+        if self.enrichment != other.enrichment {
+            changes.push("enrichment".to_string());
+        }
+        if self.server != other.server {
+            changes.push("server".to_string());
+        }
+        // The event channel is opened once at startup
+        if self.channels.event_policy != other.channels.event_policy {
+            changes.push("channels.event_policy".to_string());
+        }
+        if self.rates.stealth_capacity != other.rates.stealth_capacity
+            || self.rates.stealth_refill_per_sec != other.rates.stealth_refill_per_sec
+        {
+            changes.push("rates.stealth".to_string());
+        }
+        if self.rates.network_capacity != other.rates.network_capacity
+            || self.rates.network_refill_per_sec != other.rates.network_refill_per_sec
+        {
+            changes.push("rates.network".to_string());
+        }
+
+        changes
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ConfigUpdate {
+    pub config: AppConfig,
+    pub restart_required: Vec<String>,
+}
+
+pub struct ConfigManager {
+    path: PathBuf,
+    config: RwLock<AppConfig>,
+    modified: RwLock<Option<SystemTime>>,
+}
+
+impl ConfigManager {
+    /// Loads the config file, falling back to defaults when it does not
+    /// exist. Unknown tables are ignored.
+    pub fn load(path: impl AsRef<Path>) -> Result<Self> {
+        let path = path.as_ref().to_path_buf();
+        let config = if path.exists() {
+            Self::read_file(&path)?
+        } else {
+            AppConfig::default()
+        };
+
+        Ok(Self {
+            modified: RwLock::new(Self::modified_time(&path)),
+            path,
+            config: RwLock::new(config),
+        })
+    }
+
+    pub fn get(&self) -> AppConfig {
+        self.config.read().unwrap().clone()
+    }
+
+    /// Replaces the active config and persists it to disk. Written to a
+    /// temporary file first so a crash mid-write leaves the previous
+    /// config intact.
+    pub fn set(&self, config: AppConfig) -> Result<ConfigUpdate> {
+        config.concurrency.validate()?;
+        let previous = self.get();
+
+        let contents = toml::to_string_pretty(&config)
+            .context("Failed to serialize config")?;
+        if let Some(dir) = self.path.parent().filter(|dir| !dir.as_os_str().is_empty()) {
+            std::fs::create_dir_all(dir)
+                .with_context(|| format!("Failed to create {}", dir.display()))?;
+        }
+        let temp = self.path.with_extension("toml.tmp");
+        std::fs::write(&temp, contents)
+            .with_context(|| format!("Failed to write {}", temp.display()))?;
+        std::fs::rename(&temp, &self.path)
+            .with_context(|| format!("Failed to write {}", self.path.display()))?;
+
+        *self.modified.write().unwrap() = Self::modified_time(&self.path);
+        *self.config.write().unwrap() = config.clone();
+
+        Ok(ConfigUpdate {
+            restart_required: previous.restart_required_changes(&config),
+            config,
+        })
+    }
+
+    /// Re-reads the file if it was edited since the last load or save.
+    pub fn reload_if_changed(&self) -> Result<Option<ConfigUpdate>> {
+        let modified = Self::modified_time(&self.path);
+        if modified.is_none() || modified == *self.modified.read().unwrap() {
+            return Ok(None);
+        }
+
+        let config = Self::read_file(&self.path)?;
+        let previous = self.get();
+
+        *self.modified.write().unwrap() = modified;
+        *self.config.write().unwrap() = config.clone();
+
+        if config == previous {
+            return Ok(None);
+        }
+
+        Ok(Some(ConfigUpdate {
+            restart_required: previous.restart_required_changes(&config),
+            config,
+        }))
+    }
+
+    fn read_file(path: &Path) -> Result<AppConfig> {
+        let contents = std::fs::read_to_string(path)
+            .with_context(|| format!("Failed to read {}", path.display()))?;
+        let config: AppConfig = toml::from_str(&contents)
+            .with_context(|| format!("Invalid config in {}", path.display()))?;
+        config.concurrency.validate()
+            .with_context(|| format!("Invalid config in {}", path.display()))?;
+        Ok(config)
+    }
+
+    fn modified_time(path: &Path) -> Option<SystemTime> {
+        std::fs::metadata(path).and_then(|m| m.modified()).ok()
+    }
+}
@@ -2,23 +2,126 @@ pub mod models;
 pub mod operations;
 
 use sqlx::{SqlitePool, Row};
-use anyhow::Result;
+use sqlx::sqlite::{SqliteConnectOptions, SqliteJournalMode, SqlitePoolOptions, SqliteSynchronous};
+use anyhow::{Result, Context};
+use std::path::Path;
+use std::str::FromStr;
+use std::sync::RwLock;
+use std::time::Duration;
+
+/// How long a writer waits on a locked database before giving up. Network
+/// range scans store results from many tasks at once.
+const BUSY_TIMEOUT: Duration = Duration::from_secs(30);
+const MAX_CONNECTIONS: u32 = 8;
+const ACQUIRE_TIMEOUT: Duration = Duration::from_secs(60);
 
 pub struct Database {
     pool: SqlitePool,
+    project: RwLock<Option<ProjectDatabase>>,
+}
+
+struct ProjectDatabase {
+    project_id: String,
+    pool: SqlitePool,
 }
 
 impl Database {
     pub async fn new(database_url: &str) -> Result<Self> {
-        let pool = SqlitePool::connect(database_url).await?;
+        let pool = Self::connect(SqliteConnectOptions::from_str(database_url)?).await?;
         
         // Run migrations
         sqlx::migrate!("./migrations").run(&pool).await?;
         
-        Ok(Self { pool })
+        Ok(Self {
+            pool,
+            project: RwLock::new(None),
+        })
     }
 
-    pub fn pool(&self) -> &SqlitePool {
+    /// WAL lets readers proceed alongside the single writer, and the busy
+    /// timeout makes concurrent writers queue instead of failing with
+    /// "database is locked".
+    async fn connect(options: SqliteConnectOptions) -> Result<SqlitePool> {
+        let options = options
+            .create_if_missing(true)
+            .journal_mode(SqliteJournalMode::Wal)
+            .synchronous(SqliteSynchronous::Normal)
+            .busy_timeout(BUSY_TIMEOUT)
+            .foreign_keys(true);
+
+        let pool = SqlitePoolOptions::new()
+            .max_connections(MAX_CONNECTIONS)
+            .min_connections(1)
+            .acquire_timeout(ACQUIRE_TIMEOUT)
+            .connect_with(options)
+            .await?;
+
+        Ok(pool)
+    }
+
+    /// Pool holding assessment data: the open project's own file in
+    /// per-project mode, otherwise the main database.
+    pub fn pool(&self) -> SqlitePool {
+        match self.project.read().unwrap().as_ref() {
+            Some(project) => project.pool.clone(),
+            None => self.pool.clone(),
+        }
+    }
+
+    /// Main database holding the project registry and project settings.
+    pub fn main_pool(&self) -> &SqlitePool {
         &self.pool
     }
+
+    pub fn open_project_id(&self) -> Option<String> {
+        self.project.read().unwrap()
+            .as_ref()
+            .map(|p| p.project_id.clone())
+    }
+
+    /// Opens (creating if needed) a project's own database file and makes
+    /// it the active pool. Any previously open project file is closed.
+    pub async fn open_project_file(&self, project_id: &str, path: &Path) -> Result<()> {
+        let pool = Self::connect_project_file(path).await?;
+
+        let previous = self.project.write().unwrap().replace(ProjectDatabase {
+            project_id: project_id.to_string(),
+            pool,
+        });
+
+        operations::QueryCache::invalidate();
+        if let Some(previous) = previous {
+            previous.pool.close().await;
+        }
+
+        Ok(())
+    }
+
+    /// Creates a project's database file if needed and brings its schema
+    /// up to date, without opening the project.
+    pub async fn prepare_project_file(path: &Path) -> Result<()> {
+        Self::connect_project_file(path).await?.close().await;
+        Ok(())
+    }
+
+    async fn connect_project_file(path: &Path) -> Result<SqlitePool> {
+        if let Some(dir) = path.parent() {
+            tokio::fs::create_dir_all(dir).await?;
+        }
+
+        let pool = Self::connect(SqliteConnectOptions::new().filename(path))
+            .await
+            .with_context(|| format!("Failed to open project database {}", path.display()))?;
+
+        sqlx::migrate!("./migrations").run(&pool).await?;
+        Ok(pool)
+    }
+
+    /// Closes the open project file, returning to the main database.
+    pub async fn close_project_file(&self) -> Option<String> {
+        let previous = self.project.write().unwrap().take()?;
+        operations::QueryCache::invalidate();
+        previous.pool.close().await;
+        Some(previous.project_id)
+    }
 }
\ No newline at end of file
@@ -1,25 +1,551 @@
 use serde::{Deserialize, Serialize};
 use sqlx::FromRow;
-use chrono::{DateTime, Utc};
+use ts_rs::TS;
+use chrono::{DateTime, NaiveDate, Utc};
 use uuid::Uuid;
 use std::net::IpAddr;
 
-#[derive(Debug, Clone, Serialize, Deserialize, FromRow)]
+#[derive(Debug, Clone, Serialize, Deserialize, FromRow, TS)]
+#[ts(export, export_to = "../src/bindings/")]
 pub struct Host {
     pub id: String,
     pub ip: String,
     pub hostname: Option<String>,
     pub mac_address: Option<String>,
     pub vendor: Option<String>,
+    pub vendor_class: Option<String>,
+    /// camera, printer, router, nas or plc, from web UI fingerprints
+    pub device_type: Option<String>,
+    pub device_product: Option<String>,
     pub os_name: Option<String>,
     pub os_family: Option<String>,
+    /// Confidence in the OS, 0-100, as its source rated it
     pub os_accuracy: Option<f32>,
+    /// What the OS came from: `nmap`, `smb`, `banner` or `passive`
+    pub os_source: Option<String>,
     pub status: String,
+    pub approved: bool,
+    pub rogue: bool,
     pub created_at: DateTime<Utc>,
     pub updated_at: DateTime<Utc>,
+    /// 0-100 aggregate from findings, services and exposure
+    pub risk_score: Option<f32>,
+    /// From GeoIP, for public addresses
+    pub country_code: Option<String>,
+    pub city: Option<String>,
+    /// Person or team responsible for remediation
+    pub owner: Option<String>,
+    pub business_unit: Option<String>,
+    /// `low`, `medium`, `high` or `critical`
+    pub criticality: Option<String>,
+    /// 802.1Q VLAN the sniffer saw the host on
+    pub vlan_id: Option<i32>,
+    /// On the watchlist: every change to the host raises a notification
+    pub watched: bool,
 }
 
-#[derive(Debug, Clone, Serialize, Deserialize, FromRow)]
+/// Narrows host listings. Unset fields match every host.
+#[derive(Debug, Clone, Default, Serialize, Deserialize, TS)]
+#[ts(export, export_to = "../src/bindings/")]
+pub struct HostFilter {
+    pub vlan_id: Option<i32>,
+}
+
+/// Ownership fields to set on a batch of hosts. Unset fields are left
+/// alone; an empty string clears the field.
+#[derive(Debug, Clone, Default, Serialize, Deserialize, TS)]
+#[ts(export, export_to = "../src/bindings/")]
+#[serde(default)]
+pub struct HostOwnershipUpdate {
+    pub owner: Option<String>,
+    pub business_unit: Option<String>,
+    pub criticality: Option<String>,
+}
+
+/// One change applied to a whole selection of hosts by
+/// `bulk_hosts_action`.
+#[derive(Debug, Clone, Serialize, Deserialize, TS)]
+#[ts(export, export_to = "../src/bindings/")]
+#[serde(tag = "action", rename_all = "snake_case")]
+pub enum BulkHostAction {
+    Tag { tag: String },
+    Untag { tag: String },
+    /// `low`, `medium`, `high` or `critical`; none clears it
+    SetCriticality { criticality: Option<String> },
+    /// Moves the hosts, with everything recorded about them, into another
+    /// project's database file
+    MoveToProject { project_id: String },
+    Delete,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, FromRow, TS)]
+#[ts(export, export_to = "../src/bindings/")]
+pub struct HostIpHistory {
+    pub host_id: String,
+    pub ip: String,
+    pub first_seen: DateTime<Utc>,
+    pub last_seen: DateTime<Utc>,
+}
+
+/// Algorithms an SSH server negotiates, stored as SSH name-lists
+/// (comma separated).
+#[derive(Debug, Clone, Serialize, Deserialize, FromRow, TS)]
+#[ts(export, export_to = "../src/bindings/")]
+pub struct SshAudit {
+    pub id: String,
+    pub host_id: String,
+    pub port: i32,
+    pub banner: String,
+    pub kex_algorithms: String,
+    pub host_key_algorithms: String,
+    pub ciphers: String,
+    pub macs: String,
+    pub host_key_type: Option<String>,
+    pub host_key_bits: Option<i32>,
+    pub audited_at: DateTime<Utc>,
+}
+
+/// Evidence of an FTP or TFTP server readable without credentials: the
+/// FTP root listing or the TFTP files that could be fetched.
+#[derive(Debug, Clone, Serialize, Deserialize, FromRow, TS)]
+#[ts(export, export_to = "../src/bindings/")]
+pub struct AnonymousAccess {
+    pub id: String,
+    pub host_id: String,
+    pub protocol: String,
+    pub port: i32,
+    pub banner: Option<String>,
+    pub listing: String,
+    pub listing_sha256: String,
+    pub checked_at: DateTime<Utc>,
+}
+
+/// Outcome of a native service check such as the SMTP relay test, with
+/// the protocol transcript kept as evidence.
+#[derive(Debug, Clone, Serialize, Deserialize, FromRow, TS)]
+#[ts(export, export_to = "../src/bindings/")]
+pub struct ServiceCheck {
+    pub id: String,
+    pub host_id: String,
+    pub port: i32,
+    pub check_name: String,
+    pub vulnerable: bool,
+    pub evidence: String,
+    pub evidence_sha256: String,
+    pub checked_at: DateTime<Utc>,
+}
+
+/// A host identified as industrial control equipment by one of the OT
+/// discovery probes.
+#[derive(Debug, Clone, Serialize, Deserialize, FromRow, TS)]
+#[ts(export, export_to = "../src/bindings/")]
+pub struct IcsAsset {
+    pub id: String,
+    pub host_id: String,
+    /// `modbus`, `s7`, `dnp3` or `bacnet`
+    pub protocol: String,
+    pub port: i32,
+    pub vendor: Option<String>,
+    pub model: Option<String>,
+    pub firmware: Option<String>,
+    pub details: Option<String>,
+    pub discovered_at: DateTime<Utc>,
+}
+
+/// A device seen over the air during a site survey rather than on the
+/// wired network.
+#[derive(Debug, Clone, Serialize, Deserialize, FromRow, TS)]
+#[ts(export, export_to = "../src/bindings/")]
+pub struct WirelessDevice {
+    pub id: String,
+    /// `ble`, `wifi_ap` or `wifi_client`
+    pub kind: String,
+    pub mac_address: String,
+    pub name: Option<String>,
+    pub rssi: Option<i32>,
+    /// Advertised service UUIDs, comma separated
+    pub services: Option<String>,
+    pub source: String,
+    pub first_seen: DateTime<Utc>,
+    pub last_seen: DateTime<Utc>,
+    pub channel: Option<i32>,
+    pub encryption: Option<String>,
+    /// Access point a Wi-Fi client was associated with
+    pub bssid: Option<String>,
+}
+
+/// A public IP or load balancer enumerated from a cloud account.
+#[derive(Debug, Clone, Serialize, Deserialize, FromRow, TS)]
+#[ts(export, export_to = "../src/bindings/")]
+pub struct CloudAsset {
+    pub id: String,
+    /// `aws`, `azure` or `gcp`
+    pub provider: String,
+    pub kind: String,
+    pub resource_id: String,
+    pub name: Option<String>,
+    pub address: String,
+    pub region: Option<String>,
+    /// Ports open to the internet, comma separated
+    pub exposure: Option<String>,
+    pub discovered_at: DateTime<Utc>,
+}
+
+/// A NAT forward read from an Internet Gateway Device.
+#[derive(Debug, Clone, Serialize, Deserialize, FromRow, TS)]
+#[ts(export, export_to = "../src/bindings/")]
+pub struct NatPortMapping {
+    pub id: String,
+    /// The gateway the mapping was read from
+    pub host_id: String,
+    pub remote_host: Option<String>,
+    pub external_port: i32,
+    pub protocol: String,
+    pub internal_client: String,
+    pub internal_port: i32,
+    pub enabled: bool,
+    pub description: Option<String>,
+    #[ts(type = "number")]
+    pub lease_duration: i64,
+    pub discovered_at: DateTime<Utc>,
+}
+
+/// One OS guess for a host. The host's own os_* columns hold the fused
+/// result of all of them.
+#[derive(Debug, Clone, Serialize, Deserialize, FromRow, TS)]
+#[ts(export, export_to = "../src/bindings/")]
+pub struct OsObservation {
+    pub id: String,
+    pub host_id: String,
+    /// `nmap`, `passive`, `smb` or `banner`
+    pub source: String,
+    pub os_name: String,
+    pub os_family: String,
+    pub accuracy: f32,
+    pub observed_at: DateTime<Utc>,
+}
+
+/// One up/down observation of a host, from a scan or the monitor.
+#[derive(Debug, Clone, Serialize, Deserialize, FromRow, TS)]
+#[ts(export, export_to = "../src/bindings/")]
+pub struct HostStatusSample {
+    pub id: String,
+    pub host_id: String,
+    /// `up` or `down`
+    pub status: String,
+    pub rtt_ms: Option<f64>,
+    /// `scan` or `monitor`
+    pub source: String,
+    pub recorded_at: DateTime<Utc>,
+}
+
+/// An entry on the project activity timeline.
+#[derive(Debug, Clone, Serialize, Deserialize, FromRow, TS)]
+#[ts(export, export_to = "../src/bindings/")]
+pub struct TimelineEvent {
+    pub id: String,
+    /// `discovery`, `scan`, `finding` or `user`
+    pub category: String,
+    pub action: String,
+    pub host_id: Option<String>,
+    /// Scan, vulnerability or host the event concerns
+    pub entity_id: Option<String>,
+    pub message: String,
+    pub created_at: DateTime<Utc>,
+}
+
+/// Something that happened while nobody may have been watching: a scan
+/// finishing, a rogue device, a critical finding.
+#[derive(Debug, Clone, Serialize, Deserialize, FromRow, TS)]
+#[ts(export, export_to = "../src/bindings/")]
+pub struct Notification {
+    pub id: String,
+    /// `info` through `critical`, as for findings
+    pub severity: String,
+    pub title: String,
+    pub message: String,
+    /// `scan`, `host`, `vulnerability`, `campaign` or `report`
+    pub entity_type: Option<String>,
+    pub entity_id: Option<String>,
+    pub unread: bool,
+    pub created_at: DateTime<Utc>,
+}
+
+#[derive(Debug, Clone, Default, Serialize, Deserialize, TS)]
+#[ts(export, export_to = "../src/bindings/")]
+pub struct EventFilter {
+    pub category: Option<String>,
+    pub host_id: Option<String>,
+    pub entity_id: Option<String>,
+}
+
+#[derive(Debug, Clone, Default, Serialize, Deserialize, TS)]
+#[ts(export, export_to = "../src/bindings/")]
+pub struct TimeRange {
+    pub since: Option<DateTime<Utc>>,
+    pub until: Option<DateTime<Utc>>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, TS)]
+#[ts(export, export_to = "../src/bindings/")]
+pub struct HostAvailability {
+    pub host_id: String,
+    pub samples: Vec<HostStatusSample>,
+    pub uptime_percent: Option<f64>,
+    pub average_rtt_ms: Option<f64>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, FromRow, TS)]
+#[ts(export, export_to = "../src/bindings/")]
+pub struct BaselineSummary {
+    #[ts(type = "number")]
+    pub approved: i64,
+    #[ts(type = "number")]
+    pub rogue: i64,
+    #[ts(type = "number")]
+    pub unapproved: i64,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, FromRow, TS)]
+#[ts(export, export_to = "../src/bindings/")]
+pub struct DashboardSummary {
+    #[ts(type = "number")]
+    pub hosts: i64,
+    #[ts(type = "number")]
+    pub open_ports: i64,
+    #[ts(type = "number")]
+    pub critical: i64,
+    #[ts(type = "number")]
+    pub high: i64,
+    #[ts(type = "number")]
+    pub medium: i64,
+    #[ts(type = "number")]
+    pub low: i64,
+    #[ts(type = "number")]
+    pub info: i64,
+}
+
+/// A DNS or mDNS answer seen by the sniffer.
+#[derive(Debug, Clone, Serialize, Deserialize, FromRow, TS)]
+#[ts(export, export_to = "../src/bindings/")]
+pub struct PassiveDnsRecord {
+    pub id: String,
+    /// The question the answer was given for
+    pub query: String,
+    /// Owner of the record
+    pub name: String,
+    /// `A`, `AAAA`, `CNAME` or `PTR`
+    pub record_type: String,
+    pub value: String,
+    #[ts(type = "number")]
+    pub ttl: i64,
+    /// Who asked, and who answered, when the record was last seen
+    pub client_ip: String,
+    pub server_ip: String,
+    #[ts(type = "number")]
+    pub observations: i64,
+    pub first_seen: DateTime<Utc>,
+    pub last_seen: DateTime<Utc>,
+}
+
+/// Traffic between a client and one service of a server, totalled over
+/// every capture.
+#[derive(Debug, Clone, Serialize, Deserialize, FromRow, TS)]
+#[ts(export, export_to = "../src/bindings/")]
+pub struct Flow {
+    pub id: String,
+    pub client_ip: String,
+    pub server_ip: String,
+    /// `tcp` or `udp`
+    pub protocol: String,
+    pub server_port: i32,
+    #[ts(type = "number")]
+    pub packets: i64,
+    #[ts(type = "number")]
+    pub bytes: i64,
+    pub first_seen: DateTime<Utc>,
+    pub last_seen: DateTime<Utc>,
+}
+
+/// TLS connections the sniffer saw to one service under one name.
+#[derive(Debug, Clone, Serialize, Deserialize, FromRow, TS)]
+#[ts(export, export_to = "../src/bindings/")]
+pub struct TlsSession {
+    pub id: String,
+    pub server_ip: String,
+    pub server_port: i32,
+    /// SNI the clients sent; empty when they sent none
+    pub server_name: String,
+    /// Comma-separated ALPN protocols, e.g. `h2,http/1.1`
+    pub alpn: Option<String>,
+    /// Highest version offered by the last client
+    pub tls_version: String,
+    pub client_ip: String,
+    #[ts(type = "number")]
+    pub observations: i64,
+    pub first_seen: DateTime<Utc>,
+    pub last_seen: DateTime<Utc>,
+}
+
+/// A JA3 or JA3S hash the sniffer saw a host's TLS stack produce.
+#[derive(Debug, Clone, Serialize, Deserialize, FromRow, TS)]
+#[ts(export, export_to = "../src/bindings/")]
+pub struct TlsFingerprint {
+    pub id: String,
+    pub host_id: String,
+    /// `ja3` for a client, `ja3s` for a server
+    pub kind: String,
+    pub hash: String,
+    /// What the fingerprint database knows the hash as
+    pub software: Option<String>,
+    pub suspicious: bool,
+    #[ts(type = "number")]
+    pub observations: i64,
+    pub first_seen: DateTime<Utc>,
+    pub last_seen: DateTime<Utc>,
+}
+
+/// A header value cleartext HTTP showed for a host.
+#[derive(Debug, Clone, Serialize, Deserialize, FromRow, TS)]
+#[ts(export, export_to = "../src/bindings/")]
+pub struct HttpMetadata {
+    pub id: String,
+    pub host_id: String,
+    /// The server port, or 0 for headers the host sent as a client
+    pub port: i32,
+    /// `user_agent`, `virtual_host`, `server` or `powered_by`
+    pub kind: String,
+    pub value: String,
+    #[ts(type = "number")]
+    pub observations: i64,
+    pub first_seen: DateTime<Utc>,
+    pub last_seen: DateTime<Utc>,
+}
+
+/// One piece of software seen in HTTP headers across the project.
+#[derive(Debug, Clone, Serialize, Deserialize, TS)]
+#[ts(export, export_to = "../src/bindings/")]
+pub struct HttpSoftware {
+    /// `user_agent`, `server` or `powered_by`
+    pub kind: String,
+    pub value: String,
+    #[ts(type = "number")]
+    pub hosts: i64,
+    #[ts(type = "number")]
+    pub observations: i64,
+    pub last_seen: DateTime<Utc>,
+}
+
+/// A host, router or layer 2 segment in the topology graph.
+#[derive(Debug, Clone, Serialize, Deserialize, FromRow, TS)]
+#[ts(export, export_to = "../src/bindings/")]
+pub struct TopologyNode {
+    pub id: String,
+    /// IP address, or the CIDR of a segment
+    pub address: String,
+    /// `host`, `router`, `gateway` or `segment`
+    pub kind: String,
+    /// The stored host at this address, if there is one
+    pub host_id: Option<String>,
+    pub hostname: Option<String>,
+    pub first_seen: DateTime<Utc>,
+    pub last_seen: DateTime<Utc>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, FromRow, TS)]
+#[ts(export, export_to = "../src/bindings/")]
+pub struct TopologyEdge {
+    pub id: String,
+    pub source_id: String,
+    pub target_id: String,
+    /// `route` between traceroute hops, `layer2` from a host to its
+    /// segment, or `traffic` between hosts seen talking
+    pub kind: String,
+    /// How many times the link was observed
+    #[ts(type = "number")]
+    pub observations: i64,
+    pub first_seen: DateTime<Utc>,
+    pub last_seen: DateTime<Utc>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, TS)]
+#[ts(export, export_to = "../src/bindings/")]
+pub struct Topology {
+    pub nodes: Vec<TopologyNode>,
+    pub edges: Vec<TopologyEdge>,
+}
+
+/// A host seen using a service on another, e.g. a web server querying
+/// its database.
+#[derive(Debug, Clone, Serialize, Deserialize, FromRow, TS)]
+#[ts(export, export_to = "../src/bindings/")]
+pub struct ServiceDependency {
+    pub id: String,
+    pub client_host_id: String,
+    pub client_ip: String,
+    pub server_host_id: String,
+    pub server_ip: String,
+    pub protocol: String,
+    pub server_port: i32,
+    /// Service stored for the server's port, when it was identified
+    pub service: Option<String>,
+    /// `web`, `database`, `cache`, `queue`, `directory`, `file`, `mail`,
+    /// `dns`, `remote_access` or `other`
+    pub tier: String,
+    #[ts(type = "number")]
+    pub packets: i64,
+    #[ts(type = "number")]
+    pub bytes: i64,
+    pub first_seen: DateTime<Utc>,
+    pub last_seen: DateTime<Utc>,
+}
+
+/// The services a host relies on and the hosts relying on its own, for
+/// scoping the impact of a finding on it.
+#[derive(Debug, Clone, Serialize, Deserialize, TS)]
+#[ts(export, export_to = "../src/bindings/")]
+pub struct HostDependencies {
+    pub host_id: String,
+    pub depends_on: Vec<ServiceDependency>,
+    pub dependents: Vec<ServiceDependency>,
+}
+
+/// Hosts, services and findings of one subnet, for the heat map.
+#[derive(Debug, Clone, Serialize, Deserialize, TS)]
+#[ts(export, export_to = "../src/bindings/")]
+pub struct SubnetSummary {
+    /// e.g. `10.0.3.0/24`
+    pub subnet: String,
+    #[ts(type = "number")]
+    pub hosts: i64,
+    #[ts(type = "number")]
+    pub live_hosts: i64,
+    /// Most common open services, most frequent first
+    pub top_services: Vec<ServiceCount>,
+    #[ts(type = "number")]
+    pub critical: i64,
+    #[ts(type = "number")]
+    pub high: i64,
+    #[ts(type = "number")]
+    pub medium: i64,
+    #[ts(type = "number")]
+    pub low: i64,
+    #[ts(type = "number")]
+    pub info: i64,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, TS)]
+#[ts(export, export_to = "../src/bindings/")]
+pub struct ServiceCount {
+    /// Service name, or `port/protocol` when none was identified
+    pub service: String,
+    #[ts(type = "number")]
+    pub count: i64,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, FromRow, TS)]
+#[ts(export, export_to = "../src/bindings/")]
+#[ts(rename = "HostPort")]
 pub struct Port {
     pub id: String,
     pub host_id: String,
@@ -30,9 +556,20 @@ pub struct Port {
     pub version: Option<String>,
     pub banner: Option<String>,
     pub created_at: DateTime<Utc>,
+    /// Scan that observed the port, when it came from one
+    pub scan_id: Option<String>,
+    /// When the port was first and last observed by any scan or the
+    /// sniffer, across every record of it
+    pub first_seen: DateTime<Utc>,
+    pub last_seen: DateTime<Utc>,
+    /// What observed the port: `nmap`, `masscan`, `sniffer`, ...
+    pub source: Option<String>,
+    /// 0-100, from the source; see `source_confidence`
+    pub confidence: Option<f32>,
 }
 
-#[derive(Debug, Clone, Serialize, Deserialize, FromRow)]
+#[derive(Debug, Clone, Serialize, Deserialize, FromRow, TS)]
+#[ts(export, export_to = "../src/bindings/")]
 pub struct Scan {
     pub id: String,
     pub name: String,
@@ -43,9 +580,139 @@ pub struct Scan {
     pub start_time: DateTime<Utc>,
     pub end_time: Option<DateTime<Utc>>,
     pub created_at: DateTime<Utc>,
+    pub output_sha256: Option<String>,
+    pub result_sha256: Option<String>,
+    pub started_by: Option<String>,
+    pub reason: Option<String>,
+    pub ticket_ref: Option<String>,
+}
+
+/// Who started a scan and why, kept on the scan record for audits.
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize, TS)]
+#[ts(export, export_to = "../src/bindings/")]
+#[serde(default)]
+pub struct ScanAnnotations {
+    pub started_by: Option<String>,
+    pub reason: Option<String>,
+    /// Change or engagement ticket, e.g. `CHG-1234`
+    pub ticket_ref: Option<String>,
+}
+
+/// Filters and ordering for stored scan results. Unset fields don't
+/// filter.
+#[derive(Debug, Clone, Default, Serialize, Deserialize, TS)]
+#[ts(export, export_to = "../src/bindings/")]
+#[serde(default)]
+pub struct ScanResultFilter {
+    pub target_ip: Option<String>,
+    pub scan_id: Option<String>,
+    pub started_after: Option<DateTime<Utc>>,
+    pub started_before: Option<DateTime<Utc>>,
+    #[ts(type = "number | null")]
+    pub min_open_ports: Option<i64>,
+    pub has_vulnerabilities: Option<bool>,
+    /// `time` (default), `open_ports` or `target_ip`
+    pub sort_by: Option<String>,
+    pub descending: bool,
+    #[ts(type = "number | null")]
+    pub limit: Option<i64>,
 }
 
-#[derive(Debug, Clone, Serialize, Deserialize, FromRow)]
+/// Complete tool output of a scan, zlib-compressed.
+#[derive(Debug, Clone, Serialize, Deserialize, FromRow, TS)]
+#[ts(export, export_to = "../src/bindings/")]
+pub struct RawOutput {
+    pub id: String,
+    pub scan_id: String,
+    /// `nmap-xml` or `masscan-list`
+    pub format: String,
+    #[serde(skip)]
+    pub data: Vec<u8>,
+    #[ts(type = "number")]
+    pub original_size: i64,
+    pub created_at: DateTime<Utc>,
+    /// False for output moved over from the scan record as it was stored
+    pub compressed: bool,
+}
+
+/// Traffic to and from one host, captured on demand for Wireshark.
+#[derive(Debug, Clone, Serialize, Deserialize, FromRow, TS)]
+#[ts(export, export_to = "../src/bindings/")]
+pub struct PacketCapture {
+    pub id: String,
+    pub host_id: String,
+    pub interface: String,
+    /// The BPF expression the capture ran with, host match included
+    pub filter: String,
+    /// `running`, `completed`, `stopped` or `failed`
+    pub status: String,
+    /// Packets kept in the capture
+    #[ts(type = "number")]
+    pub packets: i64,
+    /// Oldest packets dropped to stay within the size limit
+    #[ts(type = "number")]
+    pub evicted: i64,
+    /// The pcap file, compressed
+    #[serde(skip)]
+    pub data: Option<Vec<u8>>,
+    #[ts(type = "number")]
+    pub original_size: i64,
+    pub error: Option<String>,
+    pub started_at: DateTime<Utc>,
+    pub finished_at: Option<DateTime<Utc>>,
+}
+
+/// Targets re-scanned on a fixed interval, each run ending in a report of
+/// what changed since the previous one.
+#[derive(Debug, Clone, Serialize, Deserialize, FromRow, TS)]
+#[ts(export, export_to = "../src/bindings/")]
+pub struct ReportSchedule {
+    pub id: String,
+    pub name: String,
+    pub targets: String, // JSON array of IPs and CIDRs
+    /// `quick`, `comprehensive`, `stealth` or `ot`
+    pub scan_type: String,
+    pub interval_days: i32,
+    /// Directory each report is also written to as a Markdown file
+    pub output_dir: Option<String>,
+    pub enabled: bool,
+    pub last_run_at: Option<DateTime<Utc>>,
+    pub next_run_at: DateTime<Utc>,
+    pub created_at: DateTime<Utc>,
+}
+
+/// One run of a report schedule.
+#[derive(Debug, Clone, Serialize, Deserialize, FromRow, TS)]
+#[ts(export, export_to = "../src/bindings/")]
+pub struct DifferentialReport {
+    pub id: String,
+    pub schedule_id: String,
+    /// The project's state after the run, which the next run is compared
+    /// against
+    #[serde(skip)]
+    pub snapshot: String,
+    /// Markdown
+    pub report: String,
+    /// Changes found since the previous run
+    pub changes: i32,
+    pub created_at: DateTime<Utc>,
+}
+
+/// Outcome of re-hashing a scan's stored evidence.
+#[derive(Debug, Clone, Serialize, Deserialize, TS)]
+#[ts(export, export_to = "../src/bindings/")]
+pub struct ScanIntegrity {
+    pub scan_id: String,
+    pub output_sha256: Option<String>,
+    pub result_sha256: Option<String>,
+    pub output_valid: bool,
+    pub result_valid: bool,
+    pub verified: bool,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, FromRow, TS)]
+#[ts(export, export_to = "../src/bindings/")]
+#[ts(rename = "HostVulnerability")]
 pub struct Vulnerability {
     pub id: String,
     pub host_id: String,
@@ -56,9 +723,42 @@ pub struct Vulnerability {
     pub cvss_score: Option<f32>,
     pub references: Option<String>, // JSON array
     pub discovered_at: DateTime<Utc>,
+    /// CVSS v3.x vector the score was computed from, when known
+    pub cvss_vector: Option<String>,
+    /// Listed in CISA's Known Exploited Vulnerabilities catalog
+    pub kev: bool,
+    /// EPSS exploitation probability, 0.0 to 1.0
+    pub epss: Option<f32>,
+    /// What reported the finding: `nmap`, `nuclei`, `nessus`, `manual`
+    /// or `check`
+    pub source: Option<String>,
+    /// The severity as the source gave it, before it was mapped onto the
+    /// app's scale
+    pub original_severity: Option<String>,
+    /// The check that produced the finding, as `FindingCheck` writes it;
+    /// without one the finding can't be verified by re-running it
+    pub check_ref: Option<String>,
+    /// `open`, `fixed` or `still_present`, from the last verification
+    pub status: String,
+    /// 0-100, from the source; see `source_confidence`
+    pub confidence: Option<f32>,
+}
+
+/// One re-run of the check behind a finding, with what it returned.
+#[derive(Debug, Clone, Serialize, Deserialize, FromRow, TS)]
+#[ts(export, export_to = "../src/bindings/")]
+pub struct FindingVerification {
+    pub id: String,
+    pub vulnerability_id: String,
+    /// `fixed` or `still_present`
+    pub outcome: String,
+    pub evidence: String,
+    pub evidence_sha256: String,
+    pub verified_at: DateTime<Utc>,
 }
 
-#[derive(Debug, Clone, Serialize, Deserialize, FromRow)]
+#[derive(Debug, Clone, Serialize, Deserialize, FromRow, TS)]
+#[ts(export, export_to = "../src/bindings/")]
 pub struct Script {
     pub id: String,
     pub host_id: String,
@@ -68,11 +768,192 @@ pub struct Script {
     pub executed_at: DateTime<Utc>,
 }
 
-#[derive(Debug, Clone, Serialize, Deserialize, FromRow)]
+#[derive(Debug, Clone, Serialize, Deserialize, FromRow, TS)]
+#[ts(export, export_to = "../src/bindings/")]
 pub struct Project {
     pub id: String,
     pub name: String,
     pub description: Option<String>,
     pub created_at: DateTime<Utc>,
     pub updated_at: DateTime<Utc>,
-}
\ No newline at end of file
+    pub client_name: Option<String>,
+    pub scope_statement: Option<String>,
+    pub start_date: Option<NaiveDate>,
+    pub end_date: Option<NaiveDate>,
+    pub rules_of_engagement: Option<String>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, FromRow, TS)]
+#[ts(export, export_to = "../src/bindings/")]
+pub struct ScopeEntry {
+    pub id: String,
+    pub project_id: String,
+    /// A CIDR/IP, or a domain that covers its subdomains
+    pub entry: String,
+    /// `cidr` or `domain`
+    pub kind: String,
+    pub created_at: DateTime<Utc>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, FromRow, TS)]
+#[ts(export, export_to = "../src/bindings/")]
+pub struct AuditLogEntry {
+    pub id: String,
+    pub project_id: Option<String>,
+    pub action: String,
+    pub target: Option<String>,
+    pub details: Option<String>,
+    pub created_at: DateTime<Utc>,
+}
+
+/// A user of a shared instance. See `auth::Role` for the roles.
+#[derive(Debug, Clone, Serialize, Deserialize, FromRow, TS)]
+#[ts(export, export_to = "../src/bindings/")]
+pub struct User {
+    pub id: String,
+    pub username: String,
+    /// `admin`, `operator` or `read_only`
+    pub role: String,
+    pub created_at: DateTime<Utc>,
+    pub updated_at: DateTime<Utc>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, FromRow, TS)]
+#[ts(export, export_to = "../src/bindings/")]
+pub struct ApiToken {
+    pub id: String,
+    pub user_id: String,
+    pub name: String,
+    /// SHA-256 of the token; the token itself is never stored
+    #[serde(skip_serializing)]
+    #[ts(skip)]
+    pub token_hash: String,
+    /// `view`, `scan` or `administer`, capped by the owner's role
+    pub scope: String,
+    pub expires_at: Option<DateTime<Utc>>,
+    pub last_used_at: Option<DateTime<Utc>>,
+    pub revoked_at: Option<DateTime<Utc>>,
+    pub created_at: DateTime<Utc>,
+}
+
+/// A newly created token. `token` is shown once and cannot be recovered.
+#[derive(Debug, Clone, Serialize, Deserialize, TS)]
+#[ts(export, export_to = "../src/bindings/")]
+pub struct IssuedApiToken {
+    pub token: String,
+    pub api_token: ApiToken,
+}
+
+/// Engagement details recorded on a project and printed in report headers.
+#[derive(Debug, Clone, Default, Serialize, Deserialize, TS)]
+#[ts(export, export_to = "../src/bindings/")]
+pub struct EngagementDetails {
+    pub client_name: Option<String>,
+    pub scope_statement: Option<String>,
+    pub start_date: Option<NaiveDate>,
+    pub end_date: Option<NaiveDate>,
+    pub rules_of_engagement: Option<String>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, FromRow, TS)]
+#[ts(export, export_to = "../src/bindings/")]
+pub struct PortPreset {
+    pub id: String,
+    pub name: String,
+    pub description: Option<String>,
+    /// Port list in nmap's spec syntax, e.g. `22,80,8000-8100`
+    pub ports: String,
+    pub created_at: DateTime<Utc>,
+    pub updated_at: DateTime<Utc>,
+}
+
+/// Runs a check automatically when a stored result has an open port that
+/// matches, e.g. SMB enumeration whenever 445 is open.
+#[derive(Debug, Clone, Serialize, Deserialize, FromRow, TS)]
+#[ts(export, export_to = "../src/bindings/")]
+pub struct FollowUpRule {
+    pub id: String,
+    pub name: String,
+    pub match_port: Option<i32>,
+    /// Service name as nmap reports it, e.g. `http`; compared ignoring case
+    pub match_service: Option<String>,
+    /// One of the `FollowUpAction` names, e.g. `smb_enum` or `nuclei`
+    pub action: String,
+    /// NSE script selection or nuclei filters such as `tag:cve`
+    pub arguments: Option<String>,
+    pub enabled: bool,
+    pub created_at: DateTime<Utc>,
+    pub updated_at: DateTime<Utc>,
+}
+
+/// Operational defaults stored per project in `project_settings`.
+#[derive(Debug, Clone, Default, Serialize, Deserialize, TS)]
+#[ts(export, export_to = "../src/bindings/")]
+pub struct ProjectSettings {
+    pub default_scan_profile: Option<String>,
+    pub exclude_list: Vec<String>,
+    pub rate_cap_pps: Option<u32>,
+    pub notification_targets: Vec<String>,
+    #[serde(default)]
+    pub proxy: Option<ProxySettings>,
+    /// Permit scanning addresses outside private ranges
+    #[serde(default)]
+    pub allow_public_targets: bool,
+    #[serde(default)]
+    pub pipeline: PipelineSettings,
+}
+
+/// Optional stages of the ingest pipeline. Storage always runs.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize, TS)]
+#[ts(export, export_to = "../src/bindings/")]
+#[serde(default)]
+pub struct PipelineSettings {
+    pub normalize: bool,
+    /// Skip observations repeated within a minute
+    pub dedup: bool,
+    pub reverse_dns: bool,
+    /// Vendor names from MAC address prefixes
+    pub oui: bool,
+    /// Country and city of public addresses, when a database is configured
+    pub geoip: bool,
+}
+
+impl Default for PipelineSettings {
+    fn default() -> Self {
+        Self {
+            normalize: true,
+            dedup: true,
+            reverse_dns: true,
+            oui: true,
+            geoip: true,
+        }
+    }
+}
+
+/// Pivot proxy for routing scans, e.g. a SOCKS tunnel into the client
+/// network.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize, TS)]
+#[ts(export, export_to = "../src/bindings/")]
+pub struct ProxySettings {
+    /// `socks4`, `socks5` or `http`
+    pub kind: String,
+    pub host: String,
+    pub port: u16,
+    /// `nmap` (`--proxies`) or `proxychains`
+    pub mode: String,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, FromRow, TS)]
+#[ts(export, export_to = "../src/bindings/")]
+pub struct Share {
+    pub id: String,
+    pub host_id: String,
+    pub name: String,
+    pub share_type: Option<String>,
+    pub comment: Option<String>,
+    pub anonymous_access: Option<String>,
+    pub user_access: Option<String>,
+    pub accessible: bool,
+    pub writable: bool,
+    pub discovered_at: DateTime<Utc>,
+}
@@ -1,9 +1,102 @@
 use super::models::*;
+use crate::utils::NetworkUtils;
 use sqlx::{SqlitePool, Row};
 use anyhow::Result;
+use serde::{Serialize, de::DeserializeOwned};
 use uuid::Uuid;
-use chrono::Utc;
+use chrono::{DateTime, Utc};
+use sha2::{Digest, Sha256};
+use flate2::{Compression, read::ZlibDecoder, write::ZlibEncoder};
+use std::io::{Read, Write};
+use std::any::Any;
+use std::collections::HashMap;
 use std::net::IpAddr;
+use std::sync::{Arc, Mutex, OnceLock};
+use std::time::{Duration, Instant};
+
+fn sha256_hex(data: &[u8]) -> String {
+    format!("{:x}", Sha256::digest(data))
+}
+
+fn compress(data: &[u8]) -> Result<Vec<u8>> {
+    let mut encoder = ZlibEncoder::new(Vec::new(), Compression::default());
+    encoder.write_all(data)?;
+    Ok(encoder.finish()?)
+}
+
+fn decompress(data: &[u8]) -> Result<Vec<u8>> {
+    let mut output = Vec::new();
+    ZlibDecoder::new(data).read_to_end(&mut output)?;
+    Ok(output)
+}
+
+/// How far each OS source is trusted relative to its own accuracy figure
+const OS_SOURCE_WEIGHTS: &[(&str, f32)] = &[
+    ("nmap", 1.0),
+    ("smb", 0.9),
+    ("passive", 0.6),
+    ("banner", 0.5),
+];
+const DEFAULT_OS_SOURCE_WEIGHT: f32 = 0.5;
+
+/// How long an aggregate result is served from memory. Writes through these
+/// operations clear the cache sooner; the TTL bounds staleness from writes
+/// made elsewhere, such as another process sharing the database file.
+const QUERY_CACHE_TTL: Duration = Duration::from_secs(5);
+
+type CachedQuery = (Instant, Arc<dyn Any + Send + Sync>);
+
+/// Results of the aggregate queries the dashboard polls, so a UI refreshing
+/// every few seconds during a scan doesn't rescan whole tables each time.
+pub struct QueryCache;
+
+impl QueryCache {
+    fn entries() -> &'static Mutex<HashMap<&'static str, CachedQuery>> {
+        static ENTRIES: OnceLock<Mutex<HashMap<&'static str, CachedQuery>>> = OnceLock::new();
+        ENTRIES.get_or_init(|| Mutex::new(HashMap::new()))
+    }
+
+    fn get<T: Clone + Send + Sync + 'static>(key: &'static str) -> Option<T> {
+        let entries = Self::entries().lock().unwrap();
+        let (cached_at, value) = entries.get(key)?;
+        if cached_at.elapsed() >= QUERY_CACHE_TTL {
+            return None;
+        }
+        value.downcast_ref::<T>().cloned()
+    }
+
+    fn put<T: Send + Sync + 'static>(key: &'static str, value: T) {
+        Self::entries().lock().unwrap().insert(key, (Instant::now(), Arc::new(value)));
+    }
+
+    /// Drops every cached result. Called after writes to hosts, ports or
+    /// vulnerabilities, and when the active project database changes.
+    pub fn invalidate() {
+        Self::entries().lock().unwrap().clear();
+    }
+}
+
+/// Tables of per-host records, copied with a host moved to another
+/// project. Ordered so rows come after any they refer to.
+const HOST_TABLES: &[&str] = &[
+    "ports",
+    "vulnerabilities",
+    "scripts",
+    "shares",
+    "host_ip_history",
+    "host_status_history",
+    "os_observations",
+    "ssh_audits",
+    "ics_assets",
+    "nat_port_mappings",
+    "anonymous_access",
+    "service_checks",
+    "packet_captures",
+    "tls_fingerprints",
+    "http_metadata",
+    "host_tags",
+    "events",
+];
 
 pub struct HostOperations;
 
@@ -27,14 +120,17 @@ impl HostOperations {
         )
         .fetch_one(pool)
         .await?;
-        
+
+        QueryCache::invalidate();
         Ok(host)
     }
 
+    /// Finds the host currently holding `ip`. After a DHCP move two records
+    /// may share an address; the most recently updated one wins.
     pub async fn find_by_ip(pool: &SqlitePool, ip: IpAddr) -> Result<Option<Host>> {
         let host = sqlx::query_as!(
             Host,
-            "SELECT * FROM hosts WHERE ip = ?",
+            "SELECT * FROM hosts WHERE ip = ? ORDER BY updated_at DESC LIMIT 1",
             ip.to_string()
         )
         .fetch_optional(pool)
@@ -43,327 +139,4015 @@ impl HostOperations {
         Ok(host)
     }
 
+    pub async fn find_by_mac(pool: &SqlitePool, mac_address: &str) -> Result<Option<Host>> {
+        let host = sqlx::query_as!(
+            Host,
+            "SELECT * FROM hosts WHERE mac_address = ? ORDER BY updated_at DESC LIMIT 1",
+            mac_address
+        )
+        .fetch_optional(pool)
+        .await?;
+
+        Ok(host)
+    }
+
+    /// Like `find_by_ip`, but a host recorded on another VLAN is a
+    /// different device. Hosts whose VLAN is unknown match any.
+    pub async fn find_by_ip_in_vlan(pool: &SqlitePool, ip: IpAddr, vlan_id: Option<u16>) -> Result<Option<Host>> {
+        let vlan_id = vlan_id.map(i32::from);
+        let host = sqlx::query_as!(
+            Host,
+            r#"
+            SELECT * FROM hosts
+            WHERE ip = ?1 AND (?2 IS NULL OR vlan_id IS NULL OR vlan_id = ?2)
+            ORDER BY vlan_id IS ?2 DESC, updated_at DESC
+            LIMIT 1
+            "#,
+            ip.to_string(),
+            vlan_id
+        )
+        .fetch_optional(pool)
+        .await?;
+
+        Ok(host)
+    }
+
+    /// A router trunking several VLANs answers on each with one MAC, so
+    /// the MAC only identifies a host within its VLAN.
+    async fn find_by_mac_in_vlan(pool: &SqlitePool, mac_address: &str, vlan_id: Option<u16>) -> Result<Option<Host>> {
+        let vlan_id = vlan_id.map(i32::from);
+        let host = sqlx::query_as!(
+            Host,
+            r#"
+            SELECT * FROM hosts
+            WHERE mac_address = ?1 AND (?2 IS NULL OR vlan_id IS NULL OR vlan_id = ?2)
+            ORDER BY vlan_id IS ?2 DESC, updated_at DESC
+            LIMIT 1
+            "#,
+            mac_address,
+            vlan_id
+        )
+        .fetch_optional(pool)
+        .await?;
+
+        Ok(host)
+    }
+
+    /// Finds or creates the host record for an observation. The MAC address
+    /// is the primary identity when known, so a device that changed IP
+    /// updates its existing record. Observations tagged with a VLAN only
+    /// match hosts on that VLAN, or whose VLAN is unknown. Returns the host
+    /// and whether it was newly created.
+    pub async fn resolve_identity(
+        pool: &SqlitePool,
+        ip: IpAddr,
+        mac_address: Option<&str>,
+        vlan_id: Option<u16>,
+    ) -> Result<(Host, bool)> {
+        let mac_address = mac_address.map(|m| m.to_lowercase());
+
+        let (host, created) = match &mac_address {
+            Some(mac) => match Self::find_by_mac_in_vlan(pool, mac, vlan_id).await? {
+                Some(host) => {
+                    if host.ip != ip.to_string() {
+                        Self::update_ip(pool, &host.id, ip).await?;
+                    }
+                    (host, false)
+                }
+                // An IP-only record for this address is adopted unless it
+                // already belongs to a different device
+                None => match Self::find_by_ip_in_vlan(pool, ip, vlan_id).await? {
+                    Some(host) if host.mac_address.is_none() => {
+                        Self::update_mac(pool, &host.id, mac).await?;
+                        (host, false)
+                    }
+                    _ => {
+                        let host = Self::create(pool, ip, None).await?;
+                        Self::update_mac(pool, &host.id, mac).await?;
+                        (host, true)
+                    }
+                },
+            },
+            None => match Self::find_by_ip_in_vlan(pool, ip, vlan_id).await? {
+                Some(host) => (host, false),
+                None => (Self::create(pool, ip, None).await?, true),
+            },
+        };
+
+        if let Some(vlan_id) = vlan_id {
+            Self::update_vlan(pool, &host.id, vlan_id).await?;
+        }
+        HostIpHistoryOperations::record(pool, &host.id, ip).await?;
+        if created {
+            EventOperations::record(
+                pool,
+                EVENT_DISCOVERY,
+                "host_discovered",
+                Some(&host.id),
+                Some(&host.id),
+                &format!("Discovered host {}", ip),
+            ).await?;
+        }
+
+        let host = sqlx::query_as!(Host, "SELECT * FROM hosts WHERE id = ?", host.id)
+            .fetch_one(pool)
+            .await?;
+
+        Ok((host, created))
+    }
+
+    async fn update_ip(pool: &SqlitePool, host_id: &str, ip: IpAddr) -> Result<()> {
+        sqlx::query!(
+            "UPDATE hosts SET ip = ?, updated_at = ? WHERE id = ?",
+            ip.to_string(),
+            Utc::now(),
+            host_id
+        )
+        .execute(pool)
+        .await?;
+
+        Ok(())
+    }
+
+    async fn update_vlan(pool: &SqlitePool, host_id: &str, vlan_id: u16) -> Result<()> {
+        sqlx::query!(
+            "UPDATE hosts SET vlan_id = ? WHERE id = ?",
+            vlan_id as i32,
+            host_id
+        )
+        .execute(pool)
+        .await?;
+
+        Ok(())
+    }
+
+    async fn update_mac(pool: &SqlitePool, host_id: &str, mac_address: &str) -> Result<()> {
+        sqlx::query!(
+            "UPDATE hosts SET mac_address = ?, updated_at = ? WHERE id = ?",
+            mac_address,
+            Utc::now(),
+            host_id
+        )
+        .execute(pool)
+        .await?;
+
+        Ok(())
+    }
+
+    pub async fn find_by_id(pool: &SqlitePool, host_id: &str) -> Result<Option<Host>> {
+        let host = sqlx::query_as!(
+            Host,
+            "SELECT * FROM hosts WHERE id = ?",
+            host_id
+        )
+        .fetch_optional(pool)
+        .await?;
+
+        Ok(host)
+    }
+
+    /// Records an OS observation and re-fuses the host's OS from the latest
+    /// observation of every source.
     pub async fn update_os_info(
         pool: &SqlitePool,
         host_id: &str,
+        source: &str,
         os_name: &str,
         os_family: &str,
         accuracy: f32,
     ) -> Result<()> {
+        let id = Uuid::new_v4().to_string();
+        let now = Utc::now();
+
         sqlx::query!(
             r#"
-            UPDATE hosts 
-            SET os_name = ?, os_family = ?, os_accuracy = ?, updated_at = ?
-            WHERE id = ?
+            INSERT INTO os_observations (id, host_id, source, os_name, os_family, accuracy, observed_at)
+            VALUES (?, ?, ?, ?, ?, ?, ?)
             "#,
+            id,
+            host_id,
+            source,
             os_name,
             os_family,
             accuracy,
+            now
+        )
+        .execute(pool)
+        .await?;
+
+        let observations = Self::find_os_observations(pool, host_id).await?;
+        let Some((best, confidence)) = Self::fuse_os(&observations) else {
+            return Ok(());
+        };
+
+        sqlx::query!(
+            r#"
+            UPDATE hosts
+            SET os_name = ?, os_family = ?, os_accuracy = ?, os_source = ?, updated_at = ?
+            WHERE id = ?
+            "#,
+            best.os_name,
+            best.os_family,
+            confidence,
+            best.source,
+            now,
+            host_id
+        )
+        .execute(pool)
+        .await?;
+
+        Ok(())
+    }
+
+    /// Every OS observation for a host, newest first.
+    pub async fn find_os_observations(pool: &SqlitePool, host_id: &str) -> Result<Vec<OsObservation>> {
+        let observations = sqlx::query_as!(
+            OsObservation,
+            r#"
+            SELECT * FROM os_observations
+            WHERE host_id = ?
+            ORDER BY observed_at DESC
+            "#,
+            host_id
+        )
+        .fetch_all(pool)
+        .await?;
+
+        Ok(observations)
+    }
+
+    /// Combines the newest observation from each source. Each one supports
+    /// its family with probability weight * accuracy; agreeing sources
+    /// combine as independent evidence, and the winner's confidence is
+    /// discounted by the strongest competing family.
+    fn fuse_os(observations: &[OsObservation]) -> Option<(&OsObservation, f32)> {
+        let mut latest: Vec<&OsObservation> = Vec::new();
+        for obs in observations {
+            if !latest.iter().any(|o| o.source == obs.source) {
+                latest.push(obs);
+            }
+        }
+
+        let support = |obs: &OsObservation| {
+            (OS_SOURCE_WEIGHTS.iter()
+                .find(|(source, _)| *source == obs.source)
+                .map(|(_, weight)| *weight)
+                .unwrap_or(DEFAULT_OS_SOURCE_WEIGHT) * obs.accuracy / 100.0)
+                .clamp(0.0, 1.0)
+        };
+
+        let mut families: HashMap<String, f32> = HashMap::new();
+        for obs in &latest {
+            let doubt = families.entry(obs.os_family.to_lowercase()).or_insert(1.0);
+            *doubt *= 1.0 - support(obs);
+        }
+
+        let mut scores: Vec<(&String, f32)> = families.iter()
+            .map(|(family, doubt)| (family, 1.0 - doubt))
+            .collect();
+        scores.sort_by(|a, b| b.1.total_cmp(&a.1));
+
+        let (family, score) = scores.first()?;
+        let rival = scores.get(1).map(|(_, s)| *s).unwrap_or(0.0);
+
+        let best = latest.iter()
+            .filter(|o| o.os_family.to_lowercase() == **family)
+            .max_by(|a, b| support(a).total_cmp(&support(b)))?;
+
+        Some((*best, (score * (1.0 - rival) * 100.0).round()))
+    }
+
+    /// Adds hosts to (or removes them from) the approved baseline. Approving
+    /// a host clears any rogue flag it carried.
+    pub async fn set_approved(pool: &SqlitePool, host_ids: &[String], approved: bool) -> Result<()> {
+        let mut tx = pool.begin().await?;
+        let now = Utc::now();
+
+        for host_id in host_ids {
+            sqlx::query!(
+                r#"
+                UPDATE hosts
+                SET approved = ?, rogue = CASE WHEN ? THEN 0 ELSE rogue END, updated_at = ?
+                WHERE id = ?
+                "#,
+                approved,
+                approved,
+                now,
+                host_id
+            )
+            .execute(&mut *tx)
+            .await?;
+        }
+
+        tx.commit().await?;
+        QueryCache::invalidate();
+        Ok(())
+    }
+
+    /// Stars hosts onto (or off) the watchlist.
+    pub async fn set_watched(pool: &SqlitePool, host_ids: &[String], watched: bool) -> Result<()> {
+        let mut tx = pool.begin().await?;
+
+        for host_id in host_ids {
+            sqlx::query!("UPDATE hosts SET watched = ? WHERE id = ?", watched, host_id)
+                .execute(&mut *tx)
+                .await?;
+        }
+
+        tx.commit().await?;
+        QueryCache::invalidate();
+        Ok(())
+    }
+
+    pub async fn list_watched(pool: &SqlitePool) -> Result<Vec<Host>> {
+        let hosts = sqlx::query_as!(Host, "SELECT * FROM hosts WHERE watched = 1 ORDER BY ip")
+            .fetch_all(pool)
+            .await?;
+
+        Ok(hosts)
+    }
+
+    /// Raises a notification for a change to a watched host as the change
+    /// is stored, whether or not the monitor is running. Other hosts are
+    /// left alone.
+    pub async fn notify_if_watched(pool: &SqlitePool, host_id: &str, change: &str) -> Result<()> {
+        let ip = sqlx::query_scalar!("SELECT ip FROM hosts WHERE id = ? AND watched = 1", host_id)
+            .fetch_optional(pool)
+            .await?;
+
+        if let Some(ip) = ip {
+            NotificationOperations::create(
+                pool,
+                "medium",
+                "Watched host changed",
+                &format!("{}: {}", ip, change),
+                Some("host"),
+                Some(host_id),
+            ).await?;
+        }
+
+        Ok(())
+    }
+
+    pub async fn tags(pool: &SqlitePool, host_id: &str) -> Result<Vec<String>> {
+        let tags = sqlx::query_scalar!("SELECT tag FROM host_tags WHERE host_id = ? ORDER BY tag", host_id)
+            .fetch_all(pool)
+            .await?;
+
+        Ok(tags)
+    }
+
+    /// Applies one action to every host in `host_ids`, all or nothing.
+    /// Moving to another project needs that project's database file; see
+    /// `move_to_file`. Returns the number of hosts changed.
+    pub async fn bulk_action(pool: &SqlitePool, host_ids: &[String], action: &BulkHostAction) -> Result<u64> {
+        let mut tx = pool.begin().await?;
+        let now = Utc::now();
+        let mut changed = 0;
+
+        for host_id in host_ids {
+            let result = match action {
+                BulkHostAction::Tag { tag } => sqlx::query!(
+                    r#"
+                    INSERT OR IGNORE INTO host_tags (host_id, tag, created_at)
+                    SELECT id, ?, ? FROM hosts WHERE id = ?
+                    "#,
+                    tag,
+                    now,
+                    host_id
+                )
+                .execute(&mut *tx)
+                .await?,
+                BulkHostAction::Untag { tag } => sqlx::query!(
+                    "DELETE FROM host_tags WHERE host_id = ? AND tag = ?",
+                    host_id,
+                    tag
+                )
+                .execute(&mut *tx)
+                .await?,
+                BulkHostAction::SetCriticality { criticality } => sqlx::query!(
+                    "UPDATE hosts SET criticality = ?, updated_at = ? WHERE id = ?",
+                    criticality,
+                    now,
+                    host_id
+                )
+                .execute(&mut *tx)
+                .await?,
+                BulkHostAction::Delete => {
+                    // Events don't cascade with their host
+                    sqlx::query!("DELETE FROM events WHERE host_id = ?", host_id)
+                        .execute(&mut *tx)
+                        .await?;
+                    sqlx::query!("DELETE FROM hosts WHERE id = ?", host_id)
+                        .execute(&mut *tx)
+                        .await?
+                }
+                BulkHostAction::MoveToProject { .. } => {
+                    anyhow::bail!("Moving hosts between projects goes through HostOperations::move_to_file")
+                }
+            };
+            changed += result.rows_affected();
+        }
+
+        tx.commit().await?;
+        QueryCache::invalidate();
+        Ok(changed)
+    }
+
+    /// Moves hosts and everything recorded about them into the project
+    /// database at `path`, which must have an up-to-date schema. SQLite
+    /// doesn't make a transaction atomic across attached files in WAL
+    /// mode, so the copy is committed before anything is removed here: an
+    /// interrupted move leaves the hosts in both projects, never in
+    /// neither. Returns the number of hosts moved.
+    pub async fn move_to_file(pool: &SqlitePool, host_ids: &[String], path: &std::path::Path) -> Result<u64> {
+        let mut conn = pool.acquire().await?;
+        // ATTACH isn't allowed inside a transaction
+        sqlx::query("ATTACH DATABASE ? AS target")
+            .bind(path.to_string_lossy().into_owned())
+            .execute(&mut *conn)
+            .await?;
+
+        let moved = Self::move_to_attached(&mut conn, host_ids).await;
+
+        // The move's own outcome matters more than a failed DETACH, which
+        // only leaves the connection with the file still attached
+        if let Err(e) = sqlx::query("DETACH DATABASE target").execute(&mut *conn).await {
+            eprintln!("Failed to detach {}: {}", path.display(), e);
+        }
+        if moved.is_ok() {
+            QueryCache::invalidate();
+        }
+        moved
+    }
+
+    async fn move_to_attached(conn: &mut sqlx::SqliteConnection, host_ids: &[String]) -> Result<u64> {
+        use sqlx::Connection;
+
+        // The attached schema isn't known when the query macros check
+        // their SQL, so these statements are built at runtime. Both files
+        // run the same migrations, so the columns line up.
+        let mut tx = conn.begin().await?;
+        let mut moved = 0;
+
+        for host_id in host_ids {
+            let copied = sqlx::query("INSERT INTO target.hosts SELECT * FROM main.hosts WHERE id = ?")
+                .bind(host_id)
+                .execute(&mut *tx)
+                .await?
+                .rows_affected();
+            if copied == 0 {
+                continue;
+            }
+            moved += copied;
+
+            // Parents before the rows referring to them
+            for table in HOST_TABLES {
+                sqlx::query(&format!("INSERT INTO target.{0} SELECT * FROM main.{0} WHERE host_id = ?", table))
+                    .bind(host_id)
+                    .execute(&mut *tx)
+                    .await?;
+            }
+            sqlx::query(
+                r#"
+                INSERT INTO target.finding_verifications SELECT * FROM main.finding_verifications
+                WHERE vulnerability_id IN (SELECT id FROM main.vulnerabilities WHERE host_id = ?)
+                "#,
+            )
+            .bind(host_id)
+            .execute(&mut *tx)
+            .await?;
+        }
+
+        // Dependencies only make sense with both hosts in the project
+        sqlx::query(
+            r#"
+            INSERT OR IGNORE INTO target.service_dependencies SELECT * FROM main.service_dependencies
+            WHERE client_host_id IN (SELECT id FROM target.hosts)
+                AND server_host_id IN (SELECT id FROM target.hosts)
+            "#,
+        )
+        .execute(&mut *tx)
+        .await?;
+        tx.commit().await?;
+
+        // The rest goes with the hosts through their cascades
+        let mut tx = conn.begin().await?;
+        for host_id in host_ids {
+            sqlx::query("DELETE FROM main.events WHERE host_id = ?")
+                .bind(host_id)
+                .execute(&mut *tx)
+                .await?;
+            sqlx::query("DELETE FROM main.hosts WHERE id = ?")
+                .bind(host_id)
+                .execute(&mut *tx)
+                .await?;
+        }
+
+        tx.commit().await?;
+        Ok(moved)
+    }
+
+    /// Applies the same ownership fields to every host in `host_ids`.
+    pub async fn update_ownership(pool: &SqlitePool, host_ids: &[String], update: &HostOwnershipUpdate) -> Result<()> {
+        // (whether to set it, the new value or NULL)
+        let field = |value: &Option<String>| (
+            value.is_some(),
+            value.as_deref().map(str::trim).filter(|v| !v.is_empty()).map(str::to_string),
+        );
+        let (set_owner, owner) = field(&update.owner);
+        let (set_business_unit, business_unit) = field(&update.business_unit);
+        let (set_criticality, criticality) = field(&update.criticality);
+
+        let mut tx = pool.begin().await?;
+        let now = Utc::now();
+
+        for host_id in host_ids {
+            sqlx::query!(
+                r#"
+                UPDATE hosts
+                SET owner = CASE WHEN ? THEN ? ELSE owner END,
+                    business_unit = CASE WHEN ? THEN ? ELSE business_unit END,
+                    criticality = CASE WHEN ? THEN ? ELSE criticality END,
+                    updated_at = ?
+                WHERE id = ?
+                "#,
+                set_owner,
+                owner,
+                set_business_unit,
+                business_unit,
+                set_criticality,
+                criticality,
+                now,
+                host_id
+            )
+            .execute(&mut *tx)
+            .await?;
+        }
+
+        tx.commit().await?;
+        QueryCache::invalidate();
+        Ok(())
+    }
+
+    /// Flags a host as rogue when a baseline exists and neither the host nor
+    /// its MAC address is part of it. Returns true if the flag was newly set.
+    pub async fn flag_if_rogue(pool: &SqlitePool, host_id: &str) -> Result<bool> {
+        let result = sqlx::query!(
+            r#"
+            UPDATE hosts
+            SET rogue = 1, updated_at = ?
+            WHERE id = ?
+              AND approved = 0
+              AND rogue = 0
+              AND EXISTS (SELECT 1 FROM hosts WHERE approved = 1)
+              AND NOT EXISTS (
+                  SELECT 1 FROM hosts AS baseline
+                  WHERE baseline.approved = 1
+                    AND baseline.mac_address IS NOT NULL
+                    AND baseline.mac_address = hosts.mac_address
+              )
+            "#,
+            Utc::now(),
+            host_id
+        )
+        .execute(pool)
+        .await?;
+
+        let flagged = result.rows_affected() > 0;
+        if flagged {
+            QueryCache::invalidate();
+            let ip = sqlx::query_scalar!("SELECT ip FROM hosts WHERE id = ?", host_id)
+                .fetch_one(pool)
+                .await?;
+            NotificationOperations::create(
+                pool,
+                "high",
+                "Rogue device",
+                &format!("{} is not part of the approved baseline", ip),
+                Some("host"),
+                Some(host_id),
+            ).await?;
+        }
+
+        Ok(flagged)
+    }
+
+    pub async fn list_rogue(pool: &SqlitePool, filter: &HostFilter) -> Result<Vec<Host>> {
+        let hosts = sqlx::query_as!(
+            Host,
+            r#"
+            SELECT * FROM hosts
+            WHERE rogue = 1 AND (?1 IS NULL OR vlan_id = ?1)
+            ORDER BY updated_at DESC
+            "#,
+            filter.vlan_id
+        )
+        .fetch_all(pool)
+        .await?;
+
+        Ok(hosts)
+    }
+
+    pub async fn baseline_summary(pool: &SqlitePool) -> Result<BaselineSummary> {
+        if let Some(summary) = QueryCache::get("baseline_summary") {
+            return Ok(summary);
+        }
+
+        let summary = sqlx::query_as!(
+            BaselineSummary,
+            r#"
+            SELECT
+                COALESCE(SUM(approved), 0) as "approved!: i64",
+                COALESCE(SUM(rogue), 0) as "rogue!: i64",
+                COALESCE(SUM(CASE WHEN approved = 0 THEN 1 ELSE 0 END), 0) as "unapproved!: i64"
+            FROM hosts
+            "#
+        )
+        .fetch_one(pool)
+        .await?;
+
+        QueryCache::put("baseline_summary", summary.clone());
+        Ok(summary)
+    }
+
+    pub async fn update_dhcp_info(
+        pool: &SqlitePool,
+        host_id: &str,
+        mac_address: &str,
+        hostname: Option<&str>,
+        vendor_class: Option<&str>,
+    ) -> Result<()> {
+        sqlx::query!(
+            r#"
+            UPDATE hosts
+            SET mac_address = ?,
+                hostname = COALESCE(?, hostname),
+                vendor_class = COALESCE(?, vendor_class),
+                status = 'up',
+                updated_at = ?
+            WHERE id = ?
+            "#,
+            mac_address,
+            hostname,
+            vendor_class,
             Utc::now(),
             host_id
         )
-        .execute(pool)
+        .execute(pool)
+        .await?;
+
+        Ok(())
+    }
+
+    /// Fills in a hostname learned from DNS. A name the host already has
+    /// (e.g. from DHCP or nmap) is kept.
+    pub async fn update_hostname(pool: &SqlitePool, host_id: &str, hostname: &str) -> Result<()> {
+        sqlx::query!(
+            "UPDATE hosts SET hostname = COALESCE(hostname, ?), updated_at = ? WHERE id = ?",
+            hostname,
+            Utc::now(),
+            host_id
+        )
+        .execute(pool)
+        .await?;
+
+        Ok(())
+    }
+
+    /// Sets the vendor named by the host's MAC address prefix.
+    pub async fn update_vendor(pool: &SqlitePool, host_id: &str, vendor: &str) -> Result<()> {
+        sqlx::query!(
+            "UPDATE hosts SET vendor = ?, updated_at = ? WHERE id = ?",
+            vendor,
+            Utc::now(),
+            host_id
+        )
+        .execute(pool)
+        .await?;
+
+        Ok(())
+    }
+
+    pub async fn update_location(
+        pool: &SqlitePool,
+        host_id: &str,
+        country_code: Option<&str>,
+        city: Option<&str>,
+    ) -> Result<()> {
+        sqlx::query!(
+            "UPDATE hosts SET country_code = ?, city = ?, updated_at = ? WHERE id = ?",
+            country_code,
+            city,
+            Utc::now(),
+            host_id
+        )
+        .execute(pool)
+        .await?;
+
+        Ok(())
+    }
+
+    pub async fn update_risk_score(pool: &SqlitePool, host_id: &str, risk_score: f32) -> Result<()> {
+        sqlx::query!(
+            "UPDATE hosts SET risk_score = ? WHERE id = ?",
+            risk_score,
+            host_id
+        )
+        .execute(pool)
+        .await?;
+
+        Ok(())
+    }
+
+    pub async fn list_top_risks(pool: &SqlitePool, limit: i32) -> Result<Vec<Host>> {
+        let hosts = sqlx::query_as!(
+            Host,
+            "SELECT * FROM hosts WHERE risk_score > 0 ORDER BY risk_score DESC LIMIT ?",
+            limit
+        )
+        .fetch_all(pool)
+        .await?;
+
+        Ok(hosts)
+    }
+
+    pub async fn update_device_type(
+        pool: &SqlitePool,
+        host_id: &str,
+        device_type: &str,
+        device_product: &str,
+    ) -> Result<()> {
+        sqlx::query!(
+            "UPDATE hosts SET device_type = ?, device_product = ?, updated_at = ? WHERE id = ?",
+            device_type,
+            device_product,
+            Utc::now(),
+            host_id
+        )
+        .execute(pool)
+        .await?;
+
+        Ok(())
+    }
+
+    /// Hosts classified as embedded devices, grouped by type.
+    pub async fn list_iot(pool: &SqlitePool, filter: &HostFilter) -> Result<Vec<Host>> {
+        let hosts = sqlx::query_as!(
+            Host,
+            r#"
+            SELECT * FROM hosts
+            WHERE device_type IS NOT NULL AND (?1 IS NULL OR vlan_id = ?1)
+            ORDER BY device_type, ip
+            "#,
+            filter.vlan_id
+        )
+        .fetch_all(pool)
+        .await?;
+
+        Ok(hosts)
+    }
+
+    pub async fn list_all(pool: &SqlitePool) -> Result<Vec<Host>> {
+        // Unscored hosts sort last
+        let hosts = sqlx::query_as!(Host, "SELECT * FROM hosts ORDER BY risk_score DESC, created_at DESC")
+            .fetch_all(pool)
+            .await?;
+        
+        Ok(hosts)
+    }
+
+    pub async fn list(pool: &SqlitePool, filter: &HostFilter) -> Result<Vec<Host>> {
+        let hosts = sqlx::query_as!(
+            Host,
+            r#"
+            SELECT * FROM hosts
+            WHERE ?1 IS NULL OR vlan_id = ?1
+            ORDER BY risk_score DESC, created_at DESC
+            "#,
+            filter.vlan_id
+        )
+        .fetch_all(pool)
+        .await?;
+
+        Ok(hosts)
+    }
+
+    pub async fn get_with_ports(pool: &SqlitePool, host_id: &str) -> Result<(Host, Vec<Port>)> {
+        let host = sqlx::query_as!(Host, "SELECT * FROM hosts WHERE id = ?", host_id)
+            .fetch_one(pool)
+            .await?;
+
+        let ports = PortOperations::find_by_host(pool, host_id).await?;
+        
+        Ok((host, ports))
+    }
+}
+
+pub struct HostIpHistoryOperations;
+
+impl HostIpHistoryOperations {
+    pub async fn record(pool: &SqlitePool, host_id: &str, ip: IpAddr) -> Result<()> {
+        let now = Utc::now();
+
+        sqlx::query!(
+            r#"
+            INSERT INTO host_ip_history (host_id, ip, first_seen, last_seen)
+            VALUES (?, ?, ?, ?)
+            ON CONFLICT (host_id, ip) DO UPDATE SET last_seen = excluded.last_seen
+            "#,
+            host_id,
+            ip.to_string(),
+            now,
+            now
+        )
+        .execute(pool)
+        .await?;
+
+        Ok(())
+    }
+
+    pub async fn find_by_host(pool: &SqlitePool, host_id: &str) -> Result<Vec<HostIpHistory>> {
+        let history = sqlx::query_as!(
+            HostIpHistory,
+            "SELECT * FROM host_ip_history WHERE host_id = ? ORDER BY last_seen DESC",
+            host_id
+        )
+        .fetch_all(pool)
+        .await?;
+
+        Ok(history)
+    }
+}
+
+/// How far a fact can be relied on, 0 to 100, by what reported it. Active
+/// probes that got an answer rank highest; what the sniffer inferred from
+/// traffic and what was imported from other tools rank lower.
+const SOURCE_CONFIDENCE: &[(&str, f32)] = &[
+    ("nmap", 100.0),
+    ("udp", 100.0),
+    ("ics", 100.0),
+    ("check", 100.0),
+    ("manual", 100.0),
+    // Stored nmap output parsed again
+    ("reparse", 100.0),
+    ("nuclei", 90.0),
+    // A SYN-ACK, without a service exchange
+    ("masscan", 90.0),
+    ("nessus", 70.0),
+    ("import", 70.0),
+    ("sniffer", 60.0),
+    ("pcap", 60.0),
+    ("shodan", 50.0),
+];
+/// For sources the table doesn't list
+const DEFAULT_CONFIDENCE: f32 = 50.0;
+
+pub fn source_confidence(source: &str) -> f32 {
+    SOURCE_CONFIDENCE.iter()
+        .find(|(name, _)| *name == source)
+        .map_or(DEFAULT_CONFIDENCE, |(_, confidence)| *confidence)
+}
+
+pub struct PortOperations;
+
+impl PortOperations {
+    /// Stores one observation of a port by `source`. Its first sighting
+    /// carries over from earlier records, and they're all marked seen now.
+    pub async fn create(
+        pool: &SqlitePool,
+        host_id: &str,
+        number: u16,
+        protocol: &str,
+        state: &str,
+        scan_id: Option<&str>,
+        source: &str,
+    ) -> Result<Port> {
+        let id = Uuid::new_v4().to_string();
+        let number = number as i32;
+        let now = Utc::now();
+        let confidence = source_confidence(source);
+        let mut tx = pool.begin().await?;
+
+        let first_seen = sqlx::query_scalar!(
+            r#"
+            SELECT MIN(first_seen) AS "first_seen: DateTime<Utc>" FROM ports
+            WHERE host_id = ? AND number = ? AND protocol = ?
+            "#,
+            host_id,
+            number,
+            protocol
+        )
+        .fetch_one(&mut *tx)
+        .await?
+        .unwrap_or(now);
+
+        let previous_state = sqlx::query_scalar!(
+            "SELECT state FROM ports WHERE host_id = ? AND number = ? AND protocol = ? ORDER BY created_at DESC LIMIT 1",
+            host_id,
+            number,
+            protocol
+        )
+        .fetch_optional(&mut *tx)
+        .await?;
+
+        sqlx::query!(
+            "UPDATE ports SET last_seen = ? WHERE host_id = ? AND number = ? AND protocol = ?",
+            now,
+            host_id,
+            number,
+            protocol
+        )
+        .execute(&mut *tx)
+        .await?;
+
+        let port = sqlx::query_as!(
+            Port,
+            r#"
+            INSERT INTO ports (id, host_id, number, protocol, state, created_at, scan_id, first_seen, last_seen,
+                source, confidence)
+            VALUES (?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?)
+            RETURNING *
+            "#,
+            id,
+            host_id,
+            number,
+            protocol,
+            state,
+            now,
+            scan_id,
+            first_seen,
+            now,
+            source,
+            confidence
+        )
+        .fetch_one(&mut *tx)
+        .await?;
+
+        tx.commit().await?;
+        QueryCache::invalidate();
+
+        // A port first seen closed or filtered is no change
+        let changed = match previous_state.as_deref() {
+            Some(previous) => previous != state,
+            None => state == "open",
+        };
+        if changed {
+            HostOperations::notify_if_watched(pool, host_id, &format!("port {}/{} {}", number, protocol, state)).await?;
+        }
+        Ok(port)
+    }
+
+    /// Marks a port seen now without storing a new record, for sightings
+    /// that add nothing else, such as sniffed traffic.
+    pub async fn touch(pool: &SqlitePool, host_id: &str, number: u16, protocol: &str) -> Result<()> {
+        sqlx::query!(
+            "UPDATE ports SET last_seen = ? WHERE host_id = ? AND number = ? AND protocol = ?",
+            Utc::now(),
+            host_id,
+            number as i32,
+            protocol
+        )
+        .execute(pool)
+        .await?;
+
+        Ok(())
+    }
+
+    /// The latest record of each port not seen since `cutoff`, stalest
+    /// first.
+    pub async fn find_stale(pool: &SqlitePool, cutoff: DateTime<Utc>, host_id: Option<&str>) -> Result<Vec<Port>> {
+        let ports = sqlx::query_as!(
+            Port,
+            r#"
+            SELECT * FROM ports
+            WHERE last_seen < ?1
+                AND (?2 IS NULL OR host_id = ?2)
+                AND created_at = (
+                    SELECT MAX(other.created_at) FROM ports other
+                    WHERE other.host_id = ports.host_id
+                        AND other.number = ports.number
+                        AND other.protocol = ports.protocol
+                )
+            ORDER BY last_seen, host_id, number
+            "#,
+            cutoff,
+            host_id
+        )
+        .fetch_all(pool)
+        .await?;
+
+        Ok(ports)
+    }
+
+    pub async fn update_service_info(
+        pool: &SqlitePool,
+        port_id: &str,
+        service: Option<&str>,
+        version: Option<&str>,
+        banner: Option<&str>,
+    ) -> Result<()> {
+        sqlx::query!(
+            "UPDATE ports SET service = ?, version = ?, banner = ? WHERE id = ?",
+            service,
+            version,
+            banner,
+            port_id
+        )
+        .execute(pool)
+        .await?;
+        
+        Ok(())
+    }
+
+    pub async fn find_by_host(pool: &SqlitePool, host_id: &str) -> Result<Vec<Port>> {
+        let ports = sqlx::query_as!(
+            Port,
+            "SELECT * FROM ports WHERE host_id = ? ORDER BY number",
+            host_id
+        )
+        .fetch_all(pool)
+        .await?;
+        
+        Ok(ports)
+    }
+
+    pub async fn find(pool: &SqlitePool, host_id: &str, number: u16, protocol: &str) -> Result<Option<Port>> {
+        let port = sqlx::query_as!(
+            Port,
+            "SELECT * FROM ports WHERE host_id = ? AND number = ? AND protocol = ? ORDER BY created_at DESC LIMIT 1",
+            host_id,
+            number as i32,
+            protocol
+        )
+        .fetch_optional(pool)
+        .await?;
+
+        Ok(port)
+    }
+
+    /// The host's ports whose latest record is open, one record each.
+    /// Earlier records of a port that has since closed don't count.
+    pub async fn find_open_ports(pool: &SqlitePool, host_id: &str) -> Result<Vec<Port>> {
+        let ports = sqlx::query_as!(
+            Port,
+            r#"
+            SELECT * FROM ports
+            WHERE host_id = ?1
+                AND state = 'open'
+                AND created_at = (
+                    SELECT MAX(other.created_at) FROM ports other
+                    WHERE other.host_id = ports.host_id
+                        AND other.number = ports.number
+                        AND other.protocol = ports.protocol
+                )
+            ORDER BY number, protocol
+            "#,
+            host_id
+        )
+        .fetch_all(pool)
+        .await?;
+        
+        Ok(ports)
+    }
+}
+
+pub struct ScanOperations;
+
+impl ScanOperations {
+    /// Creates the record for a scan. The coordinator's scan id is reused
+    /// so callers can look the record up by the id `start_scan` returned.
+    pub async fn create(
+        pool: &SqlitePool,
+        scan_id: Uuid,
+        name: &str,
+        targets: &[IpAddr],
+        scan_type: &str,
+        annotations: &ScanAnnotations,
+    ) -> Result<Scan> {
+        let id = scan_id.to_string();
+        let targets_json = serde_json::to_string(targets)?;
+        
+        let scan = sqlx::query_as!(
+            Scan,
+            r#"
+            INSERT INTO scans (id, name, targets, scan_type, status, progress, start_time, created_at,
+                started_by, reason, ticket_ref)
+            VALUES (?, ?, ?, ?, 'queued', 0.0, ?, ?, ?, ?, ?)
+            RETURNING id, name, targets, scan_type, status, progress, start_time, end_time,
+                created_at, output_sha256, result_sha256, started_by, reason, ticket_ref
+            "#,
+            id,
+            name,
+            targets_json,
+            scan_type,
+            Utc::now(),
+            Utc::now(),
+            annotations.started_by,
+            annotations.reason,
+            annotations.ticket_ref
+        )
+        .fetch_one(pool)
+        .await?;
+
+        let mut message = format!("{} queued", scan.name);
+        if let Some(user) = &scan.started_by {
+            message.push_str(&format!(" by {}", user));
+        }
+        if let Some(ticket) = &scan.ticket_ref {
+            message.push_str(&format!(" ({})", ticket));
+        }
+        EventOperations::record(pool, EVENT_SCAN, "queued", None, Some(&scan.id), &message)
+            .await?;
+        
+        Ok(scan)
+    }
+
+    pub async fn find_by_id(pool: &SqlitePool, scan_id: &str) -> Result<Option<Scan>> {
+        let scan = sqlx::query_as!(
+            Scan,
+            r#"
+            SELECT id, name, targets, scan_type, status, progress, start_time, end_time,
+                created_at, output_sha256, result_sha256, started_by, reason, ticket_ref
+            FROM scans WHERE id = ?
+            "#,
+            scan_id
+        )
+        .fetch_optional(pool)
+        .await?;
+
+        Ok(scan)
+    }
+
+    pub async fn update_progress(pool: &SqlitePool, scan_id: &str, progress: f32) -> Result<()> {
+        sqlx::query!(
+            "UPDATE scans SET progress = ? WHERE id = ?",
+            progress,
+            scan_id
+        )
+        .execute(pool)
+        .await?;
+        
+        Ok(())
+    }
+
+    pub async fn update_status(pool: &SqlitePool, scan_id: &str, status: &str) -> Result<()> {
+        let end_time = if status == "completed" || status == "failed" {
+            Some(Utc::now())
+        } else {
+            None
+        };
+
+        let name = sqlx::query_scalar!(
+            "UPDATE scans SET status = ?, end_time = ? WHERE id = ? RETURNING name",
+            status,
+            end_time,
+            scan_id
+        )
+        .fetch_optional(pool)
+        .await?;
+
+        if let Some(name) = name {
+            EventOperations::record(pool, EVENT_SCAN, status, None, Some(scan_id), &format!("{} {}", name, status))
+                .await?;
+        }
+        
+        Ok(())
+    }
+
+    /// Stores the raw tool output and serialized result of a finished scan
+    /// together with their SHA-256 digests. The output itself goes to
+    /// `raw_outputs`, compressed.
+    pub async fn record_evidence(
+        pool: &SqlitePool,
+        scan_id: &str,
+        format: &str,
+        raw_output: &[u8],
+        result_json: &str,
+    ) -> Result<()> {
+        let output_sha256 = sha256_hex(raw_output);
+        let result_sha256 = sha256_hex(result_json.as_bytes());
+
+        RawOutputOperations::create(pool, scan_id, format, raw_output).await?;
+
+        sqlx::query!(
+            r#"
+            UPDATE scans SET result_json = ?, output_sha256 = ?, result_sha256 = ?
+            WHERE id = ?
+            "#,
+            result_json,
+            output_sha256,
+            result_sha256,
+            scan_id
+        )
+        .execute(pool)
+        .await?;
+
+        Ok(())
+    }
+
+    /// Re-hashes the stored evidence and compares it with the digests
+    /// recorded at collection time.
+    pub async fn verify_integrity(pool: &SqlitePool, scan_id: &str) -> Result<ScanIntegrity> {
+        let row = sqlx::query!(
+            "SELECT result_json, output_sha256, result_sha256 FROM scans WHERE id = ?",
+            scan_id
+        )
+        .fetch_optional(pool)
+        .await?
+        .ok_or_else(|| anyhow::anyhow!("Scan not found: {}", scan_id))?;
+
+        let raw_output = RawOutputOperations::find_by_scan(pool, scan_id).await?
+            .first()
+            .map(RawOutputOperations::decompress)
+            .transpose()?;
+
+        let output_valid = match (&raw_output, &row.output_sha256) {
+            (Some(output), Some(hash)) => &sha256_hex(output) == hash,
+            _ => false,
+        };
+        let result_valid = match (&row.result_json, &row.result_sha256) {
+            (Some(result), Some(hash)) => &sha256_hex(result.as_bytes()) == hash,
+            _ => false,
+        };
+
+        Ok(ScanIntegrity {
+            scan_id: scan_id.to_string(),
+            output_sha256: row.output_sha256,
+            result_sha256: row.result_sha256,
+            output_valid,
+            result_valid,
+            verified: output_valid && result_valid,
+        })
+    }
+
+    /// Serialized results of finished scans matching `filter`, evaluated
+    /// against the stored result JSON.
+    pub async fn find_results(pool: &SqlitePool, filter: &ScanResultFilter) -> Result<Vec<String>> {
+        let sort_by = filter.sort_by.as_deref().unwrap_or("time");
+        let limit = filter.limit.unwrap_or(1000);
+
+        let rows = sqlx::query!(
+            r#"
+            SELECT result_json AS "result_json!" FROM scans
+            WHERE result_json IS NOT NULL
+                AND (?1 IS NULL OR json_extract(result_json, '$.target_ip') = ?1)
+                AND (?2 IS NULL OR id = ?2)
+                AND (?3 IS NULL OR start_time >= ?3)
+                AND (?4 IS NULL OR start_time <= ?4)
+                AND (?5 IS NULL OR json_array_length(result_json, '$.open_ports') >= ?5)
+                AND (?6 IS NULL OR (json_array_length(result_json, '$.vulnerabilities') > 0) = ?6)
+            ORDER BY
+                CASE WHEN ?7 = 'open_ports' AND ?8 THEN json_array_length(result_json, '$.open_ports') END DESC,
+                CASE WHEN ?7 = 'open_ports' AND NOT ?8 THEN json_array_length(result_json, '$.open_ports') END ASC,
+                CASE WHEN ?7 = 'target_ip' AND ?8 THEN json_extract(result_json, '$.target_ip') END DESC,
+                CASE WHEN ?7 = 'target_ip' AND NOT ?8 THEN json_extract(result_json, '$.target_ip') END ASC,
+                CASE WHEN ?8 THEN start_time END DESC,
+                CASE WHEN NOT ?8 THEN start_time END ASC
+            LIMIT ?9
+            "#,
+            filter.target_ip,
+            filter.scan_id,
+            filter.started_after,
+            filter.started_before,
+            filter.min_open_ports,
+            filter.has_vulnerabilities,
+            sort_by,
+            filter.descending,
+            limit
+        )
+        .fetch_all(pool)
+        .await?;
+
+        Ok(rows.into_iter().map(|row| row.result_json).collect())
+    }
+
+    /// Deletes a scan record; its raw outputs and stored result go with it.
+    /// Hosts are kept. With `rollback_ports`, ports that no other scan or
+    /// source observed are removed too. Returns the number of ports removed.
+    pub async fn delete(pool: &SqlitePool, scan_id: &str, rollback_ports: bool) -> Result<u64> {
+        let mut tx = pool.begin().await?;
+
+        let mut removed_ports = 0;
+        if rollback_ports {
+            removed_ports = sqlx::query!(
+                r#"
+                DELETE FROM ports
+                WHERE scan_id = ?1 AND NOT EXISTS (
+                    SELECT 1 FROM ports other
+                    WHERE other.host_id = ports.host_id
+                        AND other.number = ports.number
+                        AND other.protocol = ports.protocol
+                        AND (other.scan_id IS NULL OR other.scan_id != ?1)
+                )
+                "#,
+                scan_id
+            )
+            .execute(&mut *tx)
+            .await?
+            .rows_affected();
+        }
+
+        sqlx::query!("DELETE FROM raw_outputs WHERE scan_id = ?", scan_id)
+            .execute(&mut *tx)
+            .await?;
+
+        let deleted = sqlx::query!("DELETE FROM scans WHERE id = ?", scan_id)
+            .execute(&mut *tx)
+            .await?
+            .rows_affected();
+        if deleted == 0 {
+            return Err(anyhow::anyhow!("Scan not found: {}", scan_id));
+        }
+
+        tx.commit().await?;
+        if removed_ports > 0 {
+            QueryCache::invalidate();
+        }
+        Ok(removed_ports)
+    }
+
+    pub async fn list_recent(pool: &SqlitePool, limit: i32) -> Result<Vec<Scan>> {
+        let scans = sqlx::query_as!(
+            Scan,
+            r#"
+            SELECT id, name, targets, scan_type, status, progress, start_time, end_time,
+                created_at, output_sha256, result_sha256, started_by, reason, ticket_ref
+            FROM scans ORDER BY created_at DESC LIMIT ?
+            "#,
+            limit
+        )
+        .fetch_all(pool)
+        .await?;
+        
+        Ok(scans)
+    }
+}
+
+pub struct VulnerabilityOperations;
+
+impl VulnerabilityOperations {
+    /// Stores a finding. `severity` is on the app's scale; the source's
+    /// own rating is kept in `original_severity` when it had one.
+    #[allow(clippy::too_many_arguments)]
+    pub async fn create(
+        pool: &SqlitePool,
+        host_id: &str,
+        port_id: Option<&str>,
+        name: &str,
+        severity: &str,
+        description: &str,
+        cvss_score: Option<f32>,
+        cvss_vector: Option<&str>,
+        source: &str,
+        original_severity: Option<&str>,
+        check_ref: Option<&str>,
+    ) -> Result<Vulnerability> {
+        let id = Uuid::new_v4().to_string();
+        let confidence = source_confidence(source);
+        
+        let vuln = sqlx::query_as!(
+            Vulnerability,
+            r#"
+            INSERT INTO vulnerabilities (id, host_id, port_id, name, severity, description, cvss_score, cvss_vector,
+                discovered_at, source, original_severity, check_ref, confidence)
+            VALUES (?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?)
+            RETURNING *
+            "#,
+            id,
+            host_id,
+            port_id,
+            name,
+            severity,
+            description,
+            cvss_score,
+            cvss_vector,
+            Utc::now(),
+            source,
+            original_severity,
+            check_ref,
+            confidence
+        )
+        .fetch_one(pool)
+        .await?;
+        QueryCache::invalidate();
+
+        EventOperations::record(
+            pool,
+            EVENT_FINDING,
+            "created",
+            Some(host_id),
+            Some(&vuln.id),
+            &format!("{} finding: {}", vuln.severity, vuln.name),
+        ).await?;
+
+        HostOperations::notify_if_watched(pool, host_id, &format!("new {} finding: {}", vuln.severity, vuln.name)).await?;
+
+        if vuln.severity == "critical" {
+            NotificationOperations::create(
+                pool,
+                "critical",
+                "Critical finding",
+                &vuln.name,
+                Some("vulnerability"),
+                Some(&vuln.id),
+            ).await?;
+        }
+        
+        Ok(vuln)
+    }
+
+    pub async fn find_by_host(pool: &SqlitePool, host_id: &str) -> Result<Vec<Vulnerability>> {
+        let vulns = sqlx::query_as!(
+            Vulnerability,
+            "SELECT * FROM vulnerabilities WHERE host_id = ? ORDER BY discovered_at DESC",
+            host_id
+        )
+        .fetch_all(pool)
+        .await?;
+        
+        Ok(vulns)
+    }
+
+    pub async fn find_by_id(pool: &SqlitePool, vulnerability_id: &str) -> Result<Option<Vulnerability>> {
+        let vuln = sqlx::query_as!(
+            Vulnerability,
+            "SELECT * FROM vulnerabilities WHERE id = ?",
+            vulnerability_id
+        )
+        .fetch_optional(pool)
+        .await?;
+
+        Ok(vuln)
+    }
+
+    /// Applies an analyst's edit. The rating is now theirs, so the source
+    /// becomes `manual` with its confidence.
+    #[allow(clippy::too_many_arguments)]
+    pub async fn update(
+        pool: &SqlitePool,
+        vulnerability_id: &str,
+        port_id: Option<&str>,
+        name: &str,
+        severity: &str,
+        description: &str,
+        cvss_score: Option<f32>,
+        cvss_vector: Option<&str>,
+        original_severity: Option<&str>,
+    ) -> Result<Vulnerability> {
+        let manual_confidence = source_confidence("manual");
+        let vuln = sqlx::query_as!(
+            Vulnerability,
+            r#"
+            UPDATE vulnerabilities
+            SET port_id = ?, name = ?, severity = ?, description = ?, cvss_score = ?, cvss_vector = ?,
+                source = 'manual', original_severity = ?, confidence = ?
+            WHERE id = ?
+            RETURNING *
+            "#,
+            port_id,
+            name,
+            severity,
+            description,
+            cvss_score,
+            cvss_vector,
+            original_severity,
+            manual_confidence,
+            vulnerability_id
+        )
+        .fetch_optional(pool)
+        .await?
+        .ok_or_else(|| anyhow::anyhow!("Vulnerability not found: {}", vulnerability_id))?;
+
+        QueryCache::invalidate();
+        Ok(vuln)
+    }
+
+    /// Records threat-intelligence flags used in host risk scoring.
+    pub async fn set_threat_intel(
+        pool: &SqlitePool,
+        vulnerability_id: &str,
+        kev: bool,
+        epss: Option<f32>,
+    ) -> Result<Vulnerability> {
+        let vuln = sqlx::query_as!(
+            Vulnerability,
+            "UPDATE vulnerabilities SET kev = ?, epss = ? WHERE id = ? RETURNING *",
+            kev,
+            epss,
+            vulnerability_id
+        )
+        .fetch_optional(pool)
+        .await?
+        .ok_or_else(|| anyhow::anyhow!("Vulnerability not found: {}", vulnerability_id))?;
+
+        Ok(vuln)
+    }
+
+    pub async fn set_status(pool: &SqlitePool, vulnerability_id: &str, status: &str) -> Result<Vulnerability> {
+        let vuln = sqlx::query_as!(
+            Vulnerability,
+            "UPDATE vulnerabilities SET status = ? WHERE id = ? RETURNING *",
+            status,
+            vulnerability_id
+        )
+        .fetch_optional(pool)
+        .await?
+        .ok_or_else(|| anyhow::anyhow!("Vulnerability not found: {}", vulnerability_id))?;
+
+        QueryCache::invalidate();
+        Ok(vuln)
+    }
+
+    pub async fn delete(pool: &SqlitePool, vulnerability_id: &str) -> Result<()> {
+        sqlx::query!("DELETE FROM vulnerabilities WHERE id = ?", vulnerability_id)
+            .execute(pool)
+            .await?;
+
+        QueryCache::invalidate();
+        Ok(())
+    }
+
+    pub async fn find_high_severity(pool: &SqlitePool) -> Result<Vec<Vulnerability>> {
+        let vulns = sqlx::query_as!(
+            Vulnerability,
+            "SELECT * FROM vulnerabilities WHERE severity IN ('high', 'critical') ORDER BY discovered_at DESC"
+        )
+        .fetch_all(pool)
+        .await?;
+        
+        Ok(vulns)
+    }
+}
+
+pub const FINDING_OPEN: &str = "open";
+pub const FINDING_FIXED: &str = "fixed";
+pub const FINDING_STILL_PRESENT: &str = "still_present";
+
+pub struct FindingVerificationOperations;
+
+impl FindingVerificationOperations {
+    /// Logs a verification and sets the finding's status to its outcome.
+    pub async fn record(
+        pool: &SqlitePool,
+        vulnerability_id: &str,
+        outcome: &str,
+        evidence: &str,
+    ) -> Result<FindingVerification> {
+        let id = Uuid::new_v4().to_string();
+        let evidence_sha256 = sha256_hex(evidence.as_bytes());
+
+        let verification = sqlx::query_as!(
+            FindingVerification,
+            r#"
+            INSERT INTO finding_verifications (id, vulnerability_id, outcome, evidence, evidence_sha256, verified_at)
+            VALUES (?, ?, ?, ?, ?, ?)
+            RETURNING *
+            "#,
+            id,
+            vulnerability_id,
+            outcome,
+            evidence,
+            evidence_sha256,
+            Utc::now()
+        )
+        .fetch_one(pool)
+        .await?;
+
+        VulnerabilityOperations::set_status(pool, vulnerability_id, outcome).await?;
+        Ok(verification)
+    }
+
+    pub async fn find_by_vulnerability(pool: &SqlitePool, vulnerability_id: &str) -> Result<Vec<FindingVerification>> {
+        let verifications = sqlx::query_as!(
+            FindingVerification,
+            "SELECT * FROM finding_verifications WHERE vulnerability_id = ? ORDER BY verified_at DESC",
+            vulnerability_id
+        )
+        .fetch_all(pool)
+        .await?;
+
+        Ok(verifications)
+    }
+}
+
+/// Services listed per subnet in `DashboardOperations::subnet_summary`
+const SUBNET_TOP_SERVICES: usize = 5;
+
+pub struct DashboardOperations;
+
+impl DashboardOperations {
+    /// Host, open port and finding totals for the dashboard.
+    pub async fn summary(pool: &SqlitePool) -> Result<DashboardSummary> {
+        if let Some(summary) = QueryCache::get("dashboard_summary") {
+            return Ok(summary);
+        }
+
+        let summary = sqlx::query_as!(
+            DashboardSummary,
+            r#"
+            SELECT
+                (SELECT COUNT(*) FROM hosts) as "hosts!: i64",
+                (SELECT COUNT(*) FROM ports WHERE state = 'open') as "open_ports!: i64",
+                (SELECT COUNT(*) FROM vulnerabilities WHERE severity = 'critical') as "critical!: i64",
+                (SELECT COUNT(*) FROM vulnerabilities WHERE severity = 'high') as "high!: i64",
+                (SELECT COUNT(*) FROM vulnerabilities WHERE severity = 'medium') as "medium!: i64",
+                (SELECT COUNT(*) FROM vulnerabilities WHERE severity = 'low') as "low!: i64",
+                (SELECT COUNT(*) FROM vulnerabilities WHERE severity = 'info') as "info!: i64"
+            "#
+        )
+        .fetch_one(pool)
+        .await?;
+
+        QueryCache::put("dashboard_summary", summary.clone());
+        Ok(summary)
+    }
+
+    /// Groups hosts into IPv4 subnets of `prefix_len` bits (IPv6 hosts
+    /// into /64s) with their live hosts, open services and finding
+    /// totals, ordered by address.
+    pub async fn subnet_summary(pool: &SqlitePool, prefix_len: u8, filter: &HostFilter) -> Result<Vec<SubnetSummary>> {
+        // A host is live when its latest liveness sample says so; DHCP
+        // sightings set the status without one
+        let hosts = sqlx::query!(
+            r#"
+            SELECT id, ip, COALESCE(
+                (SELECT h.status FROM host_status_history h
+                 WHERE h.host_id = hosts.id
+                 ORDER BY h.recorded_at DESC LIMIT 1),
+                status
+            ) AS "status!: String"
+            FROM hosts
+            WHERE ?1 IS NULL OR vlan_id = ?1
+            "#,
+            filter.vlan_id
+        )
+        .fetch_all(pool)
+        .await?;
+        // Each port's latest record only, so rescans aren't counted again
+        let ports = sqlx::query!(
+            r#"
+            SELECT host_id, number, protocol, service FROM ports
+            WHERE state = 'open'
+                AND created_at = (
+                    SELECT MAX(other.created_at) FROM ports other
+                    WHERE other.host_id = ports.host_id
+                        AND other.number = ports.number
+                        AND other.protocol = ports.protocol
+                )
+            "#
+        )
+        .fetch_all(pool)
+        .await?;
+        let vulnerabilities = sqlx::query!("SELECT host_id, severity FROM vulnerabilities")
+            .fetch_all(pool)
+            .await?;
+
+        // Keyed by (is IPv6, network bits) so IPv4 subnets sort first
+        let mut subnet_of_host: HashMap<String, (bool, u128)> = HashMap::new();
+        let mut subnets: HashMap<(bool, u128), (SubnetSummary, HashMap<String, i64>)> = HashMap::new();
+        for host in hosts {
+            let Ok(ip) = host.ip.parse::<IpAddr>() else {
+                continue;
+            };
+            let network = NetworkUtils::subnet_of(ip, prefix_len);
+            let key = match network.first_address() {
+                IpAddr::V4(v4) => (false, u32::from(v4) as u128),
+                IpAddr::V6(v6) => (true, u128::from(v6)),
+            };
+
+            let (summary, _) = subnets.entry(key).or_insert_with(|| (SubnetSummary {
+                subnet: format!("{}/{}", network.first_address(), network.network_length()),
+                hosts: 0,
+                live_hosts: 0,
+                top_services: Vec::new(),
+                critical: 0,
+                high: 0,
+                medium: 0,
+                low: 0,
+                info: 0,
+            }, HashMap::new()));
+            summary.hosts += 1;
+            if host.status == "up" {
+                summary.live_hosts += 1;
+            }
+            subnet_of_host.insert(host.id, key);
+        }
+
+        for port in ports {
+            let Some((_, services)) = subnet_of_host.get(&port.host_id).and_then(|key| subnets.get_mut(key)) else {
+                continue;
+            };
+            let service = port.service.unwrap_or_else(|| format!("{}/{}", port.number, port.protocol));
+            *services.entry(service).or_default() += 1;
+        }
+
+        for vulnerability in vulnerabilities {
+            let Some((summary, _)) = subnet_of_host.get(&vulnerability.host_id).and_then(|key| subnets.get_mut(key)) else {
+                continue;
+            };
+            match vulnerability.severity.as_str() {
+                "critical" => summary.critical += 1,
+                "high" => summary.high += 1,
+                "medium" => summary.medium += 1,
+                "low" => summary.low += 1,
+                _ => summary.info += 1,
+            }
+        }
+
+        let mut subnets: Vec<_> = subnets.into_iter().collect();
+        subnets.sort_by_key(|(key, _)| *key);
+
+        Ok(subnets.into_iter()
+            .map(|(_, (mut summary, services))| {
+                let mut services: Vec<ServiceCount> = services.into_iter()
+                    .map(|(service, count)| ServiceCount { service, count })
+                    .collect();
+                services.sort_by(|a, b| b.count.cmp(&a.count).then_with(|| a.service.cmp(&b.service)));
+                services.truncate(SUBNET_TOP_SERVICES);
+                summary.top_services = services;
+                summary
+            })
+            .collect())
+    }
+}
+
+pub struct ProjectOperations;
+
+impl ProjectOperations {
+    pub async fn create(pool: &SqlitePool, name: &str, description: Option<&str>) -> Result<Project> {
+        let id = Uuid::new_v4().to_string();
+        let now = Utc::now();
+        
+        let project = sqlx::query_as!(
+            Project,
+            r#"
+            INSERT INTO projects (id, name, description, created_at, updated_at)
+            VALUES (?, ?, ?, ?, ?)
+            RETURNING *
+            "#,
+            id,
+            name,
+            description,
+            now,
+            now
+        )
+        .fetch_one(pool)
+        .await?;
+        
+        Ok(project)
+    }
+
+    pub async fn list_all(pool: &SqlitePool) -> Result<Vec<Project>> {
+        let projects = sqlx::query_as!(
+            Project,
+            "SELECT * FROM projects ORDER BY updated_at DESC"
+        )
+        .fetch_all(pool)
+        .await?;
+        
+        Ok(projects)
+    }
+
+    pub async fn find_by_id(pool: &SqlitePool, project_id: &str) -> Result<Option<Project>> {
+        let project = sqlx::query_as!(
+            Project,
+            "SELECT * FROM projects WHERE id = ?",
+            project_id
+        )
+        .fetch_optional(pool)
+        .await?;
+        
+        Ok(project)
+    }
+
+    pub async fn update_engagement(
+        pool: &SqlitePool,
+        project_id: &str,
+        details: &EngagementDetails,
+    ) -> Result<()> {
+        if let (Some(start), Some(end)) = (details.start_date, details.end_date) {
+            if end < start {
+                return Err(anyhow::anyhow!("Engagement end date is before its start date"));
+            }
+        }
+
+        sqlx::query!(
+            r#"
+            UPDATE projects SET client_name = ?, scope_statement = ?, start_date = ?, end_date = ?,
+                rules_of_engagement = ?, updated_at = ?
+            WHERE id = ?
+            "#,
+            details.client_name,
+            details.scope_statement,
+            details.start_date,
+            details.end_date,
+            details.rules_of_engagement,
+            Utc::now(),
+            project_id
+        )
+        .execute(pool)
+        .await?;
+
+        Ok(())
+    }
+
+    pub async fn update_description(
+        pool: &SqlitePool,
+        project_id: &str,
+        description: Option<&str>,
+    ) -> Result<()> {
+        sqlx::query!(
+            "UPDATE projects SET description = ?, updated_at = ? WHERE id = ?",
+            description,
+            Utc::now(),
+            project_id
+        )
+        .execute(pool)
+        .await?;
+        
+        Ok(())
+    }
+}
+
+pub const SETTING_DEFAULT_SCAN_PROFILE: &str = "default_scan_profile";
+pub const SETTING_EXCLUDE_LIST: &str = "exclude_list";
+pub const SETTING_RATE_CAP_PPS: &str = "rate_cap_pps";
+pub const SETTING_NOTIFICATION_TARGETS: &str = "notification_targets";
+pub const SETTING_PROXY: &str = "proxy";
+pub const SETTING_ALLOW_PUBLIC_TARGETS: &str = "allow_public_targets";
+pub const SETTING_PIPELINE: &str = "pipeline";
+
+pub struct ProjectSettingsOperations;
+
+impl ProjectSettingsOperations {
+    pub async fn get_value<T: DeserializeOwned>(
+        pool: &SqlitePool,
+        project_id: &str,
+        key: &str,
+    ) -> Result<Option<T>> {
+        let row = sqlx::query!(
+            "SELECT value FROM project_settings WHERE project_id = ? AND key = ?",
+            project_id,
+            key
+        )
+        .fetch_optional(pool)
+        .await?;
+
+        match row {
+            Some(row) => Ok(Some(serde_json::from_str(&row.value)?)),
+            None => Ok(None),
+        }
+    }
+
+    pub async fn set_value<T: Serialize>(
+        pool: &SqlitePool,
+        project_id: &str,
+        key: &str,
+        value: &T,
+    ) -> Result<()> {
+        let value = serde_json::to_string(value)?;
+
+        sqlx::query!(
+            r#"
+            INSERT INTO project_settings (project_id, key, value, updated_at)
+            VALUES (?, ?, ?, ?)
+            ON CONFLICT (project_id, key) DO UPDATE SET
+                value = excluded.value,
+                updated_at = excluded.updated_at
+            "#,
+            project_id,
+            key,
+            value,
+            Utc::now()
+        )
+        .execute(pool)
+        .await?;
+
+        Ok(())
+    }
+
+    pub async fn load(pool: &SqlitePool, project_id: &str) -> Result<ProjectSettings> {
+        Ok(ProjectSettings {
+            default_scan_profile: Self::get_value::<Option<String>>(pool, project_id, SETTING_DEFAULT_SCAN_PROFILE).await?.flatten(),
+            exclude_list: Self::get_value(pool, project_id, SETTING_EXCLUDE_LIST).await?.unwrap_or_default(),
+            rate_cap_pps: Self::get_value::<Option<u32>>(pool, project_id, SETTING_RATE_CAP_PPS).await?.flatten(),
+            notification_targets: Self::get_value(pool, project_id, SETTING_NOTIFICATION_TARGETS).await?.unwrap_or_default(),
+            proxy: Self::get_value::<Option<ProxySettings>>(pool, project_id, SETTING_PROXY).await?.flatten(),
+            allow_public_targets: Self::get_value(pool, project_id, SETTING_ALLOW_PUBLIC_TARGETS).await?.unwrap_or_default(),
+            pipeline: Self::get_value(pool, project_id, SETTING_PIPELINE).await?.unwrap_or_default(),
+        })
+    }
+
+    pub async fn save(pool: &SqlitePool, project_id: &str, settings: &ProjectSettings) -> Result<()> {
+        Self::set_value(pool, project_id, SETTING_DEFAULT_SCAN_PROFILE, &settings.default_scan_profile).await?;
+        Self::set_value(pool, project_id, SETTING_EXCLUDE_LIST, &settings.exclude_list).await?;
+        Self::set_value(pool, project_id, SETTING_RATE_CAP_PPS, &settings.rate_cap_pps).await?;
+        Self::set_value(pool, project_id, SETTING_NOTIFICATION_TARGETS, &settings.notification_targets).await?;
+        Self::set_value(pool, project_id, SETTING_PROXY, &settings.proxy).await?;
+        Self::set_value(pool, project_id, SETTING_ALLOW_PUBLIC_TARGETS, &settings.allow_public_targets).await?;
+        Self::set_value(pool, project_id, SETTING_PIPELINE, &settings.pipeline).await?;
+        Ok(())
+    }
+}
+
+pub struct SshAuditOperations;
+
+impl SshAuditOperations {
+    pub async fn upsert(
+        pool: &SqlitePool,
+        host_id: &str,
+        port: u16,
+        banner: &str,
+        kex_algorithms: &str,
+        host_key_algorithms: &str,
+        ciphers: &str,
+        macs: &str,
+        host_key_type: Option<&str>,
+        host_key_bits: Option<u32>,
+    ) -> Result<SshAudit> {
+        let id = Uuid::new_v4().to_string();
+        let port = port as i32;
+        let host_key_bits = host_key_bits.map(|b| b as i32);
+
+        let audit = sqlx::query_as!(
+            SshAudit,
+            r#"
+            INSERT INTO ssh_audits (id, host_id, port, banner, kex_algorithms, host_key_algorithms,
+                ciphers, macs, host_key_type, host_key_bits, audited_at)
+            VALUES (?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?)
+            ON CONFLICT (host_id, port) DO UPDATE SET
+                banner = excluded.banner,
+                kex_algorithms = excluded.kex_algorithms,
+                host_key_algorithms = excluded.host_key_algorithms,
+                ciphers = excluded.ciphers,
+                macs = excluded.macs,
+                host_key_type = COALESCE(excluded.host_key_type, ssh_audits.host_key_type),
+                host_key_bits = COALESCE(excluded.host_key_bits, ssh_audits.host_key_bits),
+                audited_at = excluded.audited_at
+            RETURNING *
+            "#,
+            id,
+            host_id,
+            port,
+            banner,
+            kex_algorithms,
+            host_key_algorithms,
+            ciphers,
+            macs,
+            host_key_type,
+            host_key_bits,
+            Utc::now()
+        )
+        .fetch_one(pool)
+        .await?;
+
+        Ok(audit)
+    }
+
+    pub async fn find_by_host(pool: &SqlitePool, host_id: &str) -> Result<Vec<SshAudit>> {
+        let audits = sqlx::query_as!(
+            SshAudit,
+            "SELECT * FROM ssh_audits WHERE host_id = ? ORDER BY port",
+            host_id
+        )
+        .fetch_all(pool)
+        .await?;
+
+        Ok(audits)
+    }
+}
+
+pub struct AnonymousAccessOperations;
+
+impl AnonymousAccessOperations {
+    pub async fn upsert(
+        pool: &SqlitePool,
+        host_id: &str,
+        protocol: &str,
+        port: u16,
+        banner: Option<&str>,
+        listing: &str,
+    ) -> Result<AnonymousAccess> {
+        let id = Uuid::new_v4().to_string();
+        let port = port as i32;
+        let listing_sha256 = sha256_hex(listing.as_bytes());
+
+        let access = sqlx::query_as!(
+            AnonymousAccess,
+            r#"
+            INSERT INTO anonymous_access (id, host_id, protocol, port, banner, listing, listing_sha256, checked_at)
+            VALUES (?, ?, ?, ?, ?, ?, ?, ?)
+            ON CONFLICT (host_id, protocol, port) DO UPDATE SET
+                banner = excluded.banner,
+                listing = excluded.listing,
+                listing_sha256 = excluded.listing_sha256,
+                checked_at = excluded.checked_at
+            RETURNING *
+            "#,
+            id,
+            host_id,
+            protocol,
+            port,
+            banner,
+            listing,
+            listing_sha256,
+            Utc::now()
+        )
+        .fetch_one(pool)
+        .await?;
+
+        Ok(access)
+    }
+
+    pub async fn find_by_host(pool: &SqlitePool, host_id: &str) -> Result<Vec<AnonymousAccess>> {
+        let access = sqlx::query_as!(
+            AnonymousAccess,
+            "SELECT * FROM anonymous_access WHERE host_id = ? ORDER BY protocol, port",
+            host_id
+        )
+        .fetch_all(pool)
+        .await?;
+
+        Ok(access)
+    }
+}
+
+pub struct ServiceCheckOperations;
+
+impl ServiceCheckOperations {
+    pub async fn upsert(
+        pool: &SqlitePool,
+        host_id: &str,
+        port: u16,
+        check_name: &str,
+        vulnerable: bool,
+        evidence: &str,
+    ) -> Result<ServiceCheck> {
+        let id = Uuid::new_v4().to_string();
+        let port = port as i32;
+        let evidence_sha256 = sha256_hex(evidence.as_bytes());
+
+        let check = sqlx::query_as!(
+            ServiceCheck,
+            r#"
+            INSERT INTO service_checks (id, host_id, port, check_name, vulnerable, evidence, evidence_sha256, checked_at)
+            VALUES (?, ?, ?, ?, ?, ?, ?, ?)
+            ON CONFLICT (host_id, port, check_name) DO UPDATE SET
+                vulnerable = excluded.vulnerable,
+                evidence = excluded.evidence,
+                evidence_sha256 = excluded.evidence_sha256,
+                checked_at = excluded.checked_at
+            RETURNING id, host_id, port, check_name, vulnerable as "vulnerable: bool", evidence,
+                evidence_sha256, checked_at
+            "#,
+            id,
+            host_id,
+            port,
+            check_name,
+            vulnerable,
+            evidence,
+            evidence_sha256,
+            Utc::now()
+        )
+        .fetch_one(pool)
+        .await?;
+
+        Ok(check)
+    }
+
+    pub async fn find_by_host(pool: &SqlitePool, host_id: &str) -> Result<Vec<ServiceCheck>> {
+        let checks = sqlx::query_as!(
+            ServiceCheck,
+            r#"
+            SELECT id, host_id, port, check_name, vulnerable as "vulnerable: bool", evidence,
+                evidence_sha256, checked_at
+            FROM service_checks WHERE host_id = ? ORDER BY port, check_name
+            "#,
+            host_id
+        )
+        .fetch_all(pool)
+        .await?;
+
+        Ok(checks)
+    }
+}
+
+pub struct IcsAssetOperations;
+
+impl IcsAssetOperations {
+    pub async fn upsert(
+        pool: &SqlitePool,
+        host_id: &str,
+        protocol: &str,
+        port: u16,
+        vendor: Option<&str>,
+        model: Option<&str>,
+        firmware: Option<&str>,
+        details: Option<&str>,
+    ) -> Result<IcsAsset> {
+        let id = Uuid::new_v4().to_string();
+        let port = port as i32;
+
+        let asset = sqlx::query_as!(
+            IcsAsset,
+            r#"
+            INSERT INTO ics_assets (id, host_id, protocol, port, vendor, model, firmware, details, discovered_at)
+            VALUES (?, ?, ?, ?, ?, ?, ?, ?, ?)
+            ON CONFLICT (host_id, protocol, port) DO UPDATE SET
+                vendor = COALESCE(excluded.vendor, ics_assets.vendor),
+                model = COALESCE(excluded.model, ics_assets.model),
+                firmware = COALESCE(excluded.firmware, ics_assets.firmware),
+                details = COALESCE(excluded.details, ics_assets.details)
+            RETURNING *
+            "#,
+            id,
+            host_id,
+            protocol,
+            port,
+            vendor,
+            model,
+            firmware,
+            details,
+            Utc::now()
+        )
+        .fetch_one(pool)
+        .await?;
+
+        Ok(asset)
+    }
+
+    pub async fn find_by_host(pool: &SqlitePool, host_id: &str) -> Result<Vec<IcsAsset>> {
+        let assets = sqlx::query_as!(
+            IcsAsset,
+            "SELECT * FROM ics_assets WHERE host_id = ? ORDER BY protocol",
+            host_id
+        )
+        .fetch_all(pool)
+        .await?;
+
+        Ok(assets)
+    }
+
+    pub async fn list_all(pool: &SqlitePool) -> Result<Vec<IcsAsset>> {
+        let assets = sqlx::query_as!(IcsAsset, "SELECT * FROM ics_assets ORDER BY host_id, protocol")
+            .fetch_all(pool)
+            .await?;
+
+        Ok(assets)
+    }
+}
+
+pub struct WirelessDeviceOperations;
+
+impl WirelessDeviceOperations {
+    /// Records a sighting. Fields this sighting lacks are kept from
+    /// earlier ones.
+    pub async fn upsert(
+        pool: &SqlitePool,
+        kind: &str,
+        mac_address: &str,
+        name: Option<&str>,
+        rssi: Option<i32>,
+        services: Option<&str>,
+        channel: Option<i32>,
+        encryption: Option<&str>,
+        bssid: Option<&str>,
+        source: &str,
+    ) -> Result<WirelessDevice> {
+        let id = Uuid::new_v4().to_string();
+        let now = Utc::now();
+
+        let device = sqlx::query_as!(
+            WirelessDevice,
+            r#"
+            INSERT INTO wireless_devices (id, kind, mac_address, name, rssi, services, channel, encryption,
+                bssid, source, first_seen, last_seen)
+            VALUES (?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?)
+            ON CONFLICT (kind, mac_address) DO UPDATE SET
+                name = COALESCE(excluded.name, wireless_devices.name),
+                rssi = COALESCE(excluded.rssi, wireless_devices.rssi),
+                services = COALESCE(excluded.services, wireless_devices.services),
+                channel = COALESCE(excluded.channel, wireless_devices.channel),
+                encryption = COALESCE(excluded.encryption, wireless_devices.encryption),
+                bssid = COALESCE(excluded.bssid, wireless_devices.bssid),
+                last_seen = excluded.last_seen
+            RETURNING *
+            "#,
+            id,
+            kind,
+            mac_address,
+            name,
+            rssi,
+            services,
+            channel,
+            encryption,
+            bssid,
+            source,
+            now,
+            now
+        )
+        .fetch_one(pool)
+        .await?;
+
+        Ok(device)
+    }
+
+    pub async fn list(pool: &SqlitePool, kind: Option<&str>) -> Result<Vec<WirelessDevice>> {
+        let devices = sqlx::query_as!(
+            WirelessDevice,
+            r#"
+            SELECT * FROM wireless_devices
+            WHERE ?1 IS NULL OR kind = ?1
+            ORDER BY last_seen DESC
+            "#,
+            kind
+        )
+        .fetch_all(pool)
+        .await?;
+
+        Ok(devices)
+    }
+}
+
+pub struct PassiveDnsOperations;
+
+impl PassiveDnsOperations {
+    /// Records one answer. A repeat updates who asked and answered, and
+    /// when.
+    #[allow(clippy::too_many_arguments)]
+    pub async fn record(
+        pool: &SqlitePool,
+        query: &str,
+        name: &str,
+        record_type: &str,
+        value: &str,
+        ttl: u32,
+        client_ip: IpAddr,
+        server_ip: IpAddr,
+    ) -> Result<()> {
+        let id = Uuid::new_v4().to_string();
+        let ttl = ttl as i64;
+        let client_ip = client_ip.to_string();
+        let server_ip = server_ip.to_string();
+        let now = Utc::now();
+
+        sqlx::query!(
+            r#"
+            INSERT INTO passive_dns (id, query, name, record_type, value, ttl, client_ip, server_ip,
+                observations, first_seen, last_seen)
+            VALUES (?, ?, ?, ?, ?, ?, ?, ?, 1, ?, ?)
+            ON CONFLICT (name, record_type, value) DO UPDATE SET
+                query = excluded.query,
+                ttl = excluded.ttl,
+                client_ip = excluded.client_ip,
+                server_ip = excluded.server_ip,
+                observations = passive_dns.observations + 1,
+                last_seen = excluded.last_seen
+            "#,
+            id,
+            query,
+            name,
+            record_type,
+            value,
+            ttl,
+            client_ip,
+            server_ip,
+            now,
+            now
+        )
+        .execute(pool)
+        .await?;
+
+        Ok(())
+    }
+
+    /// Records naming `ip`: its address records, its PTR records, and
+    /// the aliases pointing at names with those addresses.
+    pub async fn find_names_for(pool: &SqlitePool, ip: IpAddr, reverse_name: &str) -> Result<Vec<PassiveDnsRecord>> {
+        let ip = ip.to_string();
+
+        let records = sqlx::query_as!(
+            PassiveDnsRecord,
+            r#"
+            SELECT * FROM passive_dns
+            WHERE (record_type IN ('A', 'AAAA') AND value = ?1)
+                OR (record_type = 'PTR' AND name = ?2)
+                OR (record_type = 'CNAME' AND value IN (
+                    SELECT name FROM passive_dns WHERE record_type IN ('A', 'AAAA') AND value = ?1
+                ))
+            ORDER BY last_seen DESC
+            "#,
+            ip,
+            reverse_name
+        )
+        .fetch_all(pool)
+        .await?;
+
+        Ok(records)
+    }
+}
+
+pub struct FlowOperations;
+
+impl FlowOperations {
+    /// Adds the counters from one flush to the flow's totals.
+    #[allow(clippy::too_many_arguments)]
+    pub async fn record(
+        pool: &SqlitePool,
+        client_ip: IpAddr,
+        server_ip: IpAddr,
+        protocol: &str,
+        server_port: u16,
+        packets: u64,
+        bytes: u64,
+        first_seen: DateTime<Utc>,
+        last_seen: DateTime<Utc>,
+    ) -> Result<()> {
+        let id = Uuid::new_v4().to_string();
+        let client_ip = client_ip.to_string();
+        let server_ip = server_ip.to_string();
+        let server_port = server_port as i32;
+        let packets = packets as i64;
+        let bytes = bytes as i64;
+
+        sqlx::query!(
+            r#"
+            INSERT INTO flows (id, client_ip, server_ip, protocol, server_port, packets, bytes,
+                first_seen, last_seen)
+            VALUES (?, ?, ?, ?, ?, ?, ?, ?, ?)
+            ON CONFLICT (client_ip, server_ip, protocol, server_port) DO UPDATE SET
+                packets = flows.packets + excluded.packets,
+                bytes = flows.bytes + excluded.bytes,
+                first_seen = MIN(flows.first_seen, excluded.first_seen),
+                last_seen = MAX(flows.last_seen, excluded.last_seen)
+            "#,
+            id,
+            client_ip,
+            server_ip,
+            protocol,
+            server_port,
+            packets,
+            bytes,
+            first_seen,
+            last_seen
+        )
+        .execute(pool)
+        .await?;
+
+        Ok(())
+    }
+
+    pub async fn list(pool: &SqlitePool) -> Result<Vec<Flow>> {
+        let flows = sqlx::query_as!(Flow, "SELECT * FROM flows ORDER BY first_seen")
+            .fetch_all(pool)
+            .await?;
+
+        Ok(flows)
+    }
+
+    /// Flows `ip` took part in on either side, busiest first.
+    pub async fn find_by_ip(pool: &SqlitePool, ip: &str) -> Result<Vec<Flow>> {
+        let flows = sqlx::query_as!(
+            Flow,
+            r#"
+            SELECT * FROM flows
+            WHERE client_ip = ?1 OR server_ip = ?1
+            ORDER BY bytes DESC
+            "#,
+            ip
+        )
+        .fetch_all(pool)
+        .await?;
+
+        Ok(flows)
+    }
+}
+
+pub struct TlsSessionOperations;
+
+impl TlsSessionOperations {
+    /// Records a ClientHello. A repeat updates the client, version and
+    /// protocols to the latest seen.
+    pub async fn record(
+        pool: &SqlitePool,
+        server_ip: IpAddr,
+        server_port: u16,
+        server_name: Option<&str>,
+        alpn: &[String],
+        tls_version: &str,
+        client_ip: IpAddr,
+    ) -> Result<()> {
+        let id = Uuid::new_v4().to_string();
+        let server_ip = server_ip.to_string();
+        let server_port = server_port as i32;
+        let server_name = server_name.unwrap_or_default();
+        let alpn = (!alpn.is_empty()).then(|| alpn.join(","));
+        let client_ip = client_ip.to_string();
+        let now = Utc::now();
+
+        sqlx::query!(
+            r#"
+            INSERT INTO tls_sessions (id, server_ip, server_port, server_name, alpn, tls_version, client_ip,
+                observations, first_seen, last_seen)
+            VALUES (?, ?, ?, ?, ?, ?, ?, 1, ?, ?)
+            ON CONFLICT (server_ip, server_port, server_name) DO UPDATE SET
+                alpn = excluded.alpn,
+                tls_version = excluded.tls_version,
+                client_ip = excluded.client_ip,
+                observations = tls_sessions.observations + 1,
+                last_seen = excluded.last_seen
+            "#,
+            id,
+            server_ip,
+            server_port,
+            server_name,
+            alpn,
+            tls_version,
+            client_ip,
+            now,
+            now
+        )
+        .execute(pool)
+        .await?;
+
+        Ok(())
+    }
+
+    pub async fn find_by_server(pool: &SqlitePool, server_ip: &str) -> Result<Vec<TlsSession>> {
+        let sessions = sqlx::query_as!(
+            TlsSession,
+            "SELECT * FROM tls_sessions WHERE server_ip = ? ORDER BY server_port, server_name",
+            server_ip
+        )
+        .fetch_all(pool)
+        .await?;
+
+        Ok(sessions)
+    }
+}
+
+pub const HTTP_USER_AGENT: &str = "user_agent";
+pub const HTTP_VIRTUAL_HOST: &str = "virtual_host";
+pub const HTTP_SERVER: &str = "server";
+pub const HTTP_POWERED_BY: &str = "powered_by";
+
+pub struct HttpMetadataOperations;
+
+impl HttpMetadataOperations {
+    /// `port` is 0 for headers the host sent as a client.
+    pub async fn record(pool: &SqlitePool, host_id: &str, port: u16, kind: &str, value: &str) -> Result<()> {
+        let id = Uuid::new_v4().to_string();
+        let port = port as i32;
+        let now = Utc::now();
+
+        sqlx::query!(
+            r#"
+            INSERT INTO http_metadata (id, host_id, port, kind, value, observations, first_seen, last_seen)
+            VALUES (?, ?, ?, ?, ?, 1, ?, ?)
+            ON CONFLICT (host_id, port, kind, value) DO UPDATE SET
+                observations = http_metadata.observations + 1,
+                last_seen = excluded.last_seen
+            "#,
+            id,
+            host_id,
+            port,
+            kind,
+            value,
+            now,
+            now
+        )
+        .execute(pool)
+        .await?;
+
+        Ok(())
+    }
+
+    pub async fn find_by_host(pool: &SqlitePool, host_id: &str) -> Result<Vec<HttpMetadata>> {
+        let metadata = sqlx::query_as!(
+            HttpMetadata,
+            "SELECT * FROM http_metadata WHERE host_id = ? ORDER BY port, kind, value",
+            host_id
+        )
+        .fetch_all(pool)
+        .await?;
+
+        Ok(metadata)
+    }
+
+    /// Distinct software in user agents and server headers, most
+    /// widespread first.
+    pub async fn inventory(pool: &SqlitePool) -> Result<Vec<HttpSoftware>> {
+        let software = sqlx::query_as!(
+            HttpSoftware,
+            r#"
+            SELECT kind, value,
+                COUNT(DISTINCT host_id) as "hosts!: i64",
+                SUM(observations) as "observations!: i64",
+                MAX(last_seen) as "last_seen!: DateTime<Utc>"
+            FROM http_metadata
+            WHERE kind != ?
+            GROUP BY kind, value
+            ORDER BY 3 DESC, kind, value
+            "#,
+            HTTP_VIRTUAL_HOST
+        )
+        .fetch_all(pool)
+        .await?;
+
+        Ok(software)
+    }
+
+    /// Hosts showing one header value, e.g. every server on an old Apache
+    pub async fn find_by_value(pool: &SqlitePool, kind: &str, value: &str) -> Result<Vec<HttpMetadata>> {
+        let metadata = sqlx::query_as!(
+            HttpMetadata,
+            "SELECT * FROM http_metadata WHERE kind = ? AND value = ? ORDER BY last_seen DESC",
+            kind,
+            value
+        )
+        .fetch_all(pool)
+        .await?;
+
+        Ok(metadata)
+    }
+}
+
+pub const FINGERPRINT_JA3: &str = "ja3";
+pub const FINGERPRINT_JA3S: &str = "ja3s";
+
+pub struct TlsFingerprintOperations;
+
+impl TlsFingerprintOperations {
+    /// Records a hash seen for a host, returning whether it is new for
+    /// the host.
+    pub async fn record(
+        pool: &SqlitePool,
+        host_id: &str,
+        kind: &str,
+        hash: &str,
+        software: Option<&str>,
+        suspicious: bool,
+    ) -> Result<bool> {
+        let id = Uuid::new_v4().to_string();
+        let now = Utc::now();
+
+        let observations = sqlx::query_scalar!(
+            r#"
+            INSERT INTO tls_fingerprints (id, host_id, kind, hash, software, suspicious, observations,
+                first_seen, last_seen)
+            VALUES (?, ?, ?, ?, ?, ?, 1, ?, ?)
+            ON CONFLICT (host_id, kind, hash) DO UPDATE SET
+                software = COALESCE(excluded.software, tls_fingerprints.software),
+                suspicious = excluded.suspicious OR tls_fingerprints.suspicious,
+                observations = tls_fingerprints.observations + 1,
+                last_seen = excluded.last_seen
+            RETURNING observations
+            "#,
+            id,
+            host_id,
+            kind,
+            hash,
+            software,
+            suspicious,
+            now,
+            now
+        )
+        .fetch_one(pool)
+        .await?;
+
+        Ok(observations == 1)
+    }
+
+    pub async fn find_by_host(pool: &SqlitePool, host_id: &str) -> Result<Vec<TlsFingerprint>> {
+        let fingerprints = sqlx::query_as!(
+            TlsFingerprint,
+            "SELECT * FROM tls_fingerprints WHERE host_id = ? ORDER BY suspicious DESC, last_seen DESC",
+            host_id
+        )
+        .fetch_all(pool)
+        .await?;
+
+        Ok(fingerprints)
+    }
+
+    /// Hosts seen with the same hash, which usually means the same
+    /// software
+    pub async fn find_by_hash(pool: &SqlitePool, hash: &str) -> Result<Vec<TlsFingerprint>> {
+        let fingerprints = sqlx::query_as!(
+            TlsFingerprint,
+            "SELECT * FROM tls_fingerprints WHERE hash = ? ORDER BY last_seen DESC",
+            hash.to_ascii_lowercase()
+        )
+        .fetch_all(pool)
+        .await?;
+
+        Ok(fingerprints)
+    }
+}
+
+pub struct TopologyOperations;
+
+impl TopologyOperations {
+    /// Records a node and returns its id. A host seen again as a router
+    /// or gateway is promoted, and a gateway is never demoted.
+    pub async fn upsert_node(pool: &SqlitePool, address: &str, kind: &str) -> Result<String> {
+        let id = Uuid::new_v4().to_string();
+        let now = Utc::now();
+
+        let node_id = sqlx::query_scalar!(
+            r#"
+            INSERT INTO topology_nodes (id, address, kind, first_seen, last_seen)
+            VALUES (?, ?, ?, ?, ?)
+            ON CONFLICT (address) DO UPDATE SET
+                kind = CASE
+                    WHEN topology_nodes.kind = 'host' OR excluded.kind = 'gateway' THEN excluded.kind
+                    ELSE topology_nodes.kind
+                END,
+                last_seen = excluded.last_seen
+            RETURNING id AS "id!"
+            "#,
+            id,
+            address,
+            kind,
+            now,
+            now
+        )
+        .fetch_one(pool)
+        .await?;
+
+        Ok(node_id)
+    }
+
+    pub async fn record_edge(pool: &SqlitePool, source_id: &str, target_id: &str, kind: &str) -> Result<()> {
+        let id = Uuid::new_v4().to_string();
+        let now = Utc::now();
+
+        sqlx::query!(
+            r#"
+            INSERT INTO topology_edges (id, source_id, target_id, kind, observations, first_seen, last_seen)
+            VALUES (?, ?, ?, ?, 1, ?, ?)
+            ON CONFLICT (source_id, target_id, kind) DO UPDATE SET
+                observations = topology_edges.observations + 1,
+                last_seen = excluded.last_seen
+            "#,
+            id,
+            source_id,
+            target_id,
+            kind,
+            now,
+            now
+        )
+        .execute(pool)
+        .await?;
+
+        Ok(())
+    }
+
+    /// Addresses traceroutes found as first hops
+    pub async fn gateways(pool: &SqlitePool) -> Result<Vec<IpAddr>> {
+        let addresses = sqlx::query_scalar!("SELECT address FROM topology_nodes WHERE kind = 'gateway'")
+            .fetch_all(pool)
+            .await?;
+
+        Ok(addresses.iter().filter_map(|address| address.parse().ok()).collect())
+    }
+
+    /// The whole graph, with nodes linked to the hosts stored at their
+    /// addresses.
+    pub async fn get(pool: &SqlitePool) -> Result<Topology> {
+        let nodes = sqlx::query_as!(
+            TopologyNode,
+            r#"
+            SELECT n.id, n.address, n.kind, h.id AS "host_id?", h.hostname, n.first_seen, n.last_seen
+            FROM topology_nodes n
+            LEFT JOIN hosts h ON h.ip = n.address
+            ORDER BY n.kind, n.address
+            "#
+        )
+        .fetch_all(pool)
+        .await?;
+
+        let edges = sqlx::query_as!(
+            TopologyEdge,
+            "SELECT * FROM topology_edges ORDER BY kind, first_seen"
+        )
+        .fetch_all(pool)
+        .await?;
+
+        Ok(Topology { nodes, edges })
+    }
+}
+
+pub struct ServiceDependencyOperations;
+
+impl ServiceDependencyOperations {
+    /// Adds the counters from one flush of a flow to the dependency's
+    /// totals, keeping the latest service and tier.
+    #[allow(clippy::too_many_arguments)]
+    pub async fn record(
+        pool: &SqlitePool,
+        client_host_id: &str,
+        server_host_id: &str,
+        protocol: &str,
+        server_port: u16,
+        service: Option<&str>,
+        tier: &str,
+        packets: i64,
+        bytes: i64,
+        first_seen: DateTime<Utc>,
+        last_seen: DateTime<Utc>,
+    ) -> Result<()> {
+        let id = Uuid::new_v4().to_string();
+        let server_port = server_port as i32;
+
+        sqlx::query!(
+            r#"
+            INSERT INTO service_dependencies (id, client_host_id, server_host_id, protocol, server_port,
+                service, tier, packets, bytes, first_seen, last_seen)
+            VALUES (?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?)
+            ON CONFLICT (client_host_id, server_host_id, protocol, server_port) DO UPDATE SET
+                service = COALESCE(excluded.service, service_dependencies.service),
+                tier = excluded.tier,
+                packets = service_dependencies.packets + excluded.packets,
+                bytes = service_dependencies.bytes + excluded.bytes,
+                first_seen = MIN(service_dependencies.first_seen, excluded.first_seen),
+                last_seen = MAX(service_dependencies.last_seen, excluded.last_seen)
+            "#,
+            id,
+            client_host_id,
+            server_host_id,
+            protocol,
+            server_port,
+            service,
+            tier,
+            packets,
+            bytes,
+            first_seen,
+            last_seen
+        )
+        .execute(pool)
+        .await?;
+
+        Ok(())
+    }
+
+    /// Forgets every dependency, before they're rebuilt from the flows
+    pub async fn clear(pool: &SqlitePool) -> Result<()> {
+        sqlx::query!("DELETE FROM service_dependencies")
+            .execute(pool)
+            .await?;
+
+        Ok(())
+    }
+
+    /// Services `host_id` uses and the hosts using its own, busiest first
+    pub async fn find_by_host(pool: &SqlitePool, host_id: &str) -> Result<HostDependencies> {
+        let dependencies = sqlx::query_as!(
+            ServiceDependency,
+            r#"
+            SELECT d.id, d.client_host_id, c.ip AS client_ip, d.server_host_id, s.ip AS server_ip,
+                d.protocol, d.server_port, d.service, d.tier, d.packets, d.bytes, d.first_seen, d.last_seen
+            FROM service_dependencies d
+            JOIN hosts c ON c.id = d.client_host_id
+            JOIN hosts s ON s.id = d.server_host_id
+            WHERE d.client_host_id = ?1 OR d.server_host_id = ?1
+            ORDER BY d.bytes DESC
+            "#,
+            host_id
+        )
+        .fetch_all(pool)
+        .await?;
+
+        let (depends_on, dependents) = dependencies.into_iter()
+            .partition(|dependency| dependency.client_host_id == host_id);
+
+        Ok(HostDependencies {
+            host_id: host_id.to_string(),
+            depends_on,
+            dependents,
+        })
+    }
+}
+
+pub struct CloudAssetOperations;
+
+impl CloudAssetOperations {
+    pub async fn upsert(
+        pool: &SqlitePool,
+        provider: &str,
+        kind: &str,
+        resource_id: &str,
+        name: Option<&str>,
+        address: &str,
+        region: Option<&str>,
+        exposure: Option<&str>,
+    ) -> Result<CloudAsset> {
+        let id = Uuid::new_v4().to_string();
+
+        let asset = sqlx::query_as!(
+            CloudAsset,
+            r#"
+            INSERT INTO cloud_assets (id, provider, kind, resource_id, name, address, region, exposure, discovered_at)
+            VALUES (?, ?, ?, ?, ?, ?, ?, ?, ?)
+            ON CONFLICT (provider, resource_id, address) DO UPDATE SET
+                kind = excluded.kind,
+                name = excluded.name,
+                region = excluded.region,
+                exposure = excluded.exposure,
+                discovered_at = excluded.discovered_at
+            RETURNING *
+            "#,
+            id,
+            provider,
+            kind,
+            resource_id,
+            name,
+            address,
+            region,
+            exposure,
+            Utc::now()
+        )
+        .fetch_one(pool)
+        .await?;
+
+        Ok(asset)
+    }
+
+    pub async fn list(pool: &SqlitePool, provider: Option<&str>) -> Result<Vec<CloudAsset>> {
+        let assets = sqlx::query_as!(
+            CloudAsset,
+            r#"
+            SELECT * FROM cloud_assets
+            WHERE ?1 IS NULL OR provider = ?1
+            ORDER BY provider, address
+            "#,
+            provider
+        )
+        .fetch_all(pool)
+        .await?;
+
+        Ok(assets)
+    }
+}
+
+pub struct NatPortMappingOperations;
+
+impl NatPortMappingOperations {
+    pub async fn upsert(
+        pool: &SqlitePool,
+        host_id: &str,
+        remote_host: Option<&str>,
+        external_port: u16,
+        protocol: &str,
+        internal_client: &str,
+        internal_port: u16,
+        enabled: bool,
+        description: Option<&str>,
+        lease_duration: u32,
+    ) -> Result<NatPortMapping> {
+        let id = Uuid::new_v4().to_string();
+        let external_port = external_port as i32;
+        let internal_port = internal_port as i32;
+        let lease_duration = lease_duration as i64;
+
+        let mapping = sqlx::query_as!(
+            NatPortMapping,
+            r#"
+            INSERT INTO nat_port_mappings (id, host_id, remote_host, external_port, protocol, internal_client,
+                internal_port, enabled, description, lease_duration, discovered_at)
+            VALUES (?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?)
+            ON CONFLICT (host_id, external_port, protocol) DO UPDATE SET
+                remote_host = excluded.remote_host,
+                internal_client = excluded.internal_client,
+                internal_port = excluded.internal_port,
+                enabled = excluded.enabled,
+                description = excluded.description,
+                lease_duration = excluded.lease_duration,
+                discovered_at = excluded.discovered_at
+            RETURNING id, host_id, remote_host, external_port, protocol, internal_client, internal_port,
+                enabled as "enabled: bool", description, lease_duration, discovered_at
+            "#,
+            id,
+            host_id,
+            remote_host,
+            external_port,
+            protocol,
+            internal_client,
+            internal_port,
+            enabled,
+            description,
+            lease_duration,
+            Utc::now()
+        )
+        .fetch_one(pool)
+        .await?;
+
+        Ok(mapping)
+    }
+
+    pub async fn find_by_host(pool: &SqlitePool, host_id: &str) -> Result<Vec<NatPortMapping>> {
+        let mappings = sqlx::query_as!(
+            NatPortMapping,
+            r#"
+            SELECT id, host_id, remote_host, external_port, protocol, internal_client, internal_port,
+                enabled as "enabled: bool", description, lease_duration, discovered_at
+            FROM nat_port_mappings WHERE host_id = ? ORDER BY external_port
+            "#,
+            host_id
+        )
+        .fetch_all(pool)
+        .await?;
+
+        Ok(mappings)
+    }
+}
+
+pub struct ShareOperations;
+
+impl ShareOperations {
+    pub async fn upsert(
+        pool: &SqlitePool,
+        host_id: &str,
+        name: &str,
+        share_type: Option<&str>,
+        comment: Option<&str>,
+        anonymous_access: Option<&str>,
+        user_access: Option<&str>,
+        accessible: bool,
+        writable: bool,
+    ) -> Result<Share> {
+        let id = Uuid::new_v4().to_string();
+
+        let share = sqlx::query_as!(
+            Share,
+            r#"
+            INSERT INTO shares (id, host_id, name, share_type, comment, anonymous_access, user_access, accessible, writable, discovered_at)
+            VALUES (?, ?, ?, ?, ?, ?, ?, ?, ?, ?)
+            ON CONFLICT (host_id, name) DO UPDATE SET
+                share_type = excluded.share_type,
+                comment = excluded.comment,
+                anonymous_access = COALESCE(excluded.anonymous_access, shares.anonymous_access),
+                user_access = COALESCE(excluded.user_access, shares.user_access),
+                accessible = excluded.accessible OR shares.accessible,
+                writable = excluded.writable OR shares.writable
+            RETURNING id, host_id, name, share_type, comment, anonymous_access, user_access,
+                accessible as "accessible: bool", writable as "writable: bool", discovered_at
+            "#,
+            id,
+            host_id,
+            name,
+            share_type,
+            comment,
+            anonymous_access,
+            user_access,
+            accessible,
+            writable,
+            Utc::now()
+        )
+        .fetch_one(pool)
+        .await?;
+
+        Ok(share)
+    }
+
+    pub async fn find_by_host(pool: &SqlitePool, host_id: &str) -> Result<Vec<Share>> {
+        let shares = sqlx::query_as!(
+            Share,
+            r#"
+            SELECT id, host_id, name, share_type, comment, anonymous_access, user_access,
+                accessible as "accessible: bool", writable as "writable: bool", discovered_at
+            FROM shares WHERE host_id = ? ORDER BY name
+            "#,
+            host_id
+        )
+        .fetch_all(pool)
+        .await?;
+
+        Ok(shares)
+    }
+}
+
+
+pub struct PortPresetOperations;
+
+impl PortPresetOperations {
+    pub async fn create(
+        pool: &SqlitePool,
+        name: &str,
+        description: Option<&str>,
+        ports: &str,
+    ) -> Result<PortPreset> {
+        let id = Uuid::new_v4().to_string();
+        let now = Utc::now();
+
+        let preset = sqlx::query_as!(
+            PortPreset,
+            r#"
+            INSERT INTO port_presets (id, name, description, ports, created_at, updated_at)
+            VALUES (?, ?, ?, ?, ?, ?)
+            RETURNING *
+            "#,
+            id,
+            name,
+            description,
+            ports,
+            now,
+            now
+        )
+        .fetch_one(pool)
+        .await?;
+
+        Ok(preset)
+    }
+
+    pub async fn list_all(pool: &SqlitePool) -> Result<Vec<PortPreset>> {
+        let presets = sqlx::query_as!(
+            PortPreset,
+            "SELECT * FROM port_presets ORDER BY name"
+        )
+        .fetch_all(pool)
+        .await?;
+
+        Ok(presets)
+    }
+
+    pub async fn find_by_name(pool: &SqlitePool, name: &str) -> Result<Option<PortPreset>> {
+        let preset = sqlx::query_as!(
+            PortPreset,
+            "SELECT * FROM port_presets WHERE name = ? COLLATE NOCASE",
+            name
+        )
+        .fetch_optional(pool)
+        .await?;
+
+        Ok(preset)
+    }
+
+    pub async fn update(
+        pool: &SqlitePool,
+        preset_id: &str,
+        name: &str,
+        description: Option<&str>,
+        ports: &str,
+    ) -> Result<()> {
+        sqlx::query!(
+            "UPDATE port_presets SET name = ?, description = ?, ports = ?, updated_at = ? WHERE id = ?",
+            name,
+            description,
+            ports,
+            Utc::now(),
+            preset_id
+        )
+        .execute(pool)
+        .await?;
+
+        Ok(())
+    }
+
+    pub async fn delete(pool: &SqlitePool, preset_id: &str) -> Result<()> {
+        sqlx::query!("DELETE FROM port_presets WHERE id = ?", preset_id)
+            .execute(pool)
+            .await?;
+
+        Ok(())
+    }
+}
+
+pub struct FollowUpRuleOperations;
+
+impl FollowUpRuleOperations {
+    #[allow(clippy::too_many_arguments)]
+    pub async fn create(
+        pool: &SqlitePool,
+        name: &str,
+        match_port: Option<u16>,
+        match_service: Option<&str>,
+        action: &str,
+        arguments: Option<&str>,
+        enabled: bool,
+    ) -> Result<FollowUpRule> {
+        let id = Uuid::new_v4().to_string();
+        let match_port = match_port.map(i32::from);
+        let now = Utc::now();
+
+        let rule = sqlx::query_as!(
+            FollowUpRule,
+            r#"
+            INSERT INTO follow_up_rules (id, name, match_port, match_service, action, arguments, enabled,
+                created_at, updated_at)
+            VALUES (?, ?, ?, ?, ?, ?, ?, ?, ?)
+            RETURNING id, name, match_port, match_service, action, arguments,
+                enabled as "enabled: bool", created_at, updated_at
+            "#,
+            id,
+            name,
+            match_port,
+            match_service,
+            action,
+            arguments,
+            enabled,
+            now,
+            now
+        )
+        .fetch_one(pool)
+        .await?;
+
+        Ok(rule)
+    }
+
+    pub async fn list_all(pool: &SqlitePool) -> Result<Vec<FollowUpRule>> {
+        let rules = sqlx::query_as!(
+            FollowUpRule,
+            r#"
+            SELECT id, name, match_port, match_service, action, arguments,
+                enabled as "enabled: bool", created_at, updated_at
+            FROM follow_up_rules
+            ORDER BY name
+            "#
+        )
+        .fetch_all(pool)
+        .await?;
+
+        Ok(rules)
+    }
+
+    pub async fn list_enabled(pool: &SqlitePool) -> Result<Vec<FollowUpRule>> {
+        let rules = sqlx::query_as!(
+            FollowUpRule,
+            r#"
+            SELECT id, name, match_port, match_service, action, arguments,
+                enabled as "enabled: bool", created_at, updated_at
+            FROM follow_up_rules
+            WHERE enabled = 1
+            ORDER BY created_at
+            "#
+        )
+        .fetch_all(pool)
+        .await?;
+
+        Ok(rules)
+    }
+
+    #[allow(clippy::too_many_arguments)]
+    pub async fn update(
+        pool: &SqlitePool,
+        rule_id: &str,
+        name: &str,
+        match_port: Option<u16>,
+        match_service: Option<&str>,
+        action: &str,
+        arguments: Option<&str>,
+        enabled: bool,
+    ) -> Result<()> {
+        let match_port = match_port.map(i32::from);
+
+        sqlx::query!(
+            r#"
+            UPDATE follow_up_rules
+            SET name = ?, match_port = ?, match_service = ?, action = ?, arguments = ?, enabled = ?, updated_at = ?
+            WHERE id = ?
+            "#,
+            name,
+            match_port,
+            match_service,
+            action,
+            arguments,
+            enabled,
+            Utc::now(),
+            rule_id
+        )
+        .execute(pool)
+        .await?;
+
+        Ok(())
+    }
+
+    pub async fn delete(pool: &SqlitePool, rule_id: &str) -> Result<()> {
+        sqlx::query!("DELETE FROM follow_up_rules WHERE id = ?", rule_id)
+            .execute(pool)
+            .await?;
+
+        Ok(())
+    }
+}
+
+pub struct ScopeOperations;
+
+impl ScopeOperations {
+    pub async fn add(pool: &SqlitePool, project_id: &str, entry: &str, kind: &str) -> Result<ScopeEntry> {
+        let id = Uuid::new_v4().to_string();
+
+        let scope = sqlx::query_as!(
+            ScopeEntry,
+            r#"
+            INSERT INTO project_scope (id, project_id, entry, kind, created_at)
+            VALUES (?, ?, ?, ?, ?)
+            RETURNING *
+            "#,
+            id,
+            project_id,
+            entry,
+            kind,
+            Utc::now()
+        )
+        .fetch_one(pool)
+        .await?;
+
+        Ok(scope)
+    }
+
+    /// Like `add`, but an entry that is already in scope is left alone.
+    /// Returns whether a new entry was added.
+    pub async fn add_if_missing(pool: &SqlitePool, project_id: &str, entry: &str, kind: &str) -> Result<bool> {
+        let id = Uuid::new_v4().to_string();
+
+        let result = sqlx::query!(
+            r#"
+            INSERT INTO project_scope (id, project_id, entry, kind, created_at)
+            VALUES (?, ?, ?, ?, ?)
+            ON CONFLICT (project_id, entry) DO NOTHING
+            "#,
+            id,
+            project_id,
+            entry,
+            kind,
+            Utc::now()
+        )
+        .execute(pool)
+        .await?;
+
+        Ok(result.rows_affected() > 0)
+    }
+
+    pub async fn list(pool: &SqlitePool, project_id: &str) -> Result<Vec<ScopeEntry>> {
+        let scope = sqlx::query_as!(
+            ScopeEntry,
+            "SELECT * FROM project_scope WHERE project_id = ? ORDER BY kind, entry",
+            project_id
+        )
+        .fetch_all(pool)
+        .await?;
+
+        Ok(scope)
+    }
+
+    pub async fn remove(pool: &SqlitePool, scope_id: &str) -> Result<()> {
+        sqlx::query!("DELETE FROM project_scope WHERE id = ?", scope_id)
+            .execute(pool)
+            .await?;
+
+        Ok(())
+    }
+}
+
+pub struct AuditLogOperations;
+
+impl AuditLogOperations {
+    pub async fn record(
+        pool: &SqlitePool,
+        project_id: Option<&str>,
+        action: &str,
+        target: Option<&str>,
+        details: Option<&str>,
+    ) -> Result<()> {
+        let id = Uuid::new_v4().to_string();
+
+        sqlx::query!(
+            r#"
+            INSERT INTO audit_log (id, project_id, action, target, details, created_at)
+            VALUES (?, ?, ?, ?, ?, ?)
+            "#,
+            id,
+            project_id,
+            action,
+            target,
+            details,
+            Utc::now()
+        )
+        .execute(pool)
+        .await?;
+
+        Ok(())
+    }
+
+    pub async fn list(pool: &SqlitePool, project_id: Option<&str>, limit: i32) -> Result<Vec<AuditLogEntry>> {
+        let entries = sqlx::query_as!(
+            AuditLogEntry,
+            r#"
+            SELECT * FROM audit_log
+            WHERE ?1 IS NULL OR project_id = ?1
+            ORDER BY created_at DESC LIMIT ?2
+            "#,
+            project_id,
+            limit
+        )
+        .fetch_all(pool)
+        .await?;
+
+        Ok(entries)
+    }
+}
+
+pub const EVENT_DISCOVERY: &str = "discovery";
+pub const EVENT_SCAN: &str = "scan";
+pub const EVENT_FINDING: &str = "finding";
+pub const EVENT_USER: &str = "user";
+
+pub struct EventOperations;
+
+impl EventOperations {
+    pub async fn record(
+        pool: &SqlitePool,
+        category: &str,
+        action: &str,
+        host_id: Option<&str>,
+        entity_id: Option<&str>,
+        message: &str,
+    ) -> Result<()> {
+        let id = Uuid::new_v4().to_string();
+
+        sqlx::query!(
+            r#"
+            INSERT INTO events (id, category, action, host_id, entity_id, message, created_at)
+            VALUES (?, ?, ?, ?, ?, ?, ?)
+            "#,
+            id,
+            category,
+            action,
+            host_id,
+            entity_id,
+            message,
+            Utc::now()
+        )
+        .execute(pool)
+        .await?;
+
+        Ok(())
+    }
+
+    /// Events matching the filter within the range, newest first.
+    pub async fn list(
+        pool: &SqlitePool,
+        filter: &EventFilter,
+        range: &TimeRange,
+        limit: i32,
+    ) -> Result<Vec<TimelineEvent>> {
+        let events = sqlx::query_as!(
+            TimelineEvent,
+            r#"
+            SELECT * FROM events
+            WHERE (?1 IS NULL OR category = ?1)
+                AND (?2 IS NULL OR host_id = ?2)
+                AND (?3 IS NULL OR entity_id = ?3)
+                AND (?4 IS NULL OR created_at >= ?4)
+                AND (?5 IS NULL OR created_at <= ?5)
+            ORDER BY created_at DESC LIMIT ?6
+            "#,
+            filter.category,
+            filter.host_id,
+            filter.entity_id,
+            range.since,
+            range.until,
+            limit
+        )
+        .fetch_all(pool)
+        .await?;
+
+        Ok(events)
+    }
+}
+
+pub struct NotificationOperations;
+
+impl NotificationOperations {
+    pub async fn create(
+        pool: &SqlitePool,
+        severity: &str,
+        title: &str,
+        message: &str,
+        entity_type: Option<&str>,
+        entity_id: Option<&str>,
+    ) -> Result<Notification> {
+        let id = Uuid::new_v4().to_string();
+
+        let notification = sqlx::query_as!(
+            Notification,
+            r#"
+            INSERT INTO notifications (id, severity, title, message, entity_type, entity_id, unread, created_at)
+            VALUES (?, ?, ?, ?, ?, ?, 1, ?)
+            RETURNING id, severity, title, message, entity_type, entity_id, unread as "unread: bool", created_at
+            "#,
+            id,
+            severity,
+            title,
+            message,
+            entity_type,
+            entity_id,
+            Utc::now()
+        )
+        .fetch_one(pool)
+        .await?;
+
+        Ok(notification)
+    }
+
+    pub async fn list(pool: &SqlitePool, unread_only: bool, limit: i32) -> Result<Vec<Notification>> {
+        let notifications = sqlx::query_as!(
+            Notification,
+            r#"
+            SELECT id, severity, title, message, entity_type, entity_id, unread as "unread: bool", created_at
+            FROM notifications
+            WHERE ?1 = 0 OR unread = 1
+            ORDER BY created_at DESC LIMIT ?2
+            "#,
+            unread_only,
+            limit
+        )
+        .fetch_all(pool)
+        .await?;
+
+        Ok(notifications)
+    }
+
+    pub async fn mark_read(pool: &SqlitePool, notification_id: &str) -> Result<u64> {
+        let result = sqlx::query!(
+            "UPDATE notifications SET unread = 0 WHERE id = ? AND unread = 1",
+            notification_id
+        )
+        .execute(pool)
+        .await?;
+
+        Ok(result.rows_affected())
+    }
+
+    pub async fn mark_all_read(pool: &SqlitePool) -> Result<u64> {
+        let result = sqlx::query!("UPDATE notifications SET unread = 0 WHERE unread = 1")
+            .execute(pool)
+            .await?;
+
+        Ok(result.rows_affected())
+    }
+}
+
+pub struct UserOperations;
+
+impl UserOperations {
+    pub async fn create(pool: &SqlitePool, username: &str, role: &str) -> Result<User> {
+        let id = Uuid::new_v4().to_string();
+        let now = Utc::now();
+
+        let user = sqlx::query_as!(
+            User,
+            r#"
+            INSERT INTO users (id, username, role, created_at, updated_at)
+            VALUES (?, ?, ?, ?, ?)
+            RETURNING *
+            "#,
+            id,
+            username,
+            role,
+            now,
+            now
+        )
+        .fetch_one(pool)
+        .await?;
+
+        Ok(user)
+    }
+
+    pub async fn list(pool: &SqlitePool) -> Result<Vec<User>> {
+        let users = sqlx::query_as!(User, "SELECT * FROM users ORDER BY username")
+            .fetch_all(pool)
+            .await?;
+
+        Ok(users)
+    }
+
+    pub async fn find_by_id(pool: &SqlitePool, user_id: &str) -> Result<Option<User>> {
+        let user = sqlx::query_as!(User, "SELECT * FROM users WHERE id = ?", user_id)
+            .fetch_optional(pool)
+            .await?;
+
+        Ok(user)
+    }
+
+    pub async fn find_by_username(pool: &SqlitePool, username: &str) -> Result<Option<User>> {
+        let user = sqlx::query_as!(User, "SELECT * FROM users WHERE username = ?", username)
+            .fetch_optional(pool)
+            .await?;
+
+        Ok(user)
+    }
+
+    pub async fn set_role(pool: &SqlitePool, user_id: &str, role: &str) -> Result<User> {
+        let user = sqlx::query_as!(
+            User,
+            "UPDATE users SET role = ?, updated_at = ? WHERE id = ? RETURNING *",
+            role,
+            Utc::now(),
+            user_id
+        )
+        .fetch_optional(pool)
+        .await?
+        .ok_or_else(|| anyhow::anyhow!("User not found: {}", user_id))?;
+
+        Ok(user)
+    }
+
+    pub async fn delete(pool: &SqlitePool, user_id: &str) -> Result<()> {
+        sqlx::query!("DELETE FROM users WHERE id = ?", user_id)
+            .execute(pool)
+            .await?;
+
+        Ok(())
+    }
+
+    pub async fn count_admins(pool: &SqlitePool) -> Result<i64> {
+        let count = sqlx::query_scalar!("SELECT COUNT(*) FROM users WHERE role = 'admin'")
+            .fetch_one(pool)
+            .await?;
+
+        Ok(count as i64)
+    }
+}
+
+pub struct ApiTokenOperations;
+
+impl ApiTokenOperations {
+    pub async fn create(
+        pool: &SqlitePool,
+        user_id: &str,
+        name: &str,
+        token: &str,
+        scope: &str,
+        expires_at: Option<DateTime<Utc>>,
+    ) -> Result<ApiToken> {
+        let id = Uuid::new_v4().to_string();
+        let token_hash = sha256_hex(token.as_bytes());
+
+        let api_token = sqlx::query_as!(
+            ApiToken,
+            r#"
+            INSERT INTO api_tokens (id, user_id, name, token_hash, scope, expires_at, created_at)
+            VALUES (?, ?, ?, ?, ?, ?, ?)
+            RETURNING *
+            "#,
+            id,
+            user_id,
+            name,
+            token_hash,
+            scope,
+            expires_at,
+            Utc::now()
+        )
+        .fetch_one(pool)
+        .await?;
+
+        Ok(api_token)
+    }
+
+    pub async fn list(pool: &SqlitePool, user_id: Option<&str>) -> Result<Vec<ApiToken>> {
+        let tokens = sqlx::query_as!(
+            ApiToken,
+            "SELECT * FROM api_tokens WHERE ?1 IS NULL OR user_id = ?1 ORDER BY created_at DESC",
+            user_id
+        )
+        .fetch_all(pool)
+        .await?;
+
+        Ok(tokens)
+    }
+
+    pub async fn find_by_token(pool: &SqlitePool, token: &str) -> Result<Option<ApiToken>> {
+        let token_hash = sha256_hex(token.as_bytes());
+
+        let api_token = sqlx::query_as!(ApiToken, "SELECT * FROM api_tokens WHERE token_hash = ?", token_hash)
+            .fetch_optional(pool)
+            .await?;
+
+        Ok(api_token)
+    }
+
+    pub async fn touch(pool: &SqlitePool, token_id: &str) -> Result<()> {
+        sqlx::query!("UPDATE api_tokens SET last_used_at = ? WHERE id = ?", Utc::now(), token_id)
+            .execute(pool)
+            .await?;
+
+        Ok(())
+    }
+
+    /// Returns the revoked token, or `None` if it does not exist.
+    pub async fn revoke(pool: &SqlitePool, token_id: &str) -> Result<Option<ApiToken>> {
+        let api_token = sqlx::query_as!(
+            ApiToken,
+            "UPDATE api_tokens SET revoked_at = COALESCE(revoked_at, ?) WHERE id = ? RETURNING *",
+            Utc::now(),
+            token_id
+        )
+        .fetch_optional(pool)
+        .await?;
+
+        Ok(api_token)
+    }
+}
+
+pub struct RawOutputOperations;
+
+impl RawOutputOperations {
+    pub async fn create(pool: &SqlitePool, scan_id: &str, format: &str, output: &[u8]) -> Result<RawOutput> {
+        let id = Uuid::new_v4().to_string();
+        let data = compress(output)?;
+        let original_size = output.len() as i64;
+
+        let raw_output = sqlx::query_as!(
+            RawOutput,
+            r#"
+            INSERT INTO raw_outputs (id, scan_id, format, data, original_size, created_at)
+            VALUES (?, ?, ?, ?, ?, ?)
+            RETURNING *
+            "#,
+            id,
+            scan_id,
+            format,
+            data,
+            original_size,
+            Utc::now()
+        )
+        .fetch_one(pool)
         .await?;
-        
-        Ok(())
-    }
 
-    pub async fn list_all(pool: &SqlitePool) -> Result<Vec<Host>> {
-        let hosts = sqlx::query_as!(Host, "SELECT * FROM hosts ORDER BY created_at DESC")
-            .fetch_all(pool)
-            .await?;
-        
-        Ok(hosts)
+        Ok(raw_output)
     }
 
-    pub async fn get_with_ports(pool: &SqlitePool, host_id: &str) -> Result<(Host, Vec<Port>)> {
-        let host = sqlx::query_as!(Host, "SELECT * FROM hosts WHERE id = ?", host_id)
-            .fetch_one(pool)
-            .await?;
+    pub async fn find_by_scan(pool: &SqlitePool, scan_id: &str) -> Result<Vec<RawOutput>> {
+        let outputs = sqlx::query_as!(
+            RawOutput,
+            "SELECT * FROM raw_outputs WHERE scan_id = ? ORDER BY created_at",
+            scan_id
+        )
+        .fetch_all(pool)
+        .await?;
 
-        let ports = PortOperations::find_by_host(pool, host_id).await?;
-        
-        Ok((host, ports))
+        Ok(outputs)
+    }
+
+    pub fn decompress(output: &RawOutput) -> Result<Vec<u8>> {
+        if !output.compressed {
+            return Ok(output.data.clone());
+        }
+        decompress(&output.data)
     }
 }
 
-pub struct PortOperations;
+pub const CAPTURE_RUNNING: &str = "running";
+pub const CAPTURE_COMPLETED: &str = "completed";
+pub const CAPTURE_STOPPED: &str = "stopped";
+pub const CAPTURE_FAILED: &str = "failed";
 
-impl PortOperations {
-    pub async fn create(
-        pool: &SqlitePool,
-        host_id: &str,
-        number: u16,
-        protocol: &str,
-        state: &str,
-    ) -> Result<Port> {
+pub struct PacketCaptureOperations;
+
+impl PacketCaptureOperations {
+    pub async fn create(pool: &SqlitePool, host_id: &str, interface: &str, filter: &str) -> Result<PacketCapture> {
         let id = Uuid::new_v4().to_string();
-        
-        let port = sqlx::query_as!(
-            Port,
+
+        let capture = sqlx::query_as!(
+            PacketCapture,
             r#"
-            INSERT INTO ports (id, host_id, number, protocol, state, created_at)
+            INSERT INTO packet_captures (id, host_id, interface, filter, status, started_at)
             VALUES (?, ?, ?, ?, ?, ?)
             RETURNING *
             "#,
             id,
             host_id,
-            number as i32,
-            protocol,
-            state,
+            interface,
+            filter,
+            CAPTURE_RUNNING,
             Utc::now()
         )
         .fetch_one(pool)
         .await?;
-        
-        Ok(port)
+
+        Ok(capture)
     }
 
-    pub async fn update_service_info(
+    /// Stores the pcap file of a capture that ended, or why it failed.
+    pub async fn finish(
         pool: &SqlitePool,
-        port_id: &str,
-        service: Option<&str>,
-        version: Option<&str>,
-        banner: Option<&str>,
+        id: &str,
+        status: &str,
+        packets: usize,
+        evicted: usize,
+        pcap: Option<&[u8]>,
+        error: Option<&str>,
     ) -> Result<()> {
+        let data = pcap.map(compress).transpose()?;
+        let original_size = pcap.map_or(0, |pcap| pcap.len() as i64);
+        let packets = packets as i64;
+        let evicted = evicted as i64;
+
         sqlx::query!(
-            "UPDATE ports SET service = ?, version = ?, banner = ? WHERE id = ?",
-            service,
-            version,
-            banner,
-            port_id
+            r#"
+            UPDATE packet_captures
+            SET status = ?, packets = ?, evicted = ?, data = ?, original_size = ?, error = ?, finished_at = ?
+            WHERE id = ?
+            "#,
+            status,
+            packets,
+            evicted,
+            data,
+            original_size,
+            error,
+            Utc::now(),
+            id
         )
         .execute(pool)
         .await?;
-        
+
         Ok(())
     }
 
-    pub async fn find_by_host(pool: &SqlitePool, host_id: &str) -> Result<Vec<Port>> {
-        let ports = sqlx::query_as!(
-            Port,
-            "SELECT * FROM ports WHERE host_id = ? ORDER BY number",
-            host_id
+    pub async fn find_by_id(pool: &SqlitePool, id: &str) -> Result<Option<PacketCapture>> {
+        let capture = sqlx::query_as!(
+            PacketCapture,
+            "SELECT * FROM packet_captures WHERE id = ?",
+            id
         )
-        .fetch_all(pool)
+        .fetch_optional(pool)
         .await?;
-        
-        Ok(ports)
+
+        Ok(capture)
     }
 
-    pub async fn find_open_ports(pool: &SqlitePool, host_id: &str) -> Result<Vec<Port>> {
-        let ports = sqlx::query_as!(
-            Port,
-            "SELECT * FROM ports WHERE host_id = ? AND state = 'open' ORDER BY number",
+    pub async fn find_by_host(pool: &SqlitePool, host_id: &str) -> Result<Vec<PacketCapture>> {
+        let captures = sqlx::query_as!(
+            PacketCapture,
+            "SELECT * FROM packet_captures WHERE host_id = ? ORDER BY started_at DESC",
             host_id
         )
         .fetch_all(pool)
         .await?;
-        
-        Ok(ports)
+
+        Ok(captures)
+    }
+
+    /// The pcap file of a finished capture
+    pub fn decompress(capture: &PacketCapture) -> Result<Option<Vec<u8>>> {
+        capture.data.as_deref().map(decompress).transpose()
     }
 }
 
-pub struct ScanOperations;
+pub struct ReportScheduleOperations;
 
-impl ScanOperations {
+impl ReportScheduleOperations {
     pub async fn create(
         pool: &SqlitePool,
         name: &str,
-        targets: &[IpAddr],
+        targets: &[String],
         scan_type: &str,
-    ) -> Result<Scan> {
+        interval_days: i32,
+        output_dir: Option<&str>,
+        first_run_at: DateTime<Utc>,
+    ) -> Result<ReportSchedule> {
         let id = Uuid::new_v4().to_string();
-        let targets_json = serde_json::to_string(targets)?;
-        
-        let scan = sqlx::query_as!(
-            Scan,
+        let targets = serde_json::to_string(targets)?;
+
+        let schedule = sqlx::query_as!(
+            ReportSchedule,
             r#"
-            INSERT INTO scans (id, name, targets, scan_type, status, progress, start_time, created_at)
-            VALUES (?, ?, ?, ?, 'queued', 0.0, ?, ?)
+            INSERT INTO report_schedules (id, name, targets, scan_type, interval_days, output_dir,
+                enabled, next_run_at, created_at)
+            VALUES (?, ?, ?, ?, ?, ?, 1, ?, ?)
             RETURNING *
             "#,
             id,
             name,
-            targets_json,
+            targets,
             scan_type,
-            Utc::now(),
+            interval_days,
+            output_dir,
+            first_run_at,
             Utc::now()
         )
         .fetch_one(pool)
         .await?;
-        
-        Ok(scan)
+
+        Ok(schedule)
     }
 
-    pub async fn update_progress(pool: &SqlitePool, scan_id: &str, progress: f32) -> Result<()> {
-        sqlx::query!(
-            "UPDATE scans SET progress = ? WHERE id = ?",
-            progress,
-            scan_id
+    pub async fn list(pool: &SqlitePool) -> Result<Vec<ReportSchedule>> {
+        let schedules = sqlx::query_as!(ReportSchedule, "SELECT * FROM report_schedules ORDER BY name")
+            .fetch_all(pool)
+            .await?;
+
+        Ok(schedules)
+    }
+
+    pub async fn find_by_id(pool: &SqlitePool, id: &str) -> Result<Option<ReportSchedule>> {
+        let schedule = sqlx::query_as!(
+            ReportSchedule,
+            "SELECT * FROM report_schedules WHERE id = ?",
+            id
         )
-        .execute(pool)
+        .fetch_optional(pool)
         .await?;
-        
-        Ok(())
+
+        Ok(schedule)
     }
 
-    pub async fn update_status(pool: &SqlitePool, scan_id: &str, status: &str) -> Result<()> {
-        let end_time = if status == "completed" || status == "failed" {
-            Some(Utc::now())
-        } else {
-            None
-        };
+    /// Enabled schedules whose next run is at or before `now`
+    pub async fn due(pool: &SqlitePool, now: DateTime<Utc>) -> Result<Vec<ReportSchedule>> {
+        let schedules = sqlx::query_as!(
+            ReportSchedule,
+            "SELECT * FROM report_schedules WHERE enabled = 1 AND next_run_at <= ? ORDER BY next_run_at",
+            now
+        )
+        .fetch_all(pool)
+        .await?;
+
+        Ok(schedules)
+    }
 
+    pub async fn set_enabled(pool: &SqlitePool, id: &str, enabled: bool) -> Result<bool> {
+        let result = sqlx::query!("UPDATE report_schedules SET enabled = ? WHERE id = ?", enabled, id)
+            .execute(pool)
+            .await?;
+
+        Ok(result.rows_affected() > 0)
+    }
+
+    /// Moves the next run on, from when this one started
+    pub async fn mark_run(pool: &SqlitePool, id: &str, run_at: DateTime<Utc>, next_run_at: DateTime<Utc>) -> Result<()> {
         sqlx::query!(
-            "UPDATE scans SET status = ?, end_time = ? WHERE id = ?",
-            status,
-            end_time,
-            scan_id
+            "UPDATE report_schedules SET last_run_at = ?, next_run_at = ? WHERE id = ?",
+            run_at,
+            next_run_at,
+            id
         )
         .execute(pool)
         .await?;
-        
+
         Ok(())
     }
 
-    pub async fn list_recent(pool: &SqlitePool, limit: i32) -> Result<Vec<Scan>> {
-        let scans = sqlx::query_as!(
-            Scan,
-            "SELECT * FROM scans ORDER BY created_at DESC LIMIT ?",
-            limit
-        )
-        .fetch_all(pool)
-        .await?;
-        
-        Ok(scans)
+    pub async fn delete(pool: &SqlitePool, id: &str) -> Result<bool> {
+        let result = sqlx::query!("DELETE FROM report_schedules WHERE id = ?", id)
+            .execute(pool)
+            .await?;
+
+        Ok(result.rows_affected() > 0)
     }
 }
 
-pub struct VulnerabilityOperations;
+pub struct DifferentialReportOperations;
 
-impl VulnerabilityOperations {
+impl DifferentialReportOperations {
     pub async fn create(
         pool: &SqlitePool,
-        host_id: &str,
-        port_id: Option<&str>,
-        name: &str,
-        severity: &str,
-        description: &str,
-        cvss_score: Option<f32>,
-    ) -> Result<Vulnerability> {
+        schedule_id: &str,
+        snapshot: &str,
+        report: &str,
+        changes: usize,
+    ) -> Result<DifferentialReport> {
         let id = Uuid::new_v4().to_string();
-        
-        let vuln = sqlx::query_as!(
-            Vulnerability,
+        let changes = changes as i32;
+
+        let report = sqlx::query_as!(
+            DifferentialReport,
             r#"
-            INSERT INTO vulnerabilities (id, host_id, port_id, name, severity, description, cvss_score, discovered_at)
-            VALUES (?, ?, ?, ?, ?, ?, ?, ?)
+            INSERT INTO differential_reports (id, schedule_id, snapshot, report, changes, created_at)
+            VALUES (?, ?, ?, ?, ?, ?)
             RETURNING *
             "#,
             id,
-            host_id,
-            port_id,
-            name,
-            severity,
-            description,
-            cvss_score,
+            schedule_id,
+            snapshot,
+            report,
+            changes,
             Utc::now()
         )
         .fetch_one(pool)
         .await?;
-        
-        Ok(vuln)
+
+        Ok(report)
     }
 
-    pub async fn find_by_host(pool: &SqlitePool, host_id: &str) -> Result<Vec<Vulnerability>> {
-        let vulns = sqlx::query_as!(
-            Vulnerability,
-            "SELECT * FROM vulnerabilities WHERE host_id = ? ORDER BY discovered_at DESC",
-            host_id
+    /// The schedule's previous run, which the next one is compared against
+    pub async fn latest(pool: &SqlitePool, schedule_id: &str) -> Result<Option<DifferentialReport>> {
+        let report = sqlx::query_as!(
+            DifferentialReport,
+            "SELECT * FROM differential_reports WHERE schedule_id = ? ORDER BY created_at DESC LIMIT 1",
+            schedule_id
         )
-        .fetch_all(pool)
+        .fetch_optional(pool)
         .await?;
-        
-        Ok(vulns)
+
+        Ok(report)
     }
 
-    pub async fn find_high_severity(pool: &SqlitePool) -> Result<Vec<Vulnerability>> {
-        let vulns = sqlx::query_as!(
-            Vulnerability,
-            "SELECT * FROM vulnerabilities WHERE severity IN ('high', 'critical') ORDER BY discovered_at DESC"
+    pub async fn find_by_schedule(pool: &SqlitePool, schedule_id: &str) -> Result<Vec<DifferentialReport>> {
+        let reports = sqlx::query_as!(
+            DifferentialReport,
+            "SELECT * FROM differential_reports WHERE schedule_id = ? ORDER BY created_at DESC",
+            schedule_id
         )
         .fetch_all(pool)
         .await?;
-        
-        Ok(vulns)
+
+        Ok(reports)
     }
 }
 
-pub struct ProjectOperations;
+pub struct HostStatusOperations;
 
-impl ProjectOperations {
-    pub async fn create(pool: &SqlitePool, name: &str, description: Option<&str>) -> Result<Project> {
+impl HostStatusOperations {
+    /// Records a liveness sample and makes it the host's status.
+    pub async fn record(
+        pool: &SqlitePool,
+        host_id: &str,
+        up: bool,
+        rtt_ms: Option<f64>,
+        source: &str,
+    ) -> Result<()> {
         let id = Uuid::new_v4().to_string();
+        let status = if up { "up" } else { "down" };
+
+        let previous = sqlx::query_scalar!(
+            "SELECT status FROM host_status_history WHERE host_id = ? ORDER BY recorded_at DESC LIMIT 1",
+            host_id
+        )
+        .fetch_optional(pool)
+        .await?;
+
         let now = Utc::now();
-        
-        let project = sqlx::query_as!(
-            Project,
+        let mut tx = pool.begin().await?;
+
+        sqlx::query!(
             r#"
-            INSERT INTO projects (id, name, description, created_at, updated_at)
-            VALUES (?, ?, ?, ?, ?)
-            RETURNING *
+            INSERT INTO host_status_history (id, host_id, status, rtt_ms, source, recorded_at)
+            VALUES (?, ?, ?, ?, ?, ?)
             "#,
             id,
-            name,
-            description,
-            now,
+            host_id,
+            status,
+            rtt_ms,
+            source,
             now
         )
-        .fetch_one(pool)
+        .execute(&mut *tx)
         .await?;
-        
-        Ok(project)
-    }
 
-    pub async fn list_all(pool: &SqlitePool) -> Result<Vec<Project>> {
-        let projects = sqlx::query_as!(
-            Project,
-            "SELECT * FROM projects ORDER BY updated_at DESC"
+        // The host's own status follows its latest sample
+        sqlx::query!(
+            "UPDATE hosts SET status = ?, updated_at = ? WHERE id = ?",
+            status,
+            now,
+            host_id
         )
-        .fetch_all(pool)
+        .execute(&mut *tx)
         .await?;
-        
-        Ok(projects)
-    }
-}
 
-    pub async fn find_by_id(pool: &SqlitePool, project_id: &str) -> Result<Option<Project>> {
-        let project = sqlx::query_as!(
-            Project,
-            "SELECT * FROM projects WHERE id = ?",
-            project_id
-        )
-        .fetch_optional(pool)
-        .await?;
-        
-        Ok(project)
+        tx.commit().await?;
+        QueryCache::invalidate();
+
+        if previous.is_some_and(|previous| previous != status) {
+            HostOperations::notify_if_watched(pool, host_id, &format!("went {} ({})", status, source)).await?;
+        }
+
+        Ok(())
     }
 
-    pub async fn update_description(
+    /// Samples in the window, oldest first, with uptime and mean RTT.
+    pub async fn availability(
         pool: &SqlitePool,
-        project_id: &str,
-        description: Option<&str>,
-    ) -> Result<()> {
-        sqlx::query!(
-            "UPDATE projects SET description = ?, updated_at = ? WHERE id = ?",
-            description,
-            Utc::now(),
-            project_id
+        host_id: &str,
+        since: Option<DateTime<Utc>>,
+        until: Option<DateTime<Utc>>,
+    ) -> Result<HostAvailability> {
+        let samples = sqlx::query_as!(
+            HostStatusSample,
+            r#"
+            SELECT * FROM host_status_history
+            WHERE host_id = ?1
+                AND (?2 IS NULL OR recorded_at >= ?2)
+                AND (?3 IS NULL OR recorded_at <= ?3)
+            ORDER BY recorded_at
+            "#,
+            host_id,
+            since,
+            until
         )
-        .execute(pool)
+        .fetch_all(pool)
         .await?;
-        
-        Ok(())
+
+        let up = samples.iter().filter(|s| s.status == "up").count();
+        let rtts: Vec<f64> = samples.iter().filter_map(|s| s.rtt_ms).collect();
+
+        Ok(HostAvailability {
+            host_id: host_id.to_string(),
+            uptime_percent: (!samples.is_empty()).then(|| up as f64 / samples.len() as f64 * 100.0),
+            average_rtt_ms: (!rtts.is_empty()).then(|| rtts.iter().sum::<f64>() / rtts.len() as f64),
+            samples,
+        })
     }
 }
\ No newline at end of file
@@ -0,0 +1,104 @@
+use serde::ser::{Serialize, SerializeStruct, Serializer};
+use std::io::ErrorKind;
+
+/// Error returned by every command. It reaches the frontend as
+/// `{ code, message, context }` so the UI can react to the kind of failure
+/// (e.g. offer install guidance for a missing tool) instead of parsing text.
+#[derive(Debug, Clone, thiserror::Error)]
+pub enum LegionError {
+    #[error("{0}")]
+    Validation(String),
+    #[error("{tool} is not installed or not on the PATH")]
+    ToolMissing { tool: String },
+    #[error("{0}")]
+    Permission(String),
+    #[error("{0}")]
+    NotFound(String),
+    #[error("Database error: {0}")]
+    Db(String),
+    #[error("{0}")]
+    Timeout(String),
+    #[error("{0}")]
+    Cancelled(String),
+    #[error("{0}")]
+    Internal(String),
+}
+
+impl LegionError {
+    pub fn code(&self) -> &'static str {
+        match self {
+            LegionError::Validation(_) => "validation",
+            LegionError::ToolMissing { .. } => "tool_missing",
+            LegionError::Permission(_) => "permission",
+            LegionError::NotFound(_) => "not_found",
+            LegionError::Db(_) => "db",
+            LegionError::Timeout(_) => "timeout",
+            LegionError::Cancelled(_) => "cancelled",
+            LegionError::Internal(_) => "internal",
+        }
+    }
+
+    fn context(&self) -> Option<serde_json::Value> {
+        match self {
+            LegionError::ToolMissing { tool } => Some(serde_json::json!({ "tool": tool })),
+            _ => None,
+        }
+    }
+
+    /// Wraps a failure to start `tool`, telling a missing binary and a
+    /// permission problem apart from other I/O errors.
+    pub fn spawn(tool: &str, error: std::io::Error) -> anyhow::Error {
+        match error.kind() {
+            ErrorKind::NotFound => LegionError::ToolMissing { tool: tool.to_string() }.into(),
+            ErrorKind::PermissionDenied => {
+                LegionError::Permission(format!("Not permitted to run {}: {}", tool, error)).into()
+            }
+            _ => anyhow::Error::new(error).context(format!("Failed to start {}", tool)),
+        }
+    }
+}
+
+impl Serialize for LegionError {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        let mut error = serializer.serialize_struct("LegionError", 3)?;
+        error.serialize_field("code", self.code())?;
+        error.serialize_field("message", &self.to_string())?;
+        error.serialize_field("context", &self.context())?;
+        error.end()
+    }
+}
+
+/// Classifies an error from the layers below by the first recognizable
+/// cause in its chain. The message keeps the whole chain.
+impl From<anyhow::Error> for LegionError {
+    fn from(error: anyhow::Error) -> Self {
+        let message = format!("{:#}", error);
+
+        for cause in error.chain() {
+            if let Some(legion) = cause.downcast_ref::<LegionError>() {
+                return legion.clone();
+            }
+            if cause.is::<sqlx::Error>() {
+                return LegionError::Db(message);
+            }
+            if cause.is::<tokio::time::error::Elapsed>() {
+                return LegionError::Timeout(message);
+            }
+            if let Some(io) = cause.downcast_ref::<std::io::Error>() {
+                match io.kind() {
+                    ErrorKind::TimedOut => return LegionError::Timeout(message),
+                    ErrorKind::PermissionDenied => return LegionError::Permission(message),
+                    _ => {}
+                }
+            }
+        }
+
+        LegionError::Internal(message)
+    }
+}
+
+impl From<serde_json::Error> for LegionError {
+    fn from(error: serde_json::Error) -> Self {
+        LegionError::Internal(error.to_string())
+    }
+}
\ No newline at end of file
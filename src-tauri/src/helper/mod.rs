@@ -0,0 +1,162 @@
+pub mod protocol;
+
+pub use protocol::{HelperConnection, HelperEndpoint, HelperRequest, HelperResponse};
+
+use crate::config::HelperConfig;
+use crate::error::LegionError;
+use crate::utils::{bounded, channel_settings, BoundedReceiver, OverflowPolicy};
+use anyhow::{Result, Context, bail};
+use base64::Engine;
+use std::net::SocketAddr;
+use std::path::PathBuf;
+use std::process::Stdio;
+use std::time::Duration;
+use tokio::io::{AsyncBufReadExt, AsyncWriteExt, BufReader};
+use tokio::process::{Child, Command};
+use tokio::sync::Mutex;
+use uuid::Uuid;
+
+/// Long enough to answer an elevation prompt
+const STARTUP_TIMEOUT: Duration = Duration::from_secs(120);
+const HELPER_BINARY: &str = "legion2-helper";
+
+/// Starts and talks to the privileged helper, a small separate process
+/// that performs only raw-socket work (SYN scans and packet capture) so the
+/// GUI itself can run unprivileged. The helper listens on loopback and
+/// every connection must present the token it was handed over stdin; it
+/// exits when that stdin closes.
+pub struct PrivilegedHelper {
+    process: Mutex<Option<RunningHelper>>,
+}
+
+struct RunningHelper {
+    child: Child,
+    endpoint: HelperEndpoint,
+}
+
+impl PrivilegedHelper {
+    pub fn new() -> Self {
+        Self {
+            process: Mutex::new(None),
+        }
+    }
+
+    /// Starts the helper, prompting for elevation if configured, or returns
+    /// the one already running.
+    pub async fn start(&self, config: &HelperConfig) -> Result<HelperEndpoint> {
+        if !config.enabled {
+            return Err(LegionError::Validation("The privileged helper is disabled in the configuration".to_string()).into());
+        }
+
+        let mut process = self.process.lock().await;
+        if let Some(running) = process.as_mut() {
+            if running.child.try_wait()?.is_none() {
+                return Ok(running.endpoint.clone());
+            }
+        }
+
+        let binary = Self::binary_path(config)?;
+        let token = format!("{}{}", Uuid::new_v4().simple(), Uuid::new_v4().simple());
+
+        let mut cmd = match config.elevate.split_first() {
+            Some((program, args)) => {
+                let mut cmd = Command::new(program);
+                cmd.args(args).arg(&binary);
+                cmd
+            }
+            None => Command::new(&binary),
+        };
+        cmd.arg("serve")
+            .stdin(Stdio::piped())
+            .stdout(Stdio::piped())
+            .kill_on_drop(true);
+
+        let program = cmd.as_std().get_program().to_string_lossy().into_owned();
+        let mut child = cmd.spawn().map_err(|e| LegionError::spawn(&program, e))?;
+
+        // The token goes over stdin rather than argv, which other users can read
+        let stdin = child.stdin.as_mut().context("Helper stdin unavailable")?;
+        stdin.write_all(format!("{}\n", token).as_bytes()).await?;
+
+        let stdout = child.stdout.take().context("Helper stdout unavailable")?;
+        let mut lines = BufReader::new(stdout).lines();
+        let port = tokio::time::timeout(STARTUP_TIMEOUT, lines.next_line())
+            .await??
+            // Typically a refused elevation prompt
+            .context("Privileged helper exited during startup")?;
+        let port: u16 = port.trim().parse()
+            .with_context(|| format!("Unexpected output from the privileged helper: {}", port))?;
+
+        let endpoint = HelperEndpoint {
+            addr: SocketAddr::from(([127, 0, 0, 1], port)),
+            token,
+            binary,
+        };
+        *process = Some(RunningHelper {
+            child,
+            endpoint: endpoint.clone(),
+        });
+
+        Ok(endpoint)
+    }
+
+    /// Closing the helper's stdin makes it exit, even when it runs as a
+    /// user the app can't signal.
+    pub async fn stop(&self) {
+        self.process.lock().await.take();
+    }
+
+    pub async fn endpoint(&self) -> Option<HelperEndpoint> {
+        let mut process = self.process.lock().await;
+        let running = process.as_mut()?;
+        match running.child.try_wait() {
+            Ok(None) => Some(running.endpoint.clone()),
+            _ => None,
+        }
+    }
+
+    /// Opens a capture on `interface` through the helper. Frames arrive
+    /// until the helper stops or the receiver is dropped.
+    pub async fn capture(endpoint: &HelperEndpoint, interface: &str) -> Result<BoundedReceiver<Vec<u8>>> {
+        let mut connection = HelperConnection::open(endpoint.addr, &endpoint.token).await?;
+        connection.send(&HelperRequest::Capture { interface: interface.to_string() }).await?;
+
+        match connection.receive().await? {
+            Some(HelperResponse::Ready) => {}
+            Some(HelperResponse::Error { message }) => bail!("Helper capture on {} failed: {}", interface, message),
+            _ => bail!("Privileged helper closed the connection"),
+        }
+
+        let (frames_tx, frames_rx) = bounded("capture-frames", channel_settings().observation_capacity, OverflowPolicy::Block);
+        tokio::spawn(async move {
+            while let Ok(Some(response)) = connection.receive::<HelperResponse>().await {
+                let HelperResponse::Frame { data } = response else {
+                    break;
+                };
+                let Ok(frame) = base64::engine::general_purpose::STANDARD.decode(data) else {
+                    continue;
+                };
+                if frames_tx.send(frame).await.is_err() {
+                    break;
+                }
+            }
+        });
+
+        Ok(frames_rx)
+    }
+
+    fn binary_path(config: &HelperConfig) -> Result<PathBuf> {
+        if let Some(path) = &config.path {
+            return Ok(PathBuf::from(path));
+        }
+
+        let exe = std::env::current_exe().context("Failed to locate the app binary")?;
+        Ok(exe.with_file_name(format!("{}{}", HELPER_BINARY, std::env::consts::EXE_SUFFIX)))
+    }
+}
+
+impl Default for PrivilegedHelper {
+    fn default() -> Self {
+        Self::new()
+    }
+}
\ No newline at end of file
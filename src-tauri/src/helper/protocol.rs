@@ -0,0 +1,135 @@
+// Shared with the helper binary (src/bin/legion2-helper.rs), which includes
+// this file by path; keep it free of crate-internal imports.
+
+use anyhow::{Result, bail};
+use serde::{Deserialize, Serialize};
+use std::net::SocketAddr;
+use std::path::PathBuf;
+use tokio::io::{AsyncBufReadExt, AsyncWriteExt, BufReader, Lines};
+use tokio::net::TcpStream;
+use tokio::net::tcp::{OwnedReadHalf, OwnedWriteHalf};
+
+/// Environment the relay reads its connection details from
+pub const ADDR_ENV: &str = "LEGION2_HELPER_ADDR";
+pub const TOKEN_ENV: &str = "LEGION2_HELPER_TOKEN";
+
+/// Where a running helper listens and how to authenticate to it.
+#[derive(Debug, Clone)]
+pub struct HelperEndpoint {
+    pub addr: SocketAddr,
+    pub token: String,
+    /// The helper binary, also used unprivileged as the relay
+    pub binary: PathBuf,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(tag = "op", rename_all = "snake_case")]
+pub enum HelperRequest {
+    /// Stream raw frames from an interface
+    Capture { interface: String },
+    /// Run nmap or masscan with the given arguments
+    Scan { tool: String, args: Vec<String> },
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub enum HelperResponse {
+    /// The operation started; output follows
+    Ready,
+    /// Base64 encoded frame
+    Frame { data: String },
+    Stdout { line: String },
+    Stderr { line: String },
+    Exit { code: i32 },
+    Error { message: String },
+}
+
+pub struct HelperConnection {
+    pub lines: Lines<BufReader<OwnedReadHalf>>,
+    pub writer: OwnedWriteHalf,
+}
+
+impl HelperConnection {
+    /// Connects and authenticates; the token is the first line sent.
+    pub async fn open(addr: SocketAddr, token: &str) -> Result<Self> {
+        let stream = TcpStream::connect(addr).await?;
+        let (reader, mut writer) = stream.into_split();
+        writer.write_all(format!("{}\n", token).as_bytes()).await?;
+        Ok(Self {
+            lines: BufReader::new(reader).lines(),
+            writer,
+        })
+    }
+
+    pub async fn send<T: Serialize>(&mut self, message: &T) -> Result<()> {
+        let mut line = serde_json::to_string(message)?;
+        line.push('\n');
+        self.writer.write_all(line.as_bytes()).await?;
+        Ok(())
+    }
+
+    /// `None` once the other side has closed the connection.
+    pub async fn receive<T: for<'de> Deserialize<'de>>(&mut self) -> Result<Option<T>> {
+        match self.lines.next_line().await? {
+            Some(line) => Ok(Some(serde_json::from_str(&line)?)),
+            None => Ok(None),
+        }
+    }
+}
+
+/// Options that would let a caller read or write arbitrary files, or load
+/// code, with the helper's privileges. nmap accepts unambiguous
+/// abbreviations and single-dash long options, so prefixes are matched
+/// in both directions for it.
+const NMAP_DENIED: &[&str] = &[
+    "script", "datadir", "servicedb", "versiondb", "iL", "excludefile",
+    "resume", "stylesheet", "append-output", "oN", "oG", "oS", "oA", "oM", "oH", "oX",
+];
+const MASSCAN_DENIED: &[&str] = &[
+    "c", "conf", "resume", "readscan", "pcap", "iL", "includefile", "excludefile",
+    "hello-file", "nmap-payloads", "pcap-payloads", "nmap-service-probes",
+    "output-filename", "oX", "oG", "oJ", "oL", "oB", "oD", "oU", "oH",
+];
+
+/// Output options allowed only when writing to stdout
+const NMAP_STDOUT: &str = "oX";
+const MASSCAN_STDOUT: &str = "output-filename";
+
+/// Checks arguments the helper is asked to run a scanner with.
+pub fn check_scanner_args(tool: &str, args: &[String]) -> Result<()> {
+    let (denied, stdout_option, abbreviations) = match tool {
+        "nmap" => (NMAP_DENIED, NMAP_STDOUT, true),
+        "masscan" => (MASSCAN_DENIED, MASSCAN_STDOUT, false),
+        other => bail!("{} can't be run through the helper", other),
+    };
+
+    let mut args = args.iter();
+    while let Some(arg) = args.next() {
+        if !arg.starts_with('-') || arg == "-" {
+            continue;
+        }
+
+        let option = arg.trim_start_matches('-');
+        let (name, inline_value) = match option.split_once('=') {
+            Some((name, value)) => (name, Some(value)),
+            None => (option, None),
+        };
+
+        if name == stdout_option {
+            let value = inline_value.or_else(|| args.next().map(String::as_str));
+            if value != Some("-") {
+                bail!("{} may only write to stdout through the helper", arg);
+            }
+            continue;
+        }
+
+        let blocked = denied.iter().any(|d| {
+            name == *d || (abbreviations && (name.starts_with(d) || (name.len() >= 2 && d.starts_with(name))))
+        });
+        if blocked {
+            bail!("{} is not allowed through the helper", arg);
+        }
+    }
+
+    Ok(())
+}
\ No newline at end of file
@@ -1,44 +1,89 @@
 #![cfg_attr(not(debug_assertions), windows_subsystem = "windows")]
 
+mod auth;
 mod scanning;
 mod commands;
+mod config;
 mod database;
+mod error;
+mod helper;
+mod reporting;
+mod server;
+mod sniffer;
+mod sources;
 mod utils;
 
 use commands::*;
 use scanning::*;
+use config::{default_config_path, ConfigManager};
+use utils::{bounded, configure_channels, BoundedReceiver, OverflowPolicy};
 use database::Database;
+use helper::PrivilegedHelper;
+use reporting::ReportScheduler;
+use server::ApiServer;
+use sniffer::{NetSniffer, host_capture::HostCaptures};
+use sources::BleSurvey;
 use std::sync::Arc;
-use tokio::sync::{mpsc, RwLock};
 use anyhow::Result;
 
 #[derive(Clone)]
 pub struct AppState {
     pub scan_coordinator: Arc<ScanCoordinator>,
-    pub scan_results: Arc<RwLock<Vec<ScanResult>>>,
     pub database: Arc<Database>,
+    pub config: Arc<ConfigManager>,
+    pub net_sniffer: Arc<NetSniffer>,
+    pub host_captures: Arc<HostCaptures>,
+    pub helper: Arc<PrivilegedHelper>,
+    pub host_monitor: Arc<HostMonitor>,
+    pub ble_survey: Arc<BleSurvey>,
 }
 
-async fn initialize_database() -> Result<Arc<Database>> {
+async fn initialize_database(path: &str) -> Result<Arc<Database>> {
     // Create database directory if it doesn't exist
-    tokio::fs::create_dir_all("data").await?;
+    if let Some(dir) = std::path::Path::new(path).parent() {
+        tokio::fs::create_dir_all(dir).await?;
+    }
     
-    let database = Database::new("sqlite:data/legion2.db").await?;
+    let database = Database::new(&format!("sqlite:{}", path)).await?;
     Ok(Arc::new(database))
 }
 
+async fn setup_config_watcher(
+    config: Arc<ConfigManager>,
+    scan_coordinator: Arc<ScanCoordinator>,
+    window: tauri::Window,
+) {
+    let mut interval = tokio::time::interval(tokio::time::Duration::from_secs(2));
+
+    loop {
+        interval.tick().await;
+
+        match config.reload_if_changed() {
+            Ok(Some(update)) => {
+                scan_coordinator.apply_config(&update.config);
+                let _ = window.emit("config-changed", &update);
+            }
+            Ok(None) => {}
+            Err(e) => eprintln!("Failed to reload config: {}", e),
+        }
+    }
+}
+
+async fn setup_event_forwarder(
+    mut events_rx: BoundedReceiver<FrontendEvent>,
+    window: tauri::Window,
+) {
+    while let Some(event) = events_rx.recv().await {
+        let _ = window.emit(event.name, &event.payload);
+    }
+}
+
 async fn setup_result_handler(
-    results_storage: Arc<RwLock<Vec<ScanResult>>>,
-    mut results_rx: mpsc::Receiver<ScanResult>,
+    mut results_rx: BoundedReceiver<ScanResult>,
     window: tauri::Window,
 ) {
+    // Results are persisted by the coordinator and queried from the database
     while let Some(result) = results_rx.recv().await {
-        // Store in memory
-        {
-            let mut results = results_storage.write().await;
-            results.push(result.clone());
-        }
-        
         // Emit to frontend
         let _ = window.emit("scan-result", &result);
         
@@ -50,35 +95,74 @@ async fn setup_result_handler(
 
 #[tokio::main]
 async fn main() -> Result<()> {
+    // Before anything touches pcap
+    sniffer::capture::CaptureBackend::init();
+
     // Initialize logging
     env_logger::Builder::from_default_env()
         .filter_level(log::LevelFilter::Info)
         .init();
 
+    // Load configuration
+    let config = Arc::new(ConfigManager::load(default_config_path())?);
+    let app_config = config.get();
+
     // Initialize database
-    let database = initialize_database().await?;
+    let database = initialize_database(&app_config.database.path).await?;
     
-    // Create result channels
-    let (results_tx, results_rx) = mpsc::channel(1000);
+    let limits = app_config.concurrency_limits();
+    println!(
+        "Concurrency: {} scans, {} nmap, {} masscan, channel capacity {}",
+        limits.max_concurrent_scans, limits.nmap_concurrency, limits.masscan_concurrency, limits.channel_capacity
+    );
+
+    // Create result channels. Results wait for room; events are shed as
+    // configured so a busy frontend can't stall scans
+    configure_channels(&app_config.channels);
+    configure_severity(&app_config.severity);
+    let (results_tx, results_rx) = bounded("results", limits.channel_capacity, OverflowPolicy::Block);
+    let (events_tx, events_rx) = bounded("events", limits.channel_capacity, app_config.channels.event_policy);
     
     // Initialize scan coordinator
-    let scan_coordinator = Arc::new(ScanCoordinator::new(database.clone(), results_tx));
-    let scan_results = Arc::new(RwLock::new(Vec::new()));
+    let scan_coordinator = Arc::new(ScanCoordinator::new(
+        database.clone(),
+        results_tx,
+        events_tx,
+        &app_config,
+    ));
+
+    // Run report schedules as they come due
+    ReportScheduler::start(database.clone(), scan_coordinator.clone());
 
     let app_state = AppState {
-        scan_coordinator,
-        scan_results: scan_results.clone(),
+        scan_coordinator: scan_coordinator.clone(),
         database,
+        config: config.clone(),
+        net_sniffer: Arc::new(NetSniffer::new()),
+        host_captures: Arc::new(HostCaptures::new()),
+        helper: Arc::new(PrivilegedHelper::new()),
+        host_monitor: Arc::new(HostMonitor::new()),
+        ble_survey: Arc::new(BleSurvey::new()),
     };
 
+    // Serve the token-authenticated API when this is a shared instance
+    if app_config.server.enabled {
+        ApiServer::start(&app_config.server.bind, app_state.clone()).await?;
+    }
+
     tauri::Builder::default()
         .manage(app_state)
         .setup(|app| {
             let window = app.get_window("main").unwrap();
             
+            // Forward coordinator alerts
+            tokio::spawn(setup_event_forwarder(events_rx, window.clone()));
+
+            // Pick up edits to the config file
+            tokio::spawn(setup_config_watcher(config, scan_coordinator, window.clone()));
+
             // Setup result handler
             tokio::spawn(setup_result_handler(
-                scan_results,
                 results_rx,
                 window,
             ));
@@ -87,16 +171,148 @@ async fn main() -> Result<()> {
         })
         .invoke_handler(tauri::generate_handler![
             start_scan,
+            create_massmap_plan,
+            execute_massmap_plan,
+            resume_massmap_plan,
             cancel_scan,
+            pause_scan,
+            resume_scan,
+            resume_interrupted_scan,
+            list_interrupted_scans,
+            resume_masscan,
+            cancel_campaign,
+            cancel_all_scans,
+            delete_scan,
             get_scan_results,
+            get_scan_output,
             get_active_scans,
             scan_network_range,
+            rescan_project,
             get_scan_statistics,
+            verify_scan_integrity,
+            reparse_scan,
+            get_scan_details,
+            set_bandwidth_budget,
+            get_bandwidth_usage,
+            get_pipeline_metrics,
+            get_channel_metrics,
             get_hosts,
             get_host_details,
+            set_hosts_approved,
+            set_hosts_watched,
+            get_watchlist,
+            bulk_hosts_action,
+            get_host_tags,
+            bulk_update_hosts,
+            get_rogue_devices,
+            get_baseline_summary,
+            get_dashboard_summary,
+            get_subnet_summary,
+            get_topology,
+            get_pivot_candidates,
+            enumerate_smb_shares,
+            probe_udp_services,
+            list_interfaces,
+            get_capture_capabilities,
+            list_nse_scripts,
+            get_top_ports,
+            summarize_targets,
+            list_port_presets,
+            create_port_preset,
+            update_port_preset,
+            delete_port_preset,
+            list_follow_up_rules,
+            create_follow_up_rule,
+            update_follow_up_rule,
+            delete_follow_up_rule,
+            get_host_shares,
+            get_os_observations,
+            audit_ssh,
+            get_ssh_audits,
+            check_anonymous_access,
+            get_anonymous_access,
+            test_smtp_relay,
+            check_datastore,
+            check_smb_security,
+            probe_ipmi,
+            get_service_checks,
+            classify_device,
+            ptr_sweep,
+            get_iot_devices,
+            get_ics_assets,
+            enumerate_port_mappings,
+            get_port_mappings,
             get_vulnerabilities,
+            create_vulnerability,
+            update_vulnerability,
+            verify_fixed,
+            get_finding_verifications,
+            delete_vulnerability,
+            set_vulnerability_threat_intel,
+            recompute_risk_scores,
+            get_events,
+            get_notifications,
+            mark_read,
             create_project,
-            list_projects
+            update_project_engagement,
+            generate_report,
+            list_project_scope,
+            add_project_scope_entry,
+            remove_project_scope_entry,
+            get_audit_log,
+            create_user,
+            list_users,
+            set_user_role,
+            delete_user,
+            create_api_token,
+            list_api_tokens,
+            revoke_api_token,
+            list_projects,
+            open_project,
+            close_project,
+            get_active_project,
+            get_project_settings,
+            test_proxy,
+            test_ssh_target,
+            update_project_settings,
+            start_sniffer,
+            stop_sniffer,
+            get_passive_dns,
+            get_host_flows,
+            import_pcap,
+            get_tls_sessions,
+            get_tls_fingerprints,
+            get_hosts_by_tls_fingerprint,
+            get_http_metadata,
+            get_http_software_inventory,
+            get_http_software_hosts,
+            get_stale_ports,
+            get_dependencies,
+            rebuild_dependencies,
+            create_report_schedule,
+            list_report_schedules,
+            set_report_schedule_enabled,
+            delete_report_schedule,
+            run_report_schedule,
+            get_differential_reports,
+            start_capture,
+            stop_capture,
+            get_host_captures,
+            export_capture,
+            start_privileged_helper,
+            stop_privileged_helper,
+            start_ble_survey,
+            stop_ble_survey,
+            import_wireless_survey,
+            get_wireless_devices,
+            ingest_cloud_assets,
+            get_cloud_assets,
+            discover_ct_subdomains,
+            start_host_monitor,
+            stop_host_monitor,
+            get_host_availability,
+            get_config,
+            set_config
         ])
         .run(tauri::generate_context!())
         .expect("error while running tauri application");
@@ -0,0 +1,534 @@
+use crate::database::{Database, models::*, operations::*};
+use crate::scanning::{RiskScorer, ScanCoordinator, ScanOptions, ScanType};
+use crate::utils::progress_channel;
+use anyhow::Result;
+use chrono::{DateTime, Utc};
+use cidr::IpCidr;
+use serde::{Deserialize, Serialize};
+use sqlx::SqlitePool;
+use std::collections::BTreeMap;
+use std::fmt::Write;
+use std::net::IpAddr;
+use std::path::Path;
+use std::sync::Arc;
+use std::time::Duration;
+
+/// Hosts listed in the report's top risks section
+const TOP_RISK_HOSTS: i32 = 10;
+/// How often the scheduler looks for report schedules that are due
+const SCHEDULE_POLL_INTERVAL: Duration = Duration::from_secs(60);
+
+pub const CHANGE_NEW_HOST: &str = "new_host";
+pub const CHANGE_REMOVED_HOST: &str = "removed_host";
+pub const CHANGE_HOST_STATUS: &str = "host_status";
+pub const CHANGE_OS: &str = "os";
+pub const CHANGE_PORT_OPENED: &str = "port_opened";
+pub const CHANGE_PORT_CLOSED: &str = "port_closed";
+pub const CHANGE_SERVICE: &str = "service";
+pub const CHANGE_NEW_FINDING: &str = "new_finding";
+pub const CHANGE_RESOLVED_FINDING: &str = "resolved_finding";
+
+/// Report sections, in the order they're written
+const CHANGE_SECTIONS: &[(&str, &str)] = &[
+    (CHANGE_NEW_HOST, "New hosts"),
+    (CHANGE_REMOVED_HOST, "Hosts no longer recorded"),
+    (CHANGE_HOST_STATUS, "Host status changes"),
+    (CHANGE_OS, "Operating system changes"),
+    (CHANGE_PORT_OPENED, "Newly open ports"),
+    (CHANGE_PORT_CLOSED, "Ports no longer open"),
+    (CHANGE_SERVICE, "Service changes"),
+    (CHANGE_NEW_FINDING, "New findings"),
+    (CHANGE_RESOLVED_FINDING, "Findings no longer present"),
+];
+
+/// Renders project findings as a Markdown report.
+pub struct ReportGenerator;
+
+impl ReportGenerator {
+    pub async fn generate_markdown(database: &Database, project_id: &str) -> Result<String> {
+        let project = ProjectOperations::find_by_id(database.main_pool(), project_id)
+            .await?
+            .ok_or_else(|| anyhow::anyhow!("Project not found: {}", project_id))?;
+
+        let mut report = String::new();
+        Self::write_engagement(&mut report, &project)?;
+
+        let pool = database.pool();
+        RiskScorer::update_all(&pool).await?;
+        Self::write_top_risks(&mut report, &HostOperations::list_top_risks(&pool, TOP_RISK_HOSTS).await?)?;
+
+        let hosts = HostOperations::list_all(&pool).await?;
+
+        writeln!(report, "## Hosts\n")?;
+        if hosts.is_empty() {
+            writeln!(report, "No hosts recorded.\n")?;
+        }
+
+        for host in &hosts {
+            let ports = PortOperations::find_open_ports(&pool, &host.id).await?;
+            let vulnerabilities = VulnerabilityOperations::find_by_host(&pool, &host.id).await?;
+            Self::write_host(&mut report, host, &ports, &vulnerabilities)?;
+        }
+
+        Ok(report)
+    }
+
+    fn write_engagement(report: &mut String, project: &Project) -> Result<()> {
+        writeln!(report, "# {}\n", project.name)?;
+
+        if let Some(client) = &project.client_name {
+            writeln!(report, "**Client:** {}  ", client)?;
+        }
+        match (project.start_date, project.end_date) {
+            (Some(start), Some(end)) => writeln!(report, "**Engagement window:** {} to {}  ", start, end)?,
+            (Some(start), None) => writeln!(report, "**Engagement start:** {}  ", start)?,
+            (None, Some(end)) => writeln!(report, "**Engagement end:** {}  ", end)?,
+            (None, None) => {}
+        }
+        writeln!(report, "**Generated:** {}\n", chrono::Utc::now().format("%Y-%m-%d %H:%M UTC"))?;
+
+        if let Some(description) = &project.description {
+            writeln!(report, "{}\n", description)?;
+        }
+        if let Some(scope) = &project.scope_statement {
+            writeln!(report, "## Scope\n\n{}\n", scope)?;
+        }
+        if let Some(roe) = &project.rules_of_engagement {
+            writeln!(report, "## Rules of Engagement\n\n{}\n", roe)?;
+        }
+
+        Ok(())
+    }
+
+    fn write_top_risks(report: &mut String, hosts: &[Host]) -> Result<()> {
+        if hosts.is_empty() {
+            return Ok(());
+        }
+
+        writeln!(report, "## Top Risks\n")?;
+        writeln!(report, "| Host | Risk | OS |")?;
+        writeln!(report, "|------|------|----|")?;
+        for host in hosts {
+            let name = match &host.hostname {
+                Some(hostname) => format!("{} ({})", host.ip, hostname),
+                None => host.ip.clone(),
+            };
+            writeln!(
+                report,
+                "| {} | {:.1} | {} |",
+                name,
+                host.risk_score.unwrap_or(0.0),
+                host.os_name.as_deref().unwrap_or("-"),
+            )?;
+        }
+        writeln!(report)?;
+
+        Ok(())
+    }
+
+    fn write_host(
+        report: &mut String,
+        host: &Host,
+        ports: &[Port],
+        vulnerabilities: &[Vulnerability],
+    ) -> Result<()> {
+        match &host.hostname {
+            Some(hostname) => writeln!(report, "### {} ({})\n", host.ip, hostname)?,
+            None => writeln!(report, "### {}\n", host.ip)?,
+        }
+
+        if let Some(os) = &host.os_name {
+            writeln!(report, "OS: {} ({})\n", os, Self::provenance(host.os_source.as_deref(), host.os_accuracy))?;
+        }
+
+        if !ports.is_empty() {
+            writeln!(report, "| Port | Service | Version | Source |")?;
+            writeln!(report, "|------|---------|---------|--------|")?;
+            for port in ports {
+                writeln!(
+                    report,
+                    "| {}/{} | {} | {} | {} |",
+                    port.number,
+                    port.protocol,
+                    port.service.as_deref().unwrap_or("-"),
+                    port.version.as_deref().unwrap_or("-"),
+                    Self::provenance(port.source.as_deref(), port.confidence),
+                )?;
+            }
+            writeln!(report)?;
+        }
+
+        for vuln in vulnerabilities {
+            writeln!(
+                report,
+                "- **[{}] {}** ({}): {}",
+                vuln.severity,
+                vuln.name,
+                Self::provenance(vuln.source.as_deref(), vuln.confidence),
+                vuln.description,
+            )?;
+        }
+        if !vulnerabilities.is_empty() {
+            writeln!(report)?;
+        }
+
+        Ok(())
+    }
+
+    /// What reported a fact and the confidence in it, e.g. `nmap, 100%`
+    fn provenance(source: Option<&str>, confidence: Option<f32>) -> String {
+        match (source, confidence) {
+            (Some(source), Some(confidence)) => format!("{}, {:.0}%", source, confidence),
+            (Some(source), None) => source.to_string(),
+            (None, _) => "unknown source".to_string(),
+        }
+    }
+}
+
+/// The state of the project's hosts at one point in time, kept with each
+/// differential report for the next one to be compared against.
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct ProjectSnapshot {
+    pub hosts: BTreeMap<String, HostSnapshot>,
+}
+
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct HostSnapshot {
+    pub hostname: Option<String>,
+    pub status: String,
+    pub os_name: Option<String>,
+    /// Service and version by `number/protocol`
+    pub ports: BTreeMap<String, String>,
+    /// Severity by finding name
+    pub findings: BTreeMap<String, String>,
+}
+
+/// One difference between two snapshots.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct SnapshotChange {
+    /// One of the `CHANGE_*` kinds
+    pub kind: String,
+    pub host: String,
+    pub detail: String,
+}
+
+impl ProjectSnapshot {
+    /// The hosts covered by `targets`, which are addresses, CIDR ranges or
+    /// hostnames as a schedule lists them. Hosts the schedule doesn't scan
+    /// are left out, so they never show up as changes in its reports.
+    pub async fn capture(pool: &SqlitePool, targets: &[String]) -> Result<Self> {
+        let networks: Vec<IpCidr> = targets.iter()
+            .filter_map(|target| {
+                target.parse::<IpCidr>().ok()
+                    .or_else(|| target.parse::<IpAddr>().ok().map(IpCidr::new_host))
+            })
+            .collect();
+        let covered = |host: &Host| {
+            host.ip.parse::<IpAddr>().is_ok_and(|ip| networks.iter().any(|network| network.contains(&ip)))
+                || host.hostname.as_deref().is_some_and(|name| targets.iter().any(|t| t.eq_ignore_ascii_case(name)))
+        };
+
+        let mut hosts = BTreeMap::new();
+        for host in HostOperations::list_all(pool).await? {
+            if !covered(&host) {
+                continue;
+            }
+
+            let ports = PortOperations::find_open_ports(pool, &host.id).await?
+                .into_iter()
+                .map(|port| {
+                    let service = [port.service, port.version].into_iter().flatten().collect::<Vec<_>>().join(" ");
+                    (format!("{}/{}", port.number, port.protocol), service)
+                })
+                .collect();
+            let findings = VulnerabilityOperations::find_by_host(pool, &host.id).await?
+                .into_iter()
+                .map(|vuln| (vuln.name, vuln.severity))
+                .collect();
+
+            hosts.insert(host.ip, HostSnapshot {
+                hostname: host.hostname,
+                status: host.status,
+                os_name: host.os_name,
+                ports,
+                findings,
+            });
+        }
+
+        Ok(Self { hosts })
+    }
+
+    /// What changed from `self` to `current`
+    pub fn diff(&self, current: &ProjectSnapshot) -> Vec<SnapshotChange> {
+        let mut changes = Vec::new();
+        let mut change = |kind: &str, host: &str, detail: String| changes.push(SnapshotChange {
+            kind: kind.to_string(),
+            host: host.to_string(),
+            detail,
+        });
+
+        for (ip, before) in &self.hosts {
+            if !current.hosts.contains_key(ip) {
+                change(CHANGE_REMOVED_HOST, ip, before.hostname.clone().unwrap_or_default());
+            }
+        }
+
+        let empty = HostSnapshot::default();
+        for (ip, after) in &current.hosts {
+            let before = match self.hosts.get(ip) {
+                Some(before) => before,
+                None => {
+                    change(CHANGE_NEW_HOST, ip, after.hostname.clone().unwrap_or_default());
+                    &empty
+                }
+            };
+
+            if self.hosts.contains_key(ip) {
+                if before.status != after.status {
+                    change(CHANGE_HOST_STATUS, ip, format!("{} → {}", before.status, after.status));
+                }
+                if before.os_name != after.os_name {
+                    change(CHANGE_OS, ip, format!(
+                        "{} → {}",
+                        before.os_name.as_deref().unwrap_or("unknown"),
+                        after.os_name.as_deref().unwrap_or("unknown"),
+                    ));
+                }
+            }
+
+            for (port, service) in &after.ports {
+                match before.ports.get(port) {
+                    None => change(CHANGE_PORT_OPENED, ip, Self::describe(port, service)),
+                    Some(previous) if previous != service && !service.is_empty() => change(
+                        CHANGE_SERVICE,
+                        ip,
+                        format!("{}: {} → {}", port, if previous.is_empty() { "unknown" } else { previous }, service),
+                    ),
+                    Some(_) => {}
+                }
+            }
+            for (port, service) in &before.ports {
+                if !after.ports.contains_key(port) {
+                    change(CHANGE_PORT_CLOSED, ip, Self::describe(port, service));
+                }
+            }
+
+            for (name, severity) in &after.findings {
+                if !before.findings.contains_key(name) {
+                    change(CHANGE_NEW_FINDING, ip, format!("[{}] {}", severity, name));
+                }
+            }
+            for (name, severity) in &before.findings {
+                if !after.findings.contains_key(name) {
+                    change(CHANGE_RESOLVED_FINDING, ip, format!("[{}] {}", severity, name));
+                }
+            }
+        }
+
+        changes
+    }
+
+    fn describe(port: &str, service: &str) -> String {
+        match service.is_empty() {
+            true => port.to_string(),
+            false => format!("{} ({})", port, service),
+        }
+    }
+}
+
+impl ReportGenerator {
+    /// Renders a "what changed" report for one run of a schedule. Without
+    /// a previous run, everything found is new.
+    pub fn generate_diff_markdown(
+        schedule: &ReportSchedule,
+        since: Option<DateTime<Utc>>,
+        changes: &[SnapshotChange],
+        scan_errors: &[String],
+    ) -> Result<String> {
+        let mut report = String::new();
+        let now = Utc::now();
+
+        writeln!(report, "# {}: what changed\n", schedule.name)?;
+        match since {
+            Some(since) => writeln!(
+                report,
+                "**Period:** {} to {}  ",
+                since.format("%Y-%m-%d %H:%M UTC"),
+                now.format("%Y-%m-%d %H:%M UTC"),
+            )?,
+            None => writeln!(report, "**Period:** first report, everything below is the starting point  ")?,
+        }
+        writeln!(report, "**Targets:** {}  ", Self::schedule_targets(schedule).join(", "))?;
+        writeln!(report, "**Changes:** {}\n", changes.len())?;
+
+        if changes.is_empty() {
+            writeln!(report, "No changes since the last report.\n")?;
+        }
+
+        for (kind, title) in CHANGE_SECTIONS {
+            let section: Vec<&SnapshotChange> = changes.iter().filter(|c| c.kind == *kind).collect();
+            if section.is_empty() {
+                continue;
+            }
+
+            writeln!(report, "## {}\n", title)?;
+            for change in section {
+                match change.detail.is_empty() {
+                    true => writeln!(report, "- **{}**", change.host)?,
+                    false => writeln!(report, "- **{}**: {}", change.host, change.detail)?,
+                }
+            }
+            writeln!(report)?;
+        }
+
+        if !scan_errors.is_empty() {
+            writeln!(report, "## Scan errors\n")?;
+            for error in scan_errors {
+                writeln!(report, "- {}", error)?;
+            }
+            writeln!(report)?;
+        }
+
+        Ok(report)
+    }
+
+    fn schedule_targets(schedule: &ReportSchedule) -> Vec<String> {
+        serde_json::from_str(&schedule.targets).unwrap_or_default()
+    }
+}
+
+/// Runs report schedules when they're due: re-scans their targets, then
+/// compares the project against the previous run and stores the changes
+/// as a report. Schedules live in the project database, so they run
+/// while their project is open.
+pub struct ReportScheduler;
+
+impl ReportScheduler {
+    pub fn start(database: Arc<Database>, coordinator: Arc<ScanCoordinator>) {
+        tokio::spawn(async move {
+            let mut ticker = tokio::time::interval(SCHEDULE_POLL_INTERVAL);
+
+            loop {
+                ticker.tick().await;
+
+                // Schedules belong to whichever project is open now
+                let pool = database.pool();
+                let project_id = database.open_project_id();
+                let due = match ReportScheduleOperations::due(&pool, Utc::now()).await {
+                    Ok(due) => due,
+                    Err(e) => {
+                        eprintln!("Failed to load report schedules: {}", e);
+                        continue;
+                    }
+                };
+                for schedule in due {
+                    // Moved on before the run so a long one isn't started twice
+                    let now = Utc::now();
+                    let next = now + chrono::Duration::days(schedule.interval_days as i64);
+                    if let Err(e) = ReportScheduleOperations::mark_run(&pool, &schedule.id, now, next).await {
+                        eprintln!("Failed to update report schedule {}: {}", schedule.id, e);
+                        continue;
+                    }
+
+                    let database = database.clone();
+                    let coordinator = coordinator.clone();
+                    let project_id = project_id.clone();
+                    tokio::spawn(async move {
+                        if database.open_project_id() != project_id {
+                            eprintln!("Skipped report schedule {}: its project was closed", schedule.name);
+                            return;
+                        }
+                        if let Err(e) = Self::run(&database, &coordinator, &schedule).await {
+                            eprintln!("Report schedule {} failed: {}", schedule.name, e);
+                        }
+                    });
+                }
+            }
+        });
+    }
+
+    /// Re-scans the schedule's targets and reports what changed since its
+    /// previous run. Targets that fail to scan are listed in the report
+    /// rather than failing it.
+    pub async fn run(
+        database: &Database,
+        coordinator: &ScanCoordinator,
+        schedule: &ReportSchedule,
+    ) -> Result<DifferentialReport> {
+        let scan_type = match schedule.scan_type.as_str() {
+            "quick" => ScanType::Quick,
+            "comprehensive" => ScanType::Comprehensive,
+            "stealth" => ScanType::Stealth,
+            "ot" => ScanType::Ot,
+            _ => ScanType::Quick,
+        };
+
+        // The schedule's project, even if another one is opened meanwhile
+        let pool = database.pool();
+        let targets = ReportGenerator::schedule_targets(schedule);
+
+        let mut scan_errors = Vec::new();
+        for target in &targets {
+            let cidr = match target.parse::<IpAddr>() {
+                Ok(IpAddr::V4(ip)) => format!("{}/32", ip),
+                Ok(IpAddr::V6(ip)) => format!("{}/128", ip),
+                Err(_) => target.clone(),
+            };
+
+            let (progress_tx, mut progress_rx) = progress_channel();
+            tokio::spawn(async move { while progress_rx.recv().await.is_some() {} });
+
+            match coordinator.scan_network_range(&cidr, &[], scan_type.clone(), ScanOptions::default(), progress_tx).await {
+                Ok(campaign) => coordinator.wait_for_campaign_end(campaign.campaign_id).await,
+                Err(e) => scan_errors.push(format!("{}: {}", target, e)),
+            }
+        }
+
+        let previous = DifferentialReportOperations::latest(&pool, &schedule.id).await?;
+        let (baseline, since) = match &previous {
+            Some(previous) => (serde_json::from_str(&previous.snapshot)?, Some(previous.created_at)),
+            None => (ProjectSnapshot::default(), None),
+        };
+
+        let snapshot = ProjectSnapshot::capture(&pool, &targets).await?;
+        let changes = baseline.diff(&snapshot);
+        let markdown = ReportGenerator::generate_diff_markdown(schedule, since, &changes, &scan_errors)?;
+
+        let report = DifferentialReportOperations::create(
+            &pool,
+            &schedule.id,
+            &serde_json::to_string(&snapshot)?,
+            &markdown,
+            changes.len(),
+        ).await?;
+
+        if let Some(dir) = &schedule.output_dir {
+            let path = Path::new(dir).join(format!(
+                "{}-{}.md",
+                Self::file_stem(&schedule.name),
+                report.created_at.format("%Y-%m-%d"),
+            ));
+            if let Err(e) = tokio::fs::write(&path, &markdown).await {
+                eprintln!("Failed to write {}: {}", path.display(), e);
+            }
+        }
+
+        NotificationOperations::create(
+            &pool,
+            if scan_errors.is_empty() { "info" } else { "medium" },
+            "Differential report ready",
+            &format!("{}: {} changes since the last report", schedule.name, changes.len()),
+            Some("report"),
+            Some(&report.id),
+        ).await?;
+
+        Ok(report)
+    }
+
+    /// The schedule name as a file name
+    fn file_stem(name: &str) -> String {
+        name.chars()
+            .map(|c| if c.is_ascii_alphanumeric() || c == '-' || c == '_' { c } else { '_' })
+            .collect()
+    }
+}
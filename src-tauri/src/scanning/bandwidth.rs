@@ -0,0 +1,125 @@
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::sync::{Arc, Mutex};
+use std::sync::atomic::{AtomicU64, Ordering};
+use tokio::sync::Notify;
+
+pub const DEFAULT_BANDWIDTH_BUDGET_PPS: u32 = 10000;
+
+/// Smallest rate worth starting a job with; below this a job waits for
+/// running jobs to release their share instead.
+const MIN_JOB_RATE: u32 = 100;
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct BandwidthUsage {
+    pub total_pps: u32,
+    pub allocated_pps: u32,
+    pub active_jobs: usize,
+}
+
+struct BudgetState {
+    total_pps: u32,
+    max_jobs: u32,
+    leases: HashMap<u64, u32>,
+}
+
+/// Global packets-per-second budget shared by every masscan/nmap job. Each
+/// job leases at most `total / max_jobs` when it starts, so the first job
+/// can't take the whole budget; the sum of all leases never exceeds the cap.
+pub struct BandwidthBudget {
+    state: Mutex<BudgetState>,
+    released: Notify,
+    next_id: AtomicU64,
+}
+
+impl BandwidthBudget {
+    /// `max_jobs` is how many jobs can run at once across all scanners.
+    pub fn new(total_pps: u32, max_jobs: usize) -> Self {
+        Self {
+            state: Mutex::new(BudgetState {
+                total_pps: total_pps.max(1),
+                max_jobs: (max_jobs as u32).max(1),
+                leases: HashMap::new(),
+            }),
+            released: Notify::new(),
+            next_id: AtomicU64::new(0),
+        }
+    }
+
+    /// Waits until enough of the budget is free and leases a share of it.
+    /// The share is returned to the budget when the lease is dropped.
+    pub async fn acquire(self: &Arc<Self>) -> BandwidthLease {
+        loop {
+            let notified = self.released.notified();
+            tokio::pin!(notified);
+            notified.as_mut().enable();
+
+            if let Some(lease) = self.try_allocate() {
+                return lease;
+            }
+
+            notified.await;
+        }
+    }
+
+    fn try_allocate(self: &Arc<Self>) -> Option<BandwidthLease> {
+        let mut state = self.state.lock().unwrap();
+
+        let allocated: u32 = state.leases.values().sum();
+        let available = state.total_pps.saturating_sub(allocated);
+        let fair_share = (state.total_pps / state.max_jobs).max(1);
+        let rate = fair_share.min(available);
+
+        if rate < MIN_JOB_RATE.min(fair_share) {
+            return None;
+        }
+
+        let id = self.next_id.fetch_add(1, Ordering::Relaxed);
+        state.leases.insert(id, rate);
+
+        Some(BandwidthLease {
+            budget: self.clone(),
+            id,
+            rate,
+        })
+    }
+
+    /// Changes the cap. Running jobs keep their lease; new jobs are sized
+    /// against the new total.
+    pub fn set_total(&self, total_pps: u32) {
+        self.state.lock().unwrap().total_pps = total_pps.max(1);
+        self.released.notify_waiters();
+    }
+
+    pub fn usage(&self) -> BandwidthUsage {
+        let state = self.state.lock().unwrap();
+        BandwidthUsage {
+            total_pps: state.total_pps,
+            allocated_pps: state.leases.values().sum(),
+            active_jobs: state.leases.len(),
+        }
+    }
+
+    fn release(&self, id: u64) {
+        self.state.lock().unwrap().leases.remove(&id);
+        self.released.notify_waiters();
+    }
+}
+
+pub struct BandwidthLease {
+    budget: Arc<BandwidthBudget>,
+    id: u64,
+    rate: u32,
+}
+
+impl BandwidthLease {
+    pub fn rate(&self) -> u32 {
+        self.rate
+    }
+}
+
+impl Drop for BandwidthLease {
+    fn drop(&mut self) {
+        self.budget.release(self.id);
+    }
+}
@@ -0,0 +1,205 @@
+use crate::config::RateConfig;
+use anyhow::Result;
+use regex::Regex;
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::sync::{Arc, Mutex, OnceLock, RwLock};
+use ts_rs::TS;
+use uuid::Uuid;
+
+/// nmap: "Increasing send delay for 10.0.0.1 from 0 to 5 due to 26 out of
+/// 85 dropped probes since last increase." It backs off like this when the
+/// target or a gateway rate-limits the ICMP replies it relies on.
+fn dropped_probes_regex() -> &'static Regex {
+    static REGEX: OnceLock<Regex> = OnceLock::new();
+    REGEX.get_or_init(|| Regex::new(r"(\d+) out of (\d+) dropped probes").unwrap())
+}
+
+/// Drop rates reported as a percentage, e.g. "dropped: 12.5%" or "drops=40%"
+fn drop_percent_regex() -> &'static Regex {
+    static REGEX: OnceLock<Regex> = OnceLock::new();
+    REGEX.get_or_init(|| Regex::new(r"(?i)drop(?:ped|s)?\s*[=:]\s*(\d+(?:\.\d+)?)%").unwrap())
+}
+
+/// nmap gave up on a port after its retransmission cap
+const RETRANSMISSION_CAP: &str = "retransmission cap hit";
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct CongestionSettings {
+    /// Share of probes lost that counts as congested
+    pub loss_threshold: f64,
+    /// Consecutive congested reports before warning
+    pub samples: u32,
+    pub auto_pause: bool,
+}
+
+impl From<&RateConfig> for CongestionSettings {
+    fn from(rates: &RateConfig) -> Self {
+        Self {
+            loss_threshold: rates.congestion_loss_threshold,
+            samples: rates.congestion_samples,
+            auto_pause: rates.congestion_auto_pause,
+        }
+    }
+}
+
+/// Sent to the frontend as a `scan-congestion` event.
+#[derive(Debug, Clone, Serialize, Deserialize, TS)]
+#[ts(export, export_to = "../src/bindings/")]
+pub struct CongestionWarning {
+    pub scan_id: Uuid,
+    /// Loss in the latest report, 0.0 to 1.0
+    pub loss: f64,
+    pub message: String,
+    /// The scanner process was stopped and waits for `resume_scan`
+    pub paused: bool,
+}
+
+#[derive(Default)]
+struct ScanLoss {
+    congested_samples: u32,
+    warned: bool,
+    pid: Option<u32>,
+}
+
+/// Watches scanner output for signs that probes are being lost, so an
+/// overloaded link or rate-limiting gateway produces a warning rather than
+/// silently incomplete results.
+pub struct CongestionMonitor {
+    scans: Mutex<HashMap<Uuid, ScanLoss>>,
+    settings: RwLock<CongestionSettings>,
+}
+
+impl CongestionMonitor {
+    pub fn new(settings: CongestionSettings) -> Self {
+        Self {
+            scans: Mutex::new(HashMap::new()),
+            settings: RwLock::new(settings),
+        }
+    }
+
+    pub fn configure(&self, settings: CongestionSettings) {
+        *self.settings.write().unwrap() = settings;
+    }
+
+    /// Records the scanner process of `scan_id` so it can be paused. The
+    /// record is dropped with the returned guard, before the pid can be
+    /// reused.
+    pub fn track(self: &Arc<Self>, scan_id: Uuid, pid: Option<u32>) -> TrackedProcess {
+        self.scans.lock().unwrap().entry(scan_id).or_default().pid = pid;
+        TrackedProcess {
+            monitor: self.clone(),
+            scan_id,
+        }
+    }
+
+    /// Feeds one output line. Returns a warning the first time loss has
+    /// stayed above the threshold for the configured number of reports.
+    pub fn observe(&self, scan_id: Uuid, line: &str) -> Option<CongestionWarning> {
+        let loss = Self::parse_loss(line)?;
+        let settings = self.settings.read().unwrap().clone();
+
+        // Only scans with a running process are watched
+        let mut scans = self.scans.lock().unwrap();
+        let scan = scans.get_mut(&scan_id)?;
+        if loss < settings.loss_threshold {
+            scan.congested_samples = 0;
+            return None;
+        }
+
+        scan.congested_samples += 1;
+        if scan.warned || scan.congested_samples < settings.samples.max(1) {
+            return None;
+        }
+        scan.warned = true;
+
+        let paused = settings.auto_pause && scan.pid.map_or(false, |pid| signal(pid, Signal::Stop).is_ok());
+        Some(CongestionWarning {
+            scan_id,
+            loss,
+            message: format!(
+                "Sustained packet loss ({:.0}%); results may be incomplete. Lower the scan rate or check the path to the target.",
+                loss * 100.0
+            ),
+            paused,
+        })
+    }
+
+    /// Stops the scanner process until `resume`. Returns false when the
+    /// scan has no local process, which includes runs through the helper
+    /// or over SSH.
+    pub fn pause(&self, scan_id: Uuid) -> Result<bool> {
+        match self.pid(scan_id) {
+            Some(pid) => signal(pid, Signal::Stop).map(|_| true),
+            None => Ok(false),
+        }
+    }
+
+    pub fn resume(&self, scan_id: Uuid) -> Result<bool> {
+        let Some(pid) = self.pid(scan_id) else {
+            return Ok(false);
+        };
+        signal(pid, Signal::Continue)?;
+
+        // Warn again if loss persists after the resume
+        if let Some(scan) = self.scans.lock().unwrap().get_mut(&scan_id) {
+            scan.congested_samples = 0;
+            scan.warned = false;
+        }
+        Ok(true)
+    }
+
+    fn pid(&self, scan_id: Uuid) -> Option<u32> {
+        self.scans.lock().unwrap().get(&scan_id)?.pid
+    }
+
+    fn parse_loss(line: &str) -> Option<f64> {
+        if let Some(captures) = dropped_probes_regex().captures(line) {
+            let dropped: f64 = captures[1].parse().ok()?;
+            let total: f64 = captures[2].parse().ok()?;
+            return (total > 0.0).then(|| dropped / total);
+        }
+        if let Some(captures) = drop_percent_regex().captures(line) {
+            let percent: f64 = captures[1].parse().ok()?;
+            return Some(percent / 100.0);
+        }
+        if line.contains(RETRANSMISSION_CAP) {
+            return Some(1.0);
+        }
+        None
+    }
+}
+
+/// Keeps a scanner process registered with the monitor while it runs.
+pub struct TrackedProcess {
+    monitor: Arc<CongestionMonitor>,
+    scan_id: Uuid,
+}
+
+impl Drop for TrackedProcess {
+    fn drop(&mut self) {
+        self.monitor.scans.lock().unwrap().remove(&self.scan_id);
+    }
+}
+
+enum Signal {
+    Stop,
+    Continue,
+}
+
+#[cfg(unix)]
+fn signal(pid: u32, signal: Signal) -> Result<()> {
+    let signal = match signal {
+        Signal::Stop => libc::SIGSTOP,
+        Signal::Continue => libc::SIGCONT,
+    };
+    if unsafe { libc::kill(pid as libc::pid_t, signal) } == -1 {
+        return Err(std::io::Error::last_os_error().into());
+    }
+    Ok(())
+}
+
+#[cfg(windows)]
+fn signal(_pid: u32, _signal: Signal) -> Result<()> {
+    anyhow::bail!("Pausing scans is not supported on Windows")
+}
\ No newline at end of file
@@ -1,22 +1,106 @@
 use super::*;
-use crate::database::{Database, operations::*};
-use crate::utils::{ProcessManager, InputValidator, NetworkUtils, OutputParser, RateLimiter};
-use std::collections::HashMap;
+use crate::config::AppConfig;
+use crate::error::LegionError;
+use crate::database::{Database, models::{AnonymousAccess, FindingVerification, FollowUpRule, Host, NatPortMapping, ProjectSettings, ScanAnnotations, ScopeEntry, ServiceCheck, Share, SshAudit}, operations::*};
+use crate::sniffer::{SnifferEvent, import::{PcapImporter, PcapImportRequest, PcapImportSummary}, pipeline::SnifferPipeline};
+use crate::sources::{Observation, ObservationStream, Source, WirelessImporter, WirelessImportSummary, KIND_WIFI_AP};
+use crate::utils::{configure_channels, progress_channel, BoundedSender, GeoIpLookup, Ja3Database, ProcessManager, InputValidator, NetworkUtils, OuiLookup, OutputParser, RateLimiter, ServiceProbeMatcher};
+use std::collections::{HashMap, HashSet};
+use std::net::SocketAddr;
+use std::path::PathBuf;
+use tokio::io::AsyncReadExt;
+use tokio::net::TcpStream;
 use tokio::sync::{mpsc, RwLock, Semaphore};
 use std::sync::Arc;
-use anyhow::Result;
+use std::sync::atomic::{AtomicBool, AtomicU32, Ordering};
+use std::time::Instant;
+use anyhow::{Context, Result};
+use cidr::IpCidr;
 
 pub struct ScanCoordinator {
     active_scans: Arc<RwLock<HashMap<Uuid, ScanHandle>>>,
-    nmap_scanner: NmapScanner,
-    masscan_scanner: MasscanScanner,
+    campaigns: Arc<RwLock<HashMap<Uuid, Campaign>>>,
+    massmap_plans: Arc<RwLock<HashMap<Uuid, MassmapPlan>>>,
+    /// Cancel flags of the plans currently executing
+    running_massmaps: Arc<std::sync::Mutex<HashMap<Uuid, Arc<AtomicBool>>>>,
+    /// Interrupted masscan runs being continued by `resume_masscan`
+    resumed_masscans: Arc<std::sync::Mutex<HashSet<Uuid>>>,
+    massmap_checkpoints: PathBuf,
+    scan_checkpoints: PathBuf,
+    scan_output_dir: PathBuf,
+    nmap_scanner: Arc<NmapScanner>,
+    masscan_scanner: Arc<MasscanScanner>,
+    smb_enumerator: Arc<SmbEnumerator>,
+    ssh_auditor: Arc<SshAuditor>,
+    file_service_checker: Arc<FileServiceChecker>,
+    smtp_relay_tester: Arc<SmtpRelayTester>,
+    datastore_prober: Arc<DatastoreProber>,
+    ipmi_prober: Arc<IpmiProber>,
+    iot_spider: Arc<IotSpider>,
+    ics_prober: Arc<IcsProber>,
+    upnp_enumerator: Arc<UpnpEnumerator>,
+    ptr_sweeper: Arc<PtrSweeper>,
+    service_matcher: Arc<ServiceProbeMatcher>,
+    udp_prober: Arc<UdpProber>,
+    nuclei_scanner: Arc<NucleiScanner>,
+    wireless_importer: Arc<WirelessImporter>,
+    pcap_importer: Arc<PcapImporter>,
+    ja3: Arc<Ja3Database>,
+    pipeline: Arc<IngestPipeline>,
+    output_log: Arc<ScanOutputLog>,
+    congestion: Arc<CongestionMonitor>,
     database: Arc<Database>,
-    process_manager: ProcessManager,
+    process_manager: Arc<ProcessManager>,
     rate_limiter: Arc<RateLimiter>,
-    results_tx: mpsc::Sender<ScanResult>,
+    stealth_limiter: Arc<RateLimiter>,
+    bandwidth: Arc<BandwidthBudget>,
+    tools: Arc<ToolPaths>,
+    config_budget_pps: Arc<AtomicU32>,
+    active_project: Arc<std::sync::RwLock<Option<ActiveProject>>>,
+    /// When each follow-up rule last fired for a host port
+    follow_ups: Arc<std::sync::Mutex<HashMap<(String, IpAddr, u16), Instant>>>,
+    results_tx: BoundedSender<ScanResult>,
+    events_tx: BoundedSender<FrontendEvent>,
     scan_semaphore: Arc<Semaphore>,
 }
 
+/// The project whose settings currently drive scan defaults.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ActiveProject {
+    pub id: String,
+    pub settings: ProjectSettings,
+}
+
+/// Scans a network-range campaign keeps in flight; the next batch starts
+/// once fewer than this many are still queued or running
+pub const CAMPAIGN_BATCH_SIZE: usize = 256;
+/// Probe-database OS hints are coarse, usually just a family name
+const BANNER_OS_ACCURACY: f32 = 50.0;
+const CAMPAIGN_POLL_INTERVAL: std::time::Duration = std::time::Duration::from_secs(1);
+/// How long a banner check waits to connect and for the first bytes
+const BANNER_TIMEOUT: std::time::Duration = std::time::Duration::from_secs(5);
+/// Hosts per nmap run in the service detection phase of a massmap plan.
+/// Each finished chunk is checkpointed.
+const NMAP_CHUNK_SIZE: usize = 64;
+/// Ports a quick scan without a port list sweeps
+const QUICK_SCAN_TOP_PORTS: usize = 100;
+
+/// The in-flight scans of one network-range request.
+#[derive(Debug, Default)]
+struct Campaign {
+    scan_ids: Vec<Uuid>,
+    cancelled: bool,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct NetworkScanCampaign {
+    pub campaign_id: Uuid,
+    /// Scans of the first batch; later batches are announced with
+    /// `campaign-scans-started` events
+    pub scan_ids: Vec<Uuid>,
+    pub total_targets: u64,
+}
+
 #[derive(Debug)]
 struct ScanHandle {
     target: ScanTarget,
@@ -26,26 +110,417 @@ struct ScanHandle {
 }
 
 impl ScanCoordinator {
-    pub fn new(database: Arc<Database>, results_tx: mpsc::Sender<ScanResult>) -> Self {
+    pub fn new(
+        database: Arc<Database>,
+        results_tx: BoundedSender<ScanResult>,
+        events_tx: BoundedSender<FrontendEvent>,
+        config: &AppConfig,
+    ) -> Self {
+        let tools = Arc::new(ToolPaths::new(&config.scanners.nmap_path, &config.scanners.masscan_path, &config.scanners.nuclei_path, &config.scanners.sandbox));
+        let concurrency = config.concurrency_limits();
+        let bandwidth = Arc::new(BandwidthBudget::new(
+            config.rates.bandwidth_budget_pps,
+            concurrency.nmap_concurrency + concurrency.masscan_concurrency,
+        ));
+        let congestion = Arc::new(CongestionMonitor::new(CongestionSettings::from(&config.rates)));
+        let output_log = ScanOutputLog::start(events_tx.clone(), congestion.clone());
+        let oui = match &config.enrichment.oui_database {
+            Some(path) => OuiLookup::load(path).unwrap_or_else(|e| {
+                eprintln!("Failed to load MAC prefixes: {}", e);
+                OuiLookup::empty()
+            }),
+            None => OuiLookup::load_default(),
+        };
+        let geoip = GeoIpLookup::load_configured(config.enrichment.geoip_database.as_deref());
+
         Self {
             active_scans: Arc::new(RwLock::new(HashMap::new())),
-            nmap_scanner: NmapScanner::new(5),
-            masscan_scanner: MasscanScanner::new(3, 10000),
+            campaigns: Arc::new(RwLock::new(HashMap::new())),
+            massmap_plans: Arc::new(RwLock::new(HashMap::new())),
+            running_massmaps: Arc::new(std::sync::Mutex::new(HashMap::new())),
+            resumed_masscans: Arc::new(std::sync::Mutex::new(HashSet::new())),
+            massmap_checkpoints: config.massmap_checkpoint_dir(),
+            scan_checkpoints: config.scan_checkpoint_dir(),
+            scan_output_dir: config.scan_output_dir(),
+            nmap_scanner: Arc::new(NmapScanner::new(
+                concurrency.nmap_concurrency,
+                bandwidth.clone(),
+                tools.clone(),
+                output_log.clone(),
+                config.nmap_resume_dir(),
+            )),
+            masscan_scanner: Arc::new(MasscanScanner::new(
+                concurrency.masscan_concurrency,
+                bandwidth.clone(),
+                tools.clone(),
+                output_log.clone(),
+                config.masscan_resume_dir(),
+            )),
+            smb_enumerator: Arc::new(SmbEnumerator::new(3, tools.clone())),
+            ssh_auditor: Arc::new(SshAuditor::default()),
+            file_service_checker: Arc::new(FileServiceChecker::default()),
+            smtp_relay_tester: Arc::new(SmtpRelayTester::default()),
+            datastore_prober: Arc::new(DatastoreProber::default()),
+            ipmi_prober: Arc::new(IpmiProber::default()),
+            iot_spider: Arc::new(IotSpider::default()),
+            ics_prober: Arc::new(IcsProber::default()),
+            upnp_enumerator: Arc::new(UpnpEnumerator::default()),
+            ptr_sweeper: Arc::new(PtrSweeper::default()),
+            service_matcher: Arc::new(ServiceProbeMatcher::load_default()),
+            udp_prober: Arc::new(UdpProber::default()),
+            nuclei_scanner: Arc::new(NucleiScanner::new(2, tools.clone())),
+            wireless_importer: Arc::new(WirelessImporter::default()),
+            pcap_importer: Arc::new(PcapImporter::default()),
+            ja3: Arc::new(Ja3Database::load_configured(config.enrichment.ja3_database.as_deref())),
+            pipeline: Arc::new(IngestPipeline::new(oui, geoip, PtrSweeper::default())),
+            output_log,
+            congestion,
             database,
-            process_manager: ProcessManager::new(300), // 5 min timeout
-            rate_limiter: Arc::new(RateLimiter::new(100.0, 50.0)), // 100 capacity, 50/sec refill
+            process_manager: Arc::new(ProcessManager::new(config.concurrency.process_timeout_secs)),
+            rate_limiter: Arc::new(RateLimiter::new(config.rates.network_capacity, config.rates.network_refill_per_sec)),
+            stealth_limiter: Arc::new(RateLimiter::new(config.rates.stealth_capacity, config.rates.stealth_refill_per_sec)),
+            bandwidth,
+            tools,
+            config_budget_pps: Arc::new(AtomicU32::new(config.rates.bandwidth_budget_pps)),
+            active_project: Arc::new(std::sync::RwLock::new(None)),
+            follow_ups: Arc::new(std::sync::Mutex::new(HashMap::new())),
             results_tx,
-            scan_semaphore: Arc::new(Semaphore::new(10)), // Max 10 concurrent scans
+            events_tx,
+            scan_semaphore: Arc::new(Semaphore::new(concurrency.max_concurrent_scans)),
+        }
+    }
+
+    /// Applies the settings that can change while scans are running. The
+    /// rest are picked up on the next start.
+    pub fn apply_config(&self, config: &AppConfig) {
+        self.config_budget_pps.store(config.rates.bandwidth_budget_pps, Ordering::SeqCst);
+        self.refresh_bandwidth_budget();
+        self.tools.set(&config.scanners.nmap_path, &config.scanners.masscan_path, &config.scanners.nuclei_path, &config.scanners.sandbox);
+        self.congestion.configure(CongestionSettings::from(&config.rates));
+        configure_channels(&config.channels);
+        configure_severity(&config.severity);
+    }
+
+    /// Routes nmap and masscan through the privileged helper, or back to
+    /// running them directly.
+    pub fn set_helper(&self, endpoint: Option<HelperEndpoint>) {
+        self.tools.set_helper(endpoint);
+    }
+
+    /// Switches scan defaults (profile, excludes, rate cap) to a project.
+    pub fn activate_project(&self, project_id: &str, settings: ProjectSettings) {
+        *self.active_project.write().unwrap() = Some(ActiveProject {
+            id: project_id.to_string(),
+            settings,
+        });
+        self.refresh_bandwidth_budget();
+    }
+
+    pub fn deactivate_project(&self) {
+        *self.active_project.write().unwrap() = None;
+        self.refresh_bandwidth_budget();
+    }
+
+    pub fn active_project(&self) -> Option<ActiveProject> {
+        self.active_project.read().unwrap().clone()
+    }
+
+    pub fn default_scan_profile(&self) -> Option<String> {
+        self.active_project()
+            .and_then(|p| p.settings.default_scan_profile)
+    }
+
+    fn project_excludes(&self) -> Vec<String> {
+        self.active_project()
+            .map(|p| p.settings.exclude_list)
+            .unwrap_or_default()
+    }
+
+    /// The effective budget is the configured one, lowered to the active
+    /// project's rate cap when it has one.
+    fn refresh_bandwidth_budget(&self) {
+        let configured = self.config_budget_pps.load(Ordering::SeqCst);
+        let project_cap = self.active_project().and_then(|p| p.settings.rate_cap_pps);
+
+        self.bandwidth.set_total(project_cap.map_or(configured, |cap| cap.min(configured)));
+    }
+
+    /// Routes the scan through the active project's proxy, if it has one,
+    /// after checking the proxy is reachable.
+    async fn apply_project_proxy(&self, options: &mut ScanOptions) -> Result<()> {
+        if options.proxy.is_none() {
+            options.proxy = self.active_project().and_then(|p| p.settings.proxy);
+        }
+
+        // From a jump box the proxy would be checked from the wrong place
+        if let (Some(proxy), None) = (&options.proxy, &options.remote) {
+            ProxyRouting::check(proxy).await?;
+        }
+        if let Some(remote) = &options.remote {
+            ProcessManager::check_ssh(remote).await?;
+        }
+
+        Ok(())
+    }
+
+    /// Scripts of the local nmap installation, for script pickers.
+    pub async fn list_nse_scripts(&self) -> Result<Vec<NseScript>> {
+        NseCatalog::list(&self.tools.nmap(), &self.tools.sandbox()).await
+    }
+
+    /// Rejects script selections or custom options naming NSE scripts nmap
+    /// doesn't have, before the scan is queued. A jump box has its own
+    /// installation, so remote scans aren't checked.
+    async fn check_nse_scripts(&self, target: &ScanTarget) -> Result<()> {
+        let mut selections: Vec<&str> = target.options.scripts.selection.iter()
+            .map(String::as_str)
+            .collect();
+        if let ScanType::Custom { options } = &target.scan_type {
+            selections.extend(NseCatalog::selections(options));
+        }
+        if selections.is_empty() || target.options.remote.is_some() {
+            return Ok(());
+        }
+
+        let installed = self.list_nse_scripts().await?;
+        for selection in selections {
+            NseCatalog::check_selection(selection, &installed)?;
+        }
+        Ok(())
+    }
+
+    /// Blocks Internet-routable targets unless the active project allows
+    /// them. Private ranges are contiguous, so a CIDR is private when both
+    /// its first and last addresses are.
+    fn check_public_target(&self, network: &IpCidr) -> Result<()> {
+        let allowed = self.active_project().map_or(false, |p| p.settings.allow_public_targets);
+        if allowed {
+            return Ok(());
+        }
+
+        let private = NetworkUtils::is_private_ip(&network.first_address())
+            && NetworkUtils::is_private_ip(&network.last_address());
+        if !private {
+            return Err(LegionError::Permission(format!(
+                "{} includes public addresses; enable allow_public_targets on the project to scan it",
+                network
+            )).into());
+        }
+
+        Ok(())
+    }
+
+    /// Scope check for a single host. Domain entries need the host's name,
+    /// so without one given it is taken from the stored host or, failing
+    /// that, its PTR record; lookups are skipped when the scope has no
+    /// domain entries.
+    async fn enforce_host_scope(&self, ip: &IpAddr, hostname: Option<&str>, override_scope: bool) -> Result<()> {
+        let hostname = match hostname {
+            Some(name) => Some(name.to_string()),
+            None if self.scope_has_domains().await? => self.lookup_hostname(ip).await,
+            None => None,
+        };
+
+        self.enforce_scope(
+            &ip.to_string(),
+            |scope| ScopeGuard::covers_ip(scope, ip, hostname.as_deref()),
+            override_scope,
+        ).await
+    }
+
+    /// What every probe checks before touching a single host: the active
+    /// project's exclude list, the public-target guard and the scope.
+    async fn guard_host(&self, ip: &IpAddr, hostname: Option<&str>) -> Result<()> {
+        if NetworkUtils::is_excluded(ip, &self.project_excludes()) {
+            return Err(LegionError::Permission(format!("{} is on the active project's exclude list", ip)).into());
+        }
+        self.check_public_target(&IpCidr::new_host(*ip))?;
+        self.enforce_host_scope(ip, hostname, false).await
+    }
+
+    /// Whether a host may be probed unprompted, as the host monitor does:
+    /// the checks of `guard_host`, without audit-logging the hosts that
+    /// fail them.
+    pub async fn may_probe(&self, ip: &IpAddr, hostname: Option<&str>) -> bool {
+        if NetworkUtils::is_excluded(ip, &self.project_excludes())
+            || self.check_public_target(&IpCidr::new_host(*ip)).is_err()
+        {
+            return false;
         }
+
+        let Some(project) = self.active_project() else {
+            return true;
+        };
+        match ScopeOperations::list(self.database.main_pool(), &project.id).await {
+            Ok(scope) => scope.is_empty() || ScopeGuard::covers_ip(&scope, ip, hostname),
+            Err(e) => {
+                eprintln!("Failed to load the project scope: {}", e);
+                false
+            }
+        }
+    }
+
+    async fn scope_has_domains(&self) -> Result<bool> {
+        let Some(project) = self.active_project() else {
+            return Ok(false);
+        };
+        let scope = ScopeOperations::list(self.database.main_pool(), &project.id).await?;
+        Ok(scope.iter().any(|entry| entry.kind == "domain"))
+    }
+
+    async fn lookup_hostname(&self, ip: &IpAddr) -> Option<String> {
+        if let Ok(Some(Host { hostname: Some(name), .. })) = HostOperations::find_by_ip(&self.database.pool(), *ip).await {
+            return Some(name);
+        }
+        self.ptr_sweeper.resolve(*ip).await
+    }
+
+    /// Rejects a target outside the active project's declared scope unless
+    /// the scan overrides it. Projects without scope entries are
+    /// unrestricted. Rejections and overrides both go to the audit log.
+    async fn enforce_scope(
+        &self,
+        target: &str,
+        covered: impl FnOnce(&[ScopeEntry]) -> bool,
+        override_scope: bool,
+    ) -> Result<()> {
+        let Some(project) = self.active_project() else {
+            return Ok(());
+        };
+
+        let pool = self.database.main_pool();
+        let scope = ScopeOperations::list(pool, &project.id).await?;
+        if scope.is_empty() || covered(&scope) {
+            return Ok(());
+        }
+
+        let action = if override_scope { "scope_override" } else { "scope_violation" };
+        if let Err(e) = AuditLogOperations::record(
+            pool,
+            Some(&project.id),
+            action,
+            Some(target),
+            Some("Target outside the project scope"),
+        ).await {
+            eprintln!("Failed to write audit log: {}", e);
+        }
+
+        if override_scope {
+            Ok(())
+        } else {
+            Err(LegionError::Permission(format!("{} is outside the active project's scope", target)).into())
+        }
+    }
+
+    /// Checks the evasion settings, that the requested interface exists
+    /// and, when both are given, that the source address belongs to it.
+    fn validate_options(options: &ScanOptions) -> Result<()> {
+        let evasion = &options.evasion;
+
+        if let Some(remote) = &options.remote {
+            ProcessManager::validate_ssh_target(remote)?;
+        }
+
+        if evasion.decoys.len() > MAX_DECOYS {
+            return Err(anyhow::anyhow!("At most {} decoys are allowed", MAX_DECOYS));
+        }
+        for decoy in &evasion.decoys {
+            InputValidator::validate_decoy(decoy)?;
+        }
+        if evasion.source_port == Some(0) {
+            return Err(anyhow::anyhow!("Source port must be between 1 and 65535"));
+        }
+        if evasion.data_length.map_or(false, |len| len > MAX_DATA_LENGTH) {
+            return Err(anyhow::anyhow!("Data length must be at most {} bytes", MAX_DATA_LENGTH));
+        }
+
+        let timing = &options.timing;
+        if timing.template.map_or(false, |t| t > MAX_TIMING_TEMPLATE) {
+            return Err(anyhow::anyhow!("Timing template must be between 0 and {}", MAX_TIMING_TEMPLATE));
+        }
+        if timing.host_timeout_secs == Some(0) {
+            return Err(anyhow::anyhow!("Host timeout must be at least one second"));
+        }
+        if timing.min_rate == Some(0) || timing.max_rate == Some(0) {
+            return Err(anyhow::anyhow!("Packet rates must be at least 1 per second"));
+        }
+        if let (Some(min), Some(max)) = (timing.min_rate, timing.max_rate) {
+            if min > max {
+                return Err(anyhow::anyhow!("Minimum rate {} is above the maximum rate {}", min, max));
+            }
+        }
+
+        let discovery = &options.discovery;
+        if discovery.skip && discovery.has_probes() {
+            return Err(anyhow::anyhow!("Host discovery can't be both skipped and given probes"));
+        }
+        let mut probe_ports = discovery.syn_ports.iter().chain(&discovery.ack_ports).chain(&discovery.udp_ports);
+        if probe_ports.any(|port| *port == 0) {
+            return Err(anyhow::anyhow!("Discovery ports must be between 1 and 65535"));
+        }
+
+        for selection in &options.scripts.selection {
+            InputValidator::validate_script_selection(selection)?;
+        }
+
+        let annotations = &options.annotations;
+        if let Some(user) = &annotations.started_by {
+            InputValidator::validate_username(user)?;
+        }
+        if annotations.reason.as_ref().is_some_and(|r| r.chars().count() > MAX_SCAN_REASON_LEN) {
+            return Err(anyhow::anyhow!("Scan reason must be at most {} characters", MAX_SCAN_REASON_LEN));
+        }
+        if let Some(ticket) = &annotations.ticket_ref {
+            let valid = !ticket.is_empty()
+                && ticket.len() <= MAX_TICKET_REF_LEN
+                && ticket.chars().all(|c| c.is_ascii_alphanumeric() || matches!(c, '-' | '_' | '.' | '#' | '/' | ':'));
+            if !valid {
+                return Err(anyhow::anyhow!("Invalid ticket reference: {}", ticket));
+            }
+        }
+        InputValidator::format_script_args(&options.scripts.args)?;
+
+        // Interfaces of a remote jump box can't be checked from here
+        let Some(name) = options.interface.as_ref().filter(|_| options.remote.is_none()) else {
+            return Ok(());
+        };
+
+        let interface = NetworkUtils::list_interfaces()?
+            .into_iter()
+            .find(|i| &i.name == name)
+            .ok_or_else(|| anyhow::anyhow!("Unknown network interface: {}", name))?;
+
+        if let Some(source_ip) = &options.source_ip {
+            if !interface.addresses.contains(source_ip) {
+                return Err(anyhow::anyhow!("{} is not assigned to {}", source_ip, name));
+            }
+        }
+
+        Ok(())
     }
 
     pub async fn start_scan(
         &self,
-        target: ScanTarget,
-        progress_tx: mpsc::Sender<ScanProgress>,
+        mut target: ScanTarget,
+        progress_tx: BoundedSender<ScanProgress>,
     ) -> Result<Uuid> {
         // Validate target
         InputValidator::validate_ip(&target.ip.to_string())?;
+
+        if NetworkUtils::is_excluded(&target.ip, &self.project_excludes()) {
+            return Err(LegionError::Permission(format!("{} is on the active project's exclude list", target.ip)).into());
+        }
+
+        self.check_public_target(&IpCidr::new_host(target.ip))?;
+
+        self.enforce_host_scope(&target.ip, target.hostname.as_deref(), target.options.override_scope).await?;
+
+        Self::validate_options(&target.options)?;
+        if let ScanType::Custom { options } = &target.scan_type {
+            ScanTemplate::validate(options)?;
+        }
+        self.check_nse_scripts(&target).await?;
+        self.apply_project_proxy(&mut target.options).await?;
         
         let scan_id = target.id;
         let (cancel_tx, cancel_rx) = mpsc::channel(1);
@@ -63,10 +538,12 @@ impl ScanCoordinator {
 
         // Create database scan record
         let scan_record = ScanOperations::create(
-            self.database.pool(),
+            &self.database.pool(),
+            scan_id,
             &format!("Scan {}", target.ip),
             &[target.ip],
             &format!("{:?}", target.scan_type),
+            &target.options.annotations,
         ).await?;
 
         // Spawn scan task
@@ -88,33 +565,65 @@ impl ScanCoordinator {
     async fn execute_scan_with_cancellation(
         &self,
         target: ScanTarget,
-        progress_tx: mpsc::Sender<ScanProgress>,
+        progress_tx: BoundedSender<ScanProgress>,
         mut cancel_rx: mpsc::Receiver<()>,
         scan_record_id: &str,
     ) -> Result<ScanResult> {
-        let _permit = self.scan_semaphore.acquire().await?;
+        // Tokens for the target's network come before a slot, so scans of a
+        // throttled network don't hold slots other scans could use. Queued
+        // scans must be cancellable while they wait for either.
+        let queued = async {
+            self.rate_limiter.acquire(target.ip).await;
+            // Stealth scans also draw from a slower per-network bucket
+            if matches!(target.scan_type, ScanType::Stealth) {
+                self.stealth_limiter.acquire(target.ip).await;
+            }
+            self.scan_semaphore.acquire().await
+        };
+        let _permit = tokio::select! {
+            permit = queued => permit?,
+            _ = cancel_rx.recv() => {
+                ScanOperations::update_status(&self.database.pool(), scan_record_id, "cancelled").await?;
+                return Err(LegionError::Cancelled("Scan cancelled".to_string()).into());
+            }
+        };
         
         // Update status to running
         self.update_scan_status(&target.id, ScanStatus::Running).await;
-        ScanOperations::update_status(self.database.pool(), scan_record_id, "running").await?;
+        ScanOperations::update_status(&self.database.pool(), scan_record_id, "running").await?;
 
         // Execute scan based on type
         let scan_future = match target.scan_type {
+            // masscan and the native probes can't cross a proxy or run remotely
+            ScanType::Quick | ScanType::Comprehensive | ScanType::Ot if target.options.nmap_only() => {
+                self.execute_custom_scan(target, progress_tx).boxed()
+            }
             ScanType::Quick => self.execute_quick_scan(target, progress_tx).boxed(),
             ScanType::Comprehensive => self.execute_comprehensive_scan(target, progress_tx).boxed(),
             ScanType::Stealth => self.execute_stealth_scan(target, progress_tx).boxed(),
+            ScanType::Ot => self.execute_ot_scan(target, progress_tx).boxed(),
             ScanType::Custom { .. } => self.execute_custom_scan(target, progress_tx).boxed(),
         };
 
         // Race between scan execution and cancellation
         tokio::select! {
             result = scan_future => {
-                ScanOperations::update_status(self.database.pool(), scan_record_id, "completed").await?;
+                if let Ok(scan_result) = &result {
+                    let result_json = serde_json::to_string(scan_result)?;
+                    ScanOperations::record_evidence(
+                        &self.database.pool(),
+                        scan_record_id,
+                        scan_result.raw_output_format(),
+                        &scan_result.raw_output,
+                        &result_json,
+                    ).await?;
+                }
+                ScanOperations::update_status(&self.database.pool(), scan_record_id, "completed").await?;
                 result
             }
             _ = cancel_rx.recv() => {
-                ScanOperations::update_status(self.database.pool(), scan_record_id, "cancelled").await?;
-                Err(anyhow::anyhow!("Scan cancelled"))
+                ScanOperations::update_status(&self.database.pool(), scan_record_id, "cancelled").await?;
+                Err(LegionError::Cancelled("Scan cancelled".to_string()).into())
             }
         }
     }
@@ -122,179 +631,1789 @@ impl ScanCoordinator {
     async fn execute_quick_scan(
         &self,
         target: ScanTarget,
-        progress_tx: mpsc::Sender<ScanProgress>,
+        progress_tx: BoundedSender<ScanProgress>,
     ) -> Result<ScanResult> {
         // Use masscan for fast discovery
-        let results = self.masscan_scanner
-            .fast_port_discovery(
-                &target.ip.to_string(),
-                100, // Top 100 ports
-                Some(progress_tx.clone())
-            ).await?;
+        let request = if target.ports.is_empty() {
+            MasscanRequest::TopPorts {
+                targets: vec![target.ip],
+                count: QUICK_SCAN_TOP_PORTS,
+                options: target.options.clone(),
+            }
+        } else {
+            MasscanRequest::Scan {
+                targets: vec![target.ip],
+                ports: target.ports.clone(),
+                options: target.options.clone(),
+            }
+        };
+        let discovery = self.start_masscan(target.id, request).await?;
+        let mut results = self.ingest(discovery, Some(&progress_tx)).await?.results.into_iter();
 
-        if let Some(result) = results.first() {
-            self.store_scan_result(result).await?;
-            Ok(result.clone())
+        if let Some(mut result) = results.next() {
+            // Without banners masscan reports each open port on its own line
+            for other in results {
+                result.open_ports.extend(other.open_ports);
+                result.raw_output.extend(other.raw_output);
+            }
+            Ok(result)
         } else {
             // No ports found, still create empty result
             Ok(ScanResult {
                 id: Uuid::new_v4(),
                 target_id: target.id,
+                target_ip: target.ip,
+                mac_address: None,
                 timestamp: Utc::now(),
                 status: ScanStatus::Completed,
                 open_ports: Vec::new(),
                 os_detection: None,
                 vulnerabilities: Vec::new(),
+                host_up: None,
+                rtt_ms: None,
+                trace: Vec::new(),
+                scanned_tcp_ports: None,
+                unlisted_tcp_state: None,
+                raw_output: Vec::new(),
             })
         }
     }
 
+    /// Runs a comprehensive scan as a job graph. Each finished stage is
+    /// checkpointed under the scan's id, so a scan interrupted part way
+    /// resumes from the stage it stopped at.
     async fn execute_comprehensive_scan(
         &self,
         target: ScanTarget,
-        progress_tx: mpsc::Sender<ScanProgress>,
+        progress_tx: BoundedSender<ScanProgress>,
     ) -> Result<ScanResult> {
-        // First phase: Fast port discovery with masscan
-        let _ = progress_tx.send(ScanProgress {
-            percent: 10.0,
-            message: "Starting port discovery...".to_string(),
-            eta: None,
-        }).await;
-
-        let discovery_results = self.masscan_scanner
-            .scan_range(&[target.ip], &[], Some(progress_tx.clone()))
-            .await?;
-
-        // Second phase: Detailed nmap scan on discovered ports
-        let _ = progress_tx.send(ScanProgress {
-            percent: 50.0,
-            message: "Performing detailed analysis...".to_string(),
-            eta: None,
-        }).await;
+        let mut checkpoint = GraphCheckpoint::load(&self.scan_checkpoints, target.id).await?
+            .unwrap_or_else(|| GraphCheckpoint::new(target.id));
+        let scan = Arc::new(ComprehensiveScan::new(self.clone(), target.clone(), progress_tx.clone()));
 
-        let detailed_result = self.nmap_scanner
-            .scan_target(&target, Some(progress_tx))
+        ComprehensiveScan::graph()
+            .run(scan.clone(), &mut checkpoint, &self.scan_checkpoints, Some(&progress_tx))
             .await?;
+        GraphCheckpoint::remove(&self.scan_checkpoints, target.id).await;
 
-        self.store_scan_result(&detailed_result).await?;
-        Ok(detailed_result)
+        scan.result()
+            .ok_or_else(|| anyhow::anyhow!("No service detection result for {}", target.ip))
     }
 
-    async fn execute_stealth_scan(
+    /// Continues a comprehensive scan that was interrupted, e.g. by the app
+    /// closing, from its last finished stage.
+    pub async fn resume_interrupted_scan(
         &self,
-        target: ScanTarget,
-        progress_tx: mpsc::Sender<ScanProgress>,
-    ) -> Result<ScanResult> {
-        // Rate limited stealth scan
-        while !self.rate_limiter.acquire().await {
-            tokio::time::sleep(tokio::time::Duration::from_millis(100)).await;
+        scan_id: Uuid,
+        progress_tx: BoundedSender<ScanProgress>,
+    ) -> Result<Uuid> {
+        let checkpoint = GraphCheckpoint::load(&self.scan_checkpoints, scan_id).await?
+            .ok_or_else(|| LegionError::NotFound(format!("No interrupted scan {}", scan_id)))?;
+        let target = ComprehensiveScan::saved_target(&checkpoint.state)?;
+
+        if self.active_scans.read().await.contains_key(&scan_id) {
+            return Err(LegionError::Validation(format!("Scan {} is already running", scan_id)).into());
         }
 
-        let result = self.nmap_scanner
-            .scan_target(&target, Some(progress_tx))
-            .await?;
+        let (cancel_tx, cancel_rx) = mpsc::channel(1);
+        self.active_scans.write().await.insert(scan_id, ScanHandle {
+            target: target.clone(),
+            status: ScanStatus::Queued,
+            cancel_tx: Some(cancel_tx),
+            start_time: Utc::now(),
+        });
 
-        self.store_scan_result(&result).await?;
-        Ok(result)
-    }
+        let record_id = match ScanOperations::find_by_id(&self.database.pool(), &scan_id.to_string()).await? {
+            Some(record) => record.id,
+            None => ScanOperations::create(
+                &self.database.pool(),
+                scan_id,
+                &format!("Scan {}", target.ip),
+                &[target.ip],
+                &format!("{:?}", target.scan_type),
+                &target.options.annotations,
+            ).await?.id,
+        };
 
-    async fn execute_custom_scan(
-        &self,
-        target: ScanTarget,
-        progress_tx: mpsc::Sender<ScanProgress>,
-    ) -> Result<ScanResult> {
-        let result = self.nmap_scanner
-            .scan_target(&target, Some(progress_tx))
-            .await?;
+        let coordinator = self.clone();
+        tokio::spawn(async move {
+            let result = coordinator.execute_scan_with_cancellation(
+                target,
+                progress_tx,
+                cancel_rx,
+                &record_id,
+            ).await;
 
-        self.store_scan_result(&result).await?;
-        Ok(result)
+            coordinator.handle_scan_completion(scan_id, result).await;
+        });
+
+        Ok(scan_id)
     }
 
-    async fn store_scan_result(&self, result: &ScanResult) -> Result<()> {
-        // Store/update host
-        let host = match HostOperations::find_by_ip(self.database.pool(), result.target_id.into()).await? {
-            Some(existing) => existing,
-            None => {
-                HostOperations::create(
-                    self.database.pool(),
-                    result.target_id.into(), // This should be the IP
-                    None
-                ).await?
+    /// Comprehensive scans that were interrupted and can be resumed
+    pub async fn list_interrupted_scans(&self) -> Result<Vec<ScanTarget>> {
+        let active = self.active_scans.read().await;
+        Ok(GraphCheckpoint::list(&self.scan_checkpoints).await?
+            .into_iter()
+            .filter(|c| !active.contains_key(&c.id))
+            .filter_map(|c| ComprehensiveScan::saved_target(&c.state).ok())
+            .collect())
+    }
+
+    /// Runs the enabled follow-up rules matching a stored result's open
+    /// ports in the background.
+    fn spawn_follow_ups(&self, result: &ScanResult) {
+        let ports: Vec<Port> = result.open_ports.iter()
+            .filter(|p| p.state == "open")
+            .cloned()
+            .collect();
+        if ports.is_empty() {
+            return;
+        }
+
+        let coordinator = self.clone();
+        let ip = result.target_ip;
+        tokio::spawn(async move {
+            if let Err(e) = coordinator.run_follow_ups(ip, &ports).await {
+                eprintln!("Follow-up rules failed for {}: {}", ip, e);
             }
+        });
+    }
+
+    async fn run_follow_ups(&self, ip: IpAddr, ports: &[Port]) -> Result<()> {
+        let rules = FollowUpRuleOperations::list_enabled(self.database.main_pool()).await?;
+        if rules.is_empty() {
+            return Ok(());
+        }
+        let Some(host) = HostOperations::find_by_ip(&self.database.pool(), ip).await? else {
+            return Ok(());
         };
 
-        // Store ports
-        for port in &result.open_ports {
-            let port_record = PortOperations::create(
-                self.database.pool(),
-                &host.id,
-                port.number,
-                &port.protocol,
-                &port.state,
-            ).await?;
+        for rule in &rules {
+            for port in ports.iter().filter(|p| FollowUpRules::matches(rule, p)) {
+                if !self.claim_follow_up(&rule.id, ip, port.number) {
+                    continue;
+                }
+                if let Err(e) = self.run_follow_up(rule, &host, port).await {
+                    eprintln!("Follow-up rule '{}' failed for {}:{}: {:#}", rule.name, ip, port.number, e);
+                }
+            }
+        }
 
-            if let (Some(service), Some(version)) = (&port.service, &port.version) {
-                PortOperations::update_service_info(
-                    self.database.pool(),
-                    &port_record.id,
-                    Some(service),
-                    Some(version),
-                    port.banner.as_deref(),
-                ).await?;
+        Ok(())
+    }
+
+    /// False while the rule is still cooling down for this host port
+    fn claim_follow_up(&self, rule_id: &str, ip: IpAddr, port: u16) -> bool {
+        let mut fired = self.follow_ups.lock().unwrap();
+        fired.retain(|_, at| at.elapsed() < FOLLOW_UP_COOLDOWN);
+
+        match fired.entry((rule_id.to_string(), ip, port)) {
+            std::collections::hash_map::Entry::Occupied(_) => false,
+            std::collections::hash_map::Entry::Vacant(entry) => {
+                entry.insert(Instant::now());
+                true
             }
         }
+    }
 
-        // Store OS detection
-        if let Some(os) = &result.os_detection {
-            HostOperations::update_os_info(
-                self.database.pool(),
-                &host.id,
-                &os.name,
-                &os.family,
-                os.accuracy,
-            ).await?;
+    async fn run_follow_up(&self, rule: &FollowUpRule, host: &Host, port: &Port) -> Result<()> {
+        let selection = rule.arguments.as_deref().unwrap_or_default();
+
+        match FollowUpAction::parse(&rule.action)? {
+            FollowUpAction::SmbEnum => {
+                self.enumerate_smb_shares(host, None).await?;
+                self.check_smb_security(host).await?;
+            }
+            FollowUpAction::SshAudit => {
+                self.audit_ssh(host, port.number).await?;
+            }
+            FollowUpAction::AnonymousAccess => {
+                let protocol = if port.protocol == "udp" { "tftp" } else { "ftp" };
+                self.check_anonymous_access(host, protocol, port.number).await?;
+            }
+            FollowUpAction::SmtpRelay => {
+                self.test_smtp_relay(host, port.number).await?;
+            }
+            FollowUpAction::Datastore => {
+                let datastore = DatastoreProber::datastore_for(port.number, port.service.as_deref())
+                    .ok_or_else(|| anyhow::anyhow!("No datastore check for port {}", port.number))?;
+                self.check_datastore(host, datastore, port.number).await?;
+            }
+            FollowUpAction::Ipmi => {
+                self.probe_ipmi(host, port.number).await?;
+            }
+            FollowUpAction::Upnp => {
+                self.enumerate_port_mappings(host).await?;
+            }
+            FollowUpAction::DeviceClassification => {
+                self.classify_device(host, &IotSpider::web_ports(std::slice::from_ref(port))).await?;
+            }
+            FollowUpAction::Nse => {
+                // Queued as a scan of its own; it stores its results and
+                // rescores the host as any other scan does
+                let scan_flags = if port.protocol == "udp" { "-sU -sV" } else { "-sV" };
+                let target = ScanTarget {
+                    id: Uuid::new_v4(),
+                    ip: InputValidator::validate_ip(&host.ip)?,
+                    hostname: host.hostname.clone(),
+                    ports: vec![port.number],
+                    scan_type: ScanType::Custom { options: scan_flags.to_string() },
+                    options: ScanOptions {
+                        scripts: ScriptOptions {
+                            selection: selection.split(',').map(|s| s.trim().to_string()).collect(),
+                            args: Default::default(),
+                        },
+                        annotations: ScanAnnotations {
+                            reason: Some(format!("Follow-up rule '{}'", rule.name)),
+                            ..ScanAnnotations::default()
+                        },
+                        ..ScanOptions::default()
+                    },
+                };
+                let (progress_tx, _) = progress_channel();
+                self.start_scan(target, progress_tx).await?;
+                return Ok(());
+            }
+            FollowUpAction::Nuclei => {
+                self.run_nuclei(host, port, selection).await?;
+            }
         }
 
-        // Store vulnerabilities
-        for vuln in &result.vulnerabilities {
+        RiskScorer::update_host(&self.database.pool(), host).await?;
+        Ok(())
+    }
+
+    /// Runs nuclei against a port and raises each template match as a
+    /// vulnerability not already on the host.
+    pub async fn run_nuclei(&self, host: &Host, port: &Port, selection: &str) -> Result<Vec<NucleiFinding>> {
+        let ip = InputValidator::validate_ip(&host.ip)?;
+        self.guard_host(&ip, host.hostname.as_deref()).await?;
+        let tls = IotSpider::web_ports(std::slice::from_ref(port)).first()
+            .is_some_and(|(_, tls)| *tls);
+        let findings = self.nuclei_scanner
+            .scan(&NucleiScanner::url_for(ip, port.number, tls), selection)
+            .await?;
+
+        let existing = VulnerabilityOperations::find_by_host(&self.database.pool(), &host.id).await?;
+        for finding in &findings {
+            let name = format!("{} (port {})", finding.name, port.number);
+            if existing.iter().any(|v| v.name == name) {
+                continue;
+            }
+
+            let mut description = finding.description.clone()
+                .unwrap_or_else(|| format!("nuclei template {} matched.", finding.template_id));
+            description.push_str(&format!("\n\nTemplate: {}\nMatched at: {}", finding.template_id, finding.matched_at));
+            if !finding.cve_ids.is_empty() {
+                description.push_str(&format!("\nCVE: {}", finding.cve_ids.join(", ")));
+            }
+
             VulnerabilityOperations::create(
-                self.database.pool(),
+                &self.database.pool(),
                 &host.id,
-                None, // Link to specific port if needed
-                &vuln.name,
-                &format!("{:?}", vuln.severity),
-                &vuln.description,
-                vuln.cvss_score,
+                None,
+                &name,
+                finding.severity.as_str(),
+                &description,
+                None,
+                None,
+                SEVERITY_SOURCE_NUCLEI,
+                finding.original_severity.as_deref(),
+                Some(&FindingCheck::Nuclei { port: port.number, template_id: finding.template_id.clone() }.to_ref()),
             ).await?;
         }
 
-        Ok(())
+        Ok(findings)
     }
 
-    pub async fn scan_network_range(
-        &self,
-        cidr: &str,
-        excludes: &[String],
-        scan_type: ScanType,
-        progress_tx: mpsc::Sender<ScanProgress>,
-    ) -> Result<Vec<Uuid>> {
-        InputValidator::validate_cidr(cidr)?;
-        
-        let targets = NetworkUtils::generate_target_list(&[cidr.to_string()], excludes)?;
-        let mut scan_ids = Vec::new();
+    /// Resolves a port selection: the name of a port preset, or an
+    /// nmap-style port spec such as `22,80,8000-8100`.
+    pub async fn resolve_ports(&self, selection: &str) -> Result<Vec<u16>> {
+        let spec = match PortPresetOperations::find_by_name(self.database.main_pool(), selection).await? {
+            Some(preset) => preset.ports,
+            None => selection.to_string(),
+        };
 
-        let total_targets = targets.len();
-        for (index, ip) in targets.into_iter().enumerate() {
-            let target = ScanTarget {
-                id: Uuid::new_v4(),
-                ip,
-                hostname: None,
-                ports: vec![],
-                scan_type: scan_type.clone(),
+        TopPorts::parse_spec(&spec)
+            .map_err(|e| anyhow::anyhow!("Unknown port preset or invalid port list '{}': {}", selection, e))
+    }
+
+    /// Sends the native UDP probes to a single host and stores the
+    /// services that answered.
+    pub async fn probe_udp_services(&self, ip: IpAddr) -> Result<Vec<Port>> {
+        self.guard_host(&ip, None).await?;
+
+        let mut ports = self.udp_prober.probe_host(ip).await;
+        match self.iot_spider.probe_coap(ip).await {
+            Ok(coap) => ports.extend(coap),
+            Err(e) => eprintln!("CoAP probe to {} failed: {}", ip, e),
+        }
+
+        self.store_scan_result(&ScanResult {
+            id: Uuid::new_v4(),
+            target_id: Uuid::new_v4(),
+            target_ip: ip,
+            mac_address: None,
+            timestamp: Utc::now(),
+            status: ScanStatus::Completed,
+            open_ports: ports.clone(),
+            os_detection: None,
+            vulnerabilities: Vec::new(),
+            host_up: None,
+            rtt_ms: None,
+            trace: Vec::new(),
+            scanned_tcp_ports: None,
+            unlisted_tcp_state: None,
+            raw_output: Vec::new(),
+        }, "udp", &Enrichment::default()).await?;
+
+        Ok(ports)
+    }
+
+    pub async fn enumerate_smb_shares(
+        &self,
+        host: &Host,
+        credentials: Option<SmbCredentials>,
+    ) -> Result<Vec<Share>> {
+        let ip = InputValidator::validate_ip(&host.ip)?;
+        self.guard_host(&ip, host.hostname.as_deref()).await?;
+        let shares = self.smb_enumerator
+            .enumerate_shares(ip, credentials.as_ref())
+            .await?;
+
+        let previous = ShareOperations::find_by_host(&self.database.pool(), &host.id).await?;
+        let mut stored = Vec::new();
+
+        for share in &shares {
+            let record = ShareOperations::upsert(
+                &self.database.pool(),
+                &host.id,
+                &share.name,
+                share.share_type.as_deref(),
+                share.comment.as_deref(),
+                share.anonymous_access.as_deref(),
+                share.user_access.as_deref(),
+                share.is_accessible(),
+                share.is_writable(),
+            ).await?;
+
+            let already_flagged = previous.iter().any(|p| {
+                p.name == share.name && p.anonymous_access.as_deref()
+                    .map(|a| a.to_uppercase().contains("WRITE"))
+                    .unwrap_or(false)
+            });
+
+            if share.is_anonymously_writable() && !already_flagged {
+                VulnerabilityOperations::create(
+                    &self.database.pool(),
+                    &host.id,
+                    None,
+                    &format!("Anonymously writable SMB share: {}", share.name),
+                    Severity::High.as_str(),
+                    &format!(
+                        "The share \\\\{}\\{} can be written without authentication (anonymous access: {}).",
+                        host.ip,
+                        share.name,
+                        share.anonymous_access.as_deref().unwrap_or("unknown"),
+                    ),
+                    None,
+                    None,
+                    SEVERITY_SOURCE_CHECK,
+                    None,
+                    Some(&FindingCheck::SmbShares.to_ref()),
+                ).await?;
+            }
+
+            stored.push(record);
+        }
+
+        Ok(stored)
+    }
+
+    /// Records the algorithms an SSH server negotiates and raises a
+    /// vulnerability for each weakness not already on the host.
+    pub async fn audit_ssh(&self, host: &Host, port: u16) -> Result<SshAudit> {
+        let ip = InputValidator::validate_ip(&host.ip)?;
+        self.guard_host(&ip, host.hostname.as_deref()).await?;
+        let report = self.ssh_auditor.audit(ip, port).await?;
+
+        let record = SshAuditOperations::upsert(
+            &self.database.pool(),
+            &host.id,
+            port,
+            &report.banner,
+            &report.kex_algorithms.join(","),
+            &report.host_key_algorithms.join(","),
+            &report.ciphers.join(","),
+            &report.macs.join(","),
+            report.host_key_type.as_deref(),
+            report.host_key_bits,
+        ).await?;
+
+        let existing = VulnerabilityOperations::find_by_host(&self.database.pool(), &host.id).await?;
+        for finding in report.findings() {
+            let name = format!("{} (port {})", finding.name, port);
+            if existing.iter().any(|v| v.name == name) {
+                continue;
+            }
+
+            VulnerabilityOperations::create(
+                &self.database.pool(),
+                &host.id,
+                None,
+                &name,
+                finding.severity.as_str(),
+                &finding.description,
+                None,
+                None,
+                SEVERITY_SOURCE_CHECK,
+                None,
+                Some(&FindingCheck::SshAudit { port }.to_ref()),
+            ).await?;
+        }
+
+        Ok(record)
+    }
+
+    /// Tries an FTP or TFTP service without credentials. Successful access
+    /// is stored as evidence and raised as a finding.
+    pub async fn check_anonymous_access(&self, host: &Host, protocol: &str, port: u16) -> Result<Option<AnonymousAccess>> {
+        let ip = InputValidator::validate_ip(&host.ip)?;
+        self.guard_host(&ip, host.hostname.as_deref()).await?;
+        let result = match protocol {
+            "ftp" => self.file_service_checker.check_ftp(ip, port).await?,
+            "tftp" => self.file_service_checker.check_tftp(ip, port).await?,
+            _ => anyhow::bail!("Unsupported protocol for anonymous access check: {}", protocol),
+        };
+        let Some(result) = result else {
+            return Ok(None);
+        };
+
+        let record = AnonymousAccessOperations::upsert(
+            &self.database.pool(),
+            &host.id,
+            &result.protocol,
+            result.port,
+            result.banner.as_deref(),
+            &result.listing,
+        ).await?;
+
+        let (name, severity, description) = result.finding();
+        let existing = VulnerabilityOperations::find_by_host(&self.database.pool(), &host.id).await?;
+        if !existing.iter().any(|v| v.name == name) {
+            VulnerabilityOperations::create(
+                &self.database.pool(),
+                &host.id,
+                None,
+                &name,
+                severity.as_str(),
+                &description,
+                None,
+                None,
+                SEVERITY_SOURCE_CHECK,
+                None,
+                Some(&FindingCheck::AnonymousAccess { protocol: protocol.to_string(), port }.to_ref()),
+            ).await?;
+        }
+
+        Ok(Some(record))
+    }
+
+    /// Offers an external recipient to a mail server without sending DATA
+    /// and raises a finding when it is accepted.
+    pub async fn test_smtp_relay(&self, host: &Host, port: u16) -> Result<ServiceCheck> {
+        let ip = InputValidator::validate_ip(&host.ip)?;
+        self.guard_host(&ip, host.hostname.as_deref()).await?;
+        let result = self.smtp_relay_tester.test(ip, port).await?;
+
+        let record = ServiceCheckOperations::upsert(
+            &self.database.pool(),
+            &host.id,
+            port,
+            "smtp_open_relay",
+            result.open_relay,
+            &result.transcript,
+        ).await?;
+
+        if let Some((name, severity, description)) = result.finding() {
+            let existing = VulnerabilityOperations::find_by_host(&self.database.pool(), &host.id).await?;
+            if !existing.iter().any(|v| v.name == name) {
+                VulnerabilityOperations::create(
+                    &self.database.pool(),
+                    &host.id,
+                    None,
+                    &name,
+                    severity.as_str(),
+                    &description,
+                    None,
+                    None,
+                    SEVERITY_SOURCE_CHECK,
+                    None,
+                    Some(&FindingCheck::SmtpRelay { port }.to_ref()),
+                ).await?;
+            }
+        }
+
+        Ok(record)
+    }
+
+    /// Issues a read-only request to a datastore without credentials and
+    /// raises a Critical finding when it is served.
+    pub async fn check_datastore(&self, host: &Host, datastore: &str, port: u16) -> Result<ServiceCheck> {
+        let ip = InputValidator::validate_ip(&host.ip)?;
+        self.guard_host(&ip, host.hostname.as_deref()).await?;
+        let result = self.datastore_prober.check(ip, port, datastore).await?;
+
+        let record = ServiceCheckOperations::upsert(
+            &self.database.pool(),
+            &host.id,
+            port,
+            &result.check_name(),
+            result.unauthenticated,
+            &result.evidence,
+        ).await?;
+
+        if let Some((name, severity, description)) = result.finding() {
+            let existing = VulnerabilityOperations::find_by_host(&self.database.pool(), &host.id).await?;
+            if !existing.iter().any(|v| v.name == name) {
+                VulnerabilityOperations::create(
+                    &self.database.pool(),
+                    &host.id,
+                    None,
+                    &name,
+                    severity.as_str(),
+                    &description,
+                    None,
+                    None,
+                    SEVERITY_SOURCE_CHECK,
+                    None,
+                    Some(&FindingCheck::Datastore { datastore: datastore.to_string(), port }.to_ref()),
+                ).await?;
+            }
+        }
+
+        Ok(record)
+    }
+
+    /// Records whether the host requires SMB signing and maps vulnerable
+    /// results from the smb-vuln scripts into findings.
+    pub async fn check_smb_security(&self, host: &Host) -> Result<SmbSecurityReport> {
+        let ip = InputValidator::validate_ip(&host.ip)?;
+        self.guard_host(&ip, host.hostname.as_deref()).await?;
+        let report = self.smb_enumerator.check_security(ip).await?;
+        let pool = self.database.pool();
+        let port = SMB_PORTS[0];
+
+        if let Some(required) = report.signing_required {
+            ServiceCheckOperations::upsert(&pool, &host.id, port, "smb_signing", !required, &report.script_output).await?;
+        }
+        for vuln in &report.vulnerabilities {
+            let check_name = vuln.script.replace('-', "_");
+            ServiceCheckOperations::upsert(&pool, &host.id, port, &check_name, vuln.is_vulnerable(), &report.script_output).await?;
+        }
+
+        let existing = VulnerabilityOperations::find_by_host(&pool, &host.id).await?;
+        for (name, severity, description) in report.findings() {
+            if existing.iter().any(|v| v.name == name) {
+                continue;
+            }
+            VulnerabilityOperations::create(&pool, &host.id, None, &name, severity.as_str(), &description, None, None, SEVERITY_SOURCE_CHECK, None, Some(&FindingCheck::SmbSecurity.to_ref())).await?;
+        }
+
+        Ok(report)
+    }
+
+    /// Identifies a BMC over IPMI and tests it for the cipher-zero bypass.
+    /// Returns `None` when the host does not speak IPMI.
+    pub async fn probe_ipmi(&self, host: &Host, port: u16) -> Result<Option<IpmiReport>> {
+        let ip = InputValidator::validate_ip(&host.ip)?;
+        self.guard_host(&ip, host.hostname.as_deref()).await?;
+        let Some(report) = self.ipmi_prober.probe(ip, port).await? else {
+            return Ok(None);
+        };
+        let pool = self.database.pool();
+
+        HostOperations::update_device_type(&pool, &host.id, DEVICE_BMC, &report.product()).await?;
+        ServiceCheckOperations::upsert(&pool, &host.id, port, "ipmi_cipher_zero", report.cipher_zero, &report.summary()).await?;
+
+        let existing = VulnerabilityOperations::find_by_host(&pool, &host.id).await?;
+        for (name, severity, description) in report.findings(port) {
+            if existing.iter().any(|v| v.name == name) {
+                continue;
+            }
+            VulnerabilityOperations::create(&pool, &host.id, None, &name, severity.as_str(), &description, None, None, SEVERITY_SOURCE_CHECK, None, Some(&FindingCheck::Ipmi { port }.to_ref())).await?;
+        }
+
+        Ok(Some(report))
+    }
+
+    /// Runs the check that produced a finding again and sets the finding
+    /// to fixed or still present by whether the check reports it, logging
+    /// the check's output as evidence. Nothing else from the run is stored.
+    pub async fn verify_finding(&self, vulnerability_id: &str) -> Result<FindingVerification> {
+        let pool = self.database.pool();
+        let vuln = VulnerabilityOperations::find_by_id(&pool, vulnerability_id).await?
+            .ok_or_else(|| LegionError::NotFound(format!("Vulnerability not found: {}", vulnerability_id)))?;
+        let check = vuln.check_ref.as_deref()
+            .and_then(FindingCheck::parse)
+            .ok_or_else(|| LegionError::Validation(format!("'{}' has no recorded check to re-run", vuln.name)))?;
+        let host = HostOperations::find_by_id(&pool, &vuln.host_id).await?
+            .ok_or_else(|| LegionError::NotFound(format!("Host not found: {}", vuln.host_id)))?;
+        let ip = InputValidator::validate_ip(&host.ip)?;
+
+        // The check probes the host like any scan
+        self.guard_host(&ip, host.hostname.as_deref()).await?;
+
+        let (reported, evidence) = self.rerun_finding_check(&host, ip, &check).await?;
+        // A check that raises only one finding needs no name match, which
+        // an analyst may have edited
+        let present = reported.iter().any(|name| *name == vuln.name)
+            || (check.single_finding() && !reported.is_empty());
+        let outcome = if present { FINDING_STILL_PRESENT } else { FINDING_FIXED };
+
+        let verification = FindingVerificationOperations::record(&pool, &vuln.id, outcome, &evidence).await?;
+        EventOperations::record(
+            &pool,
+            EVENT_FINDING,
+            "verified",
+            Some(&host.id),
+            Some(&vuln.id),
+            &format!("{} verified {}", vuln.name, outcome.replace('_', " ")),
+        ).await?;
+        RiskScorer::update_host(&pool, &host).await?;
+
+        Ok(verification)
+    }
+
+    /// The names of the findings `check` raises now, with its output
+    async fn rerun_finding_check(&self, host: &Host, ip: IpAddr, check: &FindingCheck) -> Result<(Vec<String>, String)> {
+        let names = |findings: Vec<(String, Severity, String)>| findings.into_iter().map(|(name, _, _)| name).collect::<Vec<_>>();
+
+        match check {
+            FindingCheck::Nuclei { port, template_id } => {
+                let stored = PortOperations::find(&self.database.pool(), &host.id, *port, "tcp").await?;
+                let tls = stored.as_ref()
+                    .and_then(|p| IotSpider::web_ports(std::slice::from_ref(p)).first().copied())
+                    .is_some_and(|(_, tls)| tls);
+                let url = NucleiScanner::url_for(ip, *port, tls);
+                let findings = self.nuclei_scanner.scan(&url, &format!("id:{}", template_id)).await?;
+
+                let evidence = if findings.is_empty() {
+                    format!("nuclei template {} did not match {}", template_id, url)
+                } else {
+                    findings.iter()
+                        .map(|f| format!("nuclei template {} matched at {}", f.template_id, f.matched_at))
+                        .collect::<Vec<_>>()
+                        .join("\n")
+                };
+                let reported = findings.iter()
+                    .filter(|f| f.template_id == *template_id)
+                    .map(|f| format!("{} (port {})", f.name, port))
+                    .collect();
+                Ok((reported, evidence))
+            }
+            FindingCheck::Nse { port, script } => {
+                let result = self.nmap_scanner.run_script(ip, *port, script).await?;
+                let findings: Vec<&Vulnerability> = result.vulnerabilities.iter()
+                    .filter(|v| v.port == Some(*port) && v.script.as_deref() == Some(script.as_str()))
+                    .collect();
+
+                let evidence = if findings.is_empty() {
+                    format!("{} reported nothing on port {}", script, port)
+                } else {
+                    findings.iter().map(|v| v.description.as_str()).collect::<Vec<_>>().join("\n\n")
+                };
+                Ok((findings.iter().map(|v| v.name.clone()).collect(), evidence))
+            }
+            FindingCheck::SshAudit { port } => {
+                let report = self.ssh_auditor.audit(ip, *port).await?;
+                let reported = report.findings().into_iter()
+                    .map(|f| format!("{} (port {})", f.name, port))
+                    .collect();
+                Ok((reported, serde_json::to_string_pretty(&report)?))
+            }
+            FindingCheck::AnonymousAccess { protocol, port } => {
+                let result = match protocol.as_str() {
+                    "ftp" => self.file_service_checker.check_ftp(ip, *port).await?,
+                    "tftp" => self.file_service_checker.check_tftp(ip, *port).await?,
+                    _ => anyhow::bail!("Unsupported protocol for anonymous access check: {}", protocol),
+                };
+                Ok(match result {
+                    Some(result) => (vec![result.finding().0], serde_json::to_string_pretty(&result)?),
+                    None => (Vec::new(), format!("{} on port {} refused access without credentials", protocol, port)),
+                })
+            }
+            FindingCheck::SmtpRelay { port } => {
+                let result = self.smtp_relay_tester.test(ip, *port).await?;
+                Ok((names(result.finding().into_iter().collect()), result.transcript))
+            }
+            FindingCheck::Datastore { datastore, port } => {
+                let result = self.datastore_prober.check(ip, *port, datastore).await?;
+                Ok((names(result.finding().into_iter().collect()), result.evidence))
+            }
+            FindingCheck::SmbShares => {
+                let shares = self.smb_enumerator.enumerate_shares(ip, None).await?;
+                let reported = shares.iter()
+                    .filter(|share| share.is_anonymously_writable())
+                    .map(|share| format!("Anonymously writable SMB share: {}", share.name))
+                    .collect();
+                Ok((reported, serde_json::to_string_pretty(&shares)?))
+            }
+            FindingCheck::SmbSecurity => {
+                let report = self.smb_enumerator.check_security(ip).await?;
+                Ok((names(report.findings()), report.script_output))
+            }
+            FindingCheck::Ipmi { port } => {
+                Ok(match self.ipmi_prober.probe(ip, *port).await? {
+                    Some(report) => (names(report.findings(*port)), report.summary()),
+                    None => (Vec::new(), format!("No IPMI response on port {}", port)),
+                })
+            }
+            FindingCheck::Banner { port } => {
+                let recorded = PortOperations::find(&self.database.pool(), &host.id, *port, "tcp").await?
+                    .and_then(|p| p.banner)
+                    .ok_or_else(|| LegionError::Validation(format!("No banner was recorded for port {}", port)))?;
+                let Some(banner) = Self::grab_banner(ip, *port).await? else {
+                    return Ok((Vec::new(), format!("Port {} refused the connection", port)));
+                };
+
+                // Banners can carry a date or session ID, so the versions
+                // they identify are compared when both are known
+                let first_line = |banner: &str| banner.lines().next().unwrap_or_default().trim().to_string();
+                let unchanged = match (self.banner_version(&recorded, *port), self.banner_version(&banner, *port)) {
+                    (Some(before), Some(now)) => before == now,
+                    _ => first_line(&recorded) == first_line(&banner),
+                };
+                let reported = match unchanged {
+                    true => vec![format!("Port {} still announces {}", port, first_line(&banner))],
+                    false => Vec::new(),
+                };
+                Ok((reported, format!("Recorded banner:\n{}\n\nCurrent banner:\n{}", recorded, banner)))
+            }
+        }
+    }
+
+    /// The version a banner identifies, read the way stored banners are
+    fn banner_version(&self, banner: &str, port: u16) -> Option<String> {
+        match self.service_matcher.match_banner(banner.as_bytes(), port, "tcp") {
+            Some(matched) => matched.version_string(),
+            None => OutputParser::parse_service_banner(banner).version,
+        }
+    }
+
+    /// What a TCP service sends on connect. `None` when the port refuses
+    /// the connection; a service that stays silent is an error, since that
+    /// says nothing about the finding.
+    async fn grab_banner(ip: IpAddr, port: u16) -> Result<Option<String>> {
+        let connect = tokio::time::timeout(BANNER_TIMEOUT, TcpStream::connect(SocketAddr::new(ip, port))).await
+            .map_err(|_| anyhow::anyhow!("Timed out connecting to port {}", port))?;
+        let mut stream = match connect {
+            Ok(stream) => stream,
+            Err(e) if e.kind() == std::io::ErrorKind::ConnectionRefused => return Ok(None),
+            Err(e) => return Err(e.into()),
+        };
+
+        let mut buf = vec![0u8; 1024];
+        let read = tokio::time::timeout(BANNER_TIMEOUT, stream.read(&mut buf)).await
+            .map_err(|_| anyhow::anyhow!("Port {} sent no banner", port))??;
+        if read == 0 {
+            anyhow::bail!("Port {} closed the connection without a banner", port);
+        }
+        Ok(Some(String::from_utf8_lossy(&buf[..read]).trim_end().to_string()))
+    }
+
+    /// Reads and stores the NAT port-mapping table when the host is a UPnP
+    /// Internet Gateway Device. Returns nothing for any other host.
+    pub async fn enumerate_port_mappings(&self, host: &Host) -> Result<Vec<NatPortMapping>> {
+        let ip = InputValidator::validate_ip(&host.ip)?;
+        self.guard_host(&ip, host.hostname.as_deref()).await?;
+        let Some(mappings) = self.upnp_enumerator.enumerate(ip).await? else {
+            return Ok(Vec::new());
+        };
+
+        let mut stored = Vec::new();
+        for mapping in &mappings {
+            stored.push(NatPortMappingOperations::upsert(
+                &self.database.pool(),
+                &host.id,
+                mapping.remote_host.as_deref(),
+                mapping.external_port,
+                &mapping.protocol,
+                &mapping.internal_client,
+                mapping.internal_port,
+                mapping.enabled,
+                mapping.description.as_deref(),
+                mapping.lease_duration,
+            ).await?);
+        }
+
+        if !mappings.is_empty() {
+            self.emit_event("nat-mappings-found", &serde_json::json!({
+                "host_id": host.id,
+                "count": mappings.len(),
+            }));
+        }
+
+        Ok(stored)
+    }
+
+    /// Reverse-resolves every address in a range and records the names
+    /// found, creating hosts for addresses not seen before. Only the DNS
+    /// resolver is queried, never the targets themselves.
+    pub async fn ptr_sweep(&self, cidr: &str, excludes: &[String]) -> Result<Vec<PtrRecord>> {
+        InputValidator::validate_cidr(cidr)?;
+        let network: IpCidr = cidr.parse()?;
+        if NetworkUtils::address_count(&network) > MAX_PTR_SWEEP_ADDRESSES {
+            anyhow::bail!("{} is too large for a PTR sweep (at most {} addresses)", cidr, MAX_PTR_SWEEP_ADDRESSES);
+        }
+        // Only the resolver is queried, but the names still reveal what is
+        // being looked at
+        self.check_public_target(&network)?;
+        self.enforce_scope(cidr, |scope| ScopeGuard::covers_range(scope, &network), false).await?;
+
+        let mut excludes = excludes.to_vec();
+        excludes.extend(self.project_excludes());
+        let targets = NetworkUtils::target_iter(&[cidr.to_string()], &excludes)?;
+        let records = self.ptr_sweeper.sweep(targets).await;
+
+        let pool = self.database.pool();
+        for record in &records {
+            let (host, _) = HostOperations::resolve_identity(&pool, record.ip, None, None).await?;
+            HostOperations::update_hostname(&pool, &host.id, &record.hostname).await?;
+        }
+
+        self.emit_event("ptr-sweep-complete", &serde_json::json!({
+            "cidr": cidr,
+            "count": records.len(),
+        }));
+
+        Ok(records)
+    }
+
+    /// Fingerprints a host's web interfaces and stores its device type.
+    pub async fn classify_device(&self, host: &Host, web_ports: &[(u16, bool)]) -> Result<Option<DeviceMatch>> {
+        let ip = InputValidator::validate_ip(&host.ip)?;
+        self.guard_host(&ip, host.hostname.as_deref()).await?;
+        let matched = self.iot_spider.classify(ip, web_ports).await?;
+
+        if let Some(device) = &matched {
+            HostOperations::update_device_type(
+                &self.database.pool(),
+                &host.id,
+                &device.device_type,
+                &device.product,
+            ).await?;
+        }
+
+        Ok(matched)
+    }
+
+    /// OT discovery: one native identification request per ICS protocol,
+    /// with no port sweep. Responding hosts are tagged as ICS assets.
+    async fn execute_ot_scan(
+        &self,
+        target: ScanTarget,
+        progress_tx: BoundedSender<ScanProgress>,
+    ) -> Result<ScanResult> {
+        let _ = progress_tx.send(ScanProgress {
+            percent: 10.0,
+            message: "Probing industrial protocols...".to_string(),
+            eta: None,
+        }).await;
+
+        let identities = self.ics_prober.probe_host(target.ip).await;
+
+        let result = ScanResult {
+            id: Uuid::new_v4(),
+            target_id: target.id,
+            target_ip: target.ip,
+            mac_address: None,
+            timestamp: Utc::now(),
+            status: ScanStatus::Completed,
+            open_ports: identities.iter().map(IcsIdentity::to_port).collect(),
+            os_detection: None,
+            vulnerabilities: Vec::new(),
+            host_up: (!identities.is_empty()).then_some(true),
+            rtt_ms: None,
+            trace: Vec::new(),
+            scanned_tcp_ports: None,
+            unlisted_tcp_state: None,
+            raw_output: Vec::new(),
+        };
+
+        if identities.is_empty() {
+            return Ok(result);
+        }
+
+        self.store_scan_result(&result, "ics", &Enrichment::default()).await?;
+
+        if let Some(host) = HostOperations::find_by_ip(&self.database.pool(), target.ip).await? {
+            for identity in &identities {
+                IcsAssetOperations::upsert(
+                    &self.database.pool(),
+                    &host.id,
+                    &identity.protocol,
+                    identity.port,
+                    identity.vendor.as_deref(),
+                    identity.model.as_deref(),
+                    identity.firmware.as_deref(),
+                    identity.details.as_deref(),
+                ).await?;
+            }
+
+            // Web fingerprints are more specific; only fill in a blank type
+            if host.device_type.is_none() {
+                let product = identities.iter()
+                    .find_map(|i| i.model.clone().or_else(|| i.vendor.clone()))
+                    .unwrap_or_else(|| identities[0].protocol.to_uppercase());
+                HostOperations::update_device_type(&self.database.pool(), &host.id, DEVICE_PLC, &product).await?;
+            }
+        }
+
+        Ok(result)
+    }
+
+    async fn execute_stealth_scan(
+        &self,
+        target: ScanTarget,
+        progress_tx: BoundedSender<ScanProgress>,
+    ) -> Result<ScanResult> {
+        self.ingest_nmap_target(target, &progress_tx).await
+    }
+
+    async fn execute_custom_scan(
+        &self,
+        target: ScanTarget,
+        progress_tx: BoundedSender<ScanProgress>,
+    ) -> Result<ScanResult> {
+        self.ingest_nmap_target(target, &progress_tx).await
+    }
+
+    async fn ingest_nmap_target(&self, target: ScanTarget, progress_tx: &BoundedSender<ScanProgress>) -> Result<ScanResult> {
+        let variables = self.template_variables(&target).await?;
+        let scan = self.nmap_scanner.clone().start(target.id, NmapRequest::Target { target, variables }).await?;
+        self.ingest(scan, Some(progress_tx)).await?
+            .results
+            .pop()
+            .ok_or_else(|| anyhow::anyhow!("nmap produced no result"))
+    }
+
+    /// Resolves the placeholders of a custom scan's options for its
+    /// target. Hostname and open ports come from what earlier scans stored.
+    async fn template_variables(&self, target: &ScanTarget) -> Result<TemplateVariables> {
+        let ScanType::Custom { .. } = &target.scan_type else {
+            return Ok(TemplateVariables::default());
+        };
+
+        let mut variables = TemplateVariables {
+            target: Some(target.ip),
+            hostname: target.hostname.clone(),
+            output_dir: self.template_output_dir(&target.scan_type, target.id).await?,
+            ..TemplateVariables::default()
+        };
+        if let Some(host) = HostOperations::find_by_ip(&self.database.pool(), target.ip).await? {
+            let ports = PortOperations::find_open_ports(&self.database.pool(), &host.id).await?;
+            let ports: Vec<(u16, &str)> = ports.iter()
+                .map(|p| (p.number as u16, p.protocol.as_str()))
+                .collect();
+            variables.open_ports = TemplateVariables::port_list(&ports);
+            variables.hostname = variables.hostname.or(host.hostname);
+        }
+
+        Ok(variables)
+    }
+
+    /// Created only for custom scans whose options use `{output_dir}`
+    async fn template_output_dir(&self, scan_type: &ScanType, run_id: Uuid) -> Result<Option<PathBuf>> {
+        match scan_type {
+            ScanType::Custom { options } if options.contains("{output_dir}") => {
+                let dir = self.scan_output_dir.join(run_id.to_string());
+                tokio::fs::create_dir_all(&dir).await?;
+                Ok(Some(dir))
+            }
+            _ => Ok(None),
+        }
+    }
+
+    /// Stores everything a source observes as it arrives, with the run's
+    /// source as provenance, and forwards its progress to `progress_tx`.
+    /// Fails when storing fails or the run does.
+    pub async fn ingest(
+        &self,
+        mut stream: ObservationStream,
+        progress_tx: Option<&BoundedSender<ScanProgress>>,
+    ) -> Result<IngestSummary> {
+        let mut summary = IngestSummary::default();
+        let settings = self.active_project()
+            .map(|project| project.settings.pipeline)
+            .unwrap_or_default();
+
+        while let Some(mut observation) = stream.next().await {
+            if let Observation::Progress(progress) = observation {
+                if let Some(progress_tx) = progress_tx {
+                    let _ = progress_tx.send(progress).await;
+                }
+                continue;
+            }
+
+            let Some(enrichment) = self.pipeline.process(&mut observation, &settings).await else {
+                // Stored moments ago; the scan still reports it
+                if let Observation::Host(result) = observation {
+                    summary.results.push(result);
+                }
+                continue;
+            };
+
+            let started = Instant::now();
+            match observation {
+                Observation::Host(result) => {
+                    let stored = self.store_scan_result(&result, &stream.source, &enrichment).await;
+                    self.pipeline.record_store(started, stored.is_ok());
+                    stored?;
+                    self.spawn_follow_ups(&result);
+                    summary.results.push(result);
+                }
+                Observation::Passive(event) => {
+                    let stored = self.store_passive_event(event, &enrichment).await;
+                    self.pipeline.record_store(started, stored);
+                    summary.passive_events += 1;
+                }
+                Observation::Wireless(device) => {
+                    let stored = WirelessDeviceOperations::upsert(
+                        &self.database.pool(),
+                        device.kind,
+                        &device.mac_address,
+                        device.name.as_deref(),
+                        device.rssi,
+                        None,
+                        device.channel,
+                        device.encryption.as_deref(),
+                        device.bssid.as_deref(),
+                        &stream.source,
+                    ).await;
+                    self.pipeline.record_store(started, stored.is_ok());
+                    stored?;
+                    if device.kind == KIND_WIFI_AP {
+                        summary.access_points += 1;
+                    } else {
+                        summary.clients += 1;
+                    }
+                }
+                Observation::Progress(_) => {}
+            }
+        }
+
+        stream.finish().await?;
+        Ok(summary)
+    }
+
+    /// Ingests a source that runs until stopped, such as a capture, in the
+    /// background.
+    pub fn spawn_ingest(&self, stream: ObservationStream) {
+        let coordinator = self.clone();
+        tokio::spawn(async move {
+            let source = stream.source.clone();
+            if let Err(e) = coordinator.ingest(stream, None).await {
+                let error = LegionError::from(e);
+                if !matches!(error, LegionError::Cancelled(_)) {
+                    eprintln!("{} run failed: {}", source, error);
+                }
+            }
+        });
+    }
+
+    /// A capture can run for hours, so a failure to store one event is
+    /// logged rather than ending it. Returns whether it was stored.
+    async fn store_passive_event(&self, event: SnifferEvent, enrichment: &Enrichment) -> bool {
+        let stored = async {
+            let follow_ups = SnifferPipeline::handle_event(&self.database, &self.ja3, &event).await?;
+            for follow_up in &follow_ups {
+                match follow_up {
+                    SnifferEvent::HostDiscovered(host) => self.emit_event("host-discovered", host),
+                    SnifferEvent::RogueDevice(host) => self.emit_event("rogue-device", host),
+                    SnifferEvent::ArpSpoofing(alert) => self.emit_event("arp-spoofing", alert),
+                    _ => {}
+                }
+            }
+
+            let ip = match &event {
+                SnifferEvent::PassiveOs { ip, .. } => Some(*ip),
+                SnifferEvent::DhcpRequest(request) => request.ip,
+                SnifferEvent::Neighbour { ip, .. } | SnifferEvent::OpenPort { ip, .. } => Some(*ip),
+                _ => None,
+            };
+            if let Some(ip) = ip.filter(|_| !enrichment.is_empty()) {
+                if let Some(host) = HostOperations::find_by_ip(&self.database.pool(), ip).await? {
+                    self.apply_enrichment(&host.id, enrichment).await?;
+                }
+            }
+            Ok::<_, anyhow::Error>(())
+        }.await;
+
+        if let Err(e) = &stored {
+            eprintln!("Failed to store sniffer event: {}", e);
+        }
+        self.emit_event("sniffer-event", &event);
+        stored.is_ok()
+    }
+
+    /// Stores what the pipeline's enrichment stages found. Names from
+    /// scans and DHCP are kept over PTR names.
+    async fn apply_enrichment(&self, host_id: &str, enrichment: &Enrichment) -> Result<()> {
+        let pool = self.database.pool();
+        if let Some(hostname) = &enrichment.hostname {
+            HostOperations::update_hostname(&pool, host_id, hostname).await?;
+        }
+        if let Some(vendor) = &enrichment.vendor {
+            HostOperations::update_vendor(&pool, host_id, vendor).await?;
+        }
+        if let Some(location) = &enrichment.location {
+            HostOperations::update_location(
+                &pool,
+                host_id,
+                location.country_code.as_deref(),
+                location.city.as_deref(),
+            ).await?;
+        }
+
+        Ok(())
+    }
+
+    /// Counters for each stage of the ingest pipeline
+    pub fn pipeline_metrics(&self) -> Vec<StageMetrics> {
+        self.pipeline.metrics()
+    }
+
+    /// Imports an airodump-ng or Kismet survey export.
+    pub async fn import_wireless_survey(&self, contents: String) -> Result<WirelessImportSummary> {
+        let import = self.wireless_importer.clone().start(Uuid::new_v4(), contents).await?;
+        let format = import.source.clone();
+        let summary = self.ingest(import, None).await?;
+
+        Ok(WirelessImportSummary {
+            format,
+            access_points: summary.access_points,
+            clients: summary.clients,
+        })
+    }
+
+    /// Runs a capture file through the sniffer's analysis, watching the
+    /// gateways already known for spoofing.
+    pub async fn import_pcap(&self, path: PathBuf) -> Result<PcapImportSummary> {
+        let gateways = TopologyOperations::gateways(&self.database.pool()).await?
+            .into_iter()
+            .filter_map(|ip| match ip {
+                IpAddr::V4(v4) => Some(v4),
+                IpAddr::V6(_) => None,
+            })
+            .collect();
+        let request = PcapImportRequest {
+            path: path.clone(),
+            gateways,
+        };
+
+        let import = self.pcap_importer.clone().start(Uuid::new_v4(), request).await?;
+        let summary = self.ingest(import, None).await?;
+
+        Ok(PcapImportSummary {
+            path: path.display().to_string(),
+            events: summary.passive_events,
+        })
+    }
+
+    async fn store_scan_result(&self, result: &ScanResult, source: &str, enrichment: &Enrichment) -> Result<()> {
+        // Store/update host
+        let (host, created) = HostOperations::resolve_identity(
+            &self.database.pool(),
+            result.target_ip,
+            result.mac_address.as_deref(),
+            None,
+        ).await?;
+
+        if created && HostOperations::flag_if_rogue(&self.database.pool(), &host.id).await? {
+            if let Some(rogue) = HostOperations::find_by_id(&self.database.pool(), &host.id).await? {
+                self.emit_event("rogue-device", &rogue);
+            }
+        }
+
+        // Open ports prove the host was up even when the tool can't say
+        let up = result.host_up.or_else(|| (!result.open_ports.is_empty()).then_some(true));
+        if let Some(up) = up {
+            HostStatusOperations::record(&self.database.pool(), &host.id, up, result.rtt_ms, source).await?;
+        }
+
+        // Store ports
+        let mut ports = Vec::with_capacity(result.open_ports.len());
+        for port in &result.open_ports {
+            let mut port_record = PortOperations::create(
+                &self.database.pool(),
+                &host.id,
+                port.number,
+                &port.protocol,
+                &port.state,
+                Some(&result.target_id.to_string()),
+                source,
+            ).await?;
+
+            // Banners from masscan carry no service data; identify them
+            // against the nmap probe database instead of a full -sV pass,
+            // and with the built-in patterns when it has no rule or isn't
+            // installed
+            let matched = match (&port.service, &port.banner) {
+                (None, Some(banner)) => self.service_matcher
+                    .match_banner(banner.as_bytes(), port.number, &port.protocol),
+                _ => None,
+            };
+            let parsed = match (&port.service, &port.banner, &matched) {
+                (None, Some(banner), None) => Some(OutputParser::parse_service_banner(banner)),
+                _ => None,
+            };
+            let service = port.service.clone()
+                .or_else(|| matched.as_ref().map(|m| m.service.clone()))
+                .or_else(|| parsed.as_ref().and_then(|p| p.service.clone()));
+            let version = port.version.clone()
+                .or_else(|| matched.as_ref().and_then(|m| m.version_string()))
+                .or_else(|| parsed.as_ref().and_then(|p| p.version.clone()));
+
+            if service.is_some() || port.banner.is_some() {
+                PortOperations::update_service_info(
+                    &self.database.pool(),
+                    &port_record.id,
+                    service.as_deref(),
+                    version.as_deref(),
+                    port.banner.as_deref(),
+                ).await?;
+                port_record.service = service;
+                port_record.version = version;
+                port_record.banner = port.banner.clone();
+            }
+
+            // Probe matches often name the OS a service was built for
+            if let Some(os) = matched.as_ref().and_then(|m| m.os.as_deref()) {
+                HostOperations::update_os_info(
+                    &self.database.pool(),
+                    &host.id,
+                    "banner",
+                    os,
+                    os,
+                    BANNER_OS_ACCURACY,
+                ).await?;
+            }
+
+            ports.push(port_record);
+        }
+
+        // Open ports the scan probed but didn't list have changed to the
+        // state nmap gave the unlisted ones. They're stored here since
+        // nothing else sees them, which also notifies the watchlist.
+        let scanned = result.scanned_tcp_ports.as_deref()
+            .and_then(|spec| TopPorts::parse_spec(spec).ok())
+            .map(|ports| ports.into_iter().collect::<HashSet<u16>>());
+        if let (Some(scanned), Some(unlisted_state)) = (scanned, result.unlisted_tcp_state.as_deref()) {
+            let listed: HashSet<u16> = result.open_ports.iter()
+                .filter(|p| p.protocol == "tcp")
+                .map(|p| p.number)
+                .collect();
+            let previously_open: HashSet<u16> = PortOperations::find_open_ports(&self.database.pool(), &host.id).await?
+                .into_iter()
+                .filter(|p| p.protocol == "tcp")
+                .map(|p| p.number as u16)
+                .collect();
+
+            for number in previously_open {
+                if !scanned.contains(&number) || listed.contains(&number) {
+                    continue;
+                }
+                PortOperations::create(
+                    &self.database.pool(),
+                    &host.id,
+                    number,
+                    "tcp",
+                    unlisted_state,
+                    Some(&result.target_id.to_string()),
+                    source,
+                ).await?;
+            }
+        }
+
+        // Store OS detection
+        if let Some(os) = &result.os_detection {
+            HostOperations::update_os_info(
+                &self.database.pool(),
+                &host.id,
+                "nmap",
+                &os.name,
+                &os.family,
+                os.accuracy,
+            ).await?;
+        }
+
+        // Store vulnerabilities
+        let mut vulnerabilities = Vec::with_capacity(result.vulnerabilities.len());
+        for vuln in &result.vulnerabilities {
+            let port_id = vuln.port
+                .and_then(|number| ports.iter().find(|p| p.number == number as i32 && p.protocol == "tcp"))
+                .map(|p| p.id.as_str());
+            // Script findings on a port can be verified by running the script again
+            let check = vuln.port.zip(vuln.script.clone())
+                .map(|(port, script)| FindingCheck::Nse { port, script });
+            let vulnerability = VulnerabilityOperations::create(
+                &self.database.pool(),
+                &host.id,
+                port_id,
+                &vuln.name,
+                vuln.severity.as_str(),
+                &vuln.description,
+                vuln.cvss_score,
+                None,
+                SEVERITY_SOURCE_NMAP,
+                None,
+                check.map(|check| check.to_ref()).as_deref(),
+            ).await?;
+            vulnerabilities.push(vulnerability);
+        }
+
+        // Routes and MAC addresses place the host in the topology graph
+        if !result.trace.is_empty() {
+            TopologyBuilder::record_route(&self.database.pool(), result.target_ip, &result.trace).await?;
+        }
+        if result.mac_address.is_some() {
+            TopologyBuilder::record_neighbour(&self.database.pool(), result.target_ip).await?;
+        }
+
+        self.apply_enrichment(&host.id, enrichment).await?;
+
+        RiskScorer::update_host(&self.database.pool(), &host).await?;
+
+        // Re-read so the event carries OS, status and risk score as stored
+        let host = HostOperations::find_by_id(&self.database.pool(), &host.id).await?
+            .unwrap_or(host);
+        self.emit_event("host-updated", &HostDelta {
+            host,
+            created,
+            ports,
+            vulnerabilities,
+        });
+
+        Ok(())
+    }
+
+    /// Inspects the targets and the hosts already stored for them and
+    /// proposes masscan/nmap phases. The plan is held until executed.
+    pub async fn create_massmap_plan(
+        &self,
+        targets: &[String],
+        scan_type: ScanType,
+        options: ScanOptions,
+    ) -> Result<MassmapPlan> {
+        for target in targets {
+            InputValidator::validate_cidr(target)?;
+            let network: IpCidr = target.parse()?;
+            self.check_public_target(&network)?;
+            self.enforce_scope(
+                target,
+                |scope| ScopeGuard::covers_range(scope, &network),
+                options.override_scope,
+            ).await?;
+        }
+        let mut options = options;
+        Self::validate_options(&options)?;
+        if let ScanType::Custom { options } = &scan_type {
+            ScanTemplate::validate(options)?;
+            // Each nmap run covers a chunk of hosts
+            if ["{target}", "{hostname}", "{open_ports}"].iter().any(|p| options.contains(p)) {
+                return Err(LegionError::Validation(
+                    "Massmap plans can only use the {output_dir} placeholder".to_string()
+                ).into());
+            }
+        }
+        self.apply_project_proxy(&mut options).await?;
+
+        let mut hosts = Vec::new();
+        for host in HostOperations::list_all(&self.database.pool()).await? {
+            let ports = PortOperations::find_open_ports(&self.database.pool(), &host.id).await?
+                .into_iter()
+                .map(|p| p.number as u16)
+                .collect();
+            hosts.push((host, ports));
+        }
+
+        let known_hosts = MassmapPlanner::known_hosts_in(targets, &hosts);
+        let plan = MassmapPlanner::plan(targets, scan_type, options, known_hosts)?;
+
+        self.massmap_plans.write().await.insert(plan.id, plan.clone());
+        Ok(plan)
+    }
+
+    /// Runs a previously created plan in the background. Progress is
+    /// reported per phase through `massmap-progress` events.
+    pub async fn execute_massmap_plan(&self, plan_id: Uuid) -> Result<()> {
+        let plan = self.massmap_plans.write().await
+            .remove(&plan_id)
+            .ok_or_else(|| anyhow::anyhow!("Massmap plan not found: {}", plan_id))?;
+
+        let checkpoint = MassmapCheckpoint::new(plan);
+        checkpoint.save(&self.massmap_checkpoints).await?;
+        self.spawn_massmap_plan(checkpoint);
+        Ok(())
+    }
+
+    /// Continues a plan that failed or was interrupted, e.g. by the app
+    /// closing, from its last checkpoint.
+    pub async fn resume_massmap_plan(&self, plan_id: Uuid) -> Result<()> {
+        let checkpoint = MassmapCheckpoint::load(&self.massmap_checkpoints, plan_id).await?
+            .ok_or_else(|| LegionError::NotFound(format!("No interrupted massmap plan {}", plan_id)))?;
+
+        self.spawn_massmap_plan(checkpoint);
+        Ok(())
+    }
+
+    fn spawn_massmap_plan(&self, mut checkpoint: MassmapCheckpoint) {
+        let coordinator = self.clone();
+        let plan_id = checkpoint.plan.id;
+        let cancelled = Arc::new(AtomicBool::new(false));
+        self.running_massmaps.lock().unwrap().insert(plan_id, cancelled.clone());
+
+        tokio::spawn(async move {
+            let _permit = coordinator.scan_semaphore.acquire().await;
+            let outcome = if cancelled.load(Ordering::SeqCst) {
+                Err(LegionError::Cancelled(format!("Massmap plan {} was cancelled", plan_id)).into())
+            } else {
+                coordinator.run_massmap_plan(&mut checkpoint, &cancelled).await
+            };
+            coordinator.running_massmaps.lock().unwrap().remove(&plan_id);
+
+            match outcome {
+                Ok(()) => {
+                    MassmapCheckpoint::remove(&coordinator.massmap_checkpoints, plan_id).await;
+                    coordinator.nmap_scanner.clear_resume_logs(plan_id).await;
+                    coordinator.notify("info", "Massmap completed", &format!("Massmap plan {} completed", plan_id), "massmap", &plan_id.to_string())
+                        .await;
+                    coordinator.emit_event("massmap-complete", &plan_id);
+                }
+                // The checkpoint stays, so a cancelled plan can be resumed
+                Err(_) if cancelled.load(Ordering::SeqCst) => {
+                    coordinator.emit_event("massmap-cancelled", &plan_id);
+                }
+                Err(e) => {
+                    eprintln!("Massmap plan {} failed: {}", plan_id, e);
+                    coordinator.notify("medium", "Massmap failed", &format!("Massmap plan {} failed: {}", plan_id, e), "massmap", &plan_id.to_string())
+                        .await;
+                    coordinator.emit_event("massmap-failed", &serde_json::json!({
+                        "plan_id": plan_id,
+                        "error": e.to_string(),
+                    }));
+                }
+            }
+        });
+    }
+
+    async fn run_massmap_plan(&self, checkpoint: &mut MassmapCheckpoint, cancelled: &AtomicBool) -> Result<()> {
+        let plan = checkpoint.plan.clone();
+        let excludes = self.project_excludes();
+        let check_cancelled = || -> Result<()> {
+            if cancelled.load(Ordering::SeqCst) {
+                return Err(LegionError::Cancelled(format!("Massmap plan {} was cancelled", plan.id)).into());
+            }
+            Ok(())
+        };
+
+        while let Some(phase) = plan.phases.get(checkpoint.phase) {
+            let index = checkpoint.phase;
+            let ports = checkpoint.ports(index).to_vec();
+            self.emit_massmap_progress(&plan, index, 0.0, format!("Starting {}", phase.name));
+
+            match phase.tool {
+                PlanTool::Masscan => {
+                    for (i, range) in plan.targets.iter().enumerate() {
+                        if checkpoint.completed.contains(&i) {
+                            continue;
+                        }
+                        check_cancelled()?;
+                        if let Ok(network) = range.parse::<IpCidr>() {
+                            self.rate_limiter.acquire(network.first_address()).await;
+                        }
+
+                        // Ranges run in order, so saved masscan state
+                        // belongs to the first one not yet completed
+                        let request = if self.masscan_scanner.has_checkpoint(plan.id) {
+                            MasscanRequest::Resume { options: plan.options.clone() }
+                        } else {
+                            MasscanRequest::Sweep {
+                                range: range.clone(),
+                                excludes: excludes.clone(),
+                                ports: ports.clone(),
+                                options: plan.options.clone(),
+                            }
+                        };
+                        let sweep = self.start_masscan(plan.id, request).await?;
+                        let outcome = self.ingest(sweep, None).await;
+                        // A killed masscan ends its stream early; don't mark
+                        // the range complete
+                        check_cancelled()?;
+
+                        for result in outcome?.results {
+                            let ports = checkpoint.discovered.entry(result.target_ip).or_default();
+                            ports.extend(result.open_ports.iter().map(|p| p.number));
+                        }
+
+                        checkpoint.completed.push(i);
+                        checkpoint.save(&self.massmap_checkpoints).await?;
+
+                        let percent = (i + 1) as f32 / plan.targets.len() as f32 * 100.0;
+                        self.emit_massmap_progress(&plan, index, percent, format!(
+                            "Swept {} ({} hosts with open ports so far)", range, checkpoint.discovered.len()
+                        ));
+                    }
+                }
+                PlanTool::Nmap => {
+                    let (chunks, total): (Box<dyn Iterator<Item = (Vec<u16>, Vec<IpAddr>)> + Send + '_>, usize) = if phase.direct {
+                        let mut hosts = NetworkUtils::target_iter(&plan.targets, &excludes)?;
+                        let total = (phase.target_count as usize).div_ceil(NMAP_CHUNK_SIZE);
+                        let chunks = std::iter::from_fn(move || {
+                            let chunk: Vec<IpAddr> = hosts.by_ref().take(NMAP_CHUNK_SIZE).collect();
+                            (!chunk.is_empty()).then(|| (ports.clone(), chunk))
+                        });
+                        (Box::new(chunks), total)
+                    } else {
+                        let chunks = MassmapPlanner::chunk_hosts(&checkpoint.discovered, NMAP_CHUNK_SIZE);
+                        let total = chunks.len();
+                        (Box::new(chunks.into_iter()), total)
+                    };
+
+                    for (i, (ports, hosts)) in chunks.enumerate() {
+                        if checkpoint.completed.contains(&i) {
+                            continue;
+                        }
+                        check_cancelled()?;
+
+                        let hosts: Vec<IpAddr> = hosts.into_iter()
+                            .filter(|ip| !NetworkUtils::is_excluded(ip, &excludes))
+                            .collect();
+                        if !hosts.is_empty() {
+                            self.scan_massmap_chunk(&plan, i, ports, &hosts).await?;
+                            check_cancelled()?;
+                        }
+
+                        checkpoint.completed.push(i);
+                        checkpoint.save(&self.massmap_checkpoints).await?;
+
+                        let percent = (i + 1) as f32 / total.max(1) as f32 * 100.0;
+                        self.emit_massmap_progress(&plan, index, percent, format!(
+                            "Scanned chunk {}/{} ({} hosts)", i + 1, total, hosts.len()
+                        ));
+                    }
+
+                    // Later nmap phases only see hosts discovered after this one
+                    checkpoint.discovered.clear();
+                }
+            }
+
+            checkpoint.advance();
+            checkpoint.save(&self.massmap_checkpoints).await?;
+        }
+
+        Ok(())
+    }
+
+    /// Runs one chunk of a massmap nmap phase, storing each host as nmap
+    /// finishes it. A failed chunk is logged rather than failing the plan.
+    async fn scan_massmap_chunk(&self, plan: &MassmapPlan, chunk: usize, ports: Vec<u16>, hosts: &[IpAddr]) -> Result<()> {
+        for ip in hosts {
+            self.rate_limiter.acquire(*ip).await;
+        }
+
+        // Per-host fields are filled in from nmap's output
+        let template = ScanTarget {
+            id: Uuid::new_v4(),
+            ip: hosts[0],
+            hostname: None,
+            ports,
+            scan_type: plan.scan_type.clone(),
+            options: plan.options.clone(),
+        };
+
+        // Placeholders naming a single host have no value in a multi-host run
+        let variables = TemplateVariables {
+            output_dir: self.template_output_dir(&template.scan_type, plan.id).await?,
+            ..TemplateVariables::default()
+        };
+
+        let scan = self.nmap_scanner.clone().start(plan.id, NmapRequest::Hosts {
+            chunk,
+            template,
+            variables,
+            hosts: hosts.to_vec(),
+        }).await?;
+
+        if let Err(e) = self.ingest(scan, None).await {
+            eprintln!("Massmap nmap scan of chunk {} ({} hosts) failed: {}", chunk, hosts.len(), e);
+        }
+        Ok(())
+    }
+
+    fn emit_massmap_progress(&self, plan: &MassmapPlan, phase: usize, percent: f32, message: String) {
+        self.emit_event("massmap-progress", &MassmapProgress {
+            plan_id: plan.id,
+            phase,
+            phase_name: plan.phases[phase].name.clone(),
+            percent,
+            message,
+        });
+    }
+
+    pub async fn scan_network_range(
+        &self,
+        cidr: &str,
+        excludes: &[String],
+        scan_type: ScanType,
+        options: ScanOptions,
+        progress_tx: BoundedSender<ScanProgress>,
+    ) -> Result<NetworkScanCampaign> {
+        InputValidator::validate_cidr(cidr)?;
+
+        // Check the whole range up front so a partly out-of-scope range
+        // doesn't start scans before being rejected
+        let network: IpCidr = cidr.parse()?;
+        self.check_public_target(&network)?;
+        self.enforce_scope(
+            cidr,
+            |scope| ScopeGuard::covers_range(scope, &network),
+            options.override_scope,
+        ).await?;
+
+        let mut excludes = excludes.to_vec();
+        excludes.extend(self.project_excludes());
+
+        let targets = NetworkUtils::target_iter(&[cidr.to_string()], &excludes)?;
+        let total_targets = NetworkUtils::address_count(&network);
+        self.run_campaign(targets, total_targets, scan_type, options, progress_tx, "Network scan completed").await
+    }
+
+    /// Scans known hosts again as one campaign, e.g. to revalidate their
+    /// findings. Hosts on the project's exclude list are left out; any
+    /// other host outside the scope rejects the whole rescan.
+    pub async fn rescan_hosts(
+        &self,
+        ips: Vec<IpAddr>,
+        scan_type: ScanType,
+        options: ScanOptions,
+        progress_tx: BoundedSender<ScanProgress>,
+    ) -> Result<NetworkScanCampaign> {
+        let excludes = self.project_excludes();
+        let ips: Vec<IpAddr> = ips.into_iter()
+            .filter(|ip| !NetworkUtils::is_excluded(ip, &excludes))
+            .collect();
+        if ips.is_empty() {
+            return Err(LegionError::Validation("No hosts to rescan".to_string()).into());
+        }
+
+        for ip in &ips {
+            self.check_public_target(&IpCidr::new_host(*ip))?;
+            self.enforce_host_scope(ip, None, options.override_scope).await?;
+        }
+
+        let total_targets = ips.len() as u64;
+        self.run_campaign(ips.into_iter(), total_targets, scan_type, options, progress_tx, "Rescan completed").await
+    }
+
+    /// Scans each target as one cancellable campaign. `title` heads the
+    /// notification raised when its last scan finishes.
+    async fn run_campaign(
+        &self,
+        mut targets: impl Iterator<Item = IpAddr> + Send + 'static,
+        total_targets: u64,
+        scan_type: ScanType,
+        options: ScanOptions,
+        progress_tx: BoundedSender<ScanProgress>,
+        title: &'static str,
+    ) -> Result<NetworkScanCampaign> {
+        let campaign_id = Uuid::new_v4();
+        self.campaigns.write().await.insert(campaign_id, Campaign::default());
+
+        // The first batch starts here so errors reach the caller; the rest
+        // are scheduled in the background as earlier scans finish
+        let batch: Vec<IpAddr> = targets.by_ref().take(CAMPAIGN_BATCH_SIZE).collect();
+        let scan_ids = match self.start_campaign_batch(
+            campaign_id, batch, 0, total_targets, &scan_type, &options, &progress_tx,
+        ).await {
+            Ok(scan_ids) => scan_ids,
+            Err(e) => {
+                self.campaigns.write().await.remove(&campaign_id);
+                return Err(e);
+            }
+        };
+
+        let coordinator = self.clone();
+        let mut started = scan_ids.len() as u64;
+        tokio::spawn(async move {
+            while coordinator.wait_for_campaign(campaign_id, CAMPAIGN_BATCH_SIZE).await {
+                let batch: Vec<IpAddr> = targets.by_ref().take(CAMPAIGN_BATCH_SIZE).collect();
+                if batch.is_empty() {
+                    break;
+                }
+
+                match coordinator.start_campaign_batch(
+                    campaign_id, batch, started, total_targets, &scan_type, &options, &progress_tx,
+                ).await {
+                    Ok(scan_ids) => {
+                        started += scan_ids.len() as u64;
+                        coordinator.emit_event("campaign-scans-started", &serde_json::json!({
+                            "campaign_id": campaign_id,
+                            "scan_ids": scan_ids,
+                        }));
+                    }
+                    Err(e) => {
+                        eprintln!("Campaign {} stopped: {}", campaign_id, e);
+                        break;
+                    }
+                }
+            }
+
+            // Keep the campaign cancellable until its last scans finish
+            coordinator.wait_for_campaign(campaign_id, 1).await;
+            coordinator.campaigns.write().await.remove(&campaign_id);
+            coordinator.notify(
+                "info",
+                title,
+                &format!("Started {} of {} target scans", started, total_targets),
+                "campaign",
+                &campaign_id.to_string(),
+            ).await;
+            coordinator.emit_event("campaign-complete", &campaign_id);
+        });
+
+        Ok(NetworkScanCampaign { campaign_id, scan_ids, total_targets })
+    }
+
+    /// Starts one scan per address, stopping early if the campaign is
+    /// cancelled. `offset` is the number of targets already started.
+    #[allow(clippy::too_many_arguments)]
+    async fn start_campaign_batch(
+        &self,
+        campaign_id: Uuid,
+        batch: Vec<IpAddr>,
+        offset: u64,
+        total_targets: u64,
+        scan_type: &ScanType,
+        options: &ScanOptions,
+        progress_tx: &BoundedSender<ScanProgress>,
+    ) -> Result<Vec<Uuid>> {
+        let mut scan_ids = Vec::new();
+
+        for (i, ip) in batch.into_iter().enumerate() {
+            let index = offset + i as u64;
+            let target = ScanTarget {
+                id: Uuid::new_v4(),
+                ip,
+                hostname: None,
+                ports: vec![],
+                scan_type: scan_type.clone(),
+                options: options.clone(),
             };
 
-            let (individual_progress_tx, mut individual_progress_rx) = mpsc::channel(100);
+            let (individual_progress_tx, mut individual_progress_rx) = progress_channel();
             let network_progress_tx = progress_tx.clone();
             
             // Forward individual progress as network progress
@@ -310,13 +2429,88 @@ impl ScanCoordinator {
                 }
             });
 
+            if self.campaigns.read().await.get(&campaign_id).map_or(true, |c| c.cancelled) {
+                break;
+            }
+
             let scan_id = self.start_scan(target, individual_progress_tx).await?;
-            scan_ids.push(scan_id);
+
+            // A cancel that landed while this scan was starting must reach it too
+            let mut campaigns = self.campaigns.write().await;
+            match campaigns.get_mut(&campaign_id) {
+                Some(campaign) if !campaign.cancelled => {
+                    campaign.scan_ids.push(scan_id);
+                    scan_ids.push(scan_id);
+                }
+                _ => {
+                    drop(campaigns);
+                    self.cancel_scan(scan_id).await?;
+                    break;
+                }
+            }
         }
 
         Ok(scan_ids)
     }
 
+    /// Waits until fewer than `limit` of the campaign's scans are still
+    /// active, forgetting the finished ones. Returns false once the
+    /// campaign is cancelled or gone.
+    async fn wait_for_campaign(&self, campaign_id: Uuid, limit: usize) -> bool {
+        loop {
+            {
+                let mut campaigns = self.campaigns.write().await;
+                let scans = self.active_scans.read().await;
+
+                let Some(campaign) = campaigns.get_mut(&campaign_id) else {
+                    return false;
+                };
+                if campaign.cancelled {
+                    return false;
+                }
+
+                campaign.scan_ids.retain(|id| scans.contains_key(id));
+                if campaign.scan_ids.len() < limit {
+                    return true;
+                }
+            }
+
+            tokio::time::sleep(CAMPAIGN_POLL_INTERVAL).await;
+        }
+    }
+
+    /// Waits until the campaign's last scan has finished or it was
+    /// cancelled.
+    pub async fn wait_for_campaign_end(&self, campaign_id: Uuid) {
+        while self.campaigns.read().await.contains_key(&campaign_id) {
+            tokio::time::sleep(CAMPAIGN_POLL_INTERVAL).await;
+        }
+    }
+
+    fn emit_event<T: Serialize>(&self, name: &'static str, payload: &T) {
+        match serde_json::to_value(payload) {
+            // Never awaited so a busy frontend can't stall a scan; the event
+            // channel's overflow policy decides what is lost
+            Ok(payload) => { let _ = self.events_tx.try_send(FrontendEvent { name, payload }); }
+            Err(e) => eprintln!("Failed to serialize {} event: {}", name, e),
+        }
+    }
+
+    /// Stores a notification for the notification center. Failures are
+    /// logged; they must not fail the work being reported on.
+    async fn notify(&self, severity: &str, title: &str, message: &str, entity_type: &str, entity_id: &str) {
+        if let Err(e) = NotificationOperations::create(
+            &self.database.pool(),
+            severity,
+            title,
+            message,
+            Some(entity_type),
+            Some(entity_id),
+        ).await {
+            eprintln!("Failed to store notification: {}", e);
+        }
+    }
+
     async fn update_scan_status(&self, scan_id: &Uuid, status: ScanStatus) {
         let mut scans = self.active_scans.write().await;
         if let Some(handle) = scans.get_mut(scan_id) {
@@ -325,6 +2519,21 @@ impl ScanCoordinator {
     }
 
     async fn handle_scan_completion(&self, scan_id: Uuid, result: Result<ScanResult>) {
+        let notification = match &result {
+            Ok(scan_result) => (
+                "info",
+                "Scan completed",
+                format!(
+                    "Scan of {} found {} open ports and {} findings",
+                    scan_result.target_ip,
+                    scan_result.open_ports.len(),
+                    scan_result.vulnerabilities.len(),
+                ),
+            ),
+            Err(e) => ("medium", "Scan failed", format!("Scan {} failed: {}", scan_id, e)),
+        };
+        self.notify(notification.0, notification.1, &notification.2, "scan", &scan_id.to_string()).await;
+
         match result {
             Ok(scan_result) => {
                 let _ = self.results_tx.send(scan_result).await;
@@ -350,11 +2559,286 @@ impl ScanCoordinator {
             if let Some(cancel_tx) = handle.cancel_tx {
                 let _ = cancel_tx.send(()).await;
             }
+        } else if !self.cancel_massmap_plan(scan_id) {
+            // Resumed masscan runs aren't registered as active scans
+            self.masscan_scanner.cancel(scan_id);
         }
         
         Ok(())
     }
 
+    /// Stops a running massmap plan and the scanner run it is waiting on.
+    /// Returns false when no plan with that id is running.
+    fn cancel_massmap_plan(&self, plan_id: Uuid) -> bool {
+        let Some(cancelled) = self.running_massmaps.lock().unwrap().get(&plan_id).cloned() else {
+            return false;
+        };
+
+        cancelled.store(true, Ordering::SeqCst);
+        // Plan phases run under the plan's id
+        self.masscan_scanner.cancel(plan_id);
+        self.nmap_scanner.cancel(plan_id);
+        true
+    }
+
+    /// Stops the scan's scanner process in place, e.g. while a congested
+    /// link recovers.
+    pub async fn pause_scan(&self, scan_id: Uuid) -> Result<()> {
+        if !self.congestion.pause(scan_id)? {
+            return Err(LegionError::NotFound(format!("Scan {} has no local scanner process to pause", scan_id)).into());
+        }
+        Ok(())
+    }
+
+    /// Continues a scan paused by `pause_scan` or by congestion.
+    pub async fn resume_scan(&self, scan_id: Uuid) -> Result<()> {
+        if !self.congestion.resume(scan_id)? {
+            return Err(LegionError::NotFound(format!("No running scanner process for scan {}", scan_id)).into());
+        }
+        Ok(())
+    }
+
+    /// Continues a masscan run that was cancelled or otherwise interrupted,
+    /// in the background. Results are stored as the original run's would
+    /// have been; a `masscan-resume-complete` or `masscan-resume-failed`
+    /// event follows. Only the interrupted masscan run itself is resumed,
+    /// not any phases that were to follow it. The run must still pass the
+    /// checks it was started under, against the project open now.
+    pub async fn resume_masscan(&self, scan_id: Uuid) -> Result<()> {
+        if self.active_scans.read().await.contains_key(&scan_id) {
+            return Err(LegionError::Validation(format!("Scan {} is still running", scan_id)).into());
+        }
+        if !self.masscan_scanner.has_checkpoint(scan_id) {
+            return Err(LegionError::NotFound(format!("No interrupted masscan run for scan {}", scan_id)).into());
+        }
+        let info = self.masscan_scanner.resume_info(scan_id).await?
+            .ok_or_else(|| LegionError::Validation(format!(
+                "Scan {} was interrupted before its targets were saved; start it again instead", scan_id
+            )))?;
+        self.check_masscan_resume(scan_id, &info).await?;
+        if !self.resumed_masscans.lock().unwrap().insert(scan_id) {
+            return Err(LegionError::Validation(format!("Scan {} is already being resumed", scan_id)).into());
+        }
+
+        let coordinator = self.clone();
+        tokio::spawn(async move {
+            let _permit = coordinator.scan_semaphore.acquire().await;
+            let outcome = coordinator.run_masscan_resume(scan_id, info).await;
+            coordinator.resumed_masscans.lock().unwrap().remove(&scan_id);
+
+            match outcome {
+                Ok(hosts) => coordinator.emit_event("masscan-resume-complete", &serde_json::json!({
+                    "scan_id": scan_id,
+                    "hosts": hosts,
+                })),
+                Err(e) => {
+                    eprintln!("Resuming masscan for scan {} failed: {}", scan_id, e);
+                    let _ = ScanOperations::update_status(&coordinator.database.pool(), &scan_id.to_string(), "failed").await;
+                    coordinator.emit_event("masscan-resume-failed", &serde_json::json!({
+                        "scan_id": scan_id,
+                        "error": e.to_string(),
+                    }));
+                }
+            }
+        });
+
+        Ok(())
+    }
+
+    /// Runs the checks a new masscan run gets on the one being resumed:
+    /// its results must go to the project it was started in, and its
+    /// targets must still pass the public-target guard, the scope and the
+    /// exclude list. paused.conf fixes the targets, so an exclude added
+    /// since that overlaps them refuses the resume rather than narrowing
+    /// it.
+    async fn check_masscan_resume(&self, scan_id: Uuid, info: &MasscanResumeInfo) -> Result<()> {
+        if self.active_project().map(|p| p.id) != info.project_id {
+            return Err(LegionError::Validation(format!(
+                "Scan {} belongs to another project; open it to resume the scan", scan_id
+            )).into());
+        }
+
+        let excludes: Vec<IpCidr> = self.project_excludes().iter()
+            .filter(|e| !info.excludes.contains(e))
+            .filter_map(|e| e.parse().ok())
+            .collect();
+        for target in &info.targets {
+            let network: IpCidr = target.parse()
+                .map_err(|_| LegionError::Validation(format!("Invalid saved target: {}", target)))?;
+            self.check_public_target(&network)?;
+            self.enforce_scope(
+                target,
+                |scope| ScopeGuard::covers_range(scope, &network),
+                info.options.override_scope,
+            ).await?;
+
+            let excluded = excludes.iter().find(|exclude| {
+                network.contains(&exclude.first_address()) || exclude.contains(&network.first_address())
+            });
+            if let Some(exclude) = excluded {
+                return Err(LegionError::Permission(format!(
+                    "{} overlaps {} on the active project's exclude list", target, exclude
+                )).into());
+            }
+        }
+
+        Ok(())
+    }
+
+    /// Starts a masscan run, first saving what it is for so that if it is
+    /// interrupted, `resume_masscan` can check it again.
+    async fn start_masscan(&self, run_id: Uuid, request: MasscanRequest) -> Result<ObservationStream> {
+        let (targets, excludes, options) = match &request {
+            MasscanRequest::Scan { targets, options, .. } | MasscanRequest::TopPorts { targets, options, .. } => (
+                targets.iter().map(|ip| IpCidr::new_host(*ip).to_string()).collect(),
+                Vec::new(),
+                options.clone(),
+            ),
+            MasscanRequest::Sweep { range, excludes, options, .. } => (vec![range.clone()], excludes.clone(), options.clone()),
+            MasscanRequest::Resume { .. } => return self.masscan_scanner.clone().start(run_id, request).await,
+        };
+
+        self.masscan_scanner.save_resume_info(run_id, &MasscanResumeInfo {
+            project_id: self.active_project().map(|p| p.id),
+            targets,
+            excludes,
+            options,
+        }).await?;
+        self.masscan_scanner.clone().start(run_id, request).await
+    }
+
+    /// Returns the number of hosts found with open ports
+    async fn run_masscan_resume(&self, scan_id: Uuid, info: MasscanResumeInfo) -> Result<usize> {
+        for target in &info.targets {
+            if let Ok(network) = target.parse::<IpCidr>() {
+                self.rate_limiter.acquire(network.first_address()).await;
+            }
+        }
+
+        // A no-op for massmap plans, which have no scan record
+        ScanOperations::update_status(&self.database.pool(), &scan_id.to_string(), "running").await?;
+
+        let resumed = self.masscan_scanner.clone().start(scan_id, MasscanRequest::Resume { options: info.options }).await?;
+        let results = self.ingest(resumed, None).await?.results;
+
+        ScanOperations::update_status(&self.database.pool(), &scan_id.to_string(), "completed").await?;
+        Ok(results.len())
+    }
+
+    /// Deletes a finished scan and its stored output. Running scans must be
+    /// cancelled first.
+    pub async fn delete_scan(&self, scan_id: Uuid, rollback_ports: bool) -> Result<u64> {
+        if self.active_scans.read().await.contains_key(&scan_id) {
+            return Err(anyhow::anyhow!("Scan {} is still running; cancel it first", scan_id));
+        }
+
+        ScanOperations::delete(&self.database.pool(), &scan_id.to_string(), rollback_ports).await
+    }
+
+    /// Cancels every scan of a network-range campaign and stops it from
+    /// launching more. Both maps stay locked so no child slips through.
+    pub async fn cancel_campaign(&self, campaign_id: Uuid) -> Result<usize> {
+        let mut campaigns = self.campaigns.write().await;
+        let mut scans = self.active_scans.write().await;
+
+        let campaign = campaigns.get_mut(&campaign_id)
+            .ok_or_else(|| anyhow::anyhow!("Campaign not found: {}", campaign_id))?;
+        campaign.cancelled = true;
+
+        Ok(Self::cancel_handles(&mut scans, &campaign.scan_ids))
+    }
+
+    /// Cancels every running and queued scan, including all campaigns,
+    /// massmap plans and resumed masscan runs.
+    pub async fn cancel_all_scans(&self) -> usize {
+        let mut campaigns = self.campaigns.write().await;
+        let mut scans = self.active_scans.write().await;
+
+        for campaign in campaigns.values_mut() {
+            campaign.cancelled = true;
+        }
+
+        let scan_ids: Vec<Uuid> = scans.keys().copied().collect();
+        let mut cancelled = Self::cancel_handles(&mut scans, &scan_ids);
+
+        let plan_ids: Vec<Uuid> = self.running_massmaps.lock().unwrap().keys().copied().collect();
+        for plan_id in plan_ids {
+            if self.cancel_massmap_plan(plan_id) {
+                cancelled += 1;
+            }
+        }
+
+        let resumed: Vec<Uuid> = self.resumed_masscans.lock().unwrap().iter().copied().collect();
+        for scan_id in resumed {
+            self.masscan_scanner.cancel(scan_id);
+            cancelled += 1;
+        }
+
+        cancelled
+    }
+
+    /// Signals each scan's task, which kills its process and marks the
+    /// record cancelled. Returns how many scans were still active.
+    fn cancel_handles(scans: &mut HashMap<Uuid, ScanHandle>, scan_ids: &[Uuid]) -> usize {
+        let mut cancelled = 0;
+        for id in scan_ids {
+            if let Some(cancel_tx) = scans.remove(id).and_then(|handle| handle.cancel_tx) {
+                let _ = cancel_tx.try_send(());
+                cancelled += 1;
+            }
+        }
+        cancelled
+    }
+
+    /// Runs the current parsers over a scan's stored tool output and stores
+    /// the results again, so parser fixes reach historical scans.
+    pub async fn reparse_scan(&self, scan_id: Uuid) -> Result<Vec<ScanResult>> {
+        let pool = self.database.pool();
+        let scan = ScanOperations::find_by_id(&pool, &scan_id.to_string())
+            .await?
+            .ok_or_else(|| anyhow::anyhow!("Scan not found: {}", scan_id))?;
+
+        let outputs = RawOutputOperations::find_by_scan(&pool, &scan.id).await?;
+        if outputs.is_empty() {
+            return Err(anyhow::anyhow!("Scan {} has no stored output", scan_id));
+        }
+
+        let targets: Vec<IpAddr> = serde_json::from_str(&scan.targets)?;
+        let ip = *targets.first()
+            .ok_or_else(|| anyhow::anyhow!("Scan {} has no targets", scan_id))?;
+        let target = ScanTarget {
+            id: scan_id,
+            ip,
+            hostname: None,
+            ports: Vec::new(),
+            scan_type: ScanType::Quick,
+            options: ScanOptions::default(),
+        };
+
+        let mut results = Vec::new();
+        for output in &outputs {
+            let data = RawOutputOperations::decompress(output)?;
+            match output.format.as_str() {
+                RAW_FORMAT_NMAP_XML => results.push(self.nmap_scanner.parse_nmap_xml(&target, &data)?),
+                RAW_FORMAT_MASSCAN_LIST => results.extend(
+                    self.masscan_scanner.parse_list_output(&String::from_utf8_lossy(&data))
+                ),
+                other => return Err(anyhow::anyhow!("Unknown raw output format: {}", other)),
+            }
+        }
+
+        for result in &results {
+            self.store_scan_result(result, "reparse", &Enrichment::default()).await?;
+        }
+
+        Ok(results)
+    }
+
+    /// Buffered scanner output for a scan, from line `from_line` onwards.
+    pub fn get_scan_output(&self, scan_id: Uuid, from_line: u64) -> Vec<OutputLine> {
+        self.output_log.lines_from(scan_id, from_line)
+    }
+
     pub async fn get_active_scans(&self) -> Vec<(Uuid, ScanStatus)> {
         let scans = self.active_scans.read().await;
         scans.iter()
@@ -362,6 +2846,14 @@ impl ScanCoordinator {
             .collect()
     }
 
+    pub fn set_bandwidth_budget(&self, total_pps: u32) {
+        self.bandwidth.set_total(total_pps);
+    }
+
+    pub fn get_bandwidth_usage(&self) -> BandwidthUsage {
+        self.bandwidth.usage()
+    }
+
     pub async fn get_scan_statistics(&self) -> ScanStatistics {
         let scans = self.active_scans.read().await;
         let total_active = scans.len();
@@ -381,18 +2873,336 @@ impl Clone for ScanCoordinator {
     fn clone(&self) -> Self {
         Self {
             active_scans: self.active_scans.clone(),
-            nmap_scanner: NmapScanner::new(5),
-            masscan_scanner: MasscanScanner::new(3, 10000),
+            campaigns: self.campaigns.clone(),
+            massmap_plans: self.massmap_plans.clone(),
+            running_massmaps: self.running_massmaps.clone(),
+            resumed_masscans: self.resumed_masscans.clone(),
+            massmap_checkpoints: self.massmap_checkpoints.clone(),
+            scan_checkpoints: self.scan_checkpoints.clone(),
+            scan_output_dir: self.scan_output_dir.clone(),
+            nmap_scanner: self.nmap_scanner.clone(),
+            masscan_scanner: self.masscan_scanner.clone(),
+            smb_enumerator: self.smb_enumerator.clone(),
+            ssh_auditor: self.ssh_auditor.clone(),
+            file_service_checker: self.file_service_checker.clone(),
+            smtp_relay_tester: self.smtp_relay_tester.clone(),
+            datastore_prober: self.datastore_prober.clone(),
+            ipmi_prober: self.ipmi_prober.clone(),
+            iot_spider: self.iot_spider.clone(),
+            ics_prober: self.ics_prober.clone(),
+            upnp_enumerator: self.upnp_enumerator.clone(),
+            ptr_sweeper: self.ptr_sweeper.clone(),
+            service_matcher: self.service_matcher.clone(),
+            udp_prober: self.udp_prober.clone(),
+            nuclei_scanner: self.nuclei_scanner.clone(),
+            wireless_importer: self.wireless_importer.clone(),
+            pcap_importer: self.pcap_importer.clone(),
+            ja3: self.ja3.clone(),
+            pipeline: self.pipeline.clone(),
+            output_log: self.output_log.clone(),
+            congestion: self.congestion.clone(),
             database: self.database.clone(),
-            process_manager: ProcessManager::new(300),
+            process_manager: self.process_manager.clone(),
             rate_limiter: self.rate_limiter.clone(),
+            stealth_limiter: self.stealth_limiter.clone(),
+            bandwidth: self.bandwidth.clone(),
+            tools: self.tools.clone(),
+            config_budget_pps: self.config_budget_pps.clone(),
+            active_project: self.active_project.clone(),
+            follow_ups: self.follow_ups.clone(),
             results_tx: self.results_tx.clone(),
+            events_tx: self.events_tx.clone(),
             scan_semaphore: self.scan_semaphore.clone(),
         }
     }
 }
 
-#[derive(Debug, Clone, Serialize, Deserialize)]
+/// The job graph context of one comprehensive scan. Stages after service
+/// detection plan their checks from the ports it found.
+struct ComprehensiveScan {
+    coordinator: ScanCoordinator,
+    target: ScanTarget,
+    progress_tx: BoundedSender<ScanProgress>,
+    result: std::sync::Mutex<Option<ScanResult>>,
+}
+
+/// What a comprehensive scan checkpoints after each stage
+#[derive(Serialize, Deserialize)]
+struct ComprehensiveState {
+    target: ScanTarget,
+    result: Option<ScanResult>,
+}
+
+impl ComprehensiveScan {
+    fn new(coordinator: ScanCoordinator, target: ScanTarget, progress_tx: BoundedSender<ScanProgress>) -> Self {
+        Self {
+            coordinator,
+            target,
+            progress_tx,
+            result: std::sync::Mutex::new(None),
+        }
+    }
+
+    /// Discovery, then service detection, then the service checks and
+    /// finally the web checks, which reuse what the service checks stored.
+    fn graph() -> JobGraph<Self> {
+        JobGraph::new()
+            .stage(GraphStage::new("port_discovery", |_: &Self| vec![job(Self::discover_ports)]).required())
+            .stage(GraphStage::new("service_enumeration", |_: &Self| vec![
+                job(Self::detect_services),
+                job(Self::probe_udp_services),
+            ]).after(&["port_discovery"]).required())
+            .stage(GraphStage::new("vulnerability_checks", Self::plan_vulnerability_checks)
+                .after(&["service_enumeration"])
+                .concurrency(4))
+            .stage(GraphStage::new("web_checks", Self::plan_web_checks)
+                .after(&["vulnerability_checks"])
+                .concurrency(2))
+            // The stages above add findings after the result was stored
+            .stage(GraphStage::new("risk_scoring", |_: &Self| vec![
+                Self::host_job(|coordinator, host| async move {
+                    RiskScorer::update_host(&coordinator.database.pool(), &host).await?;
+                    Ok(())
+                }),
+            ]).after(&["web_checks"]).required())
+    }
+
+    fn saved_target(state: &serde_json::Value) -> Result<ScanTarget> {
+        let state: ComprehensiveState = serde_json::from_value(state.clone())?;
+        Ok(state.target)
+    }
+
+    fn result(&self) -> Option<ScanResult> {
+        self.result.lock().unwrap().clone()
+    }
+
+    fn open_ports(&self) -> Vec<Port> {
+        self.result.lock().unwrap()
+            .as_ref()
+            .map(|r| r.open_ports.clone())
+            .unwrap_or_default()
+    }
+
+    /// Wraps a check of the scanned host. A host the earlier stages didn't
+    /// store has nothing to check.
+    fn host_job<F, Fut>(check: F) -> Job<Self>
+    where
+        F: FnOnce(ScanCoordinator, Host) -> Fut + Send + 'static,
+        Fut: std::future::Future<Output = Result<()>> + Send + 'static,
+    {
+        job(move |scan: Arc<Self>| async move {
+            match HostOperations::find_by_ip(&scan.coordinator.database.pool(), scan.target.ip).await? {
+                Some(host) => check(scan.coordinator.clone(), host).await,
+                None => Ok(()),
+            }
+        })
+    }
+
+    /// Fast port discovery with masscan
+    async fn discover_ports(scan: Arc<Self>) -> Result<()> {
+        let discovery = scan.coordinator.start_masscan(scan.target.id, MasscanRequest::Scan {
+            targets: vec![scan.target.ip],
+            ports: scan.target.ports.clone(),
+            options: scan.target.options.clone(),
+        }).await?;
+        scan.coordinator.ingest(discovery, Some(&scan.progress_tx)).await?;
+        Ok(())
+    }
+
+    /// Detailed nmap scan of the discovered ports
+    async fn detect_services(scan: Arc<Self>) -> Result<()> {
+        let result = scan.coordinator.ingest_nmap_target(scan.target.clone(), &scan.progress_tx).await?;
+
+        let mut current = scan.result.lock().unwrap();
+        match current.as_mut() {
+            Some(existing) => existing.open_ports.extend(result.open_ports),
+            None => *current = Some(result),
+        }
+        Ok(())
+    }
+
+    /// UDP services rarely answer empty datagrams, so the common ones are
+    /// confirmed with protocol-valid probes
+    async fn probe_udp_services(scan: Arc<Self>) -> Result<()> {
+        let ports = scan.coordinator.probe_udp_services(scan.target.ip).await?;
+
+        let mut current = scan.result.lock().unwrap();
+        match current.as_mut() {
+            Some(existing) => existing.open_ports.extend(ports),
+            None => *current = Some(ScanResult {
+                id: Uuid::new_v4(),
+                target_id: scan.target.id,
+                target_ip: scan.target.ip,
+                mac_address: None,
+                timestamp: Utc::now(),
+                status: ScanStatus::Completed,
+                open_ports: ports,
+                os_detection: None,
+                vulnerabilities: Vec::new(),
+                host_up: None,
+                rtt_ms: None,
+                trace: Vec::new(),
+                scanned_tcp_ports: None,
+                unlisted_tcp_state: None,
+                raw_output: Vec::new(),
+            }),
+        }
+        Ok(())
+    }
+
+    fn plan_vulnerability_checks(&self) -> Vec<Job<Self>> {
+        let ports = self.open_ports();
+        let ip = self.target.ip;
+        let mut jobs = Vec::new();
+
+        // Null-session share enumeration on SMB hosts
+        if ports.iter().any(|p| p.state == "open" && SMB_PORTS.contains(&p.number)) {
+            jobs.push(Self::host_job(move |coordinator, host| async move {
+                coordinator.enumerate_smb_shares(&host, None).await
+                    .with_context(|| format!("SMB share enumeration for {}", ip))?;
+                Ok(())
+            }));
+            jobs.push(Self::host_job(move |coordinator, host| async move {
+                coordinator.check_smb_security(&host).await
+                    .with_context(|| format!("SMB security checks for {}", ip))?;
+                Ok(())
+            }));
+            jobs.push(Self::host_job(move |coordinator, host| async move {
+                let os = coordinator.smb_enumerator.discover_os(ip).await
+                    .with_context(|| format!("SMB OS discovery for {}", ip))?;
+                if let Some(os) = os {
+                    HostOperations::update_os_info(
+                        &coordinator.database.pool(),
+                        &host.id,
+                        "smb",
+                        &os.name,
+                        &os.family,
+                        os.accuracy,
+                    ).await?;
+                }
+                Ok(())
+            }));
+        }
+
+        // Key-exchange audit of every SSH server
+        let ssh_ports = ports.iter()
+            .filter(|p| p.state == "open" && p.protocol == "tcp")
+            .filter(|p| p.number == SSH_PORT || p.service.as_deref() == Some("ssh"))
+            .map(|p| p.number);
+        for port in ssh_ports {
+            jobs.push(Self::host_job(move |coordinator, host| async move {
+                coordinator.audit_ssh(&host, port).await
+                    .with_context(|| format!("SSH audit of {}:{}", ip, port))?;
+                Ok(())
+            }));
+        }
+
+        // Anonymous FTP login and unauthenticated TFTP reads
+        let file_services = ports.iter()
+            .filter(|p| p.state != "closed")
+            .filter_map(|p| match (p.protocol.as_str(), p.service.as_deref()) {
+                ("tcp", Some("ftp")) => Some(("ftp", p.number)),
+                ("tcp", _) if p.number == FTP_PORT => Some(("ftp", p.number)),
+                ("udp", Some("tftp")) => Some(("tftp", p.number)),
+                ("udp", _) if p.number == TFTP_PORT => Some(("tftp", p.number)),
+                _ => None,
+            });
+        for (protocol, port) in file_services {
+            jobs.push(Self::host_job(move |coordinator, host| async move {
+                coordinator.check_anonymous_access(&host, protocol, port).await
+                    .with_context(|| format!("Anonymous {} check of {}:{}", protocol, ip, port))?;
+                Ok(())
+            }));
+        }
+
+        // Relay test against every mail server
+        let smtp_ports = ports.iter()
+            .filter(|p| p.state == "open" && p.protocol == "tcp")
+            .filter(|p| SMTP_PORTS.contains(&p.number) || p.service.as_deref() == Some("smtp"))
+            .map(|p| p.number);
+        for port in smtp_ports {
+            jobs.push(Self::host_job(move |coordinator, host| async move {
+                coordinator.test_smtp_relay(&host, port).await
+                    .with_context(|| format!("SMTP relay test of {}:{}", ip, port))?;
+                Ok(())
+            }));
+        }
+
+        // Datastores left without authentication
+        let datastores = ports.iter()
+            .filter(|p| p.state == "open" && p.protocol == "tcp")
+            .filter_map(|p| DatastoreProber::datastore_for(p.number, p.service.as_deref()).map(|d| (d, p.number)));
+        for (datastore, port) in datastores {
+            jobs.push(Self::host_job(move |coordinator, host| async move {
+                coordinator.check_datastore(&host, datastore, port).await
+                    .with_context(|| format!("{} check of {}:{}", datastore, ip, port))?;
+                Ok(())
+            }));
+        }
+
+        // Baseboard management controllers answering IPMI
+        if ports.iter().any(|p| p.protocol == "udp" && p.number == IPMI_PORT && p.state == "open") {
+            jobs.push(Self::host_job(move |coordinator, host| async move {
+                coordinator.probe_ipmi(&host, IPMI_PORT).await
+                    .with_context(|| format!("IPMI probe of {}", ip))?;
+                Ok(())
+            }));
+        }
+
+        // NAT forwards on UPnP gateways often expose internal services
+        if ports.iter().any(|p| p.protocol == "udp" && p.number == SSDP_PORT) {
+            jobs.push(Self::host_job(move |coordinator, host| async move {
+                coordinator.enumerate_port_mappings(&host).await
+                    .with_context(|| format!("UPnP port-mapping enumeration for {}", ip))?;
+                Ok(())
+            }));
+        }
+
+        jobs
+    }
+
+    /// Classifies embedded devices from their web UIs
+    fn plan_web_checks(&self) -> Vec<Job<Self>> {
+        let web_ports = IotSpider::web_ports(&self.open_ports());
+        if web_ports.is_empty() {
+            return Vec::new();
+        }
+
+        let ip = self.target.ip;
+        vec![Self::host_job(move |coordinator, host| async move {
+            coordinator.classify_device(&host, &web_ports).await
+                .with_context(|| format!("Device classification of {}", ip))?;
+            Ok(())
+        })]
+    }
+}
+
+impl GraphContext for ComprehensiveScan {
+    fn snapshot(&self) -> Result<serde_json::Value> {
+        Ok(serde_json::to_value(ComprehensiveState {
+            target: self.target.clone(),
+            result: self.result(),
+        })?)
+    }
+
+    fn restore(&self, state: serde_json::Value) -> Result<()> {
+        let state: ComprehensiveState = serde_json::from_value(state)?;
+        *self.result.lock().unwrap() = state.result;
+        Ok(())
+    }
+}
+
+/// What one `ScanCoordinator::ingest` stored.
+#[derive(Debug, Default)]
+pub struct IngestSummary {
+    pub results: Vec<ScanResult>,
+    pub passive_events: usize,
+    pub access_points: usize,
+    pub clients: usize,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, TS)]
+#[ts(export, export_to = "../src/bindings/")]
 pub struct ScanStatistics {
     pub total_active: usize,
     pub running: usize,
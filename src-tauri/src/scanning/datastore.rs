@@ -0,0 +1,396 @@
+use super::Severity;
+use anyhow::{Result, anyhow, bail};
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use std::net::{IpAddr, SocketAddr};
+use std::time::Duration;
+use tokio::io::{AsyncReadExt, AsyncWriteExt};
+use tokio::net::TcpStream;
+
+pub const DEFAULT_DATASTORE_TIMEOUT: Duration = Duration::from_secs(5);
+const MAX_RESPONSE_BYTES: usize = 1024 * 1024;
+
+const MONGO_OP_MSG: i32 = 2013;
+
+/// Datastores checked for missing authentication, with their default ports
+/// and nmap service names.
+pub const DATASTORES: &[(&str, u16, &str)] = &[
+    ("redis", 6379, "redis"),
+    ("mongodb", 27017, "mongodb"),
+    ("elasticsearch", 9200, "elasticsearch"),
+    ("memcached", 11211, "memcache"),
+];
+
+/// What a datastore answered to an unauthenticated request.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct DatastoreCheck {
+    pub datastore: String,
+    pub port: u16,
+    /// The request was served without credentials
+    pub unauthenticated: bool,
+    /// Version and inventory captured from the response
+    pub metadata: String,
+    pub evidence: String,
+}
+
+impl DatastoreCheck {
+    pub fn check_name(&self) -> String {
+        format!("{}_no_auth", self.datastore)
+    }
+
+    pub fn finding(&self) -> Option<(String, Severity, String)> {
+        self.unauthenticated.then(|| (
+            format!("Unauthenticated {} (port {})", display_name(&self.datastore), self.port),
+            Severity::Critical,
+            format!(
+                "{} answered without credentials, so anyone who can reach it can read and likely modify its data. {}",
+                display_name(&self.datastore),
+                self.metadata
+            ),
+        ))
+    }
+}
+
+fn display_name(datastore: &str) -> &str {
+    match datastore {
+        "redis" => "Redis",
+        "mongodb" => "MongoDB",
+        "elasticsearch" => "Elasticsearch",
+        "memcached" => "Memcached",
+        other => other,
+    }
+}
+
+/// Native no-auth probes for common datastores. Every request is a read:
+/// Redis `INFO`, MongoDB `listDatabases`, Elasticsearch `/_cluster/health`
+/// and memcached `stats`.
+pub struct DatastoreProber {
+    client: reqwest::Client,
+    timeout: Duration,
+}
+
+impl DatastoreProber {
+    pub fn new(timeout: Duration) -> Self {
+        let client = reqwest::Client::builder()
+            .timeout(timeout)
+            .danger_accept_invalid_certs(true)
+            .build()
+            .unwrap_or_default();
+
+        Self { client, timeout }
+    }
+
+    /// The datastore a port most likely speaks, from its number or the
+    /// service nmap reported.
+    pub fn datastore_for(port: u16, service: Option<&str>) -> Option<&'static str> {
+        DATASTORES.iter()
+            .find(|(_, default_port, service_name)| *default_port == port || service == Some(*service_name))
+            .map(|(name, _, _)| *name)
+    }
+
+    pub async fn check(&self, ip: IpAddr, port: u16, datastore: &str) -> Result<DatastoreCheck> {
+        match datastore {
+            "redis" => self.check_redis(ip, port).await,
+            "mongodb" => self.check_mongodb(ip, port).await,
+            "elasticsearch" => self.check_elasticsearch(ip, port).await,
+            "memcached" => self.check_memcached(ip, port).await,
+            other => bail!("Unsupported datastore: {}", other),
+        }
+    }
+
+    async fn check_redis(&self, ip: IpAddr, port: u16) -> Result<DatastoreCheck> {
+        let response = self.exchange(ip, port, b"*1\r\n$4\r\nINFO\r\n", |data| {
+            // A bulk reply is complete once its declared length has arrived
+            let text = String::from_utf8_lossy(data);
+            match text.strip_prefix('$').and_then(|rest| rest.split_once("\r\n")) {
+                Some((len, body)) => len.parse::<usize>().map_or(true, |len| body.len() >= len),
+                None => text.contains("\r\n"),
+            }
+        }).await?;
+        let text = String::from_utf8_lossy(&response).into_owned();
+
+        if !text.starts_with('$') {
+            // -NOAUTH or -ERR operation not permitted
+            return Ok(self.secured("redis", port, text));
+        }
+
+        let field = |name: &str| text.lines()
+            .find_map(|line| line.strip_prefix(&format!("{}:", name)))
+            .map(str::trim)
+            .unwrap_or("unknown");
+        let keyspace: Vec<&str> = text.lines().filter(|line| line.starts_with("db")).collect();
+
+        Ok(DatastoreCheck {
+            datastore: "redis".to_string(),
+            port,
+            unauthenticated: true,
+            metadata: format!(
+                "Redis {} ({} mode, {}); keyspace: {}.",
+                field("redis_version"),
+                field("redis_mode"),
+                field("os"),
+                if keyspace.is_empty() { "empty".to_string() } else { keyspace.join(", ") }
+            ),
+            evidence: text,
+        })
+    }
+
+    async fn check_mongodb(&self, ip: IpAddr, port: u16) -> Result<DatastoreCheck> {
+        let mut command = BsonWriter::default();
+        command.int32("listDatabases", 1);
+        command.int32("nameOnly", 1);
+        command.string("$db", "admin");
+        let body = command.finish();
+
+        let mut message = Vec::with_capacity(21 + body.len());
+        message.extend_from_slice(&((21 + body.len()) as i32).to_le_bytes());
+        message.extend_from_slice(&0x4c32i32.to_le_bytes()); // request id
+        message.extend_from_slice(&0i32.to_le_bytes()); // response to
+        message.extend_from_slice(&MONGO_OP_MSG.to_le_bytes());
+        message.extend_from_slice(&0u32.to_le_bytes()); // flag bits
+        message.push(0); // section kind: body
+        message.extend_from_slice(&body);
+
+        let response = self.exchange(ip, port, &message, |data| {
+            data.len() >= 4 && data.len() >= i32::from_le_bytes([data[0], data[1], data[2], data[3]]) as usize
+        }).await?;
+
+        // Header (16), flag bits (4), section kind (1), then the document
+        let document = response.get(21..)
+            .ok_or_else(|| anyhow!("Truncated MongoDB reply"))
+            .and_then(parse_bson)?;
+        let evidence = serde_json::to_string_pretty(&document)?;
+
+        if document["ok"].as_f64() != Some(1.0) {
+            // Unauthorized (13) when authentication is enforced
+            return Ok(self.secured("mongodb", port, evidence));
+        }
+
+        let names: Vec<&str> = document["databases"].as_array().into_iter().flatten()
+            .filter_map(|db| db["name"].as_str())
+            .collect();
+
+        Ok(DatastoreCheck {
+            datastore: "mongodb".to_string(),
+            port,
+            unauthenticated: true,
+            metadata: format!("Databases: {}.", names.join(", ")),
+            evidence,
+        })
+    }
+
+    async fn check_elasticsearch(&self, ip: IpAddr, port: u16) -> Result<DatastoreCheck> {
+        let host = match ip {
+            IpAddr::V4(ip) => ip.to_string(),
+            IpAddr::V6(ip) => format!("[{}]", ip),
+        };
+
+        // Plain HTTP first; security-enabled clusters usually require TLS
+        let mut last_error = None;
+        for scheme in ["http", "https"] {
+            let response = match self.client.get(format!("{}://{}:{}/_cluster/health", scheme, host, port)).send().await {
+                Ok(response) => response,
+                Err(e) => {
+                    last_error = Some(e);
+                    continue;
+                }
+            };
+
+            let status = response.status();
+            let body = response.text().await.unwrap_or_default();
+            if !status.is_success() {
+                return Ok(self.secured("elasticsearch", port, format!("HTTP {}\n{}", status, body)));
+            }
+
+            let health: Value = serde_json::from_str(&body)?;
+            let version = self.client.get(format!("{}://{}:{}/", scheme, host, port))
+                .send()
+                .await
+                .ok();
+            let version = match version {
+                Some(response) => response.json::<Value>().await.ok()
+                    .and_then(|root| root["version"]["number"].as_str().map(str::to_string)),
+                None => None,
+            };
+
+            return Ok(DatastoreCheck {
+                datastore: "elasticsearch".to_string(),
+                port,
+                unauthenticated: true,
+                metadata: format!(
+                    "Elasticsearch {} cluster \"{}\", status {}, {} nodes.",
+                    version.as_deref().unwrap_or("(version unknown)"),
+                    health["cluster_name"].as_str().unwrap_or("unknown"),
+                    health["status"].as_str().unwrap_or("unknown"),
+                    health["number_of_nodes"].as_u64().unwrap_or(0)
+                ),
+                evidence: body,
+            });
+        }
+
+        Err(last_error.map(anyhow::Error::from).unwrap_or_else(|| anyhow!("No response from Elasticsearch")))
+    }
+
+    async fn check_memcached(&self, ip: IpAddr, port: u16) -> Result<DatastoreCheck> {
+        let response = self.exchange(ip, port, b"stats\r\n", |data| {
+            data.ends_with(b"END\r\n") || data.ends_with(b"ERROR\r\n")
+        }).await?;
+        let text = String::from_utf8_lossy(&response).into_owned();
+
+        if !text.starts_with("STAT ") {
+            // SASL-enabled servers refuse the ASCII protocol
+            return Ok(self.secured("memcached", port, text));
+        }
+
+        let stat = |name: &str| text.lines()
+            .find_map(|line| line.strip_prefix(&format!("STAT {} ", name)))
+            .map(str::trim)
+            .unwrap_or("unknown");
+
+        Ok(DatastoreCheck {
+            datastore: "memcached".to_string(),
+            port,
+            unauthenticated: true,
+            metadata: format!(
+                "Memcached {}, {} items, {} bytes stored.",
+                stat("version"),
+                stat("curr_items"),
+                stat("bytes")
+            ),
+            evidence: text,
+        })
+    }
+
+    fn secured(&self, datastore: &str, port: u16, evidence: String) -> DatastoreCheck {
+        DatastoreCheck {
+            datastore: datastore.to_string(),
+            port,
+            unauthenticated: false,
+            metadata: "Authentication required.".to_string(),
+            evidence,
+        }
+    }
+
+    /// Sends one request and reads until `complete` accepts the response,
+    /// the peer closes, or the timeout passes.
+    async fn exchange(&self, ip: IpAddr, port: u16, request: &[u8], complete: impl Fn(&[u8]) -> bool) -> Result<Vec<u8>> {
+        let mut stream = tokio::time::timeout(self.timeout, TcpStream::connect(SocketAddr::new(ip, port)))
+            .await
+            .map_err(|_| anyhow!("Connection to {}:{} timed out", ip, port))??;
+        stream.write_all(request).await?;
+
+        let mut response = Vec::new();
+        let mut buf = vec![0u8; 8192];
+        while !complete(&response) && response.len() < MAX_RESPONSE_BYTES {
+            match tokio::time::timeout(self.timeout, stream.read(&mut buf)).await {
+                Ok(Ok(0)) | Err(_) => break,
+                Ok(Ok(len)) => response.extend_from_slice(&buf[..len]),
+                Ok(Err(e)) => return Err(e.into()),
+            }
+        }
+
+        if response.is_empty() {
+            bail!("No response from {}:{}", ip, port);
+        }
+        Ok(response)
+    }
+}
+
+impl Default for DatastoreProber {
+    fn default() -> Self {
+        Self::new(DEFAULT_DATASTORE_TIMEOUT)
+    }
+}
+
+#[derive(Default)]
+struct BsonWriter {
+    elements: Vec<u8>,
+}
+
+impl BsonWriter {
+    fn int32(&mut self, name: &str, value: i32) {
+        self.elements.push(0x10);
+        self.cstring(name);
+        self.elements.extend_from_slice(&value.to_le_bytes());
+    }
+
+    fn string(&mut self, name: &str, value: &str) {
+        self.elements.push(0x02);
+        self.cstring(name);
+        self.elements.extend_from_slice(&((value.len() + 1) as i32).to_le_bytes());
+        self.cstring(value);
+    }
+
+    fn cstring(&mut self, value: &str) {
+        self.elements.extend_from_slice(value.as_bytes());
+        self.elements.push(0);
+    }
+
+    fn finish(self) -> Vec<u8> {
+        let mut document = ((self.elements.len() + 5) as i32).to_le_bytes().to_vec();
+        document.extend_from_slice(&self.elements);
+        document.push(0);
+        document
+    }
+}
+
+/// Decodes a BSON document into JSON, covering the types server replies
+/// use. Binary and other opaque values become placeholders.
+fn parse_bson(data: &[u8]) -> Result<Value> {
+    let (value, _) = parse_document(data, false)?;
+    Ok(value)
+}
+
+fn parse_document(data: &[u8], array: bool) -> Result<(Value, usize)> {
+    let truncated = || anyhow!("Truncated BSON document");
+    let read_i32 = |at: usize| -> Result<i32> {
+        Ok(i32::from_le_bytes(data.get(at..at + 4).ok_or_else(truncated)?.try_into()?))
+    };
+
+    let len = read_i32(0)? as usize;
+    if len < 5 || len > data.len() {
+        bail!("Invalid BSON document length");
+    }
+
+    let mut map = serde_json::Map::new();
+    let mut items = Vec::new();
+    let mut pos = 4;
+
+    while pos < len - 1 {
+        let kind = data[pos];
+        pos += 1;
+        let name_end = data[pos..len].iter().position(|&b| b == 0).ok_or_else(truncated)? + pos;
+        let name = String::from_utf8_lossy(&data[pos..name_end]).into_owned();
+        pos = name_end + 1;
+
+        let (value, size) = match kind {
+            0x01 => (Value::from(f64::from_le_bytes(data.get(pos..pos + 8).ok_or_else(truncated)?.try_into()?)), 8),
+            0x02 => {
+                let str_len = read_i32(pos)? as usize;
+                let bytes = data.get(pos + 4..pos + 4 + str_len.saturating_sub(1)).ok_or_else(truncated)?;
+                (Value::from(String::from_utf8_lossy(bytes).into_owned()), 4 + str_len)
+            }
+            0x03 | 0x04 => parse_document(&data[pos..], kind == 0x04)?,
+            0x05 => (Value::from("<binary>"), 4 + read_i32(pos)? as usize + 1),
+            0x07 => (Value::from("<objectid>"), 12),
+            0x08 => (Value::from(data.get(pos).copied().ok_or_else(truncated)? != 0), 1),
+            0x09 | 0x11 | 0x12 => {
+                let value = i64::from_le_bytes(data.get(pos..pos + 8).ok_or_else(truncated)?.try_into()?);
+                (Value::from(value), 8)
+            }
+            0x0A => (Value::Null, 0),
+            0x10 => (Value::from(read_i32(pos)?), 4),
+            other => bail!("Unsupported BSON type 0x{:02x}", other),
+        };
+        pos += size;
+
+        if array {
+            items.push(value);
+        } else {
+            map.insert(name, value);
+        }
+    }
+
+    let value = if array { Value::Array(items) } else { Value::Object(map) };
+    Ok((value, len))
+}
\ No newline at end of file
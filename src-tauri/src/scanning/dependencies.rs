@@ -0,0 +1,198 @@
+use crate::database::operations::{FlowOperations, HostOperations, PortOperations, ServiceDependencyOperations};
+use anyhow::Result;
+use chrono::{DateTime, Utc};
+use sqlx::SqlitePool;
+use std::net::IpAddr;
+
+pub const TIER_WEB: &str = "web";
+pub const TIER_DATABASE: &str = "database";
+pub const TIER_CACHE: &str = "cache";
+pub const TIER_QUEUE: &str = "queue";
+/// LDAP, Kerberos and Active Directory
+pub const TIER_DIRECTORY: &str = "directory";
+pub const TIER_FILE: &str = "file";
+pub const TIER_MAIL: &str = "mail";
+pub const TIER_DNS: &str = "dns";
+pub const TIER_REMOTE_ACCESS: &str = "remote_access";
+/// An open port whose service fits no other tier
+pub const TIER_OTHER: &str = "other";
+
+/// Tiers by the service names nmap gives
+const SERVICE_TIERS: &[(&str, &str)] = &[
+    ("http", TIER_WEB),
+    ("https", TIER_WEB),
+    ("http-proxy", TIER_WEB),
+    ("http-alt", TIER_WEB),
+    ("mysql", TIER_DATABASE),
+    ("postgresql", TIER_DATABASE),
+    ("ms-sql-s", TIER_DATABASE),
+    ("oracle-tns", TIER_DATABASE),
+    ("mongodb", TIER_DATABASE),
+    ("cassandra", TIER_DATABASE),
+    ("couchdb", TIER_DATABASE),
+    ("redis", TIER_CACHE),
+    ("memcache", TIER_CACHE),
+    ("amqp", TIER_QUEUE),
+    ("mqtt", TIER_QUEUE),
+    ("kafka", TIER_QUEUE),
+    ("ldap", TIER_DIRECTORY),
+    ("ldapssl", TIER_DIRECTORY),
+    ("kerberos-sec", TIER_DIRECTORY),
+    ("kpasswd5", TIER_DIRECTORY),
+    ("globalcatLDAP", TIER_DIRECTORY),
+    ("microsoft-ds", TIER_FILE),
+    ("netbios-ssn", TIER_FILE),
+    ("nfs", TIER_FILE),
+    ("ftp", TIER_FILE),
+    ("smtp", TIER_MAIL),
+    ("submission", TIER_MAIL),
+    ("imap", TIER_MAIL),
+    ("imaps", TIER_MAIL),
+    ("pop3", TIER_MAIL),
+    ("pop3s", TIER_MAIL),
+    ("domain", TIER_DNS),
+    ("ssh", TIER_REMOTE_ACCESS),
+    ("telnet", TIER_REMOTE_ACCESS),
+    ("ms-wbt-server", TIER_REMOTE_ACCESS),
+    ("vnc", TIER_REMOTE_ACCESS),
+];
+
+/// Tiers by well-known port, for services no scan has identified
+const PORT_TIERS: &[(u16, &str)] = &[
+    (80, TIER_WEB),
+    (443, TIER_WEB),
+    (8000, TIER_WEB),
+    (8080, TIER_WEB),
+    (8443, TIER_WEB),
+    (3306, TIER_DATABASE),
+    (5432, TIER_DATABASE),
+    (1433, TIER_DATABASE),
+    (1521, TIER_DATABASE),
+    (27017, TIER_DATABASE),
+    (9042, TIER_DATABASE),
+    (5984, TIER_DATABASE),
+    (9200, TIER_DATABASE),
+    (6379, TIER_CACHE),
+    (11211, TIER_CACHE),
+    (5672, TIER_QUEUE),
+    (1883, TIER_QUEUE),
+    (9092, TIER_QUEUE),
+    (61616, TIER_QUEUE),
+    (88, TIER_DIRECTORY),
+    (389, TIER_DIRECTORY),
+    (636, TIER_DIRECTORY),
+    (3268, TIER_DIRECTORY),
+    (21, TIER_FILE),
+    (139, TIER_FILE),
+    (445, TIER_FILE),
+    (2049, TIER_FILE),
+    (25, TIER_MAIL),
+    (110, TIER_MAIL),
+    (143, TIER_MAIL),
+    (465, TIER_MAIL),
+    (587, TIER_MAIL),
+    (993, TIER_MAIL),
+    (995, TIER_MAIL),
+    (53, TIER_DNS),
+    (22, TIER_REMOTE_ACCESS),
+    (23, TIER_REMOTE_ACCESS),
+    (3389, TIER_REMOTE_ACCESS),
+    (5900, TIER_REMOTE_ACCESS),
+];
+
+/// Turns observed flows into typed dependencies between stored hosts,
+/// e.g. a web server on its database, so the impact of a finding can be
+/// followed to the hosts relying on the affected one.
+pub struct DependencyMapper;
+
+impl DependencyMapper {
+    /// The tier of a service, by its identified name first and its port
+    /// otherwise. `ssl/` wrapped services take the tier of what they wrap.
+    pub fn tier(port: u16, service: Option<&str>) -> Option<&'static str> {
+        service
+            .map(|service| service.strip_prefix("ssl/").unwrap_or(service))
+            .and_then(|service| SERVICE_TIERS.iter().find(|(name, _)| name.eq_ignore_ascii_case(service)))
+            .or_else(|| PORT_TIERS.iter().find(|(number, _)| *number == port))
+            .map(|(_, tier)| *tier)
+    }
+
+    /// Records one flush of a flow as a dependency. Both ends have to be
+    /// stored hosts, and the server port has to be a known service or
+    /// stored open; anything else is most likely an ephemeral port.
+    /// Returns whether a dependency was recorded.
+    #[allow(clippy::too_many_arguments)]
+    pub async fn record_flow(
+        pool: &SqlitePool,
+        client_ip: IpAddr,
+        server_ip: IpAddr,
+        protocol: &str,
+        server_port: u16,
+        packets: i64,
+        bytes: i64,
+        first_seen: DateTime<Utc>,
+        last_seen: DateTime<Utc>,
+    ) -> Result<bool> {
+        let Some(client) = HostOperations::find_by_ip(pool, client_ip).await? else {
+            return Ok(false);
+        };
+        let Some(server) = HostOperations::find_by_ip(pool, server_ip).await? else {
+            return Ok(false);
+        };
+        if client.id == server.id {
+            return Ok(false);
+        }
+
+        let port = PortOperations::find(pool, &server.id, server_port, protocol).await?;
+        let service = port.as_ref().and_then(|port| port.service.clone());
+        let open = port.as_ref().is_some_and(|port| port.state == "open");
+        let Some(tier) = Self::tier(server_port, service.as_deref()).or(open.then_some(TIER_OTHER)) else {
+            return Ok(false);
+        };
+
+        ServiceDependencyOperations::record(
+            pool,
+            &client.id,
+            &server.id,
+            protocol,
+            server_port,
+            service.as_deref(),
+            tier,
+            packets,
+            bytes,
+            first_seen,
+            last_seen,
+        ).await?;
+
+        Ok(true)
+    }
+
+    /// Maps every stored flow again, picking up hosts and services found
+    /// since their traffic was sniffed. Returns the dependencies recorded.
+    pub async fn rebuild(pool: &SqlitePool) -> Result<usize> {
+        ServiceDependencyOperations::clear(pool).await?;
+
+        let mut recorded = 0;
+        for flow in FlowOperations::list(pool).await? {
+            let (Ok(client_ip), Ok(server_ip)) = (flow.client_ip.parse(), flow.server_ip.parse()) else {
+                continue;
+            };
+
+            let mapped = Self::record_flow(
+                pool,
+                client_ip,
+                server_ip,
+                &flow.protocol,
+                flow.server_port as u16,
+                flow.packets,
+                flow.bytes,
+                flow.first_seen,
+                flow.last_seen,
+            ).await?;
+            if mapped {
+                recorded += 1;
+            }
+        }
+
+        Ok(recorded)
+    }
+}
@@ -0,0 +1,114 @@
+use super::*;
+use crate::database::models::FollowUpRule;
+use crate::error::LegionError;
+use crate::utils::InputValidator;
+use anyhow::Result;
+use std::time::Duration;
+
+/// A rule fires at most once per host port within this window, so a port
+/// reported by masscan and then nmap, or by a follow-up's own scan, is
+/// only followed up once.
+pub const FOLLOW_UP_COOLDOWN: Duration = Duration::from_secs(3600);
+
+/// What a follow-up rule runs against a port that matched it.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum FollowUpAction {
+    /// Null-session share enumeration and the SMB security checks
+    SmbEnum,
+    SshAudit,
+    /// Anonymous FTP login, or an unauthenticated TFTP read on UDP
+    AnonymousAccess,
+    SmtpRelay,
+    Datastore,
+    Ipmi,
+    Upnp,
+    DeviceClassification,
+    /// An nmap scan of the port with the NSE scripts in the arguments
+    Nse,
+    /// nuclei with the template filters in the arguments, e.g. `tag:cve`
+    Nuclei,
+}
+
+impl FollowUpAction {
+    pub const ALL: [FollowUpAction; 10] = [
+        FollowUpAction::SmbEnum,
+        FollowUpAction::SshAudit,
+        FollowUpAction::AnonymousAccess,
+        FollowUpAction::SmtpRelay,
+        FollowUpAction::Datastore,
+        FollowUpAction::Ipmi,
+        FollowUpAction::Upnp,
+        FollowUpAction::DeviceClassification,
+        FollowUpAction::Nse,
+        FollowUpAction::Nuclei,
+    ];
+
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            FollowUpAction::SmbEnum => "smb_enum",
+            FollowUpAction::SshAudit => "ssh_audit",
+            FollowUpAction::AnonymousAccess => "anonymous_access",
+            FollowUpAction::SmtpRelay => "smtp_relay",
+            FollowUpAction::Datastore => "datastore",
+            FollowUpAction::Ipmi => "ipmi",
+            FollowUpAction::Upnp => "upnp",
+            FollowUpAction::DeviceClassification => "device_classification",
+            FollowUpAction::Nse => "nse",
+            FollowUpAction::Nuclei => "nuclei",
+        }
+    }
+
+    pub fn parse(action: &str) -> Result<Self> {
+        Self::ALL.into_iter()
+            .find(|a| a.as_str() == action)
+            .ok_or_else(|| LegionError::Validation(format!("Unknown follow-up action '{}'", action)).into())
+    }
+}
+
+pub struct FollowUpRules;
+
+impl FollowUpRules {
+    /// Checks a rule before it's stored and returns its action. A rule
+    /// needs a port or a service to match on, and the NSE and nuclei
+    /// actions need a selection to run.
+    pub fn validate(
+        match_port: Option<u16>,
+        match_service: Option<&str>,
+        action: &str,
+        arguments: Option<&str>,
+    ) -> Result<FollowUpAction> {
+        let match_service = match_service.map(str::trim).filter(|s| !s.is_empty());
+        if match_port.is_none() && match_service.is_none() {
+            return Err(LegionError::Validation("A follow-up rule needs a port or a service to match".to_string()).into());
+        }
+
+        let action = FollowUpAction::parse(action)?;
+        let arguments = arguments.map(str::trim).filter(|a| !a.is_empty());
+        match (action, arguments) {
+            (FollowUpAction::Nse, Some(selection)) => {
+                for script in selection.split(',') {
+                    InputValidator::validate_script_selection(script.trim())?;
+                }
+            }
+            (FollowUpAction::Nuclei, Some(selection)) => {
+                NucleiScanner::filter_args(selection)?;
+            }
+            (FollowUpAction::Nse | FollowUpAction::Nuclei, None) => {
+                return Err(LegionError::Validation(format!("The {} action needs a selection to run", action.as_str())).into());
+            }
+            _ => {}
+        }
+
+        Ok(action)
+    }
+
+    /// Open ports match on their number and, when the rule names one, on
+    /// the service nmap or the banner matcher identified.
+    pub fn matches(rule: &FollowUpRule, port: &Port) -> bool {
+        port.state == "open"
+            && rule.match_port.map_or(true, |number| number == port.number as i32)
+            && rule.match_service.as_deref().map_or(true, |service| {
+                port.service.as_deref().is_some_and(|s| s.eq_ignore_ascii_case(service))
+            })
+    }
+}
@@ -0,0 +1,266 @@
+use super::Severity;
+use anyhow::{Result, anyhow, bail};
+use serde::{Deserialize, Serialize};
+use std::net::{IpAddr, SocketAddr};
+use std::time::Duration;
+use tokio::io::{AsyncBufReadExt, AsyncReadExt, AsyncWriteExt, BufReader};
+use tokio::net::{TcpStream, UdpSocket};
+
+pub const FTP_PORT: u16 = 21;
+pub const TFTP_PORT: u16 = 69;
+pub const DEFAULT_FILE_SERVICE_TIMEOUT: Duration = Duration::from_secs(5);
+/// Directory listings are truncated beyond this
+const MAX_LISTING_BYTES: usize = 64 * 1024;
+
+const ANONYMOUS_PASSWORD: &str = "anonymous@example.com";
+
+/// Files commonly left on TFTP servers by network gear, PXE setups and
+/// VoIP phones.
+pub const TFTP_COMMON_FILES: &[&str] = &[
+    "startup-config",
+    "running-config",
+    "router-confg",
+    "network-confg",
+    "config.txt",
+    "pxelinux.0",
+    "pxelinux.cfg/default",
+    "boot.ini",
+    "SEPDefault.cnf",
+    "SIPDefault.cnf",
+];
+
+const TFTP_RRQ: u16 = 1;
+const TFTP_DATA: u16 = 3;
+const TFTP_ERROR: u16 = 5;
+
+/// Result of trying a file service without credentials.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct AnonymousAccessResult {
+    /// `ftp` or `tftp`
+    pub protocol: String,
+    pub port: u16,
+    pub banner: Option<String>,
+    /// FTP root listing, or the TFTP files that could be read
+    pub listing: String,
+}
+
+impl AnonymousAccessResult {
+    pub fn finding(&self) -> (String, Severity, String) {
+        match self.protocol.as_str() {
+            "tftp" => (
+                format!("TFTP files readable without authentication (port {})", self.port),
+                Severity::Medium,
+                format!(
+                    "The TFTP server returned common files without authentication. TFTP has no access control, \
+                     so configuration files it serves may expose credentials. Readable: {}.",
+                    self.listing.lines().collect::<Vec<_>>().join(", ")
+                ),
+            ),
+            _ => (
+                format!("Anonymous FTP login permitted (port {})", self.port),
+                Severity::Medium,
+                "The FTP server accepts the anonymous account. Review the stored directory listing for \
+                 sensitive files and disable anonymous access unless it is intended."
+                    .to_string(),
+            ),
+        }
+    }
+}
+
+/// Checks FTP for anonymous login and TFTP for unauthenticated reads
+/// natively. Nothing is uploaded or changed on the server.
+pub struct FileServiceChecker {
+    timeout: Duration,
+}
+
+impl FileServiceChecker {
+    pub fn new(timeout: Duration) -> Self {
+        Self { timeout }
+    }
+
+    /// Returns the root directory listing when anonymous login succeeds.
+    pub async fn check_ftp(&self, ip: IpAddr, port: u16) -> Result<Option<AnonymousAccessResult>> {
+        tokio::time::timeout(self.timeout * 3, self.ftp_session(ip, port))
+            .await
+            .map_err(|_| anyhow!("FTP session to {}:{} timed out", ip, port))?
+    }
+
+    async fn ftp_session(&self, ip: IpAddr, port: u16) -> Result<Option<AnonymousAccessResult>> {
+        let stream = tokio::time::timeout(self.timeout, TcpStream::connect(SocketAddr::new(ip, port)))
+            .await
+            .map_err(|_| anyhow!("Connection to {}:{} timed out", ip, port))??;
+        let (reader, mut writer) = stream.into_split();
+        let mut reader = BufReader::new(reader);
+
+        let (code, banner) = read_reply(&mut reader).await?;
+        if code != 220 {
+            bail!("Unexpected FTP greeting: {} {}", code, banner);
+        }
+
+        let (code, _) = command(&mut reader, &mut writer, "USER anonymous").await?;
+        let code = match code {
+            230 => code,
+            331 => command(&mut reader, &mut writer, &format!("PASS {}", ANONYMOUS_PASSWORD)).await?.0,
+            _ => code,
+        };
+        if code != 230 {
+            let _ = command(&mut reader, &mut writer, "QUIT").await;
+            return Ok(None);
+        }
+
+        // Login alone is the finding; a failed listing still leaves that
+        let listing = self.ftp_list(ip, &mut reader, &mut writer).await
+            .unwrap_or_else(|e| format!("(listing failed: {})", e));
+        let _ = command(&mut reader, &mut writer, "QUIT").await;
+
+        Ok(Some(AnonymousAccessResult {
+            protocol: "ftp".to_string(),
+            port,
+            banner: Some(banner),
+            listing,
+        }))
+    }
+
+    async fn ftp_list<R, W>(&self, ip: IpAddr, reader: &mut BufReader<R>, writer: &mut W) -> Result<String>
+    where
+        R: tokio::io::AsyncRead + Unpin,
+        W: tokio::io::AsyncWrite + Unpin,
+    {
+        // EPSV works over IPv6 and never hands us some other address
+        let (mut code, mut reply) = command(reader, writer, "EPSV").await?;
+        if code != 229 {
+            (code, reply) = command(reader, writer, "PASV").await?;
+        }
+        let data_port = match code {
+            229 => reply.split('|').find_map(|p| p.parse::<u16>().ok()),
+            227 => parse_pasv_port(&reply),
+            _ => bail!("Passive mode refused: {} {}", code, reply),
+        }
+        .ok_or_else(|| anyhow!("Unparseable passive reply: {}", reply))?;
+
+        let mut data = tokio::time::timeout(self.timeout, TcpStream::connect(SocketAddr::new(ip, data_port)))
+            .await
+            .map_err(|_| anyhow!("Data connection timed out"))??;
+
+        let (code, reply) = command(reader, writer, "LIST").await?;
+        if code != 125 && code != 150 {
+            bail!("LIST refused: {} {}", code, reply);
+        }
+
+        let mut listing = Vec::new();
+        tokio::time::timeout(self.timeout, (&mut data).take(MAX_LISTING_BYTES as u64).read_to_end(&mut listing))
+            .await
+            .map_err(|_| anyhow!("Listing timed out"))??;
+        drop(data);
+        let _ = read_reply(reader).await;
+
+        Ok(String::from_utf8_lossy(&listing).into_owned())
+    }
+
+    /// Requests each common filename and returns the ones the server
+    /// started sending. Only the first block of each is read.
+    pub async fn check_tftp(&self, ip: IpAddr, port: u16) -> Result<Option<AnonymousAccessResult>> {
+        let mut readable = Vec::new();
+        for file in TFTP_COMMON_FILES {
+            if let Some(size_hint) = self.tftp_read(ip, port, file).await? {
+                readable.push(format!("{} ({} bytes in first block)", file, size_hint));
+            }
+        }
+
+        if readable.is_empty() {
+            return Ok(None);
+        }
+
+        Ok(Some(AnonymousAccessResult {
+            protocol: "tftp".to_string(),
+            port,
+            banner: None,
+            listing: readable.join("\n"),
+        }))
+    }
+
+    async fn tftp_read(&self, ip: IpAddr, port: u16, file: &str) -> Result<Option<usize>> {
+        let bind_addr: SocketAddr = match ip {
+            IpAddr::V4(_) => "0.0.0.0:0".parse()?,
+            IpAddr::V6(_) => "[::]:0".parse()?,
+        };
+        // Not connected: the server answers from a fresh port
+        let socket = UdpSocket::bind(bind_addr).await?;
+
+        let mut request = TFTP_RRQ.to_be_bytes().to_vec();
+        request.extend_from_slice(file.as_bytes());
+        request.push(0);
+        request.extend_from_slice(b"octet\0");
+        socket.send_to(&request, SocketAddr::new(ip, port)).await?;
+
+        let mut buf = vec![0u8; 1024];
+        let (len, from) = loop {
+            match tokio::time::timeout(self.timeout, socket.recv_from(&mut buf)).await {
+                Ok(Ok((len, from))) if from.ip() == ip => break (len, from),
+                Ok(Ok(_)) => continue,
+                Ok(Err(_)) | Err(_) => return Ok(None),
+            }
+        };
+        if len < 4 {
+            return Ok(None);
+        }
+
+        match u16::from_be_bytes([buf[0], buf[1]]) {
+            TFTP_DATA => {
+                // Abort the transfer instead of acknowledging the block
+                let mut abort = TFTP_ERROR.to_be_bytes().to_vec();
+                abort.extend_from_slice(&[0, 0]);
+                abort.extend_from_slice(b"transfer cancelled\0");
+                let _ = socket.send_to(&abort, from).await;
+                Ok(Some(len - 4))
+            }
+            _ => Ok(None),
+        }
+    }
+}
+
+impl Default for FileServiceChecker {
+    fn default() -> Self {
+        Self::new(DEFAULT_FILE_SERVICE_TIMEOUT)
+    }
+}
+
+async fn command<R, W>(reader: &mut BufReader<R>, writer: &mut W, line: &str) -> Result<(u16, String)>
+where
+    R: tokio::io::AsyncRead + Unpin,
+    W: tokio::io::AsyncWrite + Unpin,
+{
+    writer.write_all(format!("{}\r\n", line).as_bytes()).await?;
+    read_reply(reader).await
+}
+
+/// Reads one reply, following `123-` continuation lines to the final
+/// `123 ` line.
+async fn read_reply<R: tokio::io::AsyncRead + Unpin>(reader: &mut BufReader<R>) -> Result<(u16, String)> {
+    let mut text = Vec::new();
+    loop {
+        let mut line = String::new();
+        if reader.read_line(&mut line).await? == 0 {
+            bail!("FTP connection closed");
+        }
+        let line = line.trim_end();
+        text.push(line.get(4..).unwrap_or("").to_string());
+
+        if line.len() >= 4 && line.as_bytes()[3] == b' ' {
+            if let Ok(code) = line[..3].parse::<u16>() {
+                return Ok((code, text.join("\n")));
+            }
+        }
+    }
+}
+
+/// Port from `227 Entering Passive Mode (h1,h2,h3,h4,p1,p2)`. The address
+/// is ignored so a server can't redirect the data connection elsewhere.
+fn parse_pasv_port(reply: &str) -> Option<u16> {
+    let inner = reply.split('(').nth(1)?.split(')').next()?;
+    let numbers: Vec<u16> = inner.split(',').filter_map(|n| n.trim().parse().ok()).collect();
+    match numbers.as_slice() {
+        [_, _, _, _, high, low] => Some(high * 256 + low),
+        _ => None,
+    }
+}
\ No newline at end of file
@@ -0,0 +1,432 @@
+use super::Port;
+use anyhow::{Result, anyhow, bail};
+use serde::{Deserialize, Serialize};
+use std::net::{IpAddr, SocketAddr};
+use std::time::Duration;
+use tokio::io::{AsyncReadExt, AsyncWriteExt};
+use tokio::net::{TcpStream, UdpSocket};
+
+pub const DEFAULT_ICS_PROBE_TIMEOUT: Duration = Duration::from_secs(3);
+
+pub const MODBUS_PORT: u16 = 502;
+pub const S7_PORT: u16 = 102;
+pub const DNP3_PORT: u16 = 20000;
+pub const BACNET_PORT: u16 = 47808;
+
+/// Outstation addresses tried for DNP3; there is no broadcast that answers
+const DNP3_ADDRESSES: &[u16] = &[1, 0, 10, 100, 1024];
+/// Our DNP3 master address
+const DNP3_MASTER: u16 = 3;
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct IcsIdentity {
+    /// `modbus`, `s7`, `dnp3` or `bacnet`
+    pub protocol: String,
+    pub port: u16,
+    pub vendor: Option<String>,
+    pub model: Option<String>,
+    pub firmware: Option<String>,
+    pub details: Option<String>,
+}
+
+impl IcsIdentity {
+    fn new(protocol: &str, port: u16) -> Self {
+        Self {
+            protocol: protocol.to_string(),
+            port,
+            vendor: None,
+            model: None,
+            firmware: None,
+            details: None,
+        }
+    }
+
+    pub fn to_port(&self) -> Port {
+        let version = [&self.vendor, &self.model, &self.firmware]
+            .iter()
+            .filter_map(|p| p.as_deref())
+            .collect::<Vec<_>>()
+            .join(" ");
+
+        Port {
+            number: self.port,
+            protocol: if self.protocol == "bacnet" { "udp" } else { "tcp" }.to_string(),
+            state: "open".to_string(),
+            service: Some(self.protocol.clone()),
+            version: (!version.is_empty()).then_some(version),
+            banner: self.details.clone(),
+        }
+    }
+}
+
+/// OT discovery with native, read-only identification requests. Each
+/// device gets at most a handful of packets, one connection at a time,
+/// since PLCs and RTUs are easily upset by scanner traffic.
+pub struct IcsProber {
+    timeout: Duration,
+}
+
+impl IcsProber {
+    pub fn new(timeout: Duration) -> Self {
+        Self { timeout }
+    }
+
+    /// Runs every protocol probe against `ip` in turn and returns the
+    /// services that identified themselves.
+    pub async fn probe_host(&self, ip: IpAddr) -> Vec<IcsIdentity> {
+        let mut identities = Vec::new();
+
+        let probes = [
+            ("modbus", self.with_timeout(Self::modbus(ip)).await),
+            ("s7", self.with_timeout(Self::s7(ip)).await),
+            ("dnp3", self.dnp3(ip).await),
+            ("bacnet", self.bacnet(ip).await),
+        ];
+
+        for (protocol, result) in probes {
+            match result {
+                Ok(Some(identity)) => identities.push(identity),
+                Ok(None) => {}
+                Err(e) => eprintln!("{} probe to {} failed: {}", protocol, ip, e),
+            }
+        }
+
+        identities
+    }
+
+    async fn with_timeout<F>(&self, probe: F) -> Result<Option<IcsIdentity>>
+    where
+        F: std::future::Future<Output = Result<Option<IcsIdentity>>>,
+    {
+        match tokio::time::timeout(self.timeout, probe).await {
+            Ok(result) => result,
+            Err(_) => Ok(None),
+        }
+    }
+
+    /// Modbus/TCP Read Device Identification (function 0x2B / MEI 0x0E),
+    /// basic objects: vendor name, product code and revision.
+    async fn modbus(ip: IpAddr) -> Result<Option<IcsIdentity>> {
+        let Some(mut stream) = connect(ip, MODBUS_PORT).await else {
+            return Ok(None);
+        };
+
+        stream.write_all(&[
+            0x00, 0x01, // transaction id
+            0x00, 0x00, // protocol id
+            0x00, 0x05, // length
+            0x00, // unit id
+            0x2b, 0x0e, 0x01, 0x00,
+        ]).await?;
+
+        let mut header = [0u8; 7];
+        stream.read_exact(&mut header).await?;
+        let len = u16::from_be_bytes([header[4], header[5]]) as usize;
+        let mut pdu = vec![0u8; len.saturating_sub(1)];
+        stream.read_exact(&mut pdu).await?;
+
+        let mut identity = IcsIdentity::new("modbus", MODBUS_PORT);
+
+        // An exception response still proves a Modbus server is listening
+        if pdu.first() != Some(&0x2b) || pdu.len() < 7 {
+            identity.details = Some("Modbus exception response (unit 0)".to_string());
+            return Ok(Some(identity));
+        }
+
+        let count = pdu[6] as usize;
+        let mut pos = 7;
+        for _ in 0..count {
+            let (Some(&id), Some(&obj_len)) = (pdu.get(pos), pdu.get(pos + 1)) else {
+                break;
+            };
+            let Some(value) = pdu.get(pos + 2..pos + 2 + obj_len as usize) else {
+                break;
+            };
+            let value = Some(String::from_utf8_lossy(value).trim().to_string());
+
+            match id {
+                0x00 => identity.vendor = value,
+                0x01 => identity.model = value,
+                0x02 => identity.firmware = value,
+                _ => {}
+            }
+            pos += 2 + obj_len as usize;
+        }
+
+        Ok(Some(identity))
+    }
+
+    /// Siemens S7comm: COTP connect, S7 setup, then a read of SZL 0x0011
+    /// (module identification) for the order number.
+    async fn s7(ip: IpAddr) -> Result<Option<IcsIdentity>> {
+        const COTP_CONNECT: &[u8] = &[
+            0x03, 0x00, 0x00, 0x16, 0x11, 0xe0, 0x00, 0x00, 0x00, 0x14, 0x00,
+            0xc1, 0x02, 0x01, 0x00, 0xc2, 0x02, 0x01, 0x02, 0xc0, 0x01, 0x0a,
+        ];
+        const S7_SETUP: &[u8] = &[
+            0x03, 0x00, 0x00, 0x19, 0x02, 0xf0, 0x80, 0x32, 0x01, 0x00, 0x00, 0x00, 0x00,
+            0x00, 0x08, 0x00, 0x00, 0xf0, 0x00, 0x00, 0x01, 0x00, 0x01, 0x01, 0xe0,
+        ];
+        const SZL_MODULE_ID: &[u8] = &[
+            0x03, 0x00, 0x00, 0x21, 0x02, 0xf0, 0x80, 0x32, 0x07, 0x00, 0x00, 0x00, 0x00,
+            0x00, 0x08, 0x00, 0x08, 0x00, 0x01, 0x12, 0x04, 0x11, 0x44, 0x01, 0x00, 0xff,
+            0x09, 0x00, 0x04, 0x00, 0x11, 0x00, 0x01,
+        ];
+
+        let Some(mut stream) = connect(ip, S7_PORT).await else {
+            return Ok(None);
+        };
+
+        stream.write_all(COTP_CONNECT).await?;
+        let confirm = read_tpkt(&mut stream).await?;
+        // COTP connection confirm
+        if confirm.get(5) != Some(&0xd0) {
+            return Ok(None);
+        }
+
+        let mut identity = IcsIdentity::new("s7", S7_PORT);
+        identity.vendor = Some("Siemens".to_string());
+
+        stream.write_all(S7_SETUP).await?;
+        let setup = read_tpkt(&mut stream).await?;
+        if setup.get(7) != Some(&0x32) {
+            identity.details = Some("ISO-TSAP service without S7comm".to_string());
+            return Ok(Some(identity));
+        }
+
+        stream.write_all(SZL_MODULE_ID).await?;
+        let szl = read_tpkt(&mut stream).await?;
+
+        // Siemens order numbers (MLFB) all start with 6ES7
+        if let Some(pos) = szl.windows(4).position(|w| w == b"6ES7") {
+            let mlfb = &szl[pos..(pos + 20).min(szl.len())];
+            identity.model = Some(String::from_utf8_lossy(mlfb).trim().to_string());
+        }
+
+        Ok(Some(identity))
+    }
+
+    /// DNP3 link status request, then a read of the device attribute
+    /// objects (group 0) for manufacturer and product names.
+    async fn dnp3(&self, ip: IpAddr) -> Result<Option<IcsIdentity>> {
+        for &address in DNP3_ADDRESSES {
+            let found = self.with_timeout(async {
+                let Some(mut stream) = connect(ip, DNP3_PORT).await else {
+                    return Ok(None);
+                };
+
+                stream.write_all(&dnp3_frame(0xc9, address, &[])).await?;
+                let status = read_dnp3_frame(&mut stream).await?;
+                // Link status response, control byte 0x0b
+                if status.control & 0x0f != 0x0b {
+                    return Ok(None);
+                }
+
+                let mut identity = IcsIdentity::new("dnp3", DNP3_PORT);
+                identity.details = Some(format!("DNP3 outstation address {}", status.source));
+
+                // transport FIR|FIN, application FIR|FIN READ, g0v252 and g0v250 all
+                let read = [0xc0, 0xc0, 0x01, 0x00, 0xfc, 0x06, 0x00, 0xfa, 0x06];
+                stream.write_all(&dnp3_frame(0xc4, status.source, &read)).await?;
+                if let Ok(response) = read_dnp3_frame(&mut stream).await {
+                    for (variation, value) in parse_device_attributes(&response.data) {
+                        match variation {
+                            252 => identity.vendor = Some(value),
+                            250 => identity.model = Some(value),
+                            _ => {}
+                        }
+                    }
+                }
+
+                Ok(Some(identity))
+            }).await?;
+
+            if found.is_some() {
+                return Ok(found);
+            }
+        }
+
+        Ok(None)
+    }
+
+    /// BACnet/IP ReadProperty against the wildcard device instance for
+    /// vendor, model and firmware.
+    async fn bacnet(&self, ip: IpAddr) -> Result<Option<IcsIdentity>> {
+        const VENDOR_NAME: u8 = 121;
+        const MODEL_NAME: u8 = 70;
+        const FIRMWARE_REVISION: u8 = 44;
+        const OBJECT_NAME: u8 = 77;
+
+        let bind_addr: SocketAddr = match ip {
+            IpAddr::V4(_) => "0.0.0.0:0".parse()?,
+            IpAddr::V6(_) => "[::]:0".parse()?,
+        };
+        let socket = UdpSocket::bind(bind_addr).await?;
+        socket.connect(SocketAddr::new(ip, BACNET_PORT)).await?;
+
+        let mut identity = IcsIdentity::new("bacnet", BACNET_PORT);
+        let mut answered = false;
+
+        for (invoke_id, property) in [VENDOR_NAME, MODEL_NAME, FIRMWARE_REVISION, OBJECT_NAME].into_iter().enumerate() {
+            socket.send(&[
+                0x81, 0x0a, 0x00, 0x11, // BVLC original unicast
+                0x01, 0x04, // NPDU expecting reply
+                0x00, 0x05, invoke_id as u8, 0x0c, // confirmed ReadProperty
+                0x0c, 0x02, 0x3f, 0xff, 0xff, // device, instance 4194303
+                0x19, property,
+            ]).await?;
+
+            let mut buf = vec![0u8; 1500];
+            let len = match tokio::time::timeout(self.timeout, socket.recv(&mut buf)).await {
+                Ok(Ok(len)) => len,
+                // The first silence means nothing is listening
+                _ if !answered => return Ok(None),
+                _ => continue,
+            };
+            answered = true;
+
+            let value = bacnet_string(&buf[..len]);
+            match property {
+                VENDOR_NAME => identity.vendor = value,
+                MODEL_NAME => identity.model = value,
+                FIRMWARE_REVISION => identity.firmware = value,
+                _ => identity.details = value,
+            }
+        }
+
+        Ok(Some(identity))
+    }
+}
+
+impl Default for IcsProber {
+    fn default() -> Self {
+        Self::new(DEFAULT_ICS_PROBE_TIMEOUT)
+    }
+}
+
+async fn connect(ip: IpAddr, port: u16) -> Option<TcpStream> {
+    TcpStream::connect(SocketAddr::new(ip, port)).await.ok()
+}
+
+async fn read_tpkt(stream: &mut TcpStream) -> Result<Vec<u8>> {
+    let mut header = [0u8; 4];
+    stream.read_exact(&mut header).await?;
+    if header[0] != 0x03 {
+        bail!("Not a TPKT packet");
+    }
+
+    let len = u16::from_be_bytes([header[2], header[3]]) as usize;
+    let mut packet = header.to_vec();
+    packet.resize(len.max(4), 0);
+    stream.read_exact(&mut packet[4..]).await?;
+    Ok(packet)
+}
+
+struct Dnp3Frame {
+    control: u8,
+    source: u16,
+    data: Vec<u8>,
+}
+
+/// DNP3 CRC: CRC-16 with the reflected 0x3D65 polynomial, complemented.
+fn dnp3_crc(data: &[u8]) -> [u8; 2] {
+    let mut crc: u16 = 0;
+    for &byte in data {
+        crc ^= byte as u16;
+        for _ in 0..8 {
+            crc = if crc & 1 != 0 { (crc >> 1) ^ 0xa6bc } else { crc >> 1 };
+        }
+    }
+    (!crc).to_le_bytes()
+}
+
+fn dnp3_frame(control: u8, destination: u16, data: &[u8]) -> Vec<u8> {
+    let mut frame = vec![0x05, 0x64, (5 + data.len()) as u8, control];
+    frame.extend_from_slice(&destination.to_le_bytes());
+    frame.extend_from_slice(&DNP3_MASTER.to_le_bytes());
+    let crc = dnp3_crc(&frame);
+    frame.extend_from_slice(&crc);
+
+    // User data goes in 16-byte blocks, each with its own CRC
+    for block in data.chunks(16) {
+        frame.extend_from_slice(block);
+        frame.extend_from_slice(&dnp3_crc(block));
+    }
+    frame
+}
+
+async fn read_dnp3_frame(stream: &mut TcpStream) -> Result<Dnp3Frame> {
+    let mut header = [0u8; 10];
+    stream.read_exact(&mut header).await?;
+    if header[0..2] != [0x05, 0x64] {
+        bail!("Not a DNP3 frame");
+    }
+
+    let data_len = (header[2] as usize).checked_sub(5)
+        .ok_or_else(|| anyhow!("Malformed DNP3 length"))?;
+    let crc_len = data_len.div_ceil(16) * 2;
+    let mut body = vec![0u8; data_len + crc_len];
+    stream.read_exact(&mut body).await?;
+
+    let data = body.chunks(18)
+        .flat_map(|block| block[..block.len().saturating_sub(2)].to_vec())
+        .collect();
+
+    Ok(Dnp3Frame {
+        control: header[3],
+        source: u16::from_le_bytes([header[6], header[7]]),
+        data,
+    })
+}
+
+/// Extracts string device attributes (group 0) from an application
+/// response: transport header, app control, function, IIN, then objects.
+fn parse_device_attributes(data: &[u8]) -> Vec<(u8, String)> {
+    let mut attributes = Vec::new();
+    let mut pos = 5;
+
+    while let Some(header) = data.get(pos..pos + 3) {
+        let (group, variation, qualifier) = (header[0], header[1], header[2]);
+        // Only the 8-bit start/stop range form carries attributes here
+        if group != 0 || qualifier != 0x00 {
+            break;
+        }
+        pos += 5;
+
+        let (Some(&data_type), Some(&len)) = (data.get(pos), data.get(pos + 1)) else {
+            break;
+        };
+        let Some(value) = data.get(pos + 2..pos + 2 + len as usize) else {
+            break;
+        };
+        // Data type 1 is a visible string
+        if data_type == 1 {
+            attributes.push((variation, String::from_utf8_lossy(value).trim().to_string()));
+        }
+        pos += 2 + len as usize;
+    }
+
+    attributes
+}
+
+/// Character string value of a ReadProperty complex ACK.
+fn bacnet_string(response: &[u8]) -> Option<String> {
+    // Complex ACK PDU type, then the value between opening and closing tag 3
+    if response.get(6)? >> 4 != 0x3 {
+        return None;
+    }
+
+    let open = response.iter().position(|&b| b == 0x3e)?;
+    let tag = *response.get(open + 1)?;
+    if tag >> 4 != 7 {
+        return None;
+    }
+
+    let (len, start) = match tag & 0x07 {
+        5 => (*response.get(open + 2)? as usize, open + 3),
+        len => (len as usize, open + 2),
+    };
+    // The first content byte is the character set
+    let value = response.get(start + 1..start + len)?;
+    Some(String::from_utf8_lossy(value).trim().to_string())
+}
\ No newline at end of file
@@ -0,0 +1,347 @@
+use super::Port;
+use anyhow::Result;
+use base64::Engine;
+use serde::{Deserialize, Serialize};
+use std::net::{IpAddr, SocketAddr};
+use std::time::Duration;
+use tokio::net::UdpSocket;
+
+pub const DEFAULT_IOT_SPIDER_TIMEOUT: Duration = Duration::from_secs(5);
+/// Only the start of a page is searched for fingerprints
+const MAX_BODY_BYTES: usize = 256 * 1024;
+pub const COAP_PORT: u16 = 5683;
+
+pub const DEVICE_CAMERA: &str = "camera";
+pub const DEVICE_PRINTER: &str = "printer";
+pub const DEVICE_ROUTER: &str = "router";
+pub const DEVICE_NAS: &str = "nas";
+pub const DEVICE_PLC: &str = "plc";
+pub const DEVICE_BMC: &str = "bmc";
+pub const DEVICE_TYPES: &[&str] = &[DEVICE_CAMERA, DEVICE_PRINTER, DEVICE_ROUTER, DEVICE_NAS, DEVICE_PLC, DEVICE_BMC];
+
+pub enum FingerprintRule {
+    /// Shodan-style favicon hash: MurmurHash3 of the base64-encoded icon
+    FaviconHash(i32),
+    /// Case-insensitive substring of the page title
+    Title(&'static str),
+    /// Case-insensitive substring of the default page body
+    Body(&'static str),
+}
+
+pub struct WebFingerprint {
+    pub device_type: &'static str,
+    pub product: &'static str,
+    pub rule: FingerprintRule,
+}
+
+use FingerprintRule::*;
+
+/// Checked in order; favicon hashes are the most specific, generic title
+/// words the least.
+pub const WEB_FINGERPRINTS: &[WebFingerprint] = &[
+    WebFingerprint { device_type: DEVICE_CAMERA, product: "Hikvision", rule: FaviconHash(999357577) },
+    WebFingerprint { device_type: DEVICE_CAMERA, product: "Hikvision", rule: Body("doc/page/login.asp") },
+    WebFingerprint { device_type: DEVICE_CAMERA, product: "Axis", rule: Body("/axis-cgi/") },
+    WebFingerprint { device_type: DEVICE_CAMERA, product: "Xiongmai", rule: Title("NETSurveillance WEB") },
+    WebFingerprint { device_type: DEVICE_CAMERA, product: "Dahua", rule: Body("/baseProj/images/favicon.ico") },
+    WebFingerprint { device_type: DEVICE_CAMERA, product: "Generic IP camera", rule: Title("Network Camera") },
+    WebFingerprint { device_type: DEVICE_CAMERA, product: "Generic IP camera", rule: Title("IPCam") },
+    WebFingerprint { device_type: DEVICE_PRINTER, product: "HP", rule: Body("/hp/device/") },
+    WebFingerprint { device_type: DEVICE_PRINTER, product: "HP", rule: Title("LaserJet") },
+    WebFingerprint { device_type: DEVICE_PRINTER, product: "Ricoh", rule: Title("Web Image Monitor") },
+    WebFingerprint { device_type: DEVICE_PRINTER, product: "Xerox", rule: Title("CentreWare Internet Services") },
+    WebFingerprint { device_type: DEVICE_PRINTER, product: "Brother", rule: Body("/general/status.html") },
+    WebFingerprint { device_type: DEVICE_PRINTER, product: "Epson", rule: Title("EPSON") },
+    WebFingerprint { device_type: DEVICE_PRINTER, product: "Canon", rule: Title("Remote UI") },
+    WebFingerprint { device_type: DEVICE_ROUTER, product: "MikroTik RouterOS", rule: Title("RouterOS") },
+    WebFingerprint { device_type: DEVICE_ROUTER, product: "OpenWrt", rule: Body("/cgi-bin/luci") },
+    WebFingerprint { device_type: DEVICE_ROUTER, product: "DD-WRT", rule: Title("DD-WRT") },
+    WebFingerprint { device_type: DEVICE_ROUTER, product: "pfSense", rule: Title("pfSense") },
+    WebFingerprint { device_type: DEVICE_ROUTER, product: "Ubiquiti airOS", rule: Title("airOS") },
+    WebFingerprint { device_type: DEVICE_ROUTER, product: "TP-Link", rule: Title("TP-LINK") },
+    WebFingerprint { device_type: DEVICE_ROUTER, product: "Netgear", rule: Title("NETGEAR") },
+    WebFingerprint { device_type: DEVICE_ROUTER, product: "ASUS", rule: Title("ASUS Wireless Router") },
+    WebFingerprint { device_type: DEVICE_NAS, product: "Synology DSM", rule: Title("Synology") },
+    WebFingerprint { device_type: DEVICE_NAS, product: "Synology DSM", rule: Body("webman/modules") },
+    WebFingerprint { device_type: DEVICE_NAS, product: "QNAP QTS", rule: Body("/cgi-bin/QTS.cgi") },
+    WebFingerprint { device_type: DEVICE_NAS, product: "QNAP", rule: Title("QNAP") },
+    WebFingerprint { device_type: DEVICE_NAS, product: "TrueNAS", rule: Title("TrueNAS") },
+    WebFingerprint { device_type: DEVICE_NAS, product: "FreeNAS", rule: Title("FreeNAS") },
+    WebFingerprint { device_type: DEVICE_NAS, product: "WD My Cloud", rule: Title("My Cloud") },
+    WebFingerprint { device_type: DEVICE_NAS, product: "Netgear ReadyNAS", rule: Title("ReadyNAS") },
+    WebFingerprint { device_type: DEVICE_PLC, product: "Siemens SIMATIC", rule: Title("SIMATIC") },
+    WebFingerprint { device_type: DEVICE_PLC, product: "Siemens S7", rule: Body("S7-1200") },
+    WebFingerprint { device_type: DEVICE_PLC, product: "Siemens S7", rule: Body("S7-1500") },
+    WebFingerprint { device_type: DEVICE_PLC, product: "Schneider Modicon", rule: Title("Modicon") },
+    WebFingerprint { device_type: DEVICE_PLC, product: "Rockwell Allen-Bradley", rule: Title("Allen-Bradley") },
+    WebFingerprint { device_type: DEVICE_PLC, product: "WAGO", rule: Title("WAGO") },
+    WebFingerprint { device_type: DEVICE_PLC, product: "Beckhoff TwinCAT", rule: Body("TwinCAT") },
+];
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct DeviceMatch {
+    pub device_type: String,
+    pub product: String,
+    pub url: String,
+}
+
+/// Crawls the web interfaces of embedded devices and classifies them
+/// against the fingerprint library.
+pub struct IotSpider {
+    client: reqwest::Client,
+    timeout: Duration,
+}
+
+impl IotSpider {
+    pub fn new(timeout: Duration) -> Self {
+        // Embedded web UIs almost always present self-signed certificates
+        let client = reqwest::Client::builder()
+            .timeout(timeout)
+            .danger_accept_invalid_certs(true)
+            .redirect(reqwest::redirect::Policy::limited(3))
+            .build()
+            .unwrap_or_default();
+
+        Self { client, timeout }
+    }
+
+    /// Ports worth fetching: anything identified as HTTP, or on a common
+    /// web port when the service is unknown.
+    pub fn web_ports(ports: &[Port]) -> Vec<(u16, bool)> {
+        ports.iter()
+            .filter(|p| p.state == "open" && p.protocol == "tcp")
+            .filter_map(|p| {
+                let service = p.service.as_deref().unwrap_or("");
+                let tls = service.contains("https") || service.contains("ssl") || matches!(p.number, 443 | 8443);
+                let web = service.contains("http") || matches!(p.number, 80 | 443 | 8000 | 8080 | 8081 | 8443 | 8888);
+                web.then_some((p.number, tls))
+            })
+            .collect()
+    }
+
+    /// Returns the first fingerprint any of the web ports matches.
+    pub async fn classify(&self, ip: IpAddr, ports: &[(u16, bool)]) -> Result<Option<DeviceMatch>> {
+        for &(port, tls) in ports {
+            let host = match ip {
+                IpAddr::V4(v4) => v4.to_string(),
+                IpAddr::V6(v6) => format!("[{}]", v6),
+            };
+            let base = format!("{}://{}:{}", if tls { "https" } else { "http" }, host, port);
+
+            let body = match self.fetch(&format!("{}/", base)).await {
+                Ok(body) => body,
+                Err(e) => {
+                    eprintln!("IoT spider could not fetch {}: {}", base, e);
+                    continue;
+                }
+            };
+            let page = String::from_utf8_lossy(&body).to_lowercase();
+            let title = extract_title(&page);
+
+            // Fetched on first use, at most once per port
+            let mut favicon: Option<Option<i32>> = None;
+
+            for fingerprint in WEB_FINGERPRINTS {
+                let matched = match fingerprint.rule {
+                    FaviconHash(hash) => {
+                        if favicon.is_none() {
+                            favicon = Some(self.fetch(&format!("{}/favicon.ico", base)).await
+                                .ok()
+                                .filter(|icon| !icon.is_empty())
+                                .map(|icon| favicon_hash(&icon)));
+                        }
+                        favicon.flatten() == Some(hash)
+                    }
+                    Title(needle) => title.map(|t| t.contains(&needle.to_lowercase())).unwrap_or(false),
+                    Body(needle) => page.contains(&needle.to_lowercase()),
+                };
+
+                if matched {
+                    return Ok(Some(DeviceMatch {
+                        device_type: fingerprint.device_type.to_string(),
+                        product: fingerprint.product.to_string(),
+                        url: base,
+                    }));
+                }
+            }
+        }
+
+        Ok(None)
+    }
+
+    /// CoAP resource discovery: a GET of `/.well-known/core` over UDP.
+    /// Constrained devices often speak nothing else, so this is the only
+    /// way they show up at all.
+    pub async fn probe_coap(&self, ip: IpAddr) -> Result<Option<Port>> {
+        let bind_addr: SocketAddr = match ip {
+            IpAddr::V4(_) => "0.0.0.0:0".parse()?,
+            IpAddr::V6(_) => "[::]:0".parse()?,
+        };
+
+        let socket = UdpSocket::bind(bind_addr).await?;
+        socket.connect(SocketAddr::new(ip, COAP_PORT)).await?;
+        socket.send(&coap_well_known_core()).await?;
+
+        let mut buf = vec![0u8; 2048];
+        loop {
+            let len = match tokio::time::timeout(self.timeout, socket.recv(&mut buf)).await {
+                Ok(Ok(len)) => len,
+                Ok(Err(_)) | Err(_) => return Ok(None),
+            };
+            let Some((code, payload)) = parse_coap_response(&buf[..len]) else {
+                return Ok(None);
+            };
+
+            // An empty ACK means the response follows separately
+            if code == 0 {
+                continue;
+            }
+
+            let listing = String::from_utf8_lossy(payload).to_string();
+            let resources = parse_link_format(&listing);
+
+            return Ok(Some(Port {
+                number: COAP_PORT,
+                protocol: "udp".to_string(),
+                state: "open".to_string(),
+                service: Some("coap".to_string()),
+                version: Some(format!("CoAP {}.{:02}, {} resources", code >> 5, code & 0x1f, resources.len())),
+                banner: (!resources.is_empty()).then(|| resources.join(" ")),
+            }));
+        }
+    }
+
+    async fn fetch(&self, url: &str) -> Result<Vec<u8>> {
+        let mut response = self.client.get(url).send().await?;
+        let mut body = Vec::new();
+
+        while let Some(chunk) = response.chunk().await? {
+            body.extend_from_slice(&chunk);
+            if body.len() >= MAX_BODY_BYTES {
+                body.truncate(MAX_BODY_BYTES);
+                break;
+            }
+        }
+
+        Ok(body)
+    }
+}
+
+impl Default for IotSpider {
+    fn default() -> Self {
+        Self::new(DEFAULT_IOT_SPIDER_TIMEOUT)
+    }
+}
+
+// CoAP: confirmable GET for /.well-known/core
+fn coap_well_known_core() -> Vec<u8> {
+    let mut packet = vec![
+        0x40, // version 1, confirmable, no token
+        0x01, // GET
+        0x4c, 0x32, // message id
+    ];
+    packet.push(0xbb); // Uri-Path (11), length 11
+    packet.extend_from_slice(b".well-known");
+    packet.push(0x04); // Uri-Path again, length 4
+    packet.extend_from_slice(b"core");
+    packet
+}
+
+/// Response code and payload of a CoAP message.
+fn parse_coap_response(message: &[u8]) -> Option<(u8, &[u8])> {
+    let header = *message.first()?;
+    if header >> 6 != 1 {
+        return None;
+    }
+    let code = *message.get(1)?;
+    let mut pos = 4 + (header & 0x0f) as usize;
+
+    // Skip options up to the payload marker
+    while let Some(&byte) = message.get(pos) {
+        if byte == 0xff {
+            return Some((code, message.get(pos + 1..)?));
+        }
+        pos += 1;
+
+        for nibble in [byte >> 4, byte & 0x0f] {
+            match nibble {
+                13 => pos += 1,
+                14 => pos += 2,
+                15 => return None,
+                _ => {}
+            }
+        }
+        let len = match byte & 0x0f {
+            13 => *message.get(pos - 1)? as usize + 13,
+            14 => u16::from_be_bytes([*message.get(pos - 2)?, *message.get(pos - 1)?]) as usize + 269,
+            len => len as usize,
+        };
+        pos += len;
+    }
+
+    Some((code, &[]))
+}
+
+/// Resource paths from a CoRE link-format listing such as
+/// `</sensors/temp>;rt="temperature",</light>`.
+fn parse_link_format(listing: &str) -> Vec<String> {
+    listing.split(',')
+        .filter_map(|link| {
+            let start = link.find('<')?;
+            let end = start + link[start..].find('>')?;
+            Some(link[start + 1..end].to_string())
+        })
+        .filter(|path| path != "/.well-known/core")
+        .collect()
+}
+
+fn extract_title(page: &str) -> Option<&str> {
+    let start = page.find("<title")?;
+    let open_end = start + page[start..].find('>')? + 1;
+    let close = open_end + page[open_end..].find("</title")?;
+    Some(page[open_end..close].trim())
+}
+
+/// Matches Shodan's `http.favicon.hash`: MurmurHash3 (x86, 32-bit, seed 0)
+/// over the icon encoded as MIME base64 with a newline every 76 characters.
+pub fn favicon_hash(icon: &[u8]) -> i32 {
+    let encoded = base64::engine::general_purpose::STANDARD.encode(icon);
+    let mut mime = String::with_capacity(encoded.len() + encoded.len() / 76 + 1);
+    for chunk in encoded.as_bytes().chunks(76) {
+        mime.push_str(std::str::from_utf8(chunk).unwrap_or_default());
+        mime.push('\n');
+    }
+
+    murmur3_32(mime.as_bytes()) as i32
+}
+
+fn murmur3_32(data: &[u8]) -> u32 {
+    const C1: u32 = 0xcc9e_2d51;
+    const C2: u32 = 0x1b87_3593;
+
+    let mut hash = 0u32;
+    let mut chunks = data.chunks_exact(4);
+
+    for chunk in &mut chunks {
+        let mut k = u32::from_le_bytes([chunk[0], chunk[1], chunk[2], chunk[3]]);
+        k = k.wrapping_mul(C1).rotate_left(15).wrapping_mul(C2);
+        hash ^= k;
+        hash = hash.rotate_left(13).wrapping_mul(5).wrapping_add(0xe654_6b64);
+    }
+
+    let tail = chunks.remainder();
+    if !tail.is_empty() {
+        let mut k = 0u32;
+        for (i, &b) in tail.iter().enumerate() {
+            k |= (b as u32) << (8 * i);
+        }
+        hash ^= k.wrapping_mul(C1).rotate_left(15).wrapping_mul(C2);
+    }
+
+    hash ^= data.len() as u32;
+    hash ^= hash >> 16;
+    hash = hash.wrapping_mul(0x85eb_ca6b);
+    hash ^= hash >> 13;
+    hash = hash.wrapping_mul(0xc2b2_ae35);
+    hash ^ (hash >> 16)
+}
\ No newline at end of file
@@ -0,0 +1,224 @@
+use super::Severity;
+use anyhow::{Result, anyhow};
+use serde::{Deserialize, Serialize};
+use std::net::{IpAddr, SocketAddr};
+use std::time::Duration;
+use tokio::net::UdpSocket;
+
+pub const IPMI_PORT: u16 = 623;
+pub const DEFAULT_IPMI_TIMEOUT: Duration = Duration::from_secs(3);
+
+const RMCP_HEADER: [u8; 4] = [0x06, 0x00, 0xff, 0x07];
+const PAYLOAD_OPEN_SESSION_REQUEST: u8 = 0x10;
+const PAYLOAD_OPEN_SESSION_RESPONSE: u8 = 0x11;
+const AUTH_TYPE_RMCP_PLUS: u8 = 0x06;
+
+/// IANA enterprise numbers of common BMC vendors
+const BMC_VENDORS: &[(u32, &str)] = &[
+    (2, "IBM"),
+    (11, "HPE iLO"),
+    (343, "Intel"),
+    (674, "Dell iDRAC"),
+    (4413, "Broadcom"),
+    (5771, "Cisco"),
+    (10368, "Fujitsu iRMC"),
+    (10876, "Supermicro"),
+    (19046, "Lenovo XCC"),
+    (20301, "IBM IMM"),
+    (28458, "Oracle ILOM"),
+    (42385, "Inventec"),
+];
+
+/// What a BMC reports about itself in Get Channel Authentication
+/// Capabilities, plus the cipher-zero test result.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct IpmiReport {
+    pub ipmi_version: String,
+    pub oem_id: u32,
+    pub vendor: Option<String>,
+    /// none, md2, md5, password and oem, as advertised
+    pub auth_types: Vec<String>,
+    pub anonymous_login: bool,
+    pub null_usernames: bool,
+    /// The BMC opened an RMCP+ session with cipher suite 0, which skips
+    /// authentication entirely
+    pub cipher_zero: bool,
+}
+
+impl IpmiReport {
+    pub fn product(&self) -> String {
+        format!("{} (IPMI {})", self.vendor.as_deref().unwrap_or("BMC"), self.ipmi_version)
+    }
+
+    pub fn summary(&self) -> String {
+        format!(
+            "IPMI {}, OEM {} ({}), auth types [{}], anonymous login {}, null usernames {}, cipher zero {}",
+            self.ipmi_version,
+            self.oem_id,
+            self.vendor.as_deref().unwrap_or("unknown"),
+            self.auth_types.join(", "),
+            self.anonymous_login,
+            self.null_usernames,
+            self.cipher_zero
+        )
+    }
+
+    pub fn findings(&self, port: u16) -> Vec<(String, Severity, String)> {
+        let mut findings = Vec::new();
+
+        if self.cipher_zero {
+            findings.push((
+                format!("IPMI cipher zero authentication bypass (port {})", port),
+                Severity::Critical,
+                "The management controller accepts RMCP+ sessions with cipher suite 0, which performs no \
+                 authentication: any password is accepted for a valid user. This gives full control of the \
+                 server hardware, including power and console."
+                    .to_string(),
+            ));
+        }
+        if self.anonymous_login || self.null_usernames {
+            findings.push((
+                format!("IPMI anonymous login enabled (port {})", port),
+                Severity::High,
+                "The management controller allows anonymous or null-username logins.".to_string(),
+            ));
+        }
+
+        findings
+    }
+}
+
+/// Native IPMI over RMCP probing of baseboard management controllers.
+pub struct IpmiProber {
+    timeout: Duration,
+}
+
+impl IpmiProber {
+    pub fn new(timeout: Duration) -> Self {
+        Self { timeout }
+    }
+
+    /// Returns `None` when nothing on the port speaks IPMI.
+    pub async fn probe(&self, ip: IpAddr, port: u16) -> Result<Option<IpmiReport>> {
+        let Some(response) = self.exchange(ip, port, &get_channel_auth_capabilities()).await? else {
+            return Ok(None);
+        };
+        let Some(mut report) = parse_auth_capabilities(&response) else {
+            return Ok(None);
+        };
+
+        // Cipher zero only exists in IPMI 2.0 (RMCP+)
+        if report.ipmi_version == "2.0" {
+            report.cipher_zero = match self.exchange(ip, port, &open_session_cipher_zero()).await? {
+                Some(response) => accepts_open_session(&response),
+                None => false,
+            };
+        }
+
+        Ok(Some(report))
+    }
+
+    async fn exchange(&self, ip: IpAddr, port: u16, request: &[u8]) -> Result<Option<Vec<u8>>> {
+        let bind_addr: SocketAddr = match ip {
+            IpAddr::V4(_) => "0.0.0.0:0".parse()?,
+            IpAddr::V6(_) => "[::]:0".parse()?,
+        };
+
+        let socket = UdpSocket::bind(bind_addr).await?;
+        socket.connect(SocketAddr::new(ip, port)).await?;
+        socket.send(request).await.map_err(|e| anyhow!("IPMI request to {} failed: {}", ip, e))?;
+
+        let mut buf = vec![0u8; 1024];
+        match tokio::time::timeout(self.timeout, socket.recv(&mut buf)).await {
+            Ok(Ok(len)) => Ok(Some(buf[..len].to_vec())),
+            Ok(Err(_)) | Err(_) => Ok(None),
+        }
+    }
+}
+
+impl Default for IpmiProber {
+    fn default() -> Self {
+        Self::new(DEFAULT_IPMI_TIMEOUT)
+    }
+}
+
+/// IPMI 1.5 session-less Get Channel Authentication Capabilities for the
+/// current channel at administrator level, asking for v2.0 extended data.
+pub(crate) fn get_channel_auth_capabilities() -> Vec<u8> {
+    let mut packet = RMCP_HEADER.to_vec();
+    packet.extend_from_slice(&[0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00]); // auth none, seq, session
+    let message = [0x20, 0x18, 0x00, 0x81, 0x00, 0x38, 0x8e, 0x04, 0x00];
+    packet.push(message.len() as u8);
+    packet.extend_from_slice(&message);
+
+    // Header checksum over rsAddr/netFn, data checksum over the rest
+    let body = packet.len() - message.len();
+    packet[body + 2] = checksum(&packet[body..body + 2]);
+    let end = packet.len() - 1;
+    packet[end] = checksum(&packet[body + 3..end]);
+    packet
+}
+
+pub(crate) fn describe_ipmi(response: &[u8]) -> Option<String> {
+    parse_auth_capabilities(response).map(|report| report.product())
+}
+
+fn parse_auth_capabilities(response: &[u8]) -> Option<IpmiReport> {
+    // RMCP (4) + IPMI 1.5 session header (10), then the response message
+    if response.len() < 29 || response[..4] != RMCP_HEADER || response[19] != 0x38 || response[20] != 0x00 {
+        return None;
+    }
+
+    let auth_support = response[22];
+    let auth_status = response[23];
+    let extended = response[24];
+    let oem_id = u32::from_le_bytes([response[25], response[26], response[27], 0]);
+
+    let auth_types = [(0x01, "none"), (0x02, "md2"), (0x04, "md5"), (0x10, "password"), (0x20, "oem")]
+        .iter()
+        .filter(|(bit, _)| auth_support & bit != 0)
+        .map(|(_, name)| name.to_string())
+        .collect();
+
+    Some(IpmiReport {
+        ipmi_version: if auth_support & 0x80 != 0 && extended & 0x02 != 0 { "2.0" } else { "1.5" }.to_string(),
+        oem_id,
+        vendor: BMC_VENDORS.iter().find(|(id, _)| *id == oem_id).map(|(_, name)| name.to_string()),
+        auth_types,
+        anonymous_login: auth_status & 0x01 != 0,
+        null_usernames: auth_status & 0x02 != 0,
+        cipher_zero: false,
+    })
+}
+
+/// RMCP+ Open Session Request proposing only cipher suite 0, where the
+/// authentication, integrity and confidentiality algorithms are all "none".
+fn open_session_cipher_zero() -> Vec<u8> {
+    let algorithm = |kind: u8| [kind, 0x00, 0x00, 0x08, 0x00, 0x00, 0x00, 0x00];
+
+    let mut payload = vec![0x00, 0x04, 0x00, 0x00]; // tag, admin privilege, reserved
+    payload.extend_from_slice(&0x4c32_0001u32.to_le_bytes()); // console session id
+    payload.extend_from_slice(&algorithm(0x00));
+    payload.extend_from_slice(&algorithm(0x01));
+    payload.extend_from_slice(&algorithm(0x02));
+
+    let mut packet = RMCP_HEADER.to_vec();
+    packet.push(AUTH_TYPE_RMCP_PLUS);
+    packet.push(PAYLOAD_OPEN_SESSION_REQUEST);
+    packet.extend_from_slice(&[0x00; 8]); // session id, sequence
+    packet.extend_from_slice(&(payload.len() as u16).to_le_bytes());
+    packet.extend_from_slice(&payload);
+    packet
+}
+
+fn accepts_open_session(response: &[u8]) -> bool {
+    // RMCP (4) + auth type, payload type, session id, sequence, length (12)
+    response.len() >= 18
+        && response[..4] == RMCP_HEADER
+        && response[5] & 0x3f == PAYLOAD_OPEN_SESSION_RESPONSE
+        && response[17] == 0x00
+}
+
+fn checksum(bytes: &[u8]) -> u8 {
+    bytes.iter().fold(0u8, |sum, b| sum.wrapping_add(*b)).wrapping_neg()
+}
\ No newline at end of file
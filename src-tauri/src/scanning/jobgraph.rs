@@ -0,0 +1,277 @@
+use super::*;
+use crate::utils::BoundedSender;
+use anyhow::{Result, bail};
+use futures::future::{BoxFuture, FutureExt};
+use futures::stream::{self, FuturesUnordered, StreamExt};
+use std::collections::HashSet;
+use std::future::Future;
+use std::path::{Path, PathBuf};
+use std::sync::Arc;
+
+/// One unit of work in a stage, e.g. auditing one SSH port.
+pub type Job<C> = Box<dyn FnOnce(Arc<C>) -> BoxFuture<'static, Result<()>> + Send>;
+
+/// Wraps an async closure as a `Job`.
+pub fn job<C, F, Fut>(run: F) -> Job<C>
+where
+    C: Send + Sync + 'static,
+    F: FnOnce(Arc<C>) -> Fut + Send + 'static,
+    Fut: Future<Output = Result<()>> + Send + 'static,
+{
+    Box::new(move |context| run(context).boxed())
+}
+
+/// What a graph's stages share: the target, handles to the scanners and
+/// whatever earlier stages found. Its snapshot is checkpointed after each
+/// stage so an interrupted graph can resume where it stopped.
+pub trait GraphContext: Send + Sync + 'static {
+    fn snapshot(&self) -> Result<serde_json::Value>;
+    fn restore(&self, state: serde_json::Value) -> Result<()>;
+}
+
+/// A stage of a job graph. Its jobs are planned once the stages it depends
+/// on are done, so they can act on what those found.
+pub struct GraphStage<C> {
+    name: &'static str,
+    depends_on: Vec<&'static str>,
+    concurrency: usize,
+    required: bool,
+    plan: Box<dyn Fn(&C) -> Vec<Job<C>> + Send + Sync>,
+}
+
+impl<C: GraphContext> GraphStage<C> {
+    pub fn new(name: &'static str, plan: impl Fn(&C) -> Vec<Job<C>> + Send + Sync + 'static) -> Self {
+        Self {
+            name,
+            depends_on: Vec::new(),
+            concurrency: 1,
+            required: false,
+            plan: Box::new(plan),
+        }
+    }
+
+    pub fn after(mut self, stages: &[&'static str]) -> Self {
+        self.depends_on.extend_from_slice(stages);
+        self
+    }
+
+    /// Jobs of this stage run at once
+    pub fn concurrency(mut self, concurrency: usize) -> Self {
+        self.concurrency = concurrency.max(1);
+        self
+    }
+
+    /// A failed job fails the whole graph. Otherwise failures are logged
+    /// and the stage's other jobs still run.
+    pub fn required(mut self) -> Self {
+        self.required = true;
+        self
+    }
+}
+
+/// Stages with dependencies between them. A stage starts as soon as every
+/// stage it depends on is done, so independent branches run in parallel.
+pub struct JobGraph<C> {
+    stages: Vec<GraphStage<C>>,
+}
+
+impl<C: GraphContext> JobGraph<C> {
+    pub fn new() -> Self {
+        Self { stages: Vec::new() }
+    }
+
+    pub fn stage(mut self, stage: GraphStage<C>) -> Self {
+        self.stages.push(stage);
+        self
+    }
+
+    /// Rejects duplicate stages, unknown dependencies and cycles.
+    pub fn validate(&self) -> Result<()> {
+        let mut names = HashSet::new();
+        for stage in &self.stages {
+            if !names.insert(stage.name) {
+                bail!("Stage '{}' is defined twice", stage.name);
+            }
+        }
+        for stage in &self.stages {
+            if let Some(unknown) = stage.depends_on.iter().find(|d| !names.contains(*d)) {
+                bail!("Stage '{}' depends on unknown stage '{}'", stage.name, unknown);
+            }
+        }
+
+        // Kahn's algorithm: whatever can't be ordered is on a cycle
+        let mut ordered: HashSet<&str> = HashSet::new();
+        loop {
+            let ready: Vec<&str> = self.stages.iter()
+                .filter(|s| !ordered.contains(s.name) && s.depends_on.iter().all(|d| ordered.contains(d)))
+                .map(|s| s.name)
+                .collect();
+            if ready.is_empty() {
+                break;
+            }
+            ordered.extend(ready);
+        }
+        if ordered.len() < self.stages.len() {
+            let cyclic: Vec<&str> = self.stages.iter()
+                .map(|s| s.name)
+                .filter(|name| !ordered.contains(name))
+                .collect();
+            bail!("Stages depend on each other in a cycle: {}", cyclic.join(", "));
+        }
+
+        Ok(())
+    }
+
+    /// Runs every stage not already completed in `checkpoint`, saving it
+    /// to `dir` after each stage. Progress is reported per stage.
+    pub async fn run(
+        &self,
+        context: Arc<C>,
+        checkpoint: &mut GraphCheckpoint,
+        dir: &Path,
+        progress_tx: Option<&BoundedSender<ScanProgress>>,
+    ) -> Result<()> {
+        self.validate()?;
+
+        if !checkpoint.completed.is_empty() {
+            context.restore(checkpoint.state.clone())?;
+        }
+
+        let total = self.stages.len();
+        let mut done: HashSet<&str> = self.stages.iter()
+            .map(|s| s.name)
+            .filter(|name| checkpoint.completed.iter().any(|c| c == name))
+            .collect();
+        let mut pending: Vec<&GraphStage<C>> = self.stages.iter()
+            .filter(|s| !done.contains(s.name))
+            .collect();
+        let mut running = FuturesUnordered::new();
+
+        loop {
+            let mut waiting = Vec::with_capacity(pending.len());
+            for stage in pending {
+                if stage.depends_on.iter().all(|d| done.contains(d)) {
+                    if let Some(progress_tx) = progress_tx {
+                        let _ = progress_tx.send(ScanProgress {
+                            percent: done.len() as f32 / total as f32 * 100.0,
+                            message: format!("Starting {}...", stage.name.replace('_', " ")),
+                            eta: None,
+                        }).await;
+                    }
+                    running.push(Self::run_stage(stage, context.clone()).map(move |result| (stage.name, result)));
+                } else {
+                    waiting.push(stage);
+                }
+            }
+            pending = waiting;
+
+            let Some((name, result)) = running.next().await else {
+                break;
+            };
+            result?;
+
+            done.insert(name);
+            checkpoint.completed.push(name.to_string());
+            checkpoint.state = context.snapshot()?;
+            checkpoint.save(dir).await?;
+        }
+
+        Ok(())
+    }
+
+    async fn run_stage(stage: &GraphStage<C>, context: Arc<C>) -> Result<()> {
+        let jobs = (stage.plan)(&context);
+
+        let results: Vec<Result<()>> = stream::iter(jobs)
+            .map(|job| job(context.clone()))
+            .buffer_unordered(stage.concurrency)
+            .collect()
+            .await;
+
+        for result in results {
+            match result {
+                Err(e) if stage.required => return Err(e.context(format!("{} stage failed", stage.name))),
+                Err(e) => eprintln!("{} job failed: {:#}", stage.name, e),
+                Ok(()) => {}
+            }
+        }
+
+        Ok(())
+    }
+}
+
+impl<C: GraphContext> Default for JobGraph<C> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+/// The stages of one graph run already done and the context they left,
+/// saved as JSON under the run's id.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct GraphCheckpoint {
+    pub id: Uuid,
+    pub completed: Vec<String>,
+    pub state: serde_json::Value,
+}
+
+impl GraphCheckpoint {
+    pub fn new(id: Uuid) -> Self {
+        Self {
+            id,
+            completed: Vec::new(),
+            state: serde_json::Value::Null,
+        }
+    }
+
+    pub async fn load(dir: &Path, id: Uuid) -> Result<Option<Self>> {
+        match tokio::fs::read(Self::path(dir, id)).await {
+            Ok(data) => Ok(Some(serde_json::from_slice(&data)?)),
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => Ok(None),
+            Err(e) => Err(e.into()),
+        }
+    }
+
+    /// Written to a temporary file first so a crash mid-write leaves the
+    /// previous checkpoint intact.
+    pub async fn save(&self, dir: &Path) -> Result<()> {
+        tokio::fs::create_dir_all(dir).await?;
+        let path = Self::path(dir, self.id);
+        let temp = path.with_extension("json.tmp");
+        tokio::fs::write(&temp, serde_json::to_vec(self)?).await?;
+        tokio::fs::rename(&temp, &path).await?;
+        Ok(())
+    }
+
+    pub async fn remove(dir: &Path, id: Uuid) {
+        let _ = tokio::fs::remove_file(Self::path(dir, id)).await;
+    }
+
+    /// Runs that were interrupted and can be resumed
+    pub async fn list(dir: &Path) -> Result<Vec<Self>> {
+        let mut entries = match tokio::fs::read_dir(dir).await {
+            Ok(entries) => entries,
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => return Ok(Vec::new()),
+            Err(e) => return Err(e.into()),
+        };
+
+        let mut checkpoints = Vec::new();
+        while let Some(entry) = entries.next_entry().await? {
+            let path = entry.path();
+            if path.extension().and_then(|e| e.to_str()) != Some("json") {
+                continue;
+            }
+            match tokio::fs::read(&path).await.map(|data| serde_json::from_slice::<Self>(&data)) {
+                Ok(Ok(checkpoint)) => checkpoints.push(checkpoint),
+                Ok(Err(e)) => eprintln!("Skipping unreadable checkpoint {}: {}", path.display(), e),
+                Err(e) => eprintln!("Failed to read checkpoint {}: {}", path.display(), e),
+            }
+        }
+
+        Ok(checkpoints)
+    }
+
+    fn path(dir: &Path, id: Uuid) -> PathBuf {
+        dir.join(format!("{}.json", id))
+    }
+}
\ No newline at end of file
@@ -1,47 +1,184 @@
 use super::*;
+use crate::error::LegionError;
 use anyhow::{Result, Context};
 use std::process::Stdio;
-use tokio::process::Command;
+use tokio::process::{Child, Command};
 use tokio::io::{AsyncBufReadExt, BufReader};
 use std::net::{IpAddr, Ipv4Addr};
+use std::ops::{Deref, DerefMut};
+use std::path::PathBuf;
 use std::str::FromStr;
 
+use std::sync::Arc;
+use crate::sources::{Observation, ObservationStream, Source, SourceRuns};
+use crate::utils::{sandbox, BoundedSender, ProcessManager};
+use futures::future::BoxFuture;
+
+/// Written by masscan to its working directory when interrupted, holding
+/// its configuration and how far it got
+const PAUSED_CONF: &str = "paused.conf";
+/// What the run was started for, kept beside paused.conf
+const RESUME_INFO: &str = "resume.json";
+/// How long an interrupted masscan gets to save its state and exit
+#[cfg(unix)]
+const INTERRUPT_GRACE: std::time::Duration = std::time::Duration::from_secs(15);
+
+/// What a masscan run was started for, saved with its checkpoint so a
+/// resume can be checked the way a new run is before anything is sent.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct MasscanResumeInfo {
+    /// The project the run's results belong to
+    pub project_id: Option<String>,
+    /// Addresses and CIDR ranges the run covers
+    pub targets: Vec<String>,
+    /// Ranges left out of `targets`
+    pub excludes: Vec<String>,
+    pub options: ScanOptions,
+}
+
 pub struct MasscanScanner {
     rate_limit: tokio::sync::Semaphore,
-    max_rate: u32, // packets per second
+    bandwidth: Arc<BandwidthBudget>,
+    tools: Arc<ToolPaths>,
+    output: Arc<ScanOutputLog>,
+    /// Each run works in `<resume_dir>/<scan_id>` so an interrupted one
+    /// leaves a paused.conf there for `resume`
+    resume_dir: PathBuf,
+    runs: SourceRuns,
 }
 
 impl MasscanScanner {
-    pub fn new(max_concurrent: usize, max_rate: u32) -> Self {
+    pub fn new(
+        max_concurrent: usize,
+        bandwidth: Arc<BandwidthBudget>,
+        tools: Arc<ToolPaths>,
+        output: Arc<ScanOutputLog>,
+        resume_dir: PathBuf,
+    ) -> Self {
         Self {
             rate_limit: tokio::sync::Semaphore::new(max_concurrent),
-            max_rate,
+            bandwidth,
+            tools,
+            output,
+            resume_dir,
+            runs: SourceRuns::default(),
+        }
+    }
+
+    /// Whether an interrupted run of `scan_id` left state to resume from
+    pub fn has_checkpoint(&self, scan_id: Uuid) -> bool {
+        self.checkpoint_dir(scan_id).join(PAUSED_CONF).is_file()
+    }
+
+    /// Saves what the run `scan_id` is being started for, so an
+    /// interrupted one can be checked again before it is resumed. Runs
+    /// through the helper can't be resumed and save nothing.
+    pub async fn save_resume_info(&self, scan_id: Uuid, info: &MasscanResumeInfo) -> Result<()> {
+        if self.tools.helper().is_some() {
+            return Ok(());
+        }
+
+        let dir = self.checkpoint_dir(scan_id);
+        tokio::fs::create_dir_all(&dir)
+            .await
+            .with_context(|| format!("Failed to create {}", dir.display()))?;
+        tokio::fs::write(dir.join(RESUME_INFO), serde_json::to_vec(info)?)
+            .await
+            .with_context(|| format!("Failed to write {}", dir.join(RESUME_INFO).display()))?;
+        Ok(())
+    }
+
+    /// What an interrupted run of `scan_id` was started for. `None` for
+    /// runs checkpointed before this was saved.
+    pub async fn resume_info(&self, scan_id: Uuid) -> Result<Option<MasscanResumeInfo>> {
+        match tokio::fs::read(self.checkpoint_dir(scan_id).join(RESUME_INFO)).await {
+            Ok(data) => Ok(Some(serde_json::from_slice(&data)?)),
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => Ok(None),
+            Err(e) => Err(e.into()),
         }
     }
 
+    /// Continues an interrupted run from its paused.conf. The saved
+    /// configuration includes targets, ports and adapter; only the rate is
+    /// taken from the current bandwidth budget, capped by `options`.
+    pub async fn resume(
+        &self,
+        scan_id: Uuid,
+        options: &ScanOptions,
+        progress_callback: Option<BoundedSender<ScanProgress>>,
+    ) -> Result<Vec<ScanResult>> {
+        if !self.has_checkpoint(scan_id) {
+            return Err(LegionError::NotFound(format!("No interrupted masscan run for scan {}", scan_id)).into());
+        }
+
+        let _permit = self.rate_limit.acquire().await?;
+        let lease = self.bandwidth.acquire().await;
+
+        // Options after --resume override the saved ones
+        let mut cmd = Command::new(self.tools.masscan());
+        cmd.arg("--resume").arg(PAUSED_CONF)
+            .arg("--rate").arg(options.rate_cap(lease.rate()).to_string())
+            .arg("--output-format").arg("list")
+            .arg("--output-filename").arg("-");
+
+        let (mut child, _tracked) = self.spawn(scan_id, cmd)?;
+
+        let stderr = self.output.capture(scan_id, OutputStream::Stderr, child.stderr.take().unwrap());
+        let stdout = child.stdout.take().unwrap();
+        let mut reader = BufReader::new(stdout).lines();
+        let mut results = Vec::new();
+
+        while let Some(line) = reader.next_line().await? {
+            self.output.push(scan_id, OutputStream::Stdout, &line);
+            if let Some(callback) = &progress_callback {
+                if let Ok(progress) = self.parse_masscan_progress(&line) {
+                    let _ = callback.send(progress).await;
+                }
+            }
+
+            if let Ok(result) = self.parse_masscan_output(&line) {
+                results.push(result);
+            }
+        }
+
+        let status = child.wait().await?;
+        self.finish(scan_id, status.success()).await;
+        let stderr = stderr.await.unwrap_or_default();
+        if !status.success() {
+            return Err(anyhow::anyhow!(
+                "Resumed masscan run failed: {}",
+                stderr
+            ));
+        }
+
+        Ok(Self::merge_banners(results))
+    }
+
     pub async fn scan_range(
         &self,
+        scan_id: Uuid,
         targets: &[IpAddr],
         ports: &[u16],
-        progress_callback: Option<tokio::sync::mpsc::Sender<ScanProgress>>,
+        options: &ScanOptions,
+        progress_callback: Option<BoundedSender<ScanProgress>>,
     ) -> Result<Vec<ScanResult>> {
         let _permit = self.rate_limit.acquire().await?;
+        let lease = self.bandwidth.acquire().await;
         
-        let mut cmd = Command::new("masscan");
-        self.configure_masscan_command(&mut cmd, targets, ports)?;
+        let mut cmd = Command::new(self.tools.masscan());
+        self.configure_masscan_command(&mut cmd, targets, ports, options.rate_cap(lease.rate()))?;
+        self.apply_source_options(&mut cmd, options);
         
-        let mut child = cmd
-            .stdout(Stdio::piped())
-            .stderr(Stdio::piped())
-            .spawn()
-            .context("Failed to start masscan process")?;
+        let (mut child, _tracked) = self.spawn(scan_id, cmd)?;
 
+        let stderr = self.output.capture(scan_id, OutputStream::Stderr, child.stderr.take().unwrap());
         let stdout = child.stdout.take().unwrap();
         let mut reader = BufReader::new(stdout).lines();
         let mut results = Vec::new();
 
         // Parse masscan output in real-time
         while let Some(line) = reader.next_line().await? {
+            self.output.push(scan_id, OutputStream::Stdout, &line);
             if let Some(callback) = &progress_callback {
                 let progress = self.parse_masscan_progress(&line)?;
                 let _ = callback.send(progress).await;
@@ -52,50 +189,53 @@ impl MasscanScanner {
             }
         }
 
-        let output = child.wait_with_output().await?;
-        
-        if !output.status.success() {
+        let status = child.wait().await?;
+        self.finish(scan_id, status.success()).await;
+        let stderr = stderr.await.unwrap_or_default();
+        if !status.success() {
             return Err(anyhow::anyhow!(
                 "Masscan failed: {}", 
-                String::from_utf8_lossy(&output.stderr)
+                stderr
             ));
         }
 
-        Ok(results)
+        Ok(Self::merge_banners(results))
     }
 
     pub async fn fast_port_discovery(
         &self,
+        scan_id: Uuid,
         cidr_range: &str,
         top_ports: usize,
-        progress_callback: Option<tokio::sync::mpsc::Sender<ScanProgress>>,
+        options: &ScanOptions,
+        progress_callback: Option<BoundedSender<ScanProgress>>,
     ) -> Result<Vec<ScanResult>> {
         let _permit = self.rate_limit.acquire().await?;
+        let lease = self.bandwidth.acquire().await;
         
         let ports = self.get_top_ports(top_ports);
         
-        let mut cmd = Command::new("masscan");
+        let mut cmd = Command::new(self.tools.masscan());
         cmd.arg(cidr_range)
             .arg("-p")
             .arg(self.format_port_list(&ports))
             .arg("--rate")
-            .arg(self.max_rate.to_string())
+            .arg(options.rate_cap(lease.rate()).to_string())
             .arg("--output-format")
             .arg("list")
             .arg("--output-filename")
             .arg("-"); // stdout
+        self.apply_source_options(&mut cmd, options);
 
-        let mut child = cmd
-            .stdout(Stdio::piped())
-            .stderr(Stdio::piped())
-            .spawn()
-            .context("Failed to start masscan for port discovery")?;
+        let (mut child, _tracked) = self.spawn(scan_id, cmd)?;
 
+        let stderr = self.output.capture(scan_id, OutputStream::Stderr, child.stderr.take().unwrap());
         let stdout = child.stdout.take().unwrap();
         let mut reader = BufReader::new(stdout).lines();
         let mut results = Vec::new();
 
         while let Some(line) = reader.next_line().await? {
+            self.output.push(scan_id, OutputStream::Stdout, &line);
             if let Some(callback) = &progress_callback {
                 if line.contains("rate:") {
                     let progress = ScanProgress {
@@ -112,12 +252,13 @@ impl MasscanScanner {
             }
         }
 
-        let output = child.wait_with_output().await?;
-        
-        if !output.status.success() {
+        let status = child.wait().await?;
+        self.finish(scan_id, status.success()).await;
+        let stderr = stderr.await.unwrap_or_default();
+        if !status.success() {
             return Err(anyhow::anyhow!(
                 "Masscan port discovery failed: {}", 
-                String::from_utf8_lossy(&output.stderr)
+                stderr
             ));
         }
 
@@ -129,6 +270,7 @@ impl MasscanScanner {
         cmd: &mut Command,
         targets: &[IpAddr],
         ports: &[u16],
+        rate: u32,
     ) -> Result<()> {
         // Add targets
         for target in targets {
@@ -139,7 +281,7 @@ impl MasscanScanner {
         cmd.arg("-p").arg(self.format_port_list(ports));
 
         // Rate limiting
-        cmd.arg("--rate").arg(self.max_rate.to_string());
+        cmd.arg("--rate").arg(rate.to_string());
 
         // Output format
         cmd.arg("--output-format").arg("list");
@@ -151,22 +293,74 @@ impl MasscanScanner {
         Ok(())
     }
 
+    fn apply_source_options(&self, cmd: &mut Command, options: &ScanOptions) {
+        if let Some(interface) = &options.interface {
+            cmd.arg("-e").arg(interface);
+        }
+        if let Some(source_ip) = &options.source_ip {
+            cmd.arg("--adapter-ip").arg(source_ip.to_string());
+        }
+    }
+
+    /// Starts masscan with piped output, through the privileged helper
+    /// when one is running. A local process stays pausable while the
+    /// returned guard is held. Runs through the helper happen in the
+    /// helper's directory and can't be paused or resumed: stopping the
+    /// relay would not stop masscan.
+    fn spawn(&self, scan_id: Uuid, cmd: Command) -> Result<(MasscanProcess, TrackedProcess)> {
+        let helper = self.tools.helper();
+        let pausable = helper.is_none();
+        let mut cmd = match helper {
+            Some(helper) => ProcessManager::wrap_helper(&cmd, "masscan", &helper)?,
+            None => {
+                let dir = self.checkpoint_dir(scan_id);
+                std::fs::create_dir_all(&dir)
+                    .with_context(|| format!("Failed to create {}", dir.display()))?;
+                // masscan writes paused.conf here as the sandbox user
+                sandbox::grant(&dir, &self.tools.sandbox())
+                    .with_context(|| format!("Failed to hand {} to the sandbox user", dir.display()))?;
+                let mut cmd = cmd;
+                cmd.current_dir(dir);
+                cmd
+            }
+        };
+        cmd.stdout(Stdio::piped())
+            .stderr(Stdio::piped())
+            .kill_on_drop(true);
+
+        let child = ProcessManager::spawn_sandboxed(&mut cmd, &self.tools.sandbox())
+            .map_err(|e| LegionError::spawn("masscan", e))?;
+        let tracked = self.output.track_process(scan_id, child.id().filter(|_| pausable));
+        Ok((MasscanProcess { child: Some(child) }, tracked))
+    }
+
+    fn checkpoint_dir(&self, scan_id: Uuid) -> PathBuf {
+        self.resume_dir.join(scan_id.to_string())
+    }
+
+    /// Removes the run's directory once it is no longer needed: after a
+    /// complete run, or when a failed one saved nothing to resume from.
+    async fn finish(&self, scan_id: Uuid, completed: bool) {
+        let dir = self.checkpoint_dir(scan_id);
+        if completed || !dir.join(PAUSED_CONF).is_file() {
+            let _ = tokio::fs::remove_dir_all(&dir).await;
+        }
+    }
+
     fn format_port_list(&self, ports: &[u16]) -> String {
         if ports.is_empty() {
             return "1-65535".to_string();
         }
 
-        ports.iter()
-            .map(|p| p.to_string())
-            .collect::<Vec<_>>()
-            .join(",")
+        TopPorts::format_spec(ports)
     }
 
     fn parse_masscan_output(&self, line: &str) -> Result<ScanResult> {
         // Parse masscan list format: "open tcp 22 192.168.1.1 1234567890"
+        // or, with --banners, "banner tcp 22 192.168.1.1 1234567890 ssh SSH-2.0-..."
         let parts: Vec<&str> = line.split_whitespace().collect();
         
-        if parts.len() < 4 || parts[0] != "open" {
+        if parts.len() < 4 || (parts[0] != "open" && parts[0] != "banner") {
             return Err(anyhow::anyhow!("Invalid masscan output format"));
         }
 
@@ -180,26 +374,101 @@ impl MasscanScanner {
             number: port,
             protocol,
             state: "open".to_string(),
-            service: None, // Masscan doesn't provide service detection
+            service: None, // Identified from the banner when it's stored
             version: None,
-            banner: if parts.len() > 4 { 
-                Some(parts[4..].join(" ")) 
-            } else { 
-                None 
+            banner: if parts[0] == "banner" && parts.len() > 6 {
+                Some(Self::unescape_banner(&parts[6..].join(" ")))
+            } else {
+                None
             },
         };
 
         Ok(ScanResult {
             id: Uuid::new_v4(),
             target_id: Uuid::new_v4(), // Generate temporary ID
+            target_ip: ip,
+            mac_address: None,
             timestamp: Utc::now(),
             status: ScanStatus::Completed,
             open_ports: vec![port_info],
             os_detection: None, // Masscan doesn't do OS detection
             vulnerabilities: Vec::new(),
+            host_up: Some(true),
+            rtt_ms: None,
+            trace: Vec::new(),
+            scanned_tcp_ports: None,
+            unlisted_tcp_state: None,
+            raw_output: format!("{}\n", line).into_bytes(),
         })
     }
 
+    /// Masscan prints non-printable banner bytes as `\xNN`.
+    fn unescape_banner(banner: &str) -> String {
+        let mut out = String::with_capacity(banner.len());
+        let mut rest = banner;
+
+        while let Some(pos) = rest.find("\\x") {
+            out.push_str(&rest[..pos]);
+            match rest.get(pos + 2..pos + 4).and_then(|h| u8::from_str_radix(h, 16).ok()) {
+                Some(byte) => {
+                    out.push(byte as char);
+                    rest = &rest[pos + 4..];
+                }
+                None => {
+                    out.push_str("\\x");
+                    rest = &rest[pos + 2..];
+                }
+            }
+        }
+
+        out.push_str(rest);
+        out
+    }
+
+    /// Folds `banner` lines into the `open` result for the same host and
+    /// port so each port is stored once. A port can report several banners
+    /// (an HTTP response and its page title); they're kept in order, one
+    /// per line, so service matching still sees the first.
+    fn merge_banners(results: Vec<ScanResult>) -> Vec<ScanResult> {
+        let mut merged: Vec<ScanResult> = Vec::new();
+
+        for result in results {
+            let existing = merged.iter_mut().find(|r| {
+                r.target_ip == result.target_ip
+                    && r.open_ports.first().map(|p| (p.number, &p.protocol))
+                        == result.open_ports.first().map(|p| (p.number, &p.protocol))
+            });
+
+            match existing {
+                Some(existing) => {
+                    existing.raw_output.extend_from_slice(&result.raw_output);
+                    if let (Some(port), Some(banner)) = (
+                        existing.open_ports.first_mut(),
+                        result.open_ports.first().and_then(|p| p.banner.clone()),
+                    ) {
+                        port.banner = Some(match port.banner.take() {
+                            Some(previous) => format!("{}\n{}", previous, banner),
+                            None => banner,
+                        });
+                    }
+                }
+                None => merged.push(result),
+            }
+        }
+
+        merged
+    }
+
+    /// Parses a complete saved list output, e.g. when re-parsing a stored
+    /// scan.
+    pub fn parse_list_output(&self, output: &str) -> Vec<ScanResult> {
+        let results = output.lines()
+            .filter_map(|line| self.parse_masscan_output(line).ok())
+            .collect();
+
+        Self::merge_banners(results)
+    }
+
     fn parse_masscan_list_output(&self, line: &str) -> Result<ScanResult> {
         self.parse_masscan_output(line)
     }
@@ -233,26 +502,23 @@ impl MasscanScanner {
     }
 
     fn get_top_ports(&self, count: usize) -> Vec<u16> {
-        // Top 1000 most common ports (subset shown)
-        let top_ports = vec![
-            21, 22, 23, 25, 53, 80, 110, 111, 135, 139, 143, 443, 993, 995,
-            1723, 3306, 3389, 5900, 8080, 8443, // ... extend as needed
-        ];
-
-        top_ports.into_iter().take(count).collect()
+        TopPorts::tcp(count)
     }
 
     // Advanced scanning methods
     pub async fn syn_scan_with_excludes(
         &self,
+        scan_id: Uuid,
         target_range: &str,
         exclude_ranges: &[&str],
         ports: &[u16],
-        progress_callback: Option<tokio::sync::mpsc::Sender<ScanProgress>>,
+        options: &ScanOptions,
+        progress_callback: Option<BoundedSender<ScanProgress>>,
     ) -> Result<Vec<ScanResult>> {
         let _permit = self.rate_limit.acquire().await?;
+        let lease = self.bandwidth.acquire().await;
         
-        let mut cmd = Command::new("masscan");
+        let mut cmd = Command::new(self.tools.masscan());
         cmd.arg(target_range);
 
         // Add exclusions
@@ -261,22 +527,21 @@ impl MasscanScanner {
         }
 
         cmd.arg("-p").arg(self.format_port_list(ports))
-            .arg("--rate").arg(self.max_rate.to_string())
+            .arg("--rate").arg(options.rate_cap(lease.rate()).to_string())
             .arg("-sS") // SYN scan
             .arg("--output-format").arg("list")
             .arg("--output-filename").arg("-");
+        self.apply_source_options(&mut cmd, options);
 
-        let mut child = cmd
-            .stdout(Stdio::piped())
-            .stderr(Stdio::piped())
-            .spawn()
-            .context("Failed to start masscan SYN scan")?;
+        let (mut child, _tracked) = self.spawn(scan_id, cmd)?;
 
+        let stderr = self.output.capture(scan_id, OutputStream::Stderr, child.stderr.take().unwrap());
         let stdout = child.stdout.take().unwrap();
         let mut reader = BufReader::new(stdout).lines();
         let mut results = Vec::new();
 
         while let Some(line) = reader.next_line().await? {
+            self.output.push(scan_id, OutputStream::Stdout, &line);
             if let Some(callback) = &progress_callback {
                 if let Ok(progress) = self.parse_masscan_progress(&line) {
                     let _ = callback.send(progress).await;
@@ -288,12 +553,13 @@ impl MasscanScanner {
             }
         }
 
-        let output = child.wait_with_output().await?;
-        
-        if !output.status.success() {
+        let status = child.wait().await?;
+        self.finish(scan_id, status.success()).await;
+        let stderr = stderr.await.unwrap_or_default();
+        if !status.success() {
             return Err(anyhow::anyhow!(
                 "Masscan SYN scan failed: {}", 
-                String::from_utf8_lossy(&output.stderr)
+                stderr
             ));
         }
 
@@ -302,34 +568,36 @@ impl MasscanScanner {
 
     pub async fn udp_scan(
         &self,
+        scan_id: Uuid,
         targets: &[IpAddr],
         udp_ports: &[u16],
-        progress_callback: Option<tokio::sync::mpsc::Sender<ScanProgress>>,
+        options: &ScanOptions,
+        progress_callback: Option<BoundedSender<ScanProgress>>,
     ) -> Result<Vec<ScanResult>> {
         let _permit = self.rate_limit.acquire().await?;
+        let lease = self.bandwidth.acquire().await;
         
-        let mut cmd = Command::new("masscan");
+        let mut cmd = Command::new(self.tools.masscan());
         
         for target in targets {
             cmd.arg(target.to_string());
         }
 
         cmd.arg("-pU:").arg(self.format_port_list(udp_ports))
-            .arg("--rate").arg((self.max_rate / 10).to_string()) // Slower for UDP
+            .arg("--rate").arg((options.rate_cap(lease.rate()) / 10).max(1).to_string()) // Slower for UDP
             .arg("--output-format").arg("list")
             .arg("--output-filename").arg("-");
+        self.apply_source_options(&mut cmd, options);
 
-        let mut child = cmd
-            .stdout(Stdio::piped())
-            .stderr(Stdio::piped())
-            .spawn()
-            .context("Failed to start masscan UDP scan")?;
+        let (mut child, _tracked) = self.spawn(scan_id, cmd)?;
 
+        let stderr = self.output.capture(scan_id, OutputStream::Stderr, child.stderr.take().unwrap());
         let stdout = child.stdout.take().unwrap();
         let mut reader = BufReader::new(stdout).lines();
         let mut results = Vec::new();
 
         while let Some(line) = reader.next_line().await? {
+            self.output.push(scan_id, OutputStream::Stdout, &line);
             if let Some(callback) = &progress_callback {
                 if let Ok(progress) = self.parse_masscan_progress(&line) {
                     let _ = callback.send(progress).await;
@@ -341,15 +609,145 @@ impl MasscanScanner {
             }
         }
 
-        let output = child.wait_with_output().await?;
-        
-        if !output.status.success() {
+        let status = child.wait().await?;
+        self.finish(scan_id, status.success()).await;
+        let stderr = stderr.await.unwrap_or_default();
+        if !status.success() {
             return Err(anyhow::anyhow!(
                 "Masscan UDP scan failed: {}", 
-                String::from_utf8_lossy(&output.stderr)
+                stderr
             ));
         }
 
         Ok(results)
     }
+}
+
+/// A run of `MasscanScanner` as a `Source`.
+pub enum MasscanRequest {
+    /// `ports` on `targets`; without ports, all of them
+    Scan {
+        targets: Vec<IpAddr>,
+        ports: Vec<u16>,
+        options: ScanOptions,
+    },
+    /// The `count` most common ports on each of `targets`
+    TopPorts {
+        targets: Vec<IpAddr>,
+        count: usize,
+        options: ScanOptions,
+    },
+    /// A range minus `excludes`, as swept by a massmap plan
+    Sweep {
+        range: String,
+        excludes: Vec<String>,
+        ports: Vec<u16>,
+        options: ScanOptions,
+    },
+    /// Continues the interrupted run with the same id, at no more than
+    /// the rate `options` allow
+    Resume {
+        options: ScanOptions,
+    },
+}
+
+impl Source for MasscanScanner {
+    type Request = MasscanRequest;
+
+    fn name(&self) -> &'static str {
+        "masscan"
+    }
+
+    fn start(self: Arc<Self>, run_id: Uuid, request: MasscanRequest) -> BoxFuture<'static, Result<ObservationStream>> {
+        let scanner = self.clone();
+        let stream = self.runs.spawn(run_id, self.name(), move |sink| async move {
+            let progress = Some(sink.progress());
+            let results = match request {
+                MasscanRequest::Scan { targets, ports, options } => {
+                    scanner.scan_range(run_id, &targets, &ports, &options, progress).await?
+                }
+                MasscanRequest::TopPorts { targets, count, options } => {
+                    let mut results = Vec::new();
+                    for target in targets {
+                        results.extend(scanner.fast_port_discovery(
+                            run_id,
+                            &target.to_string(),
+                            count,
+                            &options,
+                            progress.clone(),
+                        ).await?);
+                    }
+                    results
+                }
+                MasscanRequest::Sweep { range, excludes, ports, options } => {
+                    let excludes: Vec<&str> = excludes.iter().map(String::as_str).collect();
+                    scanner.syn_scan_with_excludes(run_id, &range, &excludes, &ports, &options, progress).await?
+                }
+                MasscanRequest::Resume { options } => scanner.resume(run_id, &options, progress).await?,
+            };
+
+            for result in results {
+                if !sink.send(Observation::Host(result)).await {
+                    break;
+                }
+            }
+            Ok::<_, anyhow::Error>(())
+        });
+
+        Box::pin(futures::future::ready(Ok(stream)))
+    }
+
+    fn cancel(&self, run_id: Uuid) -> bool {
+        self.runs.cancel(run_id)
+    }
+}
+
+/// A running masscan. Dropping it, as happens when its scan is cancelled,
+/// interrupts masscan the way Ctrl-C does so it saves paused.conf, and
+/// kills it if it hasn't exited after a grace period. Elsewhere it is
+/// killed straight away.
+struct MasscanProcess {
+    child: Option<Child>,
+}
+
+impl Deref for MasscanProcess {
+    type Target = Child;
+
+    fn deref(&self) -> &Child {
+        self.child.as_ref().unwrap()
+    }
+}
+
+impl DerefMut for MasscanProcess {
+    fn deref_mut(&mut self) -> &mut Child {
+        self.child.as_mut().unwrap()
+    }
+}
+
+impl Drop for MasscanProcess {
+    fn drop(&mut self) {
+        let Some(mut child) = self.child.take() else {
+            return;
+        };
+        if !matches!(child.try_wait(), Ok(None)) {
+            return;
+        }
+
+        #[cfg(unix)]
+        if let (Some(pid), Ok(runtime)) = (child.id(), tokio::runtime::Handle::try_current()) {
+            // SIGCONT in case the scan was paused, so it sees the SIGINT
+            unsafe {
+                libc::kill(pid as libc::pid_t, libc::SIGINT);
+                libc::kill(pid as libc::pid_t, libc::SIGCONT);
+            }
+            runtime.spawn(async move {
+                if tokio::time::timeout(INTERRUPT_GRACE, child.wait()).await.is_err() {
+                    let _ = child.kill().await;
+                }
+            });
+            return;
+        }
+
+        let _ = child.start_kill();
+    }
 }
\ No newline at end of file
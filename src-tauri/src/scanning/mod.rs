@@ -1,37 +1,352 @@
+pub mod bandwidth;
+pub mod congestion;
+pub mod coordinator;
+pub mod datastore;
+pub mod dependencies;
+pub mod followup;
+pub mod ftp;
+pub mod ics;
+pub mod iot;
+pub mod ipmi;
+pub mod jobgraph;
+pub mod masscan;
+pub mod monitor;
+pub mod nmap;
+pub mod nse;
+pub mod nuclei;
+pub mod output;
+pub mod planner;
+pub mod pipeline;
+pub mod pivot;
+pub mod proxy;
+pub mod ptr;
+pub mod risk;
+pub mod scope;
+pub mod severity;
+pub mod smb;
+pub mod smtp;
+pub mod ssh;
+pub mod template;
+pub mod top_ports;
+pub mod topology;
+pub mod udp;
+pub mod upnp;
+pub mod verification;
+
+pub use bandwidth::*;
+pub use congestion::*;
+pub use coordinator::*;
+pub use datastore::*;
+pub use dependencies::*;
+pub use followup::*;
+pub use ftp::*;
+pub use ics::*;
+pub use iot::*;
+pub use ipmi::*;
+pub use jobgraph::*;
+pub use masscan::*;
+pub use monitor::*;
+pub use nmap::*;
+pub use nse::*;
+pub use nuclei::*;
+pub use output::*;
+pub use planner::*;
+pub use pipeline::*;
+pub use pivot::*;
+pub use proxy::*;
+pub use ptr::*;
+pub use risk::*;
+pub use scope::*;
+pub use severity::*;
+pub use smb::*;
+pub use smtp::*;
+pub use ssh::*;
+pub use template::*;
+pub use top_ports::*;
+pub use topology::*;
+pub use udp::*;
+pub use upnp::*;
+pub use verification::*;
+
 use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
 use std::net::IpAddr;
 use chrono::{DateTime, Utc};
 use uuid::Uuid;
+use ts_rs::TS;
+use crate::helper::HelperEndpoint;
+use crate::utils::SandboxPolicy;
 
-#[derive(Debug, Clone, Serialize, Deserialize)]
+#[derive(Debug, Clone, Serialize, Deserialize, TS)]
+#[ts(export, export_to = "../src/bindings/")]
 pub struct ScanTarget {
     pub id: Uuid,
     pub ip: IpAddr,
     pub hostname: Option<String>,
     pub ports: Vec<u16>,
     pub scan_type: ScanType,
+    #[serde(default)]
+    pub options: ScanOptions,
+}
+
+/// Per-scan tool options layered on top of the scan type.
+#[derive(Debug, Clone, Default, Serialize, Deserialize, TS)]
+#[ts(export, export_to = "../src/bindings/")]
+#[serde(default)]
+pub struct ScanOptions {
+    /// Network interface to send from (`-e`), for multi-homed hosts and VPNs
+    pub interface: Option<String>,
+    /// Source address to send from; masscan `--adapter-ip`, nmap `-S`
+    pub source_ip: Option<IpAddr>,
+    pub evasion: EvasionOptions,
+    pub timing: TimingOptions,
+    pub discovery: DiscoveryOptions,
+    pub scripts: ScriptOptions,
+    /// Filled in from the active project; scans then run as nmap connect
+    /// scans through the proxy
+    pub proxy: Option<crate::database::models::ProxySettings>,
+    /// Run nmap on a jump box over SSH instead of locally
+    pub remote: Option<crate::utils::SshTarget>,
+    /// Scan targets outside the active project's scope; the override is
+    /// written to the audit log
+    pub override_scope: bool,
+    /// Stored on the scan record; scans a campaign starts inherit them
+    pub annotations: crate::database::models::ScanAnnotations,
+}
+
+impl ScanOptions {
+    /// masscan and the native probes only run locally; a proxied or remote
+    /// scan must be carried out by nmap alone.
+    pub fn nmap_only(&self) -> bool {
+        self.proxy.is_some() || self.remote.is_some()
+    }
+
+    /// The packet rate to use given the scan's share of the bandwidth
+    /// budget, lowered to the requested maximum.
+    pub fn rate_cap(&self, budget: u32) -> u32 {
+        self.timing.max_rate.map_or(budget, |max| max.min(budget)).max(1)
+    }
+}
+
+/// nmap timing controls. Unset fields keep the scan type's defaults.
+#[derive(Debug, Clone, Default, Serialize, Deserialize, TS)]
+#[ts(export, export_to = "../src/bindings/")]
+#[serde(default)]
+pub struct TimingOptions {
+    /// Timing template from 0 (paranoid) to 5 (insane), `-T<n>`
+    pub template: Option<u8>,
+    /// Give up on a host after this long (`--host-timeout`)
+    pub host_timeout_secs: Option<u32>,
+    pub max_retries: Option<u8>,
+    /// Packets per second (`--min-rate`)
+    pub min_rate: Option<u32>,
+    /// Packets per second (`--max-rate`); never above the scan's share of
+    /// the bandwidth budget. Also caps masscan's `--rate`.
+    pub max_rate: Option<u32>,
 }
 
-#[derive(Debug, Clone, Serialize, Deserialize)]
+/// nmap host discovery. Left unset, nmap pings with ICMP echo, SYN to 443,
+/// ACK to 80 and an ICMP timestamp request, or ARP on a local segment;
+/// networks that drop ICMP need TCP or UDP probes to ports that answer, or
+/// discovery skipped.
+#[derive(Debug, Clone, Default, Serialize, Deserialize, TS)]
+#[ts(export, export_to = "../src/bindings/")]
+#[serde(default)]
+pub struct DiscoveryOptions {
+    /// Treat every target as up (`-Pn`)
+    pub skip: bool,
+    /// TCP SYN ping ports (`-PS`)
+    pub syn_ports: Vec<u16>,
+    /// TCP ACK ping ports (`-PA`)
+    pub ack_ports: Vec<u16>,
+    /// UDP ping ports (`-PU`)
+    pub udp_ports: Vec<u16>,
+    /// ARP ping on the local segment (`-PR`)
+    pub arp: bool,
+}
+
+impl DiscoveryOptions {
+    /// Whether any probe is chosen in place of nmap's defaults
+    pub fn has_probes(&self) -> bool {
+        self.arp || !self.syn_ports.is_empty() || !self.ack_ports.is_empty() || !self.udp_ports.is_empty()
+    }
+}
+
+pub const MAX_TIMING_TEMPLATE: u8 = 5;
+/// Longest scan reason kept on a record
+pub const MAX_SCAN_REASON_LEN: usize = 500;
+pub const MAX_TICKET_REF_LEN: usize = 64;
+
+/// NSE scripts to run (`--script`) and their arguments (`--script-args`).
+#[derive(Debug, Clone, Default, Serialize, Deserialize, TS)]
+#[ts(export, export_to = "../src/bindings/")]
+#[serde(default)]
+pub struct ScriptOptions {
+    /// Script names, categories or wildcard patterns
+    pub selection: Vec<String>,
+    /// e.g. `http.useragent` or `smbusername`
+    pub args: BTreeMap<String, String>,
+}
+
+/// IDS/firewall evasion settings passed to nmap.
+#[derive(Debug, Clone, Default, Serialize, Deserialize, TS)]
+#[ts(export, export_to = "../src/bindings/")]
+#[serde(default)]
+pub struct EvasionOptions {
+    /// Decoy addresses for `-D`: IPs, `ME`, or `RND`/`RND:<n>`
+    pub decoys: Vec<String>,
+    pub source_port: Option<u16>,
+    /// Split probes into 8-byte IP fragments (`-f`)
+    pub fragment: bool,
+    /// Random bytes appended to each probe (`--data-length`)
+    pub data_length: Option<u16>,
+    pub randomize_hosts: bool,
+}
+
+/// nmap accepts more, but padding past a standard MTU only gets probes
+/// fragmented or dropped.
+pub const MAX_DATA_LENGTH: u16 = 1400;
+pub const MAX_DECOYS: usize = 16;
+
+#[derive(Debug, Clone, Serialize, Deserialize, TS)]
+#[ts(export, export_to = "../src/bindings/")]
 pub enum ScanType {
     Quick,
     Comprehensive,
     Stealth,
+    /// Gentle ICS protocol identification only; no port sweep
+    Ot,
     Custom { options: String },
 }
 
-#[derive(Debug, Clone, Serialize, Deserialize)]
+#[derive(Debug, Clone, Serialize, Deserialize, TS)]
+#[ts(export, export_to = "../src/bindings/")]
 pub struct ScanResult {
     pub id: Uuid,
     pub target_id: Uuid,
+    pub target_ip: IpAddr,
+    pub mac_address: Option<String>,
     pub timestamp: DateTime<Utc>,
     pub status: ScanStatus,
     pub open_ports: Vec<Port>,
     pub os_detection: Option<OsDetection>,
     pub vulnerabilities: Vec<Vulnerability>,
+    /// Whether the scanner saw the host up; `None` when it can't tell
+    #[serde(default)]
+    pub host_up: Option<bool>,
+    /// Smoothed round-trip time reported by nmap
+    #[serde(default)]
+    pub rtt_ms: Option<f64>,
+    /// Routers on the way to the host, when nmap ran with `--traceroute`
+    #[serde(default)]
+    pub trace: Vec<TraceHop>,
+    /// TCP ports the scan probed, as nmap's port spec. `None` when the
+    /// scanner doesn't say what it covered.
+    #[serde(default)]
+    pub scanned_tcp_ports: Option<String>,
+    /// State of the probed ports missing from `open_ports`, `closed` or
+    /// `filtered` as nmap's `extraports` gave it. Only set when the host
+    /// answered and nmap finished with it, so ports open before that are
+    /// now among these can be taken to have changed to this state.
+    #[serde(default)]
+    pub unlisted_tcp_state: Option<String>,
+    /// Unmodified tool output the result was parsed from, kept as evidence
+    #[serde(skip)]
+    pub raw_output: Vec<u8>,
 }
 
-#[derive(Debug, Clone, Serialize, Deserialize)]
+pub const RAW_FORMAT_NMAP_XML: &str = "nmap-xml";
+pub const RAW_FORMAT_MASSCAN_LIST: &str = "masscan-list";
+
+impl ScanResult {
+    /// Which parser `raw_output` is for: nmap writes XML, masscan a line
+    /// list.
+    pub fn raw_output_format(&self) -> &'static str {
+        if self.raw_output.starts_with(b"<?xml") || self.raw_output.starts_with(b"<nmaprun") {
+            RAW_FORMAT_NMAP_XML
+        } else {
+            RAW_FORMAT_MASSCAN_LIST
+        }
+    }
+}
+
+/// Locations of the external scanner binaries. Shared by every scanner so
+/// config changes apply to the next process spawned.
+pub struct ToolPaths {
+    nmap: std::sync::RwLock<String>,
+    masscan: std::sync::RwLock<String>,
+    nuclei: std::sync::RwLock<String>,
+    sandbox: std::sync::RwLock<SandboxPolicy>,
+    helper: std::sync::RwLock<Option<HelperEndpoint>>,
+}
+
+impl ToolPaths {
+    pub fn new(nmap: &str, masscan: &str, nuclei: &str, sandbox: &SandboxPolicy) -> Self {
+        Self {
+            nmap: std::sync::RwLock::new(nmap.to_string()),
+            masscan: std::sync::RwLock::new(masscan.to_string()),
+            nuclei: std::sync::RwLock::new(nuclei.to_string()),
+            sandbox: std::sync::RwLock::new(sandbox.clone()),
+            helper: std::sync::RwLock::new(None),
+        }
+    }
+
+    pub fn nmap(&self) -> String {
+        self.nmap.read().unwrap().clone()
+    }
+
+    pub fn masscan(&self) -> String {
+        self.masscan.read().unwrap().clone()
+    }
+
+    pub fn nuclei(&self) -> String {
+        self.nuclei.read().unwrap().clone()
+    }
+
+    pub fn sandbox(&self) -> SandboxPolicy {
+        self.sandbox.read().unwrap().clone()
+    }
+
+    /// Set while the privileged helper is running; raw-socket scans are
+    /// then routed through it
+    pub fn helper(&self) -> Option<HelperEndpoint> {
+        self.helper.read().unwrap().clone()
+    }
+
+    pub fn set_helper(&self, endpoint: Option<HelperEndpoint>) {
+        *self.helper.write().unwrap() = endpoint;
+    }
+
+    pub fn set(&self, nmap: &str, masscan: &str, nuclei: &str, sandbox: &SandboxPolicy) {
+        *self.nmap.write().unwrap() = nmap.to_string();
+        *self.masscan.write().unwrap() = masscan.to_string();
+        *self.nuclei.write().unwrap() = nuclei.to_string();
+        *self.sandbox.write().unwrap() = sandbox.clone();
+    }
+}
+
+/// Out-of-band notification forwarded to the frontend as a Tauri event.
+#[derive(Debug, Clone, Serialize)]
+pub struct FrontendEvent {
+    pub name: &'static str,
+    pub payload: serde_json::Value,
+}
+
+/// What one stored scan result changed, sent as a `host-updated` event so
+/// host tables can patch a single row instead of reloading the list.
+#[derive(Debug, Clone, Serialize, TS)]
+#[ts(export, export_to = "../src/bindings/")]
+pub struct HostDelta {
+    /// The host as stored after the result was applied
+    pub host: crate::database::models::Host,
+    /// First time the host was seen
+    pub created: bool,
+    pub ports: Vec<crate::database::models::Port>,
+    pub vulnerabilities: Vec<crate::database::models::Vulnerability>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, TS)]
+#[ts(export, export_to = "../src/bindings/")]
 pub enum ScanStatus {
     Queued,
     Running,
@@ -39,7 +354,8 @@ pub enum ScanStatus {
     Failed { error: String },
 }
 
-#[derive(Debug, Clone, Serialize, Deserialize)]
+#[derive(Debug, Clone, Serialize, Deserialize, TS)]
+#[ts(export, export_to = "../src/bindings/")]
 pub struct Port {
     pub number: u16,
     pub protocol: String,
@@ -49,7 +365,18 @@ pub struct Port {
     pub banner: Option<String>,
 }
 
-#[derive(Debug, Clone, Serialize, Deserialize)]
+/// One answering hop of a traceroute; the last is the host itself.
+#[derive(Debug, Clone, Serialize, Deserialize, TS)]
+#[ts(export, export_to = "../src/bindings/")]
+pub struct TraceHop {
+    pub ttl: u8,
+    pub ip: IpAddr,
+    pub rtt_ms: Option<f64>,
+    pub hostname: Option<String>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, TS)]
+#[ts(export, export_to = "../src/bindings/")]
 pub struct OsDetection {
     pub name: String,
     pub accuracy: f32,
@@ -57,7 +384,8 @@ pub struct OsDetection {
     pub vendor: String,
 }
 
-#[derive(Debug, Clone, Serialize, Deserialize)]
+#[derive(Debug, Clone, Serialize, Deserialize, TS)]
+#[ts(export, export_to = "../src/bindings/")]
 pub struct Vulnerability {
     pub id: String,
     pub name: String,
@@ -65,13 +393,44 @@ pub struct Vulnerability {
     pub description: String,
     pub cvss_score: Option<f32>,
     pub references: Vec<String>,
+    /// TCP port whose NSE script reported it; `None` for host scripts
+    /// and UDP ports
+    #[serde(default)]
+    pub port: Option<u16>,
+    /// The NSE script that reported it
+    #[serde(default)]
+    pub script: Option<String>,
 }
 
-#[derive(Debug, Clone, Serialize, Deserialize)]
+#[derive(Debug, Clone, Serialize, Deserialize, TS)]
+#[ts(export, export_to = "../src/bindings/")]
 pub enum Severity {
     Info,
     Low,
     Medium,
     High,
     Critical,
-}
\ No newline at end of file
+}
+
+impl Severity {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Severity::Info => "info",
+            Severity::Low => "low",
+            Severity::Medium => "medium",
+            Severity::High => "high",
+            Severity::Critical => "critical",
+        }
+    }
+
+    /// Qualitative rating for a CVSS v3 score. A score of 0.0 maps to info.
+    pub fn from_cvss(score: f32) -> Self {
+        match score {
+            s if s >= 9.0 => Severity::Critical,
+            s if s >= 7.0 => Severity::High,
+            s if s >= 4.0 => Severity::Medium,
+            s if s > 0.0 => Severity::Low,
+            _ => Severity::Info,
+        }
+    }
+}
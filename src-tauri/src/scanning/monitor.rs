@@ -0,0 +1,125 @@
+use super::ScanCoordinator;
+use crate::database::{Database, operations::*};
+use anyhow::{Result, bail};
+use futures::future::join_all;
+use std::net::{IpAddr, SocketAddr};
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant};
+use tokio::net::TcpStream;
+use tokio::task::JoinHandle;
+
+pub const MIN_MONITOR_INTERVAL: Duration = Duration::from_secs(10);
+const PING_TIMEOUT: Duration = Duration::from_secs(2);
+/// Hosts pinged at once in each round
+const PING_BATCH: usize = 32;
+/// Tried when a host has no known open TCP port
+const FALLBACK_PING_PORTS: &[u16] = &[443, 80, 22, 445];
+
+/// Periodically checks every stored host the active project allows probing
+/// with a TCP connect and records its status and RTT. A refused connection
+/// still proves the host is up, so no raw sockets are needed.
+pub struct HostMonitor {
+    task: Mutex<Option<JoinHandle<()>>>,
+}
+
+impl HostMonitor {
+    pub fn new() -> Self {
+        Self {
+            task: Mutex::new(None),
+        }
+    }
+
+    pub fn is_running(&self) -> bool {
+        self.task.lock().unwrap().as_ref().is_some_and(|task| !task.is_finished())
+    }
+
+    pub fn start(&self, database: Arc<Database>, coordinator: Arc<ScanCoordinator>, interval: Duration) -> Result<()> {
+        if interval < MIN_MONITOR_INTERVAL {
+            bail!("Monitor interval must be at least {} seconds", MIN_MONITOR_INTERVAL.as_secs());
+        }
+
+        // Held across the check and the spawn so two starts can't both run
+        let mut task = self.task.lock().unwrap();
+        if task.as_ref().is_some_and(|task| !task.is_finished()) {
+            bail!("Host monitor is already running");
+        }
+
+        *task = Some(tokio::spawn(async move {
+            let mut ticker = tokio::time::interval(interval);
+
+            loop {
+                ticker.tick().await;
+                if let Err(e) = Self::check_all(&database, &coordinator).await {
+                    eprintln!("Host monitor round failed: {}", e);
+                }
+            }
+        }));
+
+        Ok(())
+    }
+
+    /// Ends the monitor task, including a round in progress.
+    pub fn stop(&self) {
+        if let Some(task) = self.task.lock().unwrap().take() {
+            task.abort();
+        }
+    }
+
+    async fn check_all(database: &Database, coordinator: &ScanCoordinator) -> Result<()> {
+        let pool = database.pool();
+        let mut targets = Vec::new();
+
+        for host in HostOperations::list_all(&pool).await? {
+            let Ok(ip) = host.ip.parse::<IpAddr>() else {
+                continue;
+            };
+            if !coordinator.may_probe(&ip, host.hostname.as_deref()).await {
+                continue;
+            }
+            let mut ports: Vec<u16> = PortOperations::find_open_ports(&pool, &host.id).await?
+                .into_iter()
+                .filter(|p| p.protocol == "tcp")
+                .map(|p| p.number as u16)
+                .take(2)
+                .collect();
+            if ports.is_empty() {
+                ports = FALLBACK_PING_PORTS.to_vec();
+            }
+            targets.push((host.id, ip, ports));
+        }
+
+        for batch in targets.chunks(PING_BATCH) {
+            let pings = batch.iter().map(|(_, ip, ports)| Self::ping(*ip, ports));
+            let results = join_all(pings).await;
+
+            for ((host_id, _, _), rtt) in batch.iter().zip(results) {
+                HostStatusOperations::record(&pool, host_id, rtt.is_some(), rtt, "monitor").await?;
+            }
+        }
+
+        Ok(())
+    }
+
+    /// Round-trip time in milliseconds of the first port that answers,
+    /// with a handshake or a reset.
+    async fn ping(ip: IpAddr, ports: &[u16]) -> Option<f64> {
+        for &port in ports {
+            let started = Instant::now();
+            match tokio::time::timeout(PING_TIMEOUT, TcpStream::connect(SocketAddr::new(ip, port))).await {
+                Ok(Ok(_)) => return Some(started.elapsed().as_secs_f64() * 1000.0),
+                Ok(Err(e)) if e.kind() == std::io::ErrorKind::ConnectionRefused => {
+                    return Some(started.elapsed().as_secs_f64() * 1000.0);
+                }
+                _ => continue,
+            }
+        }
+
+        None
+    }
+}
+
+impl Default for HostMonitor {
+    fn default() -> Self {
+        Self::new()
+    }
+}
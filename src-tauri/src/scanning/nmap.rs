@@ -1,109 +1,525 @@
 use super::*;
-use anyhow::{Result, Context};
+use crate::error::LegionError;
+use ts_rs::TS;
+use anyhow::{Context, Result};
 use std::process::Stdio;
 use tokio::process::Command;
 use tokio::io::{AsyncBufReadExt, BufReader};
 use xml_rs::{EventReader, Event};
+use xml_rs::attribute::OwnedAttribute;
+
+use std::collections::HashSet;
+use std::net::IpAddr;
+use std::path::PathBuf;
+use tempfile::TempPath;
+use std::sync::Arc;
+use crate::sources::{Observation, ObservationSink, ObservationStream, Source, SourceRuns};
+use crate::utils::{sandbox, BoundedSender, InputValidator, ProcessManager};
+use futures::future::BoxFuture;
 
 pub struct NmapScanner {
     rate_limit: tokio::sync::Semaphore,
+    bandwidth: Arc<BandwidthBudget>,
+    tools: Arc<ToolPaths>,
+    output: Arc<ScanOutputLog>,
+    /// Grepable logs of multi-host runs, `<resume_dir>/<run_id>/chunk-N.gnmap`,
+    /// which `nmap --resume` continues from
+    resume_dir: PathBuf,
+    runs: SourceRuns,
 }
 
 impl NmapScanner {
-    pub fn new(max_concurrent: usize) -> Self {
+    pub fn new(
+        max_concurrent: usize,
+        bandwidth: Arc<BandwidthBudget>,
+        tools: Arc<ToolPaths>,
+        output: Arc<ScanOutputLog>,
+        resume_dir: PathBuf,
+    ) -> Self {
         Self {
             rate_limit: tokio::sync::Semaphore::new(max_concurrent),
+            bandwidth,
+            tools,
+            output,
+            resume_dir,
+            runs: SourceRuns::default(),
         }
     }
 
     pub async fn scan_target(
         &self,
         target: &ScanTarget,
-        progress_callback: Option<tokio::sync::mpsc::Sender<ScanProgress>>,
+        variables: &TemplateVariables,
+        progress_callback: Option<BoundedSender<ScanProgress>>,
     ) -> Result<ScanResult> {
         let _permit = self.rate_limit.acquire().await?;
+        let lease = self.bandwidth.acquire().await;
         
-        let mut cmd = Command::new("nmap");
+        let (mut cmd, _proxy_config) = self.base_command(&target.options)?;
         
         // Build nmap command based on scan type
-        self.configure_nmap_command(&mut cmd, target)?;
+        self.configure_nmap_command(&mut cmd, target, variables, lease.rate())?;
+        cmd.arg(target.ip.to_string());
+
+        // proxychains works by preloading into the process it starts, which
+        // can't cross to the helper
+        let proxychains = target.options.proxy.as_ref().is_some_and(|p| p.mode == "proxychains");
+        let helper = self.tools.helper().filter(|_| !proxychains);
+        // Stopping the relay or ssh client would not stop nmap itself
+        let pausable = target.options.remote.is_none() && helper.is_none();
+        if let Some(remote) = &target.options.remote {
+            cmd = ProcessManager::wrap_remote(&cmd, remote);
+        } else if let Some(helper) = helper {
+            cmd = ProcessManager::wrap_helper(&cmd, "nmap", &helper)?;
+        }
         
-        let mut child = cmd
-            .stdout(Stdio::piped())
+        // proxychains or ssh when wrapped; that is the binary that must exist
+        let program = cmd.as_std().get_program().to_string_lossy().into_owned();
+        cmd.stdout(Stdio::piped())
             .stderr(Stdio::piped())
-            .spawn()
-            .context("Failed to start nmap process")?;
+            .kill_on_drop(true);
+        let spawned = match target.options.remote {
+            Some(_) => cmd.spawn(),
+            None => ProcessManager::spawn_sandboxed(&mut cmd, &self.tools.sandbox()),
+        };
+        let mut child = spawned.map_err(|e| LegionError::spawn(&program, e))?;
+        let _tracked = self.output.track_process(target.id, child.id().filter(|_| pausable));
 
+        let stderr = self.output.capture(target.id, OutputStream::Stderr, child.stderr.take().unwrap());
         let stdout = child.stdout.take().unwrap();
         let mut reader = BufReader::new(stdout).lines();
+        let mut xml = Vec::new();
 
         // Stream output for real-time updates
         while let Some(line) = reader.next_line().await? {
+            self.output.push(target.id, OutputStream::Stdout, &line);
+            xml.extend_from_slice(line.as_bytes());
+            xml.push(b'\n');
+
             if let Some(callback) = &progress_callback {
                 let progress = self.parse_nmap_progress(&line)?;
                 let _ = callback.send(progress).await;
             }
         }
 
-        let output = child.wait_with_output().await?;
-        
-        if !output.status.success() {
+        let status = child.wait().await?;
+        let stderr = stderr.await.unwrap_or_default();
+        if !status.success() {
             return Err(anyhow::anyhow!(
                 "Nmap scan failed: {}", 
-                String::from_utf8_lossy(&output.stderr)
+                stderr
             ));
         }
 
-        self.parse_nmap_xml(target, &output.stdout)
+        self.parse_nmap_xml(target, &xml)
     }
 
-    fn configure_nmap_command(&self, cmd: &mut Command, target: &ScanTarget) -> Result<()> {
+    /// Runs one NSE script against one port of `ip` to verify a finding it
+    /// raised before. Version detection lets the script's port rule see
+    /// the real service on a non-standard port.
+    pub async fn run_script(&self, ip: IpAddr, port: u16, script: &str) -> Result<ScanResult> {
+        InputValidator::validate_script_selection(script)?;
+
+        let mut options = ScanOptions::default();
+        options.scripts.selection = vec![script.to_string()];
+        let target = ScanTarget {
+            id: Uuid::new_v4(),
+            ip,
+            hostname: None,
+            ports: vec![port],
+            scan_type: ScanType::Custom { options: "-sV -Pn".to_string() },
+            options,
+        };
+        self.scan_target(&target, &TemplateVariables::default(), None).await
+    }
+
+    /// Scans `hosts` in one nmap run with the options of `template`, sending
+    /// each host's result to `sink` as soon as nmap finishes it. Local runs also log
+    /// grepable output, so when chunk `chunk` of `run_id` was interrupted,
+    /// calling this again continues with `nmap --resume` instead of
+    /// starting over.
+    pub async fn scan_hosts(
+        &self,
+        run_id: Uuid,
+        chunk: usize,
+        template: &ScanTarget,
+        variables: &TemplateVariables,
+        hosts: &[IpAddr],
+        sink: &ObservationSink,
+    ) -> Result<()> {
+        let _permit = self.rate_limit.acquire().await?;
+        let lease = self.bandwidth.acquire().await;
+
+        let gnmap = self.resume_dir.join(run_id.to_string()).join(format!("chunk-{}.gnmap", chunk));
+        let proxychains = template.options.proxy.as_ref().is_some_and(|p| p.mode == "proxychains");
+        let helper = self.tools.helper().filter(|_| !proxychains);
+        // Only a local nmap can be resumed or paused: the helper refuses
+        // output files, a remote nmap writes its own, and stopping the relay
+        // or ssh client would not stop the scan
+        let local = template.options.remote.is_none() && helper.is_none();
+
+        let (mut cmd, _proxy_config) = self.base_command(&template.options)?;
+        if local && gnmap.is_file() {
+            // nmap takes the original command line from the log
+            cmd.arg("--resume").arg(&gnmap);
+        } else {
+            self.configure_nmap_command(&mut cmd, template, variables, lease.rate())?;
+            if local {
+                if let Some(dir) = gnmap.parent() {
+                    tokio::fs::create_dir_all(dir).await?;
+                    // nmap writes the log as the sandbox user
+                    sandbox::grant(dir, &self.tools.sandbox())
+                        .with_context(|| format!("Failed to hand {} to the sandbox user", dir.display()))?;
+                }
+                cmd.arg("-oG").arg(&gnmap);
+            }
+            cmd.args(hosts.iter().map(IpAddr::to_string));
+        }
+
+        if let Some(remote) = &template.options.remote {
+            cmd = ProcessManager::wrap_remote(&cmd, remote);
+        } else if let Some(helper) = helper {
+            cmd = ProcessManager::wrap_helper(&cmd, "nmap", &helper)?;
+        }
+
+        let program = cmd.as_std().get_program().to_string_lossy().into_owned();
+        cmd.stdout(Stdio::piped())
+            .stderr(Stdio::piped())
+            .kill_on_drop(true);
+        let spawned = match template.options.remote {
+            Some(_) => cmd.spawn(),
+            None => ProcessManager::spawn_sandboxed(&mut cmd, &self.tools.sandbox()),
+        };
+        let mut child = spawned.map_err(|e| LegionError::spawn(&program, e))?;
+        let _tracked = self.output.track_process(template.id, child.id().filter(|_| local));
+
+        let stderr = self.output.capture(template.id, OutputStream::Stderr, child.stderr.take().unwrap());
+        let stdout = child.stdout.take().unwrap();
+        let mut reader = BufReader::new(stdout).lines();
+
+        // nmap writes each finished host as a <host> element spanning whole
+        // lines, so hosts are parsed one at a time as they complete. The
+        // ports probed are given once for the run, before the first host.
+        let mut host_xml: Option<Vec<u8>> = None;
+        let mut scanned_tcp_ports: Option<String> = None;
+        while let Some(line) = reader.next_line().await? {
+            self.output.push(template.id, OutputStream::Stdout, &line);
+
+            let trimmed = line.trim_start();
+            if trimmed.starts_with("<scaninfo") {
+                let ports = EventReader::new(trimmed.as_bytes()).into_iter()
+                    .find_map(|event| match event {
+                        Ok(Event::StartElement { attributes, .. }) => Self::scanned_tcp_ports(&attributes),
+                        _ => None,
+                    });
+                scanned_tcp_ports = ports.or(scanned_tcp_ports);
+            }
+            if trimmed.starts_with("<host ") || trimmed.starts_with("<host>") {
+                host_xml = Some(Vec::new());
+            }
+            let Some(xml) = host_xml.as_mut() else {
+                continue;
+            };
+            xml.extend_from_slice(line.as_bytes());
+            xml.push(b'\n');
+
+            if line.contains("</host>") {
+                let xml = host_xml.take().unwrap_or_default();
+                match self.parse_nmap_xml(template, &xml) {
+                    Ok(mut result) => {
+                        result.scanned_tcp_ports = scanned_tcp_ports.clone();
+                        if !sink.send(Observation::Host(result)).await {
+                            return Ok(());
+                        }
+                    }
+                    Err(e) => eprintln!("Failed to parse nmap host output: {}", e),
+                }
+            }
+        }
+
+        let status = child.wait().await?;
+        let stderr = stderr.await.unwrap_or_default();
+        if !status.success() {
+            return Err(anyhow::anyhow!(
+                "Nmap scan failed: {}",
+                stderr
+            ));
+        }
+
+        let _ = tokio::fs::remove_file(&gnmap).await;
+        Ok(())
+    }
+
+    /// Removes the grepable logs of every chunk of `run_id`.
+    pub async fn clear_resume_logs(&self, run_id: Uuid) {
+        let _ = tokio::fs::remove_dir_all(self.resume_dir.join(run_id.to_string())).await;
+    }
+
+    /// Starts the command line, wrapped in proxychains when the scan is
+    /// routed that way. The proxychains config is removed when the returned
+    /// path is dropped, so it must be held until nmap exits.
+    fn base_command(&self, options: &ScanOptions) -> Result<(Command, Option<TempPath>)> {
+        let nmap = match &options.remote {
+            Some(remote) => remote.nmap_path.clone().unwrap_or_else(|| "nmap".to_string()),
+            None => self.tools.nmap(),
+        };
+
+        match &options.proxy {
+            // The proxychains config is written locally, so only a local
+            // nmap can be wrapped
+            Some(proxy) if proxy.mode == "proxychains" && options.remote.is_none() => {
+                let config = ProxyRouting::write_proxychains_config(proxy, &self.tools.sandbox())?;
+                let mut cmd = Command::new(PROXYCHAINS_BINARY);
+                cmd.arg("-q").arg("-f").arg(&config).arg(nmap);
+                Ok((cmd, Some(config)))
+            }
+            _ => Ok((Command::new(nmap), None)),
+        }
+    }
+
+    fn configure_nmap_command(&self, cmd: &mut Command, target: &ScanTarget, variables: &TemplateVariables, rate: u32) -> Result<()> {
         cmd.arg("-oX").arg("-"); // XML output to stdout
+        // Share of the global budget
+        cmd.arg("--max-rate").arg(target.options.rate_cap(rate).to_string());
+
+        // Raw SYN packets bypass a proxy, so proxied scans use connect scans
+        // and skip host discovery, which can't cross it either
+        let technique = if target.options.proxy.is_some() {
+            cmd.arg("-Pn");
+            if let Some(proxy) = target.options.proxy.as_ref().filter(|p| p.mode == "nmap") {
+                cmd.arg("--proxies").arg(ProxyRouting::proxy_url(proxy));
+            }
+            "-sT"
+        } else {
+            "-sS"
+        };
         
-        match &target.scan_type {
+        // Each preset's timing template, unless the scan chose its own
+        let default_template = match &target.scan_type {
             ScanType::Quick => {
-                cmd.args(["-sS", "-T4", "--top-ports", "1000"]);
+                cmd.arg(technique);
+                if target.ports.is_empty() {
+                    cmd.args(["--top-ports", "1000"]);
+                }
+                Some(4)
             }
             ScanType::Comprehensive => {
-                cmd.args(["-sS", "-sV", "-O", "-A", "-T4"]);
-                cmd.args(["-p", "1-65535"]);
+                // OS detection and traceroute need raw packets a proxy won't carry
+                if target.options.proxy.is_some() {
+                    cmd.args([technique, "-sV", "-sC"]);
+                } else {
+                    cmd.args([technique, "-sV", "-O", "-A"]);
+                }
+                if target.ports.is_empty() {
+                    cmd.args(["-p", "1-65535"]);
+                }
+                Some(4)
             }
             ScanType::Stealth => {
-                cmd.args(["-sS", "-T2", "-f"]);
+                cmd.arg(technique);
+                if !target.options.evasion.fragment {
+                    cmd.arg("-f");
+                }
+                Some(2)
             }
-            ScanType::Custom { options } => {
-                for opt in options.split_whitespace() {
-                    cmd.arg(opt);
+            ScanType::Ot => {
+                // Connect scan at a crawl: SYN floods have crashed PLCs
+                cmd.arg("-sT");
+                if target.ports.is_empty() {
+                    cmd.args(["-p", "102,502,20000"]);
                 }
+                Some(1)
+            }
+            ScanType::Custom { options } => {
+                cmd.args(ScanTemplate::expand(options, variables)?);
+                None
             }
+        };
+
+        let timing = &target.options.timing;
+        if let Some(template) = timing.template.or(default_template) {
+            cmd.arg(format!("-T{}", template));
+        }
+        if let Some(secs) = timing.host_timeout_secs {
+            cmd.arg("--host-timeout").arg(format!("{}s", secs));
+        }
+        if let Some(retries) = timing.max_retries {
+            cmd.arg("--max-retries").arg(retries.to_string());
+        }
+        // nmap rejects a minimum above the maximum, which the budget may
+        // have lowered
+        if let Some(min_rate) = timing.min_rate {
+            cmd.arg("--min-rate").arg(min_rate.min(target.options.rate_cap(rate)).to_string());
+        }
+
+        if let Some(interface) = &target.options.interface {
+            cmd.arg("-e").arg(interface);
+        }
+        if let Some(source_ip) = &target.options.source_ip {
+            cmd.arg("-S").arg(source_ip.to_string());
+        }
+
+        // Proxied scans have already skipped discovery
+        if target.options.proxy.is_none() {
+            self.apply_discovery_options(cmd, &target.options.discovery);
+        }
+        self.apply_evasion_options(cmd, &target.options.evasion);
+
+        let scripts = &target.options.scripts;
+        if !scripts.selection.is_empty() {
+            cmd.arg("--script").arg(scripts.selection.join(","));
+        }
+        if !scripts.args.is_empty() {
+            cmd.arg("--script-args").arg(InputValidator::format_script_args(&scripts.args)?);
+        }
+
+        // An explicit port selection (list or preset) overrides the defaults
+        if !target.ports.is_empty() {
+            cmd.arg("-p").arg(TopPorts::format_spec(&target.ports));
         }
 
-        cmd.arg(target.ip.to_string());
         Ok(())
     }
 
-    fn parse_nmap_xml(&self, target: &ScanTarget, xml_data: &[u8]) -> Result<ScanResult> {
+    fn apply_discovery_options(&self, cmd: &mut Command, discovery: &DiscoveryOptions) {
+        if discovery.skip {
+            cmd.arg("-Pn");
+            return;
+        }
+
+        let port_list = |ports: &[u16]| ports.iter().map(|p| p.to_string()).collect::<Vec<_>>().join(",");
+        if !discovery.syn_ports.is_empty() {
+            cmd.arg(format!("-PS{}", port_list(&discovery.syn_ports)));
+        }
+        if !discovery.ack_ports.is_empty() {
+            cmd.arg(format!("-PA{}", port_list(&discovery.ack_ports)));
+        }
+        if !discovery.udp_ports.is_empty() {
+            cmd.arg(format!("-PU{}", port_list(&discovery.udp_ports)));
+        }
+        if discovery.arp {
+            cmd.arg("-PR");
+        }
+    }
+
+    fn apply_evasion_options(&self, cmd: &mut Command, evasion: &EvasionOptions) {
+        if !evasion.decoys.is_empty() {
+            cmd.arg("-D").arg(evasion.decoys.join(","));
+        }
+        if let Some(port) = evasion.source_port {
+            cmd.arg("--source-port").arg(port.to_string());
+        }
+        if evasion.fragment {
+            cmd.arg("-f");
+        }
+        if let Some(length) = evasion.data_length {
+            cmd.arg("--data-length").arg(length.to_string());
+        }
+        if evasion.randomize_hosts {
+            cmd.arg("--randomize-hosts");
+        }
+    }
+
+    pub fn parse_nmap_xml(&self, target: &ScanTarget, xml_data: &[u8]) -> Result<ScanResult> {
         let mut result = ScanResult {
             id: Uuid::new_v4(),
             target_id: target.id,
+            target_ip: target.ip,
+            mac_address: None,
             timestamp: Utc::now(),
             status: ScanStatus::Completed,
             open_ports: Vec::new(),
             os_detection: None,
             vulnerabilities: Vec::new(),
+            host_up: None,
+            rtt_ms: None,
+            trace: Vec::new(),
+            scanned_tcp_ports: None,
+            unlisted_tcp_state: None,
+            raw_output: xml_data.to_vec(),
         };
 
         // XML parsing implementation
         let parser = EventReader::new(xml_data);
+        // Script findings belong to the TCP port whose element they're in;
+        // only those can be verified by running the script again
+        let mut current_port: Option<u16> = None;
+        let mut timed_out = false;
+        let mut status_reason: Option<String> = None;
+        let mut extraports: HashSet<String> = HashSet::new();
         
         for event in parser {
             match event? {
                 Event::StartElement { name, attributes, .. } => {
                     match name.local_name.as_str() {
+                        // --host-timeout gives up on a host and lists none
+                        // of its ports
+                        "host" => {
+                            timed_out = Self::attribute(&attributes, "timedout").as_deref() == Some("true");
+                        }
+                        "extraports" => {
+                            extraports.extend(Self::attribute(&attributes, "state"));
+                        }
                         "port" => {
+                            current_port = Self::attribute(&attributes, "portid")
+                                .filter(|_| Self::attribute(&attributes, "protocol").as_deref() == Some("tcp"))
+                                .and_then(|p| p.parse().ok());
                             let port = self.parse_port_element(&attributes)?;
                             result.open_ports.push(port);
                         }
+                        "script" => {
+                            let attr = |key: &str| Self::attribute(&attributes, key);
+                            if let (Some(id), Some(output)) = (attr("id"), attr("output")) {
+                                result.vulnerabilities.extend(Self::nse_vulnerability(&id, current_port, &output));
+                            }
+                        }
+                        "address" => {
+                            let attr = |key: &str| Self::attribute(&attributes, key);
+
+                            match attr("addrtype").as_deref() {
+                                Some("mac") => result.mac_address = attr("addr").map(|mac| mac.to_lowercase()),
+                                // Multi-host runs share one template target
+                                Some("ipv4") | Some("ipv6") => {
+                                    if let Some(ip) = attr("addr").and_then(|a| a.parse().ok()) {
+                                        result.target_ip = ip;
+                                    }
+                                }
+                                _ => {}
+                            }
+                        }
+                        "status" => {
+                            let state = attributes.iter()
+                                .find(|a| a.name.local_name == "state")
+                                .map(|a| a.value.as_str());
+                            result.host_up = state.map(|s| s == "up");
+                            status_reason = Self::attribute(&attributes, "reason");
+                        }
+                        "times" => {
+                            // srtt is in microseconds
+                            result.rtt_ms = attributes.iter()
+                                .find(|a| a.name.local_name == "srtt")
+                                .and_then(|a| a.value.parse::<f64>().ok())
+                                .map(|us| us / 1000.0);
+                        }
+                        // -A includes --traceroute
+                        "hop" => {
+                            let attr = |key: &str| Self::attribute(&attributes, key);
+                            let ttl = attr("ttl").and_then(|t| t.parse().ok());
+                            let ip = attr("ipaddr").and_then(|a| a.parse().ok());
+                            if let (Some(ttl), Some(ip)) = (ttl, ip) {
+                                result.trace.push(TraceHop {
+                                    ttl,
+                                    ip,
+                                    rtt_ms: attr("rtt").and_then(|r| r.parse().ok()),
+                                    hostname: attr("host"),
+                                });
+                            }
+                        }
+                        "scaninfo" => {
+                            if let Some(ports) = Self::scanned_tcp_ports(&attributes) {
+                                result.scanned_tcp_ports = Some(ports);
+                            }
+                        }
                         "osmatch" => {
                             let os = self.parse_os_element(&attributes)?;
                             result.os_detection = Some(os);
@@ -111,13 +527,82 @@ impl NmapScanner {
                         _ => {}
                     }
                 }
+                Event::EndElement { name } if name.local_name == "port" => current_port = None,
                 _ => {}
             }
         }
 
+        // nmap leaves hosts that didn't answer out of the XML entirely
+        result.host_up.get_or_insert(false);
+
+        // Under -Pn every host is "up" by user-set, and an offline one has
+        // all its ports filtered; only a host that answered something says
+        // anything about the ports nmap didn't list. With both closed and
+        // filtered ports left out it can't say which a port was.
+        let answered = status_reason.as_deref().is_some_and(|reason| reason != "user-set")
+            || result.open_ports.iter().any(|p| p.state == "open" || p.state == "closed")
+            || extraports.contains("closed");
+        if result.host_up == Some(true) && answered && !timed_out && extraports.len() == 1 {
+            result.unlisted_tcp_state = extraports.into_iter().next();
+        }
+
         Ok(result)
     }
 
+    /// The finding in a script's output when the script uses nmap's vulns
+    /// library, which prints a title under `VULNERABLE:` followed by a
+    /// `State:` line. `NOT VULNERABLE` and other scripts raise nothing.
+    pub fn nse_vulnerability(script: &str, port: Option<u16>, output: &str) -> Option<Vulnerability> {
+        let lines: Vec<&str> = output.lines().map(str::trim).collect();
+        let state = lines.iter().find_map(|line| line.strip_prefix("State:")).map(str::trim)?;
+        let severity = if state.starts_with("VULNERABLE") {
+            Severity::High
+        } else if state.starts_with("LIKELY VULNERABLE") {
+            Severity::Medium
+        } else {
+            return None;
+        };
+
+        let title = lines.iter()
+            .skip_while(|line| !line.ends_with("VULNERABLE:"))
+            .nth(1)
+            .filter(|line| !line.is_empty() && !line.starts_with("State:"))
+            .copied()
+            .unwrap_or(script);
+        let references = lines.iter()
+            .find_map(|line| line.strip_prefix("IDs:"))
+            .map(|ids| ids.split_whitespace().map(str::to_string).collect())
+            .unwrap_or_default();
+
+        Some(Vulnerability {
+            id: Uuid::new_v4().to_string(),
+            name: match port {
+                Some(port) => format!("{} (port {})", title, port),
+                None => title.to_string(),
+            },
+            severity,
+            description: format!("{} reported {}.\n{}", script, state, output.trim()),
+            cvss_score: None,
+            references,
+            port,
+            script: Some(script.to_string()),
+        })
+    }
+
+    /// The TCP ports a `<scaninfo>` element says nmap probed
+    fn scanned_tcp_ports(attributes: &[OwnedAttribute]) -> Option<String> {
+        if Self::attribute(attributes, "protocol").as_deref() != Some("tcp") {
+            return None;
+        }
+        Self::attribute(attributes, "services")
+    }
+
+    fn attribute(attributes: &[OwnedAttribute], key: &str) -> Option<String> {
+        attributes.iter()
+            .find(|a| a.name.local_name == key)
+            .map(|a| a.value.clone())
+    }
+
     fn parse_nmap_progress(&self, line: &str) -> Result<ScanProgress> {
         // Parse nmap progress output
         if line.contains("% done") {
@@ -137,7 +622,54 @@ impl NmapScanner {
     }
 }
 
-#[derive(Debug, Clone, Serialize, Deserialize)]
+/// A run of `NmapScanner` as a `Source`.
+pub enum NmapRequest {
+    Target {
+        target: ScanTarget,
+        variables: TemplateVariables,
+    },
+    /// Chunk `chunk` of a multi-host run with the options of `template`;
+    /// see `NmapScanner::scan_hosts`
+    Hosts {
+        chunk: usize,
+        template: ScanTarget,
+        variables: TemplateVariables,
+        hosts: Vec<IpAddr>,
+    },
+}
+
+impl Source for NmapScanner {
+    type Request = NmapRequest;
+
+    fn name(&self) -> &'static str {
+        "nmap"
+    }
+
+    fn start(self: Arc<Self>, run_id: Uuid, request: NmapRequest) -> BoxFuture<'static, Result<ObservationStream>> {
+        let scanner = self.clone();
+        let stream = self.runs.spawn(run_id, self.name(), move |sink| async move {
+            match request {
+                NmapRequest::Target { target, variables } => {
+                    let result = scanner.scan_target(&target, &variables, Some(sink.progress())).await?;
+                    sink.send(Observation::Host(result)).await;
+                }
+                NmapRequest::Hosts { chunk, template, variables, hosts } => {
+                    scanner.scan_hosts(run_id, chunk, &template, &variables, &hosts, &sink).await?;
+                }
+            }
+            Ok::<_, anyhow::Error>(())
+        });
+
+        Box::pin(futures::future::ready(Ok(stream)))
+    }
+
+    fn cancel(&self, run_id: Uuid) -> bool {
+        self.runs.cancel(run_id)
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, TS)]
+#[ts(export, export_to = "../src/bindings/")]
 pub struct ScanProgress {
     pub percent: f32,
     pub message: String,
@@ -0,0 +1,107 @@
+use super::*;
+use crate::error::LegionError;
+use crate::utils::ProcessManager;
+use anyhow::Result;
+use tokio::process::Command;
+
+/// Script categories nmap defines; a `--script` entry may name one
+const NSE_CATEGORIES: &[&str] = &[
+    "all", "auth", "broadcast", "brute", "default", "discovery", "dos", "exploit",
+    "external", "fuzzer", "intrusive", "malware", "safe", "version", "vuln",
+];
+
+/// An NSE script installed with the local nmap.
+#[derive(Debug, Clone, Serialize, Deserialize, TS)]
+#[ts(export, export_to = "../src/bindings/")]
+pub struct NseScript {
+    pub name: String,
+    pub categories: Vec<String>,
+    pub description: String,
+}
+
+/// Lists and checks the scripts of the local nmap installation. nmap
+/// resolves its own data directory (including NMAPDIR), so the catalog is
+/// read through `--script-help` rather than from a guessed path.
+pub struct NseCatalog;
+
+impl NseCatalog {
+    pub async fn list(nmap: &str, sandbox: &SandboxPolicy) -> Result<Vec<NseScript>> {
+        let mut cmd = Command::new(nmap);
+        cmd.args(["--script-help", "all"]);
+        let output = ProcessManager::output_sandboxed(&mut cmd, sandbox)
+            .await
+            .map_err(|e| LegionError::spawn(nmap, e))?;
+
+        if !output.status.success() {
+            return Err(anyhow::anyhow!(
+                "nmap --script-help failed: {}",
+                String::from_utf8_lossy(&output.stderr)
+            ));
+        }
+
+        Ok(Self::parse_script_help(&String::from_utf8_lossy(&output.stdout)))
+    }
+
+    /// Each script is printed as its name, a `Categories:` line, a link to
+    /// its documentation and its description indented by two spaces.
+    pub fn parse_script_help(output: &str) -> Vec<NseScript> {
+        let mut scripts: Vec<NseScript> = Vec::new();
+        let mut previous: Option<&str> = None;
+
+        for line in output.lines() {
+            if let Some(categories) = line.strip_prefix("Categories:") {
+                if let Some(name) = previous.take() {
+                    scripts.push(NseScript {
+                        name: name.trim().to_string(),
+                        categories: categories.split_whitespace().map(str::to_string).collect(),
+                        description: String::new(),
+                    });
+                }
+            } else if line.starts_with(' ') {
+                if let Some(script) = scripts.last_mut() {
+                    if !script.description.is_empty() {
+                        script.description.push(' ');
+                    }
+                    script.description.push_str(line.trim());
+                }
+            } else if !line.trim().is_empty() && !line.starts_with("https://") {
+                previous = Some(line);
+            }
+        }
+
+        scripts
+    }
+
+    /// Checks the entries of a `--script` selection that name a single
+    /// script. Categories, wildcards, paths and boolean expressions are
+    /// left to nmap.
+    pub fn check_selection(selection: &str, installed: &[NseScript]) -> Result<()> {
+        for entry in selection.split(',') {
+            let name = entry.trim().trim_start_matches('+');
+            let plain = name.chars().all(|c| c.is_ascii_alphanumeric() || c == '-' || c == '_');
+            if name.is_empty() || !plain || NSE_CATEGORIES.contains(&name) {
+                continue;
+            }
+
+            if !installed.iter().any(|s| s.name == name) {
+                return Err(LegionError::Validation(format!("NSE script {} is not installed", name)).into());
+            }
+        }
+
+        Ok(())
+    }
+
+    /// The `--script` selections in custom nmap options.
+    pub fn selections(options: &str) -> Vec<&str> {
+        let mut selections = Vec::new();
+        let mut args = options.split_whitespace();
+        while let Some(arg) = args.next() {
+            if let Some(selection) = arg.strip_prefix("--script=") {
+                selections.push(selection);
+            } else if arg == "--script" {
+                selections.extend(args.next());
+            }
+        }
+        selections
+    }
+}
\ No newline at end of file
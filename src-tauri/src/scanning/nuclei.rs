@@ -0,0 +1,134 @@
+use super::*;
+use crate::error::LegionError;
+use crate::utils::ProcessManager;
+use anyhow::Result;
+use std::sync::Arc;
+use tokio::process::Command;
+
+/// Template filters a selection may use, as `<filter>:<values>`, and the
+/// nuclei flag each maps to
+const NUCLEI_FILTERS: &[(&str, &str)] = &[
+    ("tag", "-tags"),
+    ("tags", "-tags"),
+    ("exclude-tag", "-etags"),
+    ("id", "-id"),
+    ("severity", "-severity"),
+];
+
+/// A template match nuclei reported.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct NucleiFinding {
+    pub template_id: String,
+    pub name: String,
+    pub severity: Severity,
+    /// The template's own severity, e.g. `unknown`
+    pub original_severity: Option<String>,
+    /// URL or host:port the template matched at
+    pub matched_at: String,
+    pub description: Option<String>,
+    /// e.g. `CVE-2021-44228`
+    pub cve_ids: Vec<String>,
+}
+
+pub struct NucleiScanner {
+    rate_limit: tokio::sync::Semaphore,
+    tools: Arc<ToolPaths>,
+}
+
+impl NucleiScanner {
+    pub fn new(max_concurrent: usize, tools: Arc<ToolPaths>) -> Self {
+        Self {
+            rate_limit: tokio::sync::Semaphore::new(max_concurrent),
+            tools,
+        }
+    }
+
+    /// Runs the templates a selection such as `tag:cve severity:high,critical`
+    /// picks against one URL.
+    pub async fn scan(&self, url: &str, selection: &str) -> Result<Vec<NucleiFinding>> {
+        let filters = Self::filter_args(selection)?;
+        let _permit = self.rate_limit.acquire().await?;
+
+        let nuclei = self.tools.nuclei();
+        let mut cmd = Command::new(&nuclei);
+        cmd.args(["-u", url, "-jsonl", "-silent", "-no-color", "-disable-update-check"])
+            .args(&filters);
+        let output = ProcessManager::output_sandboxed(&mut cmd, &self.tools.sandbox())
+            .await
+            .map_err(|e| LegionError::spawn(&nuclei, e))?;
+
+        if !output.status.success() {
+            return Err(anyhow::anyhow!(
+                "nuclei failed against {}: {}",
+                url,
+                String::from_utf8_lossy(&output.stderr)
+            ));
+        }
+
+        Ok(Self::parse_jsonl(&String::from_utf8_lossy(&output.stdout)))
+    }
+
+    /// Maps a selection to nuclei's filter flags. Values are limited to
+    /// plain words so a stored selection can't smuggle in other flags.
+    pub fn filter_args(selection: &str) -> Result<Vec<String>> {
+        let mut args = Vec::new();
+
+        for filter in selection.split_whitespace() {
+            let (key, values) = filter.split_once(':').ok_or_else(|| {
+                LegionError::Validation(format!("Expected <filter>:<values> in nuclei selection, got '{}'", filter))
+            })?;
+            let flag = NUCLEI_FILTERS.iter()
+                .find(|(name, _)| *name == key)
+                .map(|(_, flag)| *flag)
+                .ok_or_else(|| LegionError::Validation(format!("Unknown nuclei filter '{}'", key)))?;
+
+            let valid = !values.is_empty()
+                && !values.starts_with('-')
+                && values.chars().all(|c| c.is_ascii_alphanumeric() || matches!(c, ',' | '-' | '_' | '.'));
+            if !valid {
+                return Err(LegionError::Validation(format!("Invalid nuclei {} filter '{}'", key, values)).into());
+            }
+
+            args.push(flag.to_string());
+            args.push(values.to_string());
+        }
+
+        Ok(args)
+    }
+
+    /// `-jsonl` prints one object per match. Anything else on stdout is
+    /// skipped.
+    pub fn parse_jsonl(output: &str) -> Vec<NucleiFinding> {
+        output.lines()
+            .filter_map(|line| serde_json::from_str::<serde_json::Value>(line).ok())
+            .filter_map(|result| {
+                let info = &result["info"];
+                let template_id = result["template-id"].as_str()?.to_string();
+                let original_severity = info["severity"].as_str().map(str::to_string);
+
+                Some(NucleiFinding {
+                    name: info["name"].as_str().unwrap_or(&template_id).to_string(),
+                    severity: original_severity.as_deref()
+                        .map_or(Severity::Info, |s| SeverityNormalizer::normalize(SEVERITY_SOURCE_NUCLEI, s)),
+                    original_severity,
+                    matched_at: result["matched-at"].as_str().unwrap_or_default().to_string(),
+                    description: info["description"].as_str()
+                        .map(|d| d.trim().to_string())
+                        .filter(|d| !d.is_empty()),
+                    cve_ids: info["classification"]["cve-id"].as_array()
+                        .map(|ids| ids.iter().filter_map(|id| id.as_str()).map(str::to_uppercase).collect())
+                        .unwrap_or_default(),
+                    template_id,
+                })
+            })
+            .collect()
+    }
+
+    pub fn url_for(ip: IpAddr, port: u16, tls: bool) -> String {
+        let host = match ip {
+            IpAddr::V4(v4) => v4.to_string(),
+            IpAddr::V6(v6) => format!("[{}]", v6),
+        };
+        format!("{}://{}:{}", if tls { "https" } else { "http" }, host, port)
+    }
+}
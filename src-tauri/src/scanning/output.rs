@@ -0,0 +1,172 @@
+use super::FrontendEvent;
+use super::congestion::{CongestionMonitor, TrackedProcess};
+use crate::utils::BoundedSender;
+use serde::{Deserialize, Serialize};
+use std::collections::{HashMap, VecDeque};
+use std::sync::{Arc, Mutex, Weak};
+use std::time::Duration;
+use tokio::io::{AsyncBufReadExt, AsyncRead, BufReader};
+use tokio::task::JoinHandle;
+use uuid::Uuid;
+
+/// Lines kept per scan; older lines are dropped first
+pub const SCAN_OUTPUT_MAX_LINES: usize = 5000;
+/// Scans whose output is retained; the oldest scan's log is dropped first
+pub const SCAN_OUTPUT_MAX_SCANS: usize = 200;
+/// New lines are sent to the frontend in one batch per scan this often
+const SCAN_OUTPUT_FLUSH_INTERVAL: Duration = Duration::from_millis(250);
+
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum OutputStream {
+    Stdout,
+    Stderr,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct OutputLine {
+    /// Position in the scan's full output, counting dropped lines
+    pub line: u64,
+    pub stream: OutputStream,
+    pub text: String,
+}
+
+#[derive(Default)]
+struct OutputBuffer {
+    lines: VecDeque<OutputLine>,
+    next_line: u64,
+}
+
+#[derive(Default)]
+struct OutputBuffers {
+    scans: HashMap<Uuid, OutputBuffer>,
+    order: VecDeque<Uuid>,
+    /// Lines not yet sent to the frontend
+    pending: HashMap<Uuid, Vec<OutputLine>>,
+}
+
+/// Ring-buffered stdout/stderr of scanner processes, keyed by scan id.
+/// New lines are sent to the frontend in batched `scan-output` events, so
+/// a chatty scanner can't crowd other events out of the shared channel.
+/// Each line is also checked for reports of packet loss.
+pub struct ScanOutputLog {
+    buffers: Mutex<OutputBuffers>,
+    congestion: Arc<CongestionMonitor>,
+    events_tx: BoundedSender<FrontendEvent>,
+}
+
+impl ScanOutputLog {
+    /// Creates the log and starts sending its batches, until the log is
+    /// dropped.
+    pub fn start(events_tx: BoundedSender<FrontendEvent>, congestion: Arc<CongestionMonitor>) -> Arc<Self> {
+        let log = Arc::new(Self {
+            buffers: Mutex::new(OutputBuffers::default()),
+            congestion,
+            events_tx,
+        });
+
+        let weak: Weak<Self> = Arc::downgrade(&log);
+        tokio::spawn(async move {
+            let mut ticker = tokio::time::interval(SCAN_OUTPUT_FLUSH_INTERVAL);
+            loop {
+                ticker.tick().await;
+                let Some(log) = weak.upgrade() else {
+                    break;
+                };
+                log.flush();
+            }
+        });
+
+        log
+    }
+
+    /// Sends each scan's new lines as one `scan-output` event.
+    fn flush(&self) {
+        let pending = std::mem::take(&mut self.buffers.lock().unwrap().pending);
+
+        for (scan_id, lines) in pending {
+            let payload = serde_json::json!({ "scan_id": scan_id, "lines": lines });
+            // Never awaited so a busy frontend can't stall a scan; the event
+            // channel's overflow policy decides what is lost
+            let _ = self.events_tx.try_send(FrontendEvent { name: "scan-output", payload });
+        }
+    }
+
+    /// Registers the process writing a scan's output, so congestion can
+    /// pause it. Keep the guard until the process has exited. Pass no pid
+    /// for runs through the helper or over SSH; their loss is still
+    /// reported, but they are never paused.
+    pub fn track_process(&self, scan_id: Uuid, pid: Option<u32>) -> TrackedProcess {
+        self.congestion.track(scan_id, pid)
+    }
+
+    pub fn push(&self, scan_id: Uuid, stream: OutputStream, text: &str) {
+        {
+            let mut buffers = self.buffers.lock().unwrap();
+
+            if !buffers.scans.contains_key(&scan_id) {
+                if buffers.order.len() >= SCAN_OUTPUT_MAX_SCANS {
+                    if let Some(oldest) = buffers.order.pop_front() {
+                        buffers.scans.remove(&oldest);
+                    }
+                }
+                buffers.order.push_back(scan_id);
+            }
+
+            let buffer = buffers.scans.entry(scan_id).or_default();
+            let line = OutputLine {
+                line: buffer.next_line,
+                stream,
+                text: text.to_string(),
+            };
+            buffer.next_line += 1;
+            if buffer.lines.len() >= SCAN_OUTPUT_MAX_LINES {
+                buffer.lines.pop_front();
+            }
+            buffer.lines.push_back(line.clone());
+            buffers.pending.entry(scan_id).or_default().push(line);
+        }
+
+        if let Some(warning) = self.congestion.observe(scan_id, text) {
+            match serde_json::to_value(&warning) {
+                Ok(payload) => { let _ = self.events_tx.try_send(FrontendEvent { name: "scan-congestion", payload }); }
+                Err(e) => eprintln!("Failed to serialize scan-congestion event: {}", e),
+            }
+        }
+    }
+
+    /// Lines numbered `from_line` onwards that are still buffered.
+    pub fn lines_from(&self, scan_id: Uuid, from_line: u64) -> Vec<OutputLine> {
+        self.buffers.lock().unwrap()
+            .scans
+            .get(&scan_id)
+            .map(|buffer| {
+                buffer.lines.iter()
+                    .filter(|l| l.line >= from_line)
+                    .cloned()
+                    .collect()
+            })
+            .unwrap_or_default()
+    }
+
+    /// Forwards a process stream into the scan's log in the background and
+    /// returns everything read once the stream closes.
+    pub fn capture<R>(self: &std::sync::Arc<Self>, scan_id: Uuid, stream: OutputStream, reader: R) -> JoinHandle<String>
+    where
+        R: AsyncRead + Unpin + Send + 'static,
+    {
+        let log = self.clone();
+        tokio::spawn(async move {
+            let mut lines = BufReader::new(reader).lines();
+            let mut collected = String::new();
+
+            while let Ok(Some(line)) = lines.next_line().await {
+                log.push(scan_id, stream, &line);
+                collected.push_str(&line);
+                collected.push('\n');
+            }
+
+            collected
+        })
+    }
+}
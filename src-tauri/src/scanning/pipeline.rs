@@ -0,0 +1,402 @@
+use super::*;
+use crate::database::models::PipelineSettings;
+use crate::sniffer::SnifferEvent;
+use crate::sources::Observation;
+use crate::utils::{GeoIpLookup, GeoLocation, NetworkUtils, OuiLookup};
+use std::collections::HashMap;
+use std::collections::hash_map::DefaultHasher;
+use std::hash::{Hash, Hasher};
+use std::sync::Mutex;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::time::{Duration, Instant};
+
+/// Repeats of an observation inside this window are dropped
+const DEDUP_WINDOW: Duration = Duration::from_secs(60);
+/// Fingerprints and PTR names remembered before the oldest are evicted
+const PIPELINE_CACHE_CAPACITY: usize = 10_000;
+const RDNS_CACHE_TTL: Duration = Duration::from_secs(3600);
+
+/// The stages an observation passes through on its way to the database,
+/// in the order they run.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize, TS)]
+#[ts(export, export_to = "../src/bindings/")]
+#[serde(rename_all = "snake_case")]
+pub enum PipelineStage {
+    Normalize,
+    Dedup,
+    ReverseDns,
+    Oui,
+    GeoIp,
+    Store,
+}
+
+impl PipelineStage {
+    pub const ALL: [PipelineStage; 6] = [
+        PipelineStage::Normalize,
+        PipelineStage::Dedup,
+        PipelineStage::ReverseDns,
+        PipelineStage::Oui,
+        PipelineStage::GeoIp,
+        PipelineStage::Store,
+    ];
+
+    /// Whether the project runs this stage. Storage can't be turned off.
+    pub fn enabled(&self, settings: &PipelineSettings) -> bool {
+        match self {
+            PipelineStage::Normalize => settings.normalize,
+            PipelineStage::Dedup => settings.dedup,
+            PipelineStage::ReverseDns => settings.reverse_dns,
+            PipelineStage::Oui => settings.oui,
+            PipelineStage::GeoIp => settings.geoip,
+            PipelineStage::Store => true,
+        }
+    }
+
+    fn index(&self) -> usize {
+        *self as usize
+    }
+}
+
+/// Counters for one stage since the application started.
+#[derive(Debug, Clone, Serialize, Deserialize, TS)]
+#[ts(export, export_to = "../src/bindings/")]
+pub struct StageMetrics {
+    pub stage: PipelineStage,
+    pub processed: u64,
+    /// Observations the stage discarded, e.g. duplicates
+    pub dropped: u64,
+    /// Observations that passed while the stage was turned off
+    pub skipped: u64,
+    pub errors: u64,
+    pub total_micros: u64,
+}
+
+#[derive(Default)]
+struct StageCounters {
+    processed: AtomicU64,
+    dropped: AtomicU64,
+    skipped: AtomicU64,
+    errors: AtomicU64,
+    total_micros: AtomicU64,
+}
+
+/// What the enrichment stages learned about the host behind an
+/// observation.
+#[derive(Debug, Clone, Default)]
+pub struct Enrichment {
+    pub hostname: Option<String>,
+    pub vendor: Option<String>,
+    pub location: Option<GeoLocation>,
+}
+
+impl Enrichment {
+    pub fn is_empty(&self) -> bool {
+        self.hostname.is_none() && self.vendor.is_none() && self.location.is_none()
+    }
+}
+
+/// Normalizes, deduplicates and enriches observations before the
+/// coordinator stores them. Each stage can be turned off per project and
+/// keeps its own counters.
+pub struct IngestPipeline {
+    oui: OuiLookup,
+    geoip: GeoIpLookup,
+    ptr: PtrSweeper,
+    rdns_cache: Mutex<HashMap<IpAddr, (Option<String>, Instant)>>,
+    seen: Mutex<HashMap<u64, Instant>>,
+    counters: [StageCounters; 6],
+}
+
+impl IngestPipeline {
+    pub fn new(oui: OuiLookup, geoip: GeoIpLookup, ptr: PtrSweeper) -> Self {
+        Self {
+            oui,
+            geoip,
+            ptr,
+            rdns_cache: Mutex::new(HashMap::new()),
+            seen: Mutex::new(HashMap::new()),
+            counters: Default::default(),
+        }
+    }
+
+    /// Runs every stage but storage over `observation`, normalizing it in
+    /// place. Returns `None` when it repeats one seen within the dedup
+    /// window and needn't be stored again.
+    pub async fn process(&self, observation: &mut Observation, settings: &PipelineSettings) -> Option<Enrichment> {
+        if let Observation::Progress(_) = observation {
+            return Some(Enrichment::default());
+        }
+
+        if self.begin(PipelineStage::Normalize, settings) {
+            let started = Instant::now();
+            normalize(observation);
+            self.finish(PipelineStage::Normalize, started, false);
+        }
+
+        if self.begin(PipelineStage::Dedup, settings) {
+            let started = Instant::now();
+            let duplicate = self.is_duplicate(fingerprint(observation));
+            self.finish(PipelineStage::Dedup, started, duplicate);
+            if duplicate {
+                return None;
+            }
+        }
+
+        let (ip, mac) = subject(observation);
+        let mut enrichment = Enrichment::default();
+
+        if self.begin(PipelineStage::ReverseDns, settings) {
+            let started = Instant::now();
+            if let Some(ip) = ip.filter(|_| !is_down(observation)) {
+                enrichment.hostname = self.reverse_dns(ip).await;
+            }
+            self.finish(PipelineStage::ReverseDns, started, false);
+        }
+
+        if self.begin(PipelineStage::Oui, settings) {
+            let started = Instant::now();
+            enrichment.vendor = mac.as_deref()
+                .and_then(|mac| self.oui.lookup(mac))
+                .map(str::to_string);
+            self.finish(PipelineStage::Oui, started, false);
+        }
+
+        if self.begin(PipelineStage::GeoIp, settings) {
+            let started = Instant::now();
+            // Private ranges have no location; don't spend a lookup on them
+            enrichment.location = ip
+                .filter(|ip| !NetworkUtils::is_private_ip(ip))
+                .and_then(|ip| self.geoip.lookup(ip));
+            self.finish(PipelineStage::GeoIp, started, false);
+        }
+
+        Some(enrichment)
+    }
+
+    /// Accounts for the storage of one observation, which the coordinator
+    /// does after `process`.
+    pub fn record_store(&self, started: Instant, succeeded: bool) {
+        let counters = &self.counters[PipelineStage::Store.index()];
+        counters.processed.fetch_add(1, Ordering::Relaxed);
+        counters.total_micros.fetch_add(started.elapsed().as_micros() as u64, Ordering::Relaxed);
+        if !succeeded {
+            counters.errors.fetch_add(1, Ordering::Relaxed);
+        }
+    }
+
+    pub fn metrics(&self) -> Vec<StageMetrics> {
+        PipelineStage::ALL.iter()
+            .map(|&stage| {
+                let counters = &self.counters[stage.index()];
+                StageMetrics {
+                    stage,
+                    processed: counters.processed.load(Ordering::Relaxed),
+                    dropped: counters.dropped.load(Ordering::Relaxed),
+                    skipped: counters.skipped.load(Ordering::Relaxed),
+                    errors: counters.errors.load(Ordering::Relaxed),
+                    total_micros: counters.total_micros.load(Ordering::Relaxed),
+                }
+            })
+            .collect()
+    }
+
+    /// Counts a skip when the stage is off; returns whether to run it
+    fn begin(&self, stage: PipelineStage, settings: &PipelineSettings) -> bool {
+        let enabled = stage.enabled(settings);
+        if !enabled {
+            self.counters[stage.index()].skipped.fetch_add(1, Ordering::Relaxed);
+        }
+        enabled
+    }
+
+    fn finish(&self, stage: PipelineStage, started: Instant, dropped: bool) {
+        let counters = &self.counters[stage.index()];
+        counters.processed.fetch_add(1, Ordering::Relaxed);
+        counters.total_micros.fetch_add(started.elapsed().as_micros() as u64, Ordering::Relaxed);
+        if dropped {
+            counters.dropped.fetch_add(1, Ordering::Relaxed);
+        }
+    }
+
+    fn is_duplicate(&self, fingerprint: u64) -> bool {
+        let now = Instant::now();
+        let mut seen = self.seen.lock().unwrap();
+
+        if seen.get(&fingerprint).is_some_and(|at| now.duration_since(*at) < DEDUP_WINDOW) {
+            return true;
+        }
+        if seen.len() >= PIPELINE_CACHE_CAPACITY {
+            seen.retain(|_, at| now.duration_since(*at) < DEDUP_WINDOW);
+        }
+        seen.insert(fingerprint, now);
+        false
+    }
+
+    async fn reverse_dns(&self, ip: IpAddr) -> Option<String> {
+        let now = Instant::now();
+        if let Some((hostname, at)) = self.rdns_cache.lock().unwrap().get(&ip) {
+            if now.duration_since(*at) < RDNS_CACHE_TTL {
+                return hostname.clone();
+            }
+        }
+
+        let hostname = self.ptr.resolve(ip).await;
+
+        let mut cache = self.rdns_cache.lock().unwrap();
+        if cache.len() >= PIPELINE_CACHE_CAPACITY {
+            cache.retain(|_, (_, at)| now.duration_since(*at) < RDNS_CACHE_TTL);
+        }
+        cache.insert(ip, (hostname.clone(), now));
+        hostname
+    }
+}
+
+/// Puts addresses, MACs and service fields in one form, so the same thing
+/// reported by different tools stores and deduplicates as one.
+fn normalize(observation: &mut Observation) {
+    match observation {
+        Observation::Host(result) => {
+            result.target_ip = result.target_ip.to_canonical();
+            result.mac_address = result.mac_address.as_deref().and_then(canonical_mac);
+
+            for port in &mut result.open_ports {
+                port.protocol = port.protocol.trim().to_lowercase();
+                port.state = port.state.trim().to_lowercase();
+                port.service = non_empty(port.service.take()).map(|s| s.to_lowercase());
+                port.version = non_empty(port.version.take());
+                port.banner = non_empty(port.banner.take());
+            }
+            result.open_ports.sort_by(|a, b| (a.number, &a.protocol).cmp(&(b.number, &b.protocol)));
+            // Keep the first of each port, which carries the scanner's
+            // service data rather than a later bare listing
+            result.open_ports.dedup_by(|b, a| a.number == b.number && a.protocol == b.protocol);
+        }
+        Observation::Passive(SnifferEvent::PassiveOs { ip, .. }) => {
+            *ip = ip.to_canonical();
+        }
+        Observation::Passive(SnifferEvent::DhcpRequest(request)) => {
+            request.ip = request.ip.map(|ip| ip.to_canonical());
+            if let Some(mac) = canonical_mac(&request.mac) {
+                request.mac = mac;
+            }
+            request.hostname = non_empty(request.hostname.take()).map(|h| h.to_lowercase());
+        }
+        Observation::Passive(SnifferEvent::DnsResponse(response)) => {
+            response.client = response.client.to_canonical();
+            response.server = response.server.to_canonical();
+        }
+        Observation::Passive(SnifferEvent::Traffic { src, dst }) => {
+            *src = src.to_canonical();
+            *dst = dst.to_canonical();
+        }
+        Observation::Passive(SnifferEvent::Neighbour { ip, mac, .. }) => {
+            *ip = ip.to_canonical();
+            if let Some(canonical) = canonical_mac(mac) {
+                *mac = canonical;
+            }
+        }
+        Observation::Passive(SnifferEvent::OpenPort { ip, .. }) => {
+            *ip = ip.to_canonical();
+        }
+        Observation::Passive(SnifferEvent::TlsHello(hello)) => {
+            hello.client = hello.client.to_canonical();
+            hello.server = hello.server.to_canonical();
+        }
+        Observation::Passive(SnifferEvent::TlsServerHello(hello)) => {
+            hello.client = hello.client.to_canonical();
+            hello.server = hello.server.to_canonical();
+        }
+        Observation::Passive(SnifferEvent::HttpRequest(request)) => {
+            request.client = request.client.to_canonical();
+            request.server = request.server.to_canonical();
+        }
+        Observation::Passive(SnifferEvent::HttpResponse(response)) => {
+            response.client = response.client.to_canonical();
+            response.server = response.server.to_canonical();
+        }
+        Observation::Passive(SnifferEvent::Flows(flows)) => {
+            for flow in flows {
+                flow.client = flow.client.to_canonical();
+                flow.server = flow.server.to_canonical();
+            }
+        }
+        Observation::Passive(_) => {}
+        Observation::Wireless(device) => {
+            // Wireless devices are stored with upper-case MACs
+            if let Some(mac) = canonical_mac(&device.mac_address) {
+                device.mac_address = mac.to_uppercase();
+            }
+            device.bssid = device.bssid.as_deref()
+                .and_then(canonical_mac)
+                .map(|mac| mac.to_uppercase());
+            device.name = non_empty(device.name.take());
+        }
+        Observation::Progress(_) => {}
+    }
+}
+
+/// `aa:bb:cc:dd:ee:ff` from any common notation
+fn canonical_mac(mac: &str) -> Option<String> {
+    let hex: Vec<char> = mac.chars().filter(char::is_ascii_hexdigit).collect();
+    if hex.len() != 12 {
+        return None;
+    }
+
+    Some(hex.chunks(2)
+        .map(|octet| octet.iter().collect::<String>().to_lowercase())
+        .collect::<Vec<_>>()
+        .join(":"))
+}
+
+fn non_empty(value: Option<String>) -> Option<String> {
+    value.map(|v| v.trim().to_string()).filter(|v| !v.is_empty())
+}
+
+/// Identifies what was observed, leaving out ids, timestamps and timings
+/// that differ between otherwise identical reports.
+fn fingerprint(observation: &Observation) -> u64 {
+    let mut hasher = DefaultHasher::new();
+    match observation {
+        Observation::Host(result) => {
+            "host".hash(&mut hasher);
+            result.target_ip.hash(&mut hasher);
+            result.mac_address.hash(&mut hasher);
+            result.host_up.hash(&mut hasher);
+            format!("{:?}", result.status).hash(&mut hasher);
+            format!("{:?}", result.open_ports).hash(&mut hasher);
+            format!("{:?}", result.os_detection).hash(&mut hasher);
+            for vuln in &result.vulnerabilities {
+                vuln.name.hash(&mut hasher);
+            }
+        }
+        Observation::Passive(event) => {
+            "passive".hash(&mut hasher);
+            format!("{:?}", event).hash(&mut hasher);
+        }
+        Observation::Wireless(device) => {
+            "wireless".hash(&mut hasher);
+            format!("{:?}", device).hash(&mut hasher);
+        }
+        Observation::Progress(_) => {}
+    }
+    hasher.finish()
+}
+
+/// The address and MAC the enrichment stages look up, where the
+/// observation is about a host.
+fn subject(observation: &Observation) -> (Option<IpAddr>, Option<String>) {
+    match observation {
+        Observation::Host(result) => (Some(result.target_ip), result.mac_address.clone()),
+        Observation::Passive(SnifferEvent::PassiveOs { ip, .. }) => (Some(*ip), None),
+        Observation::Passive(SnifferEvent::DhcpRequest(request)) => (request.ip, Some(request.mac.clone())),
+        Observation::Passive(SnifferEvent::Neighbour { ip, mac, .. }) => (Some(*ip), Some(mac.clone())),
+        Observation::Passive(SnifferEvent::OpenPort { ip, .. }) => (Some(*ip), None),
+        // Wireless devices have no vendor or location columns
+        _ => (None, None),
+    }
+}
+
+/// Hosts the scanner saw down aren't worth a PTR lookup
+fn is_down(observation: &Observation) -> bool {
+    matches!(observation, Observation::Host(result) if result.host_up == Some(false))
+}
\ No newline at end of file
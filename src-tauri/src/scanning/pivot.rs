@@ -0,0 +1,172 @@
+use super::{DEVICE_CAMERA, DEVICE_NAS, DEVICE_ROUTER, EDGE_LAYER2, NODE_GATEWAY, NODE_ROUTER};
+use crate::database::models::{Host, HostIpHistory, Port, Topology};
+use crate::database::operations::{HostIpHistoryOperations, HostOperations, PortOperations, TopologyOperations};
+use crate::utils::NetworkUtils;
+use anyhow::Result;
+use serde::{Deserialize, Serialize};
+use sqlx::SqlitePool;
+use std::collections::{HashMap, HashSet};
+use std::net::IpAddr;
+use ts_rs::TS;
+
+pub const MAX_PIVOT_SCORE: f32 = 100.0;
+
+/// Addresses in more than one subnet suggest a host bridging them
+const MULTI_SUBNET_WEIGHT: f32 = 30.0;
+/// Hosts the topology graph shows forwarding traffic
+const ROUTING_NODE_WEIGHT: f32 = 25.0;
+/// Each other node the host is linked to in the topology graph
+const LINK_WEIGHT: f32 = 2.0;
+const MAX_LINK_SCORE: f32 = 15.0;
+
+/// Device types that see other hosts' traffic or can capture it
+const DEVICE_WEIGHTS: &[(&str, f32)] = &[
+    (DEVICE_ROUTER, 20.0),
+    (DEVICE_NAS, 10.0),
+    // Embedded Linux that nobody watches
+    (DEVICE_CAMERA, 5.0),
+];
+/// A general-purpose OS can run a capture tool
+const CAPTURE_OS_FAMILIES: &[&str] = &["linux", "bsd"];
+const CAPTURE_OS_WEIGHT: f32 = 5.0;
+
+/// Services that give an operator a foothold, weighted by how much
+/// control they offer
+const ACCESS_SERVICES: &[(u16, &str, &str, f32)] = &[
+    (22, "tcp", "SSH", 15.0),
+    (23, "tcp", "Telnet", 10.0),
+    (161, "udp", "SNMP", 10.0),
+];
+
+/// Prefix used to tell a host's subnets apart
+const SUBNET_PREFIX: u8 = 24;
+
+/// A host ranked as a place to pivot or monitor from.
+#[derive(Debug, Clone, Serialize, Deserialize, TS)]
+#[ts(export, export_to = "../src/bindings/")]
+pub struct PivotCandidate {
+    pub host: Host,
+    /// 0-100
+    pub score: f32,
+    /// What the score is made of, most significant first
+    pub reasons: Vec<String>,
+}
+
+/// Where a host sits in the topology graph
+#[derive(Debug, Clone, Default)]
+pub struct TopologyPosition {
+    pub routing: bool,
+    /// Distinct nodes linked to the host, segments excluded
+    pub links: usize,
+}
+
+/// Scores hosts by how useful they'd be for reaching or watching other
+/// hosts: addresses in several subnets, a routing position in the
+/// topology, device types and OSes that can capture traffic, and remote
+/// access services.
+pub struct PivotScorer;
+
+impl PivotScorer {
+    pub fn score(
+        host: &Host,
+        ports: &[Port],
+        history: &[HostIpHistory],
+        position: &TopologyPosition,
+    ) -> (f32, Vec<String>) {
+        let mut signals: Vec<(f32, String)> = Vec::new();
+
+        let subnets: HashSet<String> = history.iter()
+            .map(|entry| entry.ip.as_str())
+            .chain(std::iter::once(host.ip.as_str()))
+            .filter_map(|ip| ip.parse::<IpAddr>().ok())
+            .map(|ip| NetworkUtils::subnet_of(ip, SUBNET_PREFIX).to_string())
+            .collect();
+        if subnets.len() > 1 {
+            let mut subnets: Vec<_> = subnets.into_iter().collect();
+            subnets.sort();
+            signals.push((MULTI_SUBNET_WEIGHT, format!("Addresses in {} subnets: {}", subnets.len(), subnets.join(", "))));
+        }
+
+        if position.routing {
+            signals.push((ROUTING_NODE_WEIGHT, "Routes traffic in the topology graph".to_string()));
+        }
+        if position.links > 0 {
+            let weight = (position.links as f32 * LINK_WEIGHT).min(MAX_LINK_SCORE);
+            signals.push((weight, format!("Linked to {} other hosts", position.links)));
+        }
+
+        let device = host.device_type.as_deref()
+            .and_then(|device| DEVICE_WEIGHTS.iter().find(|(kind, _)| *kind == device));
+        if let Some((kind, weight)) = device {
+            signals.push((*weight, format!("Device type {}", kind)));
+        }
+
+        let os_family = host.os_family.as_deref().unwrap_or_default().to_lowercase();
+        if CAPTURE_OS_FAMILIES.iter().any(|family| os_family.contains(family)) {
+            signals.push((CAPTURE_OS_WEIGHT, format!("Runs {}", host.os_family.as_deref().unwrap_or_default())));
+        }
+
+        for (number, protocol, name, weight) in ACCESS_SERVICES {
+            let open = ports.iter()
+                .any(|p| p.state == "open" && p.number == *number as i32 && p.protocol == *protocol);
+            if open {
+                signals.push((*weight, format!("{} open on {}/{}", name, number, protocol)));
+            }
+        }
+
+        signals.sort_by(|a, b| b.0.total_cmp(&a.0));
+        let score = signals.iter().map(|(weight, _)| weight).sum::<f32>().min(MAX_PIVOT_SCORE);
+        let reasons = signals.into_iter().map(|(_, reason)| reason).collect();
+
+        ((score * 10.0).round() / 10.0, reasons)
+    }
+
+    /// Every host with anything to recommend it, best first.
+    pub async fn candidates(pool: &SqlitePool) -> Result<Vec<PivotCandidate>> {
+        let positions = Self::positions(&TopologyOperations::get(pool).await?);
+        let mut candidates = Vec::new();
+
+        for host in HostOperations::list_all(pool).await? {
+            let ports = PortOperations::find_open_ports(pool, &host.id).await?;
+            let history = HostIpHistoryOperations::find_by_host(pool, &host.id).await?;
+            let position = positions.get(&host.id).cloned().unwrap_or_default();
+
+            let (score, reasons) = Self::score(&host, &ports, &history, &position);
+            if score > 0.0 {
+                candidates.push(PivotCandidate { host, score, reasons });
+            }
+        }
+
+        candidates.sort_by(|a, b| b.score.total_cmp(&a.score));
+        Ok(candidates)
+    }
+
+    /// Positions of the nodes that are stored hosts, by host id
+    fn positions(topology: &Topology) -> HashMap<String, TopologyPosition> {
+        let hosts: HashMap<&str, &str> = topology.nodes.iter()
+            .filter_map(|node| Some((node.id.as_str(), node.host_id.as_deref()?)))
+            .collect();
+
+        let mut positions: HashMap<String, TopologyPosition> = HashMap::new();
+        for node in &topology.nodes {
+            let Some(host_id) = hosts.get(node.id.as_str()) else {
+                continue;
+            };
+            let links: HashSet<&str> = topology.edges.iter()
+                .filter(|edge| edge.kind != EDGE_LAYER2)
+                .filter_map(|edge| match (edge.source_id == node.id, edge.target_id == node.id) {
+                    (true, false) => Some(edge.target_id.as_str()),
+                    (false, true) => Some(edge.source_id.as_str()),
+                    _ => None,
+                })
+                .collect();
+
+            positions.insert(host_id.to_string(), TopologyPosition {
+                routing: node.kind == NODE_ROUTER || node.kind == NODE_GATEWAY,
+                links: links.len(),
+            });
+        }
+
+        positions
+    }
+}
@@ -0,0 +1,263 @@
+use super::*;
+use crate::database::models::Host;
+use crate::utils::NetworkUtils;
+use anyhow::Result;
+use cidr::IpCidr;
+use std::collections::HashMap;
+use std::path::{Path, PathBuf};
+
+/// Targets at or below this many addresses skip masscan discovery; nmap
+/// alone is quicker than the two-phase round trip.
+pub const DIRECT_NMAP_THRESHOLD: u64 = 16;
+
+/// Ports masscan sweeps in the discovery phase of a quick plan
+pub const DISCOVERY_TOP_PORTS: usize = 1000;
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum PlanTool {
+    Masscan,
+    Nmap,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct PlanPhase {
+    pub name: String,
+    pub tool: PlanTool,
+    pub description: String,
+    /// Addresses the phase starts with; nmap phases after discovery only
+    /// visit the hosts masscan found
+    pub target_count: u64,
+    pub port_count: usize,
+    /// Empty for every port, which the scanners pass as one `1-65535` range
+    #[serde(skip)]
+    pub ports: Vec<u16>,
+    /// Run against the plan targets directly rather than discovered hosts
+    pub direct: bool,
+}
+
+/// The masscan-then-nmap ("massmap") orchestration proposed for a target
+/// set, returned for review before it is executed.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct MassmapPlan {
+    pub id: Uuid,
+    pub targets: Vec<String>,
+    pub scan_type: ScanType,
+    pub options: ScanOptions,
+    pub total_addresses: u64,
+    /// Hosts in the target ranges already in the database, with their
+    /// known open ports; these are always included in service detection
+    pub known_hosts: Vec<KnownHost>,
+    pub phases: Vec<PlanPhase>,
+    pub created_at: DateTime<Utc>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct KnownHost {
+    pub ip: IpAddr,
+    pub open_ports: Vec<u16>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct MassmapProgress {
+    pub plan_id: Uuid,
+    pub phase: usize,
+    pub phase_name: String,
+    pub percent: f32,
+    pub message: String,
+}
+
+pub struct MassmapPlanner;
+
+impl MassmapPlanner {
+    pub fn plan(
+        targets: &[String],
+        scan_type: ScanType,
+        options: ScanOptions,
+        known_hosts: Vec<KnownHost>,
+    ) -> Result<MassmapPlan> {
+        if targets.is_empty() {
+            return Err(anyhow::anyhow!("No targets given"));
+        }
+
+        let mut total_addresses: u64 = 0;
+        for target in targets {
+            let network: IpCidr = target.parse()
+                .map_err(|_| anyhow::anyhow!("Invalid target: {}", target))?;
+            total_addresses = total_addresses.saturating_add(NetworkUtils::address_count(&network));
+        }
+
+        let ports = match scan_type {
+            ScanType::Comprehensive => Vec::new(),
+            ScanType::Ot => vec![S7_PORT, MODBUS_PORT, DNP3_PORT],
+            _ => TopPorts::tcp(DISCOVERY_TOP_PORTS),
+        };
+        let port_count = if ports.is_empty() { MAX_PORT_COUNT } else { ports.len() };
+
+        let mut phases = Vec::new();
+        let masscan_allowed = !matches!(scan_type, ScanType::Stealth | ScanType::Ot) && !options.nmap_only();
+
+        if total_addresses <= DIRECT_NMAP_THRESHOLD || !masscan_allowed {
+            phases.push(PlanPhase {
+                name: "Port and service scan".to_string(),
+                tool: PlanTool::Nmap,
+                description: if options.remote.is_some() {
+                    "Remote execution runs nmap on the jump box against every address".to_string()
+                } else if options.proxy.is_some() {
+                    "masscan can't run through a proxy; nmap connect-scans every address".to_string()
+                } else if masscan_allowed {
+                    "Small target set: nmap scans every address directly".to_string()
+                } else {
+                    "Stealth scans skip masscan; nmap scans every address at low timing".to_string()
+                },
+                target_count: total_addresses,
+                port_count,
+                ports,
+                direct: true,
+            });
+        } else {
+            phases.push(PlanPhase {
+                name: "Port discovery".to_string(),
+                tool: PlanTool::Masscan,
+                description: format!(
+                    "masscan sweeps {} ports across all target addresses",
+                    port_count
+                ),
+                target_count: total_addresses,
+                port_count,
+                ports,
+                direct: true,
+            });
+            phases.push(PlanPhase {
+                name: "Service detection".to_string(),
+                tool: PlanTool::Nmap,
+                description: format!(
+                    "nmap fingerprints the open ports on discovered hosts and {} known host(s)",
+                    known_hosts.len()
+                ),
+                target_count: known_hosts.len() as u64,
+                port_count: 0,
+                ports: Vec::new(),
+                direct: false,
+            });
+        }
+
+        Ok(MassmapPlan {
+            id: Uuid::new_v4(),
+            targets: targets.to_vec(),
+            scan_type,
+            options,
+            total_addresses,
+            known_hosts,
+            phases,
+            created_at: Utc::now(),
+        })
+    }
+
+    /// Splits hosts into nmap runs of at most `size`. One run scans one
+    /// port list, so only hosts with the same ports share a chunk. The
+    /// order is stable, so a resumed plan sees the same chunks.
+    pub fn chunk_hosts(hosts: &HashMap<IpAddr, Vec<u16>>, size: usize) -> Vec<(Vec<u16>, Vec<IpAddr>)> {
+        let mut hosts: Vec<(Vec<u16>, IpAddr)> = hosts.iter()
+            .map(|(ip, ports)| {
+                let mut ports = ports.clone();
+                ports.sort_unstable();
+                ports.dedup();
+                (ports, *ip)
+            })
+            .collect();
+        hosts.sort();
+
+        let mut chunks: Vec<(Vec<u16>, Vec<IpAddr>)> = Vec::new();
+        for (ports, ip) in hosts {
+            match chunks.last_mut() {
+                Some((chunk_ports, ips)) if *chunk_ports == ports && ips.len() < size => ips.push(ip),
+                _ => chunks.push((ports, vec![ip])),
+            }
+        }
+        chunks
+    }
+
+    /// Picks the stored hosts that fall inside the target ranges.
+    pub fn known_hosts_in(targets: &[String], hosts: &[(Host, Vec<u16>)]) -> Vec<KnownHost> {
+        let networks: Vec<IpCidr> = targets.iter()
+            .filter_map(|t| t.parse().ok())
+            .collect();
+
+        hosts.iter()
+            .filter_map(|(host, ports)| {
+                let ip: IpAddr = host.ip.parse().ok()?;
+                networks.iter().any(|n| n.contains(&ip)).then(|| KnownHost {
+                    ip,
+                    open_ports: ports.clone(),
+                })
+            })
+            .collect()
+    }
+}
+
+/// Progress of an executing plan, saved after every finished unit of work
+/// so an interrupted plan can be resumed. A unit is one target range in a
+/// masscan phase and one chunk of hosts in an nmap phase.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct MassmapCheckpoint {
+    pub plan: MassmapPlan,
+    /// Ports of each phase, which the plan itself doesn't serialize
+    pub phase_ports: Vec<Vec<u16>>,
+    pub phase: usize,
+    /// Units of the current phase already done
+    pub completed: Vec<usize>,
+    /// Hosts with open ports awaiting service detection
+    pub discovered: HashMap<IpAddr, Vec<u16>>,
+}
+
+impl MassmapCheckpoint {
+    pub fn new(plan: MassmapPlan) -> Self {
+        Self {
+            phase_ports: plan.phases.iter().map(|p| p.ports.clone()).collect(),
+            phase: 0,
+            completed: Vec::new(),
+            discovered: plan.known_hosts.iter()
+                .map(|h| (h.ip, h.open_ports.clone()))
+                .collect(),
+            plan,
+        }
+    }
+
+    pub fn ports(&self, phase: usize) -> &[u16] {
+        self.phase_ports.get(phase).map_or(&[], Vec::as_slice)
+    }
+
+    pub async fn load(dir: &Path, plan_id: Uuid) -> Result<Option<Self>> {
+        match tokio::fs::read(Self::path(dir, plan_id)).await {
+            Ok(data) => Ok(Some(serde_json::from_slice(&data)?)),
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => Ok(None),
+            Err(e) => Err(e.into()),
+        }
+    }
+
+    /// Written to a temporary file first so a crash mid-write leaves the
+    /// previous checkpoint intact.
+    pub async fn save(&self, dir: &Path) -> Result<()> {
+        tokio::fs::create_dir_all(dir).await?;
+        let path = Self::path(dir, self.plan.id);
+        let temp = path.with_extension("json.tmp");
+        tokio::fs::write(&temp, serde_json::to_vec(self)?).await?;
+        tokio::fs::rename(&temp, &path).await?;
+        Ok(())
+    }
+
+    pub async fn remove(dir: &Path, plan_id: Uuid) {
+        let _ = tokio::fs::remove_file(Self::path(dir, plan_id)).await;
+    }
+
+    /// Moves on to the next phase
+    pub fn advance(&mut self) {
+        self.phase += 1;
+        self.completed.clear();
+    }
+
+    fn path(dir: &Path, plan_id: Uuid) -> PathBuf {
+        dir.join(format!("{}.json", plan_id))
+    }
+}
\ No newline at end of file
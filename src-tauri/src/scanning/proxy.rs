@@ -0,0 +1,97 @@
+use crate::database::models::ProxySettings;
+use crate::utils::{sandbox, SandboxPolicy};
+use anyhow::{Result, Context, bail};
+use std::io::Write;
+use std::time::Duration;
+use tempfile::TempPath;
+use tokio::io::{AsyncReadExt, AsyncWriteExt};
+use tokio::net::TcpStream;
+
+const PROXY_CHECK_TIMEOUT: Duration = Duration::from_secs(5);
+
+pub const PROXY_KINDS: &[&str] = &["socks4", "socks5", "http"];
+pub const PROXY_MODES: &[&str] = &["nmap", "proxychains"];
+pub const PROXYCHAINS_BINARY: &str = "proxychains4";
+
+/// Only TCP connect scans survive a proxy; raw SYN, UDP and masscan
+/// traffic would leave from the local interface instead.
+pub struct ProxyRouting;
+
+impl ProxyRouting {
+    pub fn validate(proxy: &ProxySettings) -> Result<()> {
+        if !PROXY_KINDS.contains(&proxy.kind.as_str()) {
+            bail!("Unsupported proxy type: {}", proxy.kind);
+        }
+        if !PROXY_MODES.contains(&proxy.mode.as_str()) {
+            bail!("Unsupported proxy mode: {}", proxy.mode);
+        }
+        // nmap's --proxies has no SOCKS5 support
+        if proxy.mode == "nmap" && proxy.kind == "socks5" {
+            bail!("nmap --proxies does not support socks5; use proxychains mode");
+        }
+        if proxy.host.trim().is_empty() || proxy.port == 0 {
+            bail!("Proxy host and port are required");
+        }
+        // The host is written into the proxychains config line by line
+        if !proxy.host.chars().all(|c| c.is_ascii_alphanumeric() || matches!(c, '.' | '-' | ':')) {
+            bail!("Invalid proxy host: {}", proxy.host);
+        }
+        Ok(())
+    }
+
+    pub fn proxy_url(proxy: &ProxySettings) -> String {
+        format!("{}://{}:{}", proxy.kind, proxy.host, proxy.port)
+    }
+
+    /// Writes a proxychains config routing through `proxy` to a private
+    /// temporary file, readable by the sandbox account when scans run as
+    /// one. The file is deleted when the returned path is dropped.
+    pub fn write_proxychains_config(proxy: &ProxySettings, policy: &SandboxPolicy) -> Result<TempPath> {
+        Self::validate(proxy)?;
+
+        let contents = format!(
+            "strict_chain\nproxy_dns\ntcp_read_time_out 15000\ntcp_connect_time_out 8000\n\n[ProxyList]\n{} {} {}\n",
+            proxy.kind, proxy.host, proxy.port
+        );
+
+        let mut file = tempfile::Builder::new()
+            .prefix("legion2-proxychains-")
+            .suffix(".conf")
+            .tempfile()
+            .context("Failed to create the proxychains config")?;
+        file.write_all(contents.as_bytes())
+            .with_context(|| format!("Failed to write {}", file.path().display()))?;
+        sandbox::grant(file.path(), policy)
+            .with_context(|| format!("Failed to hand {} to the sandbox user", file.path().display()))?;
+
+        Ok(file.into_temp_path())
+    }
+
+    /// Confirms the proxy is reachable before any scan is routed through
+    /// it. SOCKS5 proxies are also asked to negotiate a method.
+    pub async fn check(proxy: &ProxySettings) -> Result<()> {
+        Self::validate(proxy)?;
+
+        let address = format!("{}:{}", proxy.host, proxy.port);
+        let mut stream = tokio::time::timeout(PROXY_CHECK_TIMEOUT, TcpStream::connect(&address))
+            .await
+            .map_err(|_| anyhow::anyhow!("Timed out connecting to proxy {}", address))?
+            .with_context(|| format!("Proxy {} is unreachable", address))?;
+
+        if proxy.kind == "socks5" {
+            // Greeting offering "no authentication"
+            stream.write_all(&[0x05, 0x01, 0x00]).await?;
+
+            let mut reply = [0u8; 2];
+            tokio::time::timeout(PROXY_CHECK_TIMEOUT, stream.read_exact(&mut reply))
+                .await
+                .map_err(|_| anyhow::anyhow!("Proxy {} did not answer the SOCKS5 greeting", address))??;
+
+            if reply != [0x05, 0x00] {
+                bail!("Proxy {} refused the SOCKS5 handshake", address);
+            }
+        }
+
+        Ok(())
+    }
+}
\ No newline at end of file
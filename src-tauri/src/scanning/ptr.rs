@@ -0,0 +1,114 @@
+use anyhow::Result;
+use futures::stream::{self, StreamExt};
+use serde::{Deserialize, Serialize};
+use std::net::IpAddr;
+use std::time::Duration;
+
+pub const DEFAULT_PTR_CONCURRENCY: usize = 64;
+pub const DEFAULT_PTR_TIMEOUT: Duration = Duration::from_secs(3);
+/// Largest range swept in one call; a /16
+pub const MAX_PTR_SWEEP_ADDRESSES: u64 = 65_536;
+
+/// Hostname tokens that hint at what a machine does, checked in order
+/// against the leading label with digits stripped (`db01` -> `db`).
+const HOSTNAME_ROLES: &[(&str, &str)] = &[
+    ("dc", "domain_controller"),
+    ("ad", "domain_controller"),
+    ("db", "database"),
+    ("sql", "database"),
+    ("mysql", "database"),
+    ("pg", "database"),
+    ("ora", "database"),
+    ("mail", "mail"),
+    ("mx", "mail"),
+    ("smtp", "mail"),
+    ("exch", "mail"),
+    ("fw", "firewall"),
+    ("vpn", "vpn"),
+    ("gw", "gateway"),
+    ("rtr", "router"),
+    ("sw", "switch"),
+    ("web", "web"),
+    ("www", "web"),
+    ("app", "application"),
+    ("fs", "file_server"),
+    ("nas", "file_server"),
+    ("backup", "backup"),
+    ("bkp", "backup"),
+    ("jump", "jump_host"),
+    ("bastion", "jump_host"),
+    ("vcenter", "hypervisor"),
+    ("esx", "hypervisor"),
+    ("esxi", "hypervisor"),
+    ("prn", "printer"),
+    ("printer", "printer"),
+];
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct PtrRecord {
+    pub ip: IpAddr,
+    pub hostname: String,
+    /// Role suggested by the naming convention, e.g. `database` for `db01`
+    pub role: Option<String>,
+}
+
+/// Parallel reverse-DNS lookups through the system resolver.
+pub struct PtrSweeper {
+    concurrency: usize,
+    timeout: Duration,
+}
+
+impl PtrSweeper {
+    pub fn new(concurrency: usize, timeout: Duration) -> Self {
+        Self { concurrency: concurrency.max(1), timeout }
+    }
+
+    /// Returns a record for every address with a PTR name.
+    pub async fn sweep(&self, addresses: impl Iterator<Item = IpAddr>) -> Vec<PtrRecord> {
+        let timeout = self.timeout;
+
+        let mut records: Vec<PtrRecord> = stream::iter(addresses)
+            .map(|ip| async move {
+                let hostname = Self::lookup(ip, timeout).await.ok().flatten()?;
+                Some(PtrRecord { ip, role: hostname_role(&hostname).map(str::to_string), hostname })
+            })
+            .buffer_unordered(self.concurrency)
+            .filter_map(|record| async move { record })
+            .collect()
+            .await;
+
+        records.sort_by_key(|record| record.ip);
+        records
+    }
+
+    /// The PTR name of one address, if it has one
+    pub async fn resolve(&self, ip: IpAddr) -> Option<String> {
+        Self::lookup(ip, self.timeout).await.ok().flatten()
+    }
+
+    async fn lookup(ip: IpAddr, timeout: Duration) -> Result<Option<String>> {
+        let lookup = tokio::task::spawn_blocking(move || dns_lookup::lookup_addr(&ip));
+        let Ok(name) = tokio::time::timeout(timeout, lookup).await else {
+            return Ok(None);
+        };
+
+        // getnameinfo falls back to the numeric address when there is no PTR
+        Ok(name?.ok()
+            .map(|name| name.trim_end_matches('.').to_lowercase())
+            .filter(|name| name.parse::<IpAddr>().is_err()))
+    }
+}
+
+impl Default for PtrSweeper {
+    fn default() -> Self {
+        Self::new(DEFAULT_PTR_CONCURRENCY, DEFAULT_PTR_TIMEOUT)
+    }
+}
+
+pub fn hostname_role(hostname: &str) -> Option<&'static str> {
+    let label = hostname.split('.').next()?;
+    // Conventions like `nyc-db01` or `db-prod-2` put the role in any segment
+    label.split(['-', '_'])
+        .map(|segment| segment.trim_end_matches(|c: char| c.is_ascii_digit()))
+        .find_map(|segment| HOSTNAME_ROLES.iter().find(|(token, _)| *token == segment).map(|(_, role)| *role))
+}
\ No newline at end of file
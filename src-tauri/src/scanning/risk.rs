@@ -0,0 +1,98 @@
+use crate::database::models::{Host, Port, Vulnerability};
+use crate::database::operations::{HostOperations, PortOperations, VulnerabilityOperations, FINDING_FIXED};
+use crate::utils::NetworkUtils;
+use anyhow::Result;
+use sqlx::SqlitePool;
+use std::net::IpAddr;
+
+pub const MAX_RISK_SCORE: f32 = 100.0;
+
+/// Ports whose exposure makes a host a more valuable target, weighted by
+/// how much an attacker gains from them.
+const CRITICAL_SERVICES: &[(u16, f32)] = &[
+    (23, 3.0),    // telnet
+    (88, 3.0),    // kerberos: likely a domain controller
+    (389, 2.0),   // ldap
+    (445, 2.0),   // smb
+    (623, 3.0),   // ipmi
+    (1433, 2.0),  // mssql
+    (3306, 2.0),  // mysql
+    (3389, 2.0),  // rdp
+    (5432, 2.0),  // postgres
+    (5900, 2.0),  // vnc
+    (5985, 2.0),  // winrm
+    (6379, 2.0),  // redis
+    (9200, 2.0),  // elasticsearch
+    (27017, 2.0), // mongodb
+    (22, 1.0),    // ssh
+    (502, 3.0),   // modbus
+    (102, 3.0),   // s7
+];
+const MAX_SERVICE_SCORE: f32 = 10.0;
+
+/// Findings beyond the worst one add this fraction of their weight
+const ADDITIONAL_FINDING_FACTOR: f32 = 0.25;
+const KEV_MULTIPLIER: f32 = 1.5;
+const PUBLIC_EXPOSURE_MULTIPLIER: f32 = 1.5;
+
+/// Aggregate 0-100 risk per host. Each finding weighs its severity,
+/// raised for KEV-listed issues and scaled by its EPSS probability; the
+/// worst finding counts in full and the rest partially. Critical services
+/// add to that, and the total is raised for publicly routable hosts.
+pub struct RiskScorer;
+
+impl RiskScorer {
+    pub fn score(host: &Host, ports: &[Port], vulnerabilities: &[Vulnerability]) -> f32 {
+        let mut weights: Vec<f32> = vulnerabilities.iter().map(Self::finding_weight).collect();
+        weights.sort_by(|a, b| b.total_cmp(a));
+        let finding_score = weights.first().copied().unwrap_or(0.0)
+            + weights.iter().skip(1).sum::<f32>() * ADDITIONAL_FINDING_FACTOR;
+
+        let service_score = ports.iter()
+            .filter(|p| p.state == "open")
+            .filter_map(|p| CRITICAL_SERVICES.iter().find(|(port, _)| *port as i32 == p.number))
+            .map(|(_, weight)| weight)
+            .sum::<f32>()
+            .min(MAX_SERVICE_SCORE);
+
+        let public = host.ip.parse::<IpAddr>()
+            .map(|ip| !NetworkUtils::is_private_ip(&ip))
+            .unwrap_or(false);
+        let exposure = if public { PUBLIC_EXPOSURE_MULTIPLIER } else { 1.0 };
+
+        let score = (finding_score * 3.0 + service_score * 2.0) * exposure;
+        (score.min(MAX_RISK_SCORE) * 10.0).round() / 10.0
+    }
+
+    fn finding_weight(vuln: &Vulnerability) -> f32 {
+        if vuln.status == FINDING_FIXED {
+            return 0.0;
+        }
+        let severity = match vuln.severity.as_str() {
+            "critical" => 10.0,
+            "high" => 7.0,
+            "medium" => 4.0,
+            "low" => 1.0,
+            _ => 0.0,
+        };
+        let kev = if vuln.kev { KEV_MULTIPLIER } else { 1.0 };
+        severity * kev * (1.0 + vuln.epss.unwrap_or(0.0))
+    }
+
+    /// Recomputes and stores one host's score.
+    pub async fn update_host(pool: &SqlitePool, host: &Host) -> Result<f32> {
+        let ports = PortOperations::find_open_ports(pool, &host.id).await?;
+        let vulnerabilities = VulnerabilityOperations::find_by_host(pool, &host.id).await?;
+        let score = Self::score(host, &ports, &vulnerabilities);
+
+        HostOperations::update_risk_score(pool, &host.id, score).await?;
+        Ok(score)
+    }
+
+    pub async fn update_all(pool: &SqlitePool) -> Result<()> {
+        for host in HostOperations::list_all(pool).await? {
+            Self::update_host(pool, &host).await?;
+        }
+        Ok(())
+    }
+}
\ No newline at end of file
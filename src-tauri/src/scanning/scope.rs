@@ -0,0 +1,56 @@
+use crate::database::models::ScopeEntry;
+use crate::utils::InputValidator;
+use anyhow::{Result, bail};
+use cidr::IpCidr;
+use std::net::IpAddr;
+
+pub const SCOPE_KINDS: &[&str] = &["cidr", "domain"];
+
+/// Matches scan targets against a project's declared scope. CIDR entries
+/// cover addresses; domain entries cover the hostname and its subdomains.
+pub struct ScopeGuard;
+
+impl ScopeGuard {
+    /// Classifies and normalises a scope entry as entered by the user.
+    pub fn parse_entry(entry: &str) -> Result<(String, &'static str)> {
+        let entry = entry.trim();
+
+        if let Ok(network) = entry.parse::<IpCidr>() {
+            return Ok((network.to_string(), "cidr"));
+        }
+        if let Ok(ip) = entry.parse::<IpAddr>() {
+            return Ok((IpCidr::new_host(ip).to_string(), "cidr"));
+        }
+
+        let domain = entry.trim_start_matches("*.").trim_end_matches('.').to_lowercase();
+        if domain.is_empty() {
+            bail!("Scope entry is empty");
+        }
+        InputValidator::validate_hostname(&domain)?;
+        Ok((domain, "domain"))
+    }
+
+    pub fn covers_ip(scope: &[ScopeEntry], ip: &IpAddr, hostname: Option<&str>) -> bool {
+        scope.iter().any(|entry| match entry.kind.as_str() {
+            "cidr" => entry.entry.parse::<IpCidr>().map_or(false, |n| n.contains(ip)),
+            "domain" => hostname.map_or(false, |h| Self::domain_matches(&entry.entry, h)),
+            _ => false,
+        })
+    }
+
+    /// A range is in scope only when a single CIDR entry contains all of it.
+    pub fn covers_range(scope: &[ScopeEntry], range: &IpCidr) -> bool {
+        scope.iter()
+            .filter(|entry| entry.kind == "cidr")
+            .filter_map(|entry| entry.entry.parse::<IpCidr>().ok())
+            .any(|network| {
+                network.network_length() <= range.network_length()
+                    && network.contains(&range.first_address())
+            })
+    }
+
+    fn domain_matches(domain: &str, hostname: &str) -> bool {
+        let hostname = hostname.trim_end_matches('.').to_lowercase();
+        hostname == domain || hostname.ends_with(&format!(".{}", domain))
+    }
+}
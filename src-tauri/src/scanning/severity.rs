@@ -0,0 +1,126 @@
+use super::Severity;
+use serde::{Deserialize, Serialize};
+use std::sync::{OnceLock, RwLock};
+
+/// nmap NSE scripts, rated by the vulns library's states and risk factors
+pub const SEVERITY_SOURCE_NMAP: &str = "nmap";
+pub const SEVERITY_SOURCE_NUCLEI: &str = "nuclei";
+/// Nessus risk factors, as words or the numbers 0 to 4
+pub const SEVERITY_SOURCE_NESSUS: &str = "nessus";
+pub const SEVERITY_SOURCE_MANUAL: &str = "manual";
+/// The app's own service checks, which rate on the `Severity` scale
+pub const SEVERITY_SOURCE_CHECK: &str = "check";
+
+/// Maps a value from a source's own scale onto a severity, ahead of the
+/// built-in mappings. Without a source the rule applies to every source.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct SeverityRule {
+    pub source: Option<String>,
+    pub value: String,
+    /// `info`, `low`, `medium`, `high` or `critical`
+    pub severity: String,
+}
+
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct SeverityConfig {
+    pub rules: Vec<SeverityRule>,
+}
+
+fn rules_lock() -> &'static RwLock<Vec<SeverityRule>> {
+    static RULES: OnceLock<RwLock<Vec<SeverityRule>>> = OnceLock::new();
+    RULES.get_or_init(|| RwLock::new(Vec::new()))
+}
+
+/// Replaces the configured mapping rules. Rules naming a severity outside
+/// the scale are skipped.
+pub fn configure_severity(config: &SeverityConfig) {
+    let rules = config.rules.iter()
+        .filter(|rule| match SeverityNormalizer::word(&rule.severity) {
+            Some(_) => true,
+            None => {
+                eprintln!("Ignoring severity rule for '{}': unknown severity '{}'", rule.value, rule.severity);
+                false
+            }
+        })
+        .cloned()
+        .collect();
+
+    *rules_lock().write().unwrap() = rules;
+}
+
+/// Brings the severity scales of the different finding sources onto the
+/// `Severity` enum. Findings keep the value they were given alongside.
+pub struct SeverityNormalizer;
+
+impl SeverityNormalizer {
+    /// The severity `value` stands for on `source`'s scale: a configured
+    /// rule first, then the built-in mappings. `None` when neither knows
+    /// the value.
+    pub fn map(source: &str, value: &str) -> Option<Severity> {
+        let value = value.trim();
+
+        let configured = rules_lock().read().unwrap().iter()
+            .find(|rule| {
+                rule.source.as_deref().map_or(true, |s| s.eq_ignore_ascii_case(source))
+                    && rule.value.trim().eq_ignore_ascii_case(value)
+            })
+            .and_then(|rule| Self::word(&rule.severity));
+
+        configured.or_else(|| Self::builtin(source, value))
+    }
+
+    /// As `map`, with values no mapping knows rated info.
+    pub fn normalize(source: &str, value: &str) -> Severity {
+        Self::map(source, value).unwrap_or(Severity::Info)
+    }
+
+    fn builtin(source: &str, value: &str) -> Option<Severity> {
+        let value = value.to_ascii_lowercase();
+
+        if source == SEVERITY_SOURCE_NESSUS {
+            if let Ok(level) = value.parse::<u8>() {
+                return match level {
+                    0 => Some(Severity::Info),
+                    1 => Some(Severity::Low),
+                    2 => Some(Severity::Medium),
+                    3 => Some(Severity::High),
+                    4 => Some(Severity::Critical),
+                    _ => None,
+                };
+            }
+        }
+        if source == SEVERITY_SOURCE_NMAP {
+            let state = match value.as_str() {
+                "likely vulnerable" => Some(Severity::Medium),
+                "vulnerable" | "vulnerable (dos)" => Some(Severity::High),
+                "vulnerable (exploitable)" => Some(Severity::Critical),
+                "not vulnerable" => Some(Severity::Info),
+                _ => None,
+            };
+            if state.is_some() {
+                return state;
+            }
+        }
+
+        // Other numbers are read as CVSS base scores
+        if let Ok(score) = value.parse::<f32>() {
+            return (0.0..=10.0).contains(&score).then(|| Severity::from_cvss(score));
+        }
+
+        let value = value.strip_prefix("risk factor:").map(str::trim).unwrap_or(&value);
+        Self::word(value)
+    }
+
+    /// The words most scales share
+    fn word(value: &str) -> Option<Severity> {
+        match value.trim().to_ascii_lowercase().as_str() {
+            "info" | "informational" | "information" | "none" | "log" | "unknown" => Some(Severity::Info),
+            "low" | "minor" => Some(Severity::Low),
+            "medium" | "moderate" => Some(Severity::Medium),
+            "high" | "important" | "serious" => Some(Severity::High),
+            "critical" | "urgent" | "severe" => Some(Severity::Critical),
+            _ => None,
+        }
+    }
+}
@@ -0,0 +1,449 @@
+use super::*;
+use crate::utils::ProcessManager;
+use anyhow::{Result, Context};
+use std::sync::Arc;
+use tokio::process::Command;
+use xml::reader::{EventReader, XmlEvent};
+
+pub const SMB_PORTS: [u16; 2] = [445, 139];
+/// SMB servers report their OS verbatim, so trust it nearly as much as nmap
+pub const SMB_OS_ACCURACY: f32 = 90.0;
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct SmbCredentials {
+    pub username: String,
+    pub password: String,
+    pub domain: Option<String>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct SmbShare {
+    pub name: String,
+    pub share_type: Option<String>,
+    pub comment: Option<String>,
+    pub anonymous_access: Option<String>,
+    pub user_access: Option<String>,
+}
+
+impl SmbShare {
+    pub fn is_accessible(&self) -> bool {
+        Self::access_allows(&self.anonymous_access, "READ")
+            || Self::access_allows(&self.user_access, "READ")
+    }
+
+    pub fn is_writable(&self) -> bool {
+        self.is_anonymously_writable() || Self::access_allows(&self.user_access, "WRITE")
+    }
+
+    pub fn is_anonymously_writable(&self) -> bool {
+        Self::access_allows(&self.anonymous_access, "WRITE")
+    }
+
+    fn access_allows(access: &Option<String>, right: &str) -> bool {
+        access.as_deref()
+            .map(|a| a.to_uppercase().contains(right))
+            .unwrap_or(false)
+    }
+}
+
+/// NSE scripts for SMB security checks. All are in nmap's `safe` or
+/// default-safe set: none exploit the flaw or risk crashing the host.
+const SMB_SECURITY_SCRIPTS: &str = "smb-security-mode,smb2-security-mode,smb-vuln-ms17-010,smb-double-pulsar-backdoor";
+
+/// A vulnerability reported by one of the smb-vuln scripts.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct SmbVulnerability {
+    pub script: String,
+    pub title: String,
+    /// `VULNERABLE`, `LIKELY VULNERABLE` or `NOT VULNERABLE`
+    pub state: String,
+    /// e.g. `CVE:CVE-2017-0143`
+    pub ids: Vec<String>,
+}
+
+impl SmbVulnerability {
+    pub fn is_vulnerable(&self) -> bool {
+        self.state.starts_with("VULNERABLE") || self.state.starts_with("LIKELY VULNERABLE")
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct SmbSecurityReport {
+    /// None when neither security-mode script produced output
+    pub signing_required: Option<bool>,
+    pub vulnerabilities: Vec<SmbVulnerability>,
+    /// Script output as nmap printed it, kept as evidence
+    pub script_output: String,
+}
+
+impl SmbSecurityReport {
+    pub fn findings(&self) -> Vec<(String, Severity, String)> {
+        let mut findings = Vec::new();
+
+        if self.signing_required == Some(false) {
+            findings.push((
+                "SMB signing not required".to_string(),
+                Severity::Medium,
+                "The server does not require SMB message signing, so captured authentication can be \
+                 relayed to it (NTLM relay).".to_string(),
+            ));
+        }
+
+        for vuln in self.vulnerabilities.iter().filter(|v| v.is_vulnerable()) {
+            let ids = if vuln.ids.is_empty() { String::new() } else { format!(" ({})", vuln.ids.join(", ")) };
+            findings.push((
+                vuln.title.clone(),
+                Severity::Critical,
+                format!("{} reported {}{}.", vuln.script, vuln.state, ids),
+            ));
+        }
+
+        findings
+    }
+}
+
+pub struct SmbEnumerator {
+    rate_limit: tokio::sync::Semaphore,
+    tools: Arc<ToolPaths>,
+}
+
+impl SmbEnumerator {
+    pub fn new(max_concurrent: usize, tools: Arc<ToolPaths>) -> Self {
+        Self {
+            rate_limit: tokio::sync::Semaphore::new(max_concurrent),
+            tools,
+        }
+    }
+
+    /// Enumerates shares with the smb-enum-shares NSE script. Without
+    /// credentials the script attempts a null session.
+    pub async fn enumerate_shares(
+        &self,
+        ip: IpAddr,
+        credentials: Option<&SmbCredentials>,
+    ) -> Result<Vec<SmbShare>> {
+        let _permit = self.rate_limit.acquire().await?;
+
+        let mut cmd = Command::new(self.tools.nmap());
+        cmd.args(["-Pn", "-p", "139,445", "--script", "smb-enum-shares"]);
+
+        if let Some(creds) = credentials {
+            cmd.arg("--script-args").arg(Self::format_script_args(creds));
+        }
+
+        cmd.arg("-oX").arg("-");
+        cmd.arg(ip.to_string());
+
+        let output = ProcessManager::output_sandboxed(&mut cmd, &self.tools.sandbox())
+            .await
+            .context("Failed to start nmap for SMB share enumeration")?;
+
+        if !output.status.success() {
+            return Err(anyhow::anyhow!(
+                "SMB share enumeration failed: {}",
+                String::from_utf8_lossy(&output.stderr)
+            ));
+        }
+
+        Self::parse_shares_xml(&output.stdout)
+    }
+
+    /// Reads the OS the server announces in its SMB session setup, via the
+    /// smb-os-discovery NSE script.
+    pub async fn discover_os(&self, ip: IpAddr) -> Result<Option<OsDetection>> {
+        let _permit = self.rate_limit.acquire().await?;
+
+        let mut cmd = Command::new(self.tools.nmap());
+        cmd.args(["-Pn", "-p", "139,445", "--script", "smb-os-discovery", "-oX", "-"])
+            .arg(ip.to_string());
+        let output = ProcessManager::output_sandboxed(&mut cmd, &self.tools.sandbox())
+            .await
+            .context("Failed to start nmap for SMB OS discovery")?;
+
+        if !output.status.success() {
+            return Err(anyhow::anyhow!(
+                "SMB OS discovery failed: {}",
+                String::from_utf8_lossy(&output.stderr)
+            ));
+        }
+
+        Self::parse_os_xml(&output.stdout)
+    }
+
+    /// Reads the SMB signing policy and runs the safe vulnerability
+    /// checks.
+    pub async fn check_security(&self, ip: IpAddr) -> Result<SmbSecurityReport> {
+        let _permit = self.rate_limit.acquire().await?;
+
+        let mut cmd = Command::new(self.tools.nmap());
+        cmd.args(["-Pn", "-p", "139,445", "--script", SMB_SECURITY_SCRIPTS, "-oX", "-"])
+            .arg(ip.to_string());
+        let output = ProcessManager::output_sandboxed(&mut cmd, &self.tools.sandbox())
+            .await
+            .context("Failed to start nmap for SMB security checks")?;
+
+        if !output.status.success() {
+            return Err(anyhow::anyhow!(
+                "SMB security checks failed: {}",
+                String::from_utf8_lossy(&output.stderr)
+            ));
+        }
+
+        Self::parse_security_xml(&output.stdout)
+    }
+
+    fn parse_security_xml(xml_data: &[u8]) -> Result<SmbSecurityReport> {
+        let mut signing_required: Option<bool> = None;
+        let mut vulnerabilities = Vec::new();
+        let mut script_output = Vec::new();
+
+        let mut script: Option<String> = None;
+        let mut current: Option<SmbVulnerability> = None;
+        let mut current_key: Option<String> = None;
+        let mut in_ids = false;
+        let mut depth = 0usize;
+
+        for event in EventReader::new(xml_data) {
+            match event? {
+                XmlEvent::StartElement { name, attributes, .. } => {
+                    let attr = |key: &str| attributes.iter()
+                        .find(|a| a.name.local_name == key)
+                        .map(|a| a.value.clone());
+
+                    match name.local_name.as_str() {
+                        "script" => {
+                            let id = attr("id").unwrap_or_default();
+                            let text = attr("output").unwrap_or_default();
+                            match id.as_str() {
+                                // "Message signing enabled and required" vs "...but not required"
+                                "smb2-security-mode" => {
+                                    let required = text.contains("required") && !text.contains("not required");
+                                    signing_required = Some(signing_required.unwrap_or(false) || required);
+                                }
+                                "smb-security-mode" => {
+                                    let required = text.contains("message_signing: required");
+                                    signing_required = Some(signing_required.unwrap_or(false) || required);
+                                }
+                                _ => {}
+                            }
+                            script_output.push(format!("{}:\n{}", id, text.trim_end()));
+                            script = Some(id);
+                            depth = 0;
+                        }
+                        "table" if script.is_some() => {
+                            depth += 1;
+                            // smb-vuln scripts put each vulnerability in a
+                            // top-level table keyed by its primary ID
+                            if depth == 1 && script.as_deref() != Some("smb-security-mode") {
+                                current = Some(SmbVulnerability {
+                                    script: script.clone().unwrap_or_default(),
+                                    title: attr("key").unwrap_or_default(),
+                                    state: String::new(),
+                                    ids: Vec::new(),
+                                });
+                            } else if depth == 2 && attr("key").as_deref() == Some("ids") {
+                                in_ids = true;
+                            }
+                        }
+                        "elem" if current.is_some() => current_key = attr("key"),
+                        _ => {}
+                    }
+                }
+                XmlEvent::Characters(text) => {
+                    if let Some(vuln) = current.as_mut() {
+                        let value = text.trim().to_string();
+                        match current_key.as_deref() {
+                            Some("title") if depth == 1 => vuln.title = value,
+                            Some("state") if depth == 1 => vuln.state = value,
+                            _ if in_ids => vuln.ids.push(value),
+                            _ => {}
+                        }
+                    }
+                }
+                XmlEvent::EndElement { name } => {
+                    match name.local_name.as_str() {
+                        "elem" => current_key = None,
+                        "table" if script.is_some() => {
+                            if depth == 2 {
+                                in_ids = false;
+                            }
+                            if depth == 1 {
+                                if let Some(vuln) = current.take().filter(|v| !v.state.is_empty()) {
+                                    vulnerabilities.push(vuln);
+                                }
+                            }
+                            depth = depth.saturating_sub(1);
+                        }
+                        "script" => script = None,
+                        _ => {}
+                    }
+                }
+                _ => {}
+            }
+        }
+
+        Ok(SmbSecurityReport {
+            signing_required,
+            vulnerabilities,
+            script_output: script_output.join("\n\n"),
+        })
+    }
+
+    fn parse_os_xml(xml_data: &[u8]) -> Result<Option<OsDetection>> {
+        let mut in_script = false;
+        let mut current_key: Option<String> = None;
+        let mut os: Option<String> = None;
+        let mut lanmanager: Option<String> = None;
+
+        for event in EventReader::new(xml_data) {
+            match event? {
+                XmlEvent::StartElement { name, attributes, .. } => {
+                    let attr = |key: &str| attributes.iter()
+                        .find(|a| a.name.local_name == key)
+                        .map(|a| a.value.clone());
+
+                    match name.local_name.as_str() {
+                        "script" if attr("id").as_deref() == Some("smb-os-discovery") => in_script = true,
+                        "elem" if in_script => current_key = attr("key"),
+                        _ => {}
+                    }
+                }
+                XmlEvent::Characters(text) => {
+                    let value = Some(text.trim().to_string());
+                    match current_key.as_deref() {
+                        Some("os") => os = value,
+                        Some("lanmanager") => lanmanager = value,
+                        _ => {}
+                    }
+                }
+                XmlEvent::EndElement { name } => {
+                    match name.local_name.as_str() {
+                        "elem" => current_key = None,
+                        "script" => in_script = false,
+                        _ => {}
+                    }
+                }
+                _ => {}
+            }
+        }
+
+        let Some(os) = os.filter(|o| !o.is_empty()) else {
+            return Ok(None);
+        };
+
+        // Samba reports the host's own OS name, often just "Unix"
+        let is_samba = lanmanager.map(|l| l.contains("Samba")).unwrap_or(false);
+        let family = if os.contains("Windows") {
+            "Windows"
+        } else if os.contains("Linux") {
+            "Linux"
+        } else if is_samba || os.contains("Unix") {
+            "Unix"
+        } else {
+            os.split_whitespace().next().unwrap_or("Unknown")
+        };
+
+        Ok(Some(OsDetection {
+            family: family.to_string(),
+            vendor: if family == "Windows" { "Microsoft".to_string() } else { String::new() },
+            name: os,
+            accuracy: SMB_OS_ACCURACY,
+        }))
+    }
+
+    fn format_script_args(creds: &SmbCredentials) -> String {
+        let mut args = vec![
+            format!("smbusername={}", Self::quote_arg(&creds.username)),
+            format!("smbpassword={}", Self::quote_arg(&creds.password)),
+        ];
+
+        if let Some(domain) = &creds.domain {
+            args.push(format!("smbdomain={}", Self::quote_arg(domain)));
+        }
+
+        args.join(",")
+    }
+
+    fn quote_arg(value: &str) -> String {
+        format!("\"{}\"", value.replace('\\', "\\\\").replace('"', "\\\""))
+    }
+
+    fn parse_shares_xml(xml_data: &[u8]) -> Result<Vec<SmbShare>> {
+        let mut shares = Vec::new();
+        let mut in_script = false;
+        let mut current: Option<SmbShare> = None;
+        let mut current_key: Option<String> = None;
+        let mut depth = 0usize;
+
+        for event in EventReader::new(xml_data) {
+            match event? {
+                XmlEvent::StartElement { name, attributes, .. } => {
+                    let attr = |key: &str| attributes.iter()
+                        .find(|a| a.name.local_name == key)
+                        .map(|a| a.value.clone());
+
+                    match name.local_name.as_str() {
+                        "script" if attr("id").as_deref() == Some("smb-enum-shares") => {
+                            in_script = true;
+                            depth = 0;
+                        }
+                        "table" if in_script => {
+                            depth += 1;
+                            // Each share is a top-level table keyed by its UNC path
+                            if depth == 1 {
+                                if let Some(key) = attr("key") {
+                                    current = Some(SmbShare {
+                                        name: Self::share_name(&key),
+                                        share_type: None,
+                                        comment: None,
+                                        anonymous_access: None,
+                                        user_access: None,
+                                    });
+                                }
+                            }
+                        }
+                        "elem" if current.is_some() => {
+                            current_key = attr("key");
+                        }
+                        _ => {}
+                    }
+                }
+                XmlEvent::Characters(text) => {
+                    if let (Some(share), Some(key)) = (current.as_mut(), current_key.as_deref()) {
+                        let value = Some(text.trim().to_string());
+                        match key {
+                            "Type" => share.share_type = value,
+                            "Comment" => share.comment = value,
+                            "Anonymous access" => share.anonymous_access = value,
+                            "Current user access" => share.user_access = value,
+                            _ => {}
+                        }
+                    }
+                }
+                XmlEvent::EndElement { name } => {
+                    match name.local_name.as_str() {
+                        "elem" => current_key = None,
+                        "table" if in_script => {
+                            if depth == 1 {
+                                if let Some(share) = current.take() {
+                                    shares.push(share);
+                                }
+                            }
+                            depth = depth.saturating_sub(1);
+                        }
+                        "script" => in_script = false,
+                        _ => {}
+                    }
+                }
+                _ => {}
+            }
+        }
+
+        Ok(shares)
+    }
+
+    fn share_name(key: &str) -> String {
+        // Keys look like \\10.0.0.5\ADMIN$
+        key.rsplit('\\').next().unwrap_or(key).to_string()
+    }
+}
@@ -0,0 +1,139 @@
+use super::Severity;
+use anyhow::{Result, anyhow, bail};
+use serde::{Deserialize, Serialize};
+use std::net::{IpAddr, SocketAddr};
+use std::time::Duration;
+use tokio::io::{AsyncBufReadExt, AsyncWriteExt, BufReader};
+use tokio::net::TcpStream;
+use tokio::net::tcp::{OwnedReadHalf, OwnedWriteHalf};
+
+pub const SMTP_PORTS: &[u16] = &[25, 587];
+pub const DEFAULT_SMTP_TIMEOUT: Duration = Duration::from_secs(10);
+
+const HELO_NAME: &str = "legion2.example.com";
+const TEST_SENDER: &str = "relay-test@example.com";
+/// A reserved domain, so an accepted recipient can never be delivered to
+const TEST_RECIPIENT: &str = "relay-test@example.org";
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct SmtpRelayResult {
+    pub port: u16,
+    pub banner: String,
+    /// Whether the server accepted a recipient in an external domain
+    pub open_relay: bool,
+    /// Every command and reply, prefixed `C:` and `S:`
+    pub transcript: String,
+}
+
+impl SmtpRelayResult {
+    pub fn finding(&self) -> Option<(String, Severity, String)> {
+        self.open_relay.then(|| (
+            format!("Open SMTP relay (port {})", self.port),
+            Severity::High,
+            format!(
+                "The mail server accepted a message from {} to {} without authentication, so it can be \
+                 used to send spam or spoofed mail to any domain. No message was sent: the test stopped \
+                 before DATA.",
+                TEST_SENDER, TEST_RECIPIENT
+            ),
+        ))
+    }
+}
+
+/// Non-destructive relay test: the envelope is offered up to RCPT TO and
+/// then reset, so no mail is ever sent.
+pub struct SmtpRelayTester {
+    timeout: Duration,
+}
+
+impl SmtpRelayTester {
+    pub fn new(timeout: Duration) -> Self {
+        Self { timeout }
+    }
+
+    pub async fn test(&self, ip: IpAddr, port: u16) -> Result<SmtpRelayResult> {
+        tokio::time::timeout(self.timeout * 2, self.session(ip, port))
+            .await
+            .map_err(|_| anyhow!("SMTP session to {}:{} timed out", ip, port))?
+    }
+
+    async fn session(&self, ip: IpAddr, port: u16) -> Result<SmtpRelayResult> {
+        let stream = tokio::time::timeout(self.timeout, TcpStream::connect(SocketAddr::new(ip, port)))
+            .await
+            .map_err(|_| anyhow!("Connection to {}:{} timed out", ip, port))??;
+        let (reader, writer) = stream.into_split();
+        let mut session = SmtpSession {
+            reader: BufReader::new(reader),
+            writer,
+            transcript: Vec::new(),
+        };
+
+        let (code, banner) = session.read_reply().await?;
+        if code != 220 {
+            bail!("Unexpected SMTP greeting: {} {}", code, banner);
+        }
+
+        let (code, _) = session.command(&format!("EHLO {}", HELO_NAME)).await?;
+        if code != 250 {
+            session.command(&format!("HELO {}", HELO_NAME)).await?;
+        }
+
+        let (code, _) = session.command(&format!("MAIL FROM:<{}>", TEST_SENDER)).await?;
+        let open_relay = if code == 250 {
+            let (code, _) = session.command(&format!("RCPT TO:<{}>", TEST_RECIPIENT)).await?;
+            code == 250 || code == 251
+        } else {
+            false
+        };
+
+        let _ = session.command("RSET").await;
+        let _ = session.command("QUIT").await;
+
+        Ok(SmtpRelayResult {
+            port,
+            banner,
+            open_relay,
+            transcript: session.transcript.join("\n"),
+        })
+    }
+}
+
+impl Default for SmtpRelayTester {
+    fn default() -> Self {
+        Self::new(DEFAULT_SMTP_TIMEOUT)
+    }
+}
+
+struct SmtpSession {
+    reader: BufReader<OwnedReadHalf>,
+    writer: OwnedWriteHalf,
+    transcript: Vec<String>,
+}
+
+impl SmtpSession {
+    async fn command(&mut self, line: &str) -> Result<(u16, String)> {
+        self.transcript.push(format!("C: {}", line));
+        self.writer.write_all(format!("{}\r\n", line).as_bytes()).await?;
+        self.read_reply().await
+    }
+
+    /// Reads one reply, following `250-` continuation lines.
+    async fn read_reply(&mut self) -> Result<(u16, String)> {
+        let mut text = Vec::new();
+        loop {
+            let mut line = String::new();
+            if self.reader.read_line(&mut line).await? == 0 {
+                bail!("SMTP connection closed");
+            }
+            let line = line.trim_end();
+            self.transcript.push(format!("S: {}", line));
+            text.push(line.get(4..).unwrap_or("").to_string());
+
+            if line.len() < 4 || line.as_bytes()[3] == b' ' {
+                let code = line.get(..3).and_then(|c| c.parse().ok())
+                    .ok_or_else(|| anyhow!("Malformed SMTP reply: {}", line))?;
+                return Ok((code, text.join("\n")));
+            }
+        }
+    }
+}
\ No newline at end of file
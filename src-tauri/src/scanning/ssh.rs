@@ -0,0 +1,304 @@
+use super::Severity;
+use anyhow::{Result, anyhow, bail};
+use serde::{Deserialize, Serialize};
+use std::net::{IpAddr, SocketAddr};
+use std::time::Duration;
+use tokio::io::{AsyncBufReadExt, AsyncReadExt, AsyncWriteExt, BufReader};
+use tokio::net::TcpStream;
+
+pub const SSH_PORT: u16 = 22;
+pub const DEFAULT_SSH_AUDIT_TIMEOUT: Duration = Duration::from_secs(5);
+/// RSA and DSA host keys below this size are flagged
+pub const MIN_HOST_KEY_BITS: u32 = 2048;
+
+const CLIENT_VERSION: &str = "SSH-2.0-LEGION2_audit";
+const MAX_PACKET_LEN: usize = 256 * 1024;
+
+const MSG_KEXINIT: u8 = 20;
+const MSG_KEX_ECDH_INIT: u8 = 30;
+const MSG_KEX_ECDH_REPLY: u8 = 31;
+
+/// The only key exchange we offer: any 32 bytes are a valid X25519 public
+/// value, so the server's reply (and its host key) arrives in the clear
+/// without us doing any cryptography.
+const AUDIT_KEX: &str = "curve25519-sha256,curve25519-sha256@libssh.org";
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct SshAuditReport {
+    pub banner: String,
+    pub kex_algorithms: Vec<String>,
+    pub host_key_algorithms: Vec<String>,
+    pub ciphers: Vec<String>,
+    pub macs: Vec<String>,
+    pub host_key_type: Option<String>,
+    pub host_key_bits: Option<u32>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct SshFinding {
+    pub name: String,
+    pub severity: Severity,
+    pub description: String,
+}
+
+impl SshAuditReport {
+    pub fn findings(&self) -> Vec<SshFinding> {
+        let mut findings = Vec::new();
+
+        let cbc: Vec<&str> = self.ciphers.iter()
+            .map(String::as_str)
+            .filter(|c| c.ends_with("-cbc") || *c == "rijndael-cbc@lysator.liu.se")
+            .collect();
+        if !cbc.is_empty() {
+            findings.push(SshFinding {
+                name: "SSH CBC mode ciphers enabled".to_string(),
+                severity: Severity::Low,
+                description: format!(
+                    "The server accepts CBC mode ciphers, which are open to plaintext-recovery attacks: {}.",
+                    cbc.join(", ")
+                ),
+            });
+        }
+
+        let sha1_kex: Vec<&str> = self.kex_algorithms.iter()
+            .map(String::as_str)
+            .filter(|k| k.contains("sha1"))
+            .collect();
+        if !sha1_kex.is_empty() {
+            findings.push(SshFinding {
+                name: "SSH SHA-1 key exchange enabled".to_string(),
+                severity: Severity::Medium,
+                description: format!(
+                    "The server accepts key exchange methods built on SHA-1: {}.",
+                    sha1_kex.join(", ")
+                ),
+            });
+        }
+
+        if let (Some(key_type), Some(bits)) = (&self.host_key_type, self.host_key_bits) {
+            let sized = key_type == "ssh-rsa" || key_type == "ssh-dss";
+            if sized && bits < MIN_HOST_KEY_BITS {
+                findings.push(SshFinding {
+                    name: "SSH small host key".to_string(),
+                    severity: if bits < 1024 { Severity::High } else { Severity::Medium },
+                    description: format!(
+                        "The server's {} host key is {} bits; at least {} bits are expected.",
+                        key_type, bits, MIN_HOST_KEY_BITS
+                    ),
+                });
+            }
+        }
+
+        findings
+    }
+}
+
+/// Native ssh-audit style check: negotiates far enough into the key
+/// exchange to read the server's algorithm lists and host key, then hangs
+/// up before authentication.
+pub struct SshAuditor {
+    timeout: Duration,
+}
+
+impl SshAuditor {
+    pub fn new(timeout: Duration) -> Self {
+        Self { timeout }
+    }
+
+    pub async fn audit(&self, ip: IpAddr, port: u16) -> Result<SshAuditReport> {
+        tokio::time::timeout(self.timeout, Self::negotiate(SocketAddr::new(ip, port)))
+            .await
+            .map_err(|_| anyhow!("SSH negotiation with {}:{} timed out", ip, port))?
+    }
+
+    async fn negotiate(addr: SocketAddr) -> Result<SshAuditReport> {
+        let stream = TcpStream::connect(addr).await?;
+        let mut stream = BufReader::new(stream);
+
+        // Servers may send other lines before the version string
+        let banner = loop {
+            let mut line = String::new();
+            if stream.read_line(&mut line).await? == 0 {
+                bail!("Connection closed before the SSH version string");
+            }
+            if line.starts_with("SSH-") {
+                break line.trim_end().to_string();
+            }
+        };
+
+        stream.get_mut().write_all(format!("{}\r\n", CLIENT_VERSION).as_bytes()).await?;
+
+        let server_kexinit = read_packet(&mut stream).await?;
+        if server_kexinit.first() != Some(&MSG_KEXINIT) {
+            bail!("Expected KEXINIT from server");
+        }
+        let lists = parse_kexinit(&server_kexinit)?;
+
+        let mut report = SshAuditReport {
+            banner,
+            kex_algorithms: lists[0].clone(),
+            host_key_algorithms: lists[1].clone(),
+            ciphers: union(&lists[2], &lists[3]),
+            macs: union(&lists[4], &lists[5]),
+            host_key_type: None,
+            host_key_bits: None,
+        };
+
+        // Without X25519 support the algorithm lists are all we can report
+        if !report.kex_algorithms.iter().any(|k| AUDIT_KEX.split(',').any(|a| a == k)) {
+            return Ok(report);
+        }
+
+        // Ask for an RSA key when available since its size is what varies
+        let host_key_alg = report.host_key_algorithms.iter()
+            .find(|a| a.starts_with("rsa-sha2") || *a == "ssh-rsa")
+            .or_else(|| report.host_key_algorithms.first())
+            .cloned()
+            .ok_or_else(|| anyhow!("Server offered no host key algorithms"))?;
+
+        let client_kexinit = build_kexinit(&host_key_alg, &report.ciphers, &report.macs);
+        write_packet(stream.get_mut(), &client_kexinit).await?;
+
+        let mut ecdh_init = vec![MSG_KEX_ECDH_INIT];
+        let mut ephemeral = uuid::Uuid::new_v4().as_bytes().to_vec();
+        ephemeral.extend_from_slice(uuid::Uuid::new_v4().as_bytes());
+        put_string(&mut ecdh_init, &ephemeral);
+        write_packet(stream.get_mut(), &ecdh_init).await?;
+
+        let reply = read_packet(&mut stream).await?;
+        if reply.first() != Some(&MSG_KEX_ECDH_REPLY) {
+            return Ok(report);
+        }
+
+        let mut reader = WireReader::new(&reply[1..]);
+        let host_key = reader.string()?;
+        let (key_type, bits) = host_key_size(host_key)?;
+        report.host_key_type = Some(key_type);
+        report.host_key_bits = bits;
+
+        Ok(report)
+    }
+}
+
+impl Default for SshAuditor {
+    fn default() -> Self {
+        Self::new(DEFAULT_SSH_AUDIT_TIMEOUT)
+    }
+}
+
+fn union(a: &[String], b: &[String]) -> Vec<String> {
+    let mut merged = a.to_vec();
+    merged.extend(b.iter().filter(|x| !a.contains(x)).cloned());
+    merged
+}
+
+/// Returns the ten name-lists of a KEXINIT payload.
+fn parse_kexinit(payload: &[u8]) -> Result<Vec<Vec<String>>> {
+    let mut reader = WireReader::new(payload.get(17..).ok_or_else(|| anyhow!("Truncated KEXINIT"))?);
+
+    (0..10)
+        .map(|_| {
+            let list = std::str::from_utf8(reader.string()?)?;
+            Ok(list.split(',').filter(|s| !s.is_empty()).map(str::to_string).collect())
+        })
+        .collect()
+}
+
+fn build_kexinit(host_key_alg: &str, ciphers: &[String], macs: &[String]) -> Vec<u8> {
+    let mut payload = vec![MSG_KEXINIT];
+    payload.extend_from_slice(uuid::Uuid::new_v4().as_bytes());
+
+    // Echo the server's own lists so negotiation cannot fail before the reply
+    let ciphers = ciphers.join(",");
+    let macs = macs.join(",");
+    for list in [AUDIT_KEX, host_key_alg, &ciphers, &ciphers, &macs, &macs, "none", "none", "", ""] {
+        put_string(&mut payload, list.as_bytes());
+    }
+    payload.push(0); // first_kex_packet_follows
+    payload.extend_from_slice(&[0, 0, 0, 0]);
+    payload
+}
+
+/// Key type and, where it varies, size in bits of an SSH public key blob.
+fn host_key_size(blob: &[u8]) -> Result<(String, Option<u32>)> {
+    let mut reader = WireReader::new(blob);
+    let key_type = std::str::from_utf8(reader.string()?)?.to_string();
+
+    let bits = match key_type.as_str() {
+        "ssh-rsa" => {
+            let _exponent = reader.string()?;
+            Some(mpint_bits(reader.string()?))
+        }
+        "ssh-dss" => Some(mpint_bits(reader.string()?)),
+        "ssh-ed25519" => Some(256),
+        t if t.starts_with("ecdsa-sha2-nistp") => t["ecdsa-sha2-nistp".len()..].parse().ok(),
+        _ => None,
+    };
+
+    Ok((key_type, bits))
+}
+
+fn mpint_bits(mpint: &[u8]) -> u32 {
+    let significant: Vec<u8> = mpint.iter().copied().skip_while(|&b| b == 0).collect();
+    match significant.first() {
+        Some(&top) => (significant.len() as u32 - 1) * 8 + (8 - top.leading_zeros()),
+        None => 0,
+    }
+}
+
+async fn read_packet(stream: &mut BufReader<TcpStream>) -> Result<Vec<u8>> {
+    let mut header = [0u8; 5];
+    stream.read_exact(&mut header).await?;
+
+    let packet_len = u32::from_be_bytes([header[0], header[1], header[2], header[3]]) as usize;
+    let padding_len = header[4] as usize;
+    if packet_len > MAX_PACKET_LEN || packet_len < padding_len + 1 {
+        bail!("Malformed SSH packet length {}", packet_len);
+    }
+
+    let mut body = vec![0u8; packet_len - 1];
+    stream.read_exact(&mut body).await?;
+    body.truncate(packet_len - 1 - padding_len);
+    Ok(body)
+}
+
+async fn write_packet(stream: &mut TcpStream, payload: &[u8]) -> Result<()> {
+    // Length, padding length, payload and padding must be a multiple of 8,
+    // with at least 4 bytes of padding
+    let mut padding = 8 - (payload.len() + 5) % 8;
+    if padding < 4 {
+        padding += 8;
+    }
+
+    let mut packet = Vec::with_capacity(payload.len() + padding + 5);
+    packet.extend_from_slice(&((payload.len() + padding + 1) as u32).to_be_bytes());
+    packet.push(padding as u8);
+    packet.extend_from_slice(payload);
+    packet.extend(std::iter::repeat(0).take(padding));
+
+    stream.write_all(&packet).await?;
+    Ok(())
+}
+
+fn put_string(buf: &mut Vec<u8>, data: &[u8]) {
+    buf.extend_from_slice(&(data.len() as u32).to_be_bytes());
+    buf.extend_from_slice(data);
+}
+
+struct WireReader<'a> {
+    data: &'a [u8],
+}
+
+impl<'a> WireReader<'a> {
+    fn new(data: &'a [u8]) -> Self {
+        Self { data }
+    }
+
+    fn string(&mut self) -> Result<&'a [u8]> {
+        let len_bytes = self.data.get(..4).ok_or_else(|| anyhow!("Truncated SSH field"))?;
+        let len = u32::from_be_bytes([len_bytes[0], len_bytes[1], len_bytes[2], len_bytes[3]]) as usize;
+        let value = self.data.get(4..4 + len).ok_or_else(|| anyhow!("Truncated SSH field"))?;
+        self.data = &self.data[4 + len..];
+        Ok(value)
+    }
+}
\ No newline at end of file
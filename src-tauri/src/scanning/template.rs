@@ -0,0 +1,122 @@
+use super::*;
+use crate::error::LegionError;
+use crate::utils::InputValidator;
+use anyhow::Result;
+use std::path::PathBuf;
+
+/// Placeholders a custom scan's options may use
+pub const TEMPLATE_VARIABLES: &[&str] = &["target", "open_ports", "hostname", "output_dir"];
+
+/// Values for the placeholders of one custom scan, resolved when it runs.
+/// A placeholder whose value is unknown fails the scan rather than
+/// expanding to nothing.
+#[derive(Debug, Clone, Default)]
+pub struct TemplateVariables {
+    pub target: Option<IpAddr>,
+    pub hostname: Option<String>,
+    /// Ports already known to be open on the target, in nmap's `-p` syntax
+    pub open_ports: Option<String>,
+    /// Local directory the scan may write output files to
+    pub output_dir: Option<PathBuf>,
+}
+
+impl TemplateVariables {
+    /// `T:`/`U:` prefixes are only needed once UDP ports are in the list
+    pub fn port_list(ports: &[(u16, &str)]) -> Option<String> {
+        if ports.is_empty() {
+            return None;
+        }
+        if ports.iter().all(|(_, protocol)| *protocol != "udp") {
+            return Some(ports.iter().map(|(number, _)| number.to_string()).collect::<Vec<_>>().join(","));
+        }
+
+        let list = |wanted: bool| ports.iter()
+            .filter(|(_, protocol)| (*protocol == "udp") == wanted)
+            .map(|(number, _)| number.to_string())
+            .collect::<Vec<_>>()
+            .join(",");
+        let (tcp, udp) = (list(false), list(true));
+        Some(match tcp.is_empty() {
+            true => format!("U:{}", udp),
+            false => format!("T:{},U:{}", tcp, udp),
+        })
+    }
+
+    fn value(&self, name: &str) -> Result<String> {
+        let value = match name {
+            "target" => self.target.map(|ip| ip.to_string()),
+            "hostname" => self.hostname.clone(),
+            "open_ports" => self.open_ports.clone(),
+            "output_dir" => self.output_dir.as_ref()
+                .map(|dir| dir.to_str()
+                    .map(str::to_string)
+                    .ok_or_else(|| anyhow::anyhow!("Output directory {} is not valid UTF-8", dir.display())))
+                .transpose()?,
+            _ => return Err(LegionError::Validation(format!("Unknown placeholder {{{}}}", name)).into()),
+        };
+
+        value.ok_or_else(|| LegionError::Validation(format!("No value for {{{}}} in this scan", name)).into())
+    }
+}
+
+/// Custom scan options with `{name}` placeholders.
+pub struct ScanTemplate;
+
+impl ScanTemplate {
+    /// Rejects unknown and unterminated placeholders before a scan is
+    /// queued.
+    pub fn validate(template: &str) -> Result<()> {
+        for arg in template.split_whitespace() {
+            Self::placeholders(arg)?;
+        }
+        Ok(())
+    }
+
+    /// Splits the options into arguments before substituting, so a value
+    /// only ever lands inside the argument its placeholder was in, spaces
+    /// and all.
+    pub fn expand(template: &str, variables: &TemplateVariables) -> Result<Vec<String>> {
+        let mut args = Vec::new();
+
+        for arg in template.split_whitespace() {
+            let mut expanded = String::with_capacity(arg.len());
+            let mut last = 0;
+            for (start, end, name) in Self::placeholders(arg)? {
+                let value = variables.value(name)?;
+                if name == "hostname" {
+                    InputValidator::validate_hostname(&value)?;
+                }
+                expanded.push_str(&arg[last..start]);
+                expanded.push_str(&value);
+                last = end;
+            }
+            expanded.push_str(&arg[last..]);
+            args.push(expanded);
+        }
+
+        Ok(args)
+    }
+
+    /// Byte range and name of each `{name}` in one argument
+    fn placeholders(arg: &str) -> Result<Vec<(usize, usize, &str)>> {
+        let mut found = Vec::new();
+        let mut from = 0;
+
+        while let Some(open) = arg[from..].find('{').map(|i| i + from) {
+            let close = arg[open..].find('}').map(|i| i + open)
+                .ok_or_else(|| LegionError::Validation(format!("Unterminated placeholder in '{}'", arg)))?;
+            let name = &arg[open + 1..close];
+            if !TEMPLATE_VARIABLES.contains(&name) {
+                return Err(LegionError::Validation(format!(
+                    "Unknown placeholder {{{}}}; expected one of {}",
+                    name,
+                    TEMPLATE_VARIABLES.join(", "),
+                )).into());
+            }
+            found.push((open, close + 1, name));
+            from = close + 1;
+        }
+
+        Ok(found)
+    }
+}
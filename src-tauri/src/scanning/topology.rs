@@ -0,0 +1,78 @@
+use super::*;
+use crate::database::operations::TopologyOperations;
+use crate::utils::NetworkUtils;
+use anyhow::Result;
+use sqlx::SqlitePool;
+
+pub const NODE_HOST: &str = "host";
+/// A router past the first hop of a traceroute
+pub const NODE_ROUTER: &str = "router";
+/// The first hop of a traceroute
+pub const NODE_GATEWAY: &str = "gateway";
+/// A layer 2 segment, addressed by its subnet
+pub const NODE_SEGMENT: &str = "segment";
+
+pub const EDGE_ROUTE: &str = "route";
+pub const EDGE_LAYER2: &str = "layer2";
+pub const EDGE_TRAFFIC: &str = "traffic";
+
+/// Prefix of the segment an IPv4 layer 2 neighbour is placed in
+const SEGMENT_PREFIX: u8 = 24;
+
+/// Grows the topology graph as routes, neighbours and traffic are
+/// observed.
+pub struct TopologyBuilder;
+
+impl TopologyBuilder {
+    /// Links the hops of a traceroute in TTL order. Hops that didn't
+    /// answer are missing from the trace, so an edge may span several
+    /// routers.
+    pub async fn record_route(pool: &SqlitePool, target: IpAddr, hops: &[TraceHop]) -> Result<()> {
+        let mut hops: Vec<&TraceHop> = hops.iter().collect();
+        hops.sort_by_key(|hop| hop.ttl);
+
+        let mut previous: Option<String> = None;
+        for (index, hop) in hops.iter().enumerate() {
+            let kind = if hop.ip == target {
+                NODE_HOST
+            } else if index == 0 {
+                NODE_GATEWAY
+            } else {
+                NODE_ROUTER
+            };
+
+            let node = TopologyOperations::upsert_node(pool, &hop.ip.to_string(), kind).await?;
+            if let Some(previous) = &previous {
+                TopologyOperations::record_edge(pool, previous, &node, EDGE_ROUTE).await?;
+            }
+            previous = Some(node);
+        }
+
+        Ok(())
+    }
+
+    /// MAC addresses don't survive routing, so a host whose MAC was seen
+    /// shares a segment with the scanner or sniffer. It is linked to the
+    /// segment of its subnet.
+    pub async fn record_neighbour(pool: &SqlitePool, ip: IpAddr) -> Result<()> {
+        let segment = NetworkUtils::subnet_of(ip, SEGMENT_PREFIX);
+        let segment = TopologyOperations::upsert_node(
+            pool,
+            &format!("{}/{}", segment.first_address(), segment.network_length()),
+            NODE_SEGMENT,
+        ).await?;
+        let host = TopologyOperations::upsert_node(pool, &ip.to_string(), NODE_HOST).await?;
+
+        TopologyOperations::record_edge(pool, &host, &segment, EDGE_LAYER2).await
+    }
+
+    /// Conversations are undirected, so each pair has one edge whichever
+    /// side spoke first.
+    pub async fn record_traffic(pool: &SqlitePool, a: IpAddr, b: IpAddr) -> Result<()> {
+        let (a, b) = if a <= b { (a, b) } else { (b, a) };
+        let a = TopologyOperations::upsert_node(pool, &a.to_string(), NODE_HOST).await?;
+        let b = TopologyOperations::upsert_node(pool, &b.to_string(), NODE_HOST).await?;
+
+        TopologyOperations::record_edge(pool, &a, &b, EDGE_TRAFFIC).await
+    }
+}
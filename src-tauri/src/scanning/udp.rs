@@ -0,0 +1,222 @@
+use super::Port;
+use super::ipmi::{describe_ipmi, get_channel_auth_capabilities};
+use anyhow::Result;
+use futures::future::join_all;
+use std::net::{IpAddr, SocketAddr};
+use std::time::Duration;
+use tokio::net::UdpSocket;
+
+pub const DEFAULT_UDP_PROBE_TIMEOUT: Duration = Duration::from_secs(2);
+
+/// A crafted request for a UDP service that stays silent unless it receives
+/// a well-formed query.
+pub struct UdpProbe {
+    pub service: &'static str,
+    pub port: u16,
+    payload: fn() -> Vec<u8>,
+    describe: fn(&[u8]) -> Option<String>,
+}
+
+pub const UDP_PROBES: &[UdpProbe] = &[
+    UdpProbe { service: "domain", port: 53, payload: dns_version_bind, describe: describe_dns },
+    UdpProbe { service: "ntp", port: 123, payload: ntp_client, describe: describe_ntp },
+    UdpProbe { service: "netbios-ns", port: 137, payload: netbios_nbstat, describe: describe_netbios },
+    UdpProbe { service: "snmp", port: 161, payload: snmp_get_sysdescr, describe: describe_snmp },
+    UdpProbe { service: "asf-rmcp", port: 623, payload: get_channel_auth_capabilities, describe: describe_ipmi },
+    UdpProbe { service: "ssdp", port: 1900, payload: ssdp_msearch, describe: describe_ssdp },
+];
+
+/// Confirms UDP services by sending protocol-valid payloads from Rust,
+/// which is far quicker than `nmap -sU` and catches services masscan's
+/// empty datagrams miss.
+pub struct UdpProber {
+    timeout: Duration,
+}
+
+impl UdpProber {
+    pub fn new(timeout: Duration) -> Self {
+        Self { timeout }
+    }
+
+    /// Sends every probe to `ip` concurrently and returns the ports that
+    /// answered.
+    pub async fn probe_host(&self, ip: IpAddr) -> Vec<Port> {
+        let probes = UDP_PROBES.iter().map(|probe| self.probe(ip, probe));
+
+        join_all(probes)
+            .await
+            .into_iter()
+            .filter_map(|result| match result {
+                Ok(port) => port,
+                Err(e) => {
+                    eprintln!("UDP probe to {} failed: {}", ip, e);
+                    None
+                }
+            })
+            .collect()
+    }
+
+    pub async fn probe(&self, ip: IpAddr, probe: &UdpProbe) -> Result<Option<Port>> {
+        let bind_addr: SocketAddr = match ip {
+            IpAddr::V4(_) => "0.0.0.0:0".parse()?,
+            IpAddr::V6(_) => "[::]:0".parse()?,
+        };
+
+        let socket = UdpSocket::bind(bind_addr).await?;
+        socket.connect(SocketAddr::new(ip, probe.port)).await?;
+        socket.send(&(probe.payload)()).await?;
+
+        let mut buf = vec![0u8; 4096];
+        let len = match tokio::time::timeout(self.timeout, socket.recv(&mut buf)).await {
+            Ok(Ok(len)) => len,
+            // ICMP port unreachable surfaces as a recv error: the port is closed
+            Ok(Err(_)) | Err(_) => return Ok(None),
+        };
+        let response = &buf[..len];
+
+        Ok(Some(Port {
+            number: probe.port,
+            protocol: "udp".to_string(),
+            state: "open".to_string(),
+            service: Some(probe.service.to_string()),
+            version: (probe.describe)(response),
+            banner: Some(printable(response)),
+        }))
+    }
+}
+
+impl Default for UdpProber {
+    fn default() -> Self {
+        Self::new(DEFAULT_UDP_PROBE_TIMEOUT)
+    }
+}
+
+fn printable(data: &[u8]) -> String {
+    data.iter()
+        .take(256)
+        .map(|&b| if b.is_ascii_graphic() || b == b' ' { b as char } else { '.' })
+        .collect()
+}
+
+// DNS: CHAOS TXT query for version.bind
+fn dns_version_bind() -> Vec<u8> {
+    let mut packet = vec![
+        0x4c, 0x32, // transaction id
+        0x01, 0x00, // standard query, recursion desired
+        0x00, 0x01, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00,
+    ];
+    for label in ["version", "bind"] {
+        packet.push(label.len() as u8);
+        packet.extend_from_slice(label.as_bytes());
+    }
+    packet.extend_from_slice(&[0x00, 0x00, 0x10, 0x00, 0x03]); // TXT, CH
+    packet
+}
+
+fn describe_dns(response: &[u8]) -> Option<String> {
+    // Answer count must be non-zero; the TXT string is the last
+    // length-prefixed field of the packet
+    if response.len() < 12 || u16::from_be_bytes([response[6], response[7]]) == 0 {
+        return None;
+    }
+
+    let last = response.len().checked_sub(1)?;
+    (12..last)
+        .rev()
+        .find(|&i| response[i] as usize == last - i && response[i] > 0)
+        .map(|i| String::from_utf8_lossy(&response[i + 1..]).to_string())
+}
+
+// NTP: version 4 client request
+fn ntp_client() -> Vec<u8> {
+    let mut packet = vec![0u8; 48];
+    packet[0] = 0x23; // LI 0, VN 4, mode 3
+    packet
+}
+
+fn describe_ntp(response: &[u8]) -> Option<String> {
+    let first = *response.first()?;
+    if response.len() < 48 || first & 0x07 != 4 {
+        return None;
+    }
+
+    Some(format!("NTP v{} stratum {}", (first >> 3) & 0x07, response[1]))
+}
+
+// NetBIOS NS: node status (NBSTAT) request for the wildcard name
+fn netbios_nbstat() -> Vec<u8> {
+    let mut packet = vec![
+        0x80, 0xf0, 0x00, 0x00, 0x00, 0x01, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00,
+        0x20, 0x43, 0x4b, // length 32, encoded '*'
+    ];
+    packet.extend(std::iter::repeat(0x41).take(30)); // encoded NUL padding
+    packet.extend_from_slice(&[0x00, 0x00, 0x21, 0x00, 0x01]); // NBSTAT, IN
+    packet
+}
+
+fn describe_netbios(response: &[u8]) -> Option<String> {
+    // Header (12) + name (34) + type/class/ttl/rdlength (10) + name count
+    let count = *response.get(56)? as usize;
+    let names: Vec<String> = (0..count)
+        .filter_map(|i| response.get(57 + i * 18..57 + i * 18 + 15))
+        .map(|name| String::from_utf8_lossy(name).trim().to_string())
+        .filter(|name| !name.is_empty())
+        .collect();
+
+    names.first().map(|name| format!("NetBIOS name {}", name))
+}
+
+// SNMP: v2c GetRequest for sysDescr.0 with community "public"
+fn snmp_get_sysdescr() -> Vec<u8> {
+    vec![
+        0x30, 0x29,
+        0x02, 0x01, 0x01, // version 2c
+        0x04, 0x06, b'p', b'u', b'b', b'l', b'i', b'c',
+        0xa0, 0x1c,
+        0x02, 0x04, 0x71, 0x62, 0x8a, 0x3e, // request id
+        0x02, 0x01, 0x00, 0x02, 0x01, 0x00,
+        0x30, 0x0e, 0x30, 0x0c,
+        0x06, 0x08, 0x2b, 0x06, 0x01, 0x02, 0x01, 0x01, 0x01, 0x00, // 1.3.6.1.2.1.1.1.0
+        0x05, 0x00,
+    ]
+}
+
+fn describe_snmp(response: &[u8]) -> Option<String> {
+    // sysDescr is the final OCTET STRING in the varbind
+    const SYSDESCR_OID: &[u8] = &[0x06, 0x08, 0x2b, 0x06, 0x01, 0x02, 0x01, 0x01, 0x01, 0x00];
+
+    let pos = response.windows(SYSDESCR_OID.len()).position(|w| w == SYSDESCR_OID)?;
+    let value = &response[pos + SYSDESCR_OID.len()..];
+    if *value.first()? != 0x04 {
+        return None;
+    }
+
+    let (len, start) = match *value.get(1)? {
+        len if len < 0x80 => (len as usize, 2),
+        0x81 => (*value.get(2)? as usize, 3),
+        0x82 => (u16::from_be_bytes([*value.get(2)?, *value.get(3)?]) as usize, 4),
+        _ => return None,
+    };
+
+    value.get(start..start + len)
+        .map(|descr| String::from_utf8_lossy(descr).trim().to_string())
+}
+
+// SSDP: M-SEARCH for all devices
+fn ssdp_msearch() -> Vec<u8> {
+    b"M-SEARCH * HTTP/1.1\r\n\
+      HOST: 239.255.255.250:1900\r\n\
+      MAN: \"ssdp:discover\"\r\n\
+      MX: 1\r\n\
+      ST: ssdp:all\r\n\r\n"
+        .to_vec()
+}
+
+fn describe_ssdp(response: &[u8]) -> Option<String> {
+    String::from_utf8_lossy(response)
+        .lines()
+        .find_map(|line| {
+            let (name, value) = line.split_once(':')?;
+            name.trim().eq_ignore_ascii_case("server").then(|| value.trim().to_string())
+        })
+}
\ No newline at end of file
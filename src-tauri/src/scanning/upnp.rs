@@ -0,0 +1,225 @@
+use anyhow::{Result, anyhow};
+use serde::{Deserialize, Serialize};
+use std::net::{IpAddr, SocketAddr};
+use std::time::Duration;
+use tokio::net::UdpSocket;
+use xml::reader::{EventReader, XmlEvent};
+
+pub const SSDP_PORT: u16 = 1900;
+pub const DEFAULT_UPNP_TIMEOUT: Duration = Duration::from_secs(5);
+/// Stops runaway enumeration on gateways that never return the end-of-table fault
+const MAX_PORT_MAPPINGS: u32 = 1024;
+
+const IGD_DEVICE: &str = "urn:schemas-upnp-org:device:InternetGatewayDevice:1";
+const WAN_CONNECTION_SERVICES: &[&str] = &[
+    "urn:schemas-upnp-org:service:WANIPConnection:1",
+    "urn:schemas-upnp-org:service:WANIPConnection:2",
+    "urn:schemas-upnp-org:service:WANPPPConnection:1",
+];
+
+/// One NAT forward from an IGD's port-mapping table.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct PortMapping {
+    pub remote_host: Option<String>,
+    pub external_port: u16,
+    pub protocol: String,
+    pub internal_port: u16,
+    pub internal_client: String,
+    pub enabled: bool,
+    pub description: Option<String>,
+    pub lease_duration: u32,
+}
+
+/// Reads the port-mapping table of UPnP Internet Gateway Devices with the
+/// standard GetGenericPortMappingEntry SOAP action. Read-only: nothing is
+/// added or removed.
+pub struct UpnpEnumerator {
+    client: reqwest::Client,
+    timeout: Duration,
+}
+
+impl UpnpEnumerator {
+    pub fn new(timeout: Duration) -> Self {
+        let client = reqwest::Client::builder()
+            .timeout(timeout)
+            .build()
+            .unwrap_or_default();
+
+        Self { client, timeout }
+    }
+
+    /// Returns `None` when `ip` is not an IGD, otherwise its mappings.
+    pub async fn enumerate(&self, ip: IpAddr) -> Result<Option<Vec<PortMapping>>> {
+        let Some(location) = self.find_igd(ip).await? else {
+            return Ok(None);
+        };
+
+        // Never follow a device to some other address
+        let description_url = reqwest::Url::parse(&location)?;
+        if !points_to(&description_url, ip) {
+            return Ok(None);
+        }
+
+        let description = self.client.get(description_url.clone()).send().await?.text().await?;
+        let Some((service_type, control_url)) = find_wan_service(&description)? else {
+            return Ok(None);
+        };
+        let control_url = description_url.join(&control_url)
+            .map_err(|e| anyhow!("Invalid control URL {}: {}", control_url, e))?;
+        if !points_to(&control_url, ip) {
+            return Ok(None);
+        }
+        let control_url = control_url.to_string();
+
+        let mut mappings = Vec::new();
+        for index in 0..MAX_PORT_MAPPINGS {
+            // The table ends with a SpecifiedArrayIndexInvalid (713) fault
+            match self.get_mapping(&control_url, &service_type, index).await? {
+                Some(mapping) => mappings.push(mapping),
+                None => break,
+            }
+        }
+
+        Ok(Some(mappings))
+    }
+
+    /// Unicast M-SEARCH for an IGD; returns its description URL.
+    async fn find_igd(&self, ip: IpAddr) -> Result<Option<String>> {
+        let bind_addr: SocketAddr = match ip {
+            IpAddr::V4(_) => "0.0.0.0:0".parse()?,
+            IpAddr::V6(_) => "[::]:0".parse()?,
+        };
+
+        let socket = UdpSocket::bind(bind_addr).await?;
+        socket.connect(SocketAddr::new(ip, SSDP_PORT)).await?;
+        let search = format!(
+            "M-SEARCH * HTTP/1.1\r\nHOST: 239.255.255.250:1900\r\nMAN: \"ssdp:discover\"\r\nMX: 1\r\nST: {}\r\n\r\n",
+            IGD_DEVICE
+        );
+        socket.send(search.as_bytes()).await?;
+
+        let mut buf = vec![0u8; 2048];
+        let len = match tokio::time::timeout(self.timeout, socket.recv(&mut buf)).await {
+            Ok(Ok(len)) => len,
+            Ok(Err(_)) | Err(_) => return Ok(None),
+        };
+
+        Ok(String::from_utf8_lossy(&buf[..len])
+            .lines()
+            .find_map(|line| {
+                let (name, value) = line.split_once(':')?;
+                name.trim().eq_ignore_ascii_case("location").then(|| value.trim().to_string())
+            }))
+    }
+
+    async fn get_mapping(&self, control_url: &str, service_type: &str, index: u32) -> Result<Option<PortMapping>> {
+        let body = format!(
+            "<?xml version=\"1.0\"?>\
+             <s:Envelope xmlns:s=\"http://schemas.xmlsoap.org/soap/envelope/\" \
+             s:encodingStyle=\"http://schemas.xmlsoap.org/soap/encoding/\">\
+             <s:Body><u:GetGenericPortMappingEntry xmlns:u=\"{}\">\
+             <NewPortMappingIndex>{}</NewPortMappingIndex>\
+             </u:GetGenericPortMappingEntry></s:Body></s:Envelope>",
+            service_type, index
+        );
+
+        let response = self.client.post(control_url)
+            .header("Content-Type", "text/xml; charset=\"utf-8\"")
+            .header("SOAPAction", format!("\"{}#GetGenericPortMappingEntry\"", service_type))
+            .body(body)
+            .send()
+            .await?;
+
+        // Faults come back as HTTP 500
+        if !response.status().is_success() {
+            return Ok(None);
+        }
+
+        let fields = element_texts(&response.text().await?)?;
+        let field = |name: &str| fields.iter()
+            .find(|(key, _)| key == name)
+            .map(|(_, value)| value.clone())
+            .filter(|value| !value.is_empty());
+        let number = |name: &str| field(name).and_then(|v| v.parse::<u32>().ok());
+
+        let (Some(external_port), Some(internal_port), Some(internal_client)) =
+            (number("NewExternalPort"), number("NewInternalPort"), field("NewInternalClient"))
+        else {
+            return Ok(None);
+        };
+
+        Ok(Some(PortMapping {
+            remote_host: field("NewRemoteHost"),
+            external_port: external_port as u16,
+            protocol: field("NewProtocol").unwrap_or_default().to_lowercase(),
+            internal_port: internal_port as u16,
+            internal_client,
+            enabled: field("NewEnabled").map(|v| v == "1" || v.eq_ignore_ascii_case("true")).unwrap_or(true),
+            description: field("NewPortMappingDescription"),
+            lease_duration: number("NewLeaseDuration").unwrap_or(0),
+        }))
+    }
+}
+
+impl Default for UpnpEnumerator {
+    fn default() -> Self {
+        Self::new(DEFAULT_UPNP_TIMEOUT)
+    }
+}
+
+/// Service type and control URL of the first WAN connection service in a
+/// device description.
+fn find_wan_service(description: &str) -> Result<Option<(String, String)>> {
+    let mut service_type: Option<String> = None;
+    let mut element = String::new();
+
+    for event in EventReader::new(description.as_bytes()) {
+        match event? {
+            XmlEvent::StartElement { name, .. } => element = name.local_name,
+            XmlEvent::Characters(text) => match element.as_str() {
+                "serviceType" => service_type = Some(text.trim().to_string()),
+                "controlURL" => {
+                    if let Some(service) = service_type.as_deref() {
+                        if WAN_CONNECTION_SERVICES.contains(&service) {
+                            return Ok(Some((service.to_string(), text.trim().to_string())));
+                        }
+                    }
+                }
+                _ => {}
+            },
+            XmlEvent::EndElement { name } if name.local_name == "service" => service_type = None,
+            XmlEvent::EndElement { .. } => element.clear(),
+            _ => {}
+        }
+    }
+
+    Ok(None)
+}
+
+fn points_to(url: &reqwest::Url, ip: IpAddr) -> bool {
+    url.host_str()
+        .map(|host| host.trim_start_matches('[').trim_end_matches(']'))
+        .and_then(|host| host.parse::<IpAddr>().ok())
+        == Some(ip)
+}
+
+/// Every leaf element's text, keyed by local name.
+fn element_texts(xml: &str) -> Result<Vec<(String, String)>> {
+    let mut fields = Vec::new();
+    let mut element: Option<String> = None;
+
+    for event in EventReader::new(xml.as_bytes()) {
+        match event? {
+            XmlEvent::StartElement { name, .. } => element = Some(name.local_name),
+            XmlEvent::Characters(text) => {
+                if let Some(name) = element.take() {
+                    fields.push((name, text.trim().to_string()));
+                }
+            }
+            XmlEvent::EndElement { .. } => element = None,
+            _ => {}
+        }
+    }
+
+    Ok(fields)
+}
\ No newline at end of file
@@ -0,0 +1,69 @@
+use serde::{Deserialize, Serialize};
+
+/// The check behind a finding, kept on it as `check_ref` so the same check
+/// can be run again to verify a fix. Written as `<kind>:<port>`, with the
+/// nuclei template, NSE script or datastore appended as `:<name>`.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub enum FindingCheck {
+    Nuclei { port: u16, template_id: String },
+    /// An NSE script using nmap's vulns library, run against one port
+    Nse { port: u16, script: String },
+    SshAudit { port: u16 },
+    /// `ftp` or `tftp`
+    AnonymousAccess { protocol: String, port: u16 },
+    SmtpRelay { port: u16 },
+    Datastore { datastore: String, port: u16 },
+    /// Anonymous SMB share enumeration
+    SmbShares,
+    /// SMB signing and the smb-vuln NSE scripts
+    SmbSecurity,
+    Ipmi { port: u16 },
+    /// The banner a TCP service sends on connect, compared with the one
+    /// stored for the port when the finding was raised
+    Banner { port: u16 },
+}
+
+impl FindingCheck {
+    pub fn to_ref(&self) -> String {
+        match self {
+            Self::Nuclei { port, template_id } => format!("nuclei:{}:{}", port, template_id),
+            Self::Nse { port, script } => format!("nse:{}:{}", port, script),
+            Self::SshAudit { port } => format!("ssh_audit:{}", port),
+            Self::AnonymousAccess { protocol, port } => format!("{}_anonymous:{}", protocol, port),
+            Self::SmtpRelay { port } => format!("smtp_relay:{}", port),
+            Self::Datastore { datastore, port } => format!("datastore:{}:{}", port, datastore),
+            Self::SmbShares => format!("smb_shares:{}", super::SMB_PORTS[0]),
+            Self::SmbSecurity => format!("smb_security:{}", super::SMB_PORTS[0]),
+            Self::Ipmi { port } => format!("ipmi:{}", port),
+            Self::Banner { port } => format!("banner:{}", port),
+        }
+    }
+
+    /// Checks that raise at most one finding
+    pub fn single_finding(&self) -> bool {
+        matches!(self, Self::Nuclei { .. } | Self::Nse { .. } | Self::AnonymousAccess { .. } | Self::SmtpRelay { .. } | Self::Datastore { .. } | Self::Banner { .. })
+    }
+
+    /// `None` for a reference this version doesn't know
+    pub fn parse(check_ref: &str) -> Option<Self> {
+        let mut parts = check_ref.splitn(3, ':');
+        let kind = parts.next()?;
+        let port = parts.next()?.parse::<u16>().ok()?;
+        let name = parts.next().filter(|name| !name.is_empty()).map(str::to_string);
+
+        match (kind, name) {
+            ("nuclei", Some(template_id)) => Some(Self::Nuclei { port, template_id }),
+            ("nse", Some(script)) => Some(Self::Nse { port, script }),
+            ("ssh_audit", None) => Some(Self::SshAudit { port }),
+            ("ftp_anonymous", None) => Some(Self::AnonymousAccess { protocol: "ftp".to_string(), port }),
+            ("tftp_anonymous", None) => Some(Self::AnonymousAccess { protocol: "tftp".to_string(), port }),
+            ("smtp_relay", None) => Some(Self::SmtpRelay { port }),
+            ("datastore", Some(datastore)) => Some(Self::Datastore { datastore, port }),
+            ("smb_shares", None) => Some(Self::SmbShares),
+            ("smb_security", None) => Some(Self::SmbSecurity),
+            ("ipmi", None) => Some(Self::Ipmi { port }),
+            ("banner", None) => Some(Self::Banner { port }),
+            _ => None,
+        }
+    }
+}
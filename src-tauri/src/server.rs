@@ -0,0 +1,334 @@
+//! Token-authenticated HTTP API for shared instances. Each request is
+//! `POST /api/<command>` with the command's arguments as a JSON object and
+//! an `Authorization: Bearer <token>` header. The token's scope and its
+//! owner's role decide which commands it may run; the desktop app keeps
+//! using IPC and is not subject to them.
+
+use crate::auth::Authorizer;
+use crate::commands::ActiveScanInfo;
+use crate::database::{models::*, operations::*};
+use crate::error::LegionError;
+use crate::scanning::*;
+use crate::utils::{progress_channel, InputValidator};
+use crate::AppState;
+use anyhow::{Context, Result, bail};
+use serde::Deserialize;
+use serde::de::DeserializeOwned;
+use serde_json::Value;
+use tokio::io::{AsyncBufReadExt, AsyncReadExt, AsyncWriteExt, BufReader};
+use tokio::net::{TcpListener, TcpStream};
+use tokio::sync::Semaphore;
+use std::sync::Arc;
+use std::time::Duration;
+
+/// Longest request or header line read from a client
+const MAX_LINE: u64 = 8 * 1024;
+const MAX_HEADERS: usize = 64;
+const MAX_BODY: usize = 1024 * 1024;
+/// How long a client has to send its whole request
+const READ_TIMEOUT: Duration = Duration::from_secs(10);
+/// Connections served at once; further ones wait to be accepted
+const MAX_CONNECTIONS: usize = 64;
+
+/// Commands served over the API. Anything else is only reachable from the
+/// desktop app.
+const API_COMMANDS: &[&str] = &[
+    "get_hosts",
+    "get_scan_results",
+    "get_active_scans",
+    "start_scan",
+    "cancel_scan",
+    "create_api_token",
+    "list_api_tokens",
+    "revoke_api_token",
+];
+
+pub struct ApiServer;
+
+impl ApiServer {
+    /// Binds `bind` and serves requests in the background until the app
+    /// exits.
+    pub async fn start(bind: &str, state: AppState) -> Result<()> {
+        let listener = TcpListener::bind(bind)
+            .await
+            .with_context(|| format!("Failed to bind the API server to {}", bind))?;
+        println!("API server listening on {}", listener.local_addr()?);
+
+        let connections = Arc::new(Semaphore::new(MAX_CONNECTIONS));
+        tokio::spawn(async move {
+            loop {
+                let Ok(permit) = connections.clone().acquire_owned().await else {
+                    break;
+                };
+                let (stream, peer) = match listener.accept().await {
+                    Ok(connection) => connection,
+                    Err(e) => {
+                        eprintln!("API server accept failed: {}", e);
+                        continue;
+                    }
+                };
+                let state = state.clone();
+                tokio::spawn(async move {
+                    let _permit = permit;
+                    if let Err(e) = handle(stream, &state).await {
+                        eprintln!("API request from {} failed: {}", peer, e);
+                    }
+                });
+            }
+        });
+
+        Ok(())
+    }
+}
+
+struct Request {
+    method: String,
+    path: String,
+    token: Option<String>,
+    body: Vec<u8>,
+}
+
+async fn handle(stream: TcpStream, state: &AppState) -> Result<()> {
+    let mut stream = BufReader::new(stream);
+    let request = tokio::time::timeout(READ_TIMEOUT, read_request(&mut stream))
+        .await
+        .context("Timed out reading the request")??;
+
+    let (status, body) = match respond(state, request).await {
+        Ok(value) => (200, value),
+        Err(error) => (status_of(&error), serde_json::to_value(&error)?),
+    };
+
+    let body = serde_json::to_vec(&body)?;
+    let head = format!(
+        "HTTP/1.1 {}\r\nContent-Type: application/json\r\nContent-Length: {}\r\nConnection: close\r\n\r\n",
+        status,
+        body.len()
+    );
+    let stream = stream.get_mut();
+    stream.write_all(head.as_bytes()).await?;
+    stream.write_all(&body).await?;
+    stream.shutdown().await?;
+    Ok(())
+}
+
+async fn read_request(stream: &mut BufReader<TcpStream>) -> Result<Request> {
+    let mut line = String::new();
+    (&mut *stream).take(MAX_LINE).read_line(&mut line).await?;
+    let mut parts = line.split_whitespace();
+    let (Some(method), Some(path)) = (parts.next(), parts.next()) else {
+        bail!("Malformed request line");
+    };
+    let (method, path) = (method.to_string(), path.to_string());
+
+    let mut token = None;
+    let mut content_length = 0;
+    let mut headers = 0;
+    loop {
+        line.clear();
+        (&mut *stream).take(MAX_LINE).read_line(&mut line).await?;
+        let header = line.trim_end();
+        if header.is_empty() {
+            break;
+        }
+
+        headers += 1;
+        if headers > MAX_HEADERS {
+            bail!("Too many headers");
+        }
+
+        let Some((name, value)) = header.split_once(':') else {
+            bail!("Malformed header");
+        };
+        let value = value.trim();
+        if name.eq_ignore_ascii_case("content-length") {
+            content_length = value.parse().context("Invalid Content-Length")?;
+        } else if name.eq_ignore_ascii_case("authorization") {
+            token = value.strip_prefix("Bearer ").map(|t| t.trim().to_string());
+        }
+    }
+
+    if content_length > MAX_BODY {
+        bail!("Request body of {} bytes is too large", content_length);
+    }
+    let mut body = vec![0; content_length];
+    stream.read_exact(&mut body).await?;
+
+    Ok(Request { method, path, token, body })
+}
+
+fn status_of(error: &LegionError) -> &'static str {
+    match error {
+        LegionError::Validation(_) => "400 Bad Request",
+        LegionError::Permission(_) => "403 Forbidden",
+        LegionError::NotFound(_) => "404 Not Found",
+        _ => "500 Internal Server Error",
+    }
+}
+
+async fn respond(state: &AppState, request: Request) -> Result<Value, LegionError> {
+    let command = request.path.strip_prefix("/api/")
+        .filter(|command| request.method == "POST" && API_COMMANDS.contains(command))
+        .ok_or_else(|| LegionError::NotFound(format!("No such endpoint: {} {}", request.method, request.path)))?;
+
+    let token = request.token
+        .ok_or_else(|| LegionError::Permission("Missing bearer token".to_string()))?;
+    let caller = Authorizer::authorize_token(state.database.main_pool(), &token, command)
+        .await
+        .map_err(LegionError::from)?;
+
+    let args: Value = match request.body.is_empty() {
+        true => Value::Object(Default::default()),
+        false => serde_json::from_slice(&request.body)
+            .map_err(|e| LegionError::Validation(format!("Invalid JSON body: {}", e)))?,
+    };
+
+    dispatch(state, &caller, command, args).await
+}
+
+fn parse<T: DeserializeOwned>(args: Value) -> Result<T, LegionError> {
+    serde_json::from_value(args).map_err(|e| LegionError::Validation(format!("Invalid arguments: {}", e)))
+}
+
+#[derive(Deserialize)]
+struct FilterArgs<F> {
+    filter: Option<F>,
+}
+
+#[derive(Deserialize)]
+struct StartScanArgs {
+    target_ip: String,
+    scan_type: Option<String>,
+    ports: Option<String>,
+    options: Option<ScanOptions>,
+    reason: Option<String>,
+    ticket_ref: Option<String>,
+}
+
+#[derive(Deserialize)]
+struct ScanIdArgs {
+    scan_id: String,
+}
+
+#[derive(Deserialize)]
+struct CreateTokenArgs {
+    user_id: String,
+    name: String,
+    scope: String,
+    expires_in_days: Option<i64>,
+}
+
+#[derive(Deserialize)]
+struct ListTokensArgs {
+    user_id: Option<String>,
+}
+
+#[derive(Deserialize)]
+struct TokenIdArgs {
+    token_id: String,
+}
+
+async fn dispatch(state: &AppState, caller: &User, command: &str, args: Value) -> Result<Value, LegionError> {
+    match command {
+        "get_hosts" => {
+            let args: FilterArgs<HostFilter> = parse(args)?;
+            let hosts = HostOperations::list(&state.database.pool(), &args.filter.unwrap_or_default()).await?;
+            Ok(serde_json::to_value(hosts)?)
+        }
+        "get_scan_results" => {
+            let args: FilterArgs<ScanResultFilter> = parse(args)?;
+            let results = ScanOperations::find_results(&state.database.pool(), &args.filter.unwrap_or_default()).await?;
+            let results = results.iter()
+                .map(|json| serde_json::from_str(json))
+                .collect::<Result<Vec<Value>, _>>()?;
+            Ok(Value::Array(results))
+        }
+        "get_active_scans" => {
+            let scans: Vec<ActiveScanInfo> = state.scan_coordinator.get_active_scans().await
+                .into_iter()
+                .map(|(id, status)| ActiveScanInfo { id: id.to_string(), status })
+                .collect();
+            Ok(serde_json::to_value(scans)?)
+        }
+        "start_scan" => {
+            let args: StartScanArgs = parse(args)?;
+            let ip = InputValidator::validate_ip(&args.target_ip)?;
+
+            let ports = match args.ports.as_deref().map(str::trim).filter(|p| !p.is_empty()) {
+                Some(selection) => state.scan_coordinator.resolve_ports(selection).await?,
+                None => vec![],
+            };
+
+            let scan_type = args.scan_type
+                .or_else(|| state.scan_coordinator.default_scan_profile())
+                .unwrap_or_else(|| "quick".to_string());
+            let scan_type = match scan_type.as_str() {
+                "comprehensive" => ScanType::Comprehensive,
+                "stealth" => ScanType::Stealth,
+                "ot" => ScanType::Ot,
+                _ => ScanType::Quick,
+            };
+
+            // Scans started over the API are attributed to the token's owner
+            let mut options = args.options.unwrap_or_default();
+            let non_empty = |value: Option<String>| value.map(|v| v.trim().to_string()).filter(|v| !v.is_empty());
+            options.annotations.started_by = Some(caller.username.clone());
+            options.annotations.reason = non_empty(args.reason).or(options.annotations.reason.take());
+            options.annotations.ticket_ref = non_empty(args.ticket_ref).or(options.annotations.ticket_ref.take());
+
+            let target = ScanTarget {
+                id: uuid::Uuid::new_v4(),
+                ip,
+                hostname: None,
+                ports,
+                scan_type,
+                options,
+            };
+
+            // API clients poll get_active_scans instead of streaming progress
+            let (progress_tx, _) = progress_channel();
+            let scan_id = state.scan_coordinator.start_scan(target, progress_tx).await?;
+            Ok(Value::String(scan_id.to_string()))
+        }
+        "cancel_scan" => {
+            let args: ScanIdArgs = parse(args)?;
+            let scan_id = uuid::Uuid::parse_str(&args.scan_id)
+                .map_err(|e| LegionError::Validation(format!("Invalid UUID: {}", e)))?;
+            state.scan_coordinator.cancel_scan(scan_id).await?;
+            Ok(Value::Null)
+        }
+        // Token management needs an admin caller; see `auth::ADMIN_COMMANDS`
+        "create_api_token" => {
+            let args: CreateTokenArgs = parse(args)?;
+            let issued = Authorizer::issue_token(
+                state.database.main_pool(),
+                Some(caller),
+                &args.user_id,
+                &args.name,
+                &args.scope,
+                args.expires_in_days,
+            ).await?;
+            Ok(serde_json::to_value(issued)?)
+        }
+        "list_api_tokens" => {
+            let args: ListTokensArgs = parse(args)?;
+            let tokens = ApiTokenOperations::list(state.database.main_pool(), args.user_id.as_deref()).await?;
+            Ok(serde_json::to_value(tokens)?)
+        }
+        "revoke_api_token" => {
+            let args: TokenIdArgs = parse(args)?;
+            let api_token = ApiTokenOperations::revoke(state.database.main_pool(), &args.token_id)
+                .await?
+                .ok_or_else(|| LegionError::NotFound(format!("API token not found: {}", args.token_id)))?;
+            AuditLogOperations::record(
+                state.database.main_pool(),
+                None,
+                "api_token_revoked",
+                Some(&api_token.user_id),
+                Some(&format!("{} (revoked by {})", api_token.name, caller.username)),
+            ).await?;
+            Ok(serde_json::to_value(api_token)?)
+        }
+        _ => Err(LegionError::NotFound(format!("No such command: {}", command))),
+    }
+}
@@ -0,0 +1,131 @@
+use super::packet::{format_mac, ArpPacket};
+use serde::{Deserialize, Serialize};
+use std::collections::{HashMap, HashSet, VecDeque};
+use std::net::{IpAddr, Ipv4Addr};
+use std::time::{Duration, Instant};
+
+/// This many MAC changes for one address within `ARP_FLAP_WINDOW` is
+/// treated as spoofing rather than a replaced device or a DHCP move
+const ARP_FLAP_CHANGES: usize = 3;
+const ARP_FLAP_WINDOW: Duration = Duration::from_secs(60);
+/// An address is alerted on at most once in this window
+const ARP_ALERT_COOLDOWN: Duration = Duration::from_secs(300);
+/// Addresses remembered before the table is cleared, bounding memory on
+/// large segments
+const MAX_TRACKED_ARP_MAPPINGS: usize = 65_536;
+const MAX_TRACKED_ROUTER_MACS: usize = 64;
+
+/// An address whose MAC changed in a way that suggests ARP spoofing.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ArpAlert {
+    pub ip: IpAddr,
+    /// MACs recently seen claiming the address, the current one last
+    pub macs: Vec<String>,
+    pub gateway: bool,
+    pub reason: String,
+}
+
+struct Mapping {
+    mac: [u8; 6],
+    /// MAC changes within the flap window and when they were seen
+    changes: VecDeque<([u8; 6], Instant)>,
+    alerted_at: Option<Instant>,
+}
+
+/// Follows the IP to MAC mapping ARP traffic announces. An alert is
+/// raised when an address changes MAC rapidly, or when any second MAC
+/// claims a gateway.
+pub struct ArpWatch {
+    mappings: HashMap<Ipv4Addr, Mapping>,
+    gateways: HashSet<Ipv4Addr>,
+    /// MACs seen forwarding traffic from outside the private networks
+    router_macs: HashSet<[u8; 6]>,
+}
+
+impl ArpWatch {
+    /// `gateways` are the ones already known, e.g. from traceroutes.
+    /// Others are learned from the MACs routed traffic arrives from.
+    pub fn new(gateways: impl IntoIterator<Item = Ipv4Addr>) -> Self {
+        Self {
+            mappings: HashMap::new(),
+            gateways: gateways.into_iter().collect(),
+            router_macs: HashSet::new(),
+        }
+    }
+
+    /// Notes the source MAC of a frame carrying a packet from a public
+    /// address, which only a router sends.
+    pub fn observe_routed(&mut self, src_mac: [u8; 6]) {
+        if self.router_macs.len() < MAX_TRACKED_ROUTER_MACS {
+            self.router_macs.insert(src_mac);
+        }
+    }
+
+    /// The sender fields of requests and replies alike claim a mapping.
+    pub fn observe(&mut self, arp: &ArpPacket, now: Instant) -> Option<ArpAlert> {
+        let ip = arp.sender_ip;
+        // ARP probes from hosts checking for conflicts claim nothing
+        if ip.is_unspecified() {
+            return None;
+        }
+
+        if self.router_macs.contains(&arp.sender_mac) && self.mappings.get(&ip).map_or(true, |m| m.mac == arp.sender_mac) {
+            self.gateways.insert(ip);
+        }
+
+        if self.mappings.len() >= MAX_TRACKED_ARP_MAPPINGS && !self.mappings.contains_key(&ip) {
+            self.mappings.clear();
+        }
+        let mapping = self.mappings.entry(ip).or_insert_with(|| Mapping {
+            mac: arp.sender_mac,
+            changes: VecDeque::new(),
+            alerted_at: None,
+        });
+        if mapping.mac == arp.sender_mac {
+            return None;
+        }
+
+        let previous = mapping.mac;
+        mapping.mac = arp.sender_mac;
+        mapping.changes.push_back((arp.sender_mac, now));
+        while mapping.changes.front().is_some_and(|(_, at)| now.duration_since(*at) > ARP_FLAP_WINDOW) {
+            mapping.changes.pop_front();
+        }
+
+        let gateway = self.gateways.contains(&ip);
+        let flapping = mapping.changes.len() >= ARP_FLAP_CHANGES;
+        if !gateway && !flapping {
+            return None;
+        }
+        if mapping.alerted_at.is_some_and(|at| now.duration_since(at) < ARP_ALERT_COOLDOWN) {
+            return None;
+        }
+        mapping.alerted_at = Some(now);
+
+        let mut macs = vec![format_mac(&previous)];
+        for (mac, _) in &mapping.changes {
+            let mac = format_mac(mac);
+            macs.retain(|m| *m != mac);
+            macs.push(mac);
+        }
+
+        let reason = if gateway {
+            format!("{} MACs claimed gateway {}: {}", macs.len(), ip, macs.join(", "))
+        } else {
+            format!(
+                "MAC for {} changed {} times within {}s: {}",
+                ip,
+                mapping.changes.len(),
+                ARP_FLAP_WINDOW.as_secs(),
+                macs.join(", "),
+            )
+        };
+
+        Some(ArpAlert {
+            ip: IpAddr::V4(ip),
+            macs,
+            gateway,
+            reason,
+        })
+    }
+}
@@ -0,0 +1,284 @@
+use crate::error::LegionError;
+use anyhow::{Result, Context};
+use serde::{Deserialize, Serialize};
+use std::borrow::Cow;
+use std::path::Path;
+use ts_rs::TS;
+
+// pcap link-layer types the sniffer understands
+const LINKTYPE_NULL: i32 = 0;
+const LINKTYPE_ETHERNET: i32 = 1;
+const LINKTYPE_RAW: i32 = 101;
+const LINKTYPE_LOOP: i32 = 108;
+const LINKTYPE_LINUX_SLL: i32 = 113;
+
+const ETHERTYPE_IPV4: u16 = 0x0800;
+const ETHERTYPE_IPV6: u16 = 0x86dd;
+
+/// What packet capture can do on this machine, so the UI can explain why
+/// passive monitoring is unavailable instead of failing on start.
+#[derive(Debug, Clone, Serialize, Deserialize, TS)]
+#[ts(export, export_to = "../src/bindings/")]
+pub struct CaptureCapabilities {
+    /// "libpcap", "npcap", "winpcap" or "none"
+    pub backend: String,
+    pub version: Option<String>,
+    pub installed: bool,
+    /// An interface could be opened with the current privileges
+    pub can_capture: bool,
+    pub loopback_capture: bool,
+    /// Npcap was installed with "Restrict driver access to Administrators"
+    pub admin_only: bool,
+    pub issues: Vec<String>,
+}
+
+/// Platform differences in opening captures. On Windows this is Npcap,
+/// whose wpcap.dll lives outside the DLL search path and is delay-loaded
+/// (see build.rs), so nothing may call into pcap before `init` or when
+/// `is_installed` is false.
+pub struct CaptureBackend;
+
+impl CaptureBackend {
+    /// Makes Npcap's wpcap.dll loadable. Call before any other pcap use.
+    #[cfg(windows)]
+    pub fn init() {
+        use windows_sys::Win32::System::LibraryLoader::SetDllDirectoryW;
+
+        if let Some(dir) = npcap::install_dir() {
+            let dir = npcap::wide(&dir.to_string_lossy());
+            unsafe { SetDllDirectoryW(dir.as_ptr()) };
+        }
+    }
+
+    #[cfg(not(windows))]
+    pub fn init() {}
+
+    /// Whether a capture library is present
+    #[cfg(windows)]
+    pub fn is_installed() -> bool {
+        npcap::install_dir().is_some() || npcap::winpcap_installed()
+    }
+
+    /// libpcap is linked normally everywhere but Windows
+    #[cfg(not(windows))]
+    pub fn is_installed() -> bool {
+        true
+    }
+
+    pub fn ensure_installed() -> Result<()> {
+        if !Self::is_installed() {
+            return Err(LegionError::ToolMissing { tool: "Npcap".to_string() }.into());
+        }
+        Ok(())
+    }
+
+    /// Opens `interface` for capture. Many Wi-Fi drivers on Windows refuse
+    /// promiscuous mode, so that is retried without it.
+    pub fn open(interface: &str) -> Result<pcap::Capture<pcap::Active>> {
+        Self::ensure_installed()?;
+
+        let open = |promisc: bool| {
+            pcap::Capture::from_device(interface)
+                .and_then(|c| c.promisc(promisc).snaplen(65535).timeout(500).open())
+        };
+
+        open(true)
+            .or_else(|_| open(false))
+            .with_context(|| format!("Failed to open capture on {}", interface))
+    }
+
+    /// Opens a pcap or pcapng file for reading.
+    pub fn open_file(path: &Path) -> Result<pcap::Capture<pcap::Offline>> {
+        Self::ensure_installed()?;
+
+        pcap::Capture::from_file(path)
+            .with_context(|| format!("Failed to open capture file {}", path.display()))
+    }
+
+    /// Rewrites loopback, raw IP and Linux cooked frames as Ethernet so the
+    /// parser sees one format. `None` for link types it can't handle.
+    pub fn normalize(linktype: pcap::Linktype, data: &[u8]) -> Option<Cow<'_, [u8]>> {
+        let (ethertype, payload) = match linktype.0 {
+            LINKTYPE_ETHERNET => return Some(Cow::Borrowed(data)),
+            // BSD loopback and Npcap's loopback adapter: a 4-byte address
+            // family whose values and byte order differ by OS, so the IP
+            // version is read from the packet instead
+            LINKTYPE_NULL | LINKTYPE_LOOP => {
+                let payload = data.get(4..)?;
+                (ip_ethertype(payload)?, payload)
+            }
+            LINKTYPE_RAW => (ip_ethertype(data)?, data),
+            LINKTYPE_LINUX_SLL => {
+                let ethertype = u16::from_be_bytes([*data.get(14)?, *data.get(15)?]);
+                (ethertype, data.get(16..)?)
+            }
+            _ => return None,
+        };
+
+        let mut frame = Vec::with_capacity(14 + payload.len());
+        frame.extend_from_slice(&[0u8; 12]);
+        frame.extend_from_slice(&ethertype.to_be_bytes());
+        frame.extend_from_slice(payload);
+        Some(Cow::Owned(frame))
+    }
+
+    pub fn detect() -> CaptureCapabilities {
+        let mut capabilities = Self::detect_backend();
+        if !capabilities.installed {
+            return capabilities;
+        }
+
+        match pcap::Device::list() {
+            Ok(devices) => {
+                capabilities.loopback_capture |= devices.iter().any(|d| d.flags.is_loopback());
+
+                let probe = devices.iter()
+                    .find(|d| d.flags.is_up() && !d.flags.is_loopback())
+                    .or_else(|| devices.first());
+                match probe {
+                    Some(device) => match pcap::Capture::from_device(device.name.as_str()).and_then(|c| c.timeout(100).open()) {
+                        Ok(_) => capabilities.can_capture = true,
+                        Err(e) => capabilities.issues.push(format!("Can't open {}: {}", device.name, e)),
+                    },
+                    None => capabilities.issues.push("No capture interfaces found".to_string()),
+                }
+            }
+            Err(e) => capabilities.issues.push(format!("Failed to list interfaces: {}", e)),
+        }
+
+        if !capabilities.can_capture {
+            if capabilities.admin_only {
+                capabilities.issues.push("Npcap is restricted to administrators; run as administrator or reinstall Npcap without that option".to_string());
+            } else if cfg!(unix) {
+                capabilities.issues.push("Capture needs root or CAP_NET_RAW; the privileged helper can capture instead".to_string());
+            }
+        }
+
+        capabilities
+    }
+
+    #[cfg(windows)]
+    fn detect_backend() -> CaptureCapabilities {
+        let (backend, installed) = if npcap::install_dir().is_some() {
+            ("npcap", true)
+        } else if npcap::winpcap_installed() {
+            ("winpcap", true)
+        } else {
+            ("none", false)
+        };
+
+        let mut issues = Vec::new();
+        match backend {
+            "none" => issues.push("Npcap is not installed; install it from https://npcap.com to capture and scan".to_string()),
+            "winpcap" => issues.push("WinPcap is unmaintained and can't capture loopback traffic; Npcap is recommended".to_string()),
+            _ => {}
+        }
+
+        let is_npcap = backend == "npcap";
+        CaptureCapabilities {
+            backend: backend.to_string(),
+            version: is_npcap.then(npcap::version).flatten(),
+            installed,
+            can_capture: false,
+            loopback_capture: is_npcap && npcap::parameter("LoopbackSupport").unwrap_or(1) != 0,
+            admin_only: is_npcap && npcap::parameter("AdminOnly").unwrap_or(0) != 0,
+            issues,
+        }
+    }
+
+    #[cfg(not(windows))]
+    fn detect_backend() -> CaptureCapabilities {
+        CaptureCapabilities {
+            backend: "libpcap".to_string(),
+            version: None,
+            installed: true,
+            can_capture: false,
+            loopback_capture: false,
+            admin_only: false,
+            issues: Vec::new(),
+        }
+    }
+}
+
+fn ip_ethertype(packet: &[u8]) -> Option<u16> {
+    match packet.first()? >> 4 {
+        4 => Some(ETHERTYPE_IPV4),
+        6 => Some(ETHERTYPE_IPV6),
+        _ => None,
+    }
+}
+
+#[cfg(windows)]
+mod npcap {
+    use std::path::PathBuf;
+    use windows_sys::Win32::Foundation::ERROR_SUCCESS;
+    use windows_sys::Win32::System::Registry::{RegGetValueW, HKEY_LOCAL_MACHINE, RRF_RT_REG_DWORD, RRF_RT_REG_SZ};
+    use windows_sys::Win32::System::SystemInformation::GetSystemDirectoryW;
+
+    const PARAMETERS_KEY: &str = r"SYSTEM\CurrentControlSet\Services\npcap\Parameters";
+    // The installer is 32-bit, so on 64-bit Windows its uninstall entry is
+    // under WOW6432Node
+    const UNINSTALL_KEYS: &[&str] = &[
+        r"SOFTWARE\WOW6432Node\Microsoft\Windows\CurrentVersion\Uninstall\NpcapInst",
+        r"SOFTWARE\Microsoft\Windows\CurrentVersion\Uninstall\NpcapInst",
+    ];
+
+    pub fn wide(s: &str) -> Vec<u16> {
+        s.encode_utf16().chain(std::iter::once(0)).collect()
+    }
+
+    fn system_dir() -> Option<PathBuf> {
+        let mut buffer = [0u16; 260];
+        let len = unsafe { GetSystemDirectoryW(buffer.as_mut_ptr(), buffer.len() as u32) } as usize;
+        (len > 0 && len < buffer.len()).then(|| PathBuf::from(String::from_utf16_lossy(&buffer[..len])))
+    }
+
+    /// Npcap installs wpcap.dll under System32\Npcap unless it was set up
+    /// in WinPcap-compatible mode
+    pub fn install_dir() -> Option<PathBuf> {
+        let dir = system_dir()?.join("Npcap");
+        dir.join("wpcap.dll").is_file().then_some(dir)
+    }
+
+    pub fn winpcap_installed() -> bool {
+        system_dir().map_or(false, |dir| dir.join("wpcap.dll").is_file())
+    }
+
+    pub fn parameter(name: &str) -> Option<u32> {
+        let mut data = 0u32;
+        let mut size = std::mem::size_of::<u32>() as u32;
+        let status = unsafe {
+            RegGetValueW(
+                HKEY_LOCAL_MACHINE,
+                wide(PARAMETERS_KEY).as_ptr(),
+                wide(name).as_ptr(),
+                RRF_RT_REG_DWORD,
+                std::ptr::null_mut(),
+                &mut data as *mut u32 as *mut _,
+                &mut size,
+            )
+        };
+        (status == ERROR_SUCCESS).then_some(data)
+    }
+
+    pub fn version() -> Option<String> {
+        UNINSTALL_KEYS.iter().find_map(|key| {
+            let mut buffer = [0u16; 64];
+            let mut size = std::mem::size_of_val(&buffer) as u32;
+            let status = unsafe {
+                RegGetValueW(
+                    HKEY_LOCAL_MACHINE,
+                    wide(key).as_ptr(),
+                    wide("DisplayVersion").as_ptr(),
+                    RRF_RT_REG_SZ,
+                    std::ptr::null_mut(),
+                    buffer.as_mut_ptr() as *mut _,
+                    &mut size,
+                )
+            };
+            // size is in bytes and includes the terminator
+            let len = (size as usize / 2).saturating_sub(1);
+            (status == ERROR_SUCCESS).then(|| String::from_utf16_lossy(&buffer[..len]))
+        })
+    }
+}
\ No newline at end of file
@@ -0,0 +1,168 @@
+use super::packet::{format_mac, read_u16};
+use serde::{Deserialize, Serialize};
+use std::net::{IpAddr, Ipv4Addr};
+
+pub const DHCP_SERVER_PORT: u16 = 67;
+pub const DHCPV6_SERVER_PORT: u16 = 547;
+
+const DHCP_MAGIC_COOKIE: [u8; 4] = [0x63, 0x82, 0x53, 0x63];
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct DhcpRequest {
+    pub mac: String,
+    pub ip: Option<IpAddr>,
+    pub hostname: Option<String>,
+    pub vendor_class: Option<String>,
+    pub message_type: String,
+    /// 802.1Q tag of the frame, set by the analyzer
+    #[serde(default)]
+    pub vlan_id: Option<u16>,
+}
+
+pub struct DhcpParser;
+
+impl DhcpParser {
+    /// Parses a client-to-server DHCPv4 message. Only lease-related
+    /// requests (DISCOVER, REQUEST, INFORM) are returned.
+    pub fn parse_v4(payload: &[u8]) -> Option<DhcpRequest> {
+        if payload.len() < 240 || payload[0] != 1 || payload[236..240] != DHCP_MAGIC_COOKIE {
+            return None;
+        }
+
+        let hlen = (payload[2] as usize).min(16);
+        let mac = format_mac(&payload[28..28 + hlen]);
+        let ciaddr = Ipv4Addr::new(payload[12], payload[13], payload[14], payload[15]);
+
+        let mut message_type = None;
+        let mut requested_ip = None;
+        let mut hostname = None;
+        let mut vendor_class = None;
+
+        let mut i = 240;
+        while i < payload.len() {
+            let code = payload[i];
+            match code {
+                0 => { i += 1; continue; }
+                255 => break,
+                _ => {}
+            }
+
+            let len = *payload.get(i + 1)? as usize;
+            let body = payload.get(i + 2..i + 2 + len)?;
+
+            match code {
+                53 if len == 1 => message_type = Some(body[0]),
+                50 if len == 4 => requested_ip = Some(Ipv4Addr::new(body[0], body[1], body[2], body[3])),
+                12 => hostname = Self::printable(body),
+                60 => vendor_class = Self::printable(body),
+                _ => {}
+            }
+
+            i += 2 + len;
+        }
+
+        let message_type = match message_type? {
+            1 => "discover",
+            3 => "request",
+            8 => "inform",
+            _ => return None,
+        };
+
+        let ip = requested_ip
+            .or_else(|| (!ciaddr.is_unspecified()).then_some(ciaddr))
+            .map(IpAddr::V4);
+
+        Some(DhcpRequest {
+            mac,
+            ip,
+            hostname,
+            vendor_class,
+            message_type: message_type.to_string(),
+            vlan_id: None,
+        })
+    }
+
+    /// Parses a client-to-server DHCPv6 message. The client's link-local
+    /// source address stands in for the host IP, and the MAC comes from a
+    /// link-layer DUID when one is present.
+    pub fn parse_v6(payload: &[u8], src_ip: IpAddr, src_mac: &str) -> Option<DhcpRequest> {
+        let message_type = match *payload.first()? {
+            1 => "solicit",
+            3 => "request",
+            5 => "renew",
+            11 => "information-request",
+            _ => return None,
+        };
+
+        let mut mac = None;
+        let mut hostname = None;
+        let mut vendor_class = None;
+
+        let mut i = 4;
+        while i + 4 <= payload.len() {
+            let code = read_u16(payload, i)?;
+            let len = read_u16(payload, i + 2)? as usize;
+            let body = payload.get(i + 4..i + 4 + len)?;
+
+            match code {
+                1 => mac = Self::duid_mac(body),
+                16 if len > 6 => vendor_class = Self::printable(&body[6..]),
+                39 if len > 1 => hostname = Self::decode_dns_name(&body[1..]),
+                _ => {}
+            }
+
+            i += 4 + len;
+        }
+
+        Some(DhcpRequest {
+            mac: mac.unwrap_or_else(|| src_mac.to_string()),
+            ip: Some(src_ip),
+            hostname,
+            vendor_class,
+            message_type: message_type.to_string(),
+            vlan_id: None,
+        })
+    }
+
+    fn duid_mac(duid: &[u8]) -> Option<String> {
+        let duid_type = read_u16(duid, 0)?;
+        let hw_type = read_u16(duid, 2)?;
+        if hw_type != 1 {
+            return None;
+        }
+
+        let lladdr = match duid_type {
+            1 => duid.get(8..14)?,
+            3 => duid.get(4..10)?,
+            _ => return None,
+        };
+
+        Some(format_mac(lladdr))
+    }
+
+    fn decode_dns_name(data: &[u8]) -> Option<String> {
+        let mut labels = Vec::new();
+        let mut i = 0;
+
+        while let Some(&len) = data.get(i) {
+            if len == 0 {
+                break;
+            }
+            let label = data.get(i + 1..i + 1 + len as usize)?;
+            labels.push(String::from_utf8_lossy(label).to_string());
+            i += 1 + len as usize;
+        }
+
+        (!labels.is_empty()).then(|| labels.join("."))
+    }
+
+    fn printable(data: &[u8]) -> Option<String> {
+        let text: String = String::from_utf8_lossy(data)
+            .chars()
+            .filter(|c| !c.is_control())
+            .collect();
+
+        let text = text.trim();
+        (!text.is_empty()).then(|| text.to_string())
+    }
+}
@@ -0,0 +1,165 @@
+use super::packet::{read_u16, read_u32};
+use serde::{Deserialize, Serialize};
+use std::net::{IpAddr, Ipv4Addr, Ipv6Addr};
+
+pub const DNS_PORT: u16 = 53;
+pub const MDNS_PORT: u16 = 5353;
+
+const TYPE_A: u16 = 1;
+const TYPE_CNAME: u16 = 5;
+const TYPE_PTR: u16 = 12;
+const TYPE_AAAA: u16 = 28;
+
+/// Compression pointers followed before a name is taken to be looping
+const MAX_NAME_POINTERS: usize = 16;
+
+/// One record from the answer section of a response.
+#[derive(Debug, Clone, PartialEq, Eq, Hash, Serialize, Deserialize)]
+pub struct DnsAnswer {
+    /// Owner of the record, which differs from the question along a
+    /// CNAME chain
+    pub name: String,
+    /// `A`, `AAAA`, `CNAME` or `PTR`
+    pub record_type: String,
+    /// An address for A/AAAA records, a name for CNAME and PTR
+    pub value: String,
+    pub ttl: u32,
+}
+
+/// A resolution seen on the wire: who asked, who answered, and what.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct DnsResponse {
+    pub client: IpAddr,
+    pub server: IpAddr,
+    pub query: String,
+    pub answers: Vec<DnsAnswer>,
+}
+
+pub struct DnsParser;
+
+impl DnsParser {
+    /// Parses a successful DNS or mDNS response over UDP. Only address,
+    /// alias and pointer records are kept; a response without any is
+    /// `None`.
+    pub fn parse_response(payload: &[u8], server: IpAddr, client: IpAddr) -> Option<DnsResponse> {
+        let flags = read_u16(payload, 2)?;
+        let is_response = flags & 0x8000 != 0;
+        let rcode = flags & 0x000f;
+        if !is_response || rcode != 0 {
+            return None;
+        }
+
+        let questions = read_u16(payload, 4)?;
+        let answer_count = read_u16(payload, 6)?;
+
+        let mut offset = 12;
+        let mut query = None;
+        for _ in 0..questions {
+            let (name, next) = Self::read_name(payload, offset)?;
+            query.get_or_insert(name);
+            // Type and class
+            offset = next + 4;
+        }
+
+        let mut answers = Vec::new();
+        for _ in 0..answer_count {
+            let (name, next) = Self::read_name(payload, offset)?;
+            let record_type = read_u16(payload, next)?;
+            let ttl = read_u32(payload, next + 4)?;
+            let length = read_u16(payload, next + 8)? as usize;
+            let data_start = next + 10;
+            let data = payload.get(data_start..data_start + length)?;
+            offset = data_start + length;
+
+            let value = match record_type {
+                TYPE_A if length == 4 => Ipv4Addr::new(data[0], data[1], data[2], data[3]).to_string(),
+                TYPE_AAAA if length == 16 => {
+                    let octets: [u8; 16] = data.try_into().ok()?;
+                    Ipv6Addr::from(octets).to_string()
+                }
+                TYPE_CNAME | TYPE_PTR => Self::read_name(payload, data_start)?.0,
+                _ => continue,
+            };
+
+            answers.push(DnsAnswer {
+                name,
+                record_type: Self::type_name(record_type).to_string(),
+                value,
+                ttl,
+            });
+        }
+
+        if answers.is_empty() {
+            return None;
+        }
+
+        Some(DnsResponse {
+            client,
+            server,
+            // mDNS announcements carry answers without a question
+            query: query.unwrap_or_else(|| answers[0].name.clone()),
+            answers,
+        })
+    }
+
+    /// The `in-addr.arpa`/`ip6.arpa` name a PTR lookup of `ip` asks for
+    pub fn reverse_name(ip: IpAddr) -> String {
+        match ip {
+            IpAddr::V4(v4) => {
+                let [a, b, c, d] = v4.octets();
+                format!("{}.{}.{}.{}.in-addr.arpa", d, c, b, a)
+            }
+            IpAddr::V6(v6) => {
+                let nibbles: Vec<String> = v6.octets().iter().rev()
+                    .flat_map(|byte| [byte & 0x0f, byte >> 4])
+                    .map(|nibble| format!("{:x}", nibble))
+                    .collect();
+                format!("{}.ip6.arpa", nibbles.join("."))
+            }
+        }
+    }
+
+    fn type_name(record_type: u16) -> &'static str {
+        match record_type {
+            TYPE_A => "A",
+            TYPE_AAAA => "AAAA",
+            TYPE_CNAME => "CNAME",
+            _ => "PTR",
+        }
+    }
+
+    /// Reads a possibly compressed name, returning it lowercased with the
+    /// offset just past it in the record.
+    fn read_name(payload: &[u8], start: usize) -> Option<(String, usize)> {
+        let mut labels = Vec::new();
+        let mut offset = start;
+        let mut end = None;
+        let mut pointers = 0;
+
+        loop {
+            let len = *payload.get(offset)? as usize;
+            match len {
+                0 => {
+                    end.get_or_insert(offset + 1);
+                    break;
+                }
+                l if l & 0xc0 == 0xc0 => {
+                    pointers += 1;
+                    if pointers > MAX_NAME_POINTERS {
+                        return None;
+                    }
+                    end.get_or_insert(offset + 2);
+                    offset = (read_u16(payload, offset)? & 0x3fff) as usize;
+                }
+                l if l & 0xc0 != 0 => return None,
+                l => {
+                    let label = payload.get(offset + 1..offset + 1 + l)?;
+                    labels.push(String::from_utf8_lossy(label).to_lowercase());
+                    offset += 1 + l;
+                }
+            }
+        }
+
+        Some((labels.join("."), end?))
+    }
+}
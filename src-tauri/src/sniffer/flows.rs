@@ -0,0 +1,100 @@
+use super::packet::{IpPacket, Transport};
+use crate::utils::NetworkUtils;
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::net::IpAddr;
+use std::time::Duration;
+
+/// How often accumulated counters are handed to the pipeline
+pub const FLOW_FLUSH_INTERVAL: Duration = Duration::from_secs(30);
+/// Flows held between flushes; a busier capture flushes early
+const MAX_TRACKED_FLOWS: usize = 65_536;
+
+/// Traffic between a client and one service of a server since the last
+/// flush.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct FlowStats {
+    pub client: IpAddr,
+    pub server: IpAddr,
+    /// `tcp` or `udp`
+    pub protocol: String,
+    pub server_port: u16,
+    pub packets: u64,
+    pub bytes: u64,
+    pub first_seen: DateTime<Utc>,
+    pub last_seen: DateTime<Utc>,
+}
+
+/// Counts packets and bytes per conversation, so one event per interval
+/// reaches storage rather than one per packet.
+pub struct FlowTable {
+    flows: HashMap<(IpAddr, IpAddr, &'static str, u16), FlowStats>,
+    /// Capture time of the last flush, which for imported captures is
+    /// not the time they're read at
+    last_flush: Option<DateTime<Utc>>,
+}
+
+impl FlowTable {
+    pub fn new() -> Self {
+        Self {
+            flows: HashMap::new(),
+            last_flush: None,
+        }
+    }
+
+    /// The side on the lower port is taken to be the server. Only flows
+    /// with a host on a private network are counted; traffic between two
+    /// Internet peers isn't about any assessed host.
+    pub fn observe(&mut self, ip: &IpPacket, transport: &Transport, at: DateTime<Utc>) {
+        let (protocol, src_port, dst_port) = match transport {
+            Transport::Tcp(tcp) => ("tcp", tcp.src_port, tcp.dst_port),
+            Transport::Udp(udp) => ("udp", udp.src_port, udp.dst_port),
+            Transport::Other => return,
+        };
+        if !NetworkUtils::is_private_ip(&ip.src) && !NetworkUtils::is_private_ip(&ip.dst) {
+            return;
+        }
+
+        let (client, server, server_port) = if src_port < dst_port {
+            (ip.dst, ip.src, src_port)
+        } else {
+            (ip.src, ip.dst, dst_port)
+        };
+
+        self.last_flush.get_or_insert(at);
+        let flow = self.flows.entry((client, server, protocol, server_port)).or_insert_with(|| FlowStats {
+            client,
+            server,
+            protocol: protocol.to_string(),
+            server_port,
+            packets: 0,
+            bytes: 0,
+            first_seen: at,
+            last_seen: at,
+        });
+        flow.packets += 1;
+        flow.bytes += ip.total_len as u64;
+        flow.last_seen = at;
+    }
+
+    /// Hands over the counters once the flush interval has passed or the
+    /// table is full, or at once when `force`d at the end of a capture.
+    pub fn take(&mut self, force: bool, now: DateTime<Utc>) -> Option<Vec<FlowStats>> {
+        let due = self.last_flush.is_some_and(|last| (now - last).num_seconds() >= FLOW_FLUSH_INTERVAL.as_secs() as i64)
+            || self.flows.len() >= MAX_TRACKED_FLOWS;
+        if !force && !due {
+            return None;
+        }
+        self.last_flush = Some(now);
+
+        let flows: Vec<FlowStats> = self.flows.drain().map(|(_, flow)| flow).collect();
+        (!flows.is_empty()).then_some(flows)
+    }
+}
+
+impl Default for FlowTable {
+    fn default() -> Self {
+        Self::new()
+    }
+}
@@ -0,0 +1,222 @@
+use super::capture::CaptureBackend;
+use crate::database::models::PacketCapture;
+use crate::database::operations::{PacketCaptureOperations, CAPTURE_COMPLETED, CAPTURE_FAILED, CAPTURE_STOPPED};
+use crate::error::LegionError;
+use anyhow::Result;
+use sqlx::SqlitePool;
+use std::collections::{HashMap, VecDeque};
+use std::net::IpAddr;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant};
+
+/// Longest a capture may run
+pub const MAX_CAPTURE_DURATION: Duration = Duration::from_secs(3600);
+/// Packet data kept per capture. Older packets are dropped first, so a
+/// busy host's capture ends with its most recent traffic.
+pub const CAPTURE_RING_BYTES: usize = 64 * 1024 * 1024;
+
+const PCAP_MAGIC: u32 = 0xa1b2c3d4;
+const PCAP_SNAPLEN: u32 = 65535;
+
+/// Traffic to and from one host, captured for a fixed time.
+pub struct HostCaptureRequest {
+    pub host_id: String,
+    pub ip: IpAddr,
+    pub interface: String,
+    pub duration: Duration,
+    /// Narrows the capture further, in BPF syntax
+    pub bpf_filter: Option<String>,
+}
+
+struct CapturedPacket {
+    ts_sec: u32,
+    ts_usec: u32,
+    original_len: u32,
+    data: Vec<u8>,
+}
+
+/// Captured packets, bounded by their total size.
+struct PacketRing {
+    packets: VecDeque<CapturedPacket>,
+    bytes: usize,
+    limit: usize,
+    evicted: usize,
+}
+
+impl PacketRing {
+    fn new(limit: usize) -> Self {
+        Self {
+            packets: VecDeque::new(),
+            bytes: 0,
+            limit,
+            evicted: 0,
+        }
+    }
+
+    fn push(&mut self, packet: CapturedPacket) {
+        self.bytes += packet.data.len();
+        self.packets.push_back(packet);
+
+        while self.bytes > self.limit {
+            let Some(oldest) = self.packets.pop_front() else {
+                break;
+            };
+            self.bytes -= oldest.data.len();
+            self.evicted += 1;
+        }
+    }
+
+    /// The packets as a classic pcap file, which every version of
+    /// Wireshark and tcpdump reads.
+    fn to_pcap(&self, linktype: i32) -> Vec<u8> {
+        let mut pcap = Vec::with_capacity(24 + self.bytes + self.packets.len() * 16);
+        pcap.extend_from_slice(&PCAP_MAGIC.to_le_bytes());
+        pcap.extend_from_slice(&2u16.to_le_bytes());
+        pcap.extend_from_slice(&4u16.to_le_bytes());
+        // Timezone offset and timestamp accuracy, both always zero
+        pcap.extend_from_slice(&[0u8; 8]);
+        pcap.extend_from_slice(&PCAP_SNAPLEN.to_le_bytes());
+        pcap.extend_from_slice(&(linktype as u32).to_le_bytes());
+
+        for packet in &self.packets {
+            pcap.extend_from_slice(&packet.ts_sec.to_le_bytes());
+            pcap.extend_from_slice(&packet.ts_usec.to_le_bytes());
+            pcap.extend_from_slice(&(packet.data.len() as u32).to_le_bytes());
+            pcap.extend_from_slice(&packet.original_len.to_le_bytes());
+            pcap.extend_from_slice(&packet.data);
+        }
+
+        pcap
+    }
+}
+
+/// On-demand captures of single hosts, stored as pcap files with the
+/// project. They run independently of the sniffer, which can keep
+/// running alongside.
+#[derive(Default)]
+pub struct HostCaptures {
+    running: Arc<Mutex<HashMap<String, Arc<AtomicBool>>>>,
+}
+
+impl HostCaptures {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// The host's traffic, narrowed by `bpf_filter` when one is given.
+    /// The extra filter must keep its parentheses balanced, so it can't
+    /// close the group and widen the capture past the host, as
+    /// `tcp) or (udp` would.
+    pub fn filter(ip: IpAddr, bpf_filter: Option<&str>) -> Result<String> {
+        let Some(extra) = bpf_filter.map(str::trim).filter(|f| !f.is_empty()) else {
+            return Ok(format!("host {}", ip));
+        };
+
+        let mut depth = 0usize;
+        for c in extra.chars() {
+            match c {
+                '(' => depth += 1,
+                ')' => {
+                    depth = depth.checked_sub(1).ok_or_else(|| {
+                        LegionError::Validation(format!("Unbalanced parentheses in capture filter '{}'", extra))
+                    })?;
+                }
+                _ => {}
+            }
+        }
+        if depth != 0 {
+            return Err(LegionError::Validation(format!("Unbalanced parentheses in capture filter '{}'", extra)).into());
+        }
+
+        Ok(format!("host {} and ({})", ip, extra))
+    }
+
+    /// Starts a capture and returns its record. The pcap file is stored
+    /// once the duration has passed or the capture is stopped.
+    pub async fn start(&self, pool: SqlitePool, request: HostCaptureRequest) -> Result<PacketCapture> {
+        if request.duration.is_zero() || request.duration > MAX_CAPTURE_DURATION {
+            return Err(LegionError::Validation(format!(
+                "Capture duration must be between 1 and {} seconds",
+                MAX_CAPTURE_DURATION.as_secs(),
+            )).into());
+        }
+
+        let filter = Self::filter(request.ip, request.bpf_filter.as_deref())?;
+        let mut capture = CaptureBackend::open(&request.interface)?;
+        capture.filter(&filter, true)
+            .map_err(|e| LegionError::Validation(format!("Invalid capture filter '{}': {}", filter, e)))?;
+
+        let record = PacketCaptureOperations::create(&pool, &request.host_id, &request.interface, &filter).await?;
+        let stop = Arc::new(AtomicBool::new(false));
+        self.running.lock().unwrap().insert(record.id.clone(), stop.clone());
+
+        let running = self.running.clone();
+        let capture_id = record.id.clone();
+        let duration = request.duration;
+        tokio::spawn(async move {
+            let captured = tokio::task::spawn_blocking(move || {
+                let linktype = capture.get_datalink().0;
+                let deadline = Instant::now() + duration;
+                let mut ring = PacketRing::new(CAPTURE_RING_BYTES);
+
+                while !stop.load(Ordering::SeqCst) && Instant::now() < deadline {
+                    match capture.next_packet() {
+                        Ok(packet) => ring.push(CapturedPacket {
+                            ts_sec: packet.header.ts.tv_sec as u32,
+                            ts_usec: packet.header.ts.tv_usec as u32,
+                            original_len: packet.header.len,
+                            data: packet.data.to_vec(),
+                        }),
+                        Err(pcap::Error::TimeoutExpired) => {}
+                        Err(e) => return (ring, linktype, Err(e.to_string())),
+                    }
+                }
+
+                let stopped = stop.load(Ordering::SeqCst);
+                (ring, linktype, Ok(stopped))
+            }).await;
+
+            // What was captured before a failure is kept too
+            let (status, pcap, packets, evicted, error) = match captured {
+                Ok((ring, linktype, outcome)) => {
+                    let (status, error) = match outcome {
+                        Ok(true) => (CAPTURE_STOPPED, None),
+                        Ok(false) => (CAPTURE_COMPLETED, None),
+                        Err(e) => (CAPTURE_FAILED, Some(e)),
+                    };
+                    (status, Some(ring.to_pcap(linktype)), ring.packets.len(), ring.evicted, error)
+                }
+                Err(e) => (CAPTURE_FAILED, None, 0, 0, Some(e.to_string())),
+            };
+
+            let finished = PacketCaptureOperations::finish(
+                &pool,
+                &capture_id,
+                status,
+                packets,
+                evicted,
+                pcap.as_deref(),
+                error.as_deref(),
+            ).await;
+            if let Err(e) = finished {
+                eprintln!("Failed to store capture {}: {}", capture_id, e);
+            }
+            running.lock().unwrap().remove(&capture_id);
+        });
+
+        Ok(record)
+    }
+
+    /// Ends a running capture early, keeping what it captured. Returns
+    /// whether it was running.
+    pub fn stop(&self, capture_id: &str) -> bool {
+        match self.running.lock().unwrap().get(capture_id) {
+            Some(stop) => {
+                stop.store(true, Ordering::SeqCst);
+                true
+            }
+            None => false,
+        }
+    }
+}
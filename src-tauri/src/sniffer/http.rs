@@ -0,0 +1,103 @@
+use serde::{Deserialize, Serialize};
+use std::net::IpAddr;
+
+const REQUEST_METHODS: &[&str] = &["GET", "POST", "HEAD", "PUT", "DELETE", "OPTIONS", "PATCH", "CONNECT"];
+/// Header values longer than this are cut, bounding what a hostile peer
+/// can make the sniffer store
+const MAX_HEADER_VALUE: usize = 256;
+
+/// The identifying headers of a cleartext HTTP request.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct HttpRequest {
+    pub client: IpAddr,
+    pub server: IpAddr,
+    pub server_port: u16,
+    /// The virtual host asked for
+    pub host: Option<String>,
+    pub user_agent: Option<String>,
+}
+
+/// The identifying headers of a cleartext HTTP response.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct HttpResponse {
+    pub server: IpAddr,
+    pub server_port: u16,
+    pub client: IpAddr,
+    /// The `Server` header, e.g. `Apache/2.4.41 (Ubuntu)`
+    pub software: Option<String>,
+    /// The `X-Powered-By` header, e.g. `PHP/7.2.24`
+    pub powered_by: Option<String>,
+}
+
+pub struct HttpParser;
+
+impl HttpParser {
+    /// Parses the head of a request at the start of a TCP payload. A
+    /// request without any of the headers of interest is `None`.
+    pub fn parse_request(payload: &[u8], client: IpAddr, server: IpAddr, server_port: u16) -> Option<HttpRequest> {
+        let (start, headers) = Self::head(payload)?;
+        let mut parts = start.split(' ');
+        let method = parts.next()?;
+        if !REQUEST_METHODS.contains(&method) || !parts.last()?.starts_with("HTTP/1.") {
+            return None;
+        }
+
+        let host = Self::header(&headers, "host").map(|host| host.to_lowercase());
+        let user_agent = Self::header(&headers, "user-agent");
+        if host.is_none() && user_agent.is_none() {
+            return None;
+        }
+
+        Some(HttpRequest {
+            client,
+            server,
+            server_port,
+            host,
+            user_agent,
+        })
+    }
+
+    /// Parses the head of a response at the start of a TCP payload. A
+    /// response that names no software is `None`.
+    pub fn parse_response(payload: &[u8], server: IpAddr, server_port: u16, client: IpAddr) -> Option<HttpResponse> {
+        let (start, headers) = Self::head(payload)?;
+        if !start.starts_with("HTTP/1.") {
+            return None;
+        }
+
+        let software = Self::header(&headers, "server");
+        let powered_by = Self::header(&headers, "x-powered-by");
+        if software.is_none() && powered_by.is_none() {
+            return None;
+        }
+
+        Some(HttpResponse {
+            server,
+            server_port,
+            client,
+            software,
+            powered_by,
+        })
+    }
+
+    /// The start line and header lines, as far as the segment goes
+    fn head(payload: &[u8]) -> Option<(&str, Vec<&str>)> {
+        let end = payload.windows(4)
+            .position(|w| w == b"\r\n\r\n")
+            .unwrap_or(payload.len());
+        let head = std::str::from_utf8(&payload[..end]).ok()?;
+
+        let mut lines = head.split("\r\n");
+        let start = lines.next().filter(|line| !line.is_empty())?;
+        Some((start, lines.collect()))
+    }
+
+    fn header(headers: &[&str], name: &str) -> Option<String> {
+        headers.iter()
+            .filter_map(|line| line.split_once(':'))
+            .find(|(key, _)| key.trim().eq_ignore_ascii_case(name))
+            .map(|(_, value)| value.trim())
+            .filter(|value| !value.is_empty())
+            .map(|value| value.chars().take(MAX_HEADER_VALUE).collect())
+    }
+}
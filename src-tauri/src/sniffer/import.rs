@@ -0,0 +1,84 @@
+use super::capture::CaptureBackend;
+use super::PacketAnalyzer;
+use crate::sources::{Observation, ObservationStream, Source, SourceRuns};
+use anyhow::Result;
+use chrono::{DateTime, Utc};
+use futures::future::BoxFuture;
+use serde::{Deserialize, Serialize};
+use std::net::Ipv4Addr;
+use std::path::PathBuf;
+use std::sync::Arc;
+use uuid::Uuid;
+
+/// A capture file to analyze as if it were sniffed live.
+pub struct PcapImportRequest {
+    pub path: PathBuf,
+    /// Gateways already known, watched for spoofing
+    pub gateways: Vec<Ipv4Addr>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct PcapImportSummary {
+    pub path: String,
+    /// Sniffer events stored from the capture
+    pub events: usize,
+}
+
+/// Runs pcap and pcapng files through the sniffer's analysis, for
+/// captures taken on networks the app couldn't run on. Frames are
+/// analyzed at their capture time rather than the time they're read.
+#[derive(Default)]
+pub struct PcapImporter {
+    runs: SourceRuns,
+}
+
+impl Source for PcapImporter {
+    type Request = PcapImportRequest;
+
+    fn name(&self) -> &'static str {
+        "pcap"
+    }
+
+    fn start(self: Arc<Self>, run_id: Uuid, request: PcapImportRequest) -> BoxFuture<'static, Result<ObservationStream>> {
+        Box::pin(async move {
+            let mut capture = CaptureBackend::open_file(&request.path)?;
+            let gateways = request.gateways;
+
+            Ok(self.runs.spawn(run_id, self.name(), move |sink| async move {
+                tokio::task::spawn_blocking(move || {
+                    let linktype = capture.get_datalink();
+                    let mut analyzer = PacketAnalyzer::new(gateways);
+
+                    loop {
+                        let packet = match capture.next_packet() {
+                            Ok(packet) => packet,
+                            Err(pcap::Error::NoMorePackets) => break,
+                            Err(e) => return Err(anyhow::anyhow!("Failed to read capture: {}", e)),
+                        };
+                        let Some(frame) = CaptureBackend::normalize(linktype, packet.data) else {
+                            continue;
+                        };
+                        let ts = packet.header.ts;
+                        let at = DateTime::from_timestamp(ts.tv_sec as i64, ts.tv_usec as u32 * 1000)
+                            .unwrap_or_else(Utc::now);
+
+                        for event in analyzer.analyze_at(&frame, at) {
+                            if !sink.blocking_send(Observation::Passive(event)) {
+                                return Ok(());
+                            }
+                        }
+                    }
+
+                    if let Some(event) = analyzer.flush_flows(true) {
+                        sink.blocking_send(Observation::Passive(event));
+                    }
+                    Ok(())
+                }).await?
+            }))
+        })
+    }
+
+    fn cancel(&self, run_id: Uuid) -> bool {
+        self.runs.cancel(run_id)
+    }
+}
@@ -0,0 +1,517 @@
+pub mod arp;
+pub mod capture;
+pub mod dhcp;
+pub mod dns;
+pub mod flows;
+pub mod host_capture;
+pub mod http;
+pub mod import;
+pub mod p0f;
+pub mod packet;
+pub mod pipeline;
+pub mod tls;
+
+use arp::{ArpAlert, ArpWatch};
+use capture::CaptureBackend;
+use dhcp::{DhcpParser, DhcpRequest, DHCP_SERVER_PORT, DHCPV6_SERVER_PORT};
+use dns::{DnsParser, DnsResponse, DNS_PORT, MDNS_PORT};
+use flows::{FlowStats, FlowTable};
+use http::{HttpParser, HttpRequest, HttpResponse};
+use p0f::{PassiveFingerprinter, PassiveOsGuess, SynFingerprint};
+use packet::{format_mac, ParsedPacket, Transport};
+use tls::{TlsClientHello, TlsParser, TlsServerHello};
+use crate::database::models::Host;
+use crate::helper::{HelperEndpoint, PrivilegedHelper};
+use crate::sources::{Observation, ObservationSink, ObservationStream, Source, SourceRuns};
+use crate::utils::{BoundedReceiver, NetworkUtils};
+use anyhow::{Result, bail};
+use chrono::{DateTime, Utc};
+use futures::future::BoxFuture;
+use serde::{Deserialize, Serialize};
+use std::collections::{HashMap, HashSet};
+use std::net::{IpAddr, Ipv4Addr};
+use std::sync::Arc;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::time::{Duration, Instant};
+use uuid::Uuid;
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub enum SnifferEvent {
+    PassiveOs {
+        ip: IpAddr,
+        guess: PassiveOsGuess,
+        /// 802.1Q tag of the frame, on trunk-port captures
+        #[serde(default)]
+        vlan_id: Option<u16>,
+    },
+    DhcpRequest(DhcpRequest),
+    /// An address and MAC announced in ARP, once per pair
+    Neighbour {
+        ip: IpAddr,
+        mac: String,
+        #[serde(default)]
+        vlan_id: Option<u16>,
+    },
+    /// A TCP port answering a SYN, once per port
+    OpenPort {
+        ip: IpAddr,
+        port: u16,
+        #[serde(default)]
+        vlan_id: Option<u16>,
+    },
+    /// A ClientHello with a server, name or JA3 not seen before in this
+    /// capture
+    TlsHello(TlsClientHello),
+    /// A ServerHello with a JA3S not seen before from the server
+    TlsServerHello(TlsServerHello),
+    /// Cleartext HTTP headers naming hosts or software, once per
+    /// combination
+    HttpRequest(HttpRequest),
+    HttpResponse(HttpResponse),
+    /// DNS and mDNS answers not seen before in this capture
+    DnsResponse(DnsResponse),
+    /// Packet and byte counts per conversation since the last flush
+    Flows(Vec<FlowStats>),
+    /// Two hosts on private networks seen talking, once per pair
+    Traffic {
+        src: IpAddr,
+        dst: IpAddr,
+    },
+    HostDiscovered(Host),
+    RogueDevice(Host),
+    ArpSpoofing(ArpAlert),
+}
+
+pub struct NetSniffer {
+    running: Arc<AtomicBool>,
+    runs: SourceRuns,
+}
+
+/// A capture on one interface. Without the privileges to capture
+/// directly, the privileged helper captures instead when it is running.
+pub struct SnifferRequest {
+    pub interface: String,
+    pub helper: Option<HelperEndpoint>,
+    /// Gateways already known, watched for spoofing from the start
+    pub gateways: Vec<Ipv4Addr>,
+}
+
+impl NetSniffer {
+    pub fn new() -> Self {
+        Self {
+            running: Arc::new(AtomicBool::new(false)),
+            runs: SourceRuns::default(),
+        }
+    }
+
+    pub fn is_running(&self) -> bool {
+        self.running.load(Ordering::SeqCst)
+    }
+
+    async fn analyze_helper_frames(
+        mut frames: BoundedReceiver<Vec<u8>>,
+        running: Arc<AtomicBool>,
+        sink: ObservationSink,
+        gateways: Vec<Ipv4Addr>,
+    ) -> Result<()> {
+        let mut analyzer = PacketAnalyzer::new(gateways);
+
+        while running.load(Ordering::SeqCst) {
+            // Wake up on quiet networks to notice a stop
+            let frame = match tokio::time::timeout(Duration::from_millis(500), frames.recv()).await {
+                Ok(Some(frame)) => frame,
+                Ok(None) => {
+                    eprintln!("Packet capture through the helper stopped");
+                    break;
+                }
+                Err(_) => {
+                    if let Some(event) = analyzer.flush_flows(false) {
+                        if !sink.send(Observation::Passive(event)).await {
+                            break;
+                        }
+                    }
+                    continue;
+                }
+            };
+
+            for event in analyzer.analyze(&frame) {
+                if !sink.send(Observation::Passive(event)).await {
+                    running.store(false, Ordering::SeqCst);
+                    return Ok(());
+                }
+            }
+        }
+
+        if let Some(event) = analyzer.flush_flows(true) {
+            sink.send(Observation::Passive(event)).await;
+        }
+        running.store(false, Ordering::SeqCst);
+        Ok(())
+    }
+
+    pub fn stop(&self) {
+        self.running.store(false, Ordering::SeqCst);
+    }
+}
+
+impl Default for NetSniffer {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl Source for NetSniffer {
+    type Request = SnifferRequest;
+
+    fn name(&self) -> &'static str {
+        "passive"
+    }
+
+    /// Packets are analyzed on a blocking thread and observed as they
+    /// change what is known about a host. One capture runs at a time.
+    fn start(self: Arc<Self>, run_id: Uuid, request: SnifferRequest) -> BoxFuture<'static, Result<ObservationStream>> {
+        Box::pin(async move {
+            if self.running.swap(true, Ordering::SeqCst) {
+                bail!("Sniffer is already running");
+            }
+
+            let running = self.running.clone();
+            let gateways = request.gateways;
+            let stream = match (CaptureBackend::open(&request.interface), request.helper) {
+                (Ok(mut capture), _) => self.runs.spawn(run_id, self.name(), move |sink| async move {
+                    let linktype = capture.get_datalink();
+
+                    tokio::task::spawn_blocking(move || {
+                        let mut analyzer = PacketAnalyzer::new(gateways);
+
+                        while running.load(Ordering::SeqCst) {
+                            match capture.next_packet() {
+                                Ok(packet) => {
+                                    let Some(frame) = CaptureBackend::normalize(linktype, packet.data) else {
+                                        continue;
+                                    };
+                                    for event in analyzer.analyze(&frame) {
+                                        if !sink.blocking_send(Observation::Passive(event)) {
+                                            running.store(false, Ordering::SeqCst);
+                                            return;
+                                        }
+                                    }
+                                }
+                                Err(pcap::Error::TimeoutExpired) => {
+                                    if let Some(event) = analyzer.flush_flows(false) {
+                                        if !sink.blocking_send(Observation::Passive(event)) {
+                                            break;
+                                        }
+                                    }
+                                }
+                                Err(e) => {
+                                    eprintln!("Packet capture stopped: {}", e);
+                                    break;
+                                }
+                            }
+                        }
+
+                        if let Some(event) = analyzer.flush_flows(true) {
+                            sink.blocking_send(Observation::Passive(event));
+                        }
+                        running.store(false, Ordering::SeqCst);
+                    }).await?;
+
+                    Ok::<_, anyhow::Error>(())
+                }),
+                (Err(_), Some(helper)) => {
+                    let frames = match PrivilegedHelper::capture(&helper, &request.interface).await {
+                        Ok(frames) => frames,
+                        Err(e) => {
+                            self.running.store(false, Ordering::SeqCst);
+                            return Err(e);
+                        }
+                    };
+                    self.runs.spawn(run_id, self.name(), move |sink| Self::analyze_helper_frames(frames, running, sink, gateways))
+                }
+                (Err(e), None) => {
+                    self.running.store(false, Ordering::SeqCst);
+                    return Err(e);
+                }
+            };
+
+            Ok(stream)
+        })
+    }
+
+    fn cancel(&self, run_id: Uuid) -> bool {
+        self.stop();
+        self.runs.cancel(run_id)
+    }
+}
+
+/// Conversations remembered before the set is cleared, bounding memory on
+/// busy networks at the cost of reporting some pairs again
+const MAX_TRACKED_CONVERSATIONS: usize = 65_536;
+/// The same for DNS answers
+const MAX_TRACKED_DNS_ANSWERS: usize = 65_536;
+/// The same for ARP neighbours, open ports, TLS hellos and HTTP headers
+const MAX_TRACKED_SERVICES: usize = 65_536;
+
+/// Turns raw frames into `SnifferEvent`s, suppressing repeats so the
+/// pipeline only sees changes.
+pub struct PacketAnalyzer {
+    os_signatures: HashMap<(IpAddr, Option<u16>), String>,
+    conversations: HashSet<(IpAddr, IpAddr)>,
+    /// Answers already reported, without their TTL, which counts down
+    /// in cached responses
+    dns_answers: HashSet<(String, String, String)>,
+    neighbours: HashSet<(Ipv4Addr, [u8; 6], Option<u16>)>,
+    open_ports: HashSet<(IpAddr, u16)>,
+    tls_clients: HashSet<(IpAddr, IpAddr, u16, Option<String>, String)>,
+    tls_servers: HashSet<(IpAddr, u16, String)>,
+    http_requests: HashSet<(IpAddr, IpAddr, u16, Option<String>, Option<String>)>,
+    http_responses: HashSet<(IpAddr, u16, Option<String>, Option<String>)>,
+    arp_watch: ArpWatch,
+    flows: FlowTable,
+    /// Capture time and instant of the first frame, so the windows in
+    /// `ArpWatch` run on capture time for imported captures too
+    origin: Option<(DateTime<Utc>, Instant)>,
+}
+
+impl PacketAnalyzer {
+    pub fn new(gateways: Vec<Ipv4Addr>) -> Self {
+        Self {
+            os_signatures: HashMap::new(),
+            conversations: HashSet::new(),
+            dns_answers: HashSet::new(),
+            neighbours: HashSet::new(),
+            open_ports: HashSet::new(),
+            tls_clients: HashSet::new(),
+            tls_servers: HashSet::new(),
+            http_requests: HashSet::new(),
+            http_responses: HashSet::new(),
+            arp_watch: ArpWatch::new(gateways),
+            flows: FlowTable::new(),
+            origin: None,
+        }
+    }
+
+    pub fn analyze(&mut self, data: &[u8]) -> Vec<SnifferEvent> {
+        self.analyze_at(data, Utc::now())
+    }
+
+    /// Analyzes a frame captured at `at`, as read from a capture file.
+    pub fn analyze_at(&mut self, data: &[u8], at: DateTime<Utc>) -> Vec<SnifferEvent> {
+        let mut events = Vec::new();
+
+        let Some(packet) = ParsedPacket::parse(data) else {
+            return events;
+        };
+
+        if let Some(arp) = &packet.arp {
+            let now = self.instant(at);
+            if let Some(alert) = self.arp_watch.observe(arp, now) {
+                events.push(SnifferEvent::ArpSpoofing(alert));
+            }
+            events.extend(self.new_neighbour(arp, packet.ethernet.vlan_id));
+        }
+        if packet.ip.as_ref().is_some_and(|ip| !NetworkUtils::is_private_ip(&ip.src)) {
+            self.arp_watch.observe_routed(packet.ethernet.src_mac);
+        }
+
+        if let Some(ip) = &packet.ip {
+            self.flows.observe(ip, &packet.transport, at);
+        }
+        if let Some(event) = packet.ip.as_ref().and_then(|ip| self.track_conversation(ip)) {
+            events.push(event);
+        }
+
+        match (&packet.ip, &packet.transport) {
+            (Some(ip), Transport::Tcp(tcp)) => {
+                events.extend(self.fingerprint_syn(ip, tcp, packet.ethernet.vlan_id));
+                events.extend(self.new_open_port(ip, tcp, packet.ethernet.vlan_id));
+                events.extend(self.new_tls_hello(ip, tcp));
+                events.extend(self.new_http_headers(ip, tcp));
+            }
+            (Some(ip), Transport::Udp(udp)) if udp.src_port == DNS_PORT || udp.src_port == MDNS_PORT => {
+                if let Some(event) = self.new_dns_answers(ip, udp) {
+                    events.push(event);
+                }
+            }
+            (Some(ip), Transport::Udp(udp)) => {
+                let request = match udp.dst_port {
+                    DHCP_SERVER_PORT => DhcpParser::parse_v4(udp.payload),
+                    DHCPV6_SERVER_PORT => DhcpParser::parse_v6(udp.payload, ip.src, &packet.src_mac()),
+                    _ => None,
+                };
+                if let Some(mut request) = request {
+                    request.vlan_id = packet.ethernet.vlan_id;
+                    events.push(SnifferEvent::DhcpRequest(request));
+                }
+            }
+            _ => {}
+        }
+
+        events.extend(self.flows.take(false, at).map(SnifferEvent::Flows));
+        events
+    }
+
+    /// Flow counters, once they're due or when `force`d as a capture ends
+    pub fn flush_flows(&mut self, force: bool) -> Option<SnifferEvent> {
+        self.flows.take(force, Utc::now()).map(SnifferEvent::Flows)
+    }
+
+    fn instant(&mut self, at: DateTime<Utc>) -> Instant {
+        let (start, started) = *self.origin.get_or_insert((at, Instant::now()));
+        started + (at - start).to_std().unwrap_or_default()
+    }
+
+    fn new_neighbour(&mut self, arp: &packet::ArpPacket, vlan_id: Option<u16>) -> Option<SnifferEvent> {
+        let ip = IpAddr::V4(arp.sender_ip);
+        if arp.sender_ip.is_unspecified() || !NetworkUtils::is_private_ip(&ip) {
+            return None;
+        }
+
+        if self.neighbours.len() >= MAX_TRACKED_SERVICES {
+            self.neighbours.clear();
+        }
+        if !self.neighbours.insert((arp.sender_ip, arp.sender_mac, vlan_id)) {
+            return None;
+        }
+
+        Some(SnifferEvent::Neighbour {
+            ip,
+            mac: format_mac(&arp.sender_mac),
+            vlan_id,
+        })
+    }
+
+    /// A SYN-ACK shows the port open without anyone having to scan it
+    fn new_open_port(&mut self, ip: &packet::IpPacket, tcp: &packet::TcpSegment, vlan_id: Option<u16>) -> Option<SnifferEvent> {
+        if !tcp.is_syn_ack() || !NetworkUtils::is_private_ip(&ip.src) {
+            return None;
+        }
+
+        if self.open_ports.len() >= MAX_TRACKED_SERVICES {
+            self.open_ports.clear();
+        }
+        if !self.open_ports.insert((ip.src, tcp.src_port)) {
+            return None;
+        }
+
+        Some(SnifferEvent::OpenPort {
+            ip: ip.src,
+            port: tcp.src_port,
+            vlan_id,
+        })
+    }
+
+    fn new_tls_hello(&mut self, ip: &packet::IpPacket, tcp: &packet::TcpSegment) -> Option<SnifferEvent> {
+        if let Some(hello) = TlsParser::parse_server_hello(tcp.payload, ip.src, tcp.src_port, ip.dst) {
+            if self.tls_servers.len() >= MAX_TRACKED_SERVICES {
+                self.tls_servers.clear();
+            }
+            let new = self.tls_servers.insert((hello.server, hello.server_port, hello.ja3s.clone()));
+            return new.then_some(SnifferEvent::TlsServerHello(hello));
+        }
+
+        let hello = TlsParser::parse_client_hello(tcp.payload, ip.src, ip.dst, tcp.dst_port)?;
+
+        if self.tls_clients.len() >= MAX_TRACKED_SERVICES {
+            self.tls_clients.clear();
+        }
+        let key = (hello.client, hello.server, hello.server_port, hello.server_name.clone(), hello.ja3.clone());
+        if !self.tls_clients.insert(key) {
+            return None;
+        }
+
+        Some(SnifferEvent::TlsHello(hello))
+    }
+
+    /// Only unicast traffic between private addresses is a link between
+    /// hosts; Internet peers are not assessment targets.
+    fn track_conversation(&mut self, ip: &packet::IpPacket) -> Option<SnifferEvent> {
+        // .255 is nearly always a /24's directed broadcast
+        let unicast = |addr: &IpAddr| match addr {
+            IpAddr::V4(v4) => !v4.is_multicast() && !v4.is_broadcast() && v4.octets()[3] != 255,
+            IpAddr::V6(v6) => !v6.is_multicast(),
+        };
+        let linked = |addr: &IpAddr| unicast(addr) && !addr.is_unspecified() && NetworkUtils::is_private_ip(addr);
+        if ip.src == ip.dst || !linked(&ip.src) || !linked(&ip.dst) {
+            return None;
+        }
+
+        let pair = if ip.src <= ip.dst { (ip.src, ip.dst) } else { (ip.dst, ip.src) };
+        if self.conversations.contains(&pair) {
+            return None;
+        }
+        if self.conversations.len() >= MAX_TRACKED_CONVERSATIONS {
+            self.conversations.clear();
+        }
+        self.conversations.insert(pair);
+
+        Some(SnifferEvent::Traffic {
+            src: ip.src,
+            dst: ip.dst,
+        })
+    }
+
+    fn new_http_headers(&mut self, ip: &packet::IpPacket, tcp: &packet::TcpSegment) -> Option<SnifferEvent> {
+        if tcp.payload.is_empty() {
+            return None;
+        }
+
+        if let Some(response) = HttpParser::parse_response(tcp.payload, ip.src, tcp.src_port, ip.dst) {
+            if self.http_responses.len() >= MAX_TRACKED_SERVICES {
+                self.http_responses.clear();
+            }
+            let key = (response.server, response.server_port, response.software.clone(), response.powered_by.clone());
+            return self.http_responses.insert(key).then_some(SnifferEvent::HttpResponse(response));
+        }
+
+        let request = HttpParser::parse_request(tcp.payload, ip.src, ip.dst, tcp.dst_port)?;
+        if self.http_requests.len() >= MAX_TRACKED_SERVICES {
+            self.http_requests.clear();
+        }
+        let key = (request.client, request.server, request.server_port, request.host.clone(), request.user_agent.clone());
+        self.http_requests.insert(key).then_some(SnifferEvent::HttpRequest(request))
+    }
+
+    fn new_dns_answers(&mut self, ip: &packet::IpPacket, udp: &packet::UdpDatagram) -> Option<SnifferEvent> {
+        let mut response = DnsParser::parse_response(udp.payload, ip.src, ip.dst)?;
+
+        if self.dns_answers.len() >= MAX_TRACKED_DNS_ANSWERS {
+            self.dns_answers.clear();
+        }
+        response.answers.retain(|answer| {
+            self.dns_answers.insert((answer.name.clone(), answer.record_type.clone(), answer.value.clone()))
+        });
+
+        (!response.answers.is_empty()).then_some(SnifferEvent::DnsResponse(response))
+    }
+
+    fn fingerprint_syn(
+        &mut self,
+        ip: &packet::IpPacket,
+        tcp: &packet::TcpSegment,
+        vlan_id: Option<u16>,
+    ) -> Option<SnifferEvent> {
+        let fingerprint = SynFingerprint::from_syn(ip, tcp)?;
+        let signature = fingerprint.signature_string();
+
+        if self.os_signatures.get(&(ip.src, vlan_id)) == Some(&signature) {
+            return None;
+        }
+        self.os_signatures.insert((ip.src, vlan_id), signature);
+
+        let guess = PassiveFingerprinter::classify(&fingerprint)?;
+        Some(SnifferEvent::PassiveOs {
+            ip: ip.src,
+            guess,
+            vlan_id,
+        })
+    }
+}
+
+impl Default for PacketAnalyzer {
+    fn default() -> Self {
+        Self::new(Vec::new())
+    }
+}
@@ -0,0 +1,164 @@
+use super::packet::{IpPacket, TcpOption, TcpSegment};
+use serde::{Deserialize, Serialize};
+
+/// Confidence assigned to a full signature match. Deliberately below what
+/// nmap `-O` reports so active results always take precedence.
+pub const SIGNATURE_CONFIDENCE: f32 = 60.0;
+/// Confidence assigned when only the initial TTL could be classified.
+pub const HEURISTIC_CONFIDENCE: f32 = 30.0;
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct SynFingerprint {
+    pub initial_ttl: u8,
+    pub ip_options_len: usize,
+    pub dont_fragment: bool,
+    pub mss: Option<u16>,
+    pub window: u16,
+    pub window_scale: Option<u8>,
+    pub layout: String,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct PassiveOsGuess {
+    pub os_name: String,
+    pub os_family: String,
+    pub accuracy: f32,
+    pub signature: String,
+}
+
+enum WindowRule {
+    Any,
+    Exact(u16),
+    MssMultiple(u16),
+}
+
+struct Signature {
+    os_name: &'static str,
+    os_family: &'static str,
+    initial_ttl: u8,
+    layout: &'static str,
+    window: WindowRule,
+}
+
+// Condensed from the p0f v3 TCP SYN database
+const SIGNATURES: &[Signature] = &[
+    Signature { os_name: "Linux 3.11 and newer", os_family: "Linux", initial_ttl: 64, layout: "mss,sok,ts,nop,ws", window: WindowRule::MssMultiple(20) },
+    Signature { os_name: "Linux 3.x", os_family: "Linux", initial_ttl: 64, layout: "mss,sok,ts,nop,ws", window: WindowRule::MssMultiple(10) },
+    Signature { os_name: "Linux 2.6.x", os_family: "Linux", initial_ttl: 64, layout: "mss,sok,ts,nop,ws", window: WindowRule::MssMultiple(4) },
+    Signature { os_name: "Android", os_family: "Linux", initial_ttl: 64, layout: "mss,sok,ts,nop,ws", window: WindowRule::Exact(65535) },
+    Signature { os_name: "Windows 10/11", os_family: "Windows", initial_ttl: 128, layout: "mss,nop,ws,nop,nop,sok", window: WindowRule::Exact(64240) },
+    Signature { os_name: "Windows 7/8", os_family: "Windows", initial_ttl: 128, layout: "mss,nop,ws,nop,nop,sok", window: WindowRule::Exact(8192) },
+    Signature { os_name: "Windows XP", os_family: "Windows", initial_ttl: 128, layout: "mss,nop,nop,sok", window: WindowRule::Any },
+    Signature { os_name: "Mac OS X / iOS", os_family: "Mac OS X", initial_ttl: 64, layout: "mss,nop,ws,nop,nop,ts,sok,eol", window: WindowRule::Exact(65535) },
+    Signature { os_name: "FreeBSD", os_family: "FreeBSD", initial_ttl: 64, layout: "mss,nop,ws,sok,ts", window: WindowRule::Exact(65535) },
+    Signature { os_name: "OpenBSD", os_family: "OpenBSD", initial_ttl: 64, layout: "mss,nop,nop,sok,nop,ws,nop,nop,ts", window: WindowRule::Exact(16384) },
+    Signature { os_name: "Solaris 10+", os_family: "Solaris", initial_ttl: 64, layout: "nop,nop,ts,mss,nop,ws,nop,nop,sok", window: WindowRule::Any },
+    Signature { os_name: "Cisco IOS", os_family: "IOS", initial_ttl: 255, layout: "mss", window: WindowRule::Exact(4128) },
+];
+
+impl SynFingerprint {
+    /// Builds a fingerprint from a client SYN. Returns `None` for any other
+    /// kind of segment.
+    pub fn from_syn(ip: &IpPacket, tcp: &TcpSegment) -> Option<Self> {
+        if !tcp.is_syn() {
+            return None;
+        }
+
+        let mut mss = None;
+        let mut window_scale = None;
+        let layout = tcp.options.iter()
+            .map(|opt| match opt {
+                TcpOption::Eol => "eol",
+                TcpOption::Nop => "nop",
+                TcpOption::Mss(v) => { mss = Some(*v); "mss" }
+                TcpOption::WindowScale(v) => { window_scale = Some(*v); "ws" }
+                TcpOption::SackPermitted => "sok",
+                TcpOption::Sack => "sack",
+                TcpOption::Timestamp(..) => "ts",
+                TcpOption::Other(_) => "?",
+            })
+            .collect::<Vec<_>>()
+            .join(",");
+
+        Some(Self {
+            initial_ttl: Self::guess_initial_ttl(ip.ttl),
+            ip_options_len: ip.options_len,
+            dont_fragment: ip.dont_fragment,
+            mss,
+            window: tcp.window,
+            window_scale,
+            layout,
+        })
+    }
+
+    /// Rounds an observed TTL up to the nearest common initial value.
+    pub fn guess_initial_ttl(ttl: u8) -> u8 {
+        match ttl {
+            0..=32 => 32,
+            33..=64 => 64,
+            65..=128 => 128,
+            _ => 255,
+        }
+    }
+
+    pub fn signature_string(&self) -> String {
+        format!(
+            "{}:{}:{}:{}:{}:{}",
+            self.initial_ttl,
+            self.ip_options_len,
+            self.mss.map(|m| m.to_string()).unwrap_or_else(|| "*".to_string()),
+            self.window,
+            self.window_scale.map(|w| w.to_string()).unwrap_or_else(|| "*".to_string()),
+            self.layout,
+        )
+    }
+}
+
+pub struct PassiveFingerprinter;
+
+impl PassiveFingerprinter {
+    pub fn classify(fp: &SynFingerprint) -> Option<PassiveOsGuess> {
+        Self::match_signature(fp).or_else(|| Self::ttl_heuristic(fp))
+    }
+
+    fn match_signature(fp: &SynFingerprint) -> Option<PassiveOsGuess> {
+        SIGNATURES.iter()
+            .find(|sig| {
+                sig.initial_ttl == fp.initial_ttl
+                    && sig.layout == fp.layout
+                    && Self::window_matches(&sig.window, fp)
+            })
+            .map(|sig| PassiveOsGuess {
+                os_name: sig.os_name.to_string(),
+                os_family: sig.os_family.to_string(),
+                accuracy: SIGNATURE_CONFIDENCE,
+                signature: fp.signature_string(),
+            })
+    }
+
+    fn window_matches(rule: &WindowRule, fp: &SynFingerprint) -> bool {
+        match rule {
+            WindowRule::Any => true,
+            WindowRule::Exact(window) => fp.window == *window,
+            WindowRule::MssMultiple(factor) => fp.mss
+                .map(|mss| mss as u32 * *factor as u32 == fp.window as u32)
+                .unwrap_or(false),
+        }
+    }
+
+    fn ttl_heuristic(fp: &SynFingerprint) -> Option<PassiveOsGuess> {
+        let (os_name, os_family) = match fp.initial_ttl {
+            64 => ("Linux/Unix", "Linux"),
+            128 => ("Windows", "Windows"),
+            255 => ("Network device", "embedded"),
+            _ => return None,
+        };
+
+        Some(PassiveOsGuess {
+            os_name: os_name.to_string(),
+            os_family: os_family.to_string(),
+            accuracy: HEURISTIC_CONFIDENCE,
+            signature: fp.signature_string(),
+        })
+    }
+}
@@ -0,0 +1,329 @@
+use std::net::{IpAddr, Ipv4Addr, Ipv6Addr};
+
+const ETHERTYPE_IPV4: u16 = 0x0800;
+const ETHERTYPE_IPV6: u16 = 0x86dd;
+const ETHERTYPE_ARP: u16 = 0x0806;
+const ETHERTYPE_VLAN: u16 = 0x8100;
+const ETHERTYPE_QINQ: u16 = 0x88a8;
+
+pub const PROTO_TCP: u8 = 6;
+pub const PROTO_UDP: u8 = 17;
+
+pub const TCP_FIN: u8 = 0x01;
+pub const TCP_SYN: u8 = 0x02;
+pub const TCP_RST: u8 = 0x04;
+pub const TCP_ACK: u8 = 0x10;
+
+#[derive(Debug, Clone)]
+pub struct EthernetFrame<'a> {
+    pub src_mac: [u8; 6],
+    pub dst_mac: [u8; 6],
+    pub vlan_id: Option<u16>,
+    pub ethertype: u16,
+    pub payload: &'a [u8],
+}
+
+#[derive(Debug, Clone)]
+pub struct IpPacket<'a> {
+    pub src: IpAddr,
+    pub dst: IpAddr,
+    pub ttl: u8,
+    pub protocol: u8,
+    pub dont_fragment: bool,
+    pub options_len: usize,
+    pub total_len: usize,
+    pub payload: &'a [u8],
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum TcpOption {
+    Eol,
+    Nop,
+    Mss(u16),
+    WindowScale(u8),
+    SackPermitted,
+    Sack,
+    Timestamp(u32, u32),
+    Other(u8),
+}
+
+#[derive(Debug, Clone)]
+pub struct TcpSegment<'a> {
+    pub src_port: u16,
+    pub dst_port: u16,
+    pub seq: u32,
+    pub ack: u32,
+    pub flags: u8,
+    pub window: u16,
+    pub options: Vec<TcpOption>,
+    pub payload: &'a [u8],
+}
+
+impl TcpSegment<'_> {
+    pub fn is_syn(&self) -> bool {
+        self.flags & TCP_SYN != 0 && self.flags & TCP_ACK == 0
+    }
+
+    pub fn is_syn_ack(&self) -> bool {
+        self.flags & TCP_SYN != 0 && self.flags & TCP_ACK != 0
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct UdpDatagram<'a> {
+    pub src_port: u16,
+    pub dst_port: u16,
+    pub payload: &'a [u8],
+}
+
+#[derive(Debug, Clone)]
+pub enum Transport<'a> {
+    Tcp(TcpSegment<'a>),
+    Udp(UdpDatagram<'a>),
+    Other,
+}
+
+/// An IPv4-over-Ethernet ARP request or reply
+#[derive(Debug, Clone)]
+pub struct ArpPacket {
+    pub operation: u16,
+    pub sender_mac: [u8; 6],
+    pub sender_ip: Ipv4Addr,
+    pub target_ip: Ipv4Addr,
+}
+
+#[derive(Debug, Clone)]
+pub struct ParsedPacket<'a> {
+    pub ethernet: EthernetFrame<'a>,
+    pub ip: Option<IpPacket<'a>>,
+    pub arp: Option<ArpPacket>,
+    pub transport: Transport<'a>,
+}
+
+impl<'a> ParsedPacket<'a> {
+    /// Parses an Ethernet II frame, following up to two 802.1Q/802.1ad tags.
+    /// Returns `None` for truncated or malformed frames.
+    pub fn parse(data: &'a [u8]) -> Option<Self> {
+        let ethernet = Self::parse_ethernet(data)?;
+
+        let ip = match ethernet.ethertype {
+            ETHERTYPE_IPV4 => Self::parse_ipv4(ethernet.payload),
+            ETHERTYPE_IPV6 => Self::parse_ipv6(ethernet.payload),
+            _ => None,
+        };
+
+        let arp = match ethernet.ethertype {
+            ETHERTYPE_ARP => Self::parse_arp(ethernet.payload),
+            _ => None,
+        };
+
+        let transport = match &ip {
+            Some(ip) if ip.protocol == PROTO_TCP => Self::parse_tcp(ip.payload)
+                .map(Transport::Tcp)
+                .unwrap_or(Transport::Other),
+            Some(ip) if ip.protocol == PROTO_UDP => Self::parse_udp(ip.payload)
+                .map(Transport::Udp)
+                .unwrap_or(Transport::Other),
+            _ => Transport::Other,
+        };
+
+        Some(Self { ethernet, ip, arp, transport })
+    }
+
+    pub fn src_mac(&self) -> String {
+        format_mac(&self.ethernet.src_mac)
+    }
+
+    fn parse_ethernet(data: &'a [u8]) -> Option<EthernetFrame<'a>> {
+        if data.len() < 14 {
+            return None;
+        }
+
+        let mut dst_mac = [0u8; 6];
+        let mut src_mac = [0u8; 6];
+        dst_mac.copy_from_slice(&data[0..6]);
+        src_mac.copy_from_slice(&data[6..12]);
+
+        let mut offset = 12;
+        let mut ethertype = read_u16(data, offset)?;
+        let mut vlan_id = None;
+
+        for _ in 0..2 {
+            if ethertype != ETHERTYPE_VLAN && ethertype != ETHERTYPE_QINQ {
+                break;
+            }
+            let tci = read_u16(data, offset + 2)?;
+            // The innermost tag is the one that identifies the host's VLAN
+            vlan_id = Some(tci & 0x0fff);
+            offset += 4;
+            ethertype = read_u16(data, offset)?;
+        }
+
+        Some(EthernetFrame {
+            src_mac,
+            dst_mac,
+            vlan_id,
+            ethertype,
+            payload: data.get(offset + 2..)?,
+        })
+    }
+
+    fn parse_ipv4(data: &'a [u8]) -> Option<IpPacket<'a>> {
+        if data.len() < 20 || data[0] >> 4 != 4 {
+            return None;
+        }
+
+        let header_len = ((data[0] & 0x0f) as usize) * 4;
+        let total_len = read_u16(data, 2)? as usize;
+        if header_len < 20 || total_len < header_len {
+            return None;
+        }
+
+        let end = total_len.min(data.len());
+        Some(IpPacket {
+            src: IpAddr::V4(Ipv4Addr::new(data[12], data[13], data[14], data[15])),
+            dst: IpAddr::V4(Ipv4Addr::new(data[16], data[17], data[18], data[19])),
+            ttl: data[8],
+            protocol: data[9],
+            dont_fragment: data[6] & 0x40 != 0,
+            options_len: header_len - 20,
+            total_len,
+            payload: data.get(header_len..end)?,
+        })
+    }
+
+    fn parse_ipv6(data: &'a [u8]) -> Option<IpPacket<'a>> {
+        if data.len() < 40 || data[0] >> 4 != 6 {
+            return None;
+        }
+
+        let payload_len = read_u16(data, 4)? as usize;
+        let mut src = [0u8; 16];
+        let mut dst = [0u8; 16];
+        src.copy_from_slice(&data[8..24]);
+        dst.copy_from_slice(&data[24..40]);
+
+        let end = (40 + payload_len).min(data.len());
+        Some(IpPacket {
+            src: IpAddr::V6(Ipv6Addr::from(src)),
+            dst: IpAddr::V6(Ipv6Addr::from(dst)),
+            ttl: data[7],
+            protocol: data[6],
+            dont_fragment: true,
+            options_len: 0,
+            total_len: 40 + payload_len,
+            payload: data.get(40..end)?,
+        })
+    }
+
+    fn parse_arp(data: &[u8]) -> Option<ArpPacket> {
+        // Ethernet hardware, IPv4 protocol, 6- and 4-byte addresses
+        if data.len() < 28 || read_u16(data, 0)? != 1 || read_u16(data, 2)? != ETHERTYPE_IPV4 || data[4] != 6 || data[5] != 4 {
+            return None;
+        }
+
+        let mut sender_mac = [0u8; 6];
+        sender_mac.copy_from_slice(&data[8..14]);
+        Some(ArpPacket {
+            operation: read_u16(data, 6)?,
+            sender_mac,
+            sender_ip: Ipv4Addr::new(data[14], data[15], data[16], data[17]),
+            target_ip: Ipv4Addr::new(data[24], data[25], data[26], data[27]),
+        })
+    }
+
+    fn parse_tcp(data: &'a [u8]) -> Option<TcpSegment<'a>> {
+        if data.len() < 20 {
+            return None;
+        }
+
+        let data_offset = ((data[12] >> 4) as usize) * 4;
+        if data_offset < 20 || data_offset > data.len() {
+            return None;
+        }
+
+        Some(TcpSegment {
+            src_port: read_u16(data, 0)?,
+            dst_port: read_u16(data, 2)?,
+            seq: read_u32(data, 4)?,
+            ack: read_u32(data, 8)?,
+            flags: data[13],
+            window: read_u16(data, 14)?,
+            options: Self::parse_tcp_options(&data[20..data_offset]),
+            payload: &data[data_offset..],
+        })
+    }
+
+    fn parse_tcp_options(data: &[u8]) -> Vec<TcpOption> {
+        let mut options = Vec::new();
+        let mut i = 0;
+
+        while i < data.len() {
+            let kind = data[i];
+            match kind {
+                0 => {
+                    options.push(TcpOption::Eol);
+                    break;
+                }
+                1 => {
+                    options.push(TcpOption::Nop);
+                    i += 1;
+                    continue;
+                }
+                _ => {}
+            }
+
+            let len = match data.get(i + 1) {
+                Some(&len) if len >= 2 && i + len as usize <= data.len() => len as usize,
+                _ => break,
+            };
+            let body = &data[i + 2..i + len];
+
+            options.push(match (kind, body.len()) {
+                (2, 2) => TcpOption::Mss(u16::from_be_bytes([body[0], body[1]])),
+                (3, 1) => TcpOption::WindowScale(body[0]),
+                (4, 0) => TcpOption::SackPermitted,
+                (5, _) => TcpOption::Sack,
+                (8, 8) => TcpOption::Timestamp(
+                    u32::from_be_bytes([body[0], body[1], body[2], body[3]]),
+                    u32::from_be_bytes([body[4], body[5], body[6], body[7]]),
+                ),
+                _ => TcpOption::Other(kind),
+            });
+
+            i += len;
+        }
+
+        options
+    }
+
+    fn parse_udp(data: &'a [u8]) -> Option<UdpDatagram<'a>> {
+        if data.len() < 8 {
+            return None;
+        }
+
+        let len = (read_u16(data, 4)? as usize).clamp(8, data.len());
+        Some(UdpDatagram {
+            src_port: read_u16(data, 0)?,
+            dst_port: read_u16(data, 2)?,
+            payload: &data[8..len],
+        })
+    }
+}
+
+pub fn format_mac(mac: &[u8]) -> String {
+    mac.iter()
+        .map(|b| format!("{:02x}", b))
+        .collect::<Vec<_>>()
+        .join(":")
+}
+
+pub fn read_u16(data: &[u8], offset: usize) -> Option<u16> {
+    data.get(offset..offset + 2)
+        .map(|b| u16::from_be_bytes([b[0], b[1]]))
+}
+
+pub fn read_u32(data: &[u8], offset: usize) -> Option<u32> {
+    data.get(offset..offset + 4)
+        .map(|b| u32::from_be_bytes([b[0], b[1], b[2], b[3]]))
+}
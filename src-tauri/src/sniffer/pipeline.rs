@@ -0,0 +1,322 @@
+use super::SnifferEvent;
+use crate::database::{Database, models::{Host, Port}, operations::*};
+use crate::scanning::{DependencyMapper, TopologyBuilder};
+use crate::utils::{Ja3Database, NetworkUtils};
+use anyhow::Result;
+use std::net::IpAddr;
+
+/// Stores what the sniffer observes.
+pub struct SnifferPipeline;
+
+impl SnifferPipeline {
+    /// Persists an event, returning any follow-up notifications (new or
+    /// rogue hosts, spoofing alerts) it produced. TLS fingerprints are
+    /// matched against `fingerprints`.
+    pub async fn handle_event(
+        database: &Database,
+        fingerprints: &Ja3Database,
+        event: &SnifferEvent,
+    ) -> Result<Vec<SnifferEvent>> {
+        match event {
+            SnifferEvent::PassiveOs { ip, guess, vlan_id } => {
+                let Some((host, created)) = Self::find_or_create_host(database, *ip, None, *vlan_id).await? else {
+                    return Ok(Vec::new());
+                };
+
+                HostOperations::update_os_info(
+                    &database.pool(),
+                    &host.id,
+                    "passive",
+                    &guess.os_name,
+                    &guess.os_family,
+                    guess.accuracy,
+                ).await?;
+
+                Self::host_notifications(database, &host, created).await
+            }
+            SnifferEvent::DhcpRequest(request) => {
+                let Some(ip) = request.ip else {
+                    return Ok(Vec::new());
+                };
+                let Some((host, created)) = Self::find_or_create_host(database, ip, Some(&request.mac), request.vlan_id).await? else {
+                    return Ok(Vec::new());
+                };
+
+                HostOperations::update_dhcp_info(
+                    &database.pool(),
+                    &host.id,
+                    &request.mac,
+                    request.hostname.as_deref(),
+                    request.vendor_class.as_deref(),
+                ).await?;
+                TopologyBuilder::record_neighbour(&database.pool(), ip).await?;
+
+                Self::host_notifications(database, &host, created).await
+            }
+            SnifferEvent::Neighbour { ip, mac, vlan_id } => {
+                let Some((host, created)) = Self::find_or_create_host(database, *ip, Some(mac), *vlan_id).await? else {
+                    return Ok(Vec::new());
+                };
+                TopologyBuilder::record_neighbour(&database.pool(), *ip).await?;
+
+                Self::host_notifications(database, &host, created).await
+            }
+            SnifferEvent::OpenPort { ip, port, vlan_id } => {
+                let Some((host, created)) = Self::find_or_create_host(database, *ip, None, *vlan_id).await? else {
+                    return Ok(Vec::new());
+                };
+                Self::ensure_open_port(database, &host.id, *port).await?;
+
+                Self::host_notifications(database, &host, created).await
+            }
+            SnifferEvent::TlsHello(hello) => {
+                TlsSessionOperations::record(
+                    &database.pool(),
+                    hello.server,
+                    hello.server_port,
+                    hello.server_name.as_deref(),
+                    &hello.alpn,
+                    &hello.version,
+                    hello.client,
+                ).await?;
+
+                let mut follow_ups = Vec::new();
+                if let Some((client, created)) = Self::find_or_create_host(database, hello.client, None, None).await? {
+                    Self::record_fingerprint(database, fingerprints, &client, FINGERPRINT_JA3, &hello.ja3).await?;
+                    follow_ups.extend(Self::host_notifications(database, &client, created).await?);
+                }
+
+                if let Some((server, created)) = Self::find_or_create_host(database, hello.server, None, None).await? {
+                    let port = Self::ensure_open_port(database, &server.id, hello.server_port).await?;
+                    if port.service.is_none() {
+                        PortOperations::update_service_info(
+                            &database.pool(),
+                            &port.id,
+                            Some("ssl"),
+                            port.version.as_deref(),
+                            port.banner.as_deref(),
+                        ).await?;
+                    }
+                    follow_ups.extend(Self::host_notifications(database, &server, created).await?);
+                }
+
+                Ok(follow_ups)
+            }
+            SnifferEvent::TlsServerHello(hello) => {
+                let Some((host, created)) = Self::find_or_create_host(database, hello.server, None, None).await? else {
+                    return Ok(Vec::new());
+                };
+                Self::record_fingerprint(database, fingerprints, &host, FINGERPRINT_JA3S, &hello.ja3s).await?;
+
+                Self::host_notifications(database, &host, created).await
+            }
+            SnifferEvent::HttpRequest(request) => {
+                let mut follow_ups = Vec::new();
+                if let Some((client, created)) = Self::find_or_create_host(database, request.client, None, None).await? {
+                    if let Some(user_agent) = &request.user_agent {
+                        HttpMetadataOperations::record(&database.pool(), &client.id, 0, HTTP_USER_AGENT, user_agent).await?;
+                    }
+                    follow_ups.extend(Self::host_notifications(database, &client, created).await?);
+                }
+
+                if let Some((server, created)) = Self::find_or_create_host(database, request.server, None, None).await? {
+                    if let Some(host) = &request.host {
+                        HttpMetadataOperations::record(&database.pool(), &server.id, request.server_port, HTTP_VIRTUAL_HOST, host).await?;
+                    }
+                    Self::mark_http_port(database, &server.id, request.server_port, None).await?;
+                    follow_ups.extend(Self::host_notifications(database, &server, created).await?);
+                }
+
+                Ok(follow_ups)
+            }
+            SnifferEvent::HttpResponse(response) => {
+                let Some((host, created)) = Self::find_or_create_host(database, response.server, None, None).await? else {
+                    return Ok(Vec::new());
+                };
+
+                if let Some(software) = &response.software {
+                    HttpMetadataOperations::record(&database.pool(), &host.id, response.server_port, HTTP_SERVER, software).await?;
+                }
+                if let Some(powered_by) = &response.powered_by {
+                    HttpMetadataOperations::record(&database.pool(), &host.id, response.server_port, HTTP_POWERED_BY, powered_by).await?;
+                }
+                Self::mark_http_port(database, &host.id, response.server_port, response.software.as_deref()).await?;
+
+                Self::host_notifications(database, &host, created).await
+            }
+            SnifferEvent::DnsResponse(response) => {
+                for answer in &response.answers {
+                    PassiveDnsOperations::record(
+                        &database.pool(),
+                        &response.query,
+                        &answer.name,
+                        &answer.record_type,
+                        &answer.value,
+                        answer.ttl,
+                        response.client,
+                        response.server,
+                    ).await?;
+                }
+                Ok(Vec::new())
+            }
+            SnifferEvent::Traffic { src, dst } => {
+                TopologyBuilder::record_traffic(&database.pool(), *src, *dst).await?;
+                Ok(Vec::new())
+            }
+            SnifferEvent::Flows(flows) => {
+                for flow in flows {
+                    FlowOperations::record(
+                        &database.pool(),
+                        flow.client,
+                        flow.server,
+                        &flow.protocol,
+                        flow.server_port,
+                        flow.packets,
+                        flow.bytes,
+                        flow.first_seen,
+                        flow.last_seen,
+                    ).await?;
+                    DependencyMapper::record_flow(
+                        &database.pool(),
+                        flow.client,
+                        flow.server,
+                        &flow.protocol,
+                        flow.server_port,
+                        flow.packets as i64,
+                        flow.bytes as i64,
+                        flow.first_seen,
+                        flow.last_seen,
+                    ).await?;
+                }
+                Ok(Vec::new())
+            }
+            SnifferEvent::ArpSpoofing(alert) => {
+                let host = HostOperations::find_by_ip(&database.pool(), alert.ip).await?;
+                NotificationOperations::create(
+                    &database.pool(),
+                    "high",
+                    if alert.gateway { "Gateway ARP spoofing" } else { "ARP spoofing" },
+                    &alert.reason,
+                    host.as_ref().map(|_| "host"),
+                    host.as_ref().map(|h| h.id.as_str()),
+                ).await?;
+                Ok(vec![event.clone()])
+            }
+            SnifferEvent::HostDiscovered(_) | SnifferEvent::RogueDevice(_) => Ok(Vec::new()),
+        }
+    }
+
+    /// Only hosts on private networks are created from passive traffic;
+    /// Internet peers seen on the wire are not assessment targets. When the
+    /// MAC is known it decides which record the observation belongs to, and
+    /// on trunk captures so does the VLAN.
+    async fn find_or_create_host(
+        database: &Database,
+        ip: IpAddr,
+        mac_address: Option<&str>,
+        vlan_id: Option<u16>,
+    ) -> Result<Option<(Host, bool)>> {
+        let known = mac_address.is_none()
+            && HostOperations::find_by_ip_in_vlan(&database.pool(), ip, vlan_id).await?.is_some();
+
+        if !known && !NetworkUtils::is_private_ip(&ip) {
+            return Ok(None);
+        }
+
+        let resolved = HostOperations::resolve_identity(&database.pool(), ip, mac_address, vlan_id).await?;
+        Ok(Some(resolved))
+    }
+
+    /// Stores a JA3/JA3S hash for a host, raising a notification the
+    /// first time the host is seen with one the database lists as
+    /// suspicious.
+    async fn record_fingerprint(
+        database: &Database,
+        fingerprints: &Ja3Database,
+        host: &Host,
+        kind: &str,
+        hash: &str,
+    ) -> Result<()> {
+        let known = fingerprints.lookup(hash);
+        let suspicious = known.is_some_and(|known| known.suspicious);
+        let new = TlsFingerprintOperations::record(
+            &database.pool(),
+            &host.id,
+            kind,
+            hash,
+            known.map(|known| known.software.as_str()),
+            suspicious,
+        ).await?;
+
+        if new && suspicious {
+            NotificationOperations::create(
+                &database.pool(),
+                "high",
+                "Suspicious TLS fingerprint",
+                &format!(
+                    "{} {} seen on {}: {}",
+                    kind.to_uppercase(),
+                    hash,
+                    host.ip,
+                    known.map(|known| known.software.as_str()).unwrap_or_default(),
+                ),
+                Some("host"),
+                Some(&host.id),
+            ).await?;
+        }
+
+        Ok(())
+    }
+
+    /// Passive traffic only ever adds ports; one a scan found closed since
+    /// is left as the scan saw it. Either way the port was seen now.
+    async fn ensure_open_port(database: &Database, host_id: &str, number: u16) -> Result<Port> {
+        match PortOperations::find(&database.pool(), host_id, number, "tcp").await? {
+            Some(port) => {
+                PortOperations::touch(&database.pool(), host_id, number, "tcp").await?;
+                Ok(port)
+            }
+            None => PortOperations::create(&database.pool(), host_id, number, "tcp", "open", None, "sniffer").await,
+        }
+    }
+
+    /// Names an unidentified port `http`, with the `Server` header as its
+    /// banner when a scan left none.
+    async fn mark_http_port(database: &Database, host_id: &str, number: u16, software: Option<&str>) -> Result<()> {
+        let port = Self::ensure_open_port(database, host_id, number).await?;
+        if port.service.is_some() && (port.banner.is_some() || software.is_none()) {
+            return Ok(());
+        }
+
+        PortOperations::update_service_info(
+            &database.pool(),
+            &port.id,
+            port.service.as_deref().or(Some("http")),
+            port.version.as_deref(),
+            port.banner.as_deref().or(software),
+        ).await
+    }
+
+    /// Builds the notifications for a host touched by an event: discovery
+    /// for new records, and a rogue alert when it falls outside the baseline.
+    async fn host_notifications(database: &Database, host: &Host, created: bool) -> Result<Vec<SnifferEvent>> {
+        let mut follow_ups = Vec::new();
+        let rogue = HostOperations::flag_if_rogue(&database.pool(), &host.id).await?;
+
+        if !created && !rogue {
+            return Ok(follow_ups);
+        }
+
+        // Re-read so the emitted record reflects the details written above
+        if let Some(host) = HostOperations::find_by_id(&database.pool(), &host.id).await? {
+            if created {
+                follow_ups.push(SnifferEvent::HostDiscovered(host.clone()));
+            }
+            if rogue {
+                follow_ups.push(SnifferEvent::RogueDevice(host));
+            }
+        }
+
+        Ok(follow_ups)
+    }
+}
@@ -0,0 +1,239 @@
+use super::packet::read_u16;
+use md5::{Digest, Md5};
+use serde::{Deserialize, Serialize};
+use std::net::IpAddr;
+
+const CONTENT_HANDSHAKE: u8 = 22;
+const HANDSHAKE_CLIENT_HELLO: u8 = 1;
+const HANDSHAKE_SERVER_HELLO: u8 = 2;
+
+const EXT_SERVER_NAME: u16 = 0;
+const EXT_SUPPORTED_GROUPS: u16 = 10;
+const EXT_EC_POINT_FORMATS: u16 = 11;
+const EXT_ALPN: u16 = 16;
+const EXT_SUPPORTED_VERSIONS: u16 = 43;
+
+/// What a client's ClientHello says about the server it connects to.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct TlsClientHello {
+    pub client: IpAddr,
+    pub server: IpAddr,
+    pub server_port: u16,
+    /// SNI, the name the client expects on the certificate
+    pub server_name: Option<String>,
+    /// Protocols offered over the connection, e.g. `h2`
+    pub alpn: Vec<String>,
+    /// Highest version the client offered, e.g. `TLS 1.3`
+    pub version: String,
+    /// JA3 hash of the hello, identifying the client's TLS library
+    pub ja3: String,
+}
+
+/// A server's reply to a ClientHello.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct TlsServerHello {
+    pub server: IpAddr,
+    pub server_port: u16,
+    pub client: IpAddr,
+    /// Version the server chose
+    pub version: String,
+    /// JA3S hash of the hello, identifying the server's TLS stack
+    pub ja3s: String,
+}
+
+/// The fields of a hello that go into its fingerprint, GREASE removed.
+struct HelloFields {
+    version: u16,
+    ciphers: Vec<u16>,
+    extensions: Vec<u16>,
+    groups: Vec<u16>,
+    point_formats: Vec<u8>,
+    server_name: Option<String>,
+    alpn: Vec<String>,
+    /// From the supported_versions extension, which TLS 1.3 negotiates in
+    negotiated_version: Option<u16>,
+}
+
+pub struct TlsParser;
+
+impl TlsParser {
+    /// Parses a ClientHello at the start of a TCP payload. Hellos split
+    /// across segments are only read as far as the first one goes, which
+    /// nearly always covers the SNI.
+    pub fn parse_client_hello(payload: &[u8], client: IpAddr, server: IpAddr, server_port: u16) -> Option<TlsClientHello> {
+        let fields = Self::parse_hello(payload, HANDSHAKE_CLIENT_HELLO)?;
+
+        let ja3 = format!(
+            "{},{},{},{},{}",
+            fields.version,
+            Self::join(&fields.ciphers),
+            Self::join(&fields.extensions),
+            Self::join(&fields.groups),
+            Self::join(&fields.point_formats),
+        );
+
+        Some(TlsClientHello {
+            client,
+            server,
+            server_port,
+            server_name: fields.server_name,
+            alpn: fields.alpn,
+            version: Self::version_name(fields.negotiated_version.unwrap_or(fields.version)),
+            ja3: Self::md5_hex(&ja3),
+        })
+    }
+
+    /// Parses a ServerHello at the start of a TCP payload from the server.
+    pub fn parse_server_hello(payload: &[u8], server: IpAddr, server_port: u16, client: IpAddr) -> Option<TlsServerHello> {
+        let fields = Self::parse_hello(payload, HANDSHAKE_SERVER_HELLO)?;
+
+        let ja3s = format!(
+            "{},{},{}",
+            fields.version,
+            Self::join(&fields.ciphers),
+            Self::join(&fields.extensions),
+        );
+
+        Some(TlsServerHello {
+            server,
+            server_port,
+            client,
+            version: Self::version_name(fields.negotiated_version.unwrap_or(fields.version)),
+            ja3s: Self::md5_hex(&ja3s),
+        })
+    }
+
+    /// Both hellos share a layout, but a client offers a list of cipher
+    /// suites and compression methods where a server picks one of each.
+    fn parse_hello(payload: &[u8], handshake: u8) -> Option<HelloFields> {
+        if *payload.first()? != CONTENT_HANDSHAKE || *payload.get(5)? != handshake {
+            return None;
+        }
+        let client = handshake == HANDSHAKE_CLIENT_HELLO;
+
+        let version = read_u16(payload, 9)?;
+        // Random follows the legacy version, then the session id
+        let mut offset = 11 + 32;
+        offset += 1 + *payload.get(offset)? as usize;
+
+        let ciphers = if client {
+            let len = read_u16(payload, offset)? as usize;
+            let list = payload.get(offset + 2..offset + 2 + len)?;
+            offset += 2 + len;
+            list.chunks_exact(2).map(|pair| u16::from_be_bytes([pair[0], pair[1]])).collect()
+        } else {
+            let cipher = read_u16(payload, offset)?;
+            offset += 2;
+            vec![cipher]
+        };
+        offset += match client {
+            true => 1 + *payload.get(offset)? as usize,
+            false => 1,
+        };
+
+        let mut fields = HelloFields {
+            version,
+            ciphers: ciphers.into_iter().filter(|c| !Self::is_grease(*c)).collect(),
+            extensions: Vec::new(),
+            groups: Vec::new(),
+            point_formats: Vec::new(),
+            server_name: None,
+            alpn: Vec::new(),
+            negotiated_version: None,
+        };
+
+        // A hello without extensions is still a hello
+        let extensions_end = read_u16(payload, offset)
+            .map(|len| offset + 2 + len as usize)
+            .unwrap_or(offset);
+        offset += 2;
+        while offset + 4 <= extensions_end {
+            let kind = read_u16(payload, offset)?;
+            let len = read_u16(payload, offset + 2)? as usize;
+            let Some(data) = payload.get(offset + 4..offset + 4 + len) else {
+                break;
+            };
+            offset += 4 + len;
+
+            if Self::is_grease(kind) {
+                continue;
+            }
+            fields.extensions.push(kind);
+
+            match kind {
+                EXT_SERVER_NAME => fields.server_name = Self::server_name(data),
+                EXT_ALPN => fields.alpn = Self::alpn(data),
+                EXT_SUPPORTED_GROUPS => {
+                    fields.groups = data.get(2..).unwrap_or_default()
+                        .chunks_exact(2)
+                        .map(|pair| u16::from_be_bytes([pair[0], pair[1]]))
+                        .filter(|group| !Self::is_grease(*group))
+                        .collect();
+                }
+                EXT_EC_POINT_FORMATS => fields.point_formats = data.get(1..).unwrap_or_default().to_vec(),
+                // A list of offered versions from a client, the chosen
+                // one from a server
+                EXT_SUPPORTED_VERSIONS if client => {
+                    let offered = data.get(1..1 + *data.first()? as usize)?;
+                    fields.negotiated_version = offered.chunks_exact(2)
+                        .map(|pair| u16::from_be_bytes([pair[0], pair[1]]))
+                        .filter(|v| !Self::is_grease(*v))
+                        .max();
+                }
+                EXT_SUPPORTED_VERSIONS => fields.negotiated_version = read_u16(data, 0),
+                _ => {}
+            }
+        }
+
+        Some(fields)
+    }
+
+    fn server_name(data: &[u8]) -> Option<String> {
+        // List length, then entries of type, length and name; only
+        // host names (type 0) are defined
+        if *data.get(2)? != 0 {
+            return None;
+        }
+        let len = read_u16(data, 3)? as usize;
+        let name = std::str::from_utf8(data.get(5..5 + len)?).ok()?;
+        Some(name.to_lowercase())
+    }
+
+    fn alpn(data: &[u8]) -> Vec<String> {
+        let mut protocols = Vec::new();
+        let mut offset = 2;
+        while let Some(&len) = data.get(offset) {
+            let Some(protocol) = data.get(offset + 1..offset + 1 + len as usize) else {
+                break;
+            };
+            protocols.push(String::from_utf8_lossy(protocol).into_owned());
+            offset += 1 + len as usize;
+        }
+        protocols
+    }
+
+    /// Reserved values clients offer to keep servers tolerant of unknown
+    /// ones (RFC 8701)
+    fn is_grease(value: u16) -> bool {
+        value & 0x0f0f == 0x0a0a && value >> 8 == value & 0xff
+    }
+
+    fn join<T: ToString>(values: &[T]) -> String {
+        values.iter().map(ToString::to_string).collect::<Vec<_>>().join("-")
+    }
+
+    fn md5_hex(value: &str) -> String {
+        format!("{:x}", Md5::digest(value.as_bytes()))
+    }
+
+    fn version_name(version: u16) -> String {
+        match version {
+            0x0300 => "SSL 3.0".to_string(),
+            0x0301 => "TLS 1.0".to_string(),
+            0x0302 => "TLS 1.1".to_string(),
+            0x0303 => "TLS 1.2".to_string(),
+            0x0304 => "TLS 1.3".to_string(),
+            other => format!("0x{:04x}", other),
+        }
+    }
+}
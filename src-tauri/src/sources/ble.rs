@@ -0,0 +1,153 @@
+use crate::database::{Database, models::WirelessDevice};
+use anyhow::Result;
+use std::sync::Arc;
+use std::sync::atomic::{AtomicBool, Ordering};
+use tokio::sync::mpsc;
+
+/// Minimum time between database writes for one device; advertisers
+/// update many times a second.
+#[cfg(feature = "ble")]
+const BLE_RECORD_INTERVAL: std::time::Duration = std::time::Duration::from_secs(10);
+
+/// Passive BLE survey through the host's Bluetooth adapter: records every
+/// advertising device without connecting to it. Requires the `ble` build
+/// feature.
+pub struct BleSurvey {
+    running: Arc<AtomicBool>,
+}
+
+impl BleSurvey {
+    pub fn new() -> Self {
+        Self {
+            running: Arc::new(AtomicBool::new(false)),
+        }
+    }
+
+    pub fn is_running(&self) -> bool {
+        self.running.load(Ordering::SeqCst)
+    }
+
+    /// Starts scanning on the first adapter whose name contains `adapter`,
+    /// or the first adapter found. Recorded devices go to `notify_tx`.
+    #[cfg(feature = "ble")]
+    pub async fn start(
+        &self,
+        adapter: Option<&str>,
+        database: Arc<Database>,
+        notify_tx: mpsc::Sender<WirelessDevice>,
+    ) -> Result<()> {
+        use crate::database::operations::WirelessDeviceOperations;
+        use anyhow::{Context, bail};
+        use btleplug::api::{Central, CentralEvent, Manager as _, Peripheral as _, ScanFilter};
+        use btleplug::platform::Manager;
+        use futures::StreamExt;
+        use std::collections::HashMap;
+        use std::time::Instant;
+
+        if self.running.swap(true, Ordering::SeqCst) {
+            bail!("BLE survey is already running");
+        }
+
+        let setup = async {
+            let manager = Manager::new().await?;
+            let mut selected = None;
+            for candidate in manager.adapters().await? {
+                let info = candidate.adapter_info().await.unwrap_or_default();
+                if adapter.map(|a| info.contains(a)).unwrap_or(true) {
+                    selected = Some(candidate);
+                    break;
+                }
+            }
+            let central = selected.context("No matching Bluetooth adapter found")?;
+            let events = central.events().await?;
+            central.start_scan(ScanFilter::default()).await?;
+            anyhow::Ok((central, events))
+        };
+
+        let (central, mut events) = match setup.await {
+            Ok(setup) => setup,
+            Err(e) => {
+                self.running.store(false, Ordering::SeqCst);
+                return Err(e);
+            }
+        };
+
+        let running = self.running.clone();
+        tokio::spawn(async move {
+            let mut last_recorded: HashMap<String, Instant> = HashMap::new();
+
+            while running.load(Ordering::SeqCst) {
+                let event = match tokio::time::timeout(std::time::Duration::from_millis(500), events.next()).await {
+                    Ok(Some(event)) => event,
+                    Ok(None) => break,
+                    Err(_) => continue,
+                };
+                let id = match event {
+                    CentralEvent::DeviceDiscovered(id) | CentralEvent::DeviceUpdated(id) => id,
+                    _ => continue,
+                };
+
+                let Ok(peripheral) = central.peripheral(&id).await else {
+                    continue;
+                };
+                let Ok(Some(properties)) = peripheral.properties().await else {
+                    continue;
+                };
+
+                let mac = properties.address.to_string();
+                if last_recorded.get(&mac).map(|t| t.elapsed() < BLE_RECORD_INTERVAL).unwrap_or(false) {
+                    continue;
+                }
+                last_recorded.insert(mac.clone(), Instant::now());
+
+                let services = properties.services.iter()
+                    .map(|uuid| uuid.to_string())
+                    .collect::<Vec<_>>()
+                    .join(",");
+
+                match WirelessDeviceOperations::upsert(
+                    &database.pool(),
+                    "ble",
+                    &mac,
+                    properties.local_name.as_deref(),
+                    properties.rssi.map(i32::from),
+                    (!services.is_empty()).then_some(services.as_str()),
+                    None,
+                    None,
+                    None,
+                    "ble-scan",
+                ).await {
+                    Ok(device) => {
+                        let _ = notify_tx.try_send(device);
+                    }
+                    Err(e) => eprintln!("Failed to record BLE device {}: {}", mac, e),
+                }
+            }
+
+            let _ = central.stop_scan().await;
+            running.store(false, Ordering::SeqCst);
+        });
+
+        Ok(())
+    }
+
+    #[cfg(not(feature = "ble"))]
+    pub async fn start(
+        &self,
+        _adapter: Option<&str>,
+        _database: Arc<Database>,
+        _notify_tx: mpsc::Sender<WirelessDevice>,
+    ) -> Result<()> {
+        anyhow::bail!("This build has no Bluetooth support; rebuild with the `ble` feature")
+    }
+
+    pub fn stop(&self) {
+        self.running.store(false, Ordering::SeqCst);
+    }
+}
+
+impl Default for BleSurvey {
+    fn default() -> Self {
+        Self::new()
+    }
+}
\ No newline at end of file
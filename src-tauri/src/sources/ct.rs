@@ -0,0 +1,135 @@
+use crate::database::{Database, operations::{HostOperations, ScopeOperations}};
+use crate::scanning::ScopeGuard;
+use anyhow::{Result, bail};
+use futures::stream::{self, StreamExt};
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use std::collections::BTreeSet;
+use std::net::IpAddr;
+use std::time::Duration;
+
+const CRT_SH_URL: &str = "https://crt.sh/";
+/// crt.sh is slow for large domains
+const CT_QUERY_TIMEOUT: Duration = Duration::from_secs(60);
+const DNS_TIMEOUT: Duration = Duration::from_secs(5);
+const RESOLVE_CONCURRENCY: usize = 32;
+
+/// A name found in certificate-transparency logs and what it resolves to.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct CtSubdomain {
+    pub name: String,
+    pub addresses: Vec<IpAddr>,
+    /// Whether any address falls inside the project scope
+    pub in_scope: bool,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct CtDiscoverySummary {
+    pub domains: Vec<String>,
+    pub subdomains: Vec<CtSubdomain>,
+    /// Hosts created or named from in-scope subdomains
+    pub staged: usize,
+}
+
+/// Subdomain discovery from certificate-transparency logs via crt.sh.
+pub struct CtDiscovery {
+    client: reqwest::Client,
+}
+
+impl CtDiscovery {
+    pub fn new() -> Self {
+        let client = reqwest::Client::builder()
+            .timeout(CT_QUERY_TIMEOUT)
+            .build()
+            .unwrap_or_default();
+
+        Self { client }
+    }
+
+    /// Looks up every domain scope entry of the project, resolves the
+    /// names found and stages in-scope addresses as hosts in the open
+    /// project.
+    pub async fn discover(&self, database: &Database, project_id: &str) -> Result<CtDiscoverySummary> {
+        // Taken before the lookups so hosts land in the project whose
+        // scope was read even if another project is opened meanwhile
+        let pool = database.pool();
+        let scope = ScopeOperations::list(database.main_pool(), project_id).await?;
+        let domains: Vec<String> = scope.iter()
+            .filter(|entry| entry.kind == "domain")
+            .map(|entry| entry.entry.clone())
+            .collect();
+        if domains.is_empty() {
+            bail!("Project scope has no domains to look up");
+        }
+
+        let mut names = BTreeSet::new();
+        for domain in &domains {
+            names.extend(self.query(domain).await?);
+        }
+
+        let subdomains: Vec<CtSubdomain> = stream::iter(names)
+            .map(|name| async move {
+                let addresses = resolve(&name).await;
+                CtSubdomain { name, addresses, in_scope: false }
+            })
+            .buffer_unordered(RESOLVE_CONCURRENCY)
+            .collect()
+            .await;
+
+        let mut staged = 0;
+        let mut results = Vec::with_capacity(subdomains.len());
+        for mut subdomain in subdomains {
+            for ip in &subdomain.addresses {
+                if !ScopeGuard::covers_ip(&scope, ip, Some(&subdomain.name)) {
+                    continue;
+                }
+                subdomain.in_scope = true;
+
+                let (host, _) = HostOperations::resolve_identity(&pool, *ip, None, None).await?;
+                HostOperations::update_hostname(&pool, &host.id, &subdomain.name).await?;
+                staged += 1;
+            }
+            results.push(subdomain);
+        }
+        results.sort_by(|a, b| a.name.cmp(&b.name));
+
+        Ok(CtDiscoverySummary { domains, subdomains: results, staged })
+    }
+
+    /// Distinct names under `domain` from every logged certificate.
+    pub async fn query(&self, domain: &str) -> Result<BTreeSet<String>> {
+        let entries: Vec<Value> = self.client
+            .get(CRT_SH_URL)
+            .query(&[("q", format!("%.{}", domain)), ("output", "json".to_string())])
+            .send()
+            .await?
+            .error_for_status()?
+            .json()
+            .await?;
+
+        let suffix = format!(".{}", domain);
+        Ok(entries.iter()
+            .filter_map(|entry| entry["name_value"].as_str())
+            // One entry per certificate, with its SANs newline separated
+            .flat_map(str::lines)
+            .map(|name| name.trim().trim_start_matches("*.").trim_end_matches('.').to_lowercase())
+            .filter(|name| name == domain || name.ends_with(&suffix))
+            .collect())
+    }
+}
+
+impl Default for CtDiscovery {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+async fn resolve(name: &str) -> Vec<IpAddr> {
+    match tokio::time::timeout(DNS_TIMEOUT, tokio::net::lookup_host((name, 0))).await {
+        Ok(Ok(addrs)) => {
+            let unique: BTreeSet<IpAddr> = addrs.map(|addr| addr.ip()).collect();
+            unique.into_iter().collect()
+        }
+        _ => Vec::new(),
+    }
+}
\ No newline at end of file
@@ -0,0 +1,11 @@
+pub mod ble;
+pub mod cloud;
+pub mod ct;
+pub mod source;
+pub mod wireless;
+
+pub use ble::*;
+pub use cloud::*;
+pub use ct::*;
+pub use source::*;
+pub use wireless::*;
\ No newline at end of file
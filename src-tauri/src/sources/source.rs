@@ -0,0 +1,183 @@
+use super::WirelessObservation;
+use crate::error::LegionError;
+use crate::scanning::{ScanProgress, ScanResult};
+use crate::sniffer::SnifferEvent;
+use crate::utils::{bounded, channel_settings, progress_channel, BoundedReceiver, BoundedSender, OverflowPolicy};
+use anyhow::Result;
+use futures::future::BoxFuture;
+use std::collections::HashMap;
+use std::future::Future;
+use std::sync::{Arc, Mutex};
+use tokio::task::{AbortHandle, JoinHandle};
+use uuid::Uuid;
+
+/// What a source saw, normalized so one consumer can store the output of
+/// any source.
+#[derive(Debug, Clone)]
+pub enum Observation {
+    /// A host with its ports, OS and findings from an active scan
+    Host(ScanResult),
+    /// OS fingerprints and DHCP requests from passive traffic
+    Passive(SnifferEvent),
+    /// An access point or client from a wireless survey
+    Wireless(WirelessObservation),
+    Progress(ScanProgress),
+}
+
+/// The observations of one run, in the order the source made them.
+pub struct ObservationStream {
+    pub run_id: Uuid,
+    /// Provenance recorded with everything stored from this run
+    pub source: String,
+    observations: BoundedReceiver<Observation>,
+    run: Option<JoinHandle<Result<()>>>,
+}
+
+impl ObservationStream {
+    pub async fn next(&mut self) -> Option<Observation> {
+        self.observations.recv().await
+    }
+
+    /// Waits for the run to end and returns how it ended. Closing the
+    /// stream first tells a source still producing to stop.
+    pub async fn finish(mut self) -> Result<()> {
+        self.observations.close();
+
+        let Some(run) = self.run.take() else {
+            return Ok(());
+        };
+        match run.await {
+            Ok(result) => result,
+            Err(e) if e.is_cancelled() => Err(LegionError::Cancelled(format!(
+                "{} run {} was cancelled", self.source, self.run_id
+            )).into()),
+            Err(e) => Err(anyhow::anyhow!("{} run {} failed: {}", self.source, self.run_id, e)),
+        }
+    }
+}
+
+impl Drop for ObservationStream {
+    /// A stream dropped before `finish`, as when its scan is cancelled,
+    /// stops its run
+    fn drop(&mut self) {
+        if let Some(run) = &self.run {
+            run.abort();
+        }
+    }
+}
+
+/// Where a run sends its observations.
+#[derive(Clone)]
+pub struct ObservationSink {
+    tx: BoundedSender<Observation>,
+}
+
+impl ObservationSink {
+    /// Returns false once the consumer has gone away; the run should stop.
+    pub async fn send(&self, observation: Observation) -> bool {
+        self.tx.send(observation).await.is_ok()
+    }
+
+    /// For runs producing on a blocking thread
+    pub fn blocking_send(&self, observation: Observation) -> bool {
+        self.tx.blocking_send(observation).is_ok()
+    }
+
+    /// A progress callback for the scanners, forwarded as `Progress`
+    /// observations.
+    pub fn progress(&self) -> BoundedSender<ScanProgress> {
+        let (progress_tx, mut progress_rx) = progress_channel();
+        let sink = self.clone();
+        tokio::spawn(async move {
+            while let Some(progress) = progress_rx.recv().await {
+                if !sink.send(Observation::Progress(progress)).await {
+                    break;
+                }
+            }
+        });
+        progress_tx
+    }
+}
+
+/// The running tasks of a source, so a run can be cancelled by its id.
+#[derive(Default)]
+pub struct SourceRuns {
+    runs: Arc<Mutex<HashMap<Uuid, AbortHandle>>>,
+}
+
+impl SourceRuns {
+    /// Spawns a run producing into the returned stream. The run is
+    /// forgotten once it ends.
+    pub fn spawn<F, Fut>(&self, run_id: Uuid, source: impl Into<String>, run: F) -> ObservationStream
+    where
+        F: FnOnce(ObservationSink) -> Fut,
+        Fut: Future<Output = Result<()>> + Send + 'static,
+    {
+        // Observations are never dropped; a run waits for storage instead
+        let (tx, observations) = bounded("observations", channel_settings().observation_capacity, OverflowPolicy::Block);
+        let run = run(ObservationSink { tx });
+
+        // Held until the handle is stored, so a run that ends at once
+        // can't remove itself before it was added
+        let mut runs = self.runs.lock().unwrap();
+        let entry = RunEntry {
+            runs: self.runs.clone(),
+            run_id,
+        };
+        let handle = tokio::spawn(async move {
+            let _entry = entry;
+            run.await
+        });
+        runs.insert(run_id, handle.abort_handle());
+
+        ObservationStream {
+            run_id,
+            source: source.into(),
+            observations,
+            run: Some(handle),
+        }
+    }
+
+    /// Returns whether `run_id` was running
+    pub fn cancel(&self, run_id: Uuid) -> bool {
+        match self.runs.lock().unwrap().remove(&run_id) {
+            Some(handle) => {
+                handle.abort();
+                true
+            }
+            None => false,
+        }
+    }
+}
+
+/// Forgets a run when its task ends, including by being aborted.
+struct RunEntry {
+    runs: Arc<Mutex<HashMap<Uuid, AbortHandle>>>,
+    run_id: Uuid,
+}
+
+impl Drop for RunEntry {
+    fn drop(&mut self) {
+        self.runs.lock().unwrap().remove(&self.run_id);
+    }
+}
+
+/// A producer of observations: a scanner, the sniffer or an importer.
+/// Everything a source sees reaches the coordinator as `Observation`s, so
+/// storage, provenance and progress are handled the same for all of them.
+pub trait Source: Send + Sync + 'static {
+    /// What a run needs, e.g. targets, an interface or file contents
+    type Request: Send + 'static;
+
+    /// Provenance for observations when a run doesn't name a more specific
+    /// one
+    fn name(&self) -> &'static str;
+
+    /// Starts a run. Errors here mean it never started; failures during
+    /// the run are returned by `ObservationStream::finish`.
+    fn start(self: Arc<Self>, run_id: Uuid, request: Self::Request) -> BoxFuture<'static, Result<ObservationStream>>;
+
+    /// Stops a run; its stream ends and `finish` reports it cancelled.
+    /// Returns whether the run was found.
+    fn cancel(&self, run_id: Uuid) -> bool;
+}
\ No newline at end of file
@@ -0,0 +1,192 @@
+use super::{Observation, ObservationStream, Source, SourceRuns};
+use anyhow::{Result, bail};
+use futures::future::BoxFuture;
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use std::sync::Arc;
+use uuid::Uuid;
+
+pub const KIND_WIFI_AP: &str = "wifi_ap";
+pub const KIND_WIFI_CLIENT: &str = "wifi_client";
+
+/// One access point or client from a survey export.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct WirelessObservation {
+    pub kind: &'static str,
+    pub mac_address: String,
+    /// ESSID for access points
+    pub name: Option<String>,
+    pub rssi: Option<i32>,
+    pub channel: Option<i32>,
+    pub encryption: Option<String>,
+    /// Access point a client was associated with
+    pub bssid: Option<String>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct WirelessImportSummary {
+    pub format: String,
+    pub access_points: usize,
+    pub clients: usize,
+}
+
+/// Imports access points and clients from airodump-ng CSV files and
+/// Kismet device exports (`kismetdb_dump_devices` JSON).
+#[derive(Default)]
+pub struct WirelessImporter {
+    runs: SourceRuns,
+}
+
+impl WirelessImporter {
+    /// Recognizes the format of an export and parses it
+    pub fn parse(contents: &str) -> Result<(&'static str, Vec<WirelessObservation>)> {
+        let trimmed = contents.trim_start();
+        if trimmed.starts_with('[') || trimmed.starts_with('{') {
+            Ok(("kismet", Self::parse_kismet_json(contents)?))
+        } else if trimmed.starts_with("BSSID") {
+            Ok(("airodump-ng", Self::parse_airodump_csv(contents)))
+        } else {
+            bail!("Unrecognized wireless survey format; expected airodump-ng CSV or Kismet JSON");
+        }
+    }
+
+    /// airodump-ng writes two tables separated by a blank line: access
+    /// points, then stations.
+    pub fn parse_airodump_csv(contents: &str) -> Vec<WirelessObservation> {
+        let mut observations = Vec::new();
+        let mut in_stations = false;
+
+        for line in contents.lines() {
+            let fields: Vec<&str> = line.split(',').map(str::trim).collect();
+            match fields.first().copied() {
+                None | Some("") => continue,
+                Some("BSSID") => {
+                    in_stations = false;
+                    continue;
+                }
+                Some("Station MAC") => {
+                    in_stations = true;
+                    continue;
+                }
+                _ => {}
+            }
+
+            let field = |i: usize| fields.get(i).copied().filter(|f| !f.is_empty());
+            let number = |i: usize| field(i).and_then(|f| f.parse::<i32>().ok());
+
+            if in_stations {
+                // Station MAC, First seen, Last seen, Power, # packets, BSSID, Probed ESSIDs
+                observations.push(WirelessObservation {
+                    kind: KIND_WIFI_CLIENT,
+                    mac_address: fields[0].to_uppercase(),
+                    name: None,
+                    rssi: number(3).filter(|&p| p != -1),
+                    channel: None,
+                    encryption: None,
+                    bssid: field(5)
+                        .filter(|b| !b.starts_with("(not associated)"))
+                        .map(str::to_uppercase),
+                });
+            } else {
+                // BSSID, First seen, Last seen, channel, Speed, Privacy, Cipher,
+                // Authentication, Power, # beacons, # IV, LAN IP, ID-length, ESSID, Key
+                let encryption = [field(5), field(6), field(7)]
+                    .into_iter()
+                    .flatten()
+                    .collect::<Vec<_>>()
+                    .join(" ");
+
+                observations.push(WirelessObservation {
+                    kind: KIND_WIFI_AP,
+                    mac_address: fields[0].to_uppercase(),
+                    // ESSIDs may themselves contain commas
+                    name: fields.get(13..fields.len().saturating_sub(1))
+                        .map(|parts| parts.join(","))
+                        .filter(|essid| !essid.is_empty()),
+                    rssi: number(8).filter(|&p| p != -1),
+                    channel: number(3).filter(|&c| c > 0),
+                    encryption: (!encryption.is_empty()).then_some(encryption),
+                    bssid: None,
+                });
+            }
+        }
+
+        observations
+    }
+
+    /// Kismet exports one JSON object per device, as an array or one per
+    /// line. Only Wi-Fi devices are taken.
+    pub fn parse_kismet_json(contents: &str) -> Result<Vec<WirelessObservation>> {
+        let devices: Vec<Value> = match serde_json::from_str::<Value>(contents) {
+            Ok(Value::Array(devices)) => devices,
+            Ok(device) => vec![device],
+            Err(_) => contents.lines()
+                .filter(|line| !line.trim().is_empty())
+                .map(serde_json::from_str)
+                .collect::<Result<_, _>>()?,
+        };
+
+        Ok(devices.iter().filter_map(Self::kismet_device).collect())
+    }
+
+    fn kismet_device(device: &Value) -> Option<WirelessObservation> {
+        let text = |key: &str| device.get(key)
+            .and_then(Value::as_str)
+            .map(str::trim)
+            .filter(|s| !s.is_empty())
+            .map(str::to_string);
+
+        let kind = match text("kismet.device.base.type")?.as_str() {
+            "Wi-Fi AP" => KIND_WIFI_AP,
+            "Wi-Fi Client" | "Wi-Fi Device" => KIND_WIFI_CLIENT,
+            _ => return None,
+        };
+
+        let rssi = device.pointer("/kismet.device.base.signal/kismet.common.signal.last_signal")
+            .and_then(Value::as_i64)
+            .filter(|&s| s != 0)
+            .map(|s| s as i32);
+
+        Some(WirelessObservation {
+            kind,
+            mac_address: text("kismet.device.base.macaddr")?.to_uppercase(),
+            name: text("kismet.device.base.name"),
+            rssi,
+            channel: text("kismet.device.base.channel").and_then(|c| c.parse().ok()),
+            encryption: text("kismet.device.base.crypt"),
+            bssid: device.pointer("/dot11.device/dot11.device.last_bssid")
+                .and_then(Value::as_str)
+                .filter(|b| kind == KIND_WIFI_CLIENT && *b != "00:00:00:00:00:00")
+                .map(str::to_uppercase),
+        })
+    }
+}
+
+impl Source for WirelessImporter {
+    type Request = String;
+
+    fn name(&self) -> &'static str {
+        "wireless"
+    }
+
+    /// Runs are named after the format of the export, which is recorded
+    /// with each device.
+    fn start(self: Arc<Self>, run_id: Uuid, contents: String) -> BoxFuture<'static, Result<ObservationStream>> {
+        Box::pin(async move {
+            let (format, observations) = Self::parse(&contents)?;
+
+            Ok(self.runs.spawn(run_id, format, move |sink| async move {
+                for observation in observations {
+                    if !sink.send(Observation::Wireless(observation)).await {
+                        break;
+                    }
+                }
+                Ok(())
+            }))
+        })
+    }
+
+    fn cancel(&self, run_id: Uuid) -> bool {
+        self.runs.cancel(run_id)
+    }
+}
\ No newline at end of file
@@ -0,0 +1,313 @@
+use serde::{Deserialize, Serialize};
+use std::collections::{HashMap, VecDeque};
+use std::sync::atomic::{AtomicU64, AtomicUsize, Ordering};
+use std::sync::{Arc, Mutex, OnceLock, RwLock};
+use tokio::sync::Notify;
+use tokio::sync::mpsc::error::{SendError, TrySendError};
+
+/// What a full channel does with the next message.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum OverflowPolicy {
+    /// The sender waits for room; nothing is lost
+    Block,
+    /// The new message is discarded
+    DropNewest,
+    /// The oldest queued message is discarded to make room
+    DropOldest,
+    /// The new message replaces the newest queued one. For snapshots such
+    /// as progress, where only the latest matters.
+    Merge,
+}
+
+/// Capacities and policies of the channels between scanners, the
+/// coordinator and the frontend. Changes apply to channels opened after
+/// them.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct ChannelSettings {
+    /// Progress updates queued per scan before `progress_policy` applies
+    pub progress_capacity: usize,
+    pub progress_policy: OverflowPolicy,
+    /// Applied to frontend events once the event channel is full
+    pub event_policy: OverflowPolicy,
+    /// Observations and captured frames a run buffers before it waits for
+    /// storage to catch up
+    pub observation_capacity: usize,
+}
+
+impl Default for ChannelSettings {
+    fn default() -> Self {
+        Self {
+            progress_capacity: 100,
+            progress_policy: OverflowPolicy::Merge,
+            event_policy: OverflowPolicy::DropOldest,
+            observation_capacity: 1000,
+        }
+    }
+}
+
+/// Totals for every channel opened under one name since startup.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ChannelMetrics {
+    pub name: String,
+    pub capacity: usize,
+    pub policy: OverflowPolicy,
+    pub sent: u64,
+    /// Messages discarded by `DropNewest` or `DropOldest`
+    pub dropped: u64,
+    /// Messages folded into a queued one by `Merge`
+    pub merged: u64,
+    /// Sends that had to wait for room under `Block`
+    pub blocked: u64,
+}
+
+#[derive(Default)]
+struct ChannelCounters {
+    capacity: AtomicUsize,
+    sent: AtomicU64,
+    dropped: AtomicU64,
+    merged: AtomicU64,
+    blocked: AtomicU64,
+}
+
+struct Registered {
+    policy: OverflowPolicy,
+    counters: Arc<ChannelCounters>,
+}
+
+fn registry() -> &'static Mutex<HashMap<&'static str, Registered>> {
+    static REGISTRY: OnceLock<Mutex<HashMap<&'static str, Registered>>> = OnceLock::new();
+    REGISTRY.get_or_init(|| Mutex::new(HashMap::new()))
+}
+
+fn settings_lock() -> &'static RwLock<ChannelSettings> {
+    static SETTINGS: OnceLock<RwLock<ChannelSettings>> = OnceLock::new();
+    SETTINGS.get_or_init(|| RwLock::new(ChannelSettings::default()))
+}
+
+/// Replaces the settings channels are opened with.
+pub fn configure_channels(settings: &ChannelSettings) {
+    *settings_lock().write().unwrap() = settings.clone();
+}
+
+pub fn channel_settings() -> ChannelSettings {
+    settings_lock().read().unwrap().clone()
+}
+
+/// Metrics of every named channel, sorted by name
+pub fn channel_metrics() -> Vec<ChannelMetrics> {
+    let registry = registry().lock().unwrap();
+    let mut metrics: Vec<ChannelMetrics> = registry.iter()
+        .map(|(name, registered)| ChannelMetrics {
+            name: name.to_string(),
+            capacity: registered.counters.capacity.load(Ordering::Relaxed),
+            policy: registered.policy,
+            sent: registered.counters.sent.load(Ordering::Relaxed),
+            dropped: registered.counters.dropped.load(Ordering::Relaxed),
+            merged: registered.counters.merged.load(Ordering::Relaxed),
+            blocked: registered.counters.blocked.load(Ordering::Relaxed),
+        })
+        .collect();
+    metrics.sort_by(|a, b| a.name.cmp(&b.name));
+    metrics
+}
+
+/// Opens a channel holding at most `capacity` messages. Channels opened
+/// under the same name share their metrics.
+pub fn bounded<T>(name: &'static str, capacity: usize, policy: OverflowPolicy) -> (BoundedSender<T>, BoundedReceiver<T>) {
+    let capacity = capacity.max(1);
+    let counters = {
+        let mut registry = registry().lock().unwrap();
+        let registered = registry.entry(name).or_insert_with(|| Registered {
+            policy,
+            counters: Arc::default(),
+        });
+        registered.policy = policy;
+        registered.counters.capacity.store(capacity, Ordering::Relaxed);
+        registered.counters.clone()
+    };
+
+    let shared = Arc::new(Shared {
+        state: Mutex::new(State {
+            queue: VecDeque::with_capacity(capacity),
+            senders: 1,
+            receiver_open: true,
+        }),
+        readable: Notify::new(),
+        writable: Notify::new(),
+        capacity,
+        policy,
+        counters,
+    });
+
+    (BoundedSender { shared: shared.clone() }, BoundedReceiver { shared })
+}
+
+/// A channel for scan progress, sized and merged as configured.
+pub fn progress_channel<T>() -> (BoundedSender<T>, BoundedReceiver<T>) {
+    let settings = channel_settings();
+    bounded("progress", settings.progress_capacity, settings.progress_policy)
+}
+
+struct State<T> {
+    queue: VecDeque<T>,
+    senders: usize,
+    receiver_open: bool,
+}
+
+struct Shared<T> {
+    state: Mutex<State<T>>,
+    readable: Notify,
+    writable: Notify,
+    capacity: usize,
+    policy: OverflowPolicy,
+    counters: Arc<ChannelCounters>,
+}
+
+/// The sending half of `bounded`, used like `mpsc::Sender`.
+pub struct BoundedSender<T> {
+    shared: Arc<Shared<T>>,
+}
+
+impl<T> BoundedSender<T> {
+    /// Queues `value`, applying the overflow policy when the channel is
+    /// full. Only `Block` channels wait. Fails once the receiver is gone.
+    pub async fn send(&self, mut value: T) -> Result<(), SendError<T>> {
+        let mut counted = false;
+
+        loop {
+            let notified = self.shared.writable.notified();
+            tokio::pin!(notified);
+            notified.as_mut().enable();
+
+            match self.try_send(value) {
+                Ok(()) => return Ok(()),
+                Err(TrySendError::Closed(value)) => return Err(SendError(value)),
+                Err(TrySendError::Full(returned)) => {
+                    value = returned;
+                    if !counted {
+                        self.shared.counters.blocked.fetch_add(1, Ordering::Relaxed);
+                        counted = true;
+                    }
+                }
+            }
+
+            notified.await;
+        }
+    }
+
+    /// Queues `value` without waiting. A full `Block` channel returns it;
+    /// the other policies make room or discard it and report success.
+    pub fn try_send(&self, value: T) -> Result<(), TrySendError<T>> {
+        let shared = &self.shared;
+        let counters = &shared.counters;
+        let mut state = shared.state.lock().unwrap();
+
+        if !state.receiver_open {
+            return Err(TrySendError::Closed(value));
+        }
+
+        if state.queue.len() < shared.capacity {
+            state.queue.push_back(value);
+            counters.sent.fetch_add(1, Ordering::Relaxed);
+        } else {
+            match shared.policy {
+                OverflowPolicy::Block => return Err(TrySendError::Full(value)),
+                OverflowPolicy::DropNewest => {
+                    counters.dropped.fetch_add(1, Ordering::Relaxed);
+                    return Ok(());
+                }
+                OverflowPolicy::DropOldest => {
+                    state.queue.pop_front();
+                    state.queue.push_back(value);
+                    counters.sent.fetch_add(1, Ordering::Relaxed);
+                    counters.dropped.fetch_add(1, Ordering::Relaxed);
+                }
+                OverflowPolicy::Merge => {
+                    if let Some(newest) = state.queue.back_mut() {
+                        *newest = value;
+                    }
+                    counters.merged.fetch_add(1, Ordering::Relaxed);
+                    return Ok(());
+                }
+            }
+        }
+
+        drop(state);
+        shared.readable.notify_one();
+        Ok(())
+    }
+
+    /// For senders on a blocking thread, such as a pcap capture loop
+    pub fn blocking_send(&self, value: T) -> Result<(), SendError<T>> {
+        futures::executor::block_on(self.send(value))
+    }
+
+    pub fn is_closed(&self) -> bool {
+        !self.shared.state.lock().unwrap().receiver_open
+    }
+}
+
+impl<T> Clone for BoundedSender<T> {
+    fn clone(&self) -> Self {
+        self.shared.state.lock().unwrap().senders += 1;
+        Self { shared: self.shared.clone() }
+    }
+}
+
+impl<T> Drop for BoundedSender<T> {
+    fn drop(&mut self) {
+        let mut state = self.shared.state.lock().unwrap();
+        state.senders -= 1;
+        if state.senders == 0 {
+            drop(state);
+            // Wakes the receiver to see the channel has ended
+            self.shared.readable.notify_one();
+        }
+    }
+}
+
+/// The receiving half of `bounded`, used like `mpsc::Receiver`.
+pub struct BoundedReceiver<T> {
+    shared: Arc<Shared<T>>,
+}
+
+impl<T> BoundedReceiver<T> {
+    /// The next message, or `None` once the queue is empty and every
+    /// sender is gone or the channel was closed.
+    pub async fn recv(&mut self) -> Option<T> {
+        loop {
+            let notified = self.shared.readable.notified();
+            tokio::pin!(notified);
+            notified.as_mut().enable();
+
+            {
+                let mut state = self.shared.state.lock().unwrap();
+                if let Some(value) = state.queue.pop_front() {
+                    drop(state);
+                    self.shared.writable.notify_one();
+                    return Some(value);
+                }
+                if state.senders == 0 || !state.receiver_open {
+                    return None;
+                }
+            }
+
+            notified.await;
+        }
+    }
+
+    /// Stops accepting messages. Those already queued can still be
+    /// received.
+    pub fn close(&mut self) {
+        self.shared.state.lock().unwrap().receiver_open = false;
+        self.shared.writable.notify_waiters();
+    }
+}
+
+impl<T> Drop for BoundedReceiver<T> {
+    fn drop(&mut self) {
+        self.close();
+    }
+}
\ No newline at end of file
@@ -0,0 +1,138 @@
+use crate::scanning::Severity;
+use anyhow::{Result, bail};
+use std::collections::HashMap;
+
+/// Temporal and environmental metrics are accepted in a vector but do not
+/// change the base score.
+const NON_BASE_METRICS: &[&str] = &[
+    "E", "RL", "RC", "CR", "IR", "AR", "MAV", "MAC", "MPR", "MUI", "MS", "MC", "MI", "MA",
+];
+
+/// A parsed CVSS v3.x base vector, e.g.
+/// `CVSS:3.1/AV:N/AC:L/PR:N/UI:N/S:U/C:H/I:H/A:H`.
+#[derive(Debug, Clone, PartialEq)]
+pub struct CvssVector {
+    pub attack_vector: char,
+    pub attack_complexity: char,
+    pub privileges_required: char,
+    pub user_interaction: char,
+    pub scope_changed: bool,
+    pub confidentiality: char,
+    pub integrity: char,
+    pub availability: char,
+}
+
+impl CvssVector {
+    pub fn parse(vector: &str) -> Result<Self> {
+        let vector = vector.trim();
+        let Some(metrics) = vector.strip_prefix("CVSS:3.1/").or_else(|| vector.strip_prefix("CVSS:3.0/")) else {
+            bail!("CVSS vector must start with CVSS:3.1/ or CVSS:3.0/: {}", vector);
+        };
+
+        let mut values: HashMap<&str, &str> = HashMap::new();
+        for metric in metrics.split('/') {
+            let Some((name, value)) = metric.split_once(':') else {
+                bail!("Malformed CVSS metric: {}", metric);
+            };
+            if values.insert(name, value).is_some() {
+                bail!("CVSS metric {} appears more than once", name);
+            }
+        }
+
+        let base = |name: &str, allowed: &str| -> Result<char> {
+            let value = values.get(name).copied().unwrap_or("");
+            match value.chars().next() {
+                Some(c) if value.len() == 1 && allowed.contains(c) => Ok(c),
+                _ if value.is_empty() => bail!("CVSS vector is missing {}", name),
+                _ => bail!("Invalid value for CVSS metric {}: {}", name, value),
+            }
+        };
+
+        let parsed = Self {
+            attack_vector: base("AV", "NALP")?,
+            attack_complexity: base("AC", "LH")?,
+            privileges_required: base("PR", "NLH")?,
+            user_interaction: base("UI", "NR")?,
+            scope_changed: base("S", "UC")? == 'C',
+            confidentiality: base("C", "HLN")?,
+            integrity: base("I", "HLN")?,
+            availability: base("A", "HLN")?,
+        };
+
+        const BASE_METRICS: &[&str] = &["AV", "AC", "PR", "UI", "S", "C", "I", "A"];
+        if let Some(unknown) = values.keys().find(|k| !BASE_METRICS.contains(k) && !NON_BASE_METRICS.contains(k)) {
+            bail!("Unknown CVSS metric: {}", unknown);
+        }
+
+        Ok(parsed)
+    }
+
+    /// Base score per the CVSS v3.1 specification, section 7.1.
+    pub fn base_score(&self) -> f32 {
+        let attack_vector = match self.attack_vector {
+            'N' => 0.85,
+            'A' => 0.62,
+            'L' => 0.55,
+            _ => 0.2,
+        };
+        let attack_complexity = if self.attack_complexity == 'L' { 0.77 } else { 0.44 };
+        let privileges_required = match (self.privileges_required, self.scope_changed) {
+            ('N', _) => 0.85,
+            ('L', false) => 0.62,
+            ('L', true) => 0.68,
+            (_, false) => 0.27,
+            (_, true) => 0.5,
+        };
+        let user_interaction = if self.user_interaction == 'N' { 0.85 } else { 0.62 };
+        let impact_of = |value: char| match value {
+            'H' => 0.56,
+            'L' => 0.22,
+            _ => 0.0,
+        };
+
+        let iss = 1.0
+            - (1.0 - impact_of(self.confidentiality))
+                * (1.0 - impact_of(self.integrity))
+                * (1.0 - impact_of(self.availability));
+        let impact = if self.scope_changed {
+            7.52 * (iss - 0.029) - 3.25 * (iss - 0.02f64).powi(15)
+        } else {
+            6.42 * iss
+        };
+        if impact <= 0.0 {
+            return 0.0;
+        }
+
+        let exploitability = 8.22 * attack_vector * attack_complexity * privileges_required * user_interaction;
+        let score = if self.scope_changed {
+            roundup((1.08 * (impact + exploitability)).min(10.0))
+        } else {
+            roundup((impact + exploitability).min(10.0))
+        };
+
+        score as f32
+    }
+
+    pub fn severity(&self) -> Severity {
+        Severity::from_cvss(self.base_score())
+    }
+}
+
+impl std::str::FromStr for CvssVector {
+    type Err = anyhow::Error;
+
+    fn from_str(vector: &str) -> Result<Self> {
+        Self::parse(vector)
+    }
+}
+
+/// The specification's Roundup: smallest one-decimal value not below the
+/// input, computed on integers to avoid floating point artefacts.
+fn roundup(value: f64) -> f64 {
+    let scaled = (value * 100_000.0).round() as i64;
+    if scaled % 10_000 == 0 {
+        scaled as f64 / 100_000.0
+    } else {
+        (scaled / 10_000 + 1) as f64 / 10.0
+    }
+}
\ No newline at end of file
@@ -0,0 +1,71 @@
+use anyhow::{Result, Context};
+use maxminddb::{geoip2, Reader};
+use serde::{Deserialize, Serialize};
+use std::net::IpAddr;
+use std::path::Path;
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct GeoLocation {
+    /// ISO 3166-1 alpha-2
+    pub country_code: Option<String>,
+    /// English name, when the database has cities
+    pub city: Option<String>,
+}
+
+/// Locates addresses in a MaxMind DB, such as GeoLite2-City or
+/// GeoLite2-Country.
+pub struct GeoIpLookup {
+    reader: Option<Reader<Vec<u8>>>,
+}
+
+impl GeoIpLookup {
+    pub fn empty() -> Self {
+        Self { reader: None }
+    }
+
+    pub fn load(path: impl AsRef<Path>) -> Result<Self> {
+        let path = path.as_ref();
+        let reader = Reader::open_readfile(path)
+            .with_context(|| format!("Failed to open {}", path.display()))?;
+
+        Ok(Self { reader: Some(reader) })
+    }
+
+    /// Loads the configured database, if any. A database that fails to
+    /// load leaves lookups disabled.
+    pub fn load_configured(path: Option<&str>) -> Self {
+        path.and_then(|path| match Self::load(path) {
+            Ok(lookup) => Some(lookup),
+            Err(e) => {
+                eprintln!("Failed to load GeoIP database: {}", e);
+                None
+            }
+        })
+        .unwrap_or_else(Self::empty)
+    }
+
+    pub fn is_loaded(&self) -> bool {
+        self.reader.is_some()
+    }
+
+    /// Country records are a subset of city records, so both kinds of
+    /// database are read as cities.
+    pub fn lookup(&self, ip: IpAddr) -> Option<GeoLocation> {
+        let record: geoip2::City = self.reader.as_ref()?.lookup(ip).ok()?;
+
+        let country_code = record.country
+            .and_then(|country| country.iso_code)
+            .map(str::to_string);
+        let city = record.city
+            .and_then(|city| city.names)
+            .and_then(|names| names.get("en").map(|name| name.to_string()));
+
+        (country_code.is_some() || city.is_some()).then_some(GeoLocation { country_code, city })
+    }
+}
+
+impl Default for GeoIpLookup {
+    fn default() -> Self {
+        Self::empty()
+    }
+}
\ No newline at end of file
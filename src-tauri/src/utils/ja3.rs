@@ -0,0 +1,89 @@
+use anyhow::{Result, Context};
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::path::Path;
+
+/// What a JA3 or JA3S hash is known to belong to.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct Ja3Match {
+    pub software: String,
+    /// Listed as malware or attack tooling
+    pub suspicious: bool,
+}
+
+/// Known TLS fingerprints by hash. Reads abuse.ch's SSLBL JA3 blacklist
+/// (`ja3_md5,Firstseen,Lastseen,Listingreason`), whose entries are all
+/// suspicious, and simple `hash,software[,suspicious]` lists.
+pub struct Ja3Database {
+    fingerprints: HashMap<String, Ja3Match>,
+}
+
+impl Ja3Database {
+    pub fn empty() -> Self {
+        Self { fingerprints: HashMap::new() }
+    }
+
+    pub fn load(path: impl AsRef<Path>) -> Result<Self> {
+        let path = path.as_ref();
+        let contents = std::fs::read(path)
+            .with_context(|| format!("Failed to read {}", path.display()))?;
+
+        Ok(Self::parse(&String::from_utf8_lossy(&contents)))
+    }
+
+    /// Loads the configured database, if any. A database that fails to
+    /// load leaves fingerprints unmatched.
+    pub fn load_configured(path: Option<&str>) -> Self {
+        path.and_then(|path| match Self::load(path) {
+            Ok(database) => Some(database),
+            Err(e) => {
+                eprintln!("Failed to load JA3 database: {}", e);
+                None
+            }
+        })
+        .unwrap_or_else(Self::empty)
+    }
+
+    pub fn parse(contents: &str) -> Self {
+        let fingerprints = contents.lines()
+            .map(str::trim)
+            .filter(|line| !line.is_empty() && !line.starts_with('#'))
+            .filter_map(|line| {
+                let fields: Vec<&str> = line.split(',').map(str::trim).collect();
+                let hash = fields[0].to_ascii_lowercase();
+                if hash.len() != 32 || !hash.chars().all(|c| c.is_ascii_hexdigit()) {
+                    return None;
+                }
+
+                let fingerprint = match fields.len() {
+                    4.. => Ja3Match {
+                        software: fields[3].to_string(),
+                        suspicious: true,
+                    },
+                    2 | 3 => Ja3Match {
+                        software: fields[1].to_string(),
+                        suspicious: fields.get(2).is_some_and(|flag| {
+                            matches!(flag.to_ascii_lowercase().as_str(), "suspicious" | "malicious" | "true" | "1")
+                        }),
+                    },
+                    _ => return None,
+                };
+                Some((hash, fingerprint))
+            })
+            .collect();
+
+        Self { fingerprints }
+    }
+
+    pub fn len(&self) -> usize {
+        self.fingerprints.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.fingerprints.is_empty()
+    }
+
+    pub fn lookup(&self, hash: &str) -> Option<&Ja3Match> {
+        self.fingerprints.get(&hash.to_ascii_lowercase())
+    }
+}
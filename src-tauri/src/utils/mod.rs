@@ -1,4 +1,25 @@
+pub mod channel;
+pub mod cvss;
+pub mod geoip;
+pub mod ja3;
 pub mod process;
+pub mod sandbox;
 pub mod validation;
 pub mod network;
-pub mod parsing;
\ No newline at end of file
+pub mod oui;
+pub mod parsing;
+pub mod service_probes;
+pub mod system;
+
+pub use channel::*;
+pub use cvss::*;
+pub use geoip::*;
+pub use ja3::*;
+pub use process::*;
+pub use sandbox::SandboxPolicy;
+pub use validation::*;
+pub use network::*;
+pub use oui::*;
+pub use parsing::*;
+pub use service_probes::*;
+pub use system::*;
\ No newline at end of file
@@ -1,25 +1,54 @@
-use std::net::{IpAddr, Ipv4Addr};
+use std::net::{IpAddr, Ipv4Addr, Ipv6Addr};
 use anyhow::Result;
-use cidr::{IpCidr, Ipv4Cidr};
+use serde::{Deserialize, Serialize};
+use cidr::{IpCidr, IpInet, Ipv4Cidr};
+use crate::sniffer::capture::CaptureBackend;
 
 pub struct NetworkUtils;
 
 impl NetworkUtils {
-    pub fn expand_cidr(cidr: &str) -> Result<Vec<IpAddr>> {
+    /// Lazily yields every address in `cidr`. Nothing is materialised, so
+    /// a /12 costs no more memory than a /24.
+    pub fn iter_cidr(cidr: &str) -> Result<impl Iterator<Item = IpAddr> + Send> {
         let network: IpCidr = cidr.parse()?;
-        let mut ips = Vec::new();
-        
-        // Limit to prevent memory issues
-        const MAX_IPS: usize = 65536;
-        
-        for (count, ip) in network.iter().enumerate() {
-            if count >= MAX_IPS {
-                break;
+        Ok(Self::iter_network(network))
+    }
+
+    fn iter_network(network: IpCidr) -> impl Iterator<Item = IpAddr> + Send {
+        let (first, last, is_v4) = match network {
+            IpCidr::V4(n) => (u32::from(n.first_address()) as u128, u32::from(n.last_address()) as u128, true),
+            IpCidr::V6(n) => (u128::from(n.first_address()), u128::from(n.last_address()), false),
+        };
+
+        (first..=last).map(move |addr| {
+            if is_v4 {
+                IpAddr::V4(Ipv4Addr::from(addr as u32))
+            } else {
+                IpAddr::V6(Ipv6Addr::from(addr))
             }
-            ips.push(ip);
-        }
-        
-        Ok(ips)
+        })
+    }
+
+    /// The network of `prefix_len` bits that `ip` is in. IPv6 addresses
+    /// are always grouped by their /64.
+    pub fn subnet_of(ip: IpAddr, prefix_len: u8) -> IpCidr {
+        let prefix_len = match ip {
+            IpAddr::V4(_) => prefix_len.min(32),
+            IpAddr::V6(_) => 64,
+        };
+        IpInet::new(ip, prefix_len)
+            .map(|inet| inet.network())
+            .unwrap_or_else(|_| IpCidr::new_host(ip))
+    }
+
+    /// Number of addresses in `network`, saturating for huge IPv6 ranges.
+    pub fn address_count(network: &IpCidr) -> u64 {
+        let host_bits = match network {
+            IpCidr::V4(n) => 32 - n.network_length() as u32,
+            IpCidr::V6(n) => 128 - n.network_length() as u32,
+        };
+
+        1u64.checked_shl(host_bits).unwrap_or(u64::MAX)
     }
 
     pub fn is_private_ip(ip: &IpAddr) -> bool {
@@ -65,32 +94,132 @@ impl NetworkUtils {
         }
     }
 
-    pub fn generate_target_list(
-        ranges: &[String],
-        excludes: &[String],
-    ) -> Result<Vec<IpAddr>> {
-        let mut targets = Vec::new();
-        let mut exclude_set = std::collections::HashSet::new();
-
-        // Process excludes first
-        for exclude in excludes {
-            if let Ok(ips) = Self::expand_cidr(exclude) {
-                exclude_set.extend(ips);
+    /// Collapses a host list into the fewest CIDR blocks that cover exactly
+    /// those addresses. IPv4 blocks are listed before IPv6.
+    pub fn summarize_targets(ips: &[IpAddr]) -> Vec<IpCidr> {
+        let mut v4: Vec<u128> = Vec::new();
+        let mut v6: Vec<u128> = Vec::new();
+        for ip in ips {
+            match ip {
+                IpAddr::V4(v) => v4.push(u32::from(*v) as u128),
+                IpAddr::V6(v) => v6.push(u128::from(*v)),
             }
         }
 
-        // Process target ranges
-        for range in ranges {
-            let ips = Self::expand_cidr(range)?;
-            for ip in ips {
-                if !exclude_set.contains(&ip) {
-                    targets.push(ip);
+        let to_v4 = |addr: u128| IpAddr::V4(Ipv4Addr::from(addr as u32));
+        let to_v6 = |addr: u128| IpAddr::V6(Ipv6Addr::from(addr));
+
+        let mut blocks = Self::summarize(v4, 32, to_v4);
+        blocks.extend(Self::summarize(v6, 128, to_v6));
+        blocks
+    }
+
+    fn summarize(mut addrs: Vec<u128>, bits: u32, to_ip: impl Fn(u128) -> IpAddr) -> Vec<IpCidr> {
+        addrs.sort_unstable();
+        addrs.dedup();
+
+        // Merge consecutive addresses into inclusive ranges
+        let mut ranges: Vec<(u128, u128)> = Vec::new();
+        for addr in addrs {
+            match ranges.last_mut() {
+                Some((_, end)) if end.checked_add(1) == Some(addr) => *end = addr,
+                _ => ranges.push((addr, addr)),
+            }
+        }
+
+        // Offset of the last address in a block of 2^size_bits addresses
+        let span = |size_bits: u32| 1u128.checked_shl(size_bits).map_or(u128::MAX, |size| size - 1);
+
+        let mut blocks = Vec::new();
+        for (mut start, end) in ranges {
+            loop {
+                // Largest aligned block starting at `start` that stays in range
+                let mut size_bits = start.trailing_zeros().min(bits);
+                while size_bits > 0 && start + span(size_bits) > end {
+                    size_bits -= 1;
                 }
+
+                if let Ok(block) = IpCidr::new(to_ip(start), (bits - size_bits) as u8) {
+                    blocks.push(block);
+                }
+
+                let last = start + span(size_bits);
+                if last >= end {
+                    break;
+                }
+                start = last + 1;
             }
         }
 
-        Ok(targets)
+        blocks
+    }
+
+    /// Returns true if `ip` falls inside any of the given addresses or
+    /// CIDR ranges. Unparseable entries are ignored.
+    pub fn is_excluded(ip: &IpAddr, excludes: &[String]) -> bool {
+        excludes.iter()
+            .filter_map(|e| e.parse::<IpCidr>().ok())
+            .any(|network| network.contains(ip))
     }
+
+    /// Lists capture-capable interfaces and their addresses.
+    pub fn list_interfaces() -> Result<Vec<NetworkInterface>> {
+        CaptureBackend::ensure_installed()?;
+        let devices = pcap::Device::list()?;
+
+        Ok(devices.into_iter()
+            .map(|device| NetworkInterface {
+                is_up: device.flags.is_up(),
+                // Older Npcap releases don't flag their loopback adapter
+                is_loopback: device.flags.is_loopback() || device.name.ends_with("NPF_Loopback"),
+                addresses: device.addresses.iter().map(|a| a.addr).collect(),
+                name: device.name,
+                description: device.desc,
+            })
+            .collect())
+    }
+
+    /// The interface to capture traffic with `ip` on: one with an address
+    /// in the same /24 (or /64), else the first that is up and not
+    /// loopback.
+    pub fn interface_for(ip: IpAddr) -> Result<Option<NetworkInterface>> {
+        let interfaces: Vec<NetworkInterface> = Self::list_interfaces()?
+            .into_iter()
+            .filter(|interface| interface.is_up && !interface.is_loopback && !interface.addresses.is_empty())
+            .collect();
+
+        let subnet = Self::subnet_of(ip, 24);
+        let local = interfaces.iter()
+            .position(|interface| interface.addresses.iter().any(|address| subnet.contains(address)))
+            .unwrap_or(0);
+
+        Ok(interfaces.into_iter().nth(local))
+    }
+
+    /// Lazily yields the addresses of every range that aren't covered by
+    /// an exclude. Ranges are validated up front; unparseable excludes are
+    /// ignored.
+    pub fn target_iter(ranges: &[String], excludes: &[String]) -> Result<impl Iterator<Item = IpAddr> + Send> {
+        let networks = ranges.iter()
+            .map(|range| range.parse::<IpCidr>().map_err(|_| anyhow::anyhow!("Invalid range: {}", range)))
+            .collect::<Result<Vec<_>>>()?;
+        let excludes: Vec<IpCidr> = excludes.iter()
+            .filter_map(|e| e.parse().ok())
+            .collect();
+
+        Ok(networks.into_iter()
+            .flat_map(Self::iter_network)
+            .filter(move |ip| !excludes.iter().any(|network| network.contains(ip))))
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct NetworkInterface {
+    pub name: String,
+    pub description: Option<String>,
+    pub addresses: Vec<IpAddr>,
+    pub is_up: bool,
+    pub is_loopback: bool,
 }
 
 #[derive(Debug, Clone)]
@@ -0,0 +1,98 @@
+use anyhow::{Result, Context};
+use std::collections::HashMap;
+use std::path::Path;
+
+/// Locations nmap installs its MAC prefix table to, checked in order
+pub const MAC_PREFIXES_PATHS: &[&str] = &[
+    "/usr/share/nmap/nmap-mac-prefixes",
+    "/usr/local/share/nmap/nmap-mac-prefixes",
+    "/opt/homebrew/share/nmap/nmap-mac-prefixes",
+    "C:\\Program Files (x86)\\Nmap\\nmap-mac-prefixes",
+    "C:\\Program Files\\Nmap\\nmap-mac-prefixes",
+];
+
+/// Prefix lengths in hex digits: MA-S, MA-M and MA-L (the classic OUI)
+const PREFIX_LENGTHS: [usize; 3] = [9, 7, 6];
+
+/// Vendor names by MAC address prefix, from a table in nmap-mac-prefixes
+/// format (`<hex prefix> <vendor>` per line).
+pub struct OuiLookup {
+    prefixes: HashMap<String, String>,
+}
+
+impl OuiLookup {
+    pub fn empty() -> Self {
+        Self { prefixes: HashMap::new() }
+    }
+
+    pub fn load(path: impl AsRef<Path>) -> Result<Self> {
+        let path = path.as_ref();
+        let contents = std::fs::read(path)
+            .with_context(|| format!("Failed to read {}", path.display()))?;
+
+        Ok(Self::parse(&String::from_utf8_lossy(&contents)))
+    }
+
+    /// Loads the table from the local nmap install, or returns an empty
+    /// lookup when none is found.
+    pub fn load_default() -> Self {
+        MAC_PREFIXES_PATHS.iter()
+            .map(Path::new)
+            .find(|p| p.exists())
+            .and_then(|p| match Self::load(p) {
+                Ok(lookup) => Some(lookup),
+                Err(e) => {
+                    eprintln!("Failed to load MAC prefixes: {}", e);
+                    None
+                }
+            })
+            .unwrap_or_else(Self::empty)
+    }
+
+    pub fn parse(contents: &str) -> Self {
+        let prefixes = contents.lines()
+            .map(str::trim)
+            .filter(|line| !line.is_empty() && !line.starts_with('#'))
+            .filter_map(|line| line.split_once(char::is_whitespace))
+            .filter(|(prefix, _)| PREFIX_LENGTHS.contains(&prefix.len()))
+            .map(|(prefix, vendor)| (prefix.to_ascii_uppercase(), vendor.trim().to_string()))
+            .collect();
+
+        Self { prefixes }
+    }
+
+    pub fn len(&self) -> usize {
+        self.prefixes.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.prefixes.is_empty()
+    }
+
+    /// The vendor of `mac` in any common notation. Locally administered
+    /// addresses, such as randomized ones, have none.
+    pub fn lookup(&self, mac: &str) -> Option<&str> {
+        let hex: String = mac.chars()
+            .filter(char::is_ascii_hexdigit)
+            .map(|c| c.to_ascii_uppercase())
+            .collect();
+        if hex.len() != 12 {
+            return None;
+        }
+
+        let first_octet = u8::from_str_radix(&hex[..2], 16).ok()?;
+        if first_octet & 0x02 != 0 {
+            return None;
+        }
+
+        PREFIX_LENGTHS.iter()
+            .find_map(|&len| self.prefixes.get(&hex[..len]))
+            .map(String::as_str)
+    }
+}
+
+impl Default for OuiLookup {
+    fn default() -> Self {
+        Self::empty()
+    }
+}
\ No newline at end of file
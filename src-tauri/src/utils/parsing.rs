@@ -1,6 +1,7 @@
 use anyhow::{Result, Context};
 use regex::Regex;
 use std::collections::HashMap;
+use std::net::{IpAddr, Ipv4Addr, Ipv6Addr};
 use serde_json::Value;
 
 pub struct OutputParser;
@@ -111,39 +112,99 @@ pub struct ServiceInfo {
     pub banner: Option<String>,
 }
 
-// Rate limiting utility
+/// Destination networks share a bucket at this prefix length
+const RATE_LIMIT_PREFIX_V4: u8 = 24;
+const RATE_LIMIT_PREFIX_V6: u8 = 64;
+
+/// How often idle buckets are dropped
+const BUCKET_SWEEP_INTERVAL: std::time::Duration = std::time::Duration::from_secs(60);
+
+struct TokenBucket {
+    tokens: f64,
+    last_refill: std::time::Instant,
+}
+
+struct Buckets {
+    by_network: HashMap<IpAddr, TokenBucket>,
+    last_sweep: std::time::Instant,
+}
+
+/// Token buckets keyed by destination network, shared by every scanner so
+/// concurrent scans of one subnet draw from the same allowance.
 pub struct RateLimiter {
-    tokens: tokio::sync::Mutex<f64>,
+    buckets: std::sync::Mutex<Buckets>,
     capacity: f64,
     refill_rate: f64,
-    last_refill: tokio::sync::Mutex<std::time::Instant>,
 }
 
 impl RateLimiter {
     pub fn new(capacity: f64, refill_rate: f64) -> Self {
         Self {
-            tokens: tokio::sync::Mutex::new(capacity),
+            buckets: std::sync::Mutex::new(Buckets {
+                by_network: HashMap::new(),
+                last_sweep: std::time::Instant::now(),
+            }),
             capacity,
             refill_rate,
-            last_refill: tokio::sync::Mutex::new(std::time::Instant::now()),
         }
     }
 
-    pub async fn acquire(&self) -> bool {
+    /// The network address `ip` is rate limited under.
+    pub fn network_of(ip: IpAddr) -> IpAddr {
+        match ip {
+            IpAddr::V4(v4) => {
+                let mask = u32::MAX << (32 - RATE_LIMIT_PREFIX_V4);
+                IpAddr::V4(Ipv4Addr::from(u32::from(v4) & mask))
+            }
+            IpAddr::V6(v6) => {
+                let mask = u128::MAX << (128 - RATE_LIMIT_PREFIX_V6);
+                IpAddr::V6(Ipv6Addr::from(u128::from(v6) & mask))
+            }
+        }
+    }
+
+    /// Takes a token for `ip`'s network, or returns how long until one is
+    /// available.
+    pub fn try_acquire(&self, ip: IpAddr) -> Result<(), std::time::Duration> {
         let now = std::time::Instant::now();
-        let mut last_refill = self.last_refill.lock().await;
-        let mut tokens = self.tokens.lock().await;
+        let mut buckets = self.buckets.lock().unwrap();
+        if now.duration_since(buckets.last_sweep) >= BUCKET_SWEEP_INTERVAL {
+            self.sweep(&mut buckets.by_network, now);
+            buckets.last_sweep = now;
+        }
+
+        let bucket = buckets.by_network.entry(Self::network_of(ip)).or_insert(TokenBucket {
+            tokens: self.capacity,
+            last_refill: now,
+        });
 
         // Refill tokens based on elapsed time
-        let elapsed = now.duration_since(*last_refill).as_secs_f64();
-        *tokens = (*tokens + elapsed * self.refill_rate).min(self.capacity);
-        *last_refill = now;
+        let elapsed = now.duration_since(bucket.last_refill).as_secs_f64();
+        bucket.tokens = (bucket.tokens + elapsed * self.refill_rate).min(self.capacity);
+        bucket.last_refill = now;
 
-        if *tokens >= 1.0 {
-            *tokens -= 1.0;
-            true
+        if bucket.tokens >= 1.0 {
+            bucket.tokens -= 1.0;
+            Ok(())
         } else {
-            false
+            let wait = (1.0 - bucket.tokens) / self.refill_rate.max(f64::EPSILON);
+            Err(std::time::Duration::from_secs_f64(wait))
+        }
+    }
+
+    /// Drops buckets that have refilled completely; a fresh bucket for the
+    /// same network would behave the same.
+    fn sweep(&self, buckets: &mut HashMap<IpAddr, TokenBucket>, now: std::time::Instant) {
+        buckets.retain(|_, bucket| {
+            let elapsed = now.duration_since(bucket.last_refill).as_secs_f64();
+            bucket.tokens + elapsed * self.refill_rate < self.capacity
+        });
+    }
+
+    /// Waits until a token for `ip`'s network is available.
+    pub async fn acquire(&self, ip: IpAddr) {
+        while let Err(wait) = self.try_acquire(ip) {
+            tokio::time::sleep(wait).await;
         }
     }
 }
\ No newline at end of file
@@ -1,10 +1,42 @@
-use anyhow::{Result, Context};
-use std::process::Stdio;
-use tokio::process::Command;
+use crate::error::LegionError;
+use crate::helper::HelperEndpoint;
+use crate::helper::protocol::{check_scanner_args, ADDR_ENV, TOKEN_ENV};
+use anyhow::{Result, Context, bail};
+use serde::{Deserialize, Serialize};
+use std::process::{Output, Stdio};
+use tokio::process::{Child, Command};
 use tokio::io::{AsyncBufReadExt, BufReader, Lines};
 use tokio::sync::mpsc;
+use ts_rs::TS;
+use super::sandbox::{self, SandboxPolicy};
 use std::time::Duration;
 
+const SSH_CHECK_TIMEOUT: Duration = Duration::from_secs(15);
+
+/// A jump box inside the client network that tools are run on over SSH.
+/// Authentication is key-based; the system `ssh` client is used so agent
+/// and `~/.ssh/config` settings apply.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize, TS)]
+#[ts(export, export_to = "../src/bindings/")]
+pub struct SshTarget {
+    pub host: String,
+    #[serde(default = "default_ssh_port")]
+    pub port: u16,
+    pub user: String,
+    #[serde(default)]
+    pub identity_file: Option<String>,
+    /// Run the tool with `sudo -n`, needed for raw-socket scans
+    #[serde(default)]
+    pub use_sudo: bool,
+    /// nmap binary on the remote host; `nmap` on its PATH by default
+    #[serde(default)]
+    pub nmap_path: Option<String>,
+}
+
+fn default_ssh_port() -> u16 {
+    22
+}
+
 pub struct ProcessManager {
     timeout: Duration,
 }
@@ -29,7 +61,7 @@ impl ProcessManager {
         let output = tokio::time::timeout(self.timeout, cmd.output())
             .await
             .context("Command timed out")?
-            .context("Failed to execute command")?;
+            .map_err(|e| LegionError::spawn(command, e))?;
 
         let stdout = String::from_utf8_lossy(&output.stdout).to_string();
         let stderr = String::from_utf8_lossy(&output.stderr).to_string();
@@ -51,7 +83,7 @@ impl ProcessManager {
             .stdout(Stdio::piped())
             .stderr(Stdio::piped())
             .spawn()
-            .context("Failed to spawn process")?;
+            .map_err(|e| LegionError::spawn(command, e))?;
 
         let stdout = child.stdout.take().unwrap();
         let mut reader = BufReader::new(stdout).lines();
@@ -66,6 +98,138 @@ impl ProcessManager {
         Ok(())
     }
 
+    /// Rewrites a local command line to run on `target` over SSH. Output
+    /// streams back over the SSH channel unchanged, so callers parse it
+    /// exactly as they would a local run. Killing the local ssh only drops
+    /// the connection, so the remote shell watches its stdin, which closes
+    /// with the connection, and kills the tool when it does.
+    pub fn wrap_remote(cmd: &Command, target: &SshTarget) -> Command {
+        let std_cmd = cmd.as_std();
+        let mut tool_args = vec![Self::shell_quote(&std_cmd.get_program().to_string_lossy())];
+        tool_args.extend(std_cmd.get_args().map(|a| Self::shell_quote(&a.to_string_lossy())));
+
+        let script = format!(
+            "{} & pid=$!; (cat >/dev/null; kill $pid) >/dev/null 2>&1 & wait $pid",
+            tool_args.join(" ")
+        );
+        let mut remote_args = Vec::new();
+        if target.use_sudo {
+            remote_args.push("sudo".to_string());
+            remote_args.push("-n".to_string());
+        }
+        remote_args.push("sh".to_string());
+        remote_args.push("-c".to_string());
+        remote_args.push(Self::shell_quote(&script));
+
+        let mut ssh = Self::ssh_command(target);
+        ssh.arg("--").arg(remote_args.join(" "))
+            // Held open by the child handle until ssh exits
+            .stdin(Stdio::piped());
+        ssh
+    }
+
+    /// Rewrites a local scanner command line to run through the privileged
+    /// helper. The relay prints the scanner's output as it arrives, so
+    /// callers parse it as they would a direct run.
+    pub fn wrap_helper(cmd: &Command, tool: &str, endpoint: &HelperEndpoint) -> Result<Command> {
+        let args: Vec<String> = cmd.as_std().get_args()
+            .map(|a| a.to_string_lossy().into_owned())
+            .collect();
+        // The helper checks again; failing here gives a clearer error
+        check_scanner_args(tool, &args).map_err(|e| LegionError::Validation(e.to_string()))?;
+
+        let mut relay = Command::new(&endpoint.binary);
+        relay.arg("relay").arg(tool).args(&args)
+            .env(ADDR_ENV, endpoint.addr.to_string())
+            .env(TOKEN_ENV, &endpoint.token);
+        Ok(relay)
+    }
+
+    /// Verifies the jump box accepts a non-interactive login.
+    pub async fn check_ssh(target: &SshTarget) -> Result<()> {
+        Self::validate_ssh_target(target)?;
+
+        let mut ssh = Self::ssh_command(target);
+        ssh.arg("--").arg("true")
+            .stdout(Stdio::null())
+            .stderr(Stdio::piped());
+
+        let output = tokio::time::timeout(SSH_CHECK_TIMEOUT, ssh.output())
+            .await
+            .context("SSH connection timed out")?
+            .context("Failed to run ssh")?;
+
+        if !output.status.success() {
+            bail!(
+                "SSH login to {}@{} failed: {}",
+                target.user,
+                target.host,
+                String::from_utf8_lossy(&output.stderr).trim()
+            );
+        }
+
+        Ok(())
+    }
+
+    pub fn validate_ssh_target(target: &SshTarget) -> Result<()> {
+        if target.host.trim().is_empty() || target.host.starts_with('-') {
+            bail!("Invalid SSH host: {}", target.host);
+        }
+        if target.user.trim().is_empty() || target.user.starts_with('-') {
+            bail!("Invalid SSH user: {}", target.user);
+        }
+        if target.port == 0 {
+            bail!("Invalid SSH port: 0");
+        }
+        Ok(())
+    }
+
+    fn ssh_command(target: &SshTarget) -> Command {
+        let mut ssh = Command::new("ssh");
+        ssh.args(["-o", "BatchMode=yes", "-o", "ConnectTimeout=10"])
+            .arg("-p").arg(target.port.to_string());
+
+        if let Some(identity) = &target.identity_file {
+            ssh.arg("-i").arg(identity);
+        }
+
+        ssh.arg(format!("{}@{}", target.user, target.host));
+        ssh
+    }
+
+    fn shell_quote(arg: &str) -> String {
+        if !arg.is_empty() && arg.chars().all(|c| c.is_ascii_alphanumeric() || "-_./:=,@+".contains(c)) {
+            return arg.to_string();
+        }
+        format!("'{}'", arg.replace('\'', "'\\''"))
+    }
+
+    /// Spawns a local scanner under `policy`. Tools run on a jump box over
+    /// SSH are outside this host's control and should use plain `spawn`.
+    pub fn spawn_sandboxed(cmd: &mut Command, policy: &SandboxPolicy) -> std::io::Result<Child> {
+        if !policy.enabled {
+            return cmd.spawn();
+        }
+
+        sandbox::prepare(cmd, policy)?;
+        let mut child = cmd.spawn()?;
+        // Never leave an unconfined scanner running
+        if let Err(e) = sandbox::confine(&child) {
+            let _ = child.start_kill();
+            return Err(e);
+        }
+        Ok(child)
+    }
+
+    /// `spawn_sandboxed` for a tool whose output is read once it exits.
+    pub async fn output_sandboxed(cmd: &mut Command, policy: &SandboxPolicy) -> std::io::Result<Output> {
+        cmd.stdin(Stdio::null())
+            .stdout(Stdio::piped())
+            .stderr(Stdio::piped())
+            .kill_on_drop(true);
+        Self::spawn_sandboxed(cmd, policy)?.wait_with_output().await
+    }
+
     pub async fn kill_process_tree(pid: u32) -> Result<()> {
         #[cfg(unix)]
         {
@@ -0,0 +1,377 @@
+use serde::{Deserialize, Serialize};
+use std::io;
+use std::path::Path;
+use tokio::process::{Child, Command};
+
+/// Restrictions for locally run scanner processes. Off by default: tools
+/// installed setuid or with file capabilities lose those privileges under
+/// the seccomp filter (it sets no_new_privs), so enabling it only makes
+/// sense when the app itself runs as root or the tools are run by a user
+/// with CAP_NET_RAW.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct SandboxPolicy {
+    pub enabled: bool,
+    /// Unix account the tools run as when the app runs as root. Only
+    /// CAP_NET_RAW and CAP_NET_ADMIN are kept, which raw scans need.
+    pub user: Option<String>,
+    /// Linux: deny syscalls a scanner never needs (ptrace, mount, module
+    /// loading, namespaces, bpf, ...)
+    pub seccomp: bool,
+}
+
+impl Default for SandboxPolicy {
+    fn default() -> Self {
+        Self {
+            enabled: false,
+            user: None,
+            seccomp: true,
+        }
+    }
+}
+
+/// Sets up the restrictions that must be in place before the child runs.
+/// Everything is computed here; the pre-exec hook only issues syscalls, as
+/// nothing else is safe between fork and exec.
+#[cfg(unix)]
+pub(crate) fn prepare(cmd: &mut Command, policy: &SandboxPolicy) -> io::Result<()> {
+    // An unprivileged app already runs the tools unprivileged
+    let credentials = match &policy.user {
+        Some(user) if unsafe { libc::geteuid() } == 0 => Some(lookup_user(user)?),
+        _ => None,
+    };
+
+    #[cfg(target_os = "linux")]
+    let filter = match policy.seccomp {
+        true => seccomp::filter(),
+        false => None,
+    };
+
+    unsafe {
+        cmd.pre_exec(move || {
+            if let Some((uid, gid)) = credentials {
+                drop_privileges(uid, gid)?;
+            }
+            #[cfg(target_os = "linux")]
+            if let Some(filter) = &filter {
+                seccomp::install(filter)?;
+            }
+            Ok(())
+        });
+    }
+
+    Ok(())
+}
+
+#[cfg(windows)]
+pub(crate) fn prepare(_cmd: &mut Command, _policy: &SandboxPolicy) -> io::Result<()> {
+    Ok(())
+}
+
+/// Hands `path` to the sandbox account, so a tool that has dropped to it can
+/// use a file or directory the app created for it. Nothing to do unless the
+/// sandbox switches users.
+#[cfg(unix)]
+pub fn grant(path: &Path, policy: &SandboxPolicy) -> io::Result<()> {
+    let Some(user) = policy.user.as_ref().filter(|_| policy.enabled) else {
+        return Ok(());
+    };
+    if unsafe { libc::geteuid() } != 0 {
+        return Ok(());
+    }
+
+    let (uid, gid) = lookup_user(user)?;
+    std::os::unix::fs::chown(path, Some(uid), Some(gid))
+}
+
+#[cfg(windows)]
+pub fn grant(_path: &Path, _policy: &SandboxPolicy) -> io::Result<()> {
+    Ok(())
+}
+
+/// Restrictions applied once the child exists.
+#[cfg(unix)]
+pub(crate) fn confine(_child: &Child) -> io::Result<()> {
+    Ok(())
+}
+
+/// Places the child in a job object that keeps it from spawning further
+/// processes or touching the desktop. The child has already started by
+/// the time it is assigned; scanners don't spawn anything in their first
+/// instructions, so the window doesn't matter in practice.
+#[cfg(windows)]
+pub(crate) fn confine(child: &Child) -> io::Result<()> {
+    use windows_sys::Win32::Foundation::CloseHandle;
+    use windows_sys::Win32::System::JobObjects::{AssignProcessToJobObject, CreateJobObjectW};
+
+    // Already exited
+    let Some(process) = child.raw_handle() else {
+        return Ok(());
+    };
+
+    unsafe {
+        let job = CreateJobObjectW(std::ptr::null(), std::ptr::null());
+        if job == 0 {
+            return Err(io::Error::last_os_error());
+        }
+        // Closing our handle leaves the job alive for as long as the child
+        // is in it
+        let result = job_limits(job).and_then(|_| win32(AssignProcessToJobObject(job, process as _)));
+        CloseHandle(job);
+        result
+    }
+}
+
+#[cfg(windows)]
+unsafe fn job_limits(job: windows_sys::Win32::Foundation::HANDLE) -> io::Result<()> {
+    use std::mem::size_of;
+    use windows_sys::Win32::System::JobObjects::*;
+
+    let mut limits: JOBOBJECT_EXTENDED_LIMIT_INFORMATION = std::mem::zeroed();
+    limits.BasicLimitInformation.LimitFlags =
+        JOB_OBJECT_LIMIT_ACTIVE_PROCESS | JOB_OBJECT_LIMIT_DIE_ON_UNHANDLED_EXCEPTION;
+    limits.BasicLimitInformation.ActiveProcessLimit = 1;
+    win32(SetInformationJobObject(
+        job,
+        JobObjectExtendedLimitInformation,
+        &limits as *const _ as *const _,
+        size_of::<JOBOBJECT_EXTENDED_LIMIT_INFORMATION>() as u32,
+    ))?;
+
+    let ui = JOBOBJECT_BASIC_UI_RESTRICTIONS {
+        UIRestrictionsClass: JOB_OBJECT_UILIMIT_DESKTOP
+            | JOB_OBJECT_UILIMIT_DISPLAYSETTINGS
+            | JOB_OBJECT_UILIMIT_EXITWINDOWS
+            | JOB_OBJECT_UILIMIT_GLOBALATOMS
+            | JOB_OBJECT_UILIMIT_HANDLES
+            | JOB_OBJECT_UILIMIT_READCLIPBOARD
+            | JOB_OBJECT_UILIMIT_SYSTEMPARAMETERS
+            | JOB_OBJECT_UILIMIT_WRITECLIPBOARD,
+    };
+    win32(SetInformationJobObject(
+        job,
+        JobObjectBasicUIRestrictions,
+        &ui as *const _ as *const _,
+        size_of::<JOBOBJECT_BASIC_UI_RESTRICTIONS>() as u32,
+    ))
+}
+
+#[cfg(windows)]
+fn win32(ok: windows_sys::Win32::Foundation::BOOL) -> io::Result<()> {
+    if ok == 0 {
+        return Err(io::Error::last_os_error());
+    }
+    Ok(())
+}
+
+#[cfg(unix)]
+fn lookup_user(name: &str) -> io::Result<(libc::uid_t, libc::gid_t)> {
+    let c_name = std::ffi::CString::new(name)
+        .map_err(|_| io::Error::new(io::ErrorKind::InvalidInput, "Invalid sandbox user name"))?;
+    let mut passwd: libc::passwd = unsafe { std::mem::zeroed() };
+    let mut buffer = vec![0 as libc::c_char; 4096];
+    let mut found: *mut libc::passwd = std::ptr::null_mut();
+
+    let rc = unsafe {
+        libc::getpwnam_r(c_name.as_ptr(), &mut passwd, buffer.as_mut_ptr(), buffer.len(), &mut found)
+    };
+    if rc != 0 {
+        return Err(io::Error::from_raw_os_error(rc));
+    }
+    if found.is_null() {
+        return Err(io::Error::new(
+            io::ErrorKind::NotFound,
+            format!("Sandbox user '{}' does not exist", name),
+        ));
+    }
+
+    Ok((passwd.pw_uid, passwd.pw_gid))
+}
+
+/// Switches to the sandbox account, keeping only the network capabilities.
+/// Runs in the forked child.
+#[cfg(unix)]
+fn drop_privileges(uid: libc::uid_t, gid: libc::gid_t) -> io::Result<()> {
+    unsafe {
+        #[cfg(target_os = "linux")]
+        check(libc::prctl(libc::PR_SET_KEEPCAPS, 1, 0, 0, 0))?;
+        check(libc::setgroups(1, &gid))?;
+        check(libc::setgid(gid))?;
+        check(libc::setuid(uid))?;
+        #[cfg(target_os = "linux")]
+        capabilities::keep_network()?;
+    }
+    Ok(())
+}
+
+#[cfg(unix)]
+fn check(rc: libc::c_int) -> io::Result<()> {
+    if rc == -1 {
+        return Err(io::Error::last_os_error());
+    }
+    Ok(())
+}
+
+#[cfg(target_os = "linux")]
+mod capabilities {
+    use super::check;
+    use std::io;
+
+    const CAP_NET_ADMIN: u32 = 12;
+    const CAP_NET_RAW: u32 = 13;
+    const LINUX_CAPABILITY_VERSION_3: u32 = 0x2008_0522;
+
+    #[repr(C)]
+    struct Header {
+        version: u32,
+        pid: libc::c_int,
+    }
+
+    #[repr(C)]
+    struct Data {
+        effective: u32,
+        permitted: u32,
+        inheritable: u32,
+    }
+
+    /// Narrows the capabilities kept across setuid to the network pair and
+    /// raises them as ambient, so they survive the exec into the scanner.
+    pub(super) unsafe fn keep_network() -> io::Result<()> {
+        let mask = (1 << CAP_NET_ADMIN) | (1 << CAP_NET_RAW);
+        let header = Header { version: LINUX_CAPABILITY_VERSION_3, pid: 0 };
+        let data = [
+            Data { effective: mask, permitted: mask, inheritable: mask },
+            Data { effective: 0, permitted: 0, inheritable: 0 },
+        ];
+        if libc::syscall(libc::SYS_capset, &header, data.as_ptr()) == -1 {
+            return Err(io::Error::last_os_error());
+        }
+
+        for cap in [CAP_NET_ADMIN, CAP_NET_RAW] {
+            check(libc::prctl(
+                libc::PR_CAP_AMBIENT,
+                libc::PR_CAP_AMBIENT_RAISE as libc::c_ulong,
+                cap as libc::c_ulong,
+                0 as libc::c_ulong,
+                0 as libc::c_ulong,
+            ))?;
+        }
+        Ok(())
+    }
+}
+
+#[cfg(target_os = "linux")]
+mod seccomp {
+    use super::check;
+    use std::io;
+
+    const BPF_LD_W_ABS: u16 = 0x20;
+    const BPF_JEQ_K: u16 = 0x15;
+    #[cfg(target_arch = "x86_64")]
+    const BPF_JGE_K: u16 = 0x35;
+    const BPF_RET_K: u16 = 0x06;
+
+    const SECCOMP_RET_KILL_PROCESS: u32 = 0x8000_0000;
+    const SECCOMP_RET_ERRNO: u32 = 0x0005_0000;
+    const SECCOMP_RET_ALLOW: u32 = 0x7fff_0000;
+
+    // Offsets into struct seccomp_data
+    const OFFSET_NR: u32 = 0;
+    const OFFSET_ARCH: u32 = 4;
+
+    #[cfg(target_arch = "x86_64")]
+    const AUDIT_ARCH: u32 = 0xc000_003e;
+    #[cfg(target_arch = "aarch64")]
+    const AUDIT_ARCH: u32 = 0xc000_00b7;
+
+    /// Syscall numbers above this are the x32 ABI, which would otherwise
+    /// slip past the denylist
+    #[cfg(target_arch = "x86_64")]
+    const X32_SYSCALL_BIT: u32 = 0x4000_0000;
+
+    #[cfg(any(target_arch = "x86_64", target_arch = "aarch64"))]
+    const DENIED_SYSCALLS: &[libc::c_long] = &[
+        libc::SYS_ptrace,
+        libc::SYS_process_vm_readv,
+        libc::SYS_process_vm_writev,
+        libc::SYS_mount,
+        libc::SYS_umount2,
+        libc::SYS_pivot_root,
+        libc::SYS_chroot,
+        libc::SYS_setns,
+        libc::SYS_unshare,
+        libc::SYS_init_module,
+        libc::SYS_finit_module,
+        libc::SYS_delete_module,
+        libc::SYS_kexec_load,
+        libc::SYS_reboot,
+        libc::SYS_swapon,
+        libc::SYS_swapoff,
+        libc::SYS_bpf,
+        libc::SYS_perf_event_open,
+        libc::SYS_keyctl,
+        libc::SYS_add_key,
+        libc::SYS_request_key,
+        libc::SYS_open_by_handle_at,
+        libc::SYS_userfaultfd,
+        libc::SYS_acct,
+        libc::SYS_settimeofday,
+        libc::SYS_clock_settime,
+        libc::SYS_sethostname,
+        libc::SYS_setdomainname,
+    ];
+
+    fn stmt(code: u16, k: u32) -> libc::sock_filter {
+        libc::sock_filter { code, jt: 0, jf: 0, k }
+    }
+
+    fn jump(code: u16, k: u32, jt: u8, jf: u8) -> libc::sock_filter {
+        libc::sock_filter { code, jt, jf, k }
+    }
+
+    /// Denied syscalls fail with EPERM rather than killing the scanner, so a
+    /// tool probing for an optional feature degrades instead of crashing.
+    #[cfg(any(target_arch = "x86_64", target_arch = "aarch64"))]
+    pub(super) fn filter() -> Option<Vec<libc::sock_filter>> {
+        let mut filter = vec![
+            stmt(BPF_LD_W_ABS, OFFSET_ARCH),
+            jump(BPF_JEQ_K, AUDIT_ARCH, 1, 0),
+            stmt(BPF_RET_K, SECCOMP_RET_KILL_PROCESS),
+            stmt(BPF_LD_W_ABS, OFFSET_NR),
+        ];
+        #[cfg(target_arch = "x86_64")]
+        filter.extend([
+            jump(BPF_JGE_K, X32_SYSCALL_BIT, 0, 1),
+            stmt(BPF_RET_K, SECCOMP_RET_KILL_PROCESS),
+        ]);
+        for nr in DENIED_SYSCALLS {
+            filter.push(jump(BPF_JEQ_K, *nr as u32, 0, 1));
+            filter.push(stmt(BPF_RET_K, SECCOMP_RET_ERRNO | libc::EPERM as u32));
+        }
+        filter.push(stmt(BPF_RET_K, SECCOMP_RET_ALLOW));
+        Some(filter)
+    }
+
+    /// No filter is maintained for other architectures
+    #[cfg(not(any(target_arch = "x86_64", target_arch = "aarch64")))]
+    pub(super) fn filter() -> Option<Vec<libc::sock_filter>> {
+        None
+    }
+
+    /// Runs in the forked child, after the privilege drop.
+    pub(super) fn install(filter: &[libc::sock_filter]) -> io::Result<()> {
+        let program = libc::sock_fprog {
+            len: filter.len() as libc::c_ushort,
+            filter: filter.as_ptr() as *mut libc::sock_filter,
+        };
+        unsafe {
+            check(libc::prctl(libc::PR_SET_NO_NEW_PRIVS, 1, 0, 0, 0))?;
+            check(libc::prctl(
+                libc::PR_SET_SECCOMP,
+                libc::SECCOMP_MODE_FILTER,
+                &program as *const libc::sock_fprog,
+            ))?;
+        }
+        Ok(())
+    }
+}
\ No newline at end of file
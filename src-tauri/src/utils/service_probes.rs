@@ -0,0 +1,336 @@
+use anyhow::{Result, Context};
+use regex::bytes::{Regex, RegexBuilder};
+use serde::{Deserialize, Serialize};
+use std::path::Path;
+
+/// Locations nmap installs its probe database to, checked in order
+pub const SERVICE_PROBES_PATHS: &[&str] = &[
+    "/usr/share/nmap/nmap-service-probes",
+    "/usr/local/share/nmap/nmap-service-probes",
+    "/opt/homebrew/share/nmap/nmap-service-probes",
+    "C:\\Program Files (x86)\\Nmap\\nmap-service-probes",
+    "C:\\Program Files\\Nmap\\nmap-service-probes",
+];
+
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct ServiceMatch {
+    pub service: String,
+    pub product: Option<String>,
+    pub version: Option<String>,
+    pub info: Option<String>,
+    pub hostname: Option<String>,
+    pub os: Option<String>,
+    pub device_type: Option<String>,
+    pub cpe: Vec<String>,
+    /// Softmatches only identify the service, not the product
+    pub soft: bool,
+}
+
+impl ServiceMatch {
+    /// Product and version joined the way nmap prints them in its VERSION
+    /// column.
+    pub fn version_string(&self) -> Option<String> {
+        let parts: Vec<&str> = [&self.product, &self.version, &self.info]
+            .iter()
+            .filter_map(|p| p.as_deref())
+            .collect();
+
+        (!parts.is_empty()).then(|| parts.join(" "))
+    }
+}
+
+struct MatchRule {
+    service: String,
+    pattern: Regex,
+    template: VersionTemplate,
+    soft: bool,
+}
+
+#[derive(Default)]
+struct VersionTemplate {
+    product: Option<String>,
+    version: Option<String>,
+    info: Option<String>,
+    hostname: Option<String>,
+    os: Option<String>,
+    device_type: Option<String>,
+    cpe: Vec<String>,
+}
+
+struct Probe {
+    protocol: String,
+    name: String,
+    ports: Vec<(u16, u16)>,
+    rules: Vec<MatchRule>,
+}
+
+impl Probe {
+    fn covers_port(&self, port: u16) -> bool {
+        self.ports.iter().any(|(start, end)| (*start..=*end).contains(&port))
+    }
+}
+
+/// Applies nmap-service-probes match lines to banners collected without
+/// nmap (masscan `--banners`, native grabs). Patterns the Rust regex engine
+/// cannot express (backreferences, lookaround) are skipped.
+pub struct ServiceProbeMatcher {
+    probes: Vec<Probe>,
+}
+
+impl ServiceProbeMatcher {
+    pub fn empty() -> Self {
+        Self { probes: Vec::new() }
+    }
+
+    pub fn load(path: impl AsRef<Path>) -> Result<Self> {
+        let path = path.as_ref();
+        let contents = std::fs::read(path)
+            .with_context(|| format!("Failed to read {}", path.display()))?;
+
+        Ok(Self::parse(&String::from_utf8_lossy(&contents)))
+    }
+
+    /// Loads the probe database from the local nmap install, or returns an
+    /// empty matcher when none is found.
+    pub fn load_default() -> Self {
+        SERVICE_PROBES_PATHS.iter()
+            .map(Path::new)
+            .find(|p| p.exists())
+            .and_then(|p| match Self::load(p) {
+                Ok(matcher) => Some(matcher),
+                Err(e) => {
+                    eprintln!("Failed to load service probes: {}", e);
+                    None
+                }
+            })
+            .unwrap_or_else(Self::empty)
+    }
+
+    pub fn parse(contents: &str) -> Self {
+        let mut probes: Vec<Probe> = Vec::new();
+
+        for line in contents.lines() {
+            let line = line.trim();
+            if line.is_empty() || line.starts_with('#') {
+                continue;
+            }
+
+            let (directive, rest) = line.split_once(' ').unwrap_or((line, ""));
+            match directive {
+                "Probe" => {
+                    let mut fields = rest.split_whitespace();
+                    let protocol = fields.next().unwrap_or("TCP").to_lowercase();
+                    let name = fields.next().unwrap_or_default().to_string();
+                    probes.push(Probe {
+                        protocol,
+                        name,
+                        ports: Vec::new(),
+                        rules: Vec::new(),
+                    });
+                }
+                "ports" | "sslports" => {
+                    if let Some(probe) = probes.last_mut() {
+                        probe.ports.extend(Self::parse_ports(rest));
+                    }
+                }
+                "match" | "softmatch" => {
+                    if let (Some(probe), Some(rule)) =
+                        (probes.last_mut(), Self::parse_match(rest, directive == "softmatch"))
+                    {
+                        probe.rules.push(rule);
+                    }
+                }
+                _ => {}
+            }
+        }
+
+        Self { probes }
+    }
+
+    pub fn rule_count(&self) -> usize {
+        self.probes.iter().map(|p| p.rules.len()).sum()
+    }
+
+    /// Matches a banner received on `port`. Probes registered for the port
+    /// are tried first, then the rest; a hard match beats any softmatch.
+    pub fn match_banner(&self, banner: &[u8], port: u16, protocol: &str) -> Option<ServiceMatch> {
+        let protocol = protocol.to_lowercase();
+        let candidates = self.probes.iter().filter(|p| p.protocol == protocol);
+
+        let (preferred, others): (Vec<&Probe>, Vec<&Probe>) =
+            candidates.partition(|p| p.name == "NULL" || p.covers_port(port));
+
+        let mut soft_match = None;
+        for probe in preferred.into_iter().chain(others) {
+            for rule in &probe.rules {
+                let Some(captures) = rule.pattern.captures(banner) else {
+                    continue;
+                };
+
+                let found = Self::build_match(rule, &captures);
+                if !rule.soft {
+                    return Some(found);
+                }
+                soft_match.get_or_insert(found);
+            }
+        }
+
+        soft_match
+    }
+
+    fn build_match(rule: &MatchRule, captures: &regex::bytes::Captures) -> ServiceMatch {
+        let expand = |field: &Option<String>| {
+            field.as_deref()
+                .map(|t| Self::substitute(t, captures))
+                .filter(|v| !v.is_empty())
+        };
+
+        ServiceMatch {
+            service: rule.service.clone(),
+            product: expand(&rule.template.product),
+            version: expand(&rule.template.version),
+            info: expand(&rule.template.info),
+            hostname: expand(&rule.template.hostname),
+            os: expand(&rule.template.os),
+            device_type: expand(&rule.template.device_type),
+            cpe: rule.template.cpe.iter()
+                .map(|c| Self::substitute(c, captures))
+                .collect(),
+            soft: rule.soft,
+        }
+    }
+
+    /// Expands `$N`, `$P(N)` and `$SUBST(N,"from","to")` references in a
+    /// version field.
+    fn substitute(template: &str, captures: &regex::bytes::Captures) -> String {
+        let group = |n: usize| {
+            captures.get(n)
+                .map(|m| String::from_utf8_lossy(m.as_bytes()).to_string())
+                .unwrap_or_default()
+        };
+
+        let mut out = String::new();
+        let mut rest = template;
+
+        while let Some(pos) = rest.find('$') {
+            out.push_str(&rest[..pos]);
+            rest = &rest[pos + 1..];
+
+            if let Some(digit) = rest.chars().next().and_then(|c| c.to_digit(10)) {
+                out.push_str(&group(digit as usize));
+                rest = &rest[1..];
+            } else if let Some(args) = Self::function_args(rest, "P(") {
+                let n = args.trim().parse().unwrap_or(0);
+                out.extend(group(n).chars().filter(|c| !c.is_control()));
+                rest = &rest[2 + args.len() + 1..];
+            } else if let Some(args) = Self::function_args(rest, "SUBST(") {
+                let parts: Vec<&str> = args.splitn(3, ',').collect();
+                if let [n, from, to] = parts[..] {
+                    let n = n.trim().parse().unwrap_or(0);
+                    out.push_str(&group(n).replace(from.trim().trim_matches('"'), to.trim().trim_matches('"')));
+                }
+                rest = &rest[6 + args.len() + 1..];
+            } else {
+                out.push('$');
+            }
+        }
+
+        out.push_str(rest);
+        out.trim().to_string()
+    }
+
+    fn function_args<'a>(text: &'a str, prefix: &str) -> Option<&'a str> {
+        let body = text.strip_prefix(prefix)?;
+        let end = body.find(')')?;
+        Some(&body[..end])
+    }
+
+    /// Parses `<service> m<d><pattern><d>[flags] [p/../ v/../ ... cpe:/../]`.
+    fn parse_match(rest: &str, soft: bool) -> Option<MatchRule> {
+        let (service, rest) = rest.trim().split_once(' ')?;
+        let rest = rest.trim_start().strip_prefix('m')?;
+
+        let delimiter = rest.chars().next()?;
+        let body = &rest[delimiter.len_utf8()..];
+        let end = body.find(delimiter)?;
+        let pattern = &body[..end];
+        let after = &body[end + delimiter.len_utf8()..];
+
+        let flags: String = after.chars().take_while(|c| c.is_ascii_alphabetic()).collect();
+        let fields = &after[flags.len()..];
+
+        let pattern = RegexBuilder::new(pattern)
+            .unicode(false)
+            .case_insensitive(flags.contains('i'))
+            .dot_matches_new_line(flags.contains('s'))
+            .build()
+            .ok()?;
+
+        Some(MatchRule {
+            service: service.to_string(),
+            pattern,
+            template: Self::parse_template(fields),
+            soft,
+        })
+    }
+
+    fn parse_template(fields: &str) -> VersionTemplate {
+        let mut template = VersionTemplate::default();
+        let mut rest = fields.trim();
+
+        while !rest.is_empty() {
+            let (key, after_key) = if let Some(after) = rest.strip_prefix("cpe:") {
+                ("cpe", after)
+            } else {
+                let mut chars = rest.chars();
+                let key = chars.next().unwrap();
+                (&rest[..key.len_utf8()], chars.as_str())
+            };
+
+            let Some(delimiter) = after_key.chars().next() else {
+                break;
+            };
+            let body = &after_key[delimiter.len_utf8()..];
+            let Some(end) = body.find(delimiter) else {
+                break;
+            };
+            let value = body[..end].to_string();
+
+            match key {
+                "p" => template.product = Some(value),
+                "v" => template.version = Some(value),
+                "i" => template.info = Some(value),
+                "h" => template.hostname = Some(value),
+                "o" => template.os = Some(value),
+                "d" => template.device_type = Some(value),
+                "cpe" => template.cpe.push(format!("cpe:/{}", value)),
+                _ => {}
+            }
+
+            // Skip the value, its closing delimiter and any trailing flags
+            rest = body[end + delimiter.len_utf8()..]
+                .trim_start_matches(|c: char| c.is_ascii_alphabetic())
+                .trim_start();
+        }
+
+        template
+    }
+
+    fn parse_ports(spec: &str) -> Vec<(u16, u16)> {
+        spec.split(',')
+            .filter_map(|part| {
+                let part = part.trim();
+                match part.split_once('-') {
+                    Some((start, end)) => Some((start.parse().ok()?, end.parse().ok()?)),
+                    None => part.parse().ok().map(|p| (p, p)),
+                }
+            })
+            .collect()
+    }
+}
+
+impl Default for ServiceProbeMatcher {
+    fn default() -> Self {
+        Self::empty()
+    }
+}
\ No newline at end of file
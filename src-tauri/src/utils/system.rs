@@ -0,0 +1,38 @@
+use serde::{Deserialize, Serialize};
+
+/// Host resources scan concurrency is sized from. Memory and the
+/// descriptor limit are only read on Linux; elsewhere they are `None` and
+/// don't constrain anything.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct SystemResources {
+    pub cpu_count: usize,
+    pub available_memory_mb: Option<u64>,
+    pub fd_limit: Option<u64>,
+}
+
+impl SystemResources {
+    pub fn detect() -> Self {
+        Self {
+            cpu_count: std::thread::available_parallelism().map_or(1, |n| n.get()),
+            available_memory_mb: Self::read_available_memory_mb(),
+            fd_limit: Self::read_fd_limit(),
+        }
+    }
+
+    fn read_available_memory_mb() -> Option<u64> {
+        let meminfo = std::fs::read_to_string("/proc/meminfo").ok()?;
+        meminfo.lines()
+            .find_map(|line| line.strip_prefix("MemAvailable:"))
+            .and_then(|value| value.trim().trim_end_matches("kB").trim().parse::<u64>().ok())
+            .map(|kb| kb / 1024)
+    }
+
+    /// Soft limit from the "Max open files" row of /proc/self/limits.
+    fn read_fd_limit() -> Option<u64> {
+        let limits = std::fs::read_to_string("/proc/self/limits").ok()?;
+        limits.lines()
+            .find_map(|line| line.strip_prefix("Max open files"))
+            .and_then(|values| values.split_whitespace().next())
+            .and_then(|soft| soft.parse().ok())
+    }
+}
@@ -1,19 +1,34 @@
+use std::collections::BTreeMap;
 use std::net::{IpAddr, Ipv4Addr, Ipv6Addr};
-use anyhow::{Result, bail};
+use crate::error::LegionError;
+use anyhow::Result;
 use regex::Regex;
 
+/// Validation failures carry the `validation` code through to the frontend.
+macro_rules! invalid {
+    ($($arg:tt)*) => {
+        anyhow::Error::from(LegionError::Validation(format!($($arg)*)))
+    };
+}
+
+macro_rules! bail {
+    ($($arg:tt)*) => {
+        return Err(invalid!($($arg)*))
+    };
+}
+
 pub struct InputValidator;
 
 impl InputValidator {
     pub fn validate_ip(ip: &str) -> Result<IpAddr> {
         ip.parse::<IpAddr>()
-            .map_err(|_| anyhow::anyhow!("Invalid IP address: {}", ip))
+            .map_err(|_| invalid!("Invalid IP address: {}", ip))
     }
 
     pub fn validate_cidr(cidr: &str) -> Result<()> {
         use cidr::IpCidr;
         cidr.parse::<IpCidr>()
-            .map_err(|_| anyhow::anyhow!("Invalid CIDR notation: {}", cidr))?;
+            .map_err(|_| invalid!("Invalid CIDR notation: {}", cidr))?;
         Ok(())
     }
 
@@ -30,9 +45,9 @@ impl InputValidator {
                 }
                 
                 let start: u16 = range[0].parse()
-                    .map_err(|_| anyhow::anyhow!("Invalid start port: {}", range[0]))?;
+                    .map_err(|_| invalid!("Invalid start port: {}", range[0]))?;
                 let end: u16 = range[1].parse()
-                    .map_err(|_| anyhow::anyhow!("Invalid end port: {}", range[1]))?;
+                    .map_err(|_| invalid!("Invalid end port: {}", range[1]))?;
                 
                 if start > end || end > 65535 {
                     bail!("Invalid port range: {}-{}", start, end);
@@ -43,7 +58,7 @@ impl InputValidator {
                 }
             } else {
                 let port: u16 = part.parse()
-                    .map_err(|_| anyhow::anyhow!("Invalid port: {}", part))?;
+                    .map_err(|_| invalid!("Invalid port: {}", part))?;
                 
                 if port > 65535 {
                     bail!("Port out of range: {}", port);
@@ -56,6 +71,18 @@ impl InputValidator {
         Ok(port_list)
     }
 
+    /// Accepts an nmap decoy: an IP address, `ME`, or `RND`/`RND:<n>`.
+    pub fn validate_decoy(decoy: &str) -> Result<()> {
+        if decoy == "ME" || decoy == "RND" || decoy.parse::<IpAddr>().is_ok() {
+            return Ok(());
+        }
+
+        match decoy.strip_prefix("RND:").map(str::parse::<u8>) {
+            Some(Ok(count)) if count > 0 => Ok(()),
+            _ => bail!("Invalid decoy: {}", decoy),
+        }
+    }
+
     pub fn validate_hostname(hostname: &str) -> Result<()> {
         let hostname_regex = Regex::new(r"^[a-zA-Z0-9]([a-zA-Z0-9\-]{0,61}[a-zA-Z0-9])?(\.[a-zA-Z0-9]([a-zA-Z0-9\-]{0,61}[a-zA-Z0-9])?)*$")
             .unwrap();
@@ -78,9 +105,78 @@ impl InputValidator {
             .collect()
     }
 
+    pub fn validate_severity(severity: &str) -> Result<()> {
+        match severity {
+            "info" | "low" | "medium" | "high" | "critical" => Ok(()),
+            _ => bail!("Invalid severity: {}", severity),
+        }
+    }
+
+    pub fn validate_criticality(criticality: &str) -> Result<()> {
+        match criticality {
+            "low" | "medium" | "high" | "critical" => Ok(()),
+            _ => bail!("Invalid criticality: {}", criticality),
+        }
+    }
+
+    pub fn validate_cvss_score(score: f32) -> Result<()> {
+        if !(0.0..=10.0).contains(&score) {
+            bail!("CVSS score must be between 0.0 and 10.0: {}", score);
+        }
+        Ok(())
+    }
+
+    pub fn validate_username(username: &str) -> Result<()> {
+        if username.is_empty() || username.len() > 64 {
+            bail!("Username must be 1-64 characters");
+        }
+
+        if !username.chars().all(|c| c.is_ascii_alphanumeric() || matches!(c, '.' | '_' | '-')) {
+            bail!("Invalid username: {}", username);
+        }
+
+        Ok(())
+    }
+
+    /// A `--script` entry: a script name, category or wildcard pattern.
+    /// Paths and boolean expressions are refused, so only installed
+    /// scripts can be selected.
+    pub fn validate_script_selection(selection: &str) -> Result<()> {
+        if selection.is_empty() || selection.len() > 128 {
+            bail!("Script selection must be 1-128 characters");
+        }
+
+        if !selection.chars().all(|c| c.is_ascii_alphanumeric() || matches!(c, '-' | '_' | '.' | '*')) {
+            bail!("Invalid script selection: {}", selection);
+        }
+
+        Ok(())
+    }
+
+    /// Builds a `--script-args` value. Keys are checked and every value is
+    /// quoted, so commas, braces and equals signs in a value stay part of it.
+    pub fn format_script_args(args: &BTreeMap<String, String>) -> Result<String> {
+        let mut formatted = Vec::with_capacity(args.len());
+        for (key, value) in args {
+            if key.is_empty() || key.len() > 128
+                || !key.chars().all(|c| c.is_ascii_alphanumeric() || matches!(c, '-' | '_' | '.'))
+            {
+                bail!("Invalid script argument name: {}", key);
+            }
+            if value.chars().any(char::is_control) {
+                bail!("Script argument {} contains control characters", key);
+            }
+
+            let escaped = value.replace('\\', "\\\\").replace('"', "\\\"");
+            formatted.push(format!("{}=\"{}\"", key, escaped));
+        }
+
+        Ok(formatted.join(","))
+    }
+
     pub fn validate_scan_type(scan_type: &str) -> Result<()> {
         match scan_type {
-            "quick" | "comprehensive" | "stealth" | "custom" => Ok(()),
+            "quick" | "comprehensive" | "stealth" | "ot" | "custom" => Ok(()),
             _ => bail!("Invalid scan type: {}", scan_type),
         }
     }
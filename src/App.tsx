@@ -6,35 +6,17 @@ import useHostStore from './stores/hostStore';
 import HostTable from './components/HostTable';
 import ScanProgress from './components/ScanProgress';
 import ResultViewer from './components/ResultViewer';
-
-// Types for Tauri backend communication
-interface ScanRequest {
-  target: string;
-  scan_type: string;
-  options?: Record<string, any>;
-}
+import IotDashboard from './components/IotDashboard';
 
 // Target input component with Tauri integration
 const TargetInput = () => {
   const [target, setTarget] = useState('192.168.1.1-50');
-  const [scanType, setScanType] = useState('nmap');
+  const [scanType, setScanType] = useState('quick');
   const { isScanning, startScan, stopScan } = useScanStore();
 
   const handleStartScan = async () => {
     try {
-      const scanTarget = {
-        id: crypto.randomUUID(),
-        ip: target.trim(),
-        hostname: undefined,
-        ports: [],
-        scan_type: scanType as any,
-        options: {
-          ports: '1-65535',
-          timeout: 30
-        }
-      };
-
-      await startScan(scanTarget);
+      await startScan(target.trim(), scanType);
     } catch (error) {
       console.error('Failed to start scan:', error);
     }
@@ -80,10 +62,10 @@ const TargetInput = () => {
             className="w-full px-3 py-2 bg-gray-800 border border-gray-600 rounded text-white focus:ring-2 focus:ring-blue-500"
             disabled={isScanning}
           >
-            <option value="nmap">Nmap Port Scan</option>
-            <option value="masscan">Masscan Fast Scan</option>
-            <option value="nikto">Nikto Web Scan</option>
-            <option value="dirb">Directory Brute Force</option>
+            <option value="quick">Quick Scan</option>
+            <option value="comprehensive">Comprehensive Scan</option>
+            <option value="stealth">Stealth Scan</option>
+            <option value="ot">OT Protocol Identification</option>
           </select>
         </div>
         
@@ -176,6 +158,11 @@ const App: React.FC = () => {
   useScanStore();
   useHostStore();
 
+  // Patch host rows as scan results are stored
+  useEffect(() => {
+    useHostStore.getState().initializeEventListeners();
+  }, []);
+
   // Listen for scan progress updates from Tauri
   useEffect(() => {
     const unlisten = listen('scan-progress', (event: any) => {
@@ -236,6 +223,10 @@ const App: React.FC = () => {
           <LogViewer />
         </div>
 
+        <div className="mt-6">
+          <IotDashboard />
+        </div>
+
         <div className="mt-6">
           <ResultViewer />
         </div>
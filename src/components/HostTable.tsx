@@ -1,7 +1,7 @@
 import React, { useState, useEffect, useMemo } from 'react';
 import { Monitor, Shield, AlertTriangle, Search, Filter, Download, Trash2, Eye } from 'lucide-react';
 import useHostStore from '../stores/hostStore';
-import type { Host, HostFilter } from '../types/scanning';
+import type { Host, HostFilter } from '../stores/hostStore';
 
 interface HostTableProps {
   onHostSelect?: (host: Host) => void;
@@ -291,14 +291,14 @@ const HostTable: React.FC<HostTableProps> = ({
                       </div>
                     ) : '-'}
                   </td>
-                  <td className="px-4 py-3 text-gray-300">{host.port_count}</td>
+                  <td className="px-4 py-3 text-gray-300">{host.port_count ?? '-'}</td>
                   <td className="px-4 py-3">
-                    <span className={`font-medium ${getSeverityColor(host.vulnerability_count)}`}>
-                      {host.vulnerability_count}
+                    <span className={`font-medium ${getSeverityColor(host.vulnerability_count ?? 0)}`}>
+                      {host.vulnerability_count ?? '-'}
                     </span>
                   </td>
                   <td className="px-4 py-3 text-gray-300 text-sm">
-                    {new Date(host.last_seen).toLocaleDateString()}
+                    {new Date(host.updated_at).toLocaleDateString()}
                   </td>
                   {showActions && (
                     <td className="px-4 py-3">
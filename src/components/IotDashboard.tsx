@@ -0,0 +1,114 @@
+import React, { useState, useEffect, useMemo } from 'react';
+import { invoke } from '@tauri-apps/api/tauri';
+import { Camera, Printer, Router, HardDrive, Cpu, Server, RefreshCw } from 'lucide-react';
+import type { Host } from '../bindings/Host';
+import { errorMessage } from '../types/errors';
+
+// Values the backend stores in Host.device_type
+type DeviceType = 'camera' | 'printer' | 'router' | 'nas' | 'plc' | 'bmc';
+
+const DEVICE_GROUPS: { type: DeviceType; label: string; icon: React.ReactNode }[] = [
+  { type: 'camera', label: 'Cameras', icon: <Camera className="w-5 h-5 text-red-400" /> },
+  { type: 'printer', label: 'Printers', icon: <Printer className="w-5 h-5 text-blue-400" /> },
+  { type: 'router', label: 'Routers', icon: <Router className="w-5 h-5 text-green-400" /> },
+  { type: 'nas', label: 'NAS', icon: <HardDrive className="w-5 h-5 text-yellow-400" /> },
+  { type: 'plc', label: 'PLCs', icon: <Cpu className="w-5 h-5 text-orange-400" /> },
+  { type: 'bmc', label: 'BMCs', icon: <Server className="w-5 h-5 text-purple-400" /> },
+];
+
+// IoT-focused view of hosts classified from their web UI fingerprints
+const IotDashboard: React.FC = () => {
+  const [devices, setDevices] = useState<Host[]>([]);
+  const [isLoading, setIsLoading] = useState(false);
+  const [error, setError] = useState<string | null>(null);
+
+  const loadDevices = async () => {
+    setIsLoading(true);
+    setError(null);
+
+    try {
+      setDevices(await invoke<Host[]>('get_iot_devices'));
+    } catch (err) {
+      setError(errorMessage(err));
+    } finally {
+      setIsLoading(false);
+    }
+  };
+
+  useEffect(() => {
+    loadDevices();
+  }, []);
+
+  const grouped = useMemo(() => {
+    const groups: Record<string, Host[]> = {};
+    for (const device of devices) {
+      if (device.device_type) {
+        (groups[device.device_type] ||= []).push(device);
+      }
+    }
+    return groups;
+  }, [devices]);
+
+  return (
+    <div className="bg-gray-900 p-6 rounded-lg border border-gray-700">
+      <div className="flex items-center justify-between mb-4">
+        <h2 className="text-xl font-semibold text-white flex items-center gap-2">
+          <Cpu className="w-5 h-5 text-orange-400" />
+          IoT Devices
+        </h2>
+        <button
+          onClick={loadDevices}
+          disabled={isLoading}
+          className="p-2 hover:bg-gray-800 rounded transition-colors"
+        >
+          <RefreshCw className={`w-4 h-4 ${isLoading ? 'animate-spin' : ''}`} />
+        </button>
+      </div>
+
+      {error && <p className="text-red-400 text-sm mb-4">{error}</p>}
+
+      <div className="grid grid-cols-2 lg:grid-cols-5 gap-4 mb-6">
+        {DEVICE_GROUPS.map(group => (
+          <div key={group.type} className="bg-gray-800 p-4 rounded border border-gray-700">
+            <div className="flex items-center gap-2 text-gray-300 text-sm">
+              {group.icon}
+              {group.label}
+            </div>
+            <div className="text-2xl font-bold text-white mt-2">
+              {grouped[group.type]?.length ?? 0}
+            </div>
+          </div>
+        ))}
+      </div>
+
+      {devices.length === 0 ? (
+        <p className="text-gray-500">No embedded devices classified yet.</p>
+      ) : (
+        <div className="overflow-x-auto">
+          <table className="w-full">
+            <thead className="bg-gray-800 border-b border-gray-700">
+              <tr>
+                <th className="px-4 py-3 text-left text-sm font-medium text-gray-300">Type</th>
+                <th className="px-4 py-3 text-left text-sm font-medium text-gray-300">IP Address</th>
+                <th className="px-4 py-3 text-left text-sm font-medium text-gray-300">Product</th>
+                <th className="px-4 py-3 text-left text-sm font-medium text-gray-300">Vendor</th>
+              </tr>
+            </thead>
+            <tbody>
+              {devices.map(device => (
+                <tr key={device.id} className="border-b border-gray-800 hover:bg-gray-800">
+                  <td className="px-4 py-3 text-sm text-gray-300 uppercase">{device.device_type}</td>
+                  <td className="px-4 py-3 text-sm text-white font-mono">{device.ip}</td>
+                  <td className="px-4 py-3 text-sm text-gray-300">{device.device_product || '-'}</td>
+                  <td className="px-4 py-3 text-sm text-gray-400">{device.vendor || '-'}</td>
+                </tr>
+              ))}
+            </tbody>
+          </table>
+        </div>
+      )}
+    </div>
+  );
+};
+
+export default IotDashboard;
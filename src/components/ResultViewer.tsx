@@ -1,7 +1,7 @@
 import React, { useState, useMemo } from 'react';
 import { Shield, AlertTriangle, Download, Search } from 'lucide-react';
 import useScanStore from '../stores/scanStore';
-import type { Vulnerability, Port } from '../types/scanning';
+import type { ScanResult } from '../bindings/ScanResult';
 
 interface ResultViewerProps {
   selectedScanId?: string;
@@ -15,12 +15,13 @@ const ResultViewer: React.FC<ResultViewerProps> = ({ selectedScanId }) => {
   const [searchTerm, setSearchTerm] = useState('');
 
   const currentScan = selectedScanId ? getScanById(selectedScanId) : null;
-  const results = currentScan ? [currentScan] : scanHistory.filter(scan => scan.status === 'completed');
+  const scans = currentScan ? [currentScan] : scanHistory.filter(scan => scan.status === 'completed');
+  const results: ScanResult[] = scans.flatMap(scan => (scan.result ? [scan.result] : []));
 
   const allVulnerabilities = useMemo(() => {
     const vulns = results.flatMap(scan => scan.vulnerabilities);
     return vulns.filter(vuln => {
-      if (severityFilter !== 'all' && vuln.severity !== severityFilter) return false;
+      if (severityFilter !== 'all' && vuln.severity.toLowerCase() !== severityFilter) return false;
       if (searchTerm && !vuln.name.toLowerCase().includes(searchTerm.toLowerCase())) return false;
       return true;
     });
@@ -39,7 +40,7 @@ const ResultViewer: React.FC<ResultViewerProps> = ({ selectedScanId }) => {
   }, [results, searchTerm]);
 
   const getSeverityColor = (severity: string) => {
-    switch (severity) {
+    switch (severity.toLowerCase()) {
       case 'critical': return 'text-red-500 bg-red-500/10 border-red-500/30';
       case 'high': return 'text-orange-500 bg-orange-500/10 border-orange-500/30';
       case 'medium': return 'text-yellow-500 bg-yellow-500/10 border-yellow-500/30';
@@ -174,11 +175,6 @@ const ResultViewer: React.FC<ResultViewerProps> = ({ selectedScanId }) => {
                           </span>
                         )}
                       </div>
-                      {port.confidence && (
-                        <span className="text-sm text-gray-400">
-                          {port.confidence}% confidence
-                        </span>
-                      )}
                     </div>
 
                     {port.version && (
@@ -226,7 +222,7 @@ const ResultViewer: React.FC<ResultViewerProps> = ({ selectedScanId }) => {
 
                     <p className="text-gray-300 text-sm mb-3">{vuln.description}</p>
 
-                    {vuln.references && vuln.references.length > 0 && (
+                    {vuln.references.length > 0 && (
                       <div className="space-y-1">
                         <span className="text-xs font-medium text-gray-400">References:</span>
                         {vuln.references.map((ref, refIndex) => (
@@ -251,25 +247,22 @@ const ResultViewer: React.FC<ResultViewerProps> = ({ selectedScanId }) => {
               <div key={scan.id} className="bg-gray-800 p-4 rounded border border-gray-600">
                 <div className="flex items-center justify-between mb-4">
                   <h3 className="text-lg font-semibold text-white">
-                    {scan.scan_type.toUpperCase()} Scan
+                    {scan.target_ip}
                   </h3>
                   <span className="text-sm text-gray-400">
-                    {scan.end_time && new Date(scan.end_time).toLocaleString()}
+                    {new Date(scan.timestamp).toLocaleString()}
                   </span>
                 </div>
 
                 <div className="grid grid-cols-2 md:grid-cols-4 gap-4 mb-4">
                   <div className="bg-gray-900 p-3 rounded">
-                    <div className="text-sm text-gray-400">Target</div>
-                    <div className="font-mono text-white">{scan.target_id}</div>
+                    <div className="text-sm text-gray-400">MAC Address</div>
+                    <div className="font-mono text-white">{scan.mac_address ?? '-'}</div>
                   </div>
                   <div className="bg-gray-900 p-3 rounded">
-                    <div className="text-sm text-gray-400">Duration</div>
+                    <div className="text-sm text-gray-400">Round Trip</div>
                     <div className="text-white">
-                      {scan.end_time ? 
-                        `${Math.round((new Date(scan.end_time).getTime() - new Date(scan.start_time).getTime()) / 1000)}s` :
-                        'In progress'
-                      }
+                      {scan.rtt_ms !== null ? `${scan.rtt_ms.toFixed(1)}ms` : '-'}
                     </div>
                   </div>
                   <div className="bg-gray-900 p-3 rounded">
@@ -282,25 +275,20 @@ const ResultViewer: React.FC<ResultViewerProps> = ({ selectedScanId }) => {
                   </div>
                 </div>
 
-                {scan.error_message && (
+                {scan.os_detection && (
+                  <div className="text-sm text-gray-300 mb-4">
+                    OS: {scan.os_detection.name} ({scan.os_detection.accuracy}% accuracy)
+                  </div>
+                )}
+
+                {typeof scan.status === 'object' && (
                   <div className="bg-red-900/20 border border-red-500/30 p-3 rounded mb-4">
                     <div className="flex items-center gap-2 text-red-400">
                       <AlertTriangle className="w-4 h-4" />
-                      <span className="text-sm">{scan.error_message}</span>
+                      <span className="text-sm">{scan.status.Failed.error}</span>
                     </div>
                   </div>
                 )}
-
-                {scan.raw_output && (
-                  <details className="mt-4">
-                    <summary className="cursor-pointer text-sm text-gray-400 hover:text-white">
-                      Show Raw Output
-                    </summary>
-                    <div className="mt-2 bg-gray-900 p-3 rounded font-mono text-xs text-gray-300 overflow-x-auto">
-                      <pre>{scan.raw_output}</pre>
-                    </div>
-                  </details>
-                )}
               </div>
             ))}
           </div>
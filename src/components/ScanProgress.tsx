@@ -1,7 +1,7 @@
 import React, { useEffect, useState } from 'react';
 import { Activity, Clock, Target, Shield, AlertTriangle, CheckCircle, XCircle } from 'lucide-react';
 import useScanStore from '../stores/scanStore';
-import type { ScanResult, ScanProgress as ScanProgressType } from '../types/scanning';
+import type { TrackedScan } from '../stores/scanStore';
 
 interface ScanProgressProps {
   showDetails?: boolean;
@@ -10,6 +10,7 @@ interface ScanProgressProps {
 const ScanProgress: React.FC<ScanProgressProps> = ({ showDetails = true }) => {
   const {
     activeScans,
+    scanHistory,
     currentProgress,
     statistics,
     isScanning,
@@ -65,6 +66,10 @@ const ScanProgress: React.FC<ScanProgressProps> = ({ showDetails = true }) => {
   };
 
   const activeScanArray = Array.from(activeScans.values());
+  const finishedResults = scanHistory.flatMap(scan => (scan.result ? [scan.result] : []));
+  const completedCount = scanHistory.filter(scan => scan.status === 'completed').length;
+  const openPortCount = finishedResults.reduce((sum, result) => sum + result.open_ports.length, 0);
+  const vulnerabilityCount = finishedResults.reduce((sum, result) => sum + result.vulnerabilities.length, 0);
 
   return (
     <div className="bg-gray-900 p-6 rounded-lg border border-gray-700">
@@ -88,19 +93,19 @@ const ScanProgress: React.FC<ScanProgressProps> = ({ showDetails = true }) => {
       {/* Statistics */}
       <div className="grid grid-cols-2 md:grid-cols-4 gap-4 mb-6">
         <div className="bg-gray-800 p-3 rounded">
-          <div className="text-2xl font-bold text-blue-400">{statistics.active_scans}</div>
+          <div className="text-2xl font-bold text-blue-400">{statistics.total_active}</div>
           <div className="text-sm text-gray-400">Active</div>
         </div>
         <div className="bg-gray-800 p-3 rounded">
-          <div className="text-2xl font-bold text-green-400">{statistics.completed_scans}</div>
+          <div className="text-2xl font-bold text-green-400">{completedCount}</div>
           <div className="text-sm text-gray-400">Completed</div>
         </div>
         <div className="bg-gray-800 p-3 rounded">
-          <div className="text-2xl font-bold text-yellow-400">{statistics.total_hosts_discovered}</div>
-          <div className="text-sm text-gray-400">Hosts Found</div>
+          <div className="text-2xl font-bold text-yellow-400">{openPortCount}</div>
+          <div className="text-sm text-gray-400">Open Ports</div>
         </div>
         <div className="bg-gray-800 p-3 rounded">
-          <div className="text-2xl font-bold text-red-400">{statistics.total_vulnerabilities}</div>
+          <div className="text-2xl font-bold text-red-400">{vulnerabilityCount}</div>
           <div className="text-sm text-gray-400">Vulnerabilities</div>
         </div>
       </div>
@@ -113,8 +118,8 @@ const ScanProgress: React.FC<ScanProgressProps> = ({ showDetails = true }) => {
         </div>
       ) : (
         <div className="space-y-4">
-                      {activeScanArray.map((scan: ScanResult) => {
-            const progress = currentProgress.get(scan.id);
+          {activeScanArray.map((scan: TrackedScan) => {
+            const progress = currentProgress.get(scan.target)?.progress;
             const isExpanded = expandedScans.has(scan.id);
             
             return (
@@ -130,7 +135,7 @@ const ScanProgress: React.FC<ScanProgressProps> = ({ showDetails = true }) => {
                     </span>
                     <div>
                       <h3 className="font-semibold text-white">
-                        {scan.scan_type.toUpperCase()} - {scan.target_id}
+                        {scan.scan_type.toUpperCase()} - {scan.target}
                       </h3>
                       <p className="text-sm text-gray-400">
                         Started {formatDuration(scan.start_time)} ago
@@ -142,10 +147,7 @@ const ScanProgress: React.FC<ScanProgressProps> = ({ showDetails = true }) => {
                     {progress && (
                       <div className="text-right">
                         <div className="text-sm font-medium text-white">
-                          {progress.progress}%
-                        </div>
-                        <div className="text-xs text-gray-400">
-                          {progress.current_phase}
+                          {Math.round(progress.percent)}%
                         </div>
                       </div>
                     )}
@@ -174,40 +176,23 @@ const ScanProgress: React.FC<ScanProgressProps> = ({ showDetails = true }) => {
                           scan.status === 'failed' ? 'bg-red-500' :
                           'bg-blue-500'
                         }`}
-                        style={{ width: `${progress.progress}%` }}
+                        style={{ width: `${progress.percent}%` }}
                       />
                     </div>
-                    {progress.estimated_time_remaining && (
+                    {progress.eta && (
                       <div className="text-xs text-gray-400 mt-1">
-                        Est. {Math.round(progress.estimated_time_remaining / 60)}m remaining
+                        Est. {Math.max(0, Math.round((new Date(progress.eta).getTime() - Date.now()) / 60000))}m remaining
                       </div>
                     )}
                   </div>
                 )}
 
                 {/* Detailed Progress */}
-                {showDetails && isExpanded && progress && (
+                {showDetails && isExpanded && progress?.message && (
                   <div className="mt-4 pt-4 border-t border-gray-700">
-                    <div className="grid grid-cols-2 md:grid-cols-3 gap-4 text-sm">
-                      <div>
-                        <span className="text-gray-400">Hosts Discovered:</span>
-                        <span className="ml-2 text-white">{progress.discovered_hosts}</span>
-                      </div>
-                      <div>
-                        <span className="text-gray-400">Ports Scanned:</span>
-                        <span className="ml-2 text-white">{progress.total_ports_scanned}</span>
-                      </div>
-                      <div>
-                        <span className="text-gray-400">Open Ports:</span>
-                        <span className="ml-2 text-green-400">{progress.open_ports_found}</span>
-                      </div>
+                    <div className="p-2 bg-gray-700 rounded">
+                      <span className="text-sm text-gray-300">{progress.message}</span>
                     </div>
-                    
-                    {progress.message && (
-                      <div className="mt-3 p-2 bg-gray-700 rounded">
-                        <span className="text-sm text-gray-300">{progress.message}</span>
-                      </div>
-                    )}
                   </div>
                 )}
 
@@ -222,16 +207,16 @@ const ScanProgress: React.FC<ScanProgressProps> = ({ showDetails = true }) => {
                 )}
 
                 {/* Results Summary */}
-                {scan.status === 'completed' && scan.open_ports.length > 0 && (
+                {scan.status === 'completed' && scan.result && scan.result.open_ports.length > 0 && (
                   <div className="mt-3 p-3 bg-green-900/20 border border-green-500/30 rounded">
                     <div className="flex items-center gap-2 text-green-400 mb-2">
                       <Shield className="w-4 h-4" />
                       <span className="text-sm font-medium">Scan Complete</span>
                     </div>
                     <div className="text-xs text-gray-300">
-                      Found {scan.open_ports.length} open ports
-                      {scan.vulnerabilities.length > 0 && 
-                        `, ${scan.vulnerabilities.length} vulnerabilities`
+                      Found {scan.result.open_ports.length} open ports
+                      {scan.result.vulnerabilities.length > 0 && 
+                        `, ${scan.result.vulnerabilities.length} vulnerabilities`
                       }
                     </div>
                   </div>
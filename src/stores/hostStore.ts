@@ -1,53 +1,23 @@
 import { create } from 'zustand';
 import { invoke } from '@tauri-apps/api/tauri';
+import { listen } from '@tauri-apps/api/event';
+import { errorMessage } from '../types/errors';
+import type { Host as StoredHost } from '../bindings/Host';
+import type { HostDetails } from '../bindings/HostDetails';
+import type { HostDelta } from '../bindings/HostDelta';
 
-export interface Host {
-  id: string;
-  ip: string;
-  hostname?: string;
-  mac_address?: string;
-  os_name?: string;
-  os_family?: string;
-  os_accuracy?: number;
-  status: 'up' | 'down' | 'unknown';
-  last_seen: string;
-  created_at: string;
-  updated_at: string;
-  port_count: number;
-  vulnerability_count: number;
-}
-
-export interface HostPort {
-  id: string;
-  host_id: string;
-  number: number;
-  protocol: string;
-  state: string;
-  service?: string;
-  version?: string;
-  banner?: string;
-  confidence?: number;
-  discovered_at: string;
-}
+// Generated from the Rust models by `npm run bindings`
+export type { HostPort } from '../bindings/HostPort';
+export type { HostVulnerability } from '../bindings/HostVulnerability';
+export type { HostDetails } from '../bindings/HostDetails';
 
-export interface HostVulnerability {
-  id: string;
-  host_id: string;
-  port_id?: string;
-  name: string;
-  severity: 'low' | 'medium' | 'high' | 'critical';
-  description: string;
-  cvss_score?: number;
-  references?: string[];
-  discovered_at: string;
-}
-
-export interface HostDetails {
-  host: Host;
-  ports: HostPort[];
-  vulnerabilities: HostVulnerability[];
-}
+// A stored host with the counts this window has seen arrive in host deltas
+export type Host = StoredHost & {
+  port_count?: number;
+  vulnerability_count?: number;
+};
 
+// Narrows the loaded host list client-side
 export interface HostFilter {
   status?: 'up' | 'down' | 'unknown';
   os_family?: string;
@@ -91,6 +61,10 @@ interface HostStore {
   getHostsByStatus: (status: 'up' | 'down' | 'unknown') => Host[];
   getHostsBySeverity: (severity: 'critical' | 'high') => Host[];
   updateStatistics: () => void;
+
+  // Live updates
+  applyHostDelta: (delta: HostDelta) => void;
+  initializeEventListeners: () => Promise<void>;
 }
 
 const useHostStore = create<HostStore>((set, get) => ({
@@ -124,7 +98,7 @@ const useHostStore = create<HostStore>((set, get) => ({
       get().updateStatistics();
     } catch (error) {
       set({ 
-        lastError: String(error),
+        lastError: errorMessage(error),
         isLoading: false 
       });
     }
@@ -143,7 +117,7 @@ const useHostStore = create<HostStore>((set, get) => ({
       });
     } catch (error) {
       set({ 
-        lastError: String(error),
+        lastError: errorMessage(error),
         isLoading: false 
       });
     }
@@ -156,7 +130,7 @@ const useHostStore = create<HostStore>((set, get) => ({
       
       set(state => {
         const updatedHosts = state.hosts.map(host => 
-          host.id === hostId ? hostDetails.host : host
+          host.id === hostId ? { ...host, ...hostDetails.host } : host
         );
         
         return {
@@ -168,7 +142,7 @@ const useHostStore = create<HostStore>((set, get) => ({
       
       get().updateStatistics();
     } catch (error) {
-      set({ lastError: error as string });
+      set({ lastError: errorMessage(error) });
     }
   },
 
@@ -189,7 +163,7 @@ const useHostStore = create<HostStore>((set, get) => ({
       
       get().updateStatistics();
     } catch (error) {
-      set({ lastError: error as string });
+      set({ lastError: errorMessage(error) });
       throw error;
     }
   },
@@ -238,7 +212,7 @@ const useHostStore = create<HostStore>((set, get) => ({
       
       get().updateStatistics();
     } catch (error) {
-      set({ lastError: error as string });
+      set({ lastError: errorMessage(error) });
       throw error;
     }
   },
@@ -252,7 +226,7 @@ const useHostStore = create<HostStore>((set, get) => ({
       });
       return data;
     } catch (error) {
-      set({ lastError: error as string });
+      set({ lastError: errorMessage(error) });
       throw error;
     }
   },
@@ -264,17 +238,17 @@ const useHostStore = create<HostStore>((set, get) => ({
 
 // Get hosts with high/critical vulnerabilities
   getHostsBySeverity: () => {
-    return get().hosts.filter(host => host.vulnerability_count > 0);
+    return get().hosts.filter(host => (host.vulnerability_count ?? 0) > 0);
   },
 
   // Update statistics based on current host data
   updateStatistics: () => {
     set(state => {
       const upHosts = state.hosts.filter(h => h.status === 'up').length;
-      const hostsWithVulns = state.hosts.filter(h => h.vulnerability_count > 0).length;
+      const hostsWithVulns = state.hosts.filter(h => (h.vulnerability_count ?? 0) > 0).length;
       
       // This is a simplified calculation - in reality, you'd get this from the backend
-      const criticalVulns = state.hosts.reduce((sum, host) => sum + host.vulnerability_count, 0);
+      const criticalVulns = state.hosts.reduce((sum, host) => sum + (host.vulnerability_count ?? 0), 0);
       
       return {
         totalHosts: state.hosts.length,
@@ -284,6 +258,47 @@ const useHostStore = create<HostStore>((set, get) => ({
       };
     });
   },
+
+  // Merge what one stored scan result changed without reloading the list
+  applyHostDelta: (delta: HostDelta) => {
+    set(state => {
+      const existing = state.hosts.find(host => host.id === delta.host.id);
+      const host: Host = {
+        ...existing,
+        ...delta.host,
+        port_count: (existing?.port_count ?? 0) + delta.ports.length,
+        vulnerability_count: (existing?.vulnerability_count ?? 0) + delta.vulnerabilities.length,
+      };
+
+      const updatedHosts = existing
+        ? state.hosts.map(h => (h.id === host.id ? host : h))
+        : [...state.hosts, host];
+
+      const selectedHost = state.selectedHost?.host.id === host.id
+        ? {
+            ...state.selectedHost,
+            host,
+            ports: [...state.selectedHost.ports, ...delta.ports],
+            vulnerabilities: [...state.selectedHost.vulnerabilities, ...delta.vulnerabilities],
+          }
+        : state.selectedHost;
+
+      return {
+        hosts: updatedHosts,
+        filteredHosts: applyFilter(updatedHosts, state.currentFilter),
+        selectedHost,
+      };
+    });
+
+    get().updateStatistics();
+  },
+
+  // Initialize event listeners for live host updates
+  initializeEventListeners: async () => {
+    await listen('host-updated', (event: any) => {
+      get().applyHostDelta(event.payload as HostDelta);
+    });
+  },
 }));
 
 // Helper function to apply filters to host list
@@ -301,7 +316,7 @@ function applyFilter(hosts: Host[], filter: HostFilter): Host[] {
     
     // Vulnerability filter
     if (filter.has_vulnerabilities !== undefined) {
-      const hasVulns = host.vulnerability_count > 0;
+      const hasVulns = (host.vulnerability_count ?? 0) > 0;
       if (filter.has_vulnerabilities !== hasVulns) {
         return false;
       }
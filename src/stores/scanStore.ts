@@ -1,32 +1,51 @@
 import { create } from 'zustand';
 import { invoke } from '@tauri-apps/api/tauri';
 import { listen } from '@tauri-apps/api/event';
-import type { ScanTarget, ScanProgress, ScanResult, ScanStatistics } from '../types/scanning';
+import type { ScanResult } from '../bindings/ScanResult';
+import type { ScanProgressEvent } from '../bindings/ScanProgressEvent';
+import type { ScanStatistics } from '../bindings/ScanStatistics';
+import { errorMessage } from '../types/errors';
+
+export type TrackedScanStatus = 'queued' | 'running' | 'completed' | 'failed' | 'cancelled';
+
+// A scan started from this window, with its result once the backend sends it
+export interface TrackedScan {
+  id: string;
+  target: string;
+  scan_type: string;
+  status: TrackedScanStatus;
+  start_time: string;
+  end_time?: string;
+  error_message?: string;
+  result?: ScanResult;
+}
 
 interface ScanStore {
   // State
-  activeScans: Map<string, ScanResult>;
-  scanHistory: ScanResult[];
-  currentProgress: Map<string, ScanProgress>;
+  activeScans: Map<string, TrackedScan>;
+  scanHistory: TrackedScan[];
+  // Latest progress by target address
+  currentProgress: Map<string, ScanProgressEvent>;
   statistics: ScanStatistics;
   isScanning: boolean;
   lastError: string | null;
   
   // Actions
-  startScan: (target: ScanTarget) => Promise<string>;
+  startScan: (targetIp: string, scanType: string) => Promise<string>;
   cancelScan: (scanId: string) => Promise<void>;
   stopScan: () => Promise<void>;
   cancelAllScans: () => Promise<void>;
   scanNetworkRange: (cidr: string, excludes: string[], scanType: string) => Promise<string[]>;
   
   // Progress tracking
-  updateProgress: (progress: ScanProgress) => void;
+  updateProgress: (event: ScanProgressEvent) => void;
   updateScanResult: (result: ScanResult) => void;
+  updateScan: (scan: TrackedScan) => void;
   
   // Data management
   clearHistory: () => void;
   refreshStatistics: () => Promise<void>;
-  getScanById: (scanId: string) => ScanResult | undefined;
+  getScanById: (scanId: string) => TrackedScan | undefined;
   
   // Event listeners
   initializeEventListeners: () => Promise<void>;
@@ -39,49 +58,41 @@ const useScanStore = create<ScanStore>((set, get) => ({
   scanHistory: [],
   currentProgress: new Map(),
   statistics: {
-    total_scans: 0,
-    active_scans: 0,
-    completed_scans: 0,
-    failed_scans: 0,
-    total_hosts_discovered: 0,
-    total_ports_discovered: 0,
-    total_vulnerabilities: 0,
-    scan_time_total: 0,
-    avg_scan_duration: 0,
+    total_active: 0,
+    running: 0,
+    queued: 0,
   },
   isScanning: false,
   lastError: null,
 
   // Start a new scan
-  startScan: async (target: ScanTarget) => {
+  startScan: async (targetIp: string, scanType: string) => {
     try {
       set({ lastError: null, isScanning: true });
       
-      const scanId = await invoke<string>('start_scan', { target });
+      const scanId = await invoke<string>('start_scan', { targetIp, scanType });
       
-      const newScan: ScanResult = {
+      const newScan: TrackedScan = {
         id: scanId,
-        target_id: target.id,
+        target: targetIp,
+        scan_type: scanType,
         status: 'queued',
         start_time: new Date().toISOString(),
-        open_ports: [],
-        vulnerabilities: [],
-        scan_type: target.scan_type,
       };
 
       set(state => ({
         activeScans: new Map(state.activeScans).set(scanId, newScan),
         statistics: {
           ...state.statistics,
-          total_scans: state.statistics.total_scans + 1,
-          active_scans: state.statistics.active_scans + 1,
+          total_active: state.statistics.total_active + 1,
+          queued: state.statistics.queued + 1,
         }
       }));
 
       return scanId;
     } catch (error) {
       set({ 
-        lastError: String(error),
+        lastError: errorMessage(error),
         isScanning: false 
       });
       throw error;
@@ -97,7 +108,7 @@ const useScanStore = create<ScanStore>((set, get) => ({
         const updatedScans = new Map(state.activeScans);
         const scan = updatedScans.get(scanId);
         if (scan) {
-          const updatedScan: ScanResult = {
+          const updatedScan: TrackedScan = {
             ...scan,
             status: 'cancelled',
             end_time: new Date().toISOString(),
@@ -109,12 +120,12 @@ const useScanStore = create<ScanStore>((set, get) => ({
           activeScans: updatedScans,
           statistics: {
             ...state.statistics,
-            active_scans: Math.max(0, state.statistics.active_scans - 1),
+            total_active: Math.max(0, state.statistics.total_active - 1),
           }
         };
       });
     } catch (error) {
-      set({ lastError: error as string });
+      set({ lastError: errorMessage(error) });
       throw error;
     }
   },
@@ -131,12 +142,12 @@ const useScanStore = create<ScanStore>((set, get) => ({
       await invoke('cancel_all_scans');
       
       set(state => {
-        const updatedScans = new Map();
+        const updatedScans = new Map<string, TrackedScan>();
         const currentTime = new Date().toISOString();
         
         state.activeScans.forEach((scan, id) => {
           if (scan.status === 'running' || scan.status === 'queued') {
-            const updatedScan: ScanResult = {
+            const updatedScan: TrackedScan = {
               ...scan,
               status: 'cancelled',
               end_time: currentTime,
@@ -151,13 +162,14 @@ const useScanStore = create<ScanStore>((set, get) => ({
           activeScans: updatedScans,
           isScanning: false,
           statistics: {
-            ...state.statistics,
-            active_scans: 0,
+            total_active: 0,
+            running: 0,
+            queued: 0,
           }
         };
       });
     } catch (error) {
-      set({ lastError: error as string });
+      set({ lastError: errorMessage(error) });
       throw error;
     }
   },
@@ -167,7 +179,7 @@ const useScanStore = create<ScanStore>((set, get) => ({
     try {
       set({ lastError: null, isScanning: true });
       
-      const scanIds = await invoke<string[]>('scan_network_range', {
+      const { scan_ids: scanIds } = await invoke<{ campaign_id: string; scan_ids: string[] }>('scan_network_range', {
         range: { cidr, exclude: excludes, scan_type: scanType }
       });
 
@@ -176,14 +188,12 @@ const useScanStore = create<ScanStore>((set, get) => ({
         const updatedScans = new Map(state.activeScans);
         
         scanIds.forEach(scanId => {
-          const newScan: ScanResult = {
+          const newScan: TrackedScan = {
             id: scanId,
-            target_id: `network-${scanId}`,
+            target: cidr,
+            scan_type: scanType,
             status: 'queued',
             start_time: new Date().toISOString(),
-            open_ports: [],
-            vulnerabilities: [],
-            scan_type: scanType,
           };
           updatedScans.set(scanId, newScan);
         });
@@ -192,8 +202,8 @@ const useScanStore = create<ScanStore>((set, get) => ({
           activeScans: updatedScans,
           statistics: {
             ...state.statistics,
-            total_scans: state.statistics.total_scans + scanIds.length,
-            active_scans: state.statistics.active_scans + scanIds.length,
+            total_active: state.statistics.total_active + scanIds.length,
+            queued: state.statistics.queued + scanIds.length,
           }
         };
       });
@@ -201,7 +211,7 @@ const useScanStore = create<ScanStore>((set, get) => ({
       return scanIds;
     } catch (error) {
       set({ 
-        lastError: error as string,
+        lastError: errorMessage(error),
         isScanning: false 
       });
       throw error;
@@ -209,45 +219,55 @@ const useScanStore = create<ScanStore>((set, get) => ({
   },
 
   // Update scan progress
-  updateProgress: (progress: ScanProgress) => {
+  updateProgress: (event: ScanProgressEvent) => {
     set(state => ({
-      currentProgress: new Map(state.currentProgress).set(progress.scan_id, progress)
+      currentProgress: new Map(state.currentProgress).set(event.target, event)
     }));
   },
 
-  // Update scan result
+  // Attach a stored result to the scan it came from
   updateScanResult: (result: ScanResult) => {
+    const scan = get().getScanById(result.target_id);
+    if (!scan) {
+      return;
+    }
+
+    const { status } = result;
+    if (typeof status === 'object') {
+      get().updateScan({ ...scan, result, status: 'failed', error_message: status.Failed.error, end_time: result.timestamp });
+    } else if (status === 'Completed') {
+      get().updateScan({ ...scan, result, status: 'completed', end_time: result.timestamp });
+    } else {
+      get().updateScan({ ...scan, result, status: status === 'Running' ? 'running' : 'queued' });
+    }
+  },
+
+  // Update a tracked scan, moving it to history once it has finished
+  updateScan: (scan: TrackedScan) => {
     set(state => {
       const updatedScans = new Map(state.activeScans);
-      updatedScans.set(result.id, result);
+      updatedScans.set(scan.id, scan);
 
-      // Move completed scans to history
       const newHistory = [...state.scanHistory];
-      if (result.status === 'completed' || result.status === 'failed' || result.status === 'cancelled') {
-        const existingIndex = newHistory.findIndex(scan => scan.id === result.id);
+      if (scan.status === 'completed' || scan.status === 'failed' || scan.status === 'cancelled') {
+        const existingIndex = newHistory.findIndex(s => s.id === scan.id);
         if (existingIndex >= 0) {
-          newHistory[existingIndex] = result;
+          newHistory[existingIndex] = scan;
         } else {
-          newHistory.push(result);
+          newHistory.push(scan);
         }
-        updatedScans.delete(result.id);
+        updatedScans.delete(scan.id);
       }
 
       // Update scanning status
       const hasActiveScans = Array.from(updatedScans.values()).some(
-        scan => scan.status === 'running' || scan.status === 'queued'
+        s => s.status === 'running' || s.status === 'queued'
       );
 
       return {
         activeScans: updatedScans,
         scanHistory: newHistory,
         isScanning: hasActiveScans,
-        statistics: {
-          ...state.statistics,
-          active_scans: updatedScans.size,
-          completed_scans: newHistory.filter(s => s.status === 'completed').length,
-          failed_scans: newHistory.filter(s => s.status === 'failed').length,
-        }
       };
     });
   },
@@ -263,7 +283,7 @@ const useScanStore = create<ScanStore>((set, get) => ({
       const stats = await invoke<ScanStatistics>('get_scan_statistics');
       set({ statistics: stats });
     } catch (error) {
-      set({ lastError: error as string });
+      set({ lastError: errorMessage(error) });
     }
   },
 
@@ -278,8 +298,7 @@ const useScanStore = create<ScanStore>((set, get) => ({
   initializeEventListeners: async () => {
     // Listen for scan progress updates
     await listen('scan-progress', (event: any) => {
-      const progress = event.payload as ScanProgress;
-      get().updateProgress(progress);
+      get().updateProgress(event.payload as ScanProgressEvent);
     });
 
     // Listen for scan result updates
@@ -299,7 +318,7 @@ const useScanStore = create<ScanStore>((set, get) => ({
       const { scanId, error } = event.payload;
       const scan = get().getScanById(scanId);
       if (scan) {
-        get().updateScanResult({
+        get().updateScan({
           ...scan,
           status: 'failed',
           error_message: error,
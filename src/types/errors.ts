@@ -0,0 +1,24 @@
+export type LegionErrorCode =
+  | 'validation'
+  | 'tool_missing'
+  | 'permission'
+  | 'not_found'
+  | 'db'
+  | 'timeout'
+  | 'cancelled'
+  | 'internal';
+
+// Shape of every error rejected by a backend command
+export interface LegionError {
+  code: LegionErrorCode;
+  message: string;
+  context?: { tool?: string } | null;
+}
+
+export function isLegionError(error: unknown): error is LegionError {
+  return typeof error === 'object' && error !== null && 'code' in error && 'message' in error;
+}
+
+export function errorMessage(error: unknown): string {
+  return isLegionError(error) ? error.message : String(error);
+}